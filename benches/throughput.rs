@@ -0,0 +1,435 @@
+//! Throughput baselines against the standard library.
+//! Run with `cargo bench --features test-util --bench throughput`.
+use std::{collections::VecDeque, hint::black_box};
+#[cfg(not(feature = "no_std"))]
+use std::{io, mem::MaybeUninit};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use circulate::RingBuffer;
+#[cfg(not(feature = "no_std"))]
+use circulate::{test_util::MemoryStream, BufStream, FromStd, FromStdUninit, Read, Write};
+
+const ITEMS: usize = 4096;
+#[cfg(not(feature = "no_std"))]
+const STREAM_BYTES: usize = 1 << 20;
+
+/// Deterministic input for the stream benchmarks.
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+fn push_pop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_pop");
+    group.throughput(Throughput::Elements(ITEMS as u64));
+    group.bench_function("RingBuffer", |b| {
+        let mut buffer = RingBuffer::with_capacity(ITEMS + 1);
+        b.iter(|| {
+            for i in 0..ITEMS as u32 {
+                buffer.push(black_box(i)).unwrap();
+            }
+            while let Some(i) = buffer.pop() {
+                black_box(i);
+            }
+        })
+    });
+    group.bench_function("VecDeque", |b| {
+        let mut buffer = VecDeque::with_capacity(ITEMS + 1);
+        b.iter(|| {
+            for i in 0..ITEMS as u32 {
+                buffer.push_back(black_box(i));
+            }
+            while let Some(i) = buffer.pop_front() {
+                black_box(i);
+            }
+        })
+    });
+    group.finish();
+}
+
+fn indexing(c: &mut Criterion) {
+    // Both buffers have the same capacity, so only the index arithmetic differs.
+    let window = |exact| {
+        let mut buffer = if exact { RingBuffer::with_exact_capacity(ITEMS - 1) } else { RingBuffer::with_capacity(ITEMS - 1) };
+        for i in 0..ITEMS as u32 / 2 {
+            buffer.push(i).unwrap();
+        }
+        buffer
+    };
+    assert_eq!(window(true).capacity(), window(false).capacity());
+    let mut group = c.benchmark_group("indexing");
+    group.throughput(Throughput::Elements(ITEMS as u64));
+    for (name, exact) in [("masked", false), ("exact", true)] {
+        // Slide a half full window around the buffer, so the cursors wrap.
+        group.bench_function(format!("{name}/push_pop"), |b| {
+            let mut buffer = window(exact);
+            b.iter(|| {
+                for i in 0..ITEMS as u32 {
+                    buffer.push(black_box(i)).unwrap();
+                    black_box(buffer.pop());
+                }
+            })
+        });
+        group.bench_function(format!("{name}/index"), |b| {
+            let mut buffer = window(exact);
+            // Start the window three quarters of the way through the allocation.
+            for i in 0..ITEMS as u32 / 4 {
+                buffer.push(i).unwrap();
+                buffer.pop();
+            }
+            b.iter(|| {
+                let mut sum = 0u32;
+                for _ in 0..2 {
+                    for i in 0..buffer.len() {
+                        sum = sum.wrapping_add(*buffer.get(black_box(i)).unwrap());
+                    }
+                }
+                sum
+            })
+        });
+    }
+    group.finish();
+}
+
+fn eq_slice(c: &mut Criterion) {
+    const BYTES: usize = 64 << 10;
+    // Bytes that wrap around the end of the allocation, compared against an equal slice.
+    let expected = pattern(BYTES);
+    let mut buffer = RingBuffer::with_capacity(BYTES + 1);
+    let offset = buffer.capacity() - BYTES / 2;
+    for byte in pattern(offset) {
+        buffer.push(byte).unwrap();
+        buffer.pop();
+    }
+    for &byte in &expected {
+        buffer.push(byte).unwrap();
+    }
+    assert!(offset + BYTES > buffer.capacity());
+    let mut group = c.benchmark_group("eq_slice");
+    group.throughput(Throughput::Bytes(BYTES as u64));
+    group.bench_function("eq_slice", |b| b.iter(|| black_box(&buffer).eq_slice(black_box(&expected))));
+    group.bench_function("element_wise", |b| {
+        b.iter(|| {
+            let buffer = black_box(&buffer);
+            let expected = black_box(&expected);
+            buffer.len() == expected.len() && (0..buffer.len()).all(|i| buffer.get(i) == Some(&expected[i]))
+        })
+    });
+    group.bench_function("iter_eq", |b| b.iter(|| black_box(&buffer).iter().eq(black_box(&expected))));
+    group.finish();
+}
+
+fn push_grow(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push_grow");
+    group.throughput(Throughput::Elements(ITEMS as u64));
+    // Every push past a power of two grows the buffer.
+    group.bench_function("RingBuffer", |b| {
+        b.iter(|| {
+            let mut buffer = RingBuffer::new();
+            for i in 0..ITEMS as u32 {
+                buffer.push(black_box(i)).unwrap();
+            }
+            buffer
+        })
+    });
+    group.bench_function("VecDeque", |b| {
+        b.iter(|| {
+            let mut buffer = VecDeque::new();
+            for i in 0..ITEMS as u32 {
+                buffer.push_back(black_box(i));
+            }
+            buffer
+        })
+    });
+    group.finish();
+}
+
+fn bulk(c: &mut Criterion) {
+    let input = pattern(ITEMS);
+    let mut output = vec![0; ITEMS];
+    let mut group = c.benchmark_group("bulk");
+    group.throughput(Throughput::Bytes(ITEMS as u64));
+    group.bench_function("RingBuffer", |b| {
+        let mut buffer = RingBuffer::with_capacity(ITEMS + 1);
+        // Start part way through the allocation so the copies wrap.
+        buffer.extend_from_slice(&input[..ITEMS / 3]).unwrap();
+        buffer.pop_slice(&mut output[..ITEMS / 3]);
+        b.iter(|| {
+            buffer.extend_from_slice(black_box(&input)).unwrap();
+            black_box(buffer.pop_slice(&mut output));
+        })
+    });
+    group.bench_function("VecDeque", |b| {
+        let mut buffer = VecDeque::with_capacity(ITEMS + 1);
+        buffer.extend(&input[..ITEMS / 3]);
+        buffer.drain(..);
+        b.iter(|| {
+            buffer.extend(black_box(&input));
+            output.copy_from_slice(buffer.make_contiguous());
+            buffer.clear();
+            black_box(&output);
+        })
+    });
+    group.finish();
+}
+
+fn to_vec(c: &mut Criterion) {
+    let mut buffer = RingBuffer::with_capacity(ITEMS);
+    buffer.extend_from_slice(&[0.0f32; ITEMS / 2]).unwrap();
+    buffer.pop_slice(&mut [0.0; ITEMS / 2]);
+    for i in 0..ITEMS - 1 {
+        buffer.push(i as f32).unwrap();
+    }
+    let mut group = c.benchmark_group("to_vec");
+    group.throughput(Throughput::Elements(buffer.len() as u64));
+    group.bench_function("to_vec", |b| b.iter(|| black_box(&buffer).to_vec()));
+    group.bench_function("collect", |b| b.iter(|| black_box(&buffer).iter().copied().collect::<Vec<_>>()));
+    group.finish();
+}
+
+#[cfg(not(feature = "no_std"))]
+fn stream_read(c: &mut Criterion) {
+    let input = pattern(STREAM_BYTES);
+    let mut group = c.benchmark_group("stream_read");
+    group.throughput(Throughput::Bytes(STREAM_BYTES as u64));
+    group.bench_function("BufStream", |b| {
+        let mut chunk = [MaybeUninit::uninit(); 1024];
+        b.iter_batched_ref(
+            || BufStream::with_capacity(MemoryStream::new(input.clone()), 1 << 13),
+            |stream| while stream.read(&mut chunk).unwrap() != 0 {},
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("std::io::BufReader", |b| {
+        let mut chunk = [0; 1024];
+        b.iter_batched_ref(
+            || io::BufReader::with_capacity(1 << 13, MemoryStream::new(input.clone())),
+            |stream| while io::Read::read(stream, &mut chunk).unwrap() != 0 {},
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "no_std"))]
+fn pipe_copy(c: &mut Criterion) {
+    const PIPE_BYTES: u64 = 64 << 20;
+    const CAPACITY: usize = 1 << 20;
+    /// Write `PIPE_BYTES` to a new pipe from another thread, returning the read end.
+    fn pipe() -> io::PipeReader {
+        let (reader, mut writer) = io::pipe().unwrap();
+        std::thread::spawn(move || io::copy(&mut io::Read::take(io::repeat(0xa5), PIPE_BYTES), &mut writer).unwrap());
+        reader
+    }
+    /// Read `stream` to the end a buffer at a time.
+    fn drain<S: Read<Error = io::Error>>(stream: &mut BufStream<S>) {
+        loop {
+            let (lhs, rhs) = stream.peek_all_input().unwrap();
+            let buffered = black_box(lhs).len() + black_box(rhs).len();
+            if buffered == 0 {
+                break;
+            }
+            stream.consume(buffered);
+        }
+    }
+    let mut group = c.benchmark_group("pipe_copy");
+    group.throughput(Throughput::Bytes(PIPE_BYTES));
+    group.sample_size(10);
+    // Zeroes each buffer before reading in to it.
+    group.bench_function("FromStd", |b| {
+        b.iter_batched_ref(|| BufStream::with_capacity(FromStd(pipe()), CAPACITY), drain, BatchSize::PerIteration)
+    });
+    group.bench_function("FromStdUninit", |b| {
+        b.iter_batched_ref(|| BufStream::with_capacity(FromStdUninit(pipe()), CAPACITY), drain, BatchSize::PerIteration)
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "no_std"))]
+fn chunked_flush(c: &mut Criterion) {
+    let chunks: Vec<_> = pattern(STREAM_BYTES).chunks(1 << 12).map(<[u8]>::to_vec).collect();
+    let mut group = c.benchmark_group("chunked_flush");
+    group.throughput(Throughput::Bytes(STREAM_BYTES as u64));
+    group.bench_function("write_chunk", |b| {
+        b.iter_batched_ref(
+            || (BufStream::new(MemoryStream::new(Vec::new())), chunks.clone()),
+            |(stream, chunks)| {
+                for chunk in chunks.drain(..) {
+                    stream.write_chunk(chunk);
+                }
+                stream.flush_output_to_completion().unwrap();
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("write", |b| {
+        b.iter_batched_ref(
+            || BufStream::new(MemoryStream::new(Vec::new())),
+            |stream| {
+                for chunk in &chunks {
+                    let mut chunk = &chunk[..];
+                    while !chunk.is_empty() {
+                        chunk = &chunk[stream.write(chunk).unwrap()..];
+                    }
+                }
+                stream.flush_output_to_completion().unwrap();
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "no_std"))]
+fn small_writes(c: &mut Criterion) {
+    const RECORDS: usize = 1 << 20;
+    let mut group = c.benchmark_group("small_writes");
+    group.throughput(Throughput::Bytes(RECORDS as u64 * 4));
+    group.bench_function("BufStream", |b| {
+        b.iter_batched_ref(
+            || {
+                let mut stream = BufStream::with_capacity(MemoryStream::new(Vec::new()), 1 << 12);
+                stream.set_max_output_capacity(1 << 12);
+                stream
+            },
+            |stream| {
+                for i in 0..RECORDS as u32 {
+                    let record = i.to_le_bytes();
+                    let mut record = &record[..];
+                    while !record.is_empty() {
+                        match stream.write(black_box(record)).unwrap() {
+                            0 => stream.flush_output_to_completion().unwrap(),
+                            written => record = &record[written..],
+                        }
+                    }
+                }
+                stream.flush_output_to_completion().unwrap();
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("std::io::BufWriter", |b| {
+        b.iter_batched_ref(
+            || io::BufWriter::with_capacity(1 << 12, MemoryStream::new(Vec::new())),
+            |stream| {
+                for i in 0..RECORDS as u32 {
+                    io::Write::write(stream, black_box(&i.to_le_bytes())).unwrap();
+                }
+                io::Write::flush(stream).unwrap();
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn fir(c: &mut Criterion) {
+    const TAPS: [f32; 5] = [0.1, 0.2, 0.4, 0.2, 0.1];
+    // A full buffer of samples that wraps around the end of its allocation.
+    let wrapped = || {
+        let mut buffer = RingBuffer::with_capacity(ITEMS);
+        buffer.extend_from_slice(&[0.0f32; ITEMS / 2]).unwrap();
+        buffer.pop_slice(&mut [0.0; ITEMS / 2]);
+        for i in 0..ITEMS - 1 {
+            buffer.push(i as f32).unwrap();
+        }
+        buffer
+    };
+    let mut output = Vec::with_capacity(ITEMS);
+    let mut group = c.benchmark_group("fir");
+    group.throughput(Throughput::Elements(ITEMS as u64));
+    group.bench_function("for_each_window", |b| {
+        b.iter_batched_ref(wrapped, |buffer| {
+            output.clear();
+            buffer.for_each_window(TAPS.len(), |lhs, rhs| {
+                let sum = if rhs.is_empty() {
+                    lhs.iter().zip(TAPS).map(|(x, tap)| x * tap).sum::<f32>()
+                } else {
+                    lhs.iter().chain(rhs).zip(TAPS).map(|(x, tap)| x * tap).sum::<f32>()
+                };
+                output.push(sum);
+            });
+            black_box(&output);
+        }, BatchSize::SmallInput)
+    });
+    group.bench_function("make_contiguous", |b| {
+        b.iter_batched_ref(wrapped, |buffer| {
+            output.clear();
+            for window in buffer.make_contiguous().windows(TAPS.len()) {
+                output.push(window.iter().zip(TAPS).map(|(x, tap)| x * tap).sum::<f32>());
+            }
+            black_box(&output);
+        }, BatchSize::SmallInput)
+    });
+    group.finish();
+}
+
+fn hover(c: &mut Criterion) {
+    const BURST: usize = 1 << 16;
+    let burst = |threshold| {
+        let mut buffer = RingBuffer::new();
+        buffer.set_auto_shrink(threshold);
+        for i in 0..BURST as u32 {
+            buffer.push(i).unwrap();
+        }
+        buffer
+    };
+    // Drain the burst down to a handful of items, then hover there pushing one for each popped.
+    let drain_and_hover = |buffer: &mut RingBuffer<u32>| {
+        while buffer.len() > 8 {
+            black_box(buffer.pop());
+        }
+        for i in 0..ITEMS as u32 {
+            buffer.push(black_box(i)).unwrap();
+            black_box(buffer.pop());
+        }
+    };
+    let mut group = c.benchmark_group("hover");
+    group.throughput(Throughput::Elements((BURST + ITEMS) as u64));
+    let mut settled = Vec::new();
+    for (name, threshold) in [("default", 0.0), ("auto_shrink", 0.25)] {
+        let mut buffer = burst(threshold);
+        drain_and_hover(&mut buffer);
+        settled.push((name, buffer.capacity()));
+        group.bench_function(name, |b| b.iter_batched_ref(|| burst(threshold), drain_and_hover, BatchSize::LargeInput));
+    }
+    group.finish();
+    // Criterion does not measure memory, so report the capacity each policy settles on once the
+    // timed runs are over.
+    for (name, capacity) in settled {
+        println!("hover/{name}: settled at a capacity of {capacity}");
+    }
+}
+
+fn ramp(c: &mut Criterion) {
+    const SAMPLES: usize = 1 << 20;
+    // A full buffer of samples that wraps around the end of its allocation.
+    let mut buffer = RingBuffer::with_capacity(SAMPLES);
+    buffer.extend_from_slice(&[0.0f32; SAMPLES / 2]).unwrap();
+    buffer.pop_slice(&mut [0.0; SAMPLES / 2]);
+    for i in 0..SAMPLES - 1 {
+        buffer.push(i as f32).unwrap();
+    }
+    let scale = 1.0 / SAMPLES as f32;
+    let mut group = c.benchmark_group("ramp");
+    group.throughput(Throughput::Elements(buffer.len() as u64));
+    // Blend each sample with a coefficient that depends on its index.
+    group.bench_function("for_each_mut", |b| {
+        b.iter(|| {
+            black_box(&mut buffer).for_each_mut(|i, sample| *sample = *sample * 0.5 + i as f32 * scale);
+        })
+    });
+    group.bench_function("iter_mut", |b| {
+        b.iter(|| {
+            for (i, sample) in black_box(&mut buffer).iter_mut().enumerate() {
+                *sample = *sample * 0.5 + i as f32 * scale;
+            }
+        })
+    });
+    group.finish();
+}
+
+#[cfg(not(feature = "no_std"))]
+criterion_group!(benches, push_pop, indexing, push_grow, bulk, eq_slice, to_vec, stream_read, pipe_copy, chunked_flush, small_writes, fir, hover, ramp);
+// Note: The stream benchmarks read and write through `std` streams.
+#[cfg(feature = "no_std")]
+criterion_group!(benches, push_pop, indexing, push_grow, bulk, eq_slice, to_vec, fir, hover, ramp);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+fn main() {
+    #[cfg(feature = "capi")]
+    capi_header();
+}
+
+/// Generate `circulate.h` in `OUT_DIR`, declaring the functions of the `capi` module.
+#[cfg(feature = "capi")]
+fn capi_header() {
+    let source = std::path::Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("src/capi.rs");
+    println!("cargo:rerun-if-changed={}", source.display());
+    // For the smoke test in `tests/capi.rs` to compile C for the same target.
+    println!("cargo:rustc-env=TARGET={}", std::env::var("TARGET").unwrap());
+    println!("cargo:rustc-env=HOST={}", std::env::var("HOST").unwrap());
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("CIRCULATE_H".into()),
+        usize_is_size_t: true,
+        sys_includes: vec!["stddef.h".into(), "stdint.h".into()],
+        no_includes: true,
+        ..Default::default()
+    };
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(source)
+        .generate()
+        .expect("failed to generate the C header")
+        .write_to_file(std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("circulate.h"));
+}
@@ -2,10 +2,10 @@ use circulate::RingBuffer;
 
 fn main() {
     let mut fruit = RingBuffer::new();
-    fruit.push("apples".to_owned());
-    fruit.push("oranges".to_owned());
-    fruit.push("pears".to_owned());
-    fruit.push("grapes".to_owned());
+    fruit.push("apples".to_owned()).unwrap();
+    fruit.push("oranges".to_owned()).unwrap();
+    fruit.push("pears".to_owned()).unwrap();
+    fruit.push("grapes".to_owned()).unwrap();
     for f in fruit.iter_mut() {
         f.make_ascii_uppercase()
     }
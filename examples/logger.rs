@@ -0,0 +1,59 @@
+//! A minimal logger that formats records in to a fixed size buffer and only writes them to a
+//! serial port when the logger is flushed, as might be done on an embedded target.
+use core::fmt::Write as _;
+use std::sync::Mutex;
+
+use circulate::{fmt_adapter, LossyWriter, RingBuffer, Write};
+
+/// Stands in for a UART peripheral.
+struct SerialPort;
+impl Write for SerialPort {
+    type Error = core::convert::Infallible;
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        print!("{}", String::from_utf8_lossy(slice));
+        Ok(slice.len())
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+struct SerialLogger {
+    output: Mutex<(LossyWriter, SerialPort)>,
+}
+impl log::Log for SerialLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+    fn log(&self, record: &log::Record) {
+        let (buffer, _) = &mut *self.output.lock().unwrap();
+        // Records that do not fit in the buffer are truncated rather than blocking.
+        let _ = writeln!(fmt_adapter(buffer), "[{}] {}", record.level(), record.args());
+    }
+    fn flush(&self) {
+        let (buffer, serial) = &mut *self.output.lock().unwrap();
+        while let Ok(1..) = buffer.buffer_mut().drain_to(serial) {}
+        let _ = serial.flush();
+        let dropped = buffer.reset_dropped_bytes();
+        if dropped != 0 {
+            println!("({dropped} bytes of log output were dropped)");
+        }
+    }
+}
+
+fn main() {
+    let logger = SerialLogger {
+        output: Mutex::new((LossyWriter::new(RingBuffer::with_capacity(128)), SerialPort)),
+    };
+    log::set_logger(Box::leak(Box::new(logger))).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    log::info!("booted");
+    log::warn!("temperature is {} degrees", 71);
+    log::logger().flush();
+
+    for i in 0..8 {
+        log::debug!("sample {i}");
+    }
+    log::logger().flush();
+}
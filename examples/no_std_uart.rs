@@ -0,0 +1,17 @@
+//! Runs a line echo service for a UART on the host. The service in `no_std_uart/uart.rs` uses
+//! only `core` and `alloc`, as this example is `no_std` but for the shim printing what was sent.
+//! It also runs as a test, in `tests/uart.rs`.
+#![no_std]
+
+extern crate alloc;
+extern crate std;
+
+#[path = "no_std_uart/uart.rs"]
+#[allow(dead_code)]
+mod uart;
+
+fn main() {
+    let transcript = uart::simulate(b"hello\nuart\n", 2);
+    std::print!("{}", alloc::string::String::from_utf8_lossy(&transcript.sent));
+    std::println!("{} ticks, {} bytes lost", transcript.ticks, transcript.overruns);
+}
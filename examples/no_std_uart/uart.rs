@@ -0,0 +1,158 @@
+//! A line echo service over a simulated UART, written against `core`, `alloc` and the crate only,
+//! the same as it would be for a microcontroller.
+//!
+//! The receive interrupt pushes each byte from the line in to a [`StaticRing`], the UART's receive
+//! FIFO. The UART reads and writes a byte at a time, failing with [`WouldBlock`] when no byte has
+//! arrived or the transmitter is busy, and the echo service buffers it in a [`BufStream`] with
+//! bounded buffers that never grow.
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use circulate::{BufStream, Read, StaticConsumer, StaticProducer, StaticRing, Write, WriteExt};
+
+/// The number of slots in the receive FIFO, which holds one less byte.
+pub const FIFO: usize = 8;
+/// The largest the input and output buffers of the echo service grow to.
+pub const INPUT_CAPACITY: usize = 16;
+pub const OUTPUT_CAPACITY: usize = 64;
+/// What the echo service puts before each line it echoes.
+pub const PROMPT: &[u8] = b"> ";
+
+/// The only error of the UART: no byte has arrived, or the transmitter is still busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WouldBlock;
+
+/// A UART peripheral, reading and writing a byte at a time.
+pub struct Uart<'a> {
+    /// The bytes queued by the receive interrupt.
+    rx: StaticConsumer<'a, u8, FIFO>,
+    /// The transmit holding register, which the line empties one byte per tick.
+    tx: Option<u8>,
+}
+impl Read for Uart<'_> {
+    type Error = WouldBlock;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, WouldBlock> {
+        let Some(slot) = buffer.first_mut() else {
+            return Ok(0);
+        };
+        slot.write(self.rx.pop().ok_or(WouldBlock)?);
+        Ok(1)
+    }
+}
+impl Write for Uart<'_> {
+    type Error = WouldBlock;
+    fn write(&mut self, slice: &[u8]) -> Result<usize, WouldBlock> {
+        let Some(&byte) = slice.first() else {
+            return Ok(0);
+        };
+        if self.tx.is_some() {
+            return Err(WouldBlock);
+        }
+        self.tx = Some(byte);
+        Ok(1)
+    }
+    fn flush(&mut self) -> Result<(), WouldBlock> {
+        match self.tx {
+            Some(_) => Err(WouldBlock),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The receive interrupt handler, queueing each byte as it arrives.
+pub struct ReceiveInterrupt<'a> {
+    fifo: StaticProducer<'a, u8, FIFO>,
+    /// The number of bytes lost as the FIFO was full.
+    pub overruns: usize,
+}
+impl ReceiveInterrupt<'_> {
+    pub fn receive(&mut self, byte: u8) {
+        if self.fifo.push(byte).is_err() {
+            self.overruns += 1;
+        }
+    }
+}
+
+/// Echoes each line received back after a [`PROMPT`].
+pub struct Echo<'a> {
+    stream: BufStream<Uart<'a>>,
+    /// The line being received.
+    line: Vec<u8>,
+}
+impl<'a> Echo<'a> {
+    pub fn new(uart: Uart<'a>) -> Self {
+        let mut stream = BufStream::with_capacity(uart, INPUT_CAPACITY - 1);
+        stream.set_max_input_capacity(INPUT_CAPACITY);
+        stream.set_max_output_capacity(OUTPUT_CAPACITY);
+        Self { stream, line: Vec::new() }
+    }
+    /// Make as much progress as possible without waiting, as from the main loop.
+    pub fn poll(&mut self) {
+        // Sending may not complete, and is retried on the next poll.
+        let _ = self.stream.flush_nonblocking();
+        if !self.line.ends_with(b"\n") {
+            // Until a whole line is received, reading stops when no byte has arrived.
+            let _ = self.stream.read_until(b'\n', &mut self.line);
+        }
+        // A line is echoed once there is room to buffer all of it, and otherwise stays received,
+        // holding back reading so the buffers never grow.
+        if self.line.ends_with(b"\n") && !self.stream.write_would_block(PROMPT.len() + self.line.len()) {
+            self.stream.write_all(PROMPT).unwrap();
+            self.stream.write_all(&self.line).unwrap();
+            self.line.clear();
+            let _ = self.stream.flush_nonblocking();
+        }
+    }
+    /// Returns if nothing received is waiting to be echoed or sent.
+    pub fn is_idle(&self) -> bool {
+        self.line.is_empty() && !self.stream.input_has_data() && self.stream.output_is_flushed() && self.stream.get_ref().tx.is_none()
+    }
+    pub fn uart_mut(&mut self) -> &mut Uart<'a> {
+        self.stream.get_mut()
+    }
+    pub fn capacities(&self) -> (usize, usize) {
+        (self.stream.input_capacity(), self.stream.output_capacity())
+    }
+}
+
+/// What happened on the line in a run of [`simulate`].
+pub struct Transcript {
+    /// The bytes sent by the UART.
+    pub sent: Vec<u8>,
+    /// The number of bytes received that were lost as the FIFO was full.
+    pub overruns: usize,
+    /// The number of ticks taken.
+    pub ticks: usize,
+}
+
+/// Run the echo service, with a byte of `received` arriving every `spacing` ticks, until it has
+/// echoed everything it received.
+///
+/// Each tick the line delivers a byte if one is due, the main loop polls the echo service, and the
+/// transmitter sends the byte in its holding register.
+pub fn simulate(received: &[u8], spacing: usize) -> Transcript {
+    let fifo = StaticRing::<u8, FIFO>::new();
+    let (producer, consumer) = fifo.split().unwrap();
+    let mut interrupt = ReceiveInterrupt { fifo: producer, overruns: 0 };
+    let mut echo = Echo::new(Uart { rx: consumer, tx: None });
+    let mut sent = Vec::new();
+    let mut arriving = received.iter();
+    let mut ticks = 0;
+    loop {
+        if ticks % spacing == 0 {
+            match arriving.next() {
+                Some(&byte) => interrupt.receive(byte),
+                None if echo.is_idle() => break,
+                None => (),
+            }
+        }
+        echo.poll();
+        if let Some(byte) = echo.uart_mut().tx.take() {
+            sent.push(byte);
+        }
+        // The buffers stay bounded however far the line gets ahead.
+        let (input, output) = echo.capacities();
+        assert!(input <= INPUT_CAPACITY && output <= OUTPUT_CAPACITY);
+        ticks += 1;
+    }
+    Transcript { sent, overruns: interrupt.overruns, ticks }
+}
@@ -0,0 +1,160 @@
+//! A TCP proxy that forwards each connection to an upstream address, pumping both directions in
+//! turn over non-blocking sockets on a single thread.
+//!
+//! Run with `cargo run --example proxy -- <listen address> <upstream address>` to proxy
+//! connections one at a time. Without arguments it proxies a fixed payload through a local echo
+//! server and checks that it comes back unchanged.
+#[cfg(not(feature = "no_std"))]
+use std::{
+    io::{self, ErrorKind},
+    net::{Shutdown, TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(not(feature = "no_std"))]
+use circulate::{BufStream, FromStd};
+
+#[cfg(not(feature = "no_std"))]
+/// The most output buffered for either side before reading from the other side waits.
+const MAX_BUFFERED: usize = 64 * 1024;
+
+#[cfg(not(feature = "no_std"))]
+/// One direction of a proxied connection.
+struct Half {
+    /// Set once the source has reached the end of its stream.
+    eof: bool,
+    /// Set once the end of the stream has been passed on to the destination.
+    closed: bool,
+}
+#[cfg(not(feature = "no_std"))]
+impl Half {
+    /// Read from `src` at most once and pass the bytes on to `dst`, writing as much as it accepts
+    /// without blocking. Returns if any progress was made.
+    ///
+    /// Each call reads at most one buffer, so neither direction can starve the other.
+    fn pump(&mut self, src: &mut BufStream<FromStd<TcpStream>>, dst: &mut BufStream<FromStd<TcpStream>>) -> io::Result<bool> {
+        let mut progress = false;
+        if !self.eof && !src.input_has_data() {
+            match src.buffer_read() {
+                // A non-blocking read with nothing to read fails, so no input is the end.
+                Ok(()) => self.eof = !src.input_has_data(),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => (),
+                Err(error) => return Err(error),
+            }
+            progress |= self.eof || src.input_has_data();
+        }
+        progress |= src.copy_buffered_to(dst)? != 0;
+        match dst.flush_nonblocking() {
+            Ok(flushed) => progress |= flushed.written() != 0,
+            Err(error) if error.kind() == ErrorKind::WouldBlock => (),
+            Err(error) => return Err(error),
+        }
+        if self.eof && !self.closed && !src.input_has_data() && dst.output_is_flushed() {
+            // Only the write half is shut down, as the destination may still be sending.
+            dst.get_ref().0.shutdown(Shutdown::Write)?;
+            self.closed = true;
+            progress = true;
+        }
+        Ok(progress)
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+/// Proxy `client` to `upstream` until both sides have finished sending.
+fn proxy(client: TcpStream, upstream: TcpStream) -> io::Result<()> {
+    let start = Instant::now();
+    let mut client = buffered(client)?;
+    let mut upstream = buffered(upstream)?;
+    let (mut outbound, mut inbound) = (Half { eof: false, closed: false }, Half { eof: false, closed: false });
+    while !(outbound.closed && inbound.closed) {
+        let progress = outbound.pump(&mut client, &mut upstream)? | inbound.pump(&mut upstream, &mut client)?;
+        if !progress {
+            // A real proxy would wait for readiness with the platform's poller instead.
+            thread::sleep(Duration::from_micros(200));
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    let sent = upstream.flushed_bytes();
+    let received = client.flushed_bytes();
+    println!(
+        "sent {sent} bytes and received {received} bytes in {:.3}s ({:.1} MiB/s)",
+        elapsed,
+        (sent + received) as f64 / elapsed / (1024.0 * 1024.0),
+    );
+    debug_assert_eq!((client.received_bytes(), upstream.received_bytes()), (sent, received));
+    Ok(())
+}
+
+#[cfg(not(feature = "no_std"))]
+fn buffered(stream: TcpStream) -> io::Result<BufStream<FromStd<TcpStream>>> {
+    stream.set_nonblocking(true)?;
+    stream.set_nodelay(true)?;
+    let mut stream = BufStream::with_capacity(FromStd(stream), 16 * 1024);
+    stream.set_max_output_capacity(MAX_BUFFERED);
+    Ok(stream)
+}
+
+#[cfg(not(feature = "no_std"))]
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match &args[..] {
+        [listen, upstream] => {
+            let listener = TcpListener::bind(listen)?;
+            loop {
+                let (client, peer) = listener.accept()?;
+                println!("proxying {peer}");
+                if let Err(error) = TcpStream::connect(upstream).and_then(|upstream| proxy(client, upstream)) {
+                    eprintln!("{peer}: {error}");
+                }
+            }
+        }
+        [] => self_test(),
+        _ => {
+            eprintln!("usage: proxy [<listen address> <upstream address>]");
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+/// Proxy a fixed payload through a local echo server and check that it is echoed unchanged.
+fn self_test() -> io::Result<()> {
+    let payload: Vec<u8> = (0..4 << 20).map(|i: u32| (i % 251) as u8).collect();
+
+    let echo = TcpListener::bind("127.0.0.1:0")?;
+    let echo_address = echo.local_addr()?;
+    let echo = thread::spawn(move || -> io::Result<()> {
+        let (mut stream, _) = echo.accept()?;
+        io::copy(&mut stream.try_clone()?, &mut stream)?;
+        stream.shutdown(Shutdown::Write)
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    let expected = payload.clone();
+    let client = thread::spawn(move || -> io::Result<Vec<u8>> {
+        let mut stream = TcpStream::connect(address)?;
+        let mut writer = stream.try_clone()?;
+        let send = thread::spawn(move || -> io::Result<()> {
+            io::Write::write_all(&mut writer, &payload)?;
+            writer.shutdown(Shutdown::Write)
+        });
+        let mut echoed = Vec::new();
+        io::Read::read_to_end(&mut stream, &mut echoed)?;
+        send.join().unwrap()?;
+        Ok(echoed)
+    });
+
+    let (stream, _) = listener.accept()?;
+    proxy(stream, TcpStream::connect(echo_address)?)?;
+    echo.join().unwrap()?;
+    let echoed = client.join().unwrap()?;
+    assert!(echoed == expected, "the payload was not echoed unchanged");
+    println!("echoed {} bytes unchanged", echoed.len());
+    Ok(())
+}
+
+/// The example proxies `std` sockets, so does nothing without it.
+#[cfg(feature = "no_std")]
+fn main() {}
@@ -1,9 +1,12 @@
+#[cfg(not(feature = "no_std"))]
 use std::mem::MaybeUninit;
 
-use circulate::{BufStream, Read, Write};
+#[cfg(not(feature = "no_std"))]
+use circulate::{BufStream, FromStd, Read};
 
+#[cfg(not(feature = "no_std"))]
 fn main() {
-    let mut stream = BufStream::with_capacity(std::net::TcpStream::connect("localhost:8001").unwrap(), 512);
+    let mut stream = BufStream::with_capacity(FromStd(std::net::TcpStream::connect("localhost:8001").unwrap()), 512);
 
     let mut buffer: [MaybeUninit<u8>; 4096] = unsafe { MaybeUninit::uninit().assume_init() };
     while let Ok(len @ 1..) = stream.read(&mut buffer) {
@@ -14,3 +17,7 @@ fn main() {
         println!("recv: {string:?}")
     }
 }
+
+/// The example reads from a `std` socket, so does nothing without it.
+#[cfg(feature = "no_std")]
+fn main() {}
@@ -0,0 +1,53 @@
+//! Simulates an interrupt-driven UART that echoes received bytes back, with threads standing in
+//! for the receive and transmit interrupt handlers.
+use std::{sync::mpsc, thread};
+
+use circulate::StaticRing;
+
+static RX: StaticRing<u8, 16> = StaticRing::new();
+static TX: StaticRing<u8, 16> = StaticRing::new();
+
+fn main() {
+    let (mut rx_producer, mut rx_consumer) = RX.split().unwrap();
+    let (mut tx_producer, mut tx_consumer) = TX.split().unwrap();
+
+    // The receive interrupt pushes each byte as it arrives from the line.
+    let receive = thread::spawn(move || {
+        for byte in b"hello, uart!\n" {
+            while rx_producer.push(*byte).is_err() {
+                thread::yield_now();
+            }
+        }
+    });
+    // The transmit interrupt sends each byte queued for the line.
+    let (line, echoed) = mpsc::channel();
+    let transmit = thread::spawn(move || loop {
+        match tx_consumer.pop() {
+            Some(byte) => {
+                line.send(byte).unwrap();
+                if byte == b'\n' {
+                    break;
+                }
+            }
+            None => thread::yield_now(),
+        }
+    });
+
+    // The main loop echoes received bytes, upper cased.
+    loop {
+        let Some(byte) = rx_consumer.pop() else {
+            thread::yield_now();
+            continue;
+        };
+        while tx_producer.push(byte.to_ascii_uppercase()).is_err() {
+            thread::yield_now();
+        }
+        if byte == b'\n' {
+            break;
+        }
+    }
+    receive.join().unwrap();
+    transmit.join().unwrap();
+    let echoed: Vec<u8> = echoed.iter().collect();
+    print!("{}", String::from_utf8_lossy(&echoed));
+}
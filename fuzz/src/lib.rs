@@ -0,0 +1,279 @@
+//! The checks run by each fuzz target, on operations decoded from the fuzzer's bytes.
+//!
+//! Run a target with `cargo +nightly fuzz run ring_buffer` from this directory. The same checks
+//! also run on pseudo-random bytes in `tests/fuzz.rs` of the crate, so they are built and pass
+//! along with the rest of its tests.
+
+use std::collections::VecDeque;
+use circulate::{test_util::ScriptedStream, BufStream, IoVec, IoVecCursor, IoVecMut, Read, RingBuffer, WriteAllError, WriteExt};
+
+/// Decodes values from the fuzzer's bytes, producing zeros once they run out.
+pub struct Input<'a> {
+    data: &'a [u8],
+}
+impl<'a> Input<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+    /// Returns if every byte has been used.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    pub fn byte(&mut self) -> u8 {
+        match self.data.split_first() {
+            Some((&byte, rest)) => {
+                self.data = rest;
+                byte
+            }
+            None => 0,
+        }
+    }
+    /// Get a length from 0 to `max`.
+    pub fn len(&mut self, max: usize) -> usize {
+        let value = u16::from_le_bytes([self.byte(), self.byte()]) as usize;
+        value % (max + 1)
+    }
+    /// Take up to `len` bytes, fewer if they run out.
+    pub fn bytes(&mut self, len: usize) -> &'a [u8] {
+        let (bytes, rest) = self.data.split_at(len.min(self.data.len()));
+        self.data = rest;
+        bytes
+    }
+    /// Split `len` bytes in to parts of arbitrary lengths, some of which may be empty. The last
+    /// part takes what is left once the input runs out.
+    pub fn split(&mut self, len: usize) -> Vec<usize> {
+        let mut parts = Vec::new();
+        let mut left = len;
+        while left != 0 && !self.is_empty() && parts.len() < 64 {
+            let part = self.len(left);
+            parts.push(part);
+            left -= part;
+        }
+        parts.push(left);
+        parts
+    }
+}
+
+/// Run arbitrary operations on a [`RingBuffer`], checking its items against a [`VecDeque`] after
+/// each one.
+pub fn ring_buffer(data: &[u8]) {
+    let mut input = Input::new(data);
+    let mut buffer = match input.byte() % 3 {
+        0 => RingBuffer::new(),
+        1 => RingBuffer::with_capacity(input.len(64)),
+        _ => RingBuffer::with_exact_capacity(input.len(64)),
+    };
+    let mut model = VecDeque::new();
+    while !input.is_empty() {
+        match input.byte() % 12 {
+            0 => {
+                let byte = input.byte();
+                buffer.push(byte).unwrap();
+                model.push_back(byte);
+            }
+            1 => assert_eq!(buffer.pop(), model.pop_front()),
+            2 => {
+                let len = input.len(128);
+                let bytes = input.bytes(len);
+                buffer.extend_from_slice(bytes).unwrap();
+                model.extend(bytes);
+            }
+            3 => {
+                let mut out = vec![0; input.len(128)];
+                let popped = buffer.pop_slice(&mut out);
+                assert_eq!(popped, out.len().min(model.len()));
+                assert!(model.drain(..popped).eq(out[..popped].iter().copied()));
+            }
+            4 => buffer.reserve(input.len(512)).unwrap(),
+            5 => assert_eq!(buffer.make_contiguous(), model.make_contiguous()),
+            6 => {
+                buffer.clear();
+                model.clear();
+            }
+            7 => {
+                let mut left = input.len(64);
+                let evicted = buffer.evict_while(|_| {
+                    left = left.saturating_sub(1);
+                    left != 0
+                });
+                model.drain(..evicted);
+            }
+            8 => {
+                let below = input.byte();
+                match buffer.pop_checked(|byte| *byte < below) {
+                    Ok(byte) => assert_eq!(byte, model.pop_front()),
+                    Err(_) => assert!(model.front().is_some_and(|byte| *byte >= below)),
+                }
+            }
+            9 => buffer.set_auto_shrink([0.0, 0.25, 0.5][input.len(2)]),
+            10 => {
+                let index = input.len(128);
+                if index < model.len() {
+                    let byte = input.byte();
+                    *buffer.get_mut(index).unwrap() = byte;
+                    model[index] = byte;
+                }
+            }
+            _ => {
+                let (lhs, rhs) = buffer.as_mut_slices();
+                assert!(lhs.iter().chain(&*rhs).eq(&model));
+            }
+        }
+        assert_eq!(buffer.len(), model.len());
+        assert!(buffer.iter().eq(&model));
+        assert!(buffer.len() < buffer.capacity() || buffer.capacity() == 0);
+    }
+}
+
+/// Read from and write through a [`BufStream`] over a stream scripted by the input, which reads
+/// and writes short and fails at arbitrary points, checking that the bytes read and the bytes
+/// written each arrive whole and in order.
+pub fn buf_stream(data: &[u8]) {
+    let mut input = Input::new(data);
+    let capacity = input.len(64);
+    let bounded = input.byte().is_multiple_of(2);
+    let script_len = input.len(64);
+    let script = input.bytes(script_len);
+    let incoming_len = input.len(512);
+    let incoming = input.bytes(incoming_len);
+    let mut stream = BufStream::with_capacity(ScriptedStream::from_script(incoming.to_vec(), script), capacity);
+    if bounded {
+        stream.set_max_output_capacity(capacity.max(2));
+    }
+    let mut written = Vec::new();
+    let mut read = Vec::new();
+    while !input.is_empty() {
+        match input.byte() % 7 {
+            0 => {
+                let len = input.len(128);
+                let bytes = input.bytes(len);
+                // A failed write must not have taken any of the bytes.
+                if let Ok(accepted) = circulate::Write::write(&mut stream, bytes) {
+                    written.extend_from_slice(&bytes[..accepted]);
+                }
+            }
+            1 => {
+                let _ = circulate::Write::flush(&mut stream);
+            }
+            2 => {
+                let _ = stream.flush_nonblocking();
+            }
+            3 => {
+                let mut buffer = vec![0; 1 + input.len(127)];
+                if let Ok(got) = stream.read(IoVecMut::new(&mut buffer).as_maybe_uninit_slice()) {
+                    read.extend_from_slice(&buffer[..got]);
+                }
+            }
+            4 => {
+                let mut storage = vec![0; input.len(128)];
+                let parts = input.split(storage.len());
+                let mut buffers = split_mut(&mut storage, &parts);
+                if let Ok(got) = stream.read_vectored(&mut buffers) {
+                    drop(buffers);
+                    read.extend_from_slice(&storage[..got]);
+                }
+            }
+            5 => {
+                let _ = stream.fill_at_least(input.len(64));
+            }
+            _ => {
+                let count = input.len(read.len().min(16));
+                if stream.unread(&read[read.len() - count..]).is_ok() {
+                    read.truncate(read.len() - count);
+                }
+            }
+        }
+        assert!(written.starts_with(stream.get_ref().get_ref().output()));
+        let (lhs, rhs) = stream.buffered_input();
+        assert!(incoming.starts_with(&[&read[..], lhs, rhs].concat()));
+    }
+
+    // Once the script runs out the stream no longer fails, so everything arrives.
+    while circulate::Write::flush(&mut stream).is_err() {}
+    assert_eq!(stream.get_ref().get_ref().output(), written);
+    let mut buffer = [0; 64];
+    loop {
+        match stream.read(IoVecMut::new(&mut buffer).as_maybe_uninit_slice()) {
+            Ok(0) => break,
+            Ok(got) => read.extend_from_slice(&buffer[..got]),
+            Err(_) => continue,
+        }
+    }
+    assert_eq!(read, incoming);
+}
+
+/// Split `storage` in to buffers of the lengths of each of `parts`.
+fn split_mut<'a>(mut storage: &'a mut [u8], parts: &[usize]) -> Vec<IoVecMut<'a>> {
+    parts.iter().map(|&part| {
+        let (buffer, rest) = core::mem::take(&mut storage).split_at_mut(part);
+        storage = rest;
+        IoVecMut::new(buffer)
+    }).collect()
+}
+
+/// Concatenate the bytes of `buffers`.
+fn concat(buffers: &[IoVec]) -> Vec<u8> {
+    buffers.iter().flat_map(IoVec::as_maybe_uninit_slice).map(|byte| {
+        // Safety: Every buffer is made from an initialized slice.
+        unsafe { byte.assume_init() }
+    }).collect()
+}
+
+/// Split a payload in to lists of [`IoVec`] and [`IoVecMut`] at arbitrary points, and check that
+/// advancing through them, writing them and filling them each keeps the bytes in order.
+pub fn iovec(data: &[u8]) {
+    let mut input = Input::new(data);
+    let payload_len = input.len(256);
+    let payload = input.bytes(payload_len);
+    let parts = input.split(payload.len());
+    let mut offset = 0;
+    let slices: Vec<&[u8]> = parts.iter().map(|&part| {
+        offset += part;
+        &payload[offset - part..offset]
+    }).collect();
+
+    // Advancing by arbitrary steps leaves the rest of the payload, both in the list advanced and
+    // in the buffers it was taken from.
+    let mut buffers: Vec<IoVec> = slices.iter().map(|slice| IoVec::new(slice)).collect();
+    let mut rest = &mut buffers[..];
+    let mut advanced = 0;
+    while advanced < payload.len() && !input.is_empty() {
+        let step = input.len(payload.len() - advanced);
+        IoVec::advance_slices(&mut rest, step);
+        advanced += step;
+        assert_eq!(concat(rest), &payload[advanced..]);
+    }
+    assert_eq!(concat(&buffers), &payload[advanced..]);
+
+    // Writing every buffer to a stream that writes short and fails, retrying with what is left.
+    let script_len = input.len(32);
+    let mut writer = ScriptedStream::from_script(Vec::new(), input.bytes(script_len));
+    let mut buffers: Vec<IoVec> = slices.iter().map(|slice| IoVec::new(slice)).collect();
+    loop {
+        match writer.write_all_vectored(&mut buffers) {
+            Ok(()) => break,
+            Err(WriteAllError::Write(_)) => assert_eq!([writer.get_ref().output(), &concat(&buffers)].concat(), payload),
+            Err(error) => panic!("{error:?}"),
+        }
+    }
+    assert_eq!(writer.get_ref().output(), payload);
+
+    // Filling buffers split at the same points with arbitrary chunks.
+    let mut storage = vec![0; payload.len()];
+    let mut buffers = split_mut(&mut storage, &parts);
+    let mut cursor = IoVecCursor::new(&mut buffers);
+    let mut filled = 0;
+    while !cursor.is_full() {
+        let chunk = match input.is_empty() {
+            true => payload.len() - filled,
+            false => input.len(payload.len() - filled),
+        };
+        assert_eq!(cursor.write(&payload[filled..filled + chunk]), chunk);
+        filled += chunk;
+        assert_eq!(cursor.filled(), filled);
+        assert_eq!(cursor.filled_slices().flatten().copied().collect::<Vec<u8>>(), &payload[..filled]);
+    }
+    assert_eq!(cursor.write(b"!"), 0);
+    drop(buffers);
+    assert_eq!(storage, payload);
+}
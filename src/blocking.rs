@@ -0,0 +1,255 @@
+use std::{io, sync::{Arc, Condvar, Mutex, MutexGuard}, time::{Duration, Instant}};
+use crate::RingBuffer;
+
+/// Create a bounded pipe of bytes between threads, holding up to `capacity` bytes.
+///
+/// Writes block while the pipe is full and reads block while it is empty. Dropping the
+/// [`BlockingSender`] ends the stream once the bytes already written are read, and dropping the
+/// [`BlockingReceiver`] fails every later write with [`io::ErrorKind::BrokenPipe`].
+///
+/// # Panics
+/// If `capacity` is 0.
+/// ```rust
+/// use std::{io::{Read, Write}, thread};
+///
+/// // A checksum that depends on the order of the bytes.
+/// fn fnv(hash: u64, bytes: &[u8]) -> u64 {
+///     bytes.iter().fold(hash, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100_0000_01b3))
+/// }
+///
+/// let (mut sender, mut receiver) = circulate::blocking_channel(64 * 1024);
+/// let producer = thread::spawn(move || {
+///     let mut state = 0x2545_f491_4f6c_dd1du64;
+///     let mut chunk = [0; 4000];
+///     let mut hash = 0xcbf2_9ce4_8422_2325;
+///     for _ in 0..100_000_000 / chunk.len() {
+///         for byte in &mut chunk {
+///             state ^= state << 13;
+///             state ^= state >> 7;
+///             state ^= state << 17;
+///             *byte = state as u8;
+///         }
+///         sender.write_all(&chunk).unwrap();
+///         hash = fnv(hash, &chunk);
+///     }
+///     hash
+/// });
+///
+/// let mut chunk = [0; 3000];
+/// let (mut hash, mut total) = (0xcbf2_9ce4_8422_2325, 0);
+/// loop {
+///     match receiver.read(&mut chunk).unwrap() {
+///         // The sender was dropped and every byte has been read.
+///         0 => break,
+///         count => {
+///             hash = fnv(hash, &chunk[..count]);
+///             total += count;
+///         }
+///     }
+/// }
+/// assert_eq!(total, 100_000_000);
+/// assert_eq!(hash, producer.join().unwrap());
+/// ```
+#[track_caller]
+pub fn blocking_channel(capacity: usize) -> (BlockingSender, BlockingReceiver) {
+    assert!(capacity != 0, "a blocking channel must hold at least one byte");
+    let mut buffer = RingBuffer::with_exact_capacity(capacity);
+    // One slot always remains vacant.
+    buffer.set_max_capacity(capacity + 1);
+    let shared = Arc::new(BlockingRing {
+        state: Mutex::new(State { buffer, sender: true, receiver: true }),
+        readable: Condvar::new(),
+        writable: Condvar::new(),
+    });
+    (BlockingSender { shared: shared.clone(), timeout: None }, BlockingReceiver { shared, timeout: None })
+}
+
+/// The state shared by both ends of a [`blocking_channel`].
+struct BlockingRing {
+    state: Mutex<State>,
+    /// Notified when bytes are written or the sender is dropped.
+    readable: Condvar,
+    /// Notified when bytes are read or the receiver is dropped.
+    writable: Condvar,
+}
+struct State {
+    buffer: RingBuffer<u8>,
+    /// Set while the sender has not been dropped.
+    sender: bool,
+    /// Set while the receiver has not been dropped.
+    receiver: bool,
+}
+impl BlockingRing {
+    /// Lock the state, ignoring poisoning as the state is never left inconsistent.
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+    /// Wait on `condvar` until `ready` holds, failing with [`io::ErrorKind::TimedOut`] once
+    /// `timeout` has passed.
+    fn wait_until<'a>(
+        &self,
+        condvar: &Condvar,
+        mut state: MutexGuard<'a, State>,
+        timeout: Option<Duration>,
+        ready: impl Fn(&State) -> bool,
+    ) -> io::Result<MutexGuard<'a, State>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        while !ready(&state) {
+            state = match deadline {
+                None => condvar.wait(state).unwrap_or_else(|error| error.into_inner()),
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(io::ErrorKind::TimedOut.into());
+                    }
+                    condvar.wait_timeout(state, remaining).unwrap_or_else(|error| error.into_inner()).0
+                }
+            };
+        }
+        Ok(state)
+    }
+}
+
+/// The writing half of a [`blocking_channel`].
+///
+/// The pipe ends once the sender is dropped.
+/// ```rust
+/// use std::io::{ErrorKind, Read, Write};
+///
+/// let (mut sender, mut receiver) = circulate::blocking_channel(4);
+/// sender.write_all(b"abc").unwrap();
+/// drop(sender);
+/// // Bytes written before the sender was dropped are still read.
+/// let mut bytes = Vec::new();
+/// receiver.read_to_end(&mut bytes).unwrap();
+/// assert_eq!(bytes, b"abc");
+///
+/// let (mut sender, receiver) = circulate::blocking_channel(4);
+/// sender.write_all(b"abc").unwrap();
+/// drop(receiver);
+/// // Nothing can read the bytes once the receiver is dropped, even if they fit.
+/// assert_eq!(sender.write(b"d").unwrap_err().kind(), ErrorKind::BrokenPipe);
+///
+/// // Dropping the receiver wakes a sender blocked on a full pipe.
+/// let (mut sender, receiver) = circulate::blocking_channel(4);
+/// let blocked = std::thread::spawn(move || sender.write_all(b"more than fits"));
+/// std::thread::sleep(std::time::Duration::from_millis(10));
+/// drop(receiver);
+/// assert_eq!(blocked.join().unwrap().unwrap_err().kind(), ErrorKind::BrokenPipe);
+/// ```
+pub struct BlockingSender {
+    shared: Arc<BlockingRing>,
+    timeout: Option<Duration>,
+}
+impl BlockingSender {
+    /// Set how long a write may block waiting for space before it fails with
+    /// [`io::ErrorKind::TimedOut`], or [`None`] to block indefinitely.
+    /// ```rust
+    /// use std::{io::{ErrorKind, Write}, time::Duration};
+    ///
+    /// let (mut sender, _receiver) = circulate::blocking_channel(4);
+    /// sender.set_write_timeout(Some(Duration::from_millis(10)));
+    /// // A write returns as soon as some of the bytes fit.
+    /// assert_eq!(sender.write(b"abcdef").unwrap(), 4);
+    /// assert_eq!(sender.write(b"ef").unwrap_err().kind(), ErrorKind::TimedOut);
+    /// ```
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+    /// Get how long a write may block waiting for space.
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+impl io::Write for BlockingSender {
+    fn write(&mut self, slice: &[u8]) -> io::Result<usize> {
+        if slice.is_empty() {
+            return Ok(0);
+        }
+        let shared = &*self.shared;
+        let mut state = shared.wait_until(&shared.writable, shared.lock(), self.timeout, |state| {
+            !state.receiver || !state.buffer.is_full()
+        })?;
+        if !state.receiver {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        let count = slice.len().min(state.buffer.capacity() - 1 - state.buffer.len());
+        // Note: The buffer never needs to grow, as only the free bytes are written.
+        let _ = state.buffer.extend_from_slice(&slice[..count]);
+        drop(state);
+        shared.readable.notify_one();
+        Ok(count)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl Drop for BlockingSender {
+    fn drop(&mut self) {
+        self.shared.lock().sender = false;
+        self.shared.readable.notify_one();
+    }
+}
+
+/// The reading half of a [`blocking_channel`].
+///
+/// Reads return 0 once the sender is dropped and every byte has been read.
+/// ```rust
+/// use std::io::Read;
+///
+/// // Dropping the sender wakes a receiver blocked on an empty pipe.
+/// let (sender, mut receiver) = circulate::blocking_channel(4);
+/// let blocked = std::thread::spawn(move || receiver.read(&mut [0; 4]).unwrap());
+/// std::thread::sleep(std::time::Duration::from_millis(10));
+/// drop(sender);
+/// assert_eq!(blocked.join().unwrap(), 0);
+/// ```
+pub struct BlockingReceiver {
+    shared: Arc<BlockingRing>,
+    timeout: Option<Duration>,
+}
+impl BlockingReceiver {
+    /// Set how long a read may block waiting for bytes before it fails with
+    /// [`io::ErrorKind::TimedOut`], or [`None`] to block indefinitely.
+    /// ```rust
+    /// use std::{io::{ErrorKind, Read, Write}, time::Duration};
+    ///
+    /// let (mut sender, mut receiver) = circulate::blocking_channel(4);
+    /// receiver.set_read_timeout(Some(Duration::from_millis(10)));
+    /// let mut bytes = [0; 4];
+    /// assert_eq!(receiver.read(&mut bytes).unwrap_err().kind(), ErrorKind::TimedOut);
+    ///
+    /// // A timed out read loses nothing.
+    /// sender.write_all(b"ab").unwrap();
+    /// assert_eq!(receiver.read(&mut bytes).unwrap(), 2);
+    /// assert_eq!(&bytes[..2], b"ab");
+    /// ```
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+    /// Get how long a read may block waiting for bytes.
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+}
+impl io::Read for BlockingReceiver {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        let shared = &*self.shared;
+        let mut state = shared.wait_until(&shared.readable, shared.lock(), self.timeout, |state| {
+            !state.sender || !state.buffer.is_empty()
+        })?;
+        let count = state.buffer.pop_slice(buffer);
+        drop(state);
+        shared.writable.notify_one();
+        Ok(count)
+    }
+}
+impl Drop for BlockingReceiver {
+    fn drop(&mut self) {
+        self.shared.lock().receiver = false;
+        self.shared.writable.notify_one();
+    }
+}
@@ -0,0 +1,192 @@
+//! A C interface to a byte [`RingBuffer`], for sharing one between Rust and C code in the same
+//! process.
+//!
+//! The build generates the header `circulate.h` in `OUT_DIR`. Functions returning `ptrdiff_t`
+//! return a count or 0 on success, and a negative `CIRCULATE_ERROR_*` code on failure.
+//!
+//! # Lifetime of a ring
+//! - A ring created by [`circulate_ring_new`] must be passed to [`circulate_ring_free`] exactly
+//!   once, after which the pointer must not be used again.
+//! - Calls on the same ring must not overlap. A ring may be used from any one thread at a time.
+//! - The region returned by [`circulate_ring_grant`] is valid until the next call to
+//!   [`circulate_ring_commit`] or [`circulate_ring_free`], and [`circulate_ring_push`] fails
+//!   with [`CIRCULATE_ERROR_GRANTED`] until then, rather than write in to it.
+//! - A panic never unwinds in to C. It is caught, the call returns [`CIRCULATE_ERROR_PANIC`] and
+//!   every later call but [`circulate_ring_free`] returns [`CIRCULATE_ERROR_POISONED`].
+#![allow(non_camel_case_types)]
+
+extern crate alloc;
+use alloc::boxed::Box;
+use core::ptr;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use crate::{AllocFailure, RingBuffer};
+
+/// A required pointer was null.
+pub const CIRCULATE_ERROR_NULL: isize = -1;
+/// The call panicked. The ring is poisoned.
+pub const CIRCULATE_ERROR_PANIC: isize = -2;
+/// An earlier call on the ring panicked.
+pub const CIRCULATE_ERROR_POISONED: isize = -3;
+/// A grant is outstanding, or a commit was made without one or for more than was granted.
+pub const CIRCULATE_ERROR_GRANTED: isize = -4;
+
+/// An opaque ring of bytes with a fixed capacity.
+pub struct circulate_ring_t {
+    ring: RingBuffer<u8>,
+    /// The length of the region handed out by the last grant, until it is committed.
+    granted: Option<usize>,
+    poisoned: bool,
+}
+
+/// Run `f` on a ring, catching a panic and poisoning the ring.
+fn with_ring(ring: Option<&mut circulate_ring_t>, f: impl FnOnce(&mut circulate_ring_t) -> isize) -> isize {
+    let Some(ring) = ring else {
+        return CIRCULATE_ERROR_NULL;
+    };
+    if ring.poisoned {
+        return CIRCULATE_ERROR_POISONED;
+    }
+    match catch_unwind(AssertUnwindSafe(|| f(ring))) {
+        Ok(result) => result,
+        Err(_) => {
+            ring.poisoned = true;
+            CIRCULATE_ERROR_PANIC
+        }
+    }
+}
+
+/// Create a ring holding at least `capacity` bytes.
+///
+/// Returns null if the ring cannot be allocated.
+#[no_mangle]
+pub extern "C" fn circulate_ring_new(capacity: usize) -> *mut circulate_ring_t {
+    catch_unwind(|| {
+        let mut ring = RingBuffer::new();
+        ring.set_alloc_failure_mode(AllocFailure::Saturate);
+        ring.reserve(capacity).ok()?;
+        ring.set_max_capacity(ring.capacity());
+        Some(Box::into_raw(Box::new(circulate_ring_t { ring, granted: None, poisoned: false })))
+    }).ok().flatten().unwrap_or(ptr::null_mut())
+}
+
+/// Destroy a ring and any bytes in it. Does nothing if `ring` is null.
+///
+/// # Safety
+/// `ring` must be null or a live ring created by [`circulate_ring_new`], not otherwise in use.
+#[no_mangle]
+pub unsafe extern "C" fn circulate_ring_free(ring: *mut circulate_ring_t) {
+    if !ring.is_null() {
+        drop(unsafe { Box::from_raw(ring) });
+    }
+}
+
+/// Copy as many of the `len` bytes at `data` in to the ring as fit, returning how many did.
+///
+/// # Safety
+/// `ring` must be null or a live ring not otherwise in use. `data` must be readable for `len`
+/// bytes, and may be null only if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn circulate_ring_push(ring: *mut circulate_ring_t, data: *const u8, len: usize) -> isize {
+    with_ring(unsafe { ring.as_mut() }, |ring| {
+        if ring.granted.is_some() {
+            return CIRCULATE_ERROR_GRANTED;
+        }
+        if len == 0 {
+            return 0;
+        }
+        if data.is_null() {
+            return CIRCULATE_ERROR_NULL;
+        }
+        let free = ring.ring.capacity().saturating_sub(ring.ring.len() + 1);
+        let count = len.min(free);
+        // Safety: The caller guarantees `data` is readable for `len` bytes.
+        let data = unsafe { core::slice::from_raw_parts(data, count) };
+        match ring.ring.extend_from_slice(data) {
+            Ok(()) => count as isize,
+            Err(_) => 0,
+        }
+    })
+}
+
+/// Move up to `len` bytes from the front of the ring to `out`, returning how many were moved.
+///
+/// # Safety
+/// `ring` must be null or a live ring not otherwise in use. `out` must be writable for `len`
+/// bytes, and may be null only if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn circulate_ring_pop(ring: *mut circulate_ring_t, out: *mut u8, len: usize) -> isize {
+    with_ring(unsafe { ring.as_mut() }, |ring| {
+        if len == 0 {
+            return 0;
+        }
+        if out.is_null() {
+            return CIRCULATE_ERROR_NULL;
+        }
+        // Safety: The caller guarantees `out` is writable for `len` bytes, and `u8` has no
+        // invalid values.
+        let out = unsafe { core::slice::from_raw_parts_mut(out, len) };
+        ring.ring.pop_slice(out) as isize
+    })
+}
+
+/// Get the number of bytes in the ring.
+///
+/// # Safety
+/// `ring` must be null or a live ring not otherwise in use.
+#[no_mangle]
+pub unsafe extern "C" fn circulate_ring_len(ring: *mut circulate_ring_t) -> isize {
+    with_ring(unsafe { ring.as_mut() }, |ring| ring.ring.len() as isize)
+}
+
+/// Get the number of bytes the ring can hold, which may be more than it was created with.
+///
+/// # Safety
+/// `ring` must be null or a live ring not otherwise in use.
+#[no_mangle]
+pub unsafe extern "C" fn circulate_ring_capacity(ring: *mut circulate_ring_t) -> isize {
+    with_ring(unsafe { ring.as_mut() }, |ring| ring.ring.capacity().saturating_sub(1) as isize)
+}
+
+/// Grant the contiguous free region after the last byte of the ring to be written in place.
+///
+/// Stores a pointer to the region in `out` and returns its length, which is 0 if the ring is
+/// full. It may be shorter than the free space where that wraps to the start of the ring. The
+/// bytes written are added to the ring by [`circulate_ring_commit`]. Granting again before then
+/// grants the same region.
+///
+/// # Safety
+/// `ring` must be null or a live ring not otherwise in use. `out` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn circulate_ring_grant(ring: *mut circulate_ring_t, out: *mut *mut u8) -> isize {
+    with_ring(unsafe { ring.as_mut() }, |ring| {
+        if out.is_null() {
+            return CIRCULATE_ERROR_NULL;
+        }
+        let (region, _) = ring.ring.spare_capacity_mut();
+        // Safety: The caller guarantees `out` is writable.
+        unsafe { out.write(region.as_mut_ptr().cast()) };
+        ring.granted = Some(region.len());
+        region.len() as isize
+    })
+}
+
+/// End the outstanding grant, adding the first `count` bytes of the granted region to the ring.
+///
+/// A `count` of 0 cancels the grant.
+///
+/// # Safety
+/// `ring` must be null or a live ring not otherwise in use. The first `count` bytes of the
+/// granted region must have been written.
+#[no_mangle]
+pub unsafe extern "C" fn circulate_ring_commit(ring: *mut circulate_ring_t, count: usize) -> isize {
+    with_ring(unsafe { ring.as_mut() }, |ring| match ring.granted {
+        Some(granted) if count <= granted => {
+            // Safety: The granted region is free space after the write cursor, ending before the
+            // vacant slot, and the caller guarantees `count` bytes of it are initialized.
+            unsafe { ring.ring.advance_write_unchecked(count) };
+            ring.granted = None;
+            0
+        }
+        _ => CIRCULATE_ERROR_GRANTED,
+    })
+}
@@ -0,0 +1,112 @@
+extern crate alloc;
+use alloc::vec::Vec;
+use std::sync::{Arc, Mutex, MutexGuard};
+use crate::RingBuffer;
+
+/// Create a bounded multi-producer, single-consumer channel holding up to `capacity` values.
+///
+/// Sending never blocks on a full channel, instead the value is handed back.
+/// ```rust
+/// use std::thread;
+/// let (sender, receiver) = circulate::mpsc_channel(64);
+/// let senders: Vec<_> = (0..3u32).map(|id| {
+///     let sender = sender.clone();
+///     thread::spawn(move || {
+///         for i in 0..1000 {
+///             let mut value = id * 1000 + i;
+///             while let Err(error) = sender.send(value) {
+///                 value = error.into_inner();
+///                 thread::yield_now();
+///             }
+///         }
+///     })
+/// }).collect();
+///
+/// let mut received = Vec::new();
+/// while received.len() < 3000 {
+///     match receiver.recv() {
+///         Some(value) => received.push(value),
+///         None => thread::yield_now(),
+///     }
+/// }
+/// for sender in senders {
+///     sender.join().unwrap();
+/// }
+/// assert_eq!(receiver.recv(), None);
+/// received.sort();
+/// assert!(received.into_iter().eq(0..3000));
+/// ```
+pub fn mpsc_channel<T: Send>(capacity: usize) -> (MpscSender<T>, Receiver<T>) {
+    let mut buffer = RingBuffer::with_exact_capacity(capacity);
+    // One slot always remains vacant.
+    buffer.set_max_capacity(capacity + 1);
+    let shared = Arc::new(Mutex::new(buffer));
+    (MpscSender { shared: shared.clone() }, Receiver { shared })
+}
+
+/// Lock the buffer, ignoring poisoning as the buffer is never left in an inconsistent state.
+fn lock<T>(shared: &Mutex<RingBuffer<T>>) -> MutexGuard<'_, RingBuffer<T>> {
+    shared.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+/// The sending half of an [`mpsc_channel`].
+/// It may be cloned to share the channel between many senders.
+pub struct MpscSender<T> {
+    shared: Arc<Mutex<RingBuffer<T>>>,
+}
+impl<T> Clone for MpscSender<T> {
+    fn clone(&self) -> Self {
+        Self { shared: self.shared.clone() }
+    }
+}
+impl<T> MpscSender<T> {
+    /// Send a value, handing it back if the channel is full.
+    /// ```rust
+    /// let (sender, receiver) = circulate::mpsc_channel(2);
+    /// sender.send(1).unwrap();
+    /// sender.send(2).unwrap();
+    /// assert_eq!(sender.send(3).unwrap_err().into_inner(), 3);
+    /// assert_eq!(receiver.recv_all(), [1, 2]);
+    /// assert_eq!(receiver.recv(), None);
+    /// ```
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        lock(&self.shared).push(value).map_err(SendError)
+    }
+}
+
+/// The receiving half of an [`mpsc_channel`].
+pub struct Receiver<T> {
+    shared: Arc<Mutex<RingBuffer<T>>>,
+}
+impl<T> Receiver<T> {
+    /// Receive the oldest value, or [`None`] if the channel is empty.
+    pub fn recv(&self) -> Option<T> {
+        lock(&self.shared).pop()
+    }
+    /// Receive every value currently in the channel, oldest first.
+    pub fn recv_all(&self) -> Vec<T> {
+        let mut buffer = lock(&self.shared);
+        let mut values = Vec::with_capacity(buffer.len());
+        while let Some(value) = buffer.pop() {
+            values.push(value);
+        }
+        values
+    }
+}
+
+/// The error returned by [`MpscSender::send`] when the channel is full, holding the value that
+/// could not be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+impl<T> SendError<T> {
+    /// Take back the value that could not be sent.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<T> core::fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("sending on a full channel")
+    }
+}
+impl<T: core::fmt::Debug> core::error::Error for SendError<T> {}
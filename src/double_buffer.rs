@@ -0,0 +1,56 @@
+use crate::RingBuffer;
+
+/// A pair of [`RingBuffer`]s where one is filled while the other is consumed.
+/// ```rust
+/// use circulate::DoubleBuffer;
+/// let mut buffers = DoubleBuffer::new();
+/// for i in 0..10 {
+///     buffers.fill_side().push(i).unwrap();
+/// }
+/// buffers.flip();
+/// assert!(buffers.fill_side().is_empty());
+/// assert!(buffers.consume_side().iter().eq(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+/// while let Some(_) = buffers.consume_side_mut().pop() {}
+/// assert!(buffers.consume_side().is_empty());
+/// ```
+pub struct DoubleBuffer<T> {
+    buffers: [RingBuffer<T>; 2],
+    /// The index of the buffer being filled.
+    fill: usize,
+}
+impl<T> DoubleBuffer<T> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            buffers: [RingBuffer::new(), RingBuffer::new()],
+            fill: 0,
+        }
+    }
+    /// Create a new [`DoubleBuffer`] where each side has space for at least `capacity` elements.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffers: [RingBuffer::with_capacity(capacity), RingBuffer::with_capacity(capacity)],
+            fill: 0,
+        }
+    }
+
+    /// Get the buffer currently being filled.
+    pub fn fill_side(&mut self) -> &mut RingBuffer<T> {
+        &mut self.buffers[self.fill]
+    }
+    /// Get the buffer currently being consumed.
+    pub fn consume_side(&mut self) -> &RingBuffer<T> {
+        &self.buffers[self.fill ^ 1]
+    }
+    /// Get the buffer currently being consumed, allowing values to be taken from it.
+    pub fn consume_side_mut(&mut self) -> &mut RingBuffer<T> {
+        &mut self.buffers[self.fill ^ 1]
+    }
+
+    /// Swap the roles of the buffers so the filled buffer is consumed.
+    /// Any values remaining on the consume side are dropped, leaving an empty buffer to fill.
+    pub fn flip(&mut self) {
+        self.fill ^= 1;
+        self.buffers[self.fill].clear();
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{CapacityError, RingBuffer};
+
+/// A ring of interleaved samples, such as stereo audio, that is only ever read or written in
+/// whole frames of `CHANNELS` samples.
+///
+/// Frames are stored as the items of a [`RingBuffer`], so the capacity and both cursors always
+/// lie on frame boundaries, including as the buffer grows, and a frame can never be split
+/// across a read or write. The samples are still contiguous in memory, so
+/// [`FrameRing::as_sample_slices`] views them interleaved without copying.
+/// ```rust
+/// use circulate::FrameRing;
+///
+/// let mut ring = FrameRing::<i16, 2>::with_capacity(4);
+/// ring.push_frame([1, -1]).unwrap();
+/// ring.extend_from_samples(&[2, -2, 3, -3]).unwrap();
+/// assert_eq!(ring.frames_len(), 3);
+/// assert_eq!(ring.pop_frame(), Some([1, -1]));
+///
+/// // Only whole frames are copied out.
+/// let mut samples = [0; 3];
+/// assert_eq!(ring.pop_samples(&mut samples), 1);
+/// assert_eq!(samples, [2, -2, 0]);
+/// assert!(ring.channel(1).eq(&[-3]));
+/// ```
+/// Channels never skew, however frame and bulk operations are interleaved, across the wrap and
+/// while growing.
+/// ```rust
+/// use circulate::FrameRing;
+///
+/// // Each sample records its frame and channel.
+/// let frame = |i: u32| [i * 4, i * 4 + 1, i * 4 + 2];
+/// let mut ring = FrameRing::<u32, 3>::with_capacity(4);
+/// let (mut pushed, mut popped) = (0, 0);
+/// for round in 0..200u32 {
+///     match round % 4 {
+///         0 => {
+///             ring.push_frame(frame(pushed)).unwrap();
+///             pushed += 1;
+///         }
+///         1 => {
+///             let count = round % 7;
+///             let samples: Vec<u32> = (pushed..pushed + count).flat_map(frame).collect();
+///             ring.extend_from_samples(&samples).unwrap();
+///             pushed += count;
+///         }
+///         2 => {
+///             if let Some(popped_frame) = ring.pop_frame() {
+///                 assert_eq!(popped_frame, frame(popped));
+///                 popped += 1;
+///             }
+///         }
+///         _ => {
+///             // A buffer that is not a whole number of frames leaves the rest untouched.
+///             let mut samples = vec![u32::MAX; (round % 5) as usize * 2];
+///             let count = ring.pop_samples(&mut samples);
+///             assert!(samples[..count * 3].chunks(3).eq((popped..popped + count as u32).map(frame)));
+///             assert!(samples[count * 3..].iter().all(|sample| *sample == u32::MAX));
+///             popped += count as u32;
+///         }
+///     }
+///     assert_eq!(ring.frames_len() as u32, pushed - popped);
+///     let (lhs, rhs) = ring.as_sample_slices();
+///     assert_eq!((lhs.len() % 3, rhs.len() % 3), (0, 0));
+///     for channel in 0..3 {
+///         assert!(ring.channel(channel).all(|sample| sample % 4 == channel as u32));
+///     }
+/// }
+/// ```
+pub struct FrameRing<T, const CHANNELS: usize> {
+    buffer: RingBuffer<[T; CHANNELS]>,
+}
+impl<T, const CHANNELS: usize> FrameRing<T, CHANNELS> {
+    /// Create an empty ring without allocating.
+    pub const fn new() -> Self {
+        const { assert!(CHANNELS != 0, "a frame needs at least one channel") };
+        Self { buffer: RingBuffer::new() }
+    }
+    /// Create an empty ring with space for at least `frames` frames.
+    pub fn with_capacity(frames: usize) -> Self {
+        const { assert!(CHANNELS != 0, "a frame needs at least one channel") };
+        Self { buffer: RingBuffer::with_capacity(frames) }
+    }
+    /// Create an empty ring that never grows beyond `frames` frames.
+    pub const fn with_max_capacity(frames: usize) -> Self {
+        const { assert!(CHANNELS != 0, "a frame needs at least one channel") };
+        Self { buffer: RingBuffer::with_max_capacity(frames) }
+    }
+    /// Get the buffer of frames back.
+    pub fn into_inner(self) -> RingBuffer<[T; CHANNELS]> {
+        self.buffer
+    }
+
+    /// Get the number of buffered frames.
+    pub const fn frames_len(&self) -> usize {
+        self.buffer.len()
+    }
+    /// Get the number of frames that fit before the ring grows.
+    pub const fn frames_capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+    /// Returns if no frames are buffered.
+    pub const fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+    /// Make space for at least `frames` more frames.
+    pub fn reserve(&mut self, frames: usize) -> Result<(), CapacityError> {
+        self.buffer.reserve(frames)
+    }
+    /// Drop every frame.
+    pub fn clear(&mut self) {
+        self.buffer.clear()
+    }
+
+    /// Push a frame to the write end, returning it if the ring could not grow to hold it.
+    pub fn push_frame(&mut self, frame: [T; CHANNELS]) -> Result<(), [T; CHANNELS]> {
+        self.buffer.push(frame)
+    }
+    /// Take the frame at the read end.
+    pub fn pop_frame(&mut self) -> Option<[T; CHANNELS]> {
+        self.buffer.pop()
+    }
+    /// Get the buffered frames, from the read end, as two slices.
+    pub const fn as_frame_slices(&self) -> (&[[T; CHANNELS]], &[[T; CHANNELS]]) {
+        self.buffer.as_slices()
+    }
+    /// Get the buffered frames, from the read end, as two mutable slices.
+    pub const fn as_mut_frame_slices(&mut self) -> (&mut [[T; CHANNELS]], &mut [[T; CHANNELS]]) {
+        self.buffer.as_mut_slices()
+    }
+    /// Get the buffered samples, from the read end and interleaved, as two slices. Each slice
+    /// holds a whole number of frames.
+    pub const fn as_sample_slices(&self) -> (&[T], &[T]) {
+        let (lhs, rhs) = self.buffer.as_slices();
+        (lhs.as_flattened(), rhs.as_flattened())
+    }
+    /// Returns an iterator over the samples of one channel, from the read end.
+    ///
+    /// # Panics
+    /// If `channel` is not less than `CHANNELS`.
+    #[track_caller]
+    pub fn channel(&self, channel: usize) -> impl Iterator<Item = &T> + '_ {
+        assert!(channel < CHANNELS, "channel (is {channel}) should be < CHANNELS (is {CHANNELS})");
+        self.buffer.iter().map(move |frame| &frame[channel])
+    }
+}
+impl<T: Copy, const CHANNELS: usize> FrameRing<T, CHANNELS> {
+    /// Copy interleaved samples to the write end, growing the ring as needed. Nothing is copied
+    /// if the ring cannot grow to hold all of them.
+    ///
+    /// # Panics
+    /// If the number of samples is not a whole number of frames.
+    #[track_caller]
+    pub fn extend_from_samples(&mut self, samples: &[T]) -> Result<(), CapacityError> {
+        let (frames, rest) = samples.as_chunks::<CHANNELS>();
+        assert!(rest.is_empty(), "{} samples are not a whole number of {CHANNELS} channel frames", samples.len());
+        self.buffer.extend_from_slice(frames)
+    }
+    /// Move as many whole frames as fit from the read end in to `samples`, interleaved.
+    /// Returns the number of frames moved, leaving any samples after them untouched.
+    pub fn pop_samples(&mut self, samples: &mut [T]) -> usize {
+        let (frames, _) = samples.as_chunks_mut::<CHANNELS>();
+        self.buffer.pop_slice(frames)
+    }
+}
+impl<T, const CHANNELS: usize> Default for FrameRing<T, CHANNELS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,32 @@
+use core::ops::{Deref, DerefMut};
+
+/// Runs a closure on a value when dropped, including while unwinding from a panic.
+///
+/// Code that calls user closures, or drops items whose `Drop` may panic, keeps the state it must
+/// restore in a guard so that a panic leaves it valid. Items may then be leaked, but are never
+/// dropped twice.
+pub(crate) struct DropGuard<T, F: FnMut(&mut T)> {
+    value: T,
+    on_drop: F,
+}
+impl<T, F: FnMut(&mut T)> DropGuard<T, F> {
+    pub(crate) const fn new(value: T, on_drop: F) -> Self {
+        Self { value, on_drop }
+    }
+}
+impl<T, F: FnMut(&mut T)> Deref for DropGuard<T, F> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+impl<T, F: FnMut(&mut T)> DerefMut for DropGuard<T, F> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+impl<T, F: FnMut(&mut T)> Drop for DropGuard<T, F> {
+    fn drop(&mut self) {
+        (self.on_drop)(&mut self.value)
+    }
+}
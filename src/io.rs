@@ -1,27 +1,420 @@
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String, vec::Vec};
+#[cfg(feature = "alloc")]
 use crate::RingBuffer;
 
+/// Evaluate `$call` to the stream of a [`BufStream`], [`BufReader`] or [`BufWriter`], recording
+/// it as a `tracing` event with the `tracing` feature.
+///
+/// The events have the target `circulate::io` and the fields `operation`, one of `refill`,
+/// `flush`, `bypass_read` or `bypass_write`, and `requested`, the number of bytes asked for.
+/// Successful calls are `TRACE` events with the number of bytes read or written in `actual`,
+/// and failed calls are `DEBUG` events with `failed` set. The stream's error type need not
+/// implement `Debug`, so the error itself is not recorded.
+#[cfg(feature = "alloc")]
+macro_rules! trace_io {
+    ($operation:literal, $requested:expr, $call:expr) => {{
+        #[cfg(feature = "tracing")]
+        let requested = $requested as u64;
+        let result = $call;
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(actual) => tracing::trace!(
+                target: "circulate::io",
+                operation = $operation,
+                requested,
+                actual = *actual as u64,
+            ),
+            Err(_) => tracing::debug!(target: "circulate::io", operation = $operation, requested, failed = true),
+        }
+        result
+    }};
+}
+
 #[cfg(not(feature = "no_std"))]
 pub mod std;
+#[cfg(not(feature = "no_std"))]
+pub use self::std::{ByRef, FromStd, FromStdUninit, ReadIntoUninit, ToStd};
+#[cfg(all(unix, not(feature = "no_std")))]
+pub use self::std::RawStream;
+pub mod os;
+pub mod compat;
+#[cfg(feature = "alloc")]
+mod buffered;
+#[cfg(feature = "alloc")]
+pub use buffered::{BufReader, BufWriter};
+#[cfg(feature = "alloc")]
+mod chars;
+#[cfg(feature = "alloc")]
+pub use chars::{CharReadError, Chars};
+#[cfg(feature = "alloc")]
+mod chunked;
+#[cfg(feature = "alloc")]
+mod config;
+#[cfg(feature = "alloc")]
+pub use config::BufConfig;
+#[cfg(feature = "alloc")]
+pub use chunked::{ChunkedError, ChunkedReader, ChunkedWriter};
+mod ext;
+pub use ext::{BudgetError, Budgeted, Chain, ReadExt, Take, WriteExt};
+mod fmt;
+pub use fmt::{fmt_adapter, FmtAdapter};
+#[cfg(feature = "alloc")]
+mod iovec_queue;
+#[cfg(feature = "alloc")]
+pub use iovec_queue::IoVecQueue;
+#[cfg(feature = "alloc")]
+mod lossy;
+#[cfg(feature = "alloc")]
+pub use lossy::LossyWriter;
+#[cfg(feature = "alloc")]
+mod middleware;
+#[cfg(feature = "alloc")]
+pub use middleware::{Middleware, MiddlewareBufStream, NullMiddleware};
+#[cfg(feature = "alloc")]
+mod mux;
+#[cfg(feature = "alloc")]
+pub use mux::Mux;
+mod newline;
+pub use newline::{NewlineNormalizer, NewlineReader};
+mod observer;
+pub use observer::{FlushReason, IoEvent};
+#[cfg(feature = "pod")]
+mod pod;
+#[cfg(feature = "pod")]
+pub use pod::PodError;
+#[cfg(all(feature = "pod", feature = "alloc"))]
+pub use pod::PodRef;
+#[cfg(feature = "alloc")]
+mod request;
+#[cfg(feature = "alloc")]
+pub use request::{RequestError, ResponseSpec};
+#[cfg(feature = "alloc")]
+mod ring_reader;
+#[cfg(feature = "alloc")]
+pub use ring_reader::{DrainReader, TailReader, WouldBlock};
+#[cfg(feature = "alloc")]
+mod snapshot;
+#[cfg(feature = "alloc")]
+pub use snapshot::LoadError;
+#[cfg(all(feature = "alloc", not(feature = "no_std")))]
+mod spillover;
+#[cfg(all(feature = "alloc", not(feature = "no_std")))]
+pub use spillover::{SpillStore, SpilloverBuffer};
+#[cfg(feature = "alloc")]
+mod transform;
+#[cfg(feature = "alloc")]
+pub use transform::{RleDecoder, Transform, TransformError, TransformReader, XorKeystream};
 
 // It would be good to use raw slices instead of raw pointer and length pairs.
 // Blocking: https://github.com/rust-lang/rust/issues/74265
 
+/// A source of bytes.
+///
+/// `Read` is object safe, so different readers with the same error type can be used as a
+/// `Box<dyn Read<Error = E>>`. Combinators that take the reader by value live in [`ReadExt`]
+/// instead, which is implemented for every sized reader.
 pub trait Read {
     type Error;
     /// Place the next bytes from the reader in to the `buffer` and returns the
     /// number of bytes written, and therefore initialized.
+    /// Bytes of `buffer` that were already initialized must not be de-initialized.
     fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error>;
     /// Read bytes in to the regions specified by the [`IoVecMut`] entries.
     /// Returns the number of bytes read in to the buffer, and therefore initialized.
+    ///
+    /// By default this is one [`Read::read`] in to the first non-empty buffer, as for
+    /// [`std::io::Read::read_vectored`](::std::io::Read::read_vectored). Reading again after a short read could block, or fail
+    /// and lose the bytes already read.
     fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        match buffers.iter_mut().find(|buffer| !buffer.is_empty()) {
+            Some(buffer) => self.read(buffer.as_maybe_uninit_slice()),
+            None => self.read(&mut []),
+        }
+    }
+    /// Returns if the reader already buffers its input, so that buffering it again would only
+    /// copy the bytes an extra time. Defaults to `false`.
+    ///
+    /// [`BufStream`] reads directly from a buffered reader while it has no input buffered itself.
+    /// ```rust
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{BufStream, FromStd, Read};
+    ///
+    /// let input: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+    /// let inner = BufStream::with_capacity(FromStd(std::io::Cursor::new(input.clone())), 64);
+    /// assert!(inner.is_buffered());
+    /// let mut outer = BufStream::new(inner);
+    /// let mut output = Vec::new();
+    /// let mut chunk = [MaybeUninit::uninit(); 100];
+    /// loop {
+    ///     let read = outer.read(&mut chunk).unwrap();
+    ///     if read == 0 {
+    ///         break;
+    ///     }
+    ///     output.extend(chunk[..read].iter().map(|byte| unsafe { byte.assume_init() }));
+    ///     // The bytes were not copied in to the outer input buffer.
+    ///     assert_eq!(outer.buffered_input(), (&[][..], &[][..]));
+    /// }
+    /// assert_eq!(output, input);
+    /// ```
+    fn is_buffered(&self) -> bool {
+        false
+    }
+    /// Read until `buffer` is full, retrying short reads.
+    ///
+    /// Fails with [`ReadExactError::UnexpectedEof`] if the reader returns no more bytes first,
+    /// telling how many were read, and with [`ReadExactError::Read`] if it fails. Either way the
+    /// bytes read are left at the start of `buffer`.
+    /// ```rust
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{Eof, Read, ReadExactError};
+    ///
+    /// let mut reader = &b"abcde"[..];
+    /// let mut buffer = [MaybeUninit::uninit(); 3];
+    /// reader.read_exact(&mut buffer).unwrap();
+    /// assert_eq!(buffer.map(|byte| unsafe { byte.assume_init() }), *b"abc");
+    /// let result = reader.read_exact(&mut buffer);
+    /// assert!(matches!(result, Err(ReadExactError::UnexpectedEof(Eof { needed: 3, got: 2 }))));
+    /// ```
+    fn read_exact(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<(), ReadExactError<Self::Error>> {
+        let mut got = 0;
+        while got < buffer.len() {
+            match self.read(&mut buffer[got..]).map_err(ReadExactError::Read)? {
+                0 => return Err(ReadExactError::UnexpectedEof(Eof { needed: buffer.len(), got })),
+                read => got += read,
+            }
+        }
+        Ok(())
+    }
+    /// Read all bytes until the reader returns no more, appending them to `buffer`.
+    /// Returns the number of bytes read.
+    ///
+    /// Reading stops early if `buffer` is full and has reached its maximum capacity.
+    #[cfg(feature = "alloc")]
+    fn read_to_end(&mut self, buffer: &mut RingBuffer<u8>) -> Result<usize, Self::Error> {
         let mut read = 0;
-        for buffer in buffers {
-            read += self.read(buffer.as_maybe_uninit_slice())?;
+        loop {
+            match buffer.fill_from(self)? {
+                0 => return Ok(read),
+                count => read += count,
+            }
         }
+    }
+    /// Read all bytes until the reader returns no more, appending them to `buffer`, as
+    /// [`Read::read_to_end`] does, but call `should_stop` after each read and give up with
+    /// [`CancelError::Cancelled`] once it returns `true`. Returns the number of bytes read.
+    ///
+    /// This lets a caller bound how long reading from a fast reader takes, such as by closing
+    /// over a deadline. The bytes read before cancelling remain in `buffer`, so calling again
+    /// resumes reading.
+    /// ```rust
+    /// use core::{convert::Infallible, mem::MaybeUninit};
+    /// use circulate::{CancelError, Read, RingBuffer};
+    ///
+    /// /// Reads the next scripted chunk each time.
+    /// struct Scripted(std::vec::IntoIter<&'static [u8]>);
+    /// impl Read for Scripted {
+    ///     type Error = Infallible;
+    ///     fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Infallible> {
+    ///         let chunk = self.0.next().unwrap_or_default();
+    ///         for (slot, byte) in buffer.iter_mut().zip(chunk) {
+    ///             *slot = MaybeUninit::new(*byte);
+    ///         }
+    ///         Ok(chunk.len())
+    ///     }
+    /// }
+    ///
+    /// let mut reader = Scripted(vec![&b"ab"[..], b"cde", b"f", b"ghij"].into_iter());
+    /// let mut buffer = RingBuffer::with_capacity(64);
+    /// // Stop after every second read.
+    /// let mut reads = 0;
+    /// let mut should_stop = || { reads += 1; reads % 2 == 0 };
+    /// assert_eq!(reader.read_to_end_until(&mut buffer, &mut should_stop), Err(CancelError::Cancelled { bytes_so_far: 5 }));
+    /// assert_eq!(reader.read_to_end_until(&mut buffer, &mut should_stop), Err(CancelError::Cancelled { bytes_so_far: 5 }));
+    /// assert_eq!(reader.read_to_end_until(&mut buffer, &mut should_stop), Ok(0));
+    /// assert!(buffer.iter().eq(b"abcdefghij"));
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn read_to_end_until<F: FnMut() -> bool>(&mut self, buffer: &mut RingBuffer<u8>, mut should_stop: F) -> Result<usize, CancelError<Self::Error>>
+    where
+        Self: Sized,
+    {
+        let mut read = 0;
+        loop {
+            match buffer.fill_from(self).map_err(CancelError::Read)? {
+                0 => return Ok(read),
+                count => read += count,
+            }
+            if should_stop() {
+                return Err(CancelError::Cancelled { bytes_so_far: read });
+            }
+        }
+    }
+    /// Read all bytes until the reader returns no more, appending them to `string`.
+    /// Returns the number of bytes read.
+    ///
+    /// If the bytes are not valid UTF-8 then nothing is appended and they are returned in the error.
+    /// ```rust
+    /// use circulate::{Read, ReadToStringError};
+    /// let mut string = String::from("crème ");
+    /// assert_eq!((&b"br\xc3\xbbl\xc3\xa9e"[..]).read_to_string(&mut string).unwrap(), 8);
+    /// assert_eq!(string, "crème brûlée");
+    ///
+    /// let result = (&b"\xff\xfe"[..]).read_to_string(&mut string);
+    /// assert!(matches!(result, Err(ReadToStringError::InvalidUtf8(bytes)) if bytes == [0xff, 0xfe]));
+    /// assert_eq!((&b""[..]).read_to_string(&mut string).unwrap(), 0);
+    /// assert_eq!(string, "crème brûlée");
+    /// ```
+    #[cfg(feature = "alloc")]
+    fn read_to_string(&mut self, string: &mut String) -> Result<usize, ReadToStringError<Self::Error>> {
+        let mut buffer = RingBuffer::new();
+        let read = self.read_to_end(&mut buffer).map_err(ReadToStringError::Read)?;
+        let mut bytes = Vec::with_capacity(read);
+        let (lhs, rhs) = buffer.as_mut_slices();
+        bytes.extend_from_slice(lhs);
+        bytes.extend_from_slice(rhs);
+        let text = String::from_utf8(bytes).map_err(|e| ReadToStringError::InvalidUtf8(e.into_bytes()))?;
+        string.push_str(&text);
         Ok(read)
     }
 }
 
+#[cfg(feature = "alloc")]
+macro_rules! forward_boxed_read {
+    ($($bounds:tt)*) => {
+        impl<E> Read for Box<dyn Read<Error = E> $($bounds)*> {
+            type Error = E;
+            #[inline]
+            fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+                (**self).read(buffer)
+            }
+            #[inline]
+            fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+                (**self).read_vectored(buffers)
+            }
+            #[inline]
+            fn is_buffered(&self) -> bool {
+                (**self).is_buffered()
+            }
+            #[inline]
+            fn read_exact(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<(), ReadExactError<Self::Error>> {
+                (**self).read_exact(buffer)
+            }
+            #[inline]
+            fn read_to_end(&mut self, buffer: &mut RingBuffer<u8>) -> Result<usize, Self::Error> {
+                (**self).read_to_end(buffer)
+            }
+            #[inline]
+            fn read_to_string(&mut self, string: &mut String) -> Result<usize, ReadToStringError<Self::Error>> {
+                (**self).read_to_string(string)
+            }
+        }
+    };
+}
+#[cfg(feature = "alloc")]
+forward_boxed_read!(+ '_);
+#[cfg(feature = "alloc")]
+forward_boxed_read!(+ Send + '_);
+
+/// The error returned by [`Read::read_to_string`].
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub enum ReadToStringError<E> {
+    /// The reader failed.
+    Read(E),
+    /// The bytes read were not valid UTF-8.
+    InvalidUtf8(Vec<u8>),
+}
+#[cfg(feature = "alloc")]
+impl<E: core::fmt::Display> core::fmt::Display for ReadToStringError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Read(error) => error.fmt(f),
+            Self::InvalidUtf8(_) => f.write_str("stream did not contain valid UTF-8"),
+        }
+    }
+}
+#[cfg(feature = "alloc")]
+impl<E: core::error::Error> core::error::Error for ReadToStringError<E> {}
+
+/// The error returned by operations that give up when asked to stop, such as
+/// [`Read::read_to_end_until`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelError<E> {
+    /// The operation was asked to stop before it finished, after transferring `bytes_so_far`
+    /// bytes. Any bytes buffered until then are kept, so the operation can be resumed.
+    Cancelled { bytes_so_far: usize },
+    /// The reader failed.
+    Read(E),
+}
+impl<E: core::fmt::Display> core::fmt::Display for CancelError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Cancelled { bytes_so_far } => write!(f, "cancelled after {bytes_so_far} bytes"),
+            Self::Read(error) => error.fmt(f),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for CancelError<E> {}
+
+/// How far a read got before the stream ended too early, reported the same way by every read
+/// that needs a number of bytes, such as [`BufStream::fill_at_least`].
+///
+/// The `got` bytes that were read are kept buffered rather than dropped, so nothing is lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Eof {
+    /// The number of bytes that were needed. Where the length is not known up front, such as a
+    /// response ending at a terminator, this is one more than `got`.
+    pub needed: usize,
+    /// The number of bytes obtained before the stream ended.
+    pub got: usize,
+}
+impl core::fmt::Display for Eof {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "stream ended after {} of {} bytes", self.got, self.needed)
+    }
+}
+impl core::error::Error for Eof {}
+
+/// The error returned by [`Read::read_exact`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadExactError<E> {
+    /// The reader ended before the buffer was full.
+    UnexpectedEof(Eof),
+    /// The reader failed.
+    Read(E),
+}
+impl<E: core::fmt::Display> core::fmt::Display for ReadExactError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedEof(eof) => eof.fmt(f),
+            Self::Read(error) => error.fmt(f),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for ReadExactError<E> {}
+
+/// The error returned by operations that write all of some bytes, such as
+/// [`WriteExt::write_all_vectored`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteAllError<E> {
+    /// The writer stopped accepting bytes after `bytes_so_far` bytes were written.
+    WriteZero { bytes_so_far: usize },
+    /// The writer failed.
+    Write(E),
+}
+impl<E: core::fmt::Display> core::fmt::Display for WriteAllError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::WriteZero { bytes_so_far } => write!(f, "writer stopped accepting bytes after {bytes_so_far} bytes"),
+            Self::Write(error) => error.fmt(f),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for WriteAllError<E> {}
+
 pub trait Write {
     type Error;
     /// Write `slice` to this writer.
@@ -29,115 +422,2445 @@ pub trait Write {
     fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error>;
     /// Ensure written bytes are visible to other readers of the resource.
     fn flush(&mut self) -> Result<(), Self::Error>;
+    /// Write the bytes of the [`IoVec`] entries in order, as if they were one slice.
+    /// Returns the number of bytes that were written.
+    ///
+    /// By default each buffer is written in turn until one is written short, or fails after some
+    /// bytes were written, which are then reported rather than the error. Writers that can write
+    /// several buffers at once should override this.
+    fn write_vectored(&mut self, buffers: &[IoVec]) -> Result<usize, Self::Error> {
+        let mut written = 0;
+        for buffer in buffers {
+            // Safety: The buffers of a write hold the bytes to write, so are initialized.
+            let slice = unsafe { &*(buffer.as_maybe_uninit_slice() as *const [MaybeUninit<u8>] as *const [u8]) };
+            let bytes = match self.write(slice) {
+                Ok(bytes) => bytes,
+                Err(error) if written == 0 => return Err(error),
+                Err(_) => break,
+            };
+            written += bytes;
+            if bytes < slice.len() {
+                break;
+            }
+        }
+        Ok(written)
+    }
+    /// Write up to `count` copies of `byte` to this writer.
+    /// Returns the number of bytes that were written.
+    ///
+    /// By default a small chunk of the byte is written. Buffering writers fill their buffer
+    /// directly instead. See [`WriteExt::write_zeroes`].
+    fn write_repeated(&mut self, byte: u8, count: usize) -> Result<usize, Self::Error> {
+        let chunk = [byte; 512];
+        self.write(&chunk[..count.min(chunk.len())])
+    }
+}
+
+#[cfg(feature = "alloc")]
+macro_rules! forward_boxed_write {
+    ($($bounds:tt)*) => {
+        impl<E> Write for Box<dyn Write<Error = E> $($bounds)*> {
+            type Error = E;
+            #[inline]
+            fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+                (**self).write(slice)
+            }
+            #[inline]
+            fn flush(&mut self) -> Result<(), Self::Error> {
+                (**self).flush()
+            }
+            #[inline]
+            fn write_vectored(&mut self, buffers: &[IoVec]) -> Result<usize, Self::Error> {
+                (**self).write_vectored(buffers)
+            }
+            #[inline]
+            fn write_repeated(&mut self, byte: u8, count: usize) -> Result<usize, Self::Error> {
+                (**self).write_repeated(byte, count)
+            }
+        }
+    };
+}
+#[cfg(feature = "alloc")]
+forward_boxed_write!(+ '_);
+#[cfg(feature = "alloc")]
+forward_boxed_write!(+ Send + '_);
+
+impl<R: Read + ?Sized> Read for &mut R {
+    type Error = R::Error;
+    #[inline]
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        (**self).read(buffer)
+    }
+    #[inline]
+    fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        (**self).read_vectored(buffers)
+    }
+    #[inline]
+    fn is_buffered(&self) -> bool {
+        (**self).is_buffered()
+    }
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn read_to_end(&mut self, buffer: &mut RingBuffer<u8>) -> Result<usize, Self::Error> {
+        (**self).read_to_end(buffer)
+    }
+}
+impl<W: Write + ?Sized> Write for &mut W {
+    type Error = W::Error;
+    #[inline]
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        (**self).write(slice)
+    }
+    #[inline]
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        (**self).flush()
+    }
+    #[inline]
+    fn write_vectored(&mut self, buffers: &[IoVec]) -> Result<usize, Self::Error> {
+        (**self).write_vectored(buffers)
+    }
+    #[inline]
+    fn write_repeated(&mut self, byte: u8, count: usize) -> Result<usize, Self::Error> {
+        (**self).write_repeated(byte, count)
+    }
+}
+
+/// Reads the bytes of the slice, advancing the slice past the bytes read.
+/// ```rust
+/// use circulate::Read;
+/// let mut reader = &b"abc"[..];
+/// let mut text = String::new();
+/// reader.read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "abc");
+/// assert!(reader.is_empty());
+/// ```
+impl Read for &[u8] {
+    type Error = core::convert::Infallible;
+    #[inline]
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        self.read_vectored(&mut [IoVecMut::maybe_uninit(buffer)])
+    }
+    fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        let read = IoVecCursor::new(buffers).write(self);
+        *self = &self[read..];
+        Ok(read)
+    }
+}
+/// Writes in to the start of the slice, advancing the slice past the bytes written. Writes are
+/// short once the slice is full.
+/// ```rust
+/// use circulate::Write;
+/// let mut buffer = [0; 4];
+/// let mut writer = &mut buffer[..];
+/// assert_eq!(writer.write(b"ab").unwrap(), 2);
+/// assert_eq!(writer.write(b"cde").unwrap(), 2);
+/// assert_eq!(writer.write(b"f").unwrap(), 0);
+/// assert_eq!(&buffer, b"abcd");
+/// ```
+impl Write for &mut [u8] {
+    type Error = core::convert::Infallible;
+    #[inline]
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        let len = self.len().min(slice.len());
+        let (head, tail) = core::mem::take(self).split_at_mut(len);
+        head.copy_from_slice(&slice[..len]);
+        *self = tail;
+        Ok(len)
+    }
+    #[inline]
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+/// Appends to the vector, growing it as needed.
+#[cfg(feature = "alloc")]
+impl Write for Vec<u8> {
+    type Error = core::convert::Infallible;
+    #[inline]
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        self.extend_from_slice(slice);
+        Ok(slice.len())
+    }
+    #[inline]
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn write_vectored(&mut self, buffers: &[IoVec]) -> Result<usize, Self::Error> {
+        let len = buffers.iter().map(IoVec::len).sum();
+        self.reserve(len);
+        for buffer in buffers {
+            // Safety: The buffers of a write hold the bytes to write, so are initialized.
+            self.extend_from_slice(unsafe { &*(buffer.as_maybe_uninit_slice() as *const [MaybeUninit<u8>] as *const [u8]) });
+        }
+        Ok(len)
+    }
+}
+
+/// A writer that can be closed to signal the end of the written data.
+pub trait Shutdown: Write {
+    /// Shut down the writer.
+    /// Any later calls to [`Write::write`] must return an error.
+    fn shutdown(&mut self) -> Result<(), Self::Error>;
 }
 
 use core::{marker::PhantomData, mem::MaybeUninit};
 
-pub struct BufStream<S: Sized + Read + Write> {
+/// Buffers the input and output of a stream.
+///
+/// Reading only requires the stream to implement [`Read`], and writing only [`Write`], so
+/// receive-only or send-only streams can be buffered too.
+/// ```rust
+/// use core::{convert::Infallible, mem::MaybeUninit};
+/// use circulate::{BufStream, Read, Write};
+///
+/// /// A receive-only radio.
+/// struct Receiver(u8);
+/// impl Read for Receiver {
+///     type Error = Infallible;
+///     fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Infallible> {
+///         buffer.fill(MaybeUninit::new(self.0));
+///         Ok(buffer.len())
+///     }
+/// }
+/// let mut stream = BufStream::with_capacity(Receiver(7), 16);
+/// let mut buffer = [MaybeUninit::uninit(); 4];
+/// assert_eq!(stream.read(&mut buffer).unwrap(), 4);
+///
+/// /// A send-only radio.
+/// struct Transmitter(usize);
+/// impl Write for Transmitter {
+///     type Error = Infallible;
+///     fn write(&mut self, slice: &[u8]) -> Result<usize, Infallible> {
+///         self.0 += slice.len();
+///         Ok(slice.len())
+///     }
+///     fn flush(&mut self) -> Result<(), Infallible> { Ok(()) }
+/// }
+/// let mut stream = BufStream::new(Transmitter(0));
+/// stream.write(b"beacon").unwrap();
+/// stream.flush().unwrap();
+/// assert_eq!(stream.take_stream().0, 6);
+/// ```
+/// A receive-only stream cannot be written to.
+/// ```rust,compile_fail
+/// # use core::{convert::Infallible, mem::MaybeUninit};
+/// # use circulate::{BufStream, Read, Write};
+/// # struct Receiver;
+/// # impl Read for Receiver {
+/// #     type Error = Infallible;
+/// #     fn read(&mut self, _: &mut [MaybeUninit<u8>]) -> Result<usize, Infallible> { Ok(0) }
+/// # }
+/// BufStream::new(Receiver).write(b"beacon");
+/// ```
+///
+/// Written bytes are buffered until the output is flushed. If the stream accepts fewer bytes
+/// than offered, the rest stay buffered and are written by the next flush.
+///
+/// Dropping a `BufStream` makes a best effort to flush the output, ignoring any error, but not
+/// while the thread is panicking. Flush the output before dropping it to find out whether it
+/// was written, or get the stream back with [`BufStream::into_inner`], which flushes first.
+/// [`BufStream::take_stream`] gets the stream back without flushing. [`BufStream::assert_flushed`]
+/// catches output that has not been flushed in debug builds.
+#[cfg(feature = "alloc")]
+pub struct BufStream<S> {
     stream: S,
     input: RingBuffer<u8>,
     output: RingBuffer<u8>,
+    /// Set once the stream has been shut down, after which writes go straight to the stream so
+    /// that it reports the error.
+    shutdown: bool,
+    /// The number of unread bytes that have not been read again.
+    pushback: usize,
+    pushback_limit: usize,
+    /// The minimum free space ensured in the input buffer before reading from the stream.
+    read_ahead: usize,
+    /// The state of adapting the read ahead to the sizes of reads from the stream.
+    adaptive: Adaptive,
+    /// Set if vectored reads keep reading from the stream while it fills the input buffer.
+    vectored_refill: bool,
+    /// Owned chunks of output, written after the first `output_ahead` bytes of the output buffer
+    /// and before the rest.
+    chunks: IoVecQueue,
+    /// The number of bytes at the front of the output buffer written before the queued chunks.
+    output_ahead: usize,
+    /// The total number of bytes written to the stream.
+    flushed: u64,
+    /// The total number of bytes read from the stream.
+    received: u64,
+    observer: Option<Observer>,
+    flush_on_drop: DropFlush<S>,
+    /// When the oldest output still waiting to be written was buffered.
+    #[cfg(not(feature = "no_std"))]
+    oldest_unflushed: Option<::std::time::Instant>,
 }
-impl<S: Sized + Read + Write> BufStream<S> {
+#[cfg(feature = "alloc")]
+type Observer = Box<dyn FnMut(IoEvent) + Send + Sync>;
+
+#[cfg(feature = "alloc")]
+impl<S> BufStream<S> {
     pub fn new(stream: S) -> Self {
+        Self::with_buffers(stream, RingBuffer::new(), RingBuffer::new())
+    }
+    /// Buffer `stream` with `input` and `output`, with every other setting at its default.
+    fn with_buffers(stream: S, input: RingBuffer<u8>, output: RingBuffer<u8>) -> Self {
         Self {
             stream,
-            input: RingBuffer::with_capacity(0),
-            output: RingBuffer::with_capacity(0),
+            input,
+            output,
+            shutdown: false,
+            pushback: 0,
+            pushback_limit: usize::MAX,
+            read_ahead: 0,
+            adaptive: Adaptive::new(),
+            vectored_refill: false,
+            chunks: IoVecQueue::new(),
+            output_ahead: 0,
+            flushed: 0,
+            received: 0,
+            observer: None,
+            flush_on_drop: DropFlush::DISARMED,
+            #[cfg(not(feature = "no_std"))]
+            oldest_unflushed: None,
         }
     }
-    /// Create a new buffered stream with a capacity of at least `capcity` bytes
-    /// for both input and output buffers.
+    /// Create a new buffered stream whose input and output buffers each hold at least `capacity`
+    /// bytes before they grow, rounded up as by [`RingBuffer::capacity_for`].
+    ///
+    /// A single refill reads up to the usable size of the input buffer from the stream.
+    /// ```rust
+    /// use std::io::{self, Read};
+    /// use circulate::{BufStream, FromStd, RingBuffer};
+    ///
+    /// for capacity in [1, 5, 15, 16, 100] {
+    ///     let mut stream = BufStream::with_capacity(FromStd(io::repeat(0).take(1000)), capacity);
+    ///     let usable = RingBuffer::<u8>::capacity_for(capacity);
+    ///     assert!(usable >= capacity);
+    ///     assert_eq!(stream.input_capacity() - 1, usable);
+    ///     stream.buffer_read().unwrap();
+    ///     let (lhs, rhs) = stream.buffered_input();
+    ///     assert_eq!(lhs.len() + rhs.len(), usable);
+    /// }
+    /// ```
     pub fn with_capacity(stream: S, capacity: usize) -> Self {
-        Self {
-            stream,
-            input: RingBuffer::with_capacity(capacity),
-            output: RingBuffer::with_capacity(capacity),
+        Self::with_buffers(stream, RingBuffer::with_capacity(capacity), RingBuffer::with_capacity(capacity))
+    }
+    /// Create a new buffered stream with an input capacity of at least `input_capacity` bytes that
+    /// grows the input buffer before each read from the stream so there is space for at least
+    /// `read_ahead` bytes.
+    ///
+    /// Fetching more than is needed in larger chunks reduces the number of reads for high
+    /// throughput transports, at the cost of memory. With a `read_ahead` of 0 the input buffer only
+    /// grows once it is full, which suits low latency transports. The output buffer grows as
+    /// bytes are written.
+    /// ```rust
+    /// # use std::{cell::Cell, io, rc::Rc};
+    /// # struct Counted(io::Repeat, Rc<Cell<usize>>);
+    /// # impl io::Read for Counted {
+    /// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.1.set(self.1.get() + 1); io::Read::read(&mut self.0, buf) }
+    /// # }
+    /// # impl io::Write for Counted {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { Ok(buf.len()) }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{BufStream, FromStd, Read};
+    ///
+    /// let reads = |read_ahead| {
+    ///     let count = Rc::new(Cell::new(0));
+    ///     let mut stream = BufStream::with_read_ahead(FromStd(Counted(io::repeat(0), count.clone())), 0, read_ahead);
+    ///     let mut byte = [MaybeUninit::uninit()];
+    ///     for _ in 0..10_000 {
+    ///         stream.read(&mut byte).unwrap();
+    ///     }
+    ///     count.get()
+    /// };
+    /// assert_eq!(reads(0), 10_000);
+    /// assert!(reads(4096) <= 10);
+    /// ```
+    pub fn with_read_ahead(stream: S, input_capacity: usize, read_ahead: usize) -> Self {
+        let mut this = Self::with_buffers(stream, RingBuffer::with_capacity(input_capacity), RingBuffer::new());
+        this.read_ahead = read_ahead;
+        this
+    }
+
+    /// Set what happens when the input or output buffer fails to allocate while growing.
+    /// See [`RingBuffer::set_alloc_failure_mode`].
+    ///
+    /// With [`AllocFailure::Saturate`](crate::AllocFailure::Saturate) running out of memory is
+    /// reported as backpressure: writes accept only the bytes that fit in the output buffer,
+    /// returning 0 if it is full, and [`BufStream::try_fill`] returns [`FillStatus::Full`].
+    pub fn set_alloc_failure_mode(&mut self, mode: crate::AllocFailure) {
+        self.input.set_alloc_failure_mode(mode);
+        self.output.set_alloc_failure_mode(mode);
+    }
+
+    /// Get what happens when the input or output buffer fails to allocate while growing.
+    pub const fn alloc_failure_mode(&self) -> crate::AllocFailure {
+        self.input.alloc_failure_mode()
+    }
+
+    /// Set how the input and output buffers pick the capacity to grow to.
+    /// See [`RingBuffer::set_growth_strategy`].
+    /// ```rust
+    /// use circulate::{BufStream, GrowthStrategy, Write};
+    ///
+    /// struct Fixed(usize);
+    /// impl GrowthStrategy for Fixed {
+    ///     fn next_capacity(&self, current: usize, required: usize) -> usize {
+    ///         required.max(current + self.0)
+    ///     }
+    /// }
+    ///
+    /// let mut stream = BufStream::new(Vec::new());
+    /// stream.set_growth_strategy(&Fixed(1000));
+    /// stream.write(b"hello").unwrap();
+    /// // The buffers round the capacity picked up to a power of two.
+    /// assert_eq!(stream.output_capacity(), 1024);
+    /// ```
+    pub fn set_growth_strategy(&mut self, strategy: &'static dyn crate::GrowthStrategy) {
+        self.input.set_growth_strategy(strategy);
+        self.output.set_growth_strategy(strategy);
+    }
+
+    /// Set if input bytes are overwritten with zeros as soon as they are read or consumed, for
+    /// streams carrying sensitive data such as credentials.
+    /// See [`RingBuffer::set_scrub_consumed`].
+    pub fn set_scrub_consumed(&mut self, scrub: bool) {
+        self.input.set_scrub_consumed(scrub);
+    }
+    /// Get if input bytes are overwritten with zeros as soon as they are read or consumed.
+    pub const fn scrub_consumed(&self) -> bool {
+        self.input.scrub_consumed()
+    }
+
+    /// Set a callback that is told about each read from and write to the underlying stream, such
+    /// as to measure how well the stream is buffered.
+    ///
+    /// The callback is called once the stream has taken in the result of the operation, so if it
+    /// panics the panic propagates out of the operation but the stream stays usable.
+    /// ```rust
+    /// # #[cfg(feature = "test-util")] {
+    /// use std::sync::{Arc, Mutex};
+    /// use circulate::{test_util::MemoryStream, BufStream, FlushReason, IoEvent, Read, Write};
+    ///
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// // Accepts at most four bytes per write.
+    /// let mut inner = MemoryStream::new(b"hello".to_vec());
+    /// inner.set_max_write(4);
+    /// let mut stream = BufStream::with_capacity(inner, 15);
+    /// let log = events.clone();
+    /// stream.set_io_observer(move |event| log.lock().unwrap().push(event));
+    /// // The stream can still be shared between threads.
+    /// fn shareable<T: Send + Sync>(_: &T) {}
+    /// shareable(&stream);
+    ///
+    /// let mut text = String::new();
+    /// stream.read_to_string(&mut text).unwrap();
+    /// stream.write(b"0123456").unwrap();
+    /// stream.flush().unwrap();
+    /// stream.write_chunk(b"chunk".to_vec());
+    /// stream.flush().unwrap();
+    ///
+    /// let explicit = |attempted, written| IoEvent::Flush { attempted, written, reason: FlushReason::Explicit };
+    /// assert_eq!(*events.lock().unwrap(), [
+    ///     IoEvent::Refill { requested: 15, got: 5 },
+    ///     IoEvent::Refill { requested: 15, got: 0 },
+    ///     explicit(7, 4),
+    ///     explicit(3, 3),
+    ///     IoEvent::Bypass { bytes: 4 },
+    ///     IoEvent::Bypass { bytes: 1 },
+    /// ]);
+    /// # }
+    /// ```
+    pub fn set_io_observer<F: FnMut(IoEvent) + Send + Sync + 'static>(&mut self, observer: F) {
+        self.observer = Some(Box::new(observer));
+    }
+    /// Remove the callback set with [`BufStream::set_io_observer`].
+    pub fn clear_io_observer(&mut self) {
+        self.observer = None;
+    }
+    fn emit(&mut self, event: IoEvent) {
+        if let Some(observer) = &mut self.observer {
+            observer(event);
         }
     }
-    /// Read from the reader in to the internal buffer.
-    pub fn buffer_read(&mut self) -> Result<(), <S as Read>::Error> {
-        if self.input.full() {
-            self.input.reserve(1);
-        }
-        let (lhs, rhs) = self.input.spare_capacity_mut();
-        let parts = match (lhs.len(), rhs.len()) {
-            (0, 0) => 0,
-            (_, 0) => 1,
-            (_, _) => 2,
-        };
-        let count = self.stream.read_vectored(&mut [
-            lhs.into(),
-            rhs.into()
-        ][..parts])?;
-        // Safety: The count is no larger than the space available from `spare_capacity_mut`.
+
+    /// Set if [`Read::read_vectored`] keeps reading from the stream while each read fills the
+    /// input buffer and the buffers passed have space left, rather than reading at most once.
+    /// By default it reads at most once.
+    ///
+    /// If a later read fails after bytes were already read, the bytes are returned and the
+    /// error is left for the next read.
+    /// ```rust
+    /// # use std::{cell::Cell, io, rc::Rc};
+    /// # /// Returns the script of read lengths in order, counting each read.
+    /// # struct Scripted(Vec<usize>, Rc<Cell<usize>>);
+    /// # impl io::Read for Scripted {
+    /// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    /// #         self.1.set(self.1.get() + 1);
+    /// #         let len = if self.0.is_empty() { 0 } else { self.0.remove(0).min(buf.len()) };
+    /// #         buf[..len].fill(b'x');
+    /// #         Ok(len)
+    /// #     }
+    /// # }
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{BufStream, FromStd, IoVecMut, Read};
+    ///
+    /// let reads = Rc::new(Cell::new(0));
+    /// let mut stream = BufStream::with_capacity(FromStd(Scripted(vec![15, 15, 15, 6, 15, 15], reads.clone())), 15);
+    /// let mut buffers = [[MaybeUninit::uninit(); 4]; 16];
+    /// let mut iovecs = buffers.each_mut().map(|buffer| IoVecMut::maybe_uninit(buffer));
+    /// assert_eq!(stream.read_vectored(&mut iovecs).unwrap(), 15);
+    /// assert_eq!(reads.get(), 1);
+    ///
+    /// // Refills stop once a read does not fill the input buffer.
+    /// stream.set_vectored_refill(true);
+    /// assert_eq!(stream.read_vectored(&mut iovecs).unwrap(), 15 + 15 + 6);
+    /// assert_eq!(reads.get(), 4);
+    /// // Refills stop once the buffers are full.
+    /// assert_eq!(stream.read_vectored(&mut iovecs[..4]).unwrap(), 16);
+    /// assert_eq!(reads.get(), 6);
+    /// assert_eq!(stream.read_vectored(&mut iovecs[..1]).unwrap(), 4);
+    /// assert_eq!(reads.get(), 6);
+    /// ```
+    pub fn set_vectored_refill(&mut self, refill: bool) {
+        self.vectored_refill = refill;
+    }
+
+    /// Set if the input buffer adapts its size to the stream, by default between 1 KiB and 64 KiB.
+    /// See [`BufStream::set_adaptive_limits`].
+    ///
+    /// While enabled the stream reads in to as much space as the input buffer is sized for,
+    /// replacing the read ahead, and the size is reconsidered after each read:
+    /// - After two reads in a row that fill all of the free space, the size doubles up to the cap,
+    ///   so a fast stream is read in fewer, larger reads.
+    /// - Once an exponentially weighted average of the bytes per read has been under a quarter of
+    ///   the size for sixteen reads in a row, the size halves down to the floor and the input
+    ///   buffer shrinks, so a slow stream does not hold on to memory it does not use.
+    ///
+    /// Disabling it restores the previous read ahead but keeps the input buffer as it is.
+    /// ```rust
+    /// use std::io;
+    /// use circulate::{BufStream, FromStd};
+    ///
+    /// /// Returns at most `limit` bytes per read.
+    /// struct Trickle { limit: usize }
+    /// impl io::Read for Trickle {
+    ///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    ///         let len = buf.len().min(self.limit);
+    ///         buf[..len].fill(0);
+    ///         Ok(len)
+    ///     }
+    /// }
+    ///
+    /// // A stream that fills every read grows the buffer to the cap.
+    /// let mut stream = BufStream::new(FromStd(Trickle { limit: usize::MAX }));
+    /// stream.set_adaptive_buffering(true);
+    /// let mut sizes = Vec::new();
+    /// for _ in 0..16 {
+    ///     stream.buffer_read().unwrap();
+    ///     stream.consume(stream.buffered_input().0.len());
+    ///     sizes.push(stream.input_capacity());
+    /// }
+    /// assert_eq!(sizes[0], 1024);
+    /// assert!(sizes.windows(2).all(|pair| pair[0] <= pair[1]));
+    /// assert_eq!(stream.input_capacity(), 64 * 1024);
+    ///
+    /// // When it slows to a trickle the buffer shrinks back to the floor.
+    /// stream.get_mut().0.limit = 10;
+    /// for _ in 0..200 {
+    ///     stream.buffer_read().unwrap();
+    ///     stream.consume(10);
+    /// }
+    /// assert_eq!(stream.input_capacity(), 1024);
+    ///
+    /// // Small reads with occasional bursts settle in between, whatever size they start from.
+    /// for start in [256, 16 * 1024] {
+    ///     let mut stream = BufStream::with_capacity(FromStd(Trickle { limit: 0 }), start);
+    ///     stream.set_adaptive_limits(256, 16 * 1024);
+    ///     stream.set_adaptive_buffering(true);
+    ///     for round in 0..400 {
+    ///         stream.get_mut().0.limit = if round % 4 == 0 { 4096 } else { 512 };
+    ///         stream.buffer_read().unwrap();
+    ///         stream.consume(stream.buffered_input().0.len());
+    ///     }
+    ///     assert!((1024..=4096).contains(&stream.input_capacity()), "started at {start}");
+    /// }
+    /// ```
+    pub fn set_adaptive_buffering(&mut self, adaptive: bool) {
+        if adaptive == self.adaptive.enabled {
+            return;
+        }
+        self.adaptive.enabled = adaptive;
+        if adaptive {
+            self.adaptive.read_ahead = self.read_ahead;
+            self.read_ahead = self.adaptive.clamp(self.input.capacity());
+        } else {
+            self.read_ahead = self.adaptive.read_ahead;
+        }
+    }
+    /// Set the smallest and largest sizes of the input buffer in bytes for adaptive buffering,
+    /// each rounded up to a power of two. See [`BufStream::set_adaptive_buffering`].
+    /// # Panics
+    /// Panics if `floor` is 0 or greater than `cap`.
+    #[track_caller]
+    pub fn set_adaptive_limits(&mut self, floor: usize, cap: usize) {
+        assert!(floor != 0 && floor <= cap, "adaptive buffering limits of {floor} to {cap} are invalid");
+        self.adaptive.floor = floor.max(2).next_power_of_two();
+        self.adaptive.cap = cap.max(2).next_power_of_two();
+        if self.adaptive.enabled {
+            self.read_ahead = self.adaptive.clamp(self.read_ahead + 1);
+            self.input.shrink_to(self.read_ahead + 1);
+        }
+    }
+    /// Get the capacity of the input buffer.
+    pub fn input_capacity(&self) -> usize {
+        self.input.capacity()
+    }
+    /// Get the capacity of the output buffer.
+    pub fn output_capacity(&self) -> usize {
+        self.output.capacity()
+    }
+    /// Reconsider the size of the input buffer after reading `got` bytes in to `requested` bytes
+    /// of free space. See [`BufStream::set_adaptive_buffering`].
+    fn adapt(&mut self, requested: usize, got: usize) {
+        let state = &mut self.adaptive;
+        if got == 0 {
+            // The end of the stream says nothing about its speed.
+            return;
+        }
+        // Note: One slot of the input buffer always remains vacant.
+        let size = self.read_ahead + 1;
+        state.average = state.average - state.average / 8 + got;
+        state.full = if got == requested { state.full + 1 } else { 0 };
+        state.small = if state.average / 8 < size / 4 { state.small + 1 } else { 0 };
+        if state.full >= 2 {
+            state.full = 0;
+            self.read_ahead = state.clamp(size * 2);
+        } else if state.small >= 16 {
+            state.small = 0;
+            self.read_ahead = state.clamp(size / 2);
+            self.input.shrink_to(self.read_ahead + 1);
+        }
+    }
+
+    /// Set the maximum number of bytes that may be pushed back with [`BufStream::unread`] before
+    /// they are read again. By default there is no limit.
+    pub fn set_pushback_limit(&mut self, limit: usize) {
+        self.pushback_limit = limit;
+    }
+    /// Get the maximum number of bytes that may be pushed back with [`BufStream::unread`].
+    pub const fn pushback_limit(&self) -> usize {
+        self.pushback_limit
+    }
+
+    /// Wrap or replace the underlying stream, keeping the buffered input and output, such as to
+    /// upgrade a connection to TLS after a `STARTTLS` exchange.
+    ///
+    /// Input that was already buffered is returned before anything read from the new stream.
+    /// Output that has not been flushed is written through the new stream, so flush first if it
+    /// must reach the old stream. It is flushed when the new stream is dropped, as it was for the
+    /// old one.
+    /// ```rust
+    /// # use std::{cell::RefCell, io, rc::Rc};
+    /// # struct Socket(io::Cursor<&'static [u8]>, Rc<RefCell<Vec<u8>>>);
+    /// # impl io::Read for Socket {
+    /// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { io::Read::read(&mut self.0, buf) }
+    /// # }
+    /// # impl io::Write for Socket {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.1.borrow_mut().extend_from_slice(buf); Ok(buf.len()) }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// # /// Stands in for a TLS session, decrypting to lowercase and encrypting to uppercase.
+    /// # struct Tls(Socket);
+    /// # impl io::Read for Tls {
+    /// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    /// #         let len = io::Read::read(&mut self.0, buf)?;
+    /// #         buf[..len].make_ascii_lowercase();
+    /// #         Ok(len)
+    /// #     }
+    /// # }
+    /// # impl io::Write for Tls {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { io::Write::write(&mut self.0, &buf.to_ascii_uppercase()) }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// use circulate::{BufStream, FromStd, Read, Write};
+    ///
+    /// let sent = Rc::new(RefCell::new(Vec::new()));
+    /// let socket = Socket(io::Cursor::new(b"220 ready\r\nleftOVER"), sent.clone());
+    /// let mut stream = BufStream::with_capacity(FromStd(socket), 15);
+    /// stream.buffer_read().unwrap();
+    /// stream.consume(b"220 ready\r\n".len());
+    /// stream.write(b"starttls\r\n").unwrap();
+    ///
+    /// let mut stream = stream.map_stream(|FromStd(socket)| FromStd(Tls(socket)));
+    /// stream.write(b"ehlo").unwrap();
+    /// stream.flush().unwrap();
+    /// assert_eq!(&*sent.borrow(), b"STARTTLS\r\nEHLO");
+    /// let mut text = String::new();
+    /// stream.read_to_string(&mut text).unwrap();
+    /// assert_eq!(text, "leftover");
+    /// ```
+    pub fn map_stream<S2: Write, F: FnOnce(S) -> S2>(self, f: F) -> BufStream<S2> {
+        let this = core::mem::ManuallyDrop::new(self);
+        // Safety: Only the fields that are `Copy` are used afterwards.
+        let (stream, input, output, chunks, observer) = unsafe { Self::take_fields(&this) };
+        let mut mapped = BufStream::with_buffers(f(stream), input, output);
+        mapped.shutdown = this.shutdown;
+        mapped.pushback = this.pushback;
+        mapped.pushback_limit = this.pushback_limit;
+        mapped.read_ahead = this.read_ahead;
+        mapped.adaptive = this.adaptive;
+        mapped.vectored_refill = this.vectored_refill;
+        mapped.chunks = chunks;
+        mapped.output_ahead = this.output_ahead;
+        mapped.flushed = this.flushed;
+        mapped.received = this.received;
+        mapped.observer = observer;
+        if this.flush_on_drop.is_armed() {
+            mapped.flush_on_drop = DropFlush::ARMED;
+        }
+        #[cfg(not(feature = "no_std"))]
+        {
+            mapped.oldest_unflushed = this.oldest_unflushed;
+        }
+        mapped
+    }
+    /// Move the stream and the buffers out of a stream that will not be dropped, so that they
+    /// are dropped without flushing the output.
+    /// # Safety
+    /// Only the fields of `this` that are `Copy` may be used afterwards.
+    #[allow(clippy::type_complexity)]
+    pub(crate) unsafe fn take_fields(this: &core::mem::ManuallyDrop<Self>) -> (S, RingBuffer<u8>, RingBuffer<u8>, IoVecQueue, Option<Observer>) {
+        // Safety: The caller ensures that each field is moved out exactly once.
         unsafe {
-            self.input.set_write_cursor(count)
+            (
+                core::ptr::read(&this.stream),
+                core::ptr::read(&this.input),
+                core::ptr::read(&this.output),
+                core::ptr::read(&this.chunks),
+                core::ptr::read(&this.observer),
+            )
+        }
+    }
+    /// Get the underlying stream, discarding any buffered input and output without flushing it,
+    /// such as when abandoning a connection.
+    /// ```rust
+    /// # #[cfg(feature = "test-util")] {
+    /// use std::rc::Rc;
+    /// use circulate::{test_util::MemoryStream, BufStream, RingBuffer, Write};
+    ///
+    /// let mut stream = BufStream::new(MemoryStream::default());
+    /// stream.write(b"never sent").unwrap();
+    /// stream.write_chunk(b"also never sent".to_vec());
+    /// assert!(stream.take_stream().output().is_empty());
+    ///
+    /// // The buffers are dropped as any other `RingBuffer` is, dropping their items.
+    /// let item = Rc::new(String::from("item"));
+    /// let mut buffer = RingBuffer::new();
+    /// buffer.push(item.clone()).unwrap();
+    /// drop(buffer);
+    /// assert_eq!(Rc::strong_count(&item), 1);
+    /// # }
+    /// ```
+    pub fn take_stream(self) -> S {
+        let this = core::mem::ManuallyDrop::new(self);
+        // Safety: `this` is not used afterwards.
+        unsafe { Self::take_fields(&this) }.0
+    }
+    /// Get the underlying stream along with the buffered input and the output waiting to be
+    /// written, without flushing, such as to hand a connection to a TLS library after a plaintext
+    /// handshake without losing the bytes already read ahead.
+    ///
+    /// Queued chunks are copied in to the returned output buffer amongst the buffered output, so
+    /// that every unwritten byte is returned in order.
+    /// ```rust
+    /// use std::io::Cursor;
+    /// use circulate::{BufStream, FromStd, Write};
+    ///
+    /// let reply = b"HTTP/1.1 200 OK\r\n\r\n\x16\x03\x01";
+    /// let mut stream = BufStream::with_capacity(FromStd(Cursor::new(reply.to_vec())), 64);
+    /// let mut head = Vec::new();
+    /// while !head.ends_with(b"\r\n\r\n") {
+    ///     stream.read_until(b'\n', &mut head).unwrap();
+    /// }
+    /// stream.write(b"a").unwrap();
+    /// stream.write_chunk(b"b".to_vec());
+    /// stream.write(b"c").unwrap();
+    ///
+    /// let (inner, input, output) = stream.into_parts();
+    /// assert_eq!(inner.0.position(), reply.len() as u64);
+    /// // The start of the TLS handshake was read ahead with the reply.
+    /// assert!(input.iter().eq(b"\x16\x03\x01"));
+    /// assert!(output.iter().eq(b"abc"));
+    /// ```
+    pub fn into_parts(self) -> (S, RingBuffer<u8>, RingBuffer<u8>) {
+        let this = core::mem::ManuallyDrop::new(self);
+        // Safety: Only the fields that are `Copy` are used afterwards.
+        let (stream, input, buffered, chunks, _) = unsafe { Self::take_fields(&this) };
+        let output = if chunks.is_empty() {
+            buffered
+        } else {
+            let mut output = RingBuffer::with_capacity(chunks.len() + buffered.len());
+            let ([lhs, rhs], [after_lhs, after_rhs]) = split_slices(&buffered, this.output_ahead);
+            for slice in [lhs, rhs].into_iter().chain(chunks.unwritten()).chain([after_lhs, after_rhs]) {
+                // Note: The buffer is unbounded and was allocated to hold every byte.
+                let _ = output.extend_from_slice(slice);
+            }
+            output
         };
-        // TODO: a smarter growth strategy
-        if self.input.full() {
-            self.input.reserve(1);
+        (stream, input, output)
+    }
+    /// Get a reference to the underlying stream.
+    pub const fn get_ref(&self) -> &S {
+        &self.stream
+    }
+    /// Get a mutable reference to the underlying stream.
+    ///
+    /// Reading from or writing to it directly bypasses the buffered input and output.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+    /// Replace the underlying stream with `stream`, keeping the buffered input and output, and
+    /// return the old stream. See [`BufStream::map_stream`].
+    /// ```rust
+    /// use std::io::Cursor;
+    /// use circulate::{BufStream, FromStd, Read};
+    ///
+    /// let mut stream = BufStream::with_capacity(FromStd(Cursor::new(b"first ".to_vec())), 3);
+    /// stream.buffer_read().unwrap();
+    /// let old = stream.replace_stream(FromStd(Cursor::new(b"second".to_vec())));
+    /// assert_eq!(old.0.position(), 3);
+    /// let mut text = String::new();
+    /// stream.read_to_string(&mut text).unwrap();
+    /// assert_eq!(text, "firsecond");
+    /// ```
+    pub fn replace_stream(&mut self, stream: S) -> S {
+        core::mem::replace(&mut self.stream, stream)
+    }
+
+    /// Push `bytes` back on to the front of the buffered input, so that they are returned by the
+    /// next reads before any other input.
+    ///
+    /// Unread bytes are ordinary buffered input: a read returns them without reading from the
+    /// stream, and [`BufStream::buffer_read`] appends after them. The bytes need not be the ones
+    /// that were read. They are written to the space in front of the buffered input, and if there
+    /// is not enough the input buffer grows, keeping the buffered input.
+    ///
+    /// Fails without modifying the buffer if the bytes pushed back and not yet read again would
+    /// exceed the [pushback limit](BufStream::set_pushback_limit), or if the input buffer could
+    /// not grow.
+    /// ```rust
+    /// # #[cfg(feature = "test-util")] {
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{test_util::MemoryStream, BufStream, Read};
+    ///
+    /// let text = b"the quick brown fox jumps over the lazy dog";
+    /// // Stop at every offset in the input so the pushed back bytes straddle the wrap point.
+    /// for skip in 0..text.len() {
+    ///     let mut stream = BufStream::with_capacity(MemoryStream::with_max_read(text.to_vec(), 2), 8);
+    ///     stream.set_pushback_limit(4);
+    ///     let mut byte = [MaybeUninit::uninit()];
+    ///     for _ in 0..skip {
+    ///         assert_eq!(stream.read(&mut byte).unwrap(), 1);
+    ///     }
+    ///     let back = skip.min(4);
+    ///     stream.unread(&text[skip - back..skip]).unwrap();
+    ///     if back == 4 {
+    ///         assert!(stream.unread(b"!").is_err());
+    ///     }
+    ///
+    ///     let mut rest = Vec::new();
+    ///     while stream.read(&mut byte).unwrap() == 1 {
+    ///         rest.push(unsafe { byte[0].assume_init() });
+    ///     }
+    ///     assert_eq!(rest, text[skip - back..]);
+    /// }
+    /// # }
+    /// ```
+    pub fn unread(&mut self, bytes: &[u8]) -> Result<(), UnreadError> {
+        let pushback = self.pushback + bytes.len();
+        if pushback > self.pushback_limit {
+            return Err(UnreadError { limit: self.pushback_limit });
+        }
+        if self.input.reserve(bytes.len()).is_err() {
+            return Err(UnreadError { limit: self.pushback_limit });
+        }
+        let (lhs, rhs) = self.input.spare_capacity_front_mut();
+        // The bytes fill the end of the front spare capacity, directly before the read cursor.
+        let skip = lhs.len() + rhs.len() - bytes.len();
+        for (slot, byte) in lhs.iter_mut().chain(rhs).skip(skip).zip(bytes) {
+            slot.write(*byte);
         }
+        // Safety: The `bytes.len()` slots before the read cursor were just initialised.
+        unsafe { self.input.set_read_cursor_back(bytes.len()) };
+        self.pushback = pushback;
         Ok(())
     }
+    /// Take the buffered input without copying it, leaving an empty input buffer of the same
+    /// capacity and settings so that the stream can still be read from.
+    ///
+    /// This hands the input already read from the stream, but not yet parsed, to another owner,
+    /// such as once a protocol has been negotiated. [`BufStream::prepend_input`] hands it back.
+    /// ```rust
+    /// use circulate::{BufStream, FromStd, Read};
+    ///
+    /// let mut stream = BufStream::with_capacity(FromStd(std::io::Cursor::new(b"HELLO\nrest of the data".to_vec())), 15);
+    /// stream.set_max_input_capacity(16);
+    /// stream.buffer_read().unwrap();
+    /// stream.consume(b"HELLO\n".len());
+    ///
+    /// let taken = stream.take_buffered_input();
+    /// assert!(taken.iter().eq(b"rest of t"));
+    /// assert!(!stream.input_has_data());
+    /// assert_eq!(stream.input_capacity(), taken.capacity());
+    ///
+    /// // The stream carries on from where the taken input ends.
+    /// let mut rest = String::new();
+    /// stream.read_to_string(&mut rest).unwrap();
+    /// assert_eq!(rest, "he data");
+    /// ```
+    pub fn take_buffered_input(&mut self) -> RingBuffer<u8> {
+        let empty = self.input.empty_like();
+        self.pushback = 0;
+        core::mem::replace(&mut self.input, empty)
+    }
+    /// Place the bytes of `data` in front of the buffered input, so that they are read before it
+    /// and before anything more from the stream.
+    ///
+    /// This returns input taken with [`BufStream::take_buffered_input`], or bytes read ahead by a
+    /// library such as one performing a handshake. Unlike [`BufStream::unread`] the bytes are not
+    /// limited by the pushback limit.
+    ///
+    /// If no input is buffered `data` takes the place of the input buffer without copying,
+    /// keeping the settings of the input buffer such as its maximum capacity. Otherwise the bytes
+    /// are copied in front of the buffered input, growing the input buffer as needed. When the
+    /// input buffer has reached its maximum capacity, the bytes are kept in a new buffer that
+    /// holds more than the maximum until enough of them have been consumed, so nothing is lost.
+    /// ```rust
+    /// use circulate::{BufStream, FromStd, Read, RingBuffer};
+    ///
+    /// let mut stream = BufStream::with_capacity(FromStd(std::io::Cursor::new(b" from the stream".to_vec())), 8);
+    /// stream.set_max_input_capacity(8);
+    /// stream.buffer_read().unwrap();
+    ///
+    /// // More than fits in the input buffer, ahead of the input buffered already.
+    /// let mut over_read = RingBuffer::new();
+    /// over_read.extend_from_slice(b"handshake leftovers,").unwrap();
+    /// stream.prepend_input(over_read);
+    ///
+    /// let mut text = String::new();
+    /// stream.read_to_string(&mut text).unwrap();
+    /// assert_eq!(text, "handshake leftovers, from the stream");
+    /// ```
+    pub fn prepend_input(&mut self, data: RingBuffer<u8>) {
+        if data.is_empty() {
+            return;
+        }
+        if self.input.is_empty() {
+            let mut data = data;
+            data.adopt_settings(&self.input);
+            self.input = data;
+            self.pushback = 0;
+            return;
+        }
+        let (front, back) = data.as_slices();
+        if self.input.reserve(data.len()).is_ok() {
+            let (lhs, rhs) = self.input.spare_capacity_front_mut();
+            // The bytes fill the end of the front spare capacity, directly before the read cursor.
+            let skip = lhs.len() + rhs.len() - data.len();
+            for (slot, byte) in lhs.iter_mut().chain(rhs).skip(skip).zip(front.iter().chain(back)) {
+                slot.write(*byte);
+            }
+            // Safety: The `data.len()` slots before the read cursor were just initialised.
+            unsafe { self.input.set_read_cursor_back(data.len()) };
+            return;
+        }
+        let mut combined = RingBuffer::with_capacity(data.len() + self.input.len());
+        let (lhs, rhs) = self.input.as_slices();
+        for slice in [front, back, lhs, rhs] {
+            // Note: The buffer is unbounded and was allocated to hold every byte.
+            let _ = combined.extend_from_slice(slice);
+        }
+        combined.adopt_settings(&self.input);
+        self.input = combined;
+    }
+    /// Get the buffered input as two slices, in order, without reading from the stream.
+    pub fn buffered_input(&self) -> (&[u8], &[u8]) {
+        self.input.as_slices()
+    }
+    /// Get the number of bytes of buffered input, which are read before anything more is read from
+    /// the stream.
+    pub fn input_buffer_len(&self) -> usize {
+        self.input.len()
+    }
+    /// Get the number of bytes waiting to be written to the stream, including queued chunks, so
+    /// that flushing is only needed when this is not 0.
+    /// ```rust
+    /// use circulate::{BufStream, Write};
+    ///
+    /// let mut stream = BufStream::new(Vec::new());
+    /// assert_eq!(stream.output_buffer_len(), 0);
+    /// stream.write(b"head").unwrap();
+    /// stream.write_chunk(b"body".to_vec());
+    /// assert_eq!(stream.output_buffer_len(), 8);
+    /// stream.flush().unwrap();
+    /// assert_eq!(stream.output_buffer_len(), 0);
+    /// assert_eq!(stream.get_ref(), b"headbody");
+    /// ```
+    pub fn output_buffer_len(&self) -> usize {
+        self.output.len() + self.chunks.len()
+    }
+    /// Returns if any input is buffered, so that reading will not need to read from the stream.
+    pub fn input_has_data(&self) -> bool {
+        !self.input.is_empty()
+    }
+    /// Returns if no output is waiting to be written to the stream.
+    /// ```rust
+    /// # #[cfg(feature = "test-util")] {
+    /// use circulate::{test_util::MemoryStream, BufStream, Write};
+    ///
+    /// let mut stream = BufStream::new(MemoryStream::new(b"input".to_vec()));
+    /// assert!(stream.output_is_flushed());
+    /// stream.write(b"0123456789").unwrap();
+    /// assert!(!stream.output_is_flushed());
+    /// stream.flush().unwrap();
+    /// assert!(stream.output_is_flushed());
+    /// stream.assert_flushed();
+    ///
+    /// assert!(!stream.input_has_data());
+    /// stream.buffer_read().unwrap();
+    /// assert!(stream.input_has_data());
+    /// # }
+    /// ```
+    pub fn output_is_flushed(&self) -> bool {
+        self.output.is_empty() && self.chunks.is_empty()
+    }
+    /// Discard all output waiting to be written to the stream, including queued chunks, and
+    /// return the number of bytes discarded.
+    ///
+    /// Output that failed to flush is kept so that flushing can be retried, such as after
+    /// reconnecting with [`BufStream::replace_stream`]. If the stream has failed permanently,
+    /// discarding the output lets the stream be reused for new output instead.
+    /// ```rust
+    /// # use core::mem::MaybeUninit;
+    /// # use std::io;
+    /// use circulate::{BufStream, Read, Write};
+    ///
+    /// /// A connection that breaks after accepting `limit` bytes.
+    /// struct Connection { sent: Vec<u8>, limit: usize }
+    /// impl Read for Connection {
+    ///     type Error = io::Error;
+    ///     fn read(&mut self, _: &mut [MaybeUninit<u8>]) -> io::Result<usize> { Ok(0) }
+    /// }
+    /// impl Write for Connection {
+    ///     type Error = io::Error;
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         let len = buf.len().min(self.limit - self.sent.len());
+    ///         if len == 0 && !buf.is_empty() {
+    ///             return Err(io::ErrorKind::BrokenPipe.into());
+    ///         }
+    ///         self.sent.extend_from_slice(&buf[..len]);
+    ///         Ok(len)
+    ///     }
+    ///     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// }
+    ///
+    /// let mut stream = BufStream::new(Connection { sent: Vec::new(), limit: 4 });
+    /// stream.write(b"request").unwrap();
+    /// stream.write_chunk(b" body".to_vec());
+    /// assert_eq!(stream.flush().unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    /// assert_eq!(stream.flushed_bytes(), 4);
+    ///
+    /// // Retrying on a new connection sends the rest of the output.
+    /// let old = stream.replace_stream(Connection { sent: Vec::new(), limit: 64 });
+    /// assert_eq!(old.sent, b"requ");
+    /// stream.flush().unwrap();
+    /// assert_eq!(stream.flushed_bytes(), 12);
+    ///
+    /// // Or the output is dropped and the stream reused.
+    /// stream.write(b"lost").unwrap();
+    /// stream.replace_stream(Connection { sent: Vec::new(), limit: 0 });
+    /// assert!(stream.flush().is_err());
+    /// assert_eq!(stream.discard_output(), 4);
+    /// assert!(stream.output_is_flushed());
+    /// stream.replace_stream(Connection { sent: Vec::new(), limit: 64 });
+    /// stream.write(b"new").unwrap();
+    /// stream.flush().unwrap();
+    /// assert_eq!(stream.take_stream().sent, b"new");
+    /// ```
+    pub fn discard_output(&mut self) -> usize {
+        let discarded = self.output.len() + self.chunks.len();
+        self.output.clear();
+        self.chunks.advance(self.chunks.len());
+        self.output_ahead = 0;
+        self.output_drained();
+        discarded
+    }
+    /// Get the total number of bytes written to the stream, not counting output that is
+    /// buffered or was discarded.
+    pub const fn flushed_bytes(&self) -> u64 {
+        self.flushed
+    }
+    /// Get the total number of bytes read from the stream, including input that is still
+    /// buffered.
+    pub const fn received_bytes(&self) -> u64 {
+        self.received
+    }
+    /// Panic if any output is waiting to be written to the stream, when debug assertions are enabled.
+    #[track_caller]
+    pub fn assert_flushed(&self) {
+        debug_assert!(
+            self.output_is_flushed(),
+            "BufStream has {} bytes of output that have not been flushed",
+            self.output.len() + self.chunks.len(),
+        );
+    }
+    /// Note that output was written or discarded, forgetting its time once none is left.
+    ///
+    /// After a partial flush the rest of the output keeps the time of the oldest output, as the
+    /// times of later writes are not kept.
+    fn output_drained(&mut self) {
+        #[cfg(not(feature = "no_std"))]
+        if self.output_is_flushed() {
+            self.oldest_unflushed = None;
+        }
+    }
+    /// Discard the next `count` bytes of buffered input.
+    /// # Panics
+    /// Panics if fewer than `count` bytes are buffered.
+    /// ```rust,should_panic
+    /// # use circulate::{BufStream, FromStd};
+    /// let mut stream = BufStream::new(FromStd(std::io::Cursor::new(b"abc".to_vec())));
+    /// stream.buffer_read().unwrap();
+    /// stream.consume(4);
+    /// ```
+    #[track_caller]
+    pub fn consume(&mut self, count: usize) {
+        let buffered = self.input.len();
+        assert!(count <= buffered, "cannot consume {count} bytes when only {buffered} are buffered");
+        // Safety: At least `count` bytes are buffered.
+        unsafe { self.input.advance_read_unchecked(count) };
+        self.pushback = self.pushback.saturating_sub(count);
+    }
 
-    fn read_into(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, <S as Read>::Error> {
+    /// Write the buffered input to `dst` until it is all written or `dst` stops accepting bytes,
+    /// consuming the bytes that were written. Returns the number of bytes copied.
+    ///
+    /// Nothing is read from the stream, so alternating this with [`BufStream::buffer_read`] pumps
+    /// one stream in to another a buffer at a time. Copying in to another [`BufStream`] appends to
+    /// its output buffer, which stops accepting bytes at its
+    /// [maximum capacity](BufStream::set_max_output_capacity), so a slow destination holds back
+    /// reading from the source rather than buffering without bound.
+    ///
+    /// If `dst` fails after accepting some bytes the bytes copied are returned instead of the error.
+    /// ```rust
+    /// use std::io::Cursor;
+    /// use circulate::{BufStream, FromStd};
+    ///
+    /// let mut source = BufStream::with_capacity(FromStd(Cursor::new(vec![7u8; 100])), 32);
+    /// let mut sink = BufStream::new(FromStd(Cursor::new(Vec::new())));
+    /// sink.set_max_output_capacity(16);
+    ///
+    /// let mut turns = 0;
+    /// loop {
+    ///     if !source.input_has_data() {
+    ///         source.buffer_read().unwrap();
+    ///         if !source.input_has_data() {
+    ///             break;
+    ///         }
+    ///     }
+    ///     // At most 15 bytes fit in the output buffer of the sink.
+    ///     assert!(source.copy_buffered_to(&mut sink).unwrap() <= 15);
+    ///     sink.flush_nonblocking().unwrap();
+    ///     turns += 1;
+    /// }
+    /// assert_eq!((source.received_bytes(), sink.flushed_bytes()), (100, 100));
+    /// assert!(turns >= 100 / 15);
+    /// assert_eq!(sink.take_stream().0.into_inner(), [7; 100]);
+    /// ```
+    pub fn copy_buffered_to<W: Write + ?Sized>(&mut self, dst: &mut W) -> Result<usize, W::Error> {
+        let mut copied = 0;
+        while !self.input.is_empty() {
+            let (lhs, _) = self.input.as_slices();
+            match dst.write(lhs).map(|count| count.min(lhs.len())) {
+                Ok(0) => break,
+                Ok(count) => {
+                    self.consume(count);
+                    copied += count;
+                }
+                Err(_) if copied != 0 => break,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(copied)
+    }
+
+    /// Get the buffered output written before the queued chunks, and that written after them.
+    fn output_around_chunks(&self) -> ([&[u8]; 2], [&[u8]; 2]) {
+        split_slices(&self.output, if self.chunks.is_empty() { 0 } else { self.output_ahead })
+    }
+    /// Limit the output buffer to a capacity of at most `max` bytes, after which writes are
+    /// short. See [`RingBuffer::set_max_capacity`].
+    pub fn set_max_output_capacity(&mut self, max: usize) {
+        self.output.set_max_capacity(max);
+    }
+    /// Limit the input buffer to a capacity of at most `max` bytes, after which nothing more is
+    /// read from the stream until buffered input is consumed. See [`RingBuffer::set_max_capacity`].
+    pub fn set_max_input_capacity(&mut self, max: usize) {
+        self.input.set_max_capacity(max);
+    }
+    /// Get the number of bytes that can be written before a write is short, as the output buffer
+    /// has reached its maximum capacity. Unbounded output has no limit.
+    pub fn remaining_write_capacity(&self) -> usize {
+        if self.output.max_capacity().is_none() {
+            return usize::MAX;
+        }
+        self.output.reachable_capacity().saturating_sub(1) - self.output.len()
+    }
+    /// Returns if writing `len` bytes would be short, as the output buffer would reach its
+    /// maximum capacity.
+    pub fn write_would_block(&self, len: usize) -> bool {
+        len > self.remaining_write_capacity()
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S: Read> BufStream<S> {
+    /// Read from the reader in to the internal buffer.
+    ///
+    /// The input buffer first grows to make space for the read ahead, and for at least one byte.
+    /// The stream is never asked to read in to no space, so if the input buffer is full and
+    /// cannot grow nothing is read.
+    pub fn buffer_read(&mut self) -> Result<(), <S as Read>::Error> {
+        self.fill_input()?;
+        Ok(())
+    }
+    /// Read from the stream once in to the input buffer, after growing it for the read ahead.
+    fn fill_input(&mut self) -> Result<usize, <S as Read>::Error> {
+        if self.input.is_empty() {
+            // Start from the beginning so the stream reads in to a single contiguous region.
+            self.input.clear();
+        }
+        // Read ahead is best effort, failing to grow only limits how much is read.
+        let _ = self.input.reserve(self.read_ahead.max(1));
+        let requested = self.input.capacity().saturating_sub(1) - self.input.len();
+        let got = trace_io!("refill", requested, self.input.fill_from(&mut self.stream))?;
+        self.received += got as u64;
+        self.emit(IoEvent::Refill { requested, got });
+        if self.adaptive.enabled {
+            self.adapt(requested, got);
+        }
+        Ok(got)
+    }
+
+    /// Discard all buffered input and then read and discard input from the stream until it returns
+    /// no more, such as to skip an unwanted response body before reusing a connection.
+    /// Returns the number of bytes discarded.
+    ///
+    /// The input buffer is reused for each read, so it does not grow beyond the read ahead.
+    /// ```rust
+    /// # use std::io;
+    /// use circulate::{BufStream, FromStd};
+    ///
+    /// let mut stream = BufStream::with_capacity(FromStd(io::Cursor::new(vec![7; 1000])), 64);
+    /// assert_eq!(stream.drain_input_to_completion().unwrap(), 1000);
+    /// assert!(!stream.input_has_data());
+    /// assert_eq!(stream.drain_input_to_completion().unwrap(), 0);
+    ///
+    /// let mut stream = BufStream::with_capacity(FromStd(io::Cursor::new(vec![7; 1000])), 64);
+    /// stream.buffer_read().unwrap();
+    /// stream.consume(10);
+    /// assert_eq!(stream.drain_input_to_completion().unwrap(), 990);
+    ///
+    /// let mut stream = BufStream::new(FromStd(io::empty()));
+    /// assert_eq!(stream.drain_input_to_completion().unwrap(), 0);
+    /// ```
+    pub fn drain_input_to_completion(&mut self) -> Result<usize, <S as Read>::Error> {
+        let mut discarded = self.input.len();
+        self.consume(discarded);
+        loop {
+            match self.fill_input()? {
+                0 => return Ok(discarded),
+                count => discarded += count,
+            }
+            self.input.clear();
+        }
+    }
+
+    /// Read from the stream at most once, trying to buffer at least `count` bytes of input.
+    ///
+    /// Bytes that were read are never discarded, including when the stream fails, so the fill can
+    /// be resumed by calling this again with the same `count`. They can be inspected with
+    /// [`BufStream::buffered_input`].
+    /// ```rust
+    /// # use std::io;
+    /// # /// Replays a script of reads and errors.
+    /// # struct Scripted(Vec<Result<&'static [u8], io::ErrorKind>>);
+    /// # impl io::Read for Scripted {
+    /// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    /// #         if self.0.is_empty() { return Ok(0) }
+    /// #         match self.0.remove(0) {
+    /// #             Ok(chunk) => {
+    /// #                 let len = chunk.len().min(buf.len());
+    /// #                 buf[..len].copy_from_slice(&chunk[..len]);
+    /// #                 if len < chunk.len() { self.0.insert(0, Ok(&chunk[len..])) }
+    /// #                 Ok(len)
+    /// #             }
+    /// #             Err(kind) => Err(kind.into()),
+    /// #         }
+    /// #     }
+    /// # }
+    /// # impl io::Write for Scripted {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { Ok(buf.len()) }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// use io::ErrorKind::{ConnectionReset, Interrupted};
+    /// use circulate::{BufStream, Eof, FillStatus, FromStd};
+    ///
+    /// let mut stream = BufStream::new(FromStd(Scripted(vec![Ok(b"ab"), Err(Interrupted), Ok(b"cd")])));
+    /// assert_eq!(stream.try_fill(4).unwrap(), FillStatus::Partial(2));
+    /// assert!(stream.try_fill(4).is_err());
+    /// assert_eq!(stream.buffered_input(), (&b"ab"[..], &b""[..]));
+    /// assert_eq!(stream.try_fill(4).unwrap(), FillStatus::Complete);
+    /// assert_eq!(stream.try_fill(5).unwrap(), FillStatus::Eof(Eof { needed: 5, got: 4 }));
+    ///
+    /// // Resuming after errors produces the same records as an error free stream.
+    /// let records = |script| {
+    ///     let mut stream = BufStream::new(FromStd(Scripted(script)));
+    ///     let mut records = Vec::new();
+    ///     loop {
+    ///         match stream.fill_at_least(4) {
+    ///             Ok(FillStatus::Complete) => {
+    ///                 let (lhs, rhs) = stream.buffered_input();
+    ///                 records.push([lhs, rhs].concat()[..4].to_vec());
+    ///                 stream.consume(4);
+    ///             }
+    ///             Ok(status) => return (records, status),
+    ///             Err(_) => continue,
+    ///         }
+    ///     }
+    /// };
+    /// let clean = records(vec![Ok(b"abcdefgh"), Ok(b"ijkl"), Ok(b"mn")]);
+    /// let faulty = records(vec![
+    ///     Ok(b"ab"), Err(Interrupted), Ok(b"cdef"), Err(ConnectionReset), Ok(b"g"), Ok(b"hijklmn")
+    /// ]);
+    /// assert_eq!(clean, faulty);
+    /// assert_eq!(clean.0, [b"abcd", b"efgh", b"ijkl"]);
+    /// assert_eq!(clean.1, FillStatus::Eof(Eof { needed: 4, got: 2 }));
+    /// ```
+    pub fn try_fill(&mut self, count: usize) -> Result<FillStatus, <S as Read>::Error> {
+        let buffered = self.input.len();
+        if buffered >= count {
+            return Ok(FillStatus::Complete);
+        }
+        if self.input.reserve(count - buffered).is_err() && self.input.is_full() {
+            return Ok(FillStatus::Full(buffered));
+        }
+        Ok(match self.fill_input()? {
+            0 => FillStatus::Eof(Eof { needed: count, got: buffered }),
+            _ if self.input.len() >= count => FillStatus::Complete,
+            _ => FillStatus::Partial(self.input.len()),
+        })
+    }
+    /// Read from the stream until at least `count` bytes of input are buffered or the stream ends.
+    /// Never returns [`FillStatus::Partial`]. See [`BufStream::fill_at_least_until`] to stop
+    /// part way through.
+    ///
+    /// As with [`BufStream::try_fill`], bytes that were read are kept if the stream fails and
+    /// calling this again resumes the fill.
+    pub fn fill_at_least(&mut self, count: usize) -> Result<FillStatus, <S as Read>::Error> {
+        loop {
+            match self.try_fill(count)? {
+                FillStatus::Partial(_) => continue,
+                status => return Ok(status),
+            }
+        }
+    }
+    /// Read from the stream until at least `count` bytes of input are buffered or the stream ends,
+    /// as [`BufStream::fill_at_least`] does, but call `should_stop` after each read and return
+    /// [`FillStatus::Partial`] once it returns `true`.
+    ///
+    /// This lets a caller bound how long filling takes, such as by closing over a deadline.
+    /// The bytes read are kept, so calling again resumes the fill.
+    /// ```rust
+    /// use std::{cell::Cell, io};
+    /// use circulate::{BufStream, Eof, FillStatus, FromStd};
+    ///
+    /// /// Reads at most three bytes at a time.
+    /// struct Trickle(io::Cursor<Vec<u8>>);
+    /// impl io::Read for Trickle {
+    ///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    ///         let len = buf.len().min(3);
+    ///         io::Read::read(&mut self.0, &mut buf[..len])
+    ///     }
+    /// }
+    ///
+    /// let mut stream = BufStream::with_capacity(FromStd(Trickle(io::Cursor::new(vec![1; 10]))), 16);
+    /// let reads = Cell::new(0);
+    /// let should_stop = || { reads.set(reads.get() + 1); reads.get() % 2 == 0 };
+    /// assert_eq!(stream.fill_at_least_until(8, should_stop).unwrap(), FillStatus::Partial(6));
+    /// assert_eq!(stream.fill_at_least_until(8, should_stop).unwrap(), FillStatus::Complete);
+    /// assert_eq!(stream.fill_at_least_until(11, should_stop).unwrap(), FillStatus::Eof(Eof { needed: 11, got: 10 }));
+    /// assert_eq!(reads.get(), 3);
+    /// ```
+    pub fn fill_at_least_until<F: FnMut() -> bool>(&mut self, count: usize, mut should_stop: F) -> Result<FillStatus, <S as Read>::Error> {
+        loop {
+            match self.try_fill(count)? {
+                FillStatus::Partial(buffered) if should_stop() => return Ok(FillStatus::Partial(buffered)),
+                FillStatus::Partial(_) => continue,
+                status => return Ok(status),
+            }
+        }
+    }
+    /// Get all of the buffered input as two slices, in order, without consuming it.
+    /// If no input is buffered this first reads from the stream.
+    ///
+    /// Once the input has been inspected, [`BufStream::consume`] discards the bytes that are no
+    /// longer needed.
+    /// ```rust
+    /// # use std::{cell::Cell, io, rc::Rc};
+    /// # struct Counted(&'static [u8], Rc<Cell<usize>>);
+    /// # impl io::Read for Counted {
+    /// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.1.set(self.1.get() + 1); io::Read::read(&mut self.0, buf) }
+    /// # }
+    /// # impl io::Write for Counted {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { Ok(buf.len()) }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// use circulate::{BufStream, FromStd};
+    ///
+    /// let reads = Rc::new(Cell::new(0));
+    /// let mut stream = BufStream::with_capacity(FromStd(Counted(b"GET / HTTP/1.1\r\n", reads.clone())), 64);
+    /// let (lhs, rhs) = stream.peek_all_input().unwrap();
+    /// assert_eq!([lhs, rhs].concat(), b"GET / HTTP/1.1\r\n");
+    /// assert_eq!(reads.get(), 1);
+    ///
+    /// // Buffered input is returned without reading from the stream.
+    /// let (lhs, rhs) = stream.peek_all_input().unwrap();
+    /// assert_eq!(lhs.len() + rhs.len(), 16);
+    /// assert_eq!(reads.get(), 1);
+    ///
+    /// stream.consume(4);
+    /// let (lhs, rhs) = stream.peek_all_input().unwrap();
+    /// assert_eq!([lhs, rhs].concat(), b"/ HTTP/1.1\r\n");
+    /// assert_eq!(reads.get(), 1);
+    /// ```
+    pub fn peek_all_input(&mut self) -> Result<(&[u8], &[u8]), <S as Read>::Error> {
+        if self.input.is_empty() {
+            self.buffer_read()?;
+        }
         let (lhs, rhs) = self.input.as_mut_slices();
-        let ptr = buffer.as_mut_ptr() as *mut u8;
-        let lhs_len = buffer.len().min(lhs.len());
-        let rhs_len = (buffer.len() - lhs_len).min(rhs.len());
-        let total_len = lhs_len + rhs_len;
-        // Safety:
-        // - `buffer` is valid for at least `lhs_len + rhs_len` writes.
-        // - `lhs` is valid for at least `lhs_len` reads.
-        // - `rhs` is valid for at least `rhs_len` reads.
-        // - Therefore the input buffer is valid for at least `total_len` reads.
-        // - `lhs`, `rhs` and `buffer` are mutable slice and therefore must be aligned and non-aliasing.
-        unsafe {
-            ptr.copy_from_nonoverlapping(lhs.as_ptr(), lhs_len);
-            ptr.offset(lhs_len as isize).copy_from_nonoverlapping(rhs.as_ptr(), rhs_len);
-            self.input.set_read_cursor(total_len);
+        Ok((lhs, rhs))
+    }
+    /// The same as [`BufStream::peek_all_input`], named as [`BufReader::fill_buf`](crate::BufReader::fill_buf)
+    /// so that parsers can be written against either.
+    pub fn fill_buf(&mut self) -> Result<(&[u8], &[u8]), <S as Read>::Error> {
+        self.peek_all_input()
+    }
+
+    /// Read the bytes up to and including the next `delimiter` as a buffer of their own, reading
+    /// from the stream until the delimiter is buffered.
+    ///
+    /// Returns `None` if the stream ends, or the input buffer reaches its maximum capacity, before
+    /// a delimiter is found. The bytes read so far stay buffered.
+    /// ```rust
+    /// # #[cfg(feature = "test-util")] {
+    /// use circulate::{test_util::MemoryStream, BufStream, Read};
+    ///
+    /// let mut stream = BufStream::new(MemoryStream::with_max_read(b"GET /\r\nHost: a\r\n\r\npartial".to_vec(), 3));
+    /// let mut lines = Vec::new();
+    /// while let Some(line) = stream.read_segment(b'\n').unwrap() {
+    ///     lines.push(line.to_vec());
+    /// }
+    /// assert_eq!(lines, [&b"GET /\r\n"[..], b"Host: a\r\n", b"\r\n"]);
+    ///
+    /// let mut rest = String::new();
+    /// stream.read_to_string(&mut rest).unwrap();
+    /// assert_eq!(rest, "partial");
+    /// # }
+    /// ```
+    pub fn read_segment(&mut self, delimiter: u8) -> Result<Option<RingBuffer<u8>>, <S as Read>::Error> {
+        let mut searched = 0;
+        loop {
+            if let Some(index) = self.input.find_byte(delimiter, searched) {
+                let segment = self.input.split_to(index + 1);
+                self.pushback = self.pushback.saturating_sub(segment.len());
+                return Ok(Some(segment));
+            }
+            searched = self.input.len();
+            if self.fill_input()? == 0 {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Move the bytes up to and including the next `delimiter` in to `out`, reading from the
+    /// stream until the delimiter is found or the stream ends. Returns the number of bytes added.
+    ///
+    /// Unlike [`BufStream::read_segment`] the bytes are moved out as they arrive, so a line may be
+    /// longer than the input buffer can hold. As with [`std::io::BufRead::read_until`](::std::io::BufRead::read_until), if reading
+    /// fails the bytes already added stay in `out`, so a stream that fails as no bytes are ready
+    /// yet can be read from again later to continue the same line.
+    /// ```rust
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{BufStream, Read};
+    ///
+    /// /// Has a byte ready on every other read.
+    /// struct Polled { data: &'static [u8], ready: bool }
+    /// impl Read for Polled {
+    ///     type Error = ();
+    ///     fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, ()> {
+    ///         self.ready = !self.ready;
+    ///         match (self.ready, self.data.split_first()) {
+    ///             (false, _) => Err(()),
+    ///             (true, Some((&byte, rest))) => {
+    ///                 buffer[0].write(byte);
+    ///                 self.data = rest;
+    ///                 Ok(1)
+    ///             }
+    ///             (true, None) => Ok(0),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut stream = BufStream::with_capacity(Polled { data: b"a long line\nend", ready: false }, 3);
+    /// stream.set_max_input_capacity(4);
+    /// let mut line = Vec::new();
+    /// while stream.read_until(b'\n', &mut line).is_err() {}
+    /// assert_eq!(line, b"a long line\n");
+    /// assert!(stream.input_capacity() <= 4);
+    ///
+    /// line.clear();
+    /// while stream.read_until(b'\n', &mut line).is_err() {}
+    /// assert_eq!(line, b"end");
+    /// // Once the stream has ended nothing more is added.
+    /// let mut added = stream.read_until(b'\n', &mut line);
+    /// while added.is_err() {
+    ///     added = stream.read_until(b'\n', &mut line);
+    /// }
+    /// assert_eq!(added, Ok(0));
+    /// ```
+    pub fn read_until(&mut self, delimiter: u8, out: &mut Vec<u8>) -> Result<usize, <S as Read>::Error> {
+        let mut added = 0;
+        loop {
+            let found = self.input.find_byte(delimiter, 0);
+            let count = found.map_or(self.input.len(), |index| index + 1);
+            let (lhs, rhs) = self.input.as_slices();
+            let lhs_len = lhs.len().min(count);
+            out.extend_from_slice(&lhs[..lhs_len]);
+            out.extend_from_slice(&rhs[..count - lhs_len]);
+            self.consume(count);
+            added += count;
+            if found.is_some() || self.fill_input()? == 0 {
+                return Ok(added);
+            }
+        }
+    }
+
+    /// Read in to the caller's buffers `dst` and the input buffer with a single vectored read,
+    /// so that bytes beyond those wanted now are kept for later without another read from the
+    /// stream or a copy through the input buffer. Returns the number of bytes read in to `dst`.
+    ///
+    /// Only an empty input buffer is read in to. If input is already buffered it is copied to
+    /// `dst` instead, without reading from the stream.
+    /// ```rust
+    /// use core::{convert::Infallible, mem::MaybeUninit};
+    /// use circulate::{BufStream, IoVecMut, Read};
+    ///
+    /// /// A stream that reads at most `limit` bytes per vectored read.
+    /// struct Scripted { data: &'static [u8], limit: usize }
+    /// impl Read for Scripted {
+    ///     type Error = Infallible;
+    ///     fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Infallible> {
+    ///         self.read_vectored(&mut [IoVecMut::maybe_uninit(buffer)])
+    ///     }
+    ///     fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Infallible> {
+    ///         let mut read = 0;
+    ///         for buffer in buffers {
+    ///             let len = self.data.len().min(self.limit - read);
+    ///             let count = buffer.fill_from_slice(&self.data[..len]);
+    ///             self.data = &self.data[count..];
+    ///             read += count;
+    ///         }
+    ///         Ok(read)
+    ///     }
+    /// }
+    ///
+    /// let data = b"HEADbodyoverflow";
+    /// // End the read at every position within the header, body and input buffer.
+    /// for limit in 0..=data.len() {
+    ///     let mut stream = BufStream::with_capacity(Scripted { data, limit }, 16);
+    ///     let (mut header, mut body) = ([0; 4], [0; 4]);
+    ///     let mut dst = [IoVecMut::new(&mut header), IoVecMut::new(&mut body)];
+    ///     let read = stream.read_scatter(&mut dst).unwrap();
+    ///     assert_eq!(read, limit.min(8));
+    ///     assert_eq!(header[..limit.min(4)], data[..limit.min(4)]);
+    ///     assert_eq!(body[..limit.clamp(4, 8) - 4], data[4..limit.clamp(4, 8)]);
+    ///     let (lhs, rhs) = stream.buffered_input();
+    ///     assert_eq!([lhs, rhs].concat(), data[limit.min(8)..limit]);
+    ///
+    ///     // The bytes kept in the input buffer are copied out by the next read.
+    ///     if limit > 8 {
+    ///         let mut rest = [0; 16];
+    ///         let read = stream.read_scatter(&mut [IoVecMut::new(&mut rest)]).unwrap();
+    ///         assert_eq!(rest[..read], data[8..limit]);
+    ///         assert_eq!(stream.buffered_input(), (&[][..], &[][..]));
+    ///     }
+    /// }
+    /// ```
+    pub fn read_scatter(&mut self, dst: &mut [IoVecMut]) -> Result<usize, <S as Read>::Error> {
+        if !self.input.is_empty() {
+            return Ok(self.read_into_vectored(&mut IoVecCursor::new(dst)));
+        }
+        // Start from the beginning so the input buffer is a single region after `dst`.
+        self.input.clear();
+        // Read ahead is best effort, failing to grow only limits how much is kept.
+        let _ = self.input.reserve(self.read_ahead.max(1));
+        let wanted: usize = dst.iter().map(IoVecMut::len).sum();
+        let spare = self.input.spare_capacity_mut().0;
+        let spare_len = spare.len();
+        if wanted + spare_len == 0 {
+            // The stream is never asked to read in to no space.
+            return Ok(0);
+        }
+        let mut buffers = Vec::with_capacity(dst.len() + 1);
+        buffers.extend(dst.iter_mut().map(|buffer| IoVecMut::maybe_uninit(buffer.as_maybe_uninit_slice())));
+        buffers.push(IoVecMut::maybe_uninit(spare));
+        let got = trace_io!("refill", wanted + spare_len, self.stream.read_vectored(&mut buffers))?;
+        self.received += got as u64;
+        let kept = got.saturating_sub(wanted).min(spare_len);
+        // Safety: The stream initialized the first `kept` bytes of the spare capacity.
+        unsafe { self.input.advance_write_unchecked(kept) };
+        self.emit(IoEvent::Refill { requested: wanted + spare_len, got });
+        Ok(got.min(wanted))
+    }
+
+    fn read_into(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, <S as Read>::Error> {
+        let mut buffers = [IoVecMut::maybe_uninit(buffer)];
+        Ok(self.read_into_vectored(&mut IoVecCursor::new(&mut buffers)))
+    }
+    /// Returns if reads should go directly to the stream, as it is already buffered and there is
+    /// no input buffered here to return first.
+    fn reads_through(&self) -> bool {
+        self.input.is_empty() && self.stream.is_buffered()
+    }
+    /// Copy buffered input to the unfilled part of the buffers behind `cursor`.
+    /// Returns the number of bytes copied.
+    fn read_into_vectored(&mut self, cursor: &mut IoVecCursor) -> usize {
+        let (lhs, rhs) = self.input.as_slices();
+        let copied = match cursor.write(lhs) {
+            len if len == lhs.len() => len + cursor.write(rhs),
+            len => len,
+        };
+        // Safety: No more than the buffered bytes were copied.
+        unsafe { self.input.advance_read_unchecked(copied) };
+        self.pushback = self.pushback.saturating_sub(copied);
+        copied
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S: Write> BufStream<S> {
+    /// Write the entire output buffer to the stream, retrying short writes, and then flush the stream.
+    /// If the stream stops accepting bytes or fails the remainder is left in the output buffer,
+    /// to be flushed again or discarded with [`BufStream::discard_output`].
+    pub fn flush_output_to_completion(&mut self) -> Result<(), <S as Write>::Error> {
+        self.flush_for(FlushReason::Explicit)
+    }
+    fn flush_for(&mut self, reason: FlushReason) -> Result<(), <S as Write>::Error> {
+        self.write_buffered(reason, &mut 0)?;
+        self.stream.flush()
+    }
+    /// Note that output was buffered, timing it if no other output is waiting to be written.
+    #[inline]
+    fn output_buffered(&mut self) {
+        self.flush_on_drop = DropFlush::ARMED;
+        #[cfg(not(feature = "no_std"))]
+        if self.oldest_unflushed.is_none() && !self.output_is_flushed() {
+            self.oldest_unflushed = Some(::std::time::Instant::now());
+        }
+    }
+    /// Flush the output of a stream being dropped, if any is left.
+    fn flush_dropped(&mut self) {
+        if !self.output_is_flushed() {
+            let _ = self.flush_for(FlushReason::Drop);
+        }
+    }
+    /// Queue an owned chunk of output without copying it in to the output buffer, which suits
+    /// large writes. The chunk is written after any output already buffered.
+    /// ```rust
+    /// # #[cfg(feature = "test-util")] {
+    /// use circulate::{test_util::MemoryStream, BufStream, Write};
+    ///
+    /// // Accepts at most three bytes per write.
+    /// let mut inner = MemoryStream::default();
+    /// inner.set_max_write(3);
+    /// let mut stream = BufStream::new(inner);
+    /// stream.write(b"head:").unwrap();
+    /// stream.write_chunk(b"a large body".to_vec());
+    /// stream.write(b":tail").unwrap();
+    /// stream.write_chunk(b"!".to_vec());
+    /// stream.flush().unwrap();
+    /// assert_eq!(stream.get_ref().output(), b"head:a large body:tail!");
+    /// # }
+    /// ```
+    pub fn write_chunk(&mut self, chunk: Vec<u8>) {
+        if chunk.is_empty() {
+            return;
+        }
+        if self.chunks.is_empty() {
+            // Output already buffered is written ahead of the chunk, where it is.
+            self.output_ahead = self.output.len();
+        } else if self.output.len() > self.output_ahead {
+            // Output buffered since the last chunk must be written between the chunks, so is
+            // queued with them. Only output interleaved with chunks is copied.
+            let ([_, _], [lhs, rhs]) = self.output_around_chunks();
+            let mut between = Vec::with_capacity(lhs.len() + rhs.len());
+            between.extend_from_slice(lhs);
+            between.extend_from_slice(rhs);
+            self.chunks.push(between);
+            self.output.truncate(self.output_ahead);
+        }
+        self.chunks.push(chunk);
+        self.output_buffered();
+    }
+    /// Write a frame of a header made by `make_header` from the length of `payload`, followed
+    /// by the payload, so that the frame goes out together.
+    ///
+    /// `make_header` returns the header in an array along with its length. Frames with a payload
+    /// shorter than 16 KiB are buffered with the header and payload adjacent in the output
+    /// buffer, so that they are written to the stream as one unit. Larger frames, and those that
+    /// would not fit in a bounded output buffer, are written straight to the stream with one
+    /// vectored write after flushing the output already buffered, retrying short writes.
+    /// ```rust
+    /// use core::convert::Infallible;
+    /// use circulate::{BufStream, IoVec, Write};
+    ///
+    /// /// Records the length of each buffer of each write.
+    /// #[derive(Default)]
+    /// struct Recorder(Vec<Vec<usize>>);
+    /// impl Write for Recorder {
+    ///     type Error = Infallible;
+    ///     fn write(&mut self, slice: &[u8]) -> Result<usize, Infallible> {
+    ///         self.0.push(vec![slice.len()]);
+    ///         Ok(slice.len())
+    ///     }
+    ///     fn write_vectored(&mut self, buffers: &[IoVec]) -> Result<usize, Infallible> {
+    ///         self.0.push(buffers.iter().map(IoVec::len).collect());
+    ///         Ok(buffers.iter().map(IoVec::len).sum())
+    ///     }
+    ///     fn flush(&mut self) -> Result<(), Infallible> { Ok(()) }
+    /// }
+    /// // A header of the payload length as a variable length integer.
+    /// let varint = |mut len: usize| {
+    ///     let mut header = [0; 10];
+    ///     let mut i = 0;
+    ///     while len >= 0x80 {
+    ///         header[i] = len as u8 | 0x80;
+    ///         len >>= 7;
+    ///         i += 1;
+    ///     }
+    ///     header[i] = len as u8;
+    ///     (header, i + 1)
+    /// };
+    ///
+    /// // A small frame is written to the stream in a single write.
+    /// let mut stream = BufStream::new(Recorder::default());
+    /// stream.write_framed(&[7; 200], varint).unwrap();
+    /// stream.flush().unwrap();
+    /// assert_eq!(stream.get_ref().0, [vec![2 + 200]]);
+    ///
+    /// // A large frame is written after the buffered output, with the header and payload as
+    /// // separate buffers of one vectored write.
+    /// let mut stream = BufStream::new(Recorder::default());
+    /// stream.write(b"buffered").unwrap();
+    /// stream.write_framed(&vec![7; 100_000], varint).unwrap();
+    /// stream.flush().unwrap();
+    /// assert_eq!(stream.get_ref().0, [vec![8], vec![3, 100_000]]);
+    /// ```
+    /// # Panics
+    /// Panics if the header length returned by `make_header` is greater than `MAX_HDR`.
+    pub fn write_framed<const MAX_HDR: usize>(
+        &mut self,
+        payload: &[u8],
+        make_header: impl FnOnce(usize) -> ([u8; MAX_HDR], usize),
+    ) -> Result<(), WriteAllError<<S as Write>::Error>> {
+        let (header, header_len) = make_header(payload.len());
+        let header = &header[..header_len];
+        if !self.shutdown && payload.len() < FRAME_BYPASS && self.stage_frame(header, payload) {
+            return Ok(());
+        }
+        // Buffered output is written first, so it must all be written before the frame.
+        self.write_buffered(FlushReason::Bypass, &mut 0).map_err(WriteAllError::Write)?;
+        if !self.shutdown && !self.output_is_flushed() {
+            return match self.stage_frame(header, payload) {
+                true => Ok(()),
+                false => Err(WriteAllError::WriteZero { bytes_so_far: 0 }),
+            };
+        }
+        let mut buffers = [IoVec::new(header), IoVec::new(payload)];
+        let mut buffers = &mut buffers[..];
+        let mut written = 0;
+        IoVec::advance_slices(&mut buffers, 0);
+        while !buffers.is_empty() {
+            let result = trace_io!("bypass_write", header.len() + payload.len() - written, self.stream.write_vectored(buffers));
+            match result.map_err(WriteAllError::Write)? {
+                0 => return Err(WriteAllError::WriteZero { bytes_so_far: written }),
+                bytes => {
+                    written += bytes;
+                    self.flushed += bytes as u64;
+                    self.emit(IoEvent::Bypass { bytes });
+                    IoVec::advance_slices(&mut buffers, bytes);
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Buffer a whole frame, with the header and payload adjacent in the output buffer.
+    /// Returns `false`, having buffered nothing, if the frame does not fit.
+    fn stage_frame(&mut self, header: &[u8], payload: &[u8]) -> bool {
+        let len = header.len() + payload.len();
+        if self.output.is_empty() {
+            // Start from the beginning so the frame is contiguous.
+            self.output.clear();
+        }
+        if self.write_would_block(len) || self.output.reserve(len).is_err() {
+            return false;
+        }
+        self.output.with_spare_capacity(|spare| {
+            spare.extend_from_slice(header);
+            spare.extend_from_slice(payload);
+        });
+        self.output_buffered();
+        true
+    }
+    /// The rest of [`Write::write`], for writes that do not fit in the output buffer as it is.
+    #[cold]
+    #[inline(never)]
+    fn write_outlined(&mut self, slice: &[u8]) -> Result<usize, <S as Write>::Error> {
+        if self.shutdown {
+            // Let the stream report the error.
+            let bytes = trace_io!("bypass_write", slice.len(), self.stream.write(slice))?;
+            self.flushed += bytes as u64;
+            self.emit(IoEvent::Bypass { bytes });
+            return Ok(bytes);
         }
-        Ok(total_len)
+        let written = append(&mut self.output, slice);
+        self.output_buffered();
+        Ok(written)
+    }
+    /// Write the queued chunks and then the output buffer to the stream until it stops accepting
+    /// bytes, adding the number of bytes written to `written`.
+    fn write_buffered(&mut self, reason: FlushReason, written: &mut usize) -> Result<(), <S as Write>::Error> {
+        while !self.chunks.is_empty() {
+            // The output ahead of the chunks is written with them, in one vectored write.
+            let ([lhs, rhs], _) = split_slices(&self.output, self.output_ahead);
+            match trace_io!("bypass_write", self.output_ahead + self.chunks.len(), self.chunks.write_after(&mut self.stream, [lhs, rhs]))? {
+                0 => break,
+                bytes => {
+                    let from_output = bytes.min(self.output_ahead);
+                    // Safety: The output buffer holds the `output_ahead` bytes written first.
+                    unsafe { self.output.advance_read_unchecked(from_output) };
+                    self.output_ahead -= from_output;
+                    *written += bytes;
+                    self.flushed += bytes as u64;
+                    self.emit(IoEvent::Bypass { bytes });
+                }
+            }
+        }
+        if !self.chunks.is_empty() {
+            return Ok(());
+        }
+        while !self.output.is_empty() {
+            let attempted = self.output.len();
+            let count = trace_io!("flush", attempted, self.output.drain_to(&mut self.stream))?;
+            *written += count;
+            self.flushed += count as u64;
+            self.emit(IoEvent::Flush { attempted, written: count, reason });
+            if count == 0 {
+                break;
+            }
+        }
+        self.output_drained();
+        Ok(())
+    }
+    /// Write as much buffered output as the stream accepts without waiting, for use with
+    /// non-blocking streams in an event loop. The stream is only flushed once all output has been
+    /// written.
+    ///
+    /// Writing stops once the stream accepts no more bytes or fails. If the stream fails after
+    /// some bytes were written the progress is returned instead of the error, which the next call
+    /// reports, so a stream that fails with an error such as [`std::io::ErrorKind::WouldBlock`](::std::io::ErrorKind::WouldBlock)
+    /// can be retried once it is writable again.
+    /// ```rust
+    /// # use std::{cell::{Cell, RefCell}, io, rc::Rc};
+    /// # /// Accepts three bytes per tick of the event loop.
+    /// # struct Slow { budget: Rc<Cell<usize>>, sent: Rc<RefCell<Vec<u8>>> }
+    /// # impl io::Read for Slow {
+    /// #     fn read(&mut self, _: &mut [u8]) -> io::Result<usize> { Ok(0) }
+    /// # }
+    /// # impl io::Write for Slow {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    /// #         let len = buf.len().min(self.budget.get());
+    /// #         if len == 0 {
+    /// #             return Err(io::ErrorKind::WouldBlock.into());
+    /// #         }
+    /// #         self.budget.set(self.budget.get() - len);
+    /// #         self.sent.borrow_mut().extend_from_slice(&buf[..len]);
+    /// #         Ok(len)
+    /// #     }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// use circulate::{BufStream, FromStd, Write};
+    ///
+    /// let budget = Rc::new(Cell::new(0));
+    /// let sent = Rc::new(RefCell::new(Vec::new()));
+    /// let mut stream = BufStream::new(FromStd(Slow { budget: budget.clone(), sent: sent.clone() }));
+    /// stream.set_max_output_capacity(8);
+    ///
+    /// let message = b"the quick brown fox";
+    /// let mut queued = 0;
+    /// let mut ticks = 0;
+    /// while sent.borrow().len() < message.len() {
+    ///     // Queue as much as fits without a short write.
+    ///     let len = stream.remaining_write_capacity().min(message.len() - queued);
+    ///     assert!(!stream.write_would_block(len));
+    ///     assert_eq!(stream.write(&message[queued..queued + len]).unwrap(), len);
+    ///     queued += len;
+    ///     assert!(stream.write_would_block(stream.remaining_write_capacity() + 1));
+    ///
+    ///     // The sink becomes writable.
+    ///     budget.set(3);
+    ///     ticks += 1;
+    ///     let progress = stream.flush_nonblocking().unwrap();
+    ///     assert_eq!(progress.written(), 3.min(progress.written() + progress.remaining()));
+    ///     if progress.is_complete() {
+    ///         assert!(stream.output_is_flushed());
+    ///     }
+    ///     // Nothing more is accepted until the next tick.
+    ///     if !progress.is_complete() {
+    ///         assert!(stream.flush_nonblocking().is_err());
+    ///     }
+    /// }
+    /// assert_eq!(&*sent.borrow(), message);
+    /// assert_eq!(ticks, 7);
+    /// ```
+    pub fn flush_nonblocking(&mut self) -> Result<FlushProgress, <S as Write>::Error> {
+        let mut written = 0;
+        let result = self.write_buffered(FlushReason::Explicit, &mut written);
+        let remaining = self.chunks.len() + self.output.len();
+        match result {
+            Err(error) if written == 0 => return Err(error),
+            Ok(()) if remaining == 0 => self.stream.flush()?,
+            _ => (),
+        }
+        Ok(FlushProgress { written, remaining })
     }
 }
-impl<S: Sized + Read + Write> Read for BufStream<S> {
+#[cfg(feature = "alloc")]
+impl<S: Write> BufStream<S> {
+    /// Flush the buffered output and get the underlying stream, discarding any buffered input.
+    ///
+    /// If flushing fails the stream is handed back in the error with its buffers intact, so that
+    /// flushing can be retried or the stream taken with [`BufStream::take_stream`] instead.
+    /// ```rust
+    /// # #[cfg(feature = "test-util")] {
+    /// use std::io::ErrorKind;
+    /// use circulate::{test_util::{MemoryStream, ScriptedStream, Step}, BufStream, Write};
+    ///
+    /// // The first write fails.
+    /// let mut stream = BufStream::new(ScriptedStream::new(MemoryStream::default(), [Step::Fail(ErrorKind::Interrupted)]));
+    /// stream.write(b"kept for retry").unwrap();
+    /// let Err(error) = stream.into_inner() else { panic!("the first write fails") };
+    /// assert_eq!(error.error().kind(), ErrorKind::Interrupted);
+    /// let stream = error.into_stream();
+    /// assert!(!stream.output_is_flushed());
+    /// let flaky = stream.into_inner().map_err(|error| error.into_error()).unwrap();
+    /// assert_eq!(flaky.get_ref().output(), b"kept for retry");
+    /// # }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn into_inner(mut self) -> Result<S, IntoInnerError<BufStream<S>, <S as Write>::Error>> {
+        match self.flush_for(FlushReason::Explicit) {
+            Ok(()) => Ok(self.take_stream()),
+            Err(error) => Err(IntoInnerError { stream: self, error }),
+        }
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S: Shutdown> BufStream<S> {
+    /// Flush all buffered output and then shut down the stream, such as to send a TCP FIN once
+    /// everything written has been sent.
+    ///
+    /// Afterwards writes are no longer buffered, so they return the error of the shut down
+    /// stream. Only writing is shut down, so reads carry on with the buffered input and then
+    /// the stream, until the other end finishes too. If flushing fails the stream is not shut
+    /// down, and shutting down can be tried again.
+    /// ```rust
+    /// # use std::io;
+    /// # use std::{cell::RefCell, rc::Rc};
+    /// # struct Pipe { input: io::Cursor<Vec<u8>>, output: Rc<RefCell<Vec<u8>>>, closed: bool }
+    /// # impl Read for Pipe {
+    /// #     type Error = io::Error;
+    /// #     fn read(&mut self, buf: &mut [MaybeUninit<u8>]) -> io::Result<usize> { FromStd(&mut self.input).read(buf) }
+    /// # }
+    /// # impl Write for Pipe {
+    /// #     type Error = io::Error;
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    /// #         if self.closed { return Err(io::ErrorKind::BrokenPipe.into()) }
+    /// #         io::Write::write(&mut *self.output.borrow_mut(), buf)
+    /// #     }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// # impl circulate::Shutdown for Pipe {
+    /// #     fn shutdown(&mut self) -> io::Result<()> { self.closed = true; Ok(()) }
+    /// # }
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{BufStream, FromStd, Read, Write};
+    ///
+    /// let output = Rc::new(RefCell::new(Vec::new()));
+    /// let pipe = Pipe { input: io::Cursor::new(b"hello".to_vec()), output: output.clone(), closed: false };
+    /// let mut stream = BufStream::with_capacity(pipe, 64);
+    /// stream.write(b"goodbye").unwrap();
+    ///
+    /// let mut buffer = [MaybeUninit::uninit(); 2];
+    /// assert_eq!(stream.read(&mut buffer).unwrap(), 2);
+    /// stream.shutdown().unwrap();
+    /// assert_eq!(&*output.borrow(), b"goodbye");
+    /// assert!(stream.write(b"!").is_err());
+    ///
+    /// let mut buffer = [MaybeUninit::uninit(); 16];
+    /// assert_eq!(stream.read(&mut buffer).unwrap(), 3);
+    /// assert_eq!(stream.read(&mut buffer).unwrap(), 0);
+    /// ```
+    /// Input that arrives after shutting down is still read, while every kind of write fails.
+    /// ```rust
+    /// # use std::io;
+    /// # use std::{cell::RefCell, rc::Rc};
+    /// # /// Reads whatever has arrived, and fails writes once closed.
+    /// # struct Pipe { input: Rc<RefCell<Vec<u8>>>, output: Vec<u8>, closed: bool }
+    /// # impl Read for Pipe {
+    /// #     type Error = io::Error;
+    /// #     fn read(&mut self, buf: &mut [MaybeUninit<u8>]) -> io::Result<usize> {
+    /// #         let mut input = self.input.borrow_mut();
+    /// #         let len = buf.len().min(input.len());
+    /// #         for (slot, byte) in buf.iter_mut().zip(input.drain(..len)) {
+    /// #             slot.write(byte);
+    /// #         }
+    /// #         Ok(len)
+    /// #     }
+    /// # }
+    /// # impl Write for Pipe {
+    /// #     type Error = io::Error;
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    /// #         if self.closed { return Err(io::ErrorKind::BrokenPipe.into()) }
+    /// #         io::Write::write(&mut self.output, buf)
+    /// #     }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// # impl circulate::Shutdown for Pipe {
+    /// #     fn shutdown(&mut self) -> io::Result<()> { self.closed = true; Ok(()) }
+    /// # }
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{BufStream, Eof, FillStatus, Read, Write};
+    ///
+    /// let input = Rc::new(RefCell::new(b"request".to_vec()));
+    /// let pipe = Pipe { input: input.clone(), output: Vec::new(), closed: false };
+    /// let mut stream = BufStream::with_capacity(pipe, 64);
+    /// assert_eq!(stream.write(b"response").unwrap(), 8);
+    /// stream.buffer_read().unwrap();
+    /// stream.shutdown().unwrap();
+    /// assert_eq!(stream.get_ref().output, b"response");
+    /// assert!(stream.output_is_flushed());
+    ///
+    /// // Writes fail, whether or not they would have fit in the output buffer.
+    /// let broken = |result: io::Result<usize>| result.unwrap_err().kind() == io::ErrorKind::BrokenPipe;
+    /// assert!(broken(stream.write(b"x")));
+    /// assert!(broken(stream.write(&[0; 1000])));
+    /// assert!(broken(stream.write_repeated(b'x', 3)));
+    /// assert!(stream.output_is_flushed());
+    /// // Flushing has nothing to write, so succeeds.
+    /// stream.flush().unwrap();
+    ///
+    /// // The buffered input is read, and then more arrives from the other end.
+    /// let mut buffer = [MaybeUninit::uninit(); 16];
+    /// assert_eq!(stream.read(&mut buffer).unwrap(), 7);
+    /// input.borrow_mut().extend_from_slice(b"more");
+    /// assert_eq!(stream.try_fill(4).unwrap(), FillStatus::Complete);
+    /// assert_eq!(stream.read(&mut buffer).unwrap(), 4);
+    /// // Until the other end finishes.
+    /// assert_eq!(stream.read(&mut buffer).unwrap(), 0);
+    /// assert_eq!(stream.try_fill(1).unwrap(), FillStatus::Eof(Eof { needed: 1, got: 0 }));
+    /// assert!(broken(stream.write(b"x")));
+    /// ```
+    pub fn shutdown(&mut self) -> Result<(), <S as Write>::Error> {
+        self.flush_for(FlushReason::Shutdown)?;
+        self.stream.shutdown()?;
+        self.shutdown = true;
+        Ok(())
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S: Read> Read for BufStream<S> {
     type Error = <S as Read>::Error;
     fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
         // TODO: avoid buffering when provided with a large enough buffer anyway.
-        self.buffer_read()?;
+        if self.reads_through() {
+            let got = trace_io!("bypass_read", buffer.len(), self.stream.read(buffer))?;
+            self.received += got as u64;
+            self.emit(IoEvent::Refill { requested: buffer.len(), got });
+            return Ok(got);
+        }
+        if self.input.is_empty() {
+            self.buffer_read()?;
+        }
         self.read_into(buffer)
     }
     fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
-        self.buffer_read()?;
-        let mut read = 0;
-        for buffer in buffers {
-            read += self.read_into(buffer.as_maybe_uninit_slice())?;
+        if self.reads_through() {
+            let requested = buffers.iter().map(IoVecMut::len).sum();
+            let got = trace_io!("bypass_read", requested, self.stream.read_vectored(buffers))?;
+            self.received += got as u64;
+            self.emit(IoEvent::Refill { requested, got });
+            return Ok(got);
         }
-        Ok(read)
+        let mut refilled = false;
+        if self.input.is_empty() {
+            self.buffer_read()?;
+            refilled = true;
+        }
+        let mut cursor = IoVecCursor::new(buffers);
+        loop {
+            // A read that filled the input buffer suggests the stream has more ready.
+            let filled = refilled && self.input.is_full();
+            self.read_into_vectored(&mut cursor);
+            let read = cursor.filled();
+            if !(self.vectored_refill && filled) || cursor.is_full() {
+                return Ok(read);
+            }
+            match self.fill_input() {
+                Ok(0) => return Ok(read),
+                Ok(_) => refilled = true,
+                Err(_) if read != 0 => return Ok(read),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+    fn is_buffered(&self) -> bool {
+        true
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S: Write> Write for BufStream<S> {
+    type Error = <S as Write>::Error;
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.flush_output_to_completion()
+    }
+    /// Buffer the bytes of `slice` that fit in the output buffer, growing it as needed.
+    ///
+    /// Writes that fit in the output buffer without growing it or wrapping take an inlined fast
+    /// path, so many small writes cost little more than copying the bytes.
+    /// ```rust
+    /// use circulate::{BufStream, FromStd, Write};
+    ///
+    /// let mut stream = BufStream::with_capacity(FromStd(std::io::Cursor::new(Vec::new())), 16);
+    /// stream.set_max_output_capacity(16);
+    /// let mut expected = Vec::new();
+    /// for record in 0..40u32 {
+    ///     let record = record.to_le_bytes();
+    ///     let mut rest = &record[..3];
+    ///     while !rest.is_empty() {
+    ///         // Records spill over the end of the allocation and fill the buffer exactly.
+    ///         match stream.write(rest).unwrap() {
+    ///             0 => stream.flush_output_to_completion().unwrap(),
+    ///             written => rest = &rest[written..],
+    ///         }
+    ///     }
+    ///     expected.extend_from_slice(&record[..3]);
+    ///     if record[0] % 7 == 0 {
+    ///         // Consuming part of the output leaves it wrapped.
+    ///         stream.flush_nonblocking().unwrap();
+    ///     }
+    /// }
+    /// stream.flush_output_to_completion().unwrap();
+    /// assert_eq!(stream.take_stream().0.into_inner(), expected);
+    /// ```
+    #[inline]
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        if !self.shutdown && self.output.try_extend_contiguous(slice) {
+            self.output_buffered();
+            return Ok(slice.len());
+        }
+        self.write_outlined(slice)
+    }
+    fn write_repeated(&mut self, byte: u8, count: usize) -> Result<usize, Self::Error> {
+        if self.shutdown {
+            let bytes = trace_io!("bypass_write", count, self.stream.write_repeated(byte, count))?;
+            self.flushed += bytes as u64;
+            self.emit(IoEvent::Bypass { bytes });
+            return Ok(bytes);
+        }
+        let count = self.output.reserve_up_to(count);
+        // Note: Space was reserved for `count` bytes so filling cannot fail.
+        let _ = self.output.fill_bytes(byte, count);
+        self.output_buffered();
+        Ok(count)
     }
 }
-// impl<S: Sized + Read + Write> Write for BufStream<S> {
-//     type Error = <S as Write>::Error;
-//     fn flush(&mut self) -> Result<(), Self::Error> {
-//         self.stream.flush()
-//     }
-//     fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
-//         
-//     }
-// }
 
-pub struct BufReader<> {
+#[cfg(feature = "alloc")]
+impl<S> Drop for BufStream<S> {
+    fn drop(&mut self) {
+        // Note: Flushing could panic again while unwinding, aborting the process.
+        #[cfg(not(feature = "no_std"))]
+        if ::std::thread::panicking() {
+            return;
+        }
+        if let DropFlush(Some(flush)) = self.flush_on_drop {
+            flush(self);
+        }
+    }
+}
+/// Flushes the output of a [`BufStream`] when it is dropped.
+///
+/// The `Drop` impl cannot require the stream to implement [`Write`], so the flush is armed by the
+/// methods that buffer output, which do, and carried over to streams that are also writable.
+#[cfg(feature = "alloc")]
+struct DropFlush<S>(Option<fn(&mut BufStream<S>)>);
+#[cfg(feature = "alloc")]
+impl<S> DropFlush<S> {
+    const DISARMED: Self = Self(None);
+    const fn is_armed(&self) -> bool {
+        self.0.is_some()
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S: Write> DropFlush<S> {
+    const ARMED: Self = Self(Some(BufStream::flush_dropped));
+}
+#[cfg(feature = "alloc")]
+impl<S: Default> Default for BufStream<S> {
+    /// Buffer the default stream, as with [`BufStream::new`].
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S> From<S> for BufStream<S> {
+    /// Buffer `stream`, as with [`BufStream::new`], which does not allocate until it is used.
+    fn from(stream: S) -> Self {
+        Self::new(stream)
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S> AsRef<S> for BufStream<S> {
+    fn as_ref(&self) -> &S {
+        &self.stream
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S> AsMut<S> for BufStream<S> {
+    /// Reading from or writing to the stream directly bypasses the buffered input and output.
+    fn as_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+}
+/// Shows the stream, and the number of bytes buffered in each direction out of the capacity,
+/// but never the bytes themselves.
+/// ```rust
+/// use std::io::Cursor;
+/// use circulate::{BufStream, FromStd, Read, Write};
+///
+/// let mut stream = BufStream::from(FromStd(Cursor::new(b"secret".to_vec())));
+/// assert_eq!(format!("{stream:?}"), "BufStream { stream: FromStd(Cursor { inner: [115, 101, 99, 114, 101, 116], pos: 0 }), input: 0/0, output: 0/0, queued: 0 }");
+///
+/// let mut stream = BufStream::with_capacity(FromStd(Cursor::new(Vec::new())), 15);
+/// stream.write(b"secret").unwrap();
+/// stream.get_mut().0.get_mut().extend_from_slice(b"reply");
+/// stream.buffer_read().unwrap();
+/// let debug = format!("{:?}", stream);
+/// assert!(debug.ends_with("input: 5/15, output: 6/15, queued: 0 }"), "{debug}");
+/// assert!(!debug.contains("115, 101, 99"));
+///
+/// let stream: BufStream<FromStd<Cursor<Vec<u8>>>> = BufStream::default();
+/// assert!(stream.as_ref().0.get_ref().is_empty());
+/// ```
+#[cfg(feature = "alloc")]
+impl<S: core::fmt::Debug> core::fmt::Debug for BufStream<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let usable = |buffer: &RingBuffer<u8>| buffer.capacity().saturating_sub(1);
+        f.debug_struct("BufStream")
+            .field("stream", &self.stream)
+            .field("input", &format_args!("{}/{}", self.input.len(), usable(&self.input)))
+            .field("output", &format_args!("{}/{}", self.output.len(), usable(&self.output)))
+            .field("queued", &self.chunks.len())
+            .finish()
+    }
+}
 
+/// Get the slices of the first `at` bytes of `buffer`, and of the bytes after them.
+#[cfg(feature = "alloc")]
+fn split_slices(buffer: &RingBuffer<u8>, at: usize) -> ([&[u8]; 2], [&[u8]; 2]) {
+    let (lhs, rhs) = buffer.as_slices();
+    let (lhs, after_lhs) = lhs.split_at(lhs.len().min(at));
+    let (rhs, after_rhs) = rhs.split_at(at - lhs.len());
+    ([lhs, rhs], [after_lhs, after_rhs])
+}
+/// Copy `slice` to the end of `buffer`, growing it as needed.
+/// Returns the number of bytes copied, which is less than the length of `slice` only if the
+/// buffer could not grow.
+#[cfg(feature = "alloc")]
+fn append(buffer: &mut RingBuffer<u8>, slice: &[u8]) -> usize {
+    // If the buffer cannot grow as much as is needed then it grows as far as it can, and only
+    // what fits is copied.
+    if buffer.reserve(slice.len()).is_err() && buffer.max_capacity().is_some() {
+        let _ = buffer.reserve(buffer.reachable_capacity().saturating_sub(1) - buffer.len());
+    }
+    buffer.with_spare_capacity(|spare| spare.extend_from_slice(slice))
 }
-pub struct BufWriter<> {
 
+/// The payload length from which [`BufStream::write_framed`] writes straight to the stream.
+#[cfg(feature = "alloc")]
+const FRAME_BYPASS: usize = 16 * 1024;
+/// The default smallest size of the input buffer with adaptive buffering.
+#[cfg(feature = "alloc")]
+const ADAPTIVE_FLOOR: usize = 1024;
+/// The default largest size of the input buffer with adaptive buffering.
+#[cfg(feature = "alloc")]
+const ADAPTIVE_CAP: usize = 64 * 1024;
+/// The state of adaptive buffering. See [`BufStream::set_adaptive_buffering`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy)]
+struct Adaptive {
+    enabled: bool,
+    /// The smallest size of the input buffer, a power of two.
+    floor: usize,
+    /// The largest size of the input buffer, a power of two.
+    cap: usize,
+    /// The read ahead to restore once disabled.
+    read_ahead: usize,
+    /// The exponentially weighted average of the bytes per read, scaled by 8.
+    average: usize,
+    /// The number of reads in a row that filled all of the free space.
+    full: u8,
+    /// The number of reads in a row with the average under a quarter of the size.
+    small: u8,
+}
+#[cfg(feature = "alloc")]
+impl Adaptive {
+    const fn new() -> Self {
+        Self { enabled: false, floor: ADAPTIVE_FLOOR, cap: ADAPTIVE_CAP, read_ahead: 0, average: 0, full: 0, small: 0 }
+    }
+    /// Get the read ahead for an input buffer size of `size`, kept within the limits.
+    fn clamp(&self, size: usize) -> usize {
+        // Note: One slot of the input buffer always remains vacant.
+        size.next_power_of_two().clamp(self.floor, self.cap) - 1
+    }
 }
 
+/// The progress made by [`BufStream::flush_nonblocking`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushProgress {
+    written: usize,
+    remaining: usize,
+}
+#[cfg(feature = "alloc")]
+impl FlushProgress {
+    /// The number of bytes written to the stream.
+    pub const fn written(&self) -> usize {
+        self.written
+    }
+    /// The number of bytes still waiting to be written.
+    pub const fn remaining(&self) -> usize {
+        self.remaining
+    }
+    /// Returns if all output has been written.
+    pub const fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// The outcome of [`BufStream::try_fill`], [`BufStream::fill_at_least`] and
+/// [`BufStream::fill_at_least_until`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillStatus {
+    /// At least the requested number of bytes are buffered.
+    Complete,
+    /// Fewer bytes than requested are buffered but the stream may have more.
+    Partial(usize),
+    /// The stream ended with fewer bytes than requested buffered, which stay buffered.
+    Eof(Eof),
+    /// The input buffer is full and could not grow to hold the requested bytes.
+    /// See [`BufStream::set_alloc_failure_mode`].
+    Full(usize),
+}
+
+/// The error returned by [`BufStream::into_inner`] and [`BufWriter::into_inner`] when the
+/// buffered output could not be flushed, holding the stream so that nothing is lost.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct IntoInnerError<S, E> {
+    stream: S,
+    error: E,
+}
+#[cfg(feature = "alloc")]
+impl<S, E> IntoInnerError<S, E> {
+    /// Get the error that caused flushing to fail.
+    pub const fn error(&self) -> &E {
+        &self.error
+    }
+    /// Get the error that caused flushing to fail, dropping the stream.
+    pub fn into_error(self) -> E {
+        self.error
+    }
+    /// Get the stream back, with its buffers as they were when flushing failed.
+    pub fn into_stream(self) -> S {
+        self.stream
+    }
+    /// Get both the stream and the error.
+    pub fn into_parts(self) -> (S, E) {
+        (self.stream, self.error)
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S, E: core::fmt::Display> core::fmt::Display for IntoInnerError<S, E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "failed to flush the stream: {}", self.error)
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S: core::fmt::Debug, E: core::error::Error> core::error::Error for IntoInnerError<S, E> {}
+
+/// The error returned by [`BufStream::unread`] and [`BufReader::unread`] when too many bytes would
+/// be pushed back.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnreadError {
+    limit: usize,
+}
+#[cfg(feature = "alloc")]
+impl UnreadError {
+    /// The pushback limit that would have been exceeded.
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+}
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for UnreadError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "unread bytes would exceed the pushback limit of {}", self.limit)
+    }
+}
+#[cfg(feature = "alloc")]
+impl core::error::Error for UnreadError {}
+
 /// An immutable slice used for vectored IO.
 /// 
-/// On Linux this is ABI compatible with struct iovec.
+/// The layout is portable and only guaranteed to be ABI compatible with `struct iovec` on Unix
+/// platforms. Use the conversions in [`os`](crate::os) to obtain the native representation.
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct IoVec<'a> {
     ptr: *const u8,
     len: usize,
@@ -183,6 +2906,10 @@ impl<'a> IoVec<'a> {
         self.len
     }
     #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    #[inline]
     pub fn as_maybe_uninit_slice(&self) -> &'a [MaybeUninit<u8>] {
         // Safety: The requirements of a slice are required to make a `IoVec`.
         unsafe {
@@ -192,6 +2919,58 @@ impl<'a> IoVec<'a> {
             )
         }
     }
+    /// Skip the first `count` bytes of the buffer.
+    /// # Panics
+    /// Panics if `count` is greater than the length of the buffer.
+    #[inline]
+    pub fn advance(&mut self, count: usize) {
+        assert!(count <= self.len, "cannot advance past the end of the buffer");
+        // Safety: The pointer stays within the slice, or one past its end.
+        self.ptr = unsafe { self.ptr.add(count) };
+        self.len -= count;
+    }
+    /// Skip the first `count` bytes of a list of buffers, such as after a short vectored write,
+    /// removing the buffers left empty from the front of the list.
+    ///
+    /// The buffers removed are also emptied, so the list they were taken from describes the same
+    /// bytes.
+    /// ```rust
+    /// use circulate::IoVec;
+    /// let mut buffers = [IoVec::new(b"ab"), IoVec::new(b""), IoVec::new(b"cde")];
+    /// let mut rest = &mut buffers[..];
+    /// IoVec::advance_slices(&mut rest, 3);
+    /// assert_eq!(rest.len(), 1);
+    /// assert_eq!(rest[0].len(), 2);
+    /// assert!(buffers.iter().map(IoVec::len).eq([0, 0, 2]));
+    ///
+    /// let mut rest = &mut buffers[..];
+    /// IoVec::advance_slices(&mut rest, 2);
+    /// assert!(rest.is_empty());
+    /// ```
+    /// # Panics
+    /// Panics if `count` is greater than the total length of the buffers.
+    pub fn advance_slices(buffers: &mut &mut [IoVec<'a>], mut count: usize) {
+        let mut emptied = 0;
+        for buffer in buffers.iter_mut() {
+            if buffer.len > count {
+                break;
+            }
+            count -= buffer.len;
+            buffer.advance(buffer.len);
+            emptied += 1;
+        }
+        *buffers = &mut core::mem::take(buffers)[emptied..];
+        match buffers.first_mut() {
+            Some(first) => first.advance(count),
+            None => assert!(count == 0, "cannot advance past the end of the buffers"),
+        }
+    }
+}
+impl Default for IoVec<'_> {
+    /// An empty `IoVec`.
+    fn default() -> Self {
+        Self::new(&[])
+    }
 }
 impl<'a> From<&'a [u8]> for IoVec<'a> {
     fn from(value: &'a [u8]) -> Self {
@@ -203,6 +2982,16 @@ impl<'a> From<&'a [MaybeUninit<u8>]> for IoVec<'a> {
         Self::maybe_uninit(value)
     }
 }
+/// Flatten a list of exactly one [`IoVec`] to the buffer itself, handing back any other list.
+impl<'b, 'a: 'b> TryFrom<&'b [IoVec<'a>]> for IoVec<'a> {
+    type Error = &'b [IoVec<'a>];
+    fn try_from(value: &'b [IoVec<'a>]) -> Result<Self, Self::Error> {
+        match value {
+            [single] => Ok(*single),
+            _ => Err(value),
+        }
+    }
+}
 impl<'a> From<&IoVec<'a>> for &'a [MaybeUninit<u8>] {
     fn from(value: &IoVec<'a>) -> Self {
         value.as_maybe_uninit_slice()
@@ -211,7 +3000,8 @@ impl<'a> From<&IoVec<'a>> for &'a [MaybeUninit<u8>] {
 
 /// A mutable slice used for vectored IO.
 /// 
-/// On Linux this is ABI compatible with struct iovec.
+/// The layout is portable and only guaranteed to be ABI compatible with `struct iovec` on Unix
+/// platforms. Use the conversions in [`os`](crate::os) to obtain the native representation.
 #[repr(C)]
 pub struct IoVecMut<'a> {
     ptr: *mut u8,
@@ -258,6 +3048,40 @@ impl<'a> IoVecMut<'a> {
         self.len
     }
     #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Copy as much of `src` as fits in to the start of the `IoVecMut`, returning the number of
+    /// bytes copied.
+    /// ```rust
+    /// use circulate::IoVecMut;
+    /// let mut buffer = [0; 4];
+    /// assert_eq!(IoVecMut::new(&mut buffer).fill_from_slice(b"ab"), 2);
+    /// assert_eq!(&buffer, b"ab\0\0");
+    /// assert_eq!(IoVecMut::new(&mut buffer).fill_from_slice(b"wxyz"), 4);
+    /// assert_eq!(&buffer, b"wxyz");
+    /// assert_eq!(IoVecMut::new(&mut buffer).fill_from_slice(b"too long"), 4);
+    /// assert_eq!(&buffer, b"too ");
+    ///
+    /// assert!(!IoVecMut::new(&mut buffer).fill_all_from_slice(b"abc"));
+    /// assert_eq!(&buffer, b"too ");
+    /// assert!(IoVecMut::new(&mut buffer).fill_all_from_slice(b"abcd"));
+    /// assert_eq!(&buffer, b"abcd");
+    /// ```
+    #[inline]
+    pub fn fill_from_slice(&mut self, src: &[u8]) -> usize {
+        let len = self.len.min(src.len());
+        // Safety: The `IoVecMut` is valid for `self.len` writes and cannot overlap the shared `src`.
+        unsafe { self.ptr.copy_from_nonoverlapping(src.as_ptr(), len) };
+        len
+    }
+    /// Copy all of `src` in to the `IoVecMut`, or return `false` without copying if the lengths
+    /// differ.
+    #[inline]
+    pub fn fill_all_from_slice(&mut self, src: &[u8]) -> bool {
+        src.len() == self.len && self.fill_from_slice(src) == src.len()
+    }
+    #[inline]
     pub fn as_maybe_uninit_slice(&mut self) -> &'a mut [MaybeUninit<u8>] {
         // Safety: The requirements of a slice are required to make a `IoVecMut`.
         unsafe {
@@ -268,6 +3092,81 @@ impl<'a> IoVecMut<'a> {
         }
     }
 }
+/// Fills a list of [`IoVecMut`] in order, tracking how many leading bytes have been written.
+///
+/// Only the filled bytes can be read back, so a partially filled list of uninitialized buffers
+/// can be inspected safely.
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use circulate::{IoVecCursor, IoVecMut};
+///
+/// let (mut a, mut b) = ([MaybeUninit::uninit(); 3], [MaybeUninit::uninit(); 4]);
+/// let mut buffers = [IoVecMut::maybe_uninit(&mut a), IoVecMut::new(&mut []), IoVecMut::maybe_uninit(&mut b)];
+/// let mut cursor = IoVecCursor::new(&mut buffers);
+/// assert_eq!(cursor.write(b"ab"), 2);
+/// assert!(cursor.filled_slices().eq([&b"ab"[..]]));
+/// assert_eq!(cursor.write(b"cde"), 3);
+/// assert!(cursor.filled_slices().eq([&b"abc"[..], b"", b"de"]));
+/// assert_eq!(cursor.write(b"fghij"), 2);
+/// assert!(cursor.is_full());
+/// assert_eq!(cursor.write(b"ij"), 0);
+/// assert_eq!(cursor.filled(), 7);
+/// assert!(cursor.filled_slices().eq([&b"abc"[..], b"", b"defg"]));
+/// ```
+pub struct IoVecCursor<'b, 'a> {
+    buffers: &'b mut [IoVecMut<'a>],
+    /// The index of the buffer being filled.
+    index: usize,
+    /// The number of bytes filled in the buffer at `index`.
+    offset: usize,
+    filled: usize,
+}
+impl<'b, 'a> IoVecCursor<'b, 'a> {
+    pub fn new(buffers: &'b mut [IoVecMut<'a>]) -> Self {
+        let mut cursor = Self { buffers, index: 0, offset: 0, filled: 0 };
+        cursor.skip_filled();
+        cursor
+    }
+    /// Copy as much of `data` as fits after the filled bytes, returning the number of bytes copied.
+    pub fn write(&mut self, mut data: &[u8]) -> usize {
+        let start = self.filled;
+        while !data.is_empty() && !self.is_full() {
+            let buffer = &self.buffers[self.index];
+            // Safety: `offset` is within the buffer, which is valid for writes.
+            let mut unfilled = unsafe { IoVecMut::from_raw_parts(buffer.as_ptr().add(self.offset), buffer.len() - self.offset) };
+            let len = unfilled.fill_from_slice(data);
+            data = &data[len..];
+            self.offset += len;
+            self.filled += len;
+            self.skip_filled();
+        }
+        self.filled - start
+    }
+    /// Move on to the next buffer with room while the current one is full.
+    fn skip_filled(&mut self) {
+        while self.index < self.buffers.len() && self.offset == self.buffers[self.index].len() {
+            self.index += 1;
+            self.offset = 0;
+        }
+    }
+    /// Get the total number of bytes written.
+    pub const fn filled(&self) -> usize {
+        self.filled
+    }
+    /// Returns if every buffer has been filled.
+    pub const fn is_full(&self) -> bool {
+        self.index == self.buffers.len()
+    }
+    /// Get the filled part of each buffer that has been written to, in order.
+    pub fn filled_slices(&self) -> impl Iterator<Item = &[u8]> + use<'_, 'a> {
+        self.buffers.iter().take(self.index + 1).enumerate().map(|(index, buffer)| {
+            let len = if index == self.index { self.offset } else { buffer.len() };
+            // Safety: The leading `len` bytes of the buffer have been written.
+            unsafe { core::slice::from_raw_parts(buffer.as_ptr(), len) }
+        })
+    }
+}
+
 impl<'a> From<&'a mut [u8]> for IoVecMut<'a> {
     fn from(value: &'a mut [u8]) -> Self {
         Self::new(value)
@@ -278,6 +3177,31 @@ impl<'a> From<&'a mut [MaybeUninit<u8>]> for IoVecMut<'a> {
         Self::maybe_uninit(value)
     }
 }
+/// Flatten a list of exactly one [`IoVecMut`] to the buffer itself, handing back any other list.
+/// ```rust
+/// use circulate::IoVecMut;
+/// let mut buffer = [0; 4];
+/// let mut single = [IoVecMut::new(&mut buffer)];
+/// let Ok(mut flat) = IoVecMut::try_from(&mut single[..]) else { unreachable!() };
+/// assert_eq!(flat.fill_from_slice(b"abcd"), 4);
+/// assert_eq!(&buffer, b"abcd");
+///
+/// let (mut a, mut b) = ([0; 2], [0; 2]);
+/// let mut pair = [IoVecMut::new(&mut a), IoVecMut::new(&mut b)];
+/// let Err(pair) = IoVecMut::try_from(&mut pair[..]) else { unreachable!() };
+/// assert_eq!(pair.len(), 2);
+/// let empty: &mut [IoVecMut] = &mut [];
+/// assert!(IoVecMut::try_from(empty).is_err());
+/// ```
+impl<'b, 'a: 'b> TryFrom<&'b mut [IoVecMut<'a>]> for IoVecMut<'b> {
+    type Error = &'b mut [IoVecMut<'a>];
+    fn try_from(value: &'b mut [IoVecMut<'a>]) -> Result<Self, Self::Error> {
+        match value {
+            [single] => Ok(IoVecMut { ptr: single.ptr, len: single.len, _marker: PhantomData }),
+            _ => Err(value),
+        }
+    }
+}
 impl<'a> From<&mut IoVecMut<'a>> for &'a mut [MaybeUninit<u8>] {
     fn from(value: &mut IoVecMut<'a>) -> Self {
         value.as_maybe_uninit_slice()
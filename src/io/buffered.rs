@@ -0,0 +1,603 @@
+extern crate alloc;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use alloc::boxed::Box;
+use crate::RingBuffer;
+use super::{FlushReason, IntoInnerError, IoEvent, IoVecCursor, IoVecMut, Observer, Read, UnreadError, Write};
+
+/// The capacity of the buffer of a [`BufReader`] or [`BufWriter`] made with `new`.
+pub(super) const DEFAULT_CAPACITY: usize = 8 * 1024 - 1;
+
+/// Buffers the input of a reader, for streams that are only read such as a receive-only UART.
+/// [`BufStream`](super::BufStream) buffers both directions of a stream.
+///
+/// Each read from the inner reader fills as much of the buffer as it returns, and later reads are
+/// served from the buffer until it is empty. Reads at least as large as the buffer go straight to
+/// the inner reader while nothing is buffered, as do all reads from an inner reader that is
+/// [already buffered](Read::is_buffered).
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use circulate::{BufReader, Read};
+///
+/// let mut reader = BufReader::with_capacity(&b"hello world"[..], 8);
+/// let mut word = [MaybeUninit::uninit(); 5];
+/// assert_eq!(reader.read(&mut word).unwrap(), 5);
+/// // The rest of the first read from the slice stays buffered.
+/// let (lhs, rhs) = reader.buffer();
+/// assert_eq!([lhs, rhs].concat(), b" world");
+/// reader.consume(1);
+///
+/// let mut rest = String::new();
+/// reader.read_to_string(&mut rest).unwrap();
+/// assert_eq!(rest, "world");
+/// ```
+pub struct BufReader<R: Read> {
+    inner: R,
+    buffer: RingBuffer<u8>,
+    /// The number of unread bytes that have not been read again.
+    pushback: usize,
+    pushback_limit: usize,
+    observer: Option<Observer>,
+}
+impl<R: Read> BufReader<R> {
+    /// Buffer `inner` with a buffer of 8 KiB.
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(inner, DEFAULT_CAPACITY)
+    }
+    /// Buffer `inner` with a buffer that holds at least `capacity` bytes, rounded up as by
+    /// [`RingBuffer::capacity_for`]. The buffer does not grow.
+    ///
+    /// A `capacity` of 0 is raised to 1, as [`BufReader::fill_buf`] could otherwise never return
+    /// anything but the empty slices that mean the inner reader has ended.
+    pub fn with_capacity(inner: R, capacity: usize) -> Self {
+        let mut buffer = RingBuffer::with_capacity(capacity.max(1));
+        buffer.set_max_capacity(buffer.capacity());
+        Self { inner, buffer, pushback: 0, pushback_limit: usize::MAX, observer: None }
+    }
+    /// Get the buffered bytes without consuming them, in two slices as they wrap around the
+    /// buffer. Nothing is read from the inner reader.
+    pub fn buffer(&self) -> (&[u8], &[u8]) {
+        self.buffer.as_slices()
+    }
+    /// Read from the inner reader if nothing is buffered, and then get the buffered bytes without
+    /// consuming them. Both slices are empty once the inner reader ends.
+    /// ```rust
+    /// use circulate::BufReader;
+    ///
+    /// let mut reader = BufReader::new(&b"ab"[..]);
+    /// assert_eq!(reader.buffer(), (&[][..], &[][..]));
+    /// assert_eq!(reader.fill_buf().unwrap(), (&b"ab"[..], &[][..]));
+    /// reader.consume(2);
+    /// assert_eq!(reader.fill_buf().unwrap(), (&[][..], &[][..]));
+    /// ```
+    pub fn fill_buf(&mut self) -> Result<(&[u8], &[u8]), R::Error> {
+        if self.buffer.is_empty() {
+            // Start from the beginning so the reader reads in to a single contiguous region.
+            self.buffer.clear();
+            let requested = self.buffer.capacity().saturating_sub(1);
+            let got = trace_io!("refill", requested, self.buffer.fill_from(&mut self.inner))?;
+            self.emit(IoEvent::Refill { requested, got });
+        }
+        Ok(self.buffer.as_slices())
+    }
+    /// Discard the next `count` buffered bytes.
+    /// # Panics
+    /// Panics if fewer than `count` bytes are buffered.
+    #[track_caller]
+    pub fn consume(&mut self, count: usize) {
+        let buffered = self.buffer.len();
+        assert!(count <= buffered, "cannot consume {count} bytes when only {buffered} are buffered");
+        // Safety: At least `count` bytes are buffered.
+        unsafe { self.buffer.advance_read_unchecked(count) };
+        self.pushback = self.pushback.saturating_sub(count);
+    }
+    /// Push `bytes` back to be read again before the buffered bytes, such as the lookahead of a
+    /// tokenizer. See [`BufStream::unread`](super::BufStream::unread).
+    ///
+    /// The bytes are written to the space in front of the buffered bytes. The buffer does not
+    /// grow, so this fails without modifying it if there is not enough space, as well as if the
+    /// bytes pushed back and not yet read again would exceed the
+    /// [pushback limit](BufReader::set_pushback_limit).
+    /// ```rust
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{BufReader, Read};
+    ///
+    /// let mut reader = BufReader::with_capacity(&b"if x"[..], 8);
+    /// reader.set_pushback_limit(2);
+    /// let mut word = [MaybeUninit::uninit(); 2];
+    /// assert_eq!(reader.read(&mut word).unwrap(), 2);
+    /// reader.unread(b"if").unwrap();
+    /// assert!(reader.unread(b" ").is_err());
+    ///
+    /// let mut text = String::new();
+    /// reader.read_to_string(&mut text).unwrap();
+    /// assert_eq!(text, "if x");
+    /// ```
+    pub fn unread(&mut self, bytes: &[u8]) -> Result<(), UnreadError> {
+        let pushback = self.pushback + bytes.len();
+        if pushback > self.pushback_limit || self.buffer.reserve(bytes.len()).is_err() {
+            return Err(UnreadError { limit: self.pushback_limit });
+        }
+        let (lhs, rhs) = self.buffer.spare_capacity_front_mut();
+        // The bytes fill the end of the front spare capacity, directly before the read cursor.
+        let skip = lhs.len() + rhs.len() - bytes.len();
+        for (slot, byte) in lhs.iter_mut().chain(rhs).skip(skip).zip(bytes) {
+            slot.write(*byte);
+        }
+        // Safety: The `bytes.len()` slots before the read cursor were just initialised.
+        unsafe { self.buffer.set_read_cursor_back(bytes.len()) };
+        self.pushback = pushback;
+        Ok(())
+    }
+    /// Set the maximum number of bytes that may be pushed back with [`BufReader::unread`] before
+    /// they are read again. By default there is no limit other than the space in the buffer.
+    pub fn set_pushback_limit(&mut self, limit: usize) {
+        self.pushback_limit = limit;
+    }
+    /// Get the maximum number of bytes that may be pushed back with [`BufReader::unread`].
+    pub const fn pushback_limit(&self) -> usize {
+        self.pushback_limit
+    }
+    /// Set if buffered bytes are overwritten with zeros as soon as they are read or consumed.
+    /// See [`BufStream::set_scrub_consumed`](super::BufStream::set_scrub_consumed).
+    pub fn set_scrub_consumed(&mut self, scrub: bool) {
+        self.buffer.set_scrub_consumed(scrub);
+    }
+    /// Get if buffered bytes are overwritten with zeros as soon as they are read or consumed.
+    pub const fn scrub_consumed(&self) -> bool {
+        self.buffer.scrub_consumed()
+    }
+    /// Set a callback that is told about each read from the inner reader, as
+    /// [`IoEvent::Refill`]. See [`BufStream::set_io_observer`](super::BufStream::set_io_observer).
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    /// use circulate::{BufReader, IoEvent, Read};
+    ///
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let mut reader = BufReader::with_capacity(&b"hello"[..], 15);
+    /// let log = events.clone();
+    /// reader.set_io_observer(move |event| log.lock().unwrap().push(event));
+    ///
+    /// let mut text = String::new();
+    /// reader.read_to_string(&mut text).unwrap();
+    /// assert_eq!(*events.lock().unwrap(), [
+    ///     IoEvent::Refill { requested: 15, got: 5 },
+    ///     IoEvent::Refill { requested: 15, got: 0 },
+    /// ]);
+    /// ```
+    pub fn set_io_observer<F: FnMut(IoEvent) + Send + Sync + 'static>(&mut self, observer: F) {
+        self.observer = Some(Box::new(observer));
+    }
+    /// Remove the callback set with [`BufReader::set_io_observer`].
+    pub fn clear_io_observer(&mut self) {
+        self.observer = None;
+    }
+    fn emit(&mut self, event: IoEvent) {
+        if let Some(observer) = &mut self.observer {
+            observer(event);
+        }
+    }
+    /// Get a reference to the inner reader.
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Get a mutable reference to the inner reader.
+    ///
+    /// Reading from it directly skips the bytes that are buffered.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Get the inner reader, discarding any buffered bytes.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Get the number of buffered bytes.
+    #[cfg(not(feature = "no_std"))]
+    pub(super) fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+    /// Returns if a read of `len` bytes should go straight to the inner reader.
+    fn reads_through(&self, len: usize) -> bool {
+        self.buffer.is_empty() && (self.inner.is_buffered() || len >= self.buffer.capacity().saturating_sub(1))
+    }
+    /// Copy buffered bytes to the unfilled part of the buffers behind `cursor`, reading from the
+    /// inner reader first if nothing is buffered.
+    fn read_buffered(&mut self, cursor: &mut IoVecCursor) -> Result<usize, R::Error> {
+        let (lhs, rhs) = self.fill_buf()?;
+        let copied = match cursor.write(lhs) {
+            len if len == lhs.len() => len + cursor.write(rhs),
+            len => len,
+        };
+        // Safety: No more than the buffered bytes were copied.
+        unsafe { self.buffer.advance_read_unchecked(copied) };
+        self.pushback = self.pushback.saturating_sub(copied);
+        Ok(copied)
+    }
+}
+impl<R: Read> Read for BufReader<R> {
+    type Error = R::Error;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        if self.reads_through(buffer.len()) {
+            let got = trace_io!("bypass_read", buffer.len(), self.inner.read(buffer))?;
+            self.emit(IoEvent::Refill { requested: buffer.len(), got });
+            return Ok(got);
+        }
+        self.read_buffered(&mut IoVecCursor::new(&mut [IoVecMut::maybe_uninit(buffer)]))
+    }
+    fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        let requested = buffers.iter().map(IoVecMut::len).sum();
+        if self.reads_through(requested) {
+            let got = trace_io!("bypass_read", requested, self.inner.read_vectored(buffers))?;
+            self.emit(IoEvent::Refill { requested, got });
+            return Ok(got);
+        }
+        self.read_buffered(&mut IoVecCursor::new(buffers))
+    }
+    fn is_buffered(&self) -> bool {
+        true
+    }
+}
+
+/// Buffers the output of a writer, for streams that are only written.
+/// [`BufStream`](super::BufStream) buffers both directions of a stream.
+///
+/// Writes are buffered until the buffer is full and then written to the inner writer, retrying
+/// short writes. Writes at least as large as the buffer go straight to the inner writer once the
+/// buffered bytes are written.
+///
+/// Dropping a `BufWriter` flushes it, ignoring any error, but not while the thread is panicking.
+/// Use [`BufWriter::into_inner`] to see whether flushing failed.
+/// ```rust
+/// use circulate::{BufWriter, Write};
+///
+/// let mut output = Vec::new();
+/// let mut writer = BufWriter::with_capacity(&mut output, 16);
+/// writer.write(b"hello ").unwrap();
+/// writer.write(b"world").unwrap();
+/// assert_eq!(writer.buffer().len(), 11);
+/// drop(writer);
+/// assert_eq!(output, b"hello world");
+/// ```
+pub struct BufWriter<W: Write> {
+    inner: W,
+    buffer: RingBuffer<u8>,
+    /// The total number of bytes written to the inner writer.
+    flushed: u64,
+    observer: Option<Observer>,
+    /// When the oldest byte still waiting to be written was buffered.
+    #[cfg(not(feature = "no_std"))]
+    oldest_unflushed: Option<::std::time::Instant>,
+}
+impl<W: Write> BufWriter<W> {
+    /// Buffer `inner` with a buffer of 8 KiB.
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(inner, DEFAULT_CAPACITY)
+    }
+    /// Buffer `inner` with a buffer that holds at least `capacity` bytes, rounded up as by
+    /// [`RingBuffer::capacity_for`]. The buffer does not grow.
+    pub fn with_capacity(inner: W, capacity: usize) -> Self {
+        let mut buffer = RingBuffer::with_capacity(capacity);
+        buffer.set_max_capacity(buffer.capacity());
+        Self {
+            inner,
+            buffer,
+            flushed: 0,
+            observer: None,
+            #[cfg(not(feature = "no_std"))]
+            oldest_unflushed: None,
+        }
+    }
+    /// Get the bytes waiting to be written.
+    pub const fn buffer(&self) -> &RingBuffer<u8> {
+        &self.buffer
+    }
+    /// Drop the bytes waiting to be written, returning how many there were, such as after the
+    /// inner writer has failed for good. See [`BufStream::discard_output`](super::BufStream::discard_output).
+    ///
+    /// A failed flush leaves the buffer as it was, less the bytes that were written, so that
+    /// flushing can be retried. Discarding is the other way to carry on.
+    /// ```rust
+    /// # use std::io;
+    /// # /// Accepts `limit` bytes and then fails.
+    /// # struct Connection { sent: Vec<u8>, limit: usize }
+    /// # impl io::Write for Connection {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    /// #         let len = buf.len().min(self.limit - self.sent.len());
+    /// #         if len == 0 { return Err(io::ErrorKind::BrokenPipe.into()) }
+    /// #         self.sent.extend_from_slice(&buf[..len]);
+    /// #         Ok(len)
+    /// #     }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// use circulate::{BufWriter, FromStd, Write};
+    ///
+    /// let mut writer = BufWriter::new(FromStd(Connection { sent: Vec::new(), limit: 4 }));
+    /// writer.write(b"request").unwrap();
+    /// assert_eq!(writer.flush().unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    /// assert_eq!(writer.flushed_bytes(), 4);
+    /// assert_eq!(writer.discard_buffer(), 3);
+    ///
+    /// writer.get_mut().0 = Connection { sent: Vec::new(), limit: 64 };
+    /// writer.write(b"new").unwrap();
+    /// writer.flush().unwrap();
+    /// assert_eq!(writer.get_ref().0.sent, b"new");
+    /// assert_eq!(writer.flushed_bytes(), 7);
+    /// ```
+    pub fn discard_buffer(&mut self) -> usize {
+        let discarded = self.buffer.len();
+        self.buffer.clear();
+        self.buffer_drained();
+        discarded
+    }
+    /// Get the total number of bytes written to the inner writer, not counting bytes that are
+    /// buffered or were discarded.
+    pub const fn flushed_bytes(&self) -> u64 {
+        self.flushed
+    }
+    /// Get a reference to the inner writer.
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    /// Get a mutable reference to the inner writer.
+    ///
+    /// Writing to it directly puts the bytes ahead of those that are buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+    /// Flush the buffered bytes and get the inner writer.
+    ///
+    /// If flushing fails the writer is handed back in the error with its buffer intact, so that
+    /// flushing can be retried. Bytes the inner writer stops accepting without failing are
+    /// dropped along with the buffer, so use [`BufWriter::into_parts`] to keep them.
+    /// ```rust
+    /// # #[cfg(feature = "test-util")] {
+    /// use std::io::ErrorKind;
+    /// use circulate::{test_util::{MemoryStream, ScriptedStream, Step}, BufWriter, Write};
+    ///
+    /// // The first write fails.
+    /// let mut writer = BufWriter::new(ScriptedStream::new(MemoryStream::default(), [Step::Fail(ErrorKind::Interrupted)]));
+    /// writer.write(b"kept for retry").unwrap();
+    /// let Err(error) = writer.into_inner() else { panic!("the first write fails") };
+    /// assert_eq!(error.error().kind(), ErrorKind::Interrupted);
+    /// let writer = error.into_stream();
+    /// assert_eq!(writer.buffer().len(), 14);
+    /// let flaky = writer.into_inner().map_err(|error| error.into_error()).unwrap();
+    /// assert_eq!(flaky.get_ref().output(), b"kept for retry");
+    /// # }
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn into_inner(mut self) -> Result<W, IntoInnerError<BufWriter<W>, W::Error>> {
+        match self.flush() {
+            Ok(()) => Ok(self.into_parts().0),
+            Err(error) => Err(IntoInnerError { stream: self, error }),
+        }
+    }
+    /// Get the inner writer and the bytes that have not been written to it, without flushing.
+    pub fn into_parts(self) -> (W, RingBuffer<u8>) {
+        let this = ManuallyDrop::new(self);
+        // Safety: `this` is never dropped, so each field is moved out exactly once.
+        unsafe { (core::ptr::read(&this.inner), core::ptr::read(&this.buffer)) }
+    }
+
+    /// Set a callback that is told about each write to the inner writer, such as to count how
+    /// often the buffer fills up. See [`BufStream::set_io_observer`](super::BufStream::set_io_observer).
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    /// use circulate::{BufWriter, FlushReason, IoEvent, Write};
+    ///
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let mut writer = BufWriter::with_capacity(Vec::new(), 15);
+    /// let log = events.clone();
+    /// writer.set_io_observer(move |event| log.lock().unwrap().push(event));
+    /// // The writer can still be shared between threads.
+    /// fn shareable<T: Send + Sync>(_: &T) {}
+    /// shareable(&writer);
+    ///
+    /// writer.write(b"123").unwrap();
+    /// writer.write(b"456789abcdefg").unwrap();
+    /// writer.write(b"large enough to skip the buffer").unwrap();
+    /// writer.write(b"!").unwrap();
+    /// writer.flush().unwrap();
+    ///
+    /// let flush = |attempted, reason| IoEvent::Flush { attempted, written: attempted, reason };
+    /// assert_eq!(*events.lock().unwrap(), [
+    ///     flush(3, FlushReason::Full),
+    ///     flush(13, FlushReason::Full),
+    ///     IoEvent::Bypass { bytes: 31 },
+    ///     flush(1, FlushReason::Explicit),
+    /// ]);
+    /// ```
+    pub fn set_io_observer<F: FnMut(IoEvent) + Send + Sync + 'static>(&mut self, observer: F) {
+        self.observer = Some(Box::new(observer));
+    }
+    /// Remove the callback set with [`BufWriter::set_io_observer`].
+    pub fn clear_io_observer(&mut self) {
+        self.observer = None;
+    }
+    fn emit(&mut self, event: IoEvent) {
+        if let Some(observer) = &mut self.observer {
+            observer(event);
+        }
+    }
+
+    /// Write the buffered bytes to the inner writer until they are all written or it stops
+    /// accepting bytes.
+    fn write_buffered(&mut self, reason: FlushReason) -> Result<(), W::Error> {
+        while !self.buffer.is_empty() {
+            let attempted = self.buffer.len();
+            let written = trace_io!("flush", attempted, self.buffer.drain_to(&mut self.inner))?;
+            self.flushed += written as u64;
+            self.emit(IoEvent::Flush { attempted, written, reason });
+            if written == 0 {
+                break;
+            }
+        }
+        self.buffer_drained();
+        Ok(())
+    }
+    /// Write the buffered bytes for `reason` and then flush the inner writer.
+    fn flush_for(&mut self, reason: FlushReason) -> Result<(), W::Error> {
+        self.write_buffered(reason)?;
+        self.inner.flush()
+    }
+    /// Note that bytes were buffered, timing them if no others are waiting to be written.
+    #[inline]
+    fn buffer_filled(&mut self) {
+        #[cfg(not(feature = "no_std"))]
+        if self.oldest_unflushed.is_none() && !self.buffer.is_empty() {
+            self.oldest_unflushed = Some(::std::time::Instant::now());
+        }
+    }
+    /// Note that bytes were written or discarded, forgetting their time once none are left.
+    fn buffer_drained(&mut self) {
+        #[cfg(not(feature = "no_std"))]
+        if self.buffer.is_empty() {
+            self.oldest_unflushed = None;
+        }
+    }
+    /// The rest of [`Write::write`], for writes that do not fit in the buffer as it is.
+    #[cold]
+    #[inline(never)]
+    fn write_outlined(&mut self, slice: &[u8]) -> Result<usize, W::Error> {
+        let usable = self.buffer.capacity().saturating_sub(1);
+        if self.buffer.len() + slice.len() > usable {
+            self.write_buffered(FlushReason::Full)?;
+        }
+        if self.buffer.is_empty() && slice.len() >= usable {
+            let bytes = trace_io!("bypass_write", slice.len(), self.inner.write(slice))?;
+            self.flushed += bytes as u64;
+            self.emit(IoEvent::Bypass { bytes });
+            return Ok(bytes);
+        }
+        let written = self.buffer.with_spare_capacity(|spare| spare.extend_from_slice(slice));
+        self.buffer_filled();
+        Ok(written)
+    }
+}
+#[cfg(not(feature = "no_std"))]
+impl<W: Write> BufWriter<W> {
+    /// Get how long ago the oldest byte still waiting to be written was buffered, or [`None`] if
+    /// there is none. See [`BufStream::oldest_unflushed_age`](super::BufStream::oldest_unflushed_age).
+    pub fn oldest_unflushed_age(&self) -> Option<::std::time::Duration> {
+        self.oldest_unflushed.map(|instant| instant.elapsed())
+    }
+    /// Flush the buffered bytes if the oldest of them was buffered at least `age` ago, returning
+    /// whether they were flushed. See [`BufStream::flush_if_older_than`](super::BufStream::flush_if_older_than).
+    ///
+    /// Bytes left after a partial flush keep the time of the oldest byte, writes that go straight
+    /// to the inner writer do not start the clock, and discarding the buffer stops it.
+    /// ```rust
+    /// use std::{thread, time::Duration};
+    /// use circulate::{BufWriter, Write};
+    ///
+    /// let mut writer = BufWriter::new(Vec::new());
+    /// let age = Duration::from_millis(2);
+    /// assert!(!writer.flush_if_older_than(age).unwrap());
+    ///
+    /// writer.write(b"ls").unwrap();
+    /// writer.write(b" -l\n").unwrap();
+    /// while !writer.flush_if_older_than(age).unwrap() {
+    ///     thread::sleep(Duration::from_millis(1));
+    /// }
+    /// assert_eq!(writer.get_ref(), b"ls -l\n");
+    /// assert_eq!(writer.oldest_unflushed_age(), None);
+    /// ```
+    pub fn flush_if_older_than(&mut self, age: ::std::time::Duration) -> Result<bool, W::Error> {
+        match self.oldest_unflushed_age() {
+            Some(waited) if waited >= age => {
+                self.flush_for(FlushReason::Deadline)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+impl<W: Write> Write for BufWriter<W> {
+    type Error = W::Error;
+    /// Buffer the bytes of `slice`, writing the buffered bytes to the inner writer first if they
+    /// do not all fit.
+    ///
+    /// Writes that fit in the buffer without wrapping take an inlined fast path, so many small
+    /// writes cost little more than copying the bytes.
+    #[inline]
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        if self.buffer.try_extend_contiguous(slice) {
+            self.buffer_filled();
+            return Ok(slice.len());
+        }
+        self.write_outlined(slice)
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.flush_for(FlushReason::Explicit)
+    }
+}
+impl<W: Write> Drop for BufWriter<W> {
+    fn drop(&mut self) {
+        // Note: Flushing could panic again while unwinding, aborting the process.
+        #[cfg(not(feature = "no_std"))]
+        if ::std::thread::panicking() {
+            return;
+        }
+        let _ = self.flush_for(FlushReason::Drop);
+    }
+}
+
+/// Shows the number of buffered bytes and the usable capacity of the buffer, but never the
+/// bytes themselves.
+/// ```rust
+/// use circulate::{BufReader, Read};
+///
+/// let mut reader = BufReader::with_capacity(&b"secret"[..], 15);
+/// reader.fill_buf().unwrap();
+/// assert_eq!(format!("{reader:?}"), "BufReader { inner: [], buffer: 6/15 }");
+/// assert!(reader.as_ref().is_empty());
+/// ```
+impl<R: Read + core::fmt::Debug> core::fmt::Debug for BufReader<R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("BufReader")
+            .field("inner", &self.inner)
+            .field("buffer", &format_args!("{}/{}", self.buffer.len(), self.buffer.capacity().saturating_sub(1)))
+            .finish()
+    }
+}
+/// Shows the number of buffered bytes and the usable capacity of the buffer, but never the
+/// bytes themselves.
+/// ```rust
+/// use circulate::{BufWriter, Write};
+///
+/// let mut writer = BufWriter::with_capacity(Vec::new(), 15);
+/// writer.write(b"secret").unwrap();
+/// assert_eq!(format!("{writer:?}"), "BufWriter { inner: [], buffer: 6/15 }");
+/// writer.as_mut().push(b'!');
+/// assert_eq!(writer.as_ref(), b"!");
+/// ```
+impl<W: Write + core::fmt::Debug> core::fmt::Debug for BufWriter<W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("BufWriter")
+            .field("inner", &self.inner)
+            .field("buffer", &format_args!("{}/{}", self.buffer.len(), self.buffer.capacity().saturating_sub(1)))
+            .finish()
+    }
+}
+impl<R: Read> AsRef<R> for BufReader<R> {
+    fn as_ref(&self) -> &R {
+        &self.inner
+    }
+}
+impl<R: Read> AsMut<R> for BufReader<R> {
+    /// Reading from the inner reader directly skips the bytes that are buffered.
+    fn as_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+impl<W: Write> AsRef<W> for BufWriter<W> {
+    fn as_ref(&self) -> &W {
+        &self.inner
+    }
+}
+impl<W: Write> AsMut<W> for BufWriter<W> {
+    /// Writing to the inner writer directly puts the bytes ahead of those that are buffered.
+    fn as_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
@@ -0,0 +1,117 @@
+use super::{BufStream, Eof, Read};
+
+impl<S: Read> BufStream<S> {
+    /// Read the next character of UTF-8 text, or return [`None`] at the end of the stream.
+    ///
+    /// Only as many bytes as the character is long are consumed, reading from the stream as
+    /// needed. Invalid bytes are consumed so that reading can resume after the error. If the
+    /// stream ends part way through a character, its bytes stay buffered.
+    /// ```rust
+    /// # #[cfg(feature = "test-util")] {
+    /// use circulate::{test_util::MemoryStream, BufStream, CharReadError, Eof};
+    ///
+    /// let trickle = |bytes: &[u8]| MemoryStream::with_max_read(bytes.to_vec(), 1);
+    ///
+    /// let mut stream = BufStream::with_capacity(trickle("aé€😀".as_bytes()), 4);
+    /// let text: Result<String, _> = stream.chars().collect();
+    /// assert_eq!(text.unwrap(), "aé€😀");
+    /// assert!(stream.read_char().unwrap().is_none());
+    ///
+    /// let mut stream = BufStream::new(trickle(b"\x80a\xe2A\xe2\x82"));
+    /// assert!(matches!(stream.read_char(), Err(CharReadError::InvalidUtf8)));
+    /// assert_eq!(stream.read_char().unwrap(), Some('a'));
+    /// assert!(matches!(stream.read_char(), Err(CharReadError::InvalidUtf8)));
+    /// assert_eq!(stream.read_char().unwrap(), Some('A'));
+    /// assert!(matches!(stream.read_char(), Err(CharReadError::UnexpectedEof(Eof { needed: 3, got: 2 }))));
+    /// assert_eq!(stream.buffered_input(), (&b"\xe2\x82"[..], &b""[..]));
+    /// stream.consume(2);
+    /// assert!(stream.read_char().unwrap().is_none());
+    /// # }
+    /// ```
+    pub fn read_char(&mut self) -> Result<Option<char>, CharReadError<<S as Read>::Error>> {
+        let (bytes, read) = self.fill_peek(1)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let first = bytes[0];
+        let len = match first.leading_ones() {
+            0 => 1,
+            len @ 2..=4 => len as usize,
+            _ => {
+                self.consume(1);
+                return Err(CharReadError::InvalidUtf8);
+            }
+        };
+        let (bytes, read) = self.fill_peek(len)?;
+        match core::str::from_utf8(&bytes[..read]) {
+            Ok(text) if read == len => {
+                self.consume(len);
+                Ok(text.chars().next())
+            }
+            Err(error) if error.error_len().is_some() => {
+                self.consume(error.error_len().unwrap_or(1));
+                Err(CharReadError::InvalidUtf8)
+            }
+            // The bytes are valid so far, but the stream ended before the character did.
+            _ => Err(CharReadError::UnexpectedEof(Eof { needed: len, got: read })),
+        }
+    }
+    /// Get an iterator over the characters of UTF-8 text read from the stream.
+    /// See [`BufStream::read_char`].
+    ///
+    /// The iterator ends after reporting that the stream ended part way through a character,
+    /// rather than report it again for the bytes left buffered.
+    pub fn chars(&mut self) -> Chars<'_, S> {
+        Chars { stream: self, ended: false }
+    }
+    /// Buffer up to `count` bytes of input, which is at most 4, and copy them out without
+    /// consuming them. Returns the bytes and how many of them were buffered.
+    fn fill_peek(&mut self, count: usize) -> Result<([u8; 4], usize), CharReadError<<S as Read>::Error>> {
+        self.fill_at_least(count).map_err(CharReadError::Read)?;
+        let mut bytes = [0; 4];
+        let mut read = 0;
+        for (slot, byte) in bytes[..count].iter_mut().zip(self.input.iter()) {
+            *slot = *byte;
+            read += 1;
+        }
+        Ok((bytes, read))
+    }
+}
+
+/// An iterator over the characters read from a [`BufStream`]. See [`BufStream::chars`].
+pub struct Chars<'a, S> {
+    stream: &'a mut BufStream<S>,
+    ended: bool,
+}
+impl<S: Read> Iterator for Chars<'_, S> {
+    type Item = Result<char, CharReadError<<S as Read>::Error>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ended {
+            return None;
+        }
+        let next = self.stream.read_char().transpose();
+        self.ended = matches!(next, Some(Err(CharReadError::UnexpectedEof(_))));
+        next
+    }
+}
+
+/// The error returned by [`BufStream::read_char`].
+#[derive(Debug)]
+pub enum CharReadError<E> {
+    /// The stream failed.
+    Read(E),
+    /// The next bytes were not valid UTF-8.
+    InvalidUtf8,
+    /// The stream ended part way through a character, whose bytes stay buffered.
+    UnexpectedEof(Eof),
+}
+impl<E: core::fmt::Display> core::fmt::Display for CharReadError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Read(error) => error.fmt(f),
+            Self::InvalidUtf8 => f.write_str("stream did not contain valid UTF-8"),
+            Self::UnexpectedEof(eof) => write!(f, "{eof} of a character"),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for CharReadError<E> {}
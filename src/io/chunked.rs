@@ -0,0 +1,327 @@
+extern crate alloc;
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use crate::RingBuffer;
+use super::{append, Read, Write};
+
+/// Decodes the HTTP/1.1 chunked transfer encoding, reading only the payload of each chunk.
+///
+/// Framing lines are buffered, but chunk payloads are passed through as they arrive, so a chunk
+/// never needs to be held in memory as a whole. Chunk extensions are ignored and trailers are
+/// collected for [`trailers`](Self::trailers). Once the last chunk has been read, reads return
+/// no more bytes, leaving anything after the trailers unread in the buffer.
+/// ```rust
+/// # #[cfg(feature = "test-util")] {
+/// use circulate::{test_util::MemoryStream, ChunkedError, ChunkedReader, Read, RingBuffer};
+///
+/// let trickle = |bytes: &[u8]| MemoryStream::with_max_read(bytes.to_vec(), 3);
+///
+/// let body = b"5\r\nhello\r\n7;name=value\r\n, world\r\n0\r\nExpires: never\r\n\r\n";
+/// let mut reader = ChunkedReader::new(trickle(body));
+/// let mut buffer = RingBuffer::new();
+/// reader.read_to_end(&mut buffer).unwrap();
+/// assert!(buffer.iter().eq(b"hello, world"));
+/// assert_eq!(reader.trailers(), b"Expires: never\r\n");
+/// assert!(reader.is_done());
+///
+/// // Each chunk must be followed by a line break.
+/// let mut reader = ChunkedReader::new(trickle(b"3\r\nabcd\r\n0\r\n\r\n"));
+/// assert!(matches!(reader.read_to_end(&mut RingBuffer::new()), Err(ChunkedError::Malformed)));
+///
+/// // Framing lines must fit in the buffer.
+/// let mut reader = ChunkedReader::with_capacity(trickle(b"000000000000000005\r\nhello\r\n"), 15);
+/// assert!(matches!(reader.read_to_end(&mut RingBuffer::new()), Err(ChunkedError::LineTooLong)));
+///
+/// let mut reader = ChunkedReader::new(trickle(b"a\r\nhello"));
+/// assert!(matches!(reader.read_to_end(&mut RingBuffer::new()), Err(ChunkedError::UnexpectedEof)));
+/// # }
+/// ```
+pub struct ChunkedReader<R> {
+    inner: R,
+    /// Bytes read from `inner` that have not been decoded.
+    buffer: RingBuffer<u8>,
+    state: DecodeState,
+    trailers: Vec<u8>,
+}
+#[derive(Debug, Clone, Copy)]
+enum DecodeState {
+    /// Expecting the size line of the next chunk.
+    Size,
+    /// Within the payload of a chunk, with the number of bytes remaining.
+    Payload(u64),
+    /// Expecting the line break after the payload of a chunk.
+    PayloadEnd,
+    /// Expecting a trailer or the empty line ending the body.
+    Trailers,
+    Done,
+}
+impl<R: Read> ChunkedReader<R> {
+    /// Create a reader that decodes the chunked body read from `inner`, buffering 4095 bytes.
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(inner, 4095)
+    }
+    /// Create a reader that decodes the chunked body read from `inner`, buffering at least
+    /// `capacity` bytes, rounded up as by [`RingBuffer::capacity_for`].
+    ///
+    /// Framing lines and the trailers must be shorter than the capacity.
+    pub fn with_capacity(inner: R, capacity: usize) -> Self {
+        let mut buffer = RingBuffer::with_capacity(capacity);
+        buffer.set_max_capacity(buffer.capacity());
+        Self { inner, buffer, state: DecodeState::Size, trailers: Vec::new() }
+    }
+    /// Get the trailers read after the last chunk, each ending in a line break.
+    pub fn trailers(&self) -> &[u8] {
+        &self.trailers
+    }
+    /// Returns if the last chunk and the trailers have been read.
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, DecodeState::Done)
+    }
+    /// Get the underlying reader and the bytes buffered from it but not decoded.
+    pub fn into_inner(self) -> (R, RingBuffer<u8>) {
+        (self.inner, self.buffer)
+    }
+
+    /// Buffer the next line, returning its length including the line break.
+    fn fill_line(&mut self) -> Result<usize, ChunkedError<R::Error>> {
+        let mut searched = 0;
+        loop {
+            if let Some(position) = self.buffer.iter().skip(searched).position(|&byte| byte == b'\n') {
+                return Ok(searched + position + 1);
+            }
+            searched = self.buffer.len();
+            if self.buffer.is_full() {
+                return Err(ChunkedError::LineTooLong);
+            }
+            if self.buffer.fill_from(&mut self.inner).map_err(ChunkedError::Stream)? == 0 {
+                return Err(ChunkedError::UnexpectedEof);
+            }
+        }
+    }
+    fn consume_line(&mut self, len: usize) {
+        // Safety: `len` is no more than the buffered bytes, as returned by `fill_line`.
+        unsafe {
+            self.buffer.advance_read_unchecked(len);
+        }
+    }
+    /// Decode framing lines until within a payload or at the end of the body.
+    fn decode_framing(&mut self) -> Result<(), ChunkedError<R::Error>> {
+        loop {
+            match self.state {
+                DecodeState::Payload(_) | DecodeState::Done => return Ok(()),
+                DecodeState::Size => {
+                    let len = self.fill_line()?;
+                    let size = parse_size(self.buffer.iter().take(len)).ok_or(ChunkedError::Malformed)?;
+                    self.consume_line(len);
+                    self.state = match size {
+                        0 => DecodeState::Trailers,
+                        size => DecodeState::Payload(size),
+                    };
+                }
+                DecodeState::PayloadEnd => {
+                    let len = self.fill_line()?;
+                    if !self.buffer.iter().take(len).eq(b"\r\n") {
+                        return Err(ChunkedError::Malformed);
+                    }
+                    self.consume_line(len);
+                    self.state = DecodeState::Size;
+                }
+                DecodeState::Trailers => {
+                    let len = self.fill_line()?;
+                    if len < 2 || self.buffer.get(len - 2) != Some(&b'\r') {
+                        return Err(ChunkedError::Malformed);
+                    }
+                    if len == 2 {
+                        self.state = DecodeState::Done;
+                    } else if self.trailers.len() + len >= self.buffer.capacity() {
+                        return Err(ChunkedError::LineTooLong);
+                    } else {
+                        self.trailers.extend(self.buffer.iter().take(len));
+                    }
+                    self.consume_line(len);
+                }
+            }
+        }
+    }
+}
+impl<R: Read> Read for ChunkedReader<R> {
+    type Error = ChunkedError<R::Error>;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        self.decode_framing()?;
+        let DecodeState::Payload(remaining) = self.state else {
+            return Ok(0);
+        };
+        let limit = usize::try_from(remaining).unwrap_or(usize::MAX).min(buffer.len());
+        let count = if self.buffer.is_empty() {
+            // Read the payload directly, without buffering it first.
+            match self.inner.read(&mut buffer[..limit]).map_err(ChunkedError::Stream)? {
+                0 => return Err(ChunkedError::UnexpectedEof),
+                count => count,
+            }
+        } else {
+            self.buffer.pop_slice_uninit(&mut buffer[..limit])
+        };
+        self.state = match remaining - count as u64 {
+            0 => DecodeState::PayloadEnd,
+            remaining => DecodeState::Payload(remaining),
+        };
+        Ok(count)
+    }
+}
+
+/// Parse a chunk size line, ignoring any extensions.
+fn parse_size<'a>(mut line: impl Iterator<Item = &'a u8>) -> Option<u64> {
+    let mut size: u64 = 0;
+    let mut digits = 0;
+    let end = loop {
+        let &byte = line.next()?;
+        let digit = match byte {
+            b'0'..=b'9' => byte - b'0',
+            b'a'..=b'f' => byte - b'a' + 10,
+            b'A'..=b'F' => byte - b'A' + 10,
+            _ => break byte,
+        };
+        size = size.checked_mul(16)?.checked_add(digit.into())?;
+        digits += 1;
+    };
+    if digits == 0 {
+        return None;
+    }
+    let mut last = end;
+    if end == b';' {
+        for &byte in line.by_ref() {
+            if byte == b'\r' {
+                last = byte;
+                break;
+            }
+        }
+    }
+    (last == b'\r' && line.next() == Some(&b'\n') && line.next().is_none()).then_some(size)
+}
+
+/// Encodes written bytes with the HTTP/1.1 chunked transfer encoding.
+///
+/// Writes smaller than the coalescing threshold are buffered and sent together as one chunk;
+/// any other write is sent as a chunk straight away, without copying it. The body must be ended
+/// with [`finish`](Self::finish).
+///
+/// The framing of a chunk cannot be taken back once started, so each chunk is written in full,
+/// and a failed write leaves the body unusable.
+/// ```rust
+/// use circulate::{ChunkedReader, ChunkedWriter, Read, RingBuffer, Write};
+///
+/// let mut writer = ChunkedWriter::with_threshold(Vec::new(), 8);
+/// writer.write(b"hel").unwrap();
+/// writer.write(b"lo").unwrap();
+/// writer.write(b", world!").unwrap();
+/// writer.write(b"!").unwrap();
+/// let body = writer.finish().unwrap();
+/// assert_eq!(body, b"d\r\nhello, world!\r\n1\r\n!\r\n0\r\n\r\n");
+///
+/// let mut buffer = RingBuffer::new();
+/// ChunkedReader::new(&body[..]).read_to_end(&mut buffer).unwrap();
+/// assert!(buffer.iter().eq(b"hello, world!!"));
+/// ```
+pub struct ChunkedWriter<W> {
+    inner: W,
+    /// Small writes waiting to be sent as a single chunk.
+    pending: RingBuffer<u8>,
+}
+impl<W: Write> ChunkedWriter<W> {
+    /// Create a writer that encodes a chunked body to `inner`, coalescing writes of less than
+    /// 1024 bytes.
+    pub fn new(inner: W) -> Self {
+        Self::with_threshold(inner, 1024)
+    }
+    /// Create a writer that encodes a chunked body to `inner`, coalescing writes until they
+    /// total at least `threshold` bytes.
+    pub fn with_threshold(inner: W, threshold: usize) -> Self {
+        let mut pending = RingBuffer::with_exact_capacity(threshold);
+        pending.set_max_capacity(pending.capacity());
+        Self { inner, pending }
+    }
+    /// Write the pending bytes and then the last chunk, returning the underlying writer.
+    pub fn finish(mut self) -> Result<W, ChunkedError<W::Error>> {
+        self.write_chunk(&[])?;
+        self.write_all(b"0\r\n\r\n")?;
+        self.inner.flush().map_err(ChunkedError::Stream)?;
+        Ok(self.inner)
+    }
+
+    /// Write the pending bytes followed by `slice` as a single chunk.
+    fn write_chunk(&mut self, slice: &[u8]) -> Result<(), ChunkedError<W::Error>> {
+        let len = self.pending.len() + slice.len();
+        if len == 0 {
+            // An empty chunk would end the body.
+            return Ok(());
+        }
+        let mut size = [0; 2 * size_of::<usize>() + 2];
+        let digits = (len.ilog2() / 4 + 1) as usize;
+        for (i, byte) in size[..digits].iter_mut().rev().enumerate() {
+            *byte = b"0123456789abcdef"[len >> (4 * i) & 0xf];
+        }
+        size[digits..digits + 2].copy_from_slice(b"\r\n");
+        self.write_all(&size[..digits + 2])?;
+        while !self.pending.is_empty() {
+            if self.pending.drain_to(&mut self.inner).map_err(ChunkedError::Stream)? == 0 {
+                return Err(ChunkedError::WriteZero);
+            }
+        }
+        self.write_all(slice)?;
+        self.write_all(b"\r\n")
+    }
+    fn write_all(&mut self, mut slice: &[u8]) -> Result<(), ChunkedError<W::Error>> {
+        while !slice.is_empty() {
+            match self.inner.write(slice).map_err(ChunkedError::Stream)? {
+                0 => return Err(ChunkedError::WriteZero),
+                count => slice = &slice[count..],
+            }
+        }
+        Ok(())
+    }
+}
+impl<W: Write> Write for ChunkedWriter<W> {
+    type Error = ChunkedError<W::Error>;
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        if self.pending.len() + slice.len() < self.pending.capacity() {
+            return Ok(append(&mut self.pending, slice));
+        }
+        self.write_chunk(slice)?;
+        Ok(slice.len())
+    }
+    /// Write the pending bytes as a chunk and flush the underlying writer.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.write_chunk(&[])?;
+        self.inner.flush().map_err(ChunkedError::Stream)
+    }
+}
+
+/// The error returned by [`ChunkedReader`] and [`ChunkedWriter`].
+#[derive(Debug)]
+pub enum ChunkedError<E> {
+    /// The underlying stream failed.
+    Stream(E),
+    /// The chunk framing was not valid.
+    Malformed,
+    /// A framing line or the trailers did not fit in the buffer.
+    LineTooLong,
+    /// The stream ended before the last chunk.
+    UnexpectedEof,
+    /// The underlying writer stopped accepting bytes part way through a chunk.
+    WriteZero,
+}
+impl<E: core::fmt::Display> core::fmt::Display for ChunkedError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Stream(error) => error.fmt(f),
+            Self::Malformed => f.write_str("malformed chunk framing"),
+            Self::LineTooLong => f.write_str("chunk framing line too long"),
+            Self::UnexpectedEof => f.write_str("stream ended before the last chunk"),
+            Self::WriteZero => f.write_str("writer stopped accepting bytes part way through a chunk"),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for ChunkedError<E> {}
@@ -0,0 +1,230 @@
+//! Adapters implementing [`Read`] and [`Write`] for the I/O types of other crates, and for
+//! closures.
+//!
+//! Adapters for other crates are enabled by the feature named after the crate.
+
+use core::mem::MaybeUninit;
+use super::{IoVecMut, Read, Write};
+
+/// An adapter implementing [`Read`] and [`Write`] with a pair of closures, for quickly wiring up
+/// a stream.
+///
+/// Flushing does nothing.
+/// ```rust
+/// use core::{convert::Infallible, mem::MaybeUninit};
+/// use circulate::{compat::FnStream, BufStream, Read, Write};
+///
+/// let mut received = Vec::new();
+/// let mut sent = 0u8;
+/// let stream = FnStream::new(
+///     |buffer: &mut [MaybeUninit<u8>]| {
+///         let len = buffer.len().min(3);
+///         for byte in &mut buffer[..len] {
+///             *byte = MaybeUninit::new(sent);
+///             sent += 1;
+///         }
+///         Ok::<_, Infallible>(len)
+///     },
+///     |slice: &[u8]| {
+///         received.extend_from_slice(slice);
+///         Ok::<_, Infallible>(slice.len())
+///     },
+/// );
+/// let mut stream = BufStream::with_capacity(stream, 16);
+/// let mut buffer = [MaybeUninit::uninit(); 8];
+/// assert_eq!(stream.read(&mut buffer).unwrap(), 3);
+/// stream.write(b"hello").unwrap();
+/// stream.flush().unwrap();
+/// drop(stream);
+/// assert_eq!(received, b"hello");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct FnStream<R, W> {
+    read: R,
+    write: W,
+}
+impl<R, W> FnStream<R, W> {
+    /// Create a stream that reads by calling `read` and writes by calling `write`.
+    pub const fn new(read: R, write: W) -> Self {
+        Self { read, write }
+    }
+    /// Get the closures back.
+    pub fn into_inner(self) -> (R, W) {
+        (self.read, self.write)
+    }
+}
+impl<R, W, E> Read for FnStream<R, W>
+where
+    R: FnMut(&mut [MaybeUninit<u8>]) -> Result<usize, E>,
+{
+    type Error = E;
+    #[inline]
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        (self.read)(buffer)
+    }
+    /// Reads in to the first non-empty buffer only, so that an error cannot lose bytes already
+    /// read in to an earlier buffer.
+    fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        match buffers.iter_mut().find(|buffer| !buffer.is_empty()) {
+            Some(buffer) => (self.read)(buffer.as_maybe_uninit_slice()),
+            None => Ok(0),
+        }
+    }
+}
+impl<R, W, E> Write for FnStream<R, W>
+where
+    W: FnMut(&[u8]) -> Result<usize, E>,
+{
+    type Error = E;
+    #[inline]
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        (self.write)(slice)
+    }
+    #[inline]
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-hal-nb")]
+pub use self::embedded_hal_nb::NbSerial;
+#[cfg(feature = "embedded-hal-nb")]
+mod embedded_hal_nb {
+    use core::mem::MaybeUninit;
+    use embedded_hal_nb::{nb, serial};
+    use crate::{IoVecMut, Read, Write};
+
+    /// An adapter implementing [`Read`] and [`Write`] for an [`embedded_hal_nb`] serial port.
+    ///
+    /// Reads and writes move as many bytes as the port accepts without blocking. If it would
+    /// block before any bytes were moved the call fails with [`nb::Error::WouldBlock`] rather
+    /// than spinning, so it can be retried later. If it would block or fails after some bytes
+    /// were moved, those bytes are reported instead so that none are lost.
+    /// ```rust
+    /// use core::{convert::Infallible, mem::MaybeUninit};
+    /// use std::collections::VecDeque;
+    /// use embedded_hal_nb::{nb, serial};
+    /// use circulate::{compat::NbSerial, IoVecMut, Read, Write};
+    ///
+    /// /// A port with a scripted receive queue, blocking once it runs out, and a transmit FIFO of
+    /// /// three bytes.
+    /// #[derive(Default)]
+    /// struct Uart { rx: VecDeque<nb::Result<u8, serial::ErrorKind>>, tx: Vec<u8>, fifo: usize }
+    /// impl serial::ErrorType for Uart {
+    ///     type Error = serial::ErrorKind;
+    /// }
+    /// impl serial::Read for Uart {
+    ///     fn read(&mut self) -> nb::Result<u8, Self::Error> {
+    ///         self.rx.pop_front().unwrap_or(Err(nb::Error::WouldBlock))
+    ///     }
+    /// }
+    /// impl serial::Write for Uart {
+    ///     fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+    ///         if self.fifo == 3 {
+    ///             return Err(nb::Error::WouldBlock);
+    ///         }
+    ///         self.fifo += 1;
+    ///         self.tx.push(word);
+    ///         Ok(())
+    ///     }
+    ///     fn flush(&mut self) -> nb::Result<(), Self::Error> {
+    ///         match self.fifo {
+    ///             0 => Ok(()),
+    ///             _ => Err(nb::Error::WouldBlock),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let overrun = nb::Error::Other(serial::ErrorKind::Overrun);
+    /// let rx = [Ok(b'a'), Ok(b'b'), Err(nb::Error::WouldBlock), Err(nb::Error::WouldBlock)];
+    /// let rx = rx.into_iter().chain([Ok(b'c'), Ok(b'd'), Ok(b'e'), Err(nb::Error::WouldBlock)]);
+    /// let rx = rx.chain([Ok(b'f'), Err(overrun), Err(overrun)]);
+    /// let mut serial = NbSerial::new(Uart { rx: rx.collect(), ..Uart::default() });
+    /// let mut buffer = [MaybeUninit::uninit(); 8];
+    /// assert_eq!(serial.read(&mut buffer).unwrap(), 2);
+    /// assert_eq!(serial.read(&mut buffer), Err(nb::Error::WouldBlock));
+    ///
+    /// // Vectored reads fill each buffer in turn.
+    /// let (mut a, mut b) = ([0; 2], [0; 2]);
+    /// assert_eq!(serial.read_vectored(&mut [IoVecMut::new(&mut a), IoVecMut::new(&mut b)]).unwrap(), 3);
+    /// assert_eq!((a, b), (*b"cd", *b"e\0"));
+    ///
+    /// // The bytes read before a failure are returned first, and the port fails again after them.
+    /// assert_eq!(serial.read(&mut buffer).unwrap(), 1);
+    /// assert_eq!(serial.read(&mut buffer), Err(overrun));
+    ///
+    /// assert_eq!(serial.write(b"hello").unwrap(), 3);
+    /// assert_eq!(serial.write(b"lo"), Err(nb::Error::WouldBlock));
+    /// assert_eq!(serial.flush(), Err(nb::Error::WouldBlock));
+    /// serial.get_mut().fifo = 0;
+    /// assert_eq!(serial.flush(), Ok(()));
+    /// assert_eq!(serial.write(b"lo").unwrap(), 2);
+    /// assert_eq!(serial.into_inner().tx, b"hello");
+    /// ```
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct NbSerial<T>(T);
+    impl<T> NbSerial<T> {
+        /// Adapt `serial`, a port implementing [`serial::Read`], [`serial::Write`] or both.
+        pub const fn new(serial: T) -> Self {
+            Self(serial)
+        }
+        /// Get a reference to the serial port.
+        pub const fn get_ref(&self) -> &T {
+            &self.0
+        }
+        /// Get a mutable reference to the serial port.
+        pub fn get_mut(&mut self) -> &mut T {
+            &mut self.0
+        }
+        /// Get the serial port back.
+        pub fn into_inner(self) -> T {
+            self.0
+        }
+    }
+    /// Map a port that would block or failed after `moved` bytes to success, so that no bytes
+    /// are lost.
+    fn partial<E>(error: nb::Error<E>, moved: usize) -> Result<usize, nb::Error<E>> {
+        match moved {
+            0 => Err(error),
+            moved => Ok(moved),
+        }
+    }
+    impl<T: serial::Read> Read for NbSerial<T> {
+        type Error = nb::Error<T::Error>;
+        fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+            for (read, byte) in buffer.iter_mut().enumerate() {
+                match self.0.read() {
+                    Ok(word) => *byte = MaybeUninit::new(word),
+                    Err(error) => return partial(error, read),
+                }
+            }
+            Ok(buffer.len())
+        }
+        fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+            let mut read = 0;
+            for buffer in buffers {
+                let buffer = buffer.as_maybe_uninit_slice();
+                match self.read(buffer) {
+                    Ok(len) if len == buffer.len() => read += len,
+                    Ok(len) => return Ok(read + len),
+                    Err(error) => return partial(error, read),
+                }
+            }
+            Ok(read)
+        }
+    }
+    impl<T: serial::Write> Write for NbSerial<T> {
+        type Error = nb::Error<T::Error>;
+        fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+            for (written, byte) in slice.iter().enumerate() {
+                if let Err(error) = self.0.write(*byte) {
+                    return partial(error, written);
+                }
+            }
+            Ok(slice.len())
+        }
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            self.0.flush()
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{AllocFailure, RingBuffer};
+use super::{buffered::DEFAULT_CAPACITY, BufReader, BufStream, BufWriter, Read, Write};
+
+/// The options of a [`BufStream`], [`BufReader`] or [`BufWriter`], set all at once with
+/// [`BufStream::with_config`], [`BufReader::with_config`] or [`BufWriter::with_config`].
+///
+/// Each method sets one option and returns the configuration, and the default matches
+/// [`BufStream::new`]. Every option can also be changed later through the stream's own setter.
+/// The buffers of a [`BufReader`] and [`BufWriter`] never grow, so they only take the options
+/// that apply to a buffer of a fixed capacity.
+/// ```rust
+/// use std::io;
+/// use circulate::{AllocFailure, BufConfig, BufStream, FromStd, IoVecMut};
+///
+/// let stream = || FromStd(io::Cursor::new(vec![7; 256]));
+/// // Each option, and how a stream shows it is set.
+/// type Check = fn(&mut BufStream<FromStd<io::Cursor<Vec<u8>>>>) -> bool;
+/// let cases: [(BufConfig, Check); 11] = [
+///     (BufConfig::new().input_capacity(15), |stream| stream.input_capacity() == 16),
+///     (BufConfig::new().output_capacity(15), |stream| stream.output_capacity() == 16),
+///     (BufConfig::new().read_ahead(100), |stream| {
+///         stream.buffer_read().unwrap();
+///         stream.input_capacity() > 100
+///     }),
+///     (BufConfig::new().max_input_capacity(16), |stream| {
+///         (0..6).for_each(|_| stream.buffer_read().unwrap());
+///         stream.input_capacity() == 16
+///     }),
+///     (BufConfig::new().max_output_capacity(16), |stream| stream.remaining_write_capacity() == 15),
+///     (BufConfig::new().pushback_limit(2), |stream| stream.unread(b"abc").is_err()),
+///     (BufConfig::new().adaptive_buffering(true), |stream| {
+///         stream.buffer_read().unwrap();
+///         stream.input_capacity() == 1024
+///     }),
+///     (BufConfig::new().adaptive_buffering(true).adaptive_limits(64, 128), |stream| {
+///         stream.buffer_read().unwrap();
+///         stream.input_capacity() == 64
+///     }),
+///     (BufConfig::new().input_capacity(15).vectored_refill(true), |stream| {
+///         let mut buffer = [0; 64];
+///         circulate::Read::read_vectored(stream, &mut [IoVecMut::new(&mut buffer)]).unwrap() == 64
+///     }),
+///     (BufConfig::new().scrub_consumed(true), |stream| stream.scrub_consumed()),
+///     (BufConfig::new().alloc_failure_mode(AllocFailure::Saturate), |stream| {
+///         stream.alloc_failure_mode() == AllocFailure::Saturate
+///     }),
+/// ];
+/// for (config, check) in cases {
+///     assert!(check(&mut BufStream::with_config(stream(), config.clone())), "{config:?}");
+///     assert!(!check(&mut BufStream::with_config(stream(), BufConfig::default())), "{config:?}");
+///     assert!(!check(&mut BufStream::new(stream())), "{config:?}");
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufConfig {
+    input_capacity: usize,
+    output_capacity: usize,
+    read_ahead: usize,
+    max_input_capacity: Option<usize>,
+    max_output_capacity: Option<usize>,
+    pushback_limit: usize,
+    adaptive: bool,
+    adaptive_limits: Option<(usize, usize)>,
+    vectored_refill: bool,
+    scrub_consumed: bool,
+    alloc_failure: AllocFailure,
+}
+impl BufConfig {
+    /// Create the default configuration.
+    pub const fn new() -> Self {
+        Self {
+            input_capacity: 0,
+            output_capacity: 0,
+            read_ahead: 0,
+            max_input_capacity: None,
+            max_output_capacity: None,
+            pushback_limit: usize::MAX,
+            adaptive: false,
+            adaptive_limits: None,
+            vectored_refill: false,
+            scrub_consumed: false,
+            alloc_failure: AllocFailure::Abort,
+        }
+    }
+    /// Hold at least `capacity` bytes of input before growing. See [`BufStream::with_capacity`].
+    pub const fn input_capacity(mut self, capacity: usize) -> Self {
+        self.input_capacity = capacity;
+        self
+    }
+    /// Hold at least `capacity` bytes of output before growing. See [`BufStream::with_capacity`].
+    pub const fn output_capacity(mut self, capacity: usize) -> Self {
+        self.output_capacity = capacity;
+        self
+    }
+    /// Make space for at least `read_ahead` bytes before each read from the stream. See
+    /// [`BufStream::with_read_ahead`].
+    pub const fn read_ahead(mut self, read_ahead: usize) -> Self {
+        self.read_ahead = read_ahead;
+        self
+    }
+    /// Never grow the input buffer beyond `max` bytes, so that a stalled consumer cannot make it
+    /// grow without bound. See [`BufStream::set_max_input_capacity`].
+    pub const fn max_input_capacity(mut self, max: usize) -> Self {
+        self.max_input_capacity = Some(max);
+        self
+    }
+    /// Never grow the output buffer beyond `max` bytes. See
+    /// [`BufStream::set_max_output_capacity`].
+    pub const fn max_output_capacity(mut self, max: usize) -> Self {
+        self.max_output_capacity = Some(max);
+        self
+    }
+    /// See [`BufStream::set_pushback_limit`].
+    pub const fn pushback_limit(mut self, limit: usize) -> Self {
+        self.pushback_limit = limit;
+        self
+    }
+    /// See [`BufStream::set_adaptive_buffering`].
+    pub const fn adaptive_buffering(mut self, adaptive: bool) -> Self {
+        self.adaptive = adaptive;
+        self
+    }
+    /// See [`BufStream::set_adaptive_limits`].
+    ///
+    /// # Panics
+    /// [`BufStream::with_config`] panics if `floor` is 0 or greater than `cap`.
+    pub const fn adaptive_limits(mut self, floor: usize, cap: usize) -> Self {
+        self.adaptive_limits = Some((floor, cap));
+        self
+    }
+    /// See [`BufStream::set_vectored_refill`].
+    pub const fn vectored_refill(mut self, refill: bool) -> Self {
+        self.vectored_refill = refill;
+        self
+    }
+    /// See [`BufStream::set_scrub_consumed`].
+    pub const fn scrub_consumed(mut self, scrub: bool) -> Self {
+        self.scrub_consumed = scrub;
+        self
+    }
+    /// See [`BufStream::set_alloc_failure_mode`].
+    pub const fn alloc_failure_mode(mut self, mode: AllocFailure) -> Self {
+        self.alloc_failure = mode;
+        self
+    }
+}
+impl Default for BufConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> BufStream<S> {
+    /// Create a new buffered stream with the options of `config`.
+    ///
+    /// # Panics
+    /// If the adaptive limits are invalid, see [`BufStream::set_adaptive_limits`].
+    #[track_caller]
+    pub fn with_config(stream: S, config: BufConfig) -> Self {
+        let mut input = RingBuffer::with_capacity(config.input_capacity);
+        if let Some(max) = config.max_input_capacity {
+            input.set_max_capacity(max);
+        }
+        let mut output = RingBuffer::with_capacity(config.output_capacity);
+        if let Some(max) = config.max_output_capacity {
+            output.set_max_capacity(max);
+        }
+        let mut this = Self::with_buffers(stream, input, output);
+        this.pushback_limit = config.pushback_limit;
+        this.read_ahead = config.read_ahead;
+        this.vectored_refill = config.vectored_refill;
+        this.set_alloc_failure_mode(config.alloc_failure);
+        this.set_scrub_consumed(config.scrub_consumed);
+        if let Some((floor, cap)) = config.adaptive_limits {
+            this.set_adaptive_limits(floor, cap);
+        }
+        this.set_adaptive_buffering(config.adaptive);
+        this
+    }
+}
+impl<R: Read> BufReader<R> {
+    /// Create a buffered reader with the options of `config` that apply to it.
+    ///
+    /// The buffer holds the input capacity, or 8 KiB if it is not set, but no more than the
+    /// maximum input capacity. The pushback limit and scrubbing of consumed bytes are also taken,
+    /// and the other options are ignored.
+    /// ```rust
+    /// use circulate::{BufConfig, BufReader};
+    ///
+    /// let config = BufConfig::new().input_capacity(64).max_input_capacity(16).pushback_limit(2).scrub_consumed(true);
+    /// let mut reader = BufReader::with_config(&b"abc"[..], config);
+    /// assert_eq!(format!("{reader:?}"), "BufReader { inner: [97, 98, 99], buffer: 0/15 }");
+    /// assert_eq!(reader.pushback_limit(), 2);
+    /// assert!(reader.scrub_consumed());
+    ///
+    /// let reader = BufReader::with_config(&b"abc"[..], BufConfig::default());
+    /// assert!(format!("{reader:?}").ends_with("buffer: 0/8191 }"));
+    /// ```
+    pub fn with_config(inner: R, config: BufConfig) -> Self {
+        let capacity = fixed_capacity(config.input_capacity, config.max_input_capacity);
+        let mut this = Self::with_capacity(inner, capacity);
+        this.set_pushback_limit(config.pushback_limit);
+        this.set_scrub_consumed(config.scrub_consumed);
+        this
+    }
+}
+impl<W: Write> BufWriter<W> {
+    /// Create a buffered writer with the options of `config` that apply to it.
+    ///
+    /// The buffer holds the output capacity, or 8 KiB if it is not set, but no more than the
+    /// maximum output capacity. The other options are ignored.
+    /// ```rust
+    /// use circulate::{BufConfig, BufWriter};
+    ///
+    /// let writer = BufWriter::with_config(Vec::new(), BufConfig::new().output_capacity(15));
+    /// assert_eq!(format!("{writer:?}"), "BufWriter { inner: [], buffer: 0/15 }");
+    /// let writer = BufWriter::with_config(Vec::new(), BufConfig::new().max_output_capacity(100));
+    /// assert_eq!(format!("{writer:?}"), "BufWriter { inner: [], buffer: 0/63 }");
+    /// ```
+    pub fn with_config(inner: W, config: BufConfig) -> Self {
+        Self::with_capacity(inner, fixed_capacity(config.output_capacity, config.max_output_capacity))
+    }
+}
+
+/// Get the capacity of a buffer that does not grow, from the capacity and maximum capacity of a
+/// [`BufConfig`].
+fn fixed_capacity(capacity: usize, max: Option<usize>) -> usize {
+    let capacity = if capacity == 0 { DEFAULT_CAPACITY } else { capacity };
+    match max {
+        // The most slots a buffer rounded up to a power of two can have, less the vacant one.
+        Some(max) if max != 0 => capacity.min((1 << max.ilog2()) - 1),
+        Some(_) => 0,
+        None => capacity,
+    }
+}
@@ -0,0 +1,429 @@
+use core::mem::MaybeUninit;
+use super::{IoVec, IoVecMut, Read, Write, WriteAllError};
+#[cfg(feature = "pod")]
+use super::PodError;
+
+/// Combinators for [`Read`] that take the reader by value.
+///
+/// These are kept out of [`Read`] so that it remains usable as a trait object. The trait is
+/// implemented for every sized reader, including boxed trait objects.
+/// ```rust
+/// use core::{convert::Infallible, mem::MaybeUninit};
+/// use circulate::{BufStream, Read, ReadExt, RingBuffer, Write};
+///
+/// /// An endless stream of a single byte.
+/// struct Repeat(u8);
+/// impl Read for Repeat {
+///     type Error = Infallible;
+///     fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Infallible> {
+///         buffer.fill(MaybeUninit::new(self.0));
+///         Ok(buffer.len())
+///     }
+/// }
+///
+/// let sources: Vec<Box<dyn Read<Error = Infallible>>> = vec![
+///     Box::new(Repeat(b'a').take(3)),
+///     Box::new(Repeat(b'b').take(2)),
+///     Box::new(Repeat(b'c').take(1)),
+/// ];
+/// let mut reader = sources.into_iter().reduce(|a, b| Box::new(a.chain(b))).unwrap();
+/// let mut buffer = RingBuffer::new();
+/// assert_eq!(reader.read_to_end(&mut buffer).unwrap(), 6);
+/// assert!(buffer.iter().eq(b"aaabbc"));
+///
+/// /// A transport that only receives.
+/// struct Receiver(Box<dyn Read<Error = Infallible>>);
+/// impl Read for Receiver {
+///     type Error = Infallible;
+///     fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Infallible> {
+///         self.0.read(buffer)
+///     }
+/// }
+/// impl Write for Receiver {
+///     type Error = Infallible;
+///     fn write(&mut self, slice: &[u8]) -> Result<usize, Infallible> { Ok(slice.len()) }
+///     fn flush(&mut self) -> Result<(), Infallible> { Ok(()) }
+/// }
+/// let mut stream = BufStream::new(Receiver(Box::new(Repeat(b'z').take(4))));
+/// let mut string = String::new();
+/// stream.read_to_string(&mut string).unwrap();
+/// assert_eq!(string, "zzzz");
+/// ```
+pub trait ReadExt: Read + Sized {
+    /// Create a reader that reads at most `limit` bytes from this reader.
+    fn take(self, limit: usize) -> Take<Self> {
+        Take { inner: self, limit }
+    }
+    /// Create a reader that reads from this reader until it returns no more bytes, and then
+    /// from `next`.
+    fn chain<R: Read<Error = Self::Error>>(self, next: R) -> Chain<Self, R> {
+        Chain { first: self, second: next, first_done: false }
+    }
+    /// Create a reader that reads at most `budget` bytes before yielding. See [`Budgeted`].
+    fn budgeted(self, budget: usize) -> Budgeted<Self> {
+        Budgeted { inner: self, budget, remaining: budget }
+    }
+    /// Read a value of a plain data type, such as a `repr(C)` header, reading until all of its
+    /// `size_of::<T>()` bytes are read.
+    ///
+    /// The bytes are read straight in to the value, so it does not matter how they would be
+    /// aligned in a byte array. They are taken in the layout of `T` on the target: converting
+    /// fields from the byte order of the protocol, such as with [`u32::from_be`], is left to the
+    /// caller.
+    ///
+    /// Bytes read before the reader fails or ends are lost. [`BufStream::view_pod`](super::BufStream::view_pod)
+    /// leaves them buffered instead.
+    /// ```rust
+    /// use circulate::{Eof, PodError, ReadExt};
+    ///
+    /// let mut reader = &[0x12, 0x34, 0x56, 0x78, 0x9a][..];
+    /// let value: u32 = reader.read_pod().unwrap();
+    /// assert_eq!(u32::from_be(value), 0x12345678);
+    /// assert!(matches!(reader.read_pod::<u32>(), Err(PodError::UnexpectedEof(Eof { needed: 4, got: 1 }))));
+    ///
+    /// let mut records = [0u16; 3];
+    /// (&[1, 0, 2, 0, 3, 0][..]).read_pod_vectored(&mut records).unwrap();
+    /// assert_eq!(records.map(u16::from_le), [1, 2, 3]);
+    /// ```
+    #[cfg(feature = "pod")]
+    fn read_pod<T: bytemuck::Pod>(&mut self) -> Result<T, PodError<Self::Error>> {
+        let mut value = MaybeUninit::<T>::uninit();
+        // Safety: The bytes of a `MaybeUninit` need not be initialized.
+        let bytes = unsafe { core::slice::from_raw_parts_mut(value.as_mut_ptr().cast::<MaybeUninit<u8>>(), size_of::<T>()) };
+        self.read_exact(bytes)?;
+        // Safety: Every byte was read, and any bytes are a valid `Pod` value.
+        Ok(unsafe { value.assume_init() })
+    }
+    /// Read consecutive values of a plain data type in to `values`, such as the records following
+    /// a header, reading until every byte of them is read. See [`ReadExt::read_pod`].
+    #[cfg(feature = "pod")]
+    fn read_pod_vectored<T: bytemuck::Pod>(&mut self, values: &mut [T]) -> Result<(), PodError<Self::Error>> {
+        let bytes: &mut [u8] = bytemuck::cast_slice_mut(values);
+        // Safety: The bytes are initialized, and readers must not de-initialize them.
+        let bytes = unsafe { &mut *(bytes as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        Ok(self.read_exact(bytes)?)
+    }
+}
+impl<R: Read> ReadExt for R {}
+
+/// Conveniences for [`Write`], implemented for every writer.
+pub trait WriteExt: Write {
+    /// Write `count` zero bytes, such as to pad a frame.
+    /// Returns the number of bytes written, which is less than `count` only if the writer
+    /// stopped accepting bytes.
+    ///
+    /// The bytes are written with [`Write::write_repeated`], so buffering writers fill their
+    /// buffer directly rather than copying from a chunk of zeros.
+    /// ```rust
+    /// use circulate::{BufStream, FromStd, Write, WriteExt};
+    ///
+    /// let mut stream = BufStream::new(FromStd(std::io::Cursor::new(Vec::new())));
+    /// stream.write(b"frame").unwrap();
+    /// assert_eq!(stream.write_zeroes(1 << 20).unwrap(), 1 << 20);
+    /// stream.flush().unwrap();
+    /// let written = stream.take_stream().0.into_inner();
+    /// assert_eq!(written.len(), 5 + (1 << 20));
+    /// assert!(written[5..].iter().all(|byte| *byte == 0));
+    ///
+    /// // Unbuffered writers are written to a chunk at a time.
+    /// let mut writer = Vec::new();
+    /// assert_eq!(writer.write_zeroes(1000).unwrap(), 1000);
+    /// assert_eq!(writer, [0; 1000]);
+    /// ```
+    fn write_zeroes(&mut self, count: usize) -> Result<usize, Self::Error> {
+        let mut written = 0;
+        while written < count {
+            match self.write_repeated(0, count - written)? {
+                0 => break,
+                bytes => written += bytes,
+            }
+        }
+        Ok(written)
+    }
+    /// Write all of `slice`, retrying short writes.
+    ///
+    /// Fails with [`WriteAllError::WriteZero`] if the writer stops accepting bytes, such as a
+    /// [`BufStream`](crate::BufStream) whose output buffer has reached its maximum capacity, and
+    /// with [`WriteAllError::Write`] if it fails. Either way the error tells how many bytes were
+    /// written.
+    /// ```rust
+    /// use circulate::{BufStream, WriteAllError, WriteExt};
+    ///
+    /// let mut stream = BufStream::new(Vec::new());
+    /// stream.set_max_output_capacity(8);
+    /// stream.write_all(b"ping\n").unwrap();
+    /// let result = stream.write_all(b"pong\n");
+    /// assert!(matches!(result, Err(WriteAllError::WriteZero { bytes_so_far: 2 })));
+    /// ```
+    fn write_all(&mut self, mut slice: &[u8]) -> Result<(), WriteAllError<Self::Error>> {
+        let mut written = 0;
+        while !slice.is_empty() {
+            match self.write(slice).map_err(WriteAllError::Write)? {
+                0 => return Err(WriteAllError::WriteZero { bytes_so_far: written }),
+                bytes => {
+                    written += bytes;
+                    slice = &slice[bytes..];
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Write all the bytes of `buffers` with [`Write::write_vectored`], retrying short writes.
+    /// The buffers are advanced past the bytes written, so on failure they describe the bytes
+    /// left to write.
+    fn write_all_vectored(&mut self, mut buffers: &mut [IoVec<'_>]) -> Result<(), WriteAllError<Self::Error>> {
+        let mut written = 0;
+        IoVec::advance_slices(&mut buffers, 0);
+        while !buffers.is_empty() {
+            match self.write_vectored(buffers).map_err(WriteAllError::Write)? {
+                0 => return Err(WriteAllError::WriteZero { bytes_so_far: written }),
+                bytes => {
+                    written += bytes;
+                    IoVec::advance_slices(&mut buffers, bytes);
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Write all the bytes of `segments` in order, such as the header and payload of a frame,
+    /// without first copying them together.
+    ///
+    /// The segments are written with [`WriteExt::write_all_vectored`], up to 16 at a time.
+    /// ```rust
+    /// use circulate::{IoVec, WriteAllError, WriteExt};
+    ///
+    /// let mut writer = Vec::new();
+    /// writer.write_all_segments(&[IoVec::new(&[0, 5]), IoVec::new(b""), IoVec::new(b"hello")]).unwrap();
+    /// assert_eq!(writer, b"\0\x05hello");
+    ///
+    /// // A writer that stops accepting bytes part way through.
+    /// let mut full = [0; 4];
+    /// let result = (&mut full[..]).write_all_segments(&[IoVec::new(&[0, 5]), IoVec::new(b"hello")]);
+    /// assert!(matches!(result, Err(WriteAllError::WriteZero { bytes_so_far: 4 })));
+    /// assert_eq!(&full, b"\0\x05he");
+    /// ```
+    fn write_all_segments(&mut self, segments: &[IoVec<'_>]) -> Result<(), WriteAllError<Self::Error>> {
+        const BATCH: usize = 16;
+        let mut batch = [IoVec::default(); BATCH];
+        let mut written = 0;
+        for segments in segments.chunks(BATCH) {
+            let batch = &mut batch[..segments.len()];
+            batch.copy_from_slice(segments);
+            self.write_all_vectored(batch).map_err(|error| match error {
+                WriteAllError::WriteZero { bytes_so_far } => WriteAllError::WriteZero { bytes_so_far: written + bytes_so_far },
+                error => error,
+            })?;
+            written += segments.iter().map(IoVec::len).sum::<usize>();
+        }
+        Ok(())
+    }
+}
+impl<W: Write + ?Sized> WriteExt for W {}
+
+/// A reader that reads at most a limited number of bytes. See [`ReadExt::take`].
+#[derive(Debug)]
+pub struct Take<R> {
+    inner: R,
+    limit: usize,
+}
+impl<R> Take<R> {
+    /// Get the number of bytes that may still be read.
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+    /// Get the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+impl<R: Read> Read for Take<R> {
+    type Error = R::Error;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        if self.limit == 0 {
+            return Ok(0);
+        }
+        let len = buffer.len().min(self.limit);
+        let count = self.inner.read(&mut buffer[..len])?.min(len);
+        self.limit -= count;
+        Ok(count)
+    }
+    fn is_buffered(&self) -> bool {
+        self.inner.is_buffered()
+    }
+}
+
+/// A reader that fails with [`BudgetError::Exhausted`] once it has read a budget of bytes, so
+/// that an operation reading many bytes, such as [`Read::read_to_end`], hands back control
+/// part way through. See [`ReadExt::budgeted`].
+///
+/// Failing refills the budget, so repeating the operation carries on where it stopped. This lets
+/// a single thread take turns reading from several sources without one monopolizing it.
+/// ```rust
+/// use circulate::{BudgetError, BufStream, FromStd, Read, ReadExt, RingBuffer};
+///
+/// let mut sources = [&b"aaaaaaaaaa"[..], b"bbbbbb"].map(|source| source.budgeted(4));
+/// let mut buffers = [RingBuffer::new(), RingBuffer::new()];
+/// let mut done = [false; 2];
+/// let mut turns = Vec::new();
+/// while !done.iter().all(|done| *done) {
+///     for i in 0..2 {
+///         if done[i] {
+///             continue;
+///         }
+///         match sources[i].read_to_end(&mut buffers[i]) {
+///             Ok(_) => done[i] = true,
+///             Err(BudgetError::Exhausted) => (),
+///             Err(error) => panic!("{error}"),
+///         }
+///         turns.push((i, buffers[i].len()));
+///     }
+/// }
+/// assert_eq!(turns, [(0, 4), (1, 4), (0, 8), (1, 6), (0, 10)]);
+/// assert!(buffers[0].iter().eq(b"aaaaaaaaaa") && buffers[1].iter().eq(b"bbbbbb"));
+///
+/// // A buffered stream keeps what it has read when the budget runs out.
+/// let mut stream = BufStream::with_capacity(FromStd(std::io::Cursor::new(vec![7; 64])).budgeted(4), 16);
+/// let mut turns = 1;
+/// while let Err(BudgetError::Exhausted) = stream.fill_at_least(10) {
+///     turns += 1;
+/// }
+/// assert_eq!(turns, 3);
+/// assert_eq!(stream.peek_all_input().unwrap().0.len(), 12);
+/// ```
+#[derive(Debug)]
+pub struct Budgeted<R> {
+    inner: R,
+    budget: usize,
+    remaining: usize,
+}
+impl<R> Budgeted<R> {
+    /// Get the number of bytes that may be read before the budget is exhausted.
+    pub const fn remaining(&self) -> usize {
+        self.remaining
+    }
+    /// Set the number of bytes that may be read each turn, refilling the budget.
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        self.remaining = budget;
+    }
+    /// Get the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    /// Fail with [`BudgetError::Exhausted`], refilling the budget for the next turn.
+    fn exhausted<E>(&mut self) -> Result<usize, BudgetError<E>> {
+        self.remaining = self.budget;
+        Err(BudgetError::Exhausted)
+    }
+}
+impl<R: Read> Read for Budgeted<R> {
+    type Error = BudgetError<R::Error>;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        if self.remaining == 0 && !buffer.is_empty() {
+            return self.exhausted();
+        }
+        let len = buffer.len().min(self.remaining);
+        let count = self.inner.read(&mut buffer[..len]).map_err(BudgetError::Read)?.min(len);
+        self.remaining -= count;
+        Ok(count)
+    }
+    /// Read in to the buffers with one vectored read of the inner reader, limited to the budget.
+    /// ```rust
+    /// use circulate::{BudgetError, IoVecMut, Read, ReadExt};
+    ///
+    /// let mut reader = (&[7; 64][..]).budgeted(10);
+    /// let mut buffers = [[0; 4]; 3];
+    /// let read = |reader: &mut circulate::Budgeted<&[u8]>, buffers: &mut [[u8; 4]; 3]| {
+    ///     let [a, b, c] = buffers;
+    ///     reader.read_vectored(&mut [IoVecMut::new(a), IoVecMut::new(b), IoVecMut::new(c)])
+    /// };
+    /// // The budget runs out part way through the third buffer.
+    /// assert_eq!(read(&mut reader, &mut buffers).ok(), Some(10));
+    /// assert_eq!(buffers, [[7; 4], [7; 4], [7, 7, 0, 0]]);
+    /// assert!(matches!(read(&mut reader, &mut buffers), Err(BudgetError::Exhausted)));
+    /// assert_eq!(read(&mut reader, &mut buffers).ok(), Some(10));
+    /// ```
+    fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        if buffers.iter().all(IoVecMut::is_empty) {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            return self.exhausted();
+        }
+        // Find the buffer the budget runs out in, and how much of it the budget covers.
+        let mut left = self.remaining;
+        let end = buffers.iter().position(|buffer| match left.checked_sub(buffer.len()) {
+            Some(rest) if rest != 0 => {
+                left = rest;
+                false
+            }
+            _ => true,
+        });
+        let result = match end {
+            Some(end) if left < buffers[end].len() => {
+                // Safety: The shortened buffer covers the start of the original one, which is
+                // put back before the buffers are used again.
+                let covered = unsafe { IoVecMut::from_raw_parts(buffers[end].as_ptr(), left) };
+                let original = core::mem::replace(&mut buffers[end], covered);
+                let result = self.inner.read_vectored(&mut buffers[..=end]);
+                buffers[end] = original;
+                result
+            }
+            Some(end) => self.inner.read_vectored(&mut buffers[..=end]),
+            None => self.inner.read_vectored(buffers),
+        };
+        let count = result.map_err(BudgetError::Read)?.min(self.remaining);
+        self.remaining -= count;
+        Ok(count)
+    }
+    fn is_buffered(&self) -> bool {
+        self.inner.is_buffered()
+    }
+}
+/// The error returned by [`Budgeted`] readers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetError<E> {
+    /// The budget of bytes for this turn has been read. Reading again starts a new turn.
+    Exhausted,
+    /// The underlying reader failed.
+    Read(E),
+}
+impl<E: core::fmt::Display> core::fmt::Display for BudgetError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Exhausted => f.write_str("read budget exhausted"),
+            Self::Read(error) => error.fmt(f),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for BudgetError<E> {}
+
+/// A reader that reads from one reader and then another. See [`ReadExt::chain`].
+#[derive(Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+    /// Set once the first reader has returned no more bytes.
+    first_done: bool,
+}
+impl<A, B> Chain<A, B> {
+    /// Get the two underlying readers.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+impl<A: Read, B: Read<Error = A::Error>> Read for Chain<A, B> {
+    type Error = A::Error;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        if !self.first_done {
+            match self.first.read(buffer)? {
+                0 if !buffer.is_empty() => self.first_done = true,
+                count => return Ok(count),
+            }
+        }
+        self.second.read(buffer)
+    }
+    fn is_buffered(&self) -> bool {
+        if self.first_done { self.second.is_buffered() } else { self.first.is_buffered() }
+    }
+}
@@ -0,0 +1,69 @@
+use core::fmt;
+use super::Write;
+
+/// Adapt `writer` to [`core::fmt::Write`], for use with [`write!`] and formatting machinery.
+///
+/// Formatting can only report that an error occurred, so the error of the writer is stashed in
+/// the adapter to be retrieved afterwards rather than being lost.
+/// ```rust
+/// # use std::io;
+/// # struct Broken;
+/// # impl io::Write for Broken {
+/// #     fn write(&mut self, _: &[u8]) -> io::Result<usize> { Err(io::ErrorKind::BrokenPipe.into()) }
+/// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+/// # }
+/// use core::fmt::Write as _;
+/// use circulate::{fmt_adapter, FromStd, LossyWriter, RingBuffer};
+///
+/// let mut writer = LossyWriter::new(RingBuffer::with_capacity(64));
+/// let mut adapter = fmt_adapter(&mut writer);
+/// write!(adapter, "{} + {} = {}", 1, 2, 1 + 2).unwrap();
+/// assert!(adapter.into_result().is_ok());
+/// assert!(writer.buffer().eq_slice(b"1 + 2 = 3"));
+///
+/// let mut broken = FromStd(Broken);
+/// let mut adapter = fmt_adapter(&mut broken);
+/// assert!(write!(adapter, "{}", 42).is_err());
+/// assert_eq!(adapter.into_result().unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+/// ```
+pub fn fmt_adapter<W: Write + ?Sized>(writer: &mut W) -> FmtAdapter<'_, W> {
+    FmtAdapter { writer, error: None }
+}
+
+/// A [`core::fmt::Write`] implementation over a [`Write`], created by [`fmt_adapter`].
+pub struct FmtAdapter<'a, W: Write + ?Sized> {
+    writer: &'a mut W,
+    /// The first error returned by the writer.
+    error: Option<W::Error>,
+}
+impl<W: Write + ?Sized> FmtAdapter<'_, W> {
+    /// Take the error that caused formatting to fail, if any.
+    pub fn take_error(&mut self) -> Option<W::Error> {
+        self.error.take()
+    }
+    /// Returns the error that caused formatting to fail, if any.
+    pub fn into_result(self) -> Result<(), W::Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+impl<W: Write + ?Sized> fmt::Write for FmtAdapter<'_, W> {
+    /// Write all of `s`, retrying short writes.
+    /// Fails without stashing an error if the writer stops accepting bytes.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut bytes = s.as_bytes();
+        while !bytes.is_empty() {
+            match self.writer.write(bytes) {
+                Ok(0) => return Err(fmt::Error),
+                Ok(written) => bytes = &bytes[written..],
+                Err(error) => {
+                    self.error.get_or_insert(error);
+                    return Err(fmt::Error);
+                }
+            }
+        }
+        Ok(())
+    }
+}
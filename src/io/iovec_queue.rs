@@ -0,0 +1,129 @@
+extern crate alloc;
+use alloc::vec::Vec;
+use crate::RingBuffer;
+use super::{IoVec, Write};
+
+/// A queue of owned byte chunks waiting to be written, which can be described as [`IoVec`]s for
+/// vectored writes without first copying the chunks in to one buffer.
+/// ```rust
+/// use circulate::{IoVec, IoVecQueue};
+/// let mut queue = IoVecQueue::new();
+/// queue.push(b"hello".to_vec());
+/// queue.push(b", ".to_vec());
+/// queue.push(b"world".to_vec());
+/// assert_eq!(queue.len(), 12);
+///
+/// let lens = |queue: &IoVecQueue| {
+///     let mut vecs = [IoVec::default(); 4];
+///     let filled = queue.as_io_vecs(&mut vecs);
+///     vecs[..filled].iter().map(IoVec::len).collect::<Vec<_>>()
+/// };
+/// assert_eq!(lens(&queue), [5, 2, 5]);
+///
+/// // A partial write ending within the second chunk.
+/// queue.advance(6);
+/// assert_eq!(lens(&queue), [1, 5]);
+///
+/// // A partial write ending exactly on a chunk boundary.
+/// queue.advance(1);
+/// let mut vecs = [IoVec::default(); 1];
+/// assert_eq!(queue.as_io_vecs(&mut vecs), 1);
+/// assert_eq!(unsafe { &*(vecs[0].as_maybe_uninit_slice() as *const _ as *const [u8]) }, b"world");
+/// queue.advance(5);
+/// assert!(queue.is_empty());
+/// assert_eq!(lens(&queue), []);
+/// ```
+pub struct IoVecQueue {
+    chunks: RingBuffer<Vec<u8>>,
+    /// The number of bytes of the front chunk that have been written.
+    offset: usize,
+    /// The number of bytes waiting to be written.
+    len: usize,
+}
+impl IoVecQueue {
+    /// The most entries filled by [`IoVecQueue::as_io_vecs`], which is the smallest limit on the
+    /// number of buffers in a single vectored write across supported platforms.
+    pub const IOV_MAX: usize = 1024;
+    /// The most entries written at once by [`IoVecQueue::write_to`], kept small enough to
+    /// describe on the stack.
+    const BATCH: usize = 64;
+
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            chunks: RingBuffer::new(),
+            offset: 0,
+            len: 0,
+        }
+    }
+    /// Queue a chunk to be written after those already queued.
+    pub fn push(&mut self, chunk: Vec<u8>) {
+        if chunk.is_empty() {
+            return;
+        }
+        self.len += chunk.len();
+        // The queue is unbounded so pushing cannot fail.
+        let _ = self.chunks.push(chunk);
+    }
+    /// Get the number of bytes waiting to be written.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    /// Returns if there are no bytes waiting to be written.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Describe the unwritten bytes of the queued chunks in order, filling at most
+    /// [`IoVecQueue::IOV_MAX`] entries of `out`. Returns the number of entries filled.
+    pub fn as_io_vecs<'a>(&'a self, out: &mut [IoVec<'a>]) -> usize {
+        let mut filled = 0;
+        for (out, chunk) in out.iter_mut().take(Self::IOV_MAX).zip(self.chunks.iter()) {
+            *out = IoVec::new(if filled == 0 { &chunk[self.offset..] } else { chunk });
+            filled += 1;
+        }
+        filled
+    }
+    /// Get the unwritten bytes of each queued chunk, in order.
+    pub(crate) fn unwritten(&self) -> impl Iterator<Item = &[u8]> {
+        self.chunks.iter().enumerate().map(|(index, chunk)| if index == 0 { &chunk[self.offset..] } else { &chunk[..] })
+    }
+    /// Mark the next `count` bytes as written, releasing every chunk that was written completely.
+    /// # Panics
+    /// Panics if fewer than `count` bytes are queued.
+    pub fn advance(&mut self, count: usize) {
+        assert!(count <= self.len, "cannot advance past the end of the queued chunks");
+        self.len -= count;
+        self.offset += count;
+        while let Some(front) = self.chunks.get(0) {
+            if self.offset < front.len() {
+                break;
+            }
+            self.offset -= front.len();
+            self.chunks.pop();
+        }
+    }
+    /// Write the queued chunks to `writer` in one vectored write, advancing past the bytes that
+    /// were written. Returns the number of bytes written, which is 0 if the queue is empty.
+    pub fn write_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> Result<usize, W::Error> {
+        self.write_after(writer, [&[], &[]])
+    }
+    /// Write `lead` and then the queued chunks to `writer` in one vectored write, advancing past
+    /// the bytes of the chunks that were written. Returns the number of bytes written, including
+    /// those of `lead`.
+    pub(crate) fn write_after<W: Write + ?Sized>(&mut self, writer: &mut W, lead: [&[u8]; 2]) -> Result<usize, W::Error> {
+        let mut vecs = [IoVec::default(); Self::BATCH];
+        let mut filled = 0;
+        for slice in lead.into_iter().filter(|slice| !slice.is_empty()) {
+            vecs[filled] = IoVec::new(slice);
+            filled += 1;
+        }
+        filled += self.as_io_vecs(&mut vecs[filled..]);
+        if filled == 0 {
+            return Ok(0);
+        }
+        let written = writer.write_vectored(&vecs[..filled])?;
+        self.advance(written.saturating_sub(lead[0].len() + lead[1].len()));
+        Ok(written)
+    }
+}
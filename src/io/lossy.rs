@@ -0,0 +1,69 @@
+use core::convert::Infallible;
+use crate::RingBuffer;
+use super::Write;
+
+/// A writer in to a [`RingBuffer`] that drops whatever does not fit instead of growing the buffer.
+///
+/// A write copies as much of the slice as there is space for and returns the number of bytes
+/// kept. Unlike a short write the rest of the slice is discarded: callers should not retry it, and
+/// nothing written later fills the gap. The number of discarded bytes is counted by
+/// [`LossyWriter::dropped_bytes`].
+/// ```rust
+/// use circulate::{LossyWriter, RingBuffer, Write};
+///
+/// let mut writer = LossyWriter::new(RingBuffer::with_capacity(7));
+/// assert_eq!(writer.write(b"hello").unwrap(), 5);
+/// assert_eq!(writer.write(b"world").unwrap(), 2);
+/// assert_eq!(writer.write(b"!").unwrap(), 0);
+/// assert_eq!(writer.dropped_bytes(), 4);
+/// assert!(writer.buffer().iter().eq(b"hellowo"));
+///
+/// // Draining the buffer makes room again.
+/// writer.buffer_mut().pop();
+/// assert_eq!(writer.write(b"?!").unwrap(), 1);
+/// assert!(writer.buffer().iter().eq(b"ellowo?"));
+/// assert_eq!(writer.reset_dropped_bytes(), 5);
+/// assert_eq!(writer.dropped_bytes(), 0);
+/// ```
+pub struct LossyWriter {
+    buffer: RingBuffer<u8>,
+    dropped: usize,
+}
+impl LossyWriter {
+    /// Write in to `buffer`, keeping at most as many bytes as it currently has space for.
+    pub const fn new(buffer: RingBuffer<u8>) -> Self {
+        Self { buffer, dropped: 0 }
+    }
+    /// Get the buffer that is written to.
+    pub const fn buffer(&self) -> &RingBuffer<u8> {
+        &self.buffer
+    }
+    /// Get a mutable reference to the buffer that is written to.
+    pub fn buffer_mut(&mut self) -> &mut RingBuffer<u8> {
+        &mut self.buffer
+    }
+    /// Unwrap the buffer that is written to.
+    pub fn into_inner(self) -> RingBuffer<u8> {
+        self.buffer
+    }
+
+    /// Get the number of bytes dropped since the writer was created or the count was reset.
+    pub const fn dropped_bytes(&self) -> usize {
+        self.dropped
+    }
+    /// Reset the count of dropped bytes, returning the previous count.
+    pub fn reset_dropped_bytes(&mut self) -> usize {
+        core::mem::take(&mut self.dropped)
+    }
+}
+impl Write for LossyWriter {
+    type Error = Infallible;
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        let kept = self.buffer.with_spare_capacity(|spare| spare.extend_from_slice(slice));
+        self.dropped += slice.len() - kept;
+        Ok(kept)
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use core::mem::MaybeUninit;
+use crate::RingBuffer;
+use super::{append, BufStream, IoVecCursor, IoVecMut, Read, Write};
+
+/// A processing stage applied to the bytes passing through a [`MiddlewareBufStream`].
+/// ```rust
+/// # use std::{cell::RefCell, io, rc::Rc};
+/// # struct Mock { input: &'static [u8], output: Rc<RefCell<Vec<u8>>> }
+/// # impl io::Read for Mock {
+/// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { io::Read::read(&mut self.input, buf) }
+/// # }
+/// # impl io::Write for Mock {
+/// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { io::Write::write(&mut *self.output.borrow_mut(), buf) }
+/// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+/// # }
+/// use core::mem::MaybeUninit;
+/// use circulate::{BufStream, FromStd, Middleware, Read, RingBuffer, Write};
+///
+/// struct Xor(u8);
+/// impl Middleware for Xor {
+///     fn process_input(&mut self, buffer: &mut RingBuffer<u8>) {
+///         buffer.iter_mut().for_each(|byte| *byte ^= self.0);
+///     }
+///     fn process_output(&mut self, buffer: &mut RingBuffer<u8>) {
+///         buffer.iter_mut().for_each(|byte| *byte ^= self.0);
+///     }
+/// }
+///
+/// let output = Rc::new(RefCell::new(Vec::new()));
+/// let mock = Mock { input: &[b'h' ^ 0x5a, b'i' ^ 0x5a], output: output.clone() };
+/// let mut stream = BufStream::with_capacity(FromStd(mock), 16).compose(Xor(0x5a));
+///
+/// stream.write(b"ok").unwrap();
+/// stream.flush().unwrap();
+/// assert_eq!(&*output.borrow(), &[b'o' ^ 0x5a, b'k' ^ 0x5a]);
+///
+/// let mut buffer = [MaybeUninit::uninit(); 16];
+/// assert_eq!(stream.read(&mut buffer).unwrap(), 2);
+/// assert_eq!(unsafe { &*(&buffer[..2] as *const [MaybeUninit<u8>] as *const [u8]) }, b"hi");
+/// ```
+pub trait Middleware {
+    /// Process bytes that were just read from the stream.
+    /// `buffer` holds only the bytes from a single read, which may be modified, removed or added to.
+    fn process_input(&mut self, buffer: &mut RingBuffer<u8>);
+    /// Process bytes before they are added to the output buffer of the stream.
+    /// `buffer` holds only the bytes from a single write, which may be modified, removed or added to.
+    fn process_output(&mut self, buffer: &mut RingBuffer<u8>);
+}
+/// Middleware that passes all bytes through unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullMiddleware;
+impl Middleware for NullMiddleware {
+    fn process_input(&mut self, _: &mut RingBuffer<u8>) {}
+    fn process_output(&mut self, _: &mut RingBuffer<u8>) {}
+}
+/// Applies the first middleware and then the second, in both directions.
+impl<A: Middleware, B: Middleware> Middleware for (A, B) {
+    fn process_input(&mut self, buffer: &mut RingBuffer<u8>) {
+        self.0.process_input(buffer);
+        self.1.process_input(buffer);
+    }
+    fn process_output(&mut self, buffer: &mut RingBuffer<u8>) {
+        self.0.process_output(buffer);
+        self.1.process_output(buffer);
+    }
+}
+
+impl<S> BufStream<S> {
+    /// Process the bytes passing through this stream with `middleware`.
+    pub fn compose<M: Middleware>(self, middleware: M) -> MiddlewareBufStream<S, M> {
+        MiddlewareBufStream {
+            inner: self,
+            middleware,
+            staged: RingBuffer::new(),
+        }
+    }
+}
+
+/// A [`BufStream`] that applies [`Middleware`] to its input and output.
+pub struct MiddlewareBufStream<S, M> {
+    inner: BufStream<S>,
+    middleware: M,
+    /// The bytes of a single write while they are processed, and any processed bytes that did
+    /// not fit in the output buffer.
+    staged: RingBuffer<u8>,
+}
+impl<S, M: Middleware> MiddlewareBufStream<S, M> {
+    /// Add another stage of middleware, applied after those already composed.
+    /// ```rust
+    /// # use std::{cell::RefCell, io, rc::Rc};
+    /// # struct Mock { input: &'static [u8], output: Rc<RefCell<Vec<u8>>> }
+    /// # impl io::Read for Mock {
+    /// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { io::Read::read(&mut self.input, buf) }
+    /// # }
+    /// # impl io::Write for Mock {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { io::Write::write(&mut *self.output.borrow_mut(), buf) }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// use core::mem::MaybeUninit;
+    /// use circulate::{BufStream, FromStd, Middleware, NullMiddleware, Read, RingBuffer, Write};
+    ///
+    /// struct Map(fn(u8) -> u8);
+    /// impl Middleware for Map {
+    ///     fn process_input(&mut self, buffer: &mut RingBuffer<u8>) {
+    ///         buffer.iter_mut().for_each(|byte| *byte = (self.0)(*byte));
+    ///     }
+    ///     fn process_output(&mut self, buffer: &mut RingBuffer<u8>) {
+    ///         buffer.iter_mut().for_each(|byte| *byte = (self.0)(*byte));
+    ///     }
+    /// }
+    ///
+    /// let output = Rc::new(RefCell::new(Vec::new()));
+    /// let mock = Mock { input: &[1, 2, 3], output: output.clone() };
+    /// let mut stream = BufStream::with_capacity(FromStd(mock), 16)
+    ///     .compose(Map(|byte| byte + 1))
+    ///     .compose(NullMiddleware)
+    ///     .compose(Map(|byte| byte * 2));
+    ///
+    /// stream.write(&[1, 2, 3]).unwrap();
+    /// stream.flush().unwrap();
+    /// assert_eq!(&*output.borrow(), &[4, 6, 8]);
+    ///
+    /// let mut buffer = [MaybeUninit::uninit(); 16];
+    /// assert_eq!(stream.read(&mut buffer).unwrap(), 3);
+    /// assert_eq!(unsafe { &*(&buffer[..3] as *const [MaybeUninit<u8>] as *const [u8]) }, &[4, 6, 8]);
+    /// ```
+    pub fn compose<N: Middleware>(self, middleware: N) -> MiddlewareBufStream<S, (M, N)> {
+        MiddlewareBufStream {
+            inner: self.inner,
+            middleware: (self.middleware, middleware),
+            staged: self.staged,
+        }
+    }
+    /// Get a mutable reference to the middleware.
+    pub fn middleware_mut(&mut self) -> &mut M {
+        &mut self.middleware
+    }
+}
+impl<S: Read, M: Middleware> MiddlewareBufStream<S, M> {
+    /// Read from the stream if no input is buffered, processing the new bytes.
+    fn buffer_read_processed(&mut self) -> Result<(), <S as Read>::Error> {
+        // Input is only read once the buffer is empty, so the middleware sees each byte once.
+        if self.inner.input.is_empty() {
+            self.inner.buffer_read()?;
+            self.middleware.process_input(&mut self.inner.input);
+        }
+        Ok(())
+    }
+}
+impl<S: Read, M: Middleware> Read for MiddlewareBufStream<S, M> {
+    type Error = <S as Read>::Error;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        self.buffer_read_processed()?;
+        self.inner.read_into(buffer)
+    }
+    fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        self.buffer_read_processed()?;
+        Ok(self.inner.read_into_vectored(&mut IoVecCursor::new(buffers)))
+    }
+    fn is_buffered(&self) -> bool {
+        true
+    }
+}
+impl<S: Write, M: Middleware> Write for MiddlewareBufStream<S, M> {
+    type Error = <S as Write>::Error;
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        while self.staged.drain_to(&mut self.inner)? != 0 {}
+        self.inner.flush()
+    }
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        // Processed bytes left over when the output buffer could not grow are written first.
+        while self.staged.drain_to(&mut self.inner)? != 0 {}
+        if !self.staged.is_empty() {
+            return Ok(0);
+        }
+        let written = append(&mut self.staged, slice);
+        self.middleware.process_output(&mut self.staged);
+        while self.staged.drain_to(&mut self.inner)? != 0 {}
+        Ok(written)
+    }
+}
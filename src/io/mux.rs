@@ -0,0 +1,91 @@
+extern crate alloc;
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use super::{BufStream, Read, Write};
+
+/// A collection of buffered streams addressed by the ID returned when they were added.
+/// ```rust
+/// # use std::{cell::RefCell, io, rc::Rc};
+/// # struct Mock { input: &'static [u8], output: Rc<RefCell<Vec<u8>>> }
+/// # impl io::Read for Mock {
+/// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { io::Read::read(&mut self.input, buf) }
+/// # }
+/// # impl io::Write for Mock {
+/// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { io::Write::write(&mut *self.output.borrow_mut(), buf) }
+/// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+/// # }
+/// use core::mem::MaybeUninit;
+/// use circulate::{FromStd, Mux};
+///
+/// let (a_output, b_output) = (Rc::new(RefCell::new(Vec::new())), Rc::new(RefCell::new(Vec::new())));
+/// let mut mux = Mux::with_capacity(64);
+/// let a = mux.add(FromStd(Mock { input: b"first", output: a_output.clone() }));
+/// let b = mux.add(FromStd(Mock { input: b"second", output: b_output.clone() }));
+///
+/// let mut buffer = [MaybeUninit::uninit(); 16];
+/// assert_eq!(mux.read_from(a, &mut buffer).unwrap(), 5);
+/// assert_eq!(mux.read_from(b, &mut buffer).unwrap(), 6);
+/// assert_eq!(mux.read_from(a, &mut buffer).unwrap(), 0);
+///
+/// mux.write_to(b, b"reply").unwrap();
+/// assert!(b_output.borrow().is_empty());
+/// mux.flush_all().unwrap();
+/// assert!(a_output.borrow().is_empty());
+/// assert_eq!(&*b_output.borrow(), b"reply");
+/// ```
+pub struct Mux<S> {
+    streams: Vec<BufStream<S>>,
+    /// The initial buffer capacity of added streams.
+    capacity: usize,
+}
+impl<S: Read + Write> Mux<S> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            streams: Vec::new(),
+            capacity: 0,
+        }
+    }
+    /// Create a new multiplexer that buffers added streams with a capacity of at least
+    /// `capacity` bytes for both input and output buffers.
+    pub const fn with_capacity(capacity: usize) -> Self {
+        Self {
+            streams: Vec::new(),
+            capacity,
+        }
+    }
+    /// Add a stream, returning the ID used to address it.
+    pub fn add(&mut self, stream: S) -> usize {
+        self.streams.push(BufStream::with_capacity(stream, self.capacity));
+        self.streams.len() - 1
+    }
+    /// Get the number of streams.
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+    /// Returns if no streams have been added.
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// Read from the stream with the given `id`.
+    /// # Panics
+    /// Panics if there is no stream with the given `id`.
+    pub fn read_from(&mut self, id: usize, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, <S as Read>::Error> {
+        self.streams[id].read(buffer)
+    }
+    /// Write to the output buffer of the stream with the given `id`.
+    /// # Panics
+    /// Panics if there is no stream with the given `id`.
+    pub fn write_to(&mut self, id: usize, slice: &[u8]) -> Result<usize, <S as Write>::Error> {
+        self.streams[id].write(slice)
+    }
+    /// Flush the output buffers of every stream.
+    /// Stops at the first stream that fails.
+    pub fn flush_all(&mut self) -> Result<(), <S as Write>::Error> {
+        for stream in &mut self.streams {
+            stream.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+use core::mem::MaybeUninit;
+use super::{Read, Write};
+
+/// A writer that writes each `\n` as `\r\n`, for text protocols and terminals expecting CRLF
+/// line endings.
+///
+/// A `\n` that already follows a `\r` is written unchanged, so `\r\n` is not doubled even if the
+/// `\r` and `\n` are split across writes. A lone `\r` is also written unchanged.
+///
+/// Nothing is buffered. If the inner writer accepts the inserted `\r` but not the `\n` after it,
+/// the `\n` is reported as unwritten, and writing it again completes the `\r\n` rather than
+/// inserting a second `\r`.
+/// ```rust
+/// use circulate::{NewlineNormalizer, Write};
+///
+/// let mut writer = NewlineNormalizer::new(Vec::new());
+/// assert_eq!(writer.write(b"one\ntwo\r\nthree\r").unwrap(), 15);
+/// assert_eq!(writer.write(b"\nfour\rfive\n").unwrap(), 11);
+/// assert_eq!(writer.into_inner(), b"one\r\ntwo\r\nthree\r\nfour\rfive\r\n");
+///
+/// // Splitting the input anywhere makes no difference to the output.
+/// let input = b"\n\r\na\r\r\n\nb\rc\n\r";
+/// let expected = b"\r\n\r\na\r\r\n\r\nb\rc\r\n\r";
+/// for i in 0..=input.len() {
+///     for j in i..=input.len() {
+///         let mut writer = NewlineNormalizer::new(Vec::new());
+///         for chunk in [&input[..i], &input[i..j], &input[j..]] {
+///             assert_eq!(writer.write(chunk).unwrap(), chunk.len());
+///         }
+///         assert_eq!(writer.into_inner(), expected, "split at {i} and {j}");
+///     }
+/// }
+/// ```
+/// Short writes and errors from the inner writer never lose or duplicate the inserted `\r`.
+/// ```rust
+/// use circulate::{NewlineNormalizer, Write};
+///
+/// /// Accepts at most `limit` bytes per write, and fails every third write.
+/// struct Flaky { output: Vec<u8>, limit: usize, writes: usize }
+/// impl Write for Flaky {
+///     type Error = ();
+///     fn write(&mut self, slice: &[u8]) -> Result<usize, ()> {
+///         self.writes += 1;
+///         if self.writes % 3 == 0 {
+///             return Err(());
+///         }
+///         let len = slice.len().min(self.limit);
+///         self.output.extend_from_slice(&slice[..len]);
+///         Ok(len)
+///     }
+///     fn flush(&mut self) -> Result<(), ()> { Ok(()) }
+/// }
+///
+/// let input = b"a\nb\r\n\n\rc\r\r\nd\n";
+/// for limit in 1..4 {
+///     let mut writer = NewlineNormalizer::new(Flaky { output: Vec::new(), limit, writes: 0 });
+///     let mut rest = &input[..];
+///     while !rest.is_empty() {
+///         if let Ok(count) = writer.write(rest) {
+///             rest = &rest[count..];
+///         }
+///     }
+///     assert_eq!(writer.into_inner().output, b"a\r\nb\r\n\r\n\rc\r\r\nd\r\n");
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NewlineNormalizer<W> {
+    inner: W,
+    /// Set if the last byte written to the inner writer was `\r`.
+    after_cr: bool,
+}
+impl<W> NewlineNormalizer<W> {
+    /// Normalize the line endings written to `inner`.
+    pub const fn new(inner: W) -> Self {
+        Self { inner, after_cr: false }
+    }
+    /// Get a reference to the inner writer.
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    /// Get a mutable reference to the inner writer.
+    ///
+    /// Writing to it directly may leave a `\n` written next without its `\r`.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+    /// Get the inner writer back.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+impl<W: Write> NewlineNormalizer<W> {
+    /// Write each slice in turn as if by [`Write::write`], stopping at the first that is not
+    /// written completely. Returns the total number of bytes written from the slices.
+    ///
+    /// A `\r` at the end of one slice pairs with a `\n` at the start of the next.
+    /// ```rust
+    /// use circulate::NewlineNormalizer;
+    ///
+    /// let mut writer = NewlineNormalizer::new(Vec::new());
+    /// assert_eq!(writer.write_slices(&[b"a\r", b"\nb\n", b"", b"\n"]).unwrap(), 6);
+    /// assert_eq!(writer.into_inner(), b"a\r\nb\r\n\r\n");
+    /// ```
+    pub fn write_slices(&mut self, slices: &[&[u8]]) -> Result<usize, W::Error> {
+        let mut written = 0;
+        for slice in slices {
+            match self.write(slice) {
+                Ok(count) if count == slice.len() => written += count,
+                Ok(count) => return Ok(written + count),
+                Err(_) if written != 0 => return Ok(written),
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(written)
+    }
+}
+impl<W: Write> Write for NewlineNormalizer<W> {
+    type Error = W::Error;
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        let mut written = 0;
+        while let Some(&first) = slice.get(written) {
+            // The inserted `\r` is not counted as written, but is remembered so that it is never
+            // written twice.
+            let inserted = first == b'\n' && !self.after_cr;
+            let chunk: &[u8] = if inserted {
+                b"\r"
+            } else {
+                // Write up to the next `\n` that needs a `\r` inserted before it.
+                let rest = &slice[written..];
+                let end = rest[1..].iter().position(|byte| *byte == b'\n').map_or(rest.len(), |i| i + 1);
+                &rest[..end]
+            };
+            match self.inner.write(chunk) {
+                Ok(0) => break,
+                Ok(count) => {
+                    self.after_cr = chunk[count - 1] == b'\r';
+                    if !inserted {
+                        written += count;
+                    }
+                }
+                Err(_) if written != 0 => break,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(written)
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush()
+    }
+}
+
+/// A reader that reads each `\r\n` as `\n`, the counterpart of [`NewlineNormalizer`].
+///
+/// A lone `\r` is read unchanged. Whether a `\r` at the end of one read is part of a `\r\n`
+/// depends on the next byte, so it is held back until the next read, or until the end of the
+/// stream. Nothing else is buffered.
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use circulate::{NewlineReader, Read, ReadExt, RingBuffer};
+///
+/// let mut reader = NewlineReader::new(&b"one\r\ntwo\nthree\rfour\r\r\n\r"[..]);
+/// let mut buffer = RingBuffer::new();
+/// reader.read_to_end(&mut buffer).unwrap();
+/// assert!(buffer.iter().eq(b"one\ntwo\nthree\rfour\r\n\r"));
+///
+/// // The input may be split anywhere, and read in to buffers of any size.
+/// let input = b"\r\n\ra\r\r\n\nb\r\rc\r\n\r";
+/// let expected = b"\n\ra\r\n\nb\r\rc\n\r";
+/// for i in 0..=input.len() {
+///     for size in 1..4 {
+///         let mut reader = NewlineReader::new((&input[..i]).chain(&input[i..]));
+///         let mut output = Vec::new();
+///         let mut chunk = [MaybeUninit::uninit(); 3];
+///         loop {
+///             let read = reader.read(&mut chunk[..size]).unwrap();
+///             if read == 0 {
+///                 break;
+///             }
+///             output.extend(chunk[..read].iter().map(|byte| unsafe { byte.assume_init() }));
+///         }
+///         assert_eq!(output, expected, "split at {i} reading {size} at a time");
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NewlineReader<R> {
+    inner: R,
+    held: Held,
+}
+/// A byte read from the inner reader but not yet returned.
+#[derive(Debug, Clone, Copy, Default)]
+enum Held {
+    #[default]
+    Nothing,
+    /// A `\r` that is dropped if the next byte is `\n`.
+    Cr,
+    /// A byte that followed a lone `\r`, read to find out that it was lone. Never `\r`.
+    Byte(u8),
+}
+impl<R> NewlineReader<R> {
+    /// Normalize the line endings read from `inner`.
+    pub const fn new(inner: R) -> Self {
+        Self { inner, held: Held::Nothing }
+    }
+    /// Get a reference to the inner reader.
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Get a mutable reference to the inner reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Get the inner reader back, losing a byte that may have been held back.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+impl<R: Read> Read for NewlineReader<R> {
+    type Error = R::Error;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let filled = match self.held {
+                Held::Byte(byte) => {
+                    self.held = Held::Nothing;
+                    buffer[0] = MaybeUninit::new(byte);
+                    return Ok(1);
+                }
+                Held::Cr if buffer.len() == 1 => {
+                    // There is only room to read the next byte over the `\r`.
+                    self.held = Held::Nothing;
+                    if self.inner.read(buffer)? != 0 {
+                        // Safety: The byte was read and so is initialized.
+                        match unsafe { buffer[0].assume_init() } {
+                            b'\n' => return Ok(1),
+                            b'\r' => self.held = Held::Cr,
+                            byte => self.held = Held::Byte(byte),
+                        }
+                    }
+                    buffer[0] = MaybeUninit::new(b'\r');
+                    return Ok(1);
+                }
+                Held::Cr => {
+                    buffer[0] = MaybeUninit::new(b'\r');
+                    match self.inner.read(&mut buffer[1..])?.min(buffer.len() - 1) {
+                        0 => {
+                            self.held = Held::Nothing;
+                            return Ok(1);
+                        }
+                        read => read + 1,
+                    }
+                }
+                Held::Nothing => match self.inner.read(buffer)?.min(buffer.len()) {
+                    0 => return Ok(0),
+                    read => read,
+                },
+            };
+            // Safety: The first `filled` bytes were initialized above.
+            let bytes = unsafe { &mut *(&mut buffer[..filled] as *mut [MaybeUninit<u8>] as *mut [u8]) };
+            self.held = Held::Nothing;
+            let mut len = 0;
+            for i in 0..filled {
+                let byte = bytes[i];
+                if byte == b'\r' {
+                    match bytes.get(i + 1) {
+                        Some(b'\n') => continue,
+                        Some(_) => (),
+                        None => {
+                            self.held = Held::Cr;
+                            continue;
+                        }
+                    }
+                }
+                bytes[len] = byte;
+                len += 1;
+            }
+            // Only a held back `\r` was read, which must not be mistaken for the end of the stream.
+            if len != 0 {
+                return Ok(len);
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+/// An operation of a [`BufStream`](super::BufStream) or [`BufWriter`](super::BufWriter) on its
+/// underlying stream, reported to the observer set with
+/// [`BufStream::set_io_observer`](super::BufStream::set_io_observer) or
+/// [`BufWriter::set_io_observer`](super::BufWriter::set_io_observer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IoEvent {
+    /// The input buffer was refilled by one read from the stream, which returned `got` of the
+    /// `requested` bytes.
+    Refill { requested: usize, got: usize },
+    /// Buffered output was written to the stream in one write, which accepted `written` of the
+    /// `attempted` bytes.
+    Flush { attempted: usize, written: usize, reason: FlushReason },
+    /// Bytes were written to the stream without passing through the output buffer, such as
+    /// chunks queued with [`BufStream::write_chunk`](super::BufStream::write_chunk) or a write
+    /// too large for the buffer of a [`BufWriter`](super::BufWriter).
+    Bypass { bytes: usize },
+}
+
+/// Why buffered output was written to the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FlushReason {
+    /// The output was flushed on request.
+    Explicit,
+    /// The output was flushed before shutting down the stream.
+    Shutdown,
+    /// The output was flushed so that bytes could be written to the stream after it without
+    /// being buffered, such as a large frame written with
+    /// [`BufStream::write_framed`](super::BufStream::write_framed).
+    Bypass,
+    /// The output was flushed as it had waited too long, by
+    /// [`BufStream::flush_if_older_than`](super::BufStream::flush_if_older_than).
+    Deadline,
+    /// The output was flushed as the [`BufStream`](super::BufStream) was dropped.
+    Drop,
+    /// The output was flushed as a write did not fit in the full buffer of a
+    /// [`BufWriter`](super::BufWriter).
+    Full,
+}
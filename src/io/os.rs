@@ -0,0 +1,93 @@
+//! Conversions between the portable [`IoVec`](crate::IoVec) and [`IoVecMut`](crate::IoVecMut) types and
+//! the scatter-gather structures native to each platform.
+
+#[cfg(unix)]
+pub mod unix {
+    use core::{ffi::c_void, mem::{align_of, offset_of, size_of}};
+    use crate::{IoVec, IoVecMut};
+
+    /// ABI compatible with `struct iovec` from `<sys/uio.h>`.
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct RawIoVec {
+        pub iov_base: *mut c_void,
+        pub iov_len: usize,
+    }
+
+    // `IoVec` and `IoVecMut` are only guaranteed to match `struct iovec` on Unix platforms.
+    const _: () = {
+        assert!(size_of::<IoVec>() == size_of::<RawIoVec>());
+        assert!(align_of::<IoVec>() == align_of::<RawIoVec>());
+        assert!(offset_of!(IoVec, ptr) == offset_of!(RawIoVec, iov_base));
+        assert!(offset_of!(IoVec, len) == offset_of!(RawIoVec, iov_len));
+        assert!(size_of::<IoVecMut>() == size_of::<RawIoVec>());
+        assert!(align_of::<IoVecMut>() == align_of::<RawIoVec>());
+        assert!(offset_of!(IoVecMut, ptr) == offset_of!(RawIoVec, iov_base));
+        assert!(offset_of!(IoVecMut, len) == offset_of!(RawIoVec, iov_len));
+    };
+
+    /// View a slice of [`IoVec`] as an array of `struct iovec` suitable for `writev(2)`.
+    /// ```rust
+    /// use circulate::{IoVec, os::unix::as_iovec};
+    /// let (a, b) = (*b"ring", *b"buffer");
+    /// let buffers = [IoVec::new(&a), IoVec::new(&b)];
+    /// let iovecs = as_iovec(&buffers);
+    /// assert_eq!(iovecs[1].iov_base as *const u8, b.as_ptr());
+    /// assert_eq!(iovecs[1].iov_len, b.len());
+    /// ```
+    #[inline]
+    pub fn as_iovec<'a>(buffers: &'a [IoVec]) -> &'a [RawIoVec] {
+        // Safety: The layouts are asserted to be identical.
+        unsafe {
+            core::slice::from_raw_parts(buffers.as_ptr() as *const RawIoVec, buffers.len())
+        }
+    }
+    /// View a slice of [`IoVecMut`] as an array of `struct iovec` suitable for `readv(2)`.
+    #[inline]
+    pub fn as_iovec_mut<'a>(buffers: &'a mut [IoVecMut]) -> &'a mut [RawIoVec] {
+        // Safety: The layouts are asserted to be identical.
+        unsafe {
+            core::slice::from_raw_parts_mut(buffers.as_mut_ptr() as *mut RawIoVec, buffers.len())
+        }
+    }
+}
+
+#[cfg(windows)]
+pub mod windows {
+    use core::mem::{offset_of, size_of};
+    use crate::{IoVec, IoVecMut};
+
+    /// ABI compatible with `WSABUF` from `<winsock2.h>`.
+    /// Unlike `struct iovec` the length comes first and is limited to a `u32`.
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct WsaBuf {
+        pub len: u32,
+        pub buf: *mut u8,
+    }
+
+    const _: () = {
+        assert!(size_of::<WsaBuf>() == 2 * size_of::<usize>());
+        assert!(offset_of!(WsaBuf, len) == 0);
+        assert!(offset_of!(WsaBuf, buf) == size_of::<usize>());
+    };
+
+    /// Convert an [`IoVec`] to a `WSABUF`.
+    /// Returns [`None`] if the length of the buffer cannot be represented by a `WSABUF`.
+    #[inline]
+    pub fn to_wsabuf(buffer: &IoVec) -> Option<WsaBuf> {
+        Some(WsaBuf {
+            len: u32::try_from(buffer.len()).ok()?,
+            buf: buffer.as_ptr() as *mut u8,
+        })
+    }
+    /// Convert an [`IoVecMut`] to a `WSABUF`.
+    /// Returns [`None`] if the length of the buffer cannot be represented by a `WSABUF`.
+    #[inline]
+    pub fn to_wsabuf_mut(buffer: &IoVecMut) -> Option<WsaBuf> {
+        Some(WsaBuf {
+            len: u32::try_from(buffer.len()).ok()?,
+            buf: buffer.as_ptr(),
+        })
+    }
+}
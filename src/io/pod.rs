@@ -0,0 +1,119 @@
+use super::{Eof, ReadExactError};
+#[cfg(feature = "alloc")]
+use bytemuck::Pod;
+#[cfg(feature = "alloc")]
+use super::{BufStream, FillStatus, Read};
+
+#[cfg(feature = "alloc")]
+impl<S: Read> BufStream<S> {
+    /// View the next `size_of::<T>()` bytes of input as a `T`, reading from the stream until they
+    /// are buffered. The bytes are not consumed, so once the value has been inspected
+    /// [`BufStream::consume`] discards them.
+    ///
+    /// Where the bytes are contiguous in the input buffer and aligned for `T` the value is borrowed
+    /// from the buffer without copying. Otherwise, such as where they wrap around the end of the
+    /// buffer, they are copied out in to a [`PodRef::Copied`].
+    ///
+    /// As with [`ReadExt::read_pod`](super::ReadExt::read_pod), the bytes are taken in the layout
+    /// of `T` on the target and converting fields from the byte order of the protocol is left to
+    /// the caller.
+    /// ```rust
+    /// use bytemuck::{Pod, Zeroable};
+    /// use circulate::{BufStream, FromStd, PodRef};
+    ///
+    /// #[repr(C)]
+    /// #[derive(Clone, Copy, Debug, PartialEq)]
+    /// struct Header {
+    ///     kind: u16,
+    ///     flags: u16,
+    ///     len: u32,
+    /// }
+    /// // Safety: `Header` is `repr(C)` with no padding, and every field is `Pod`.
+    /// unsafe impl Zeroable for Header {}
+    /// unsafe impl Pod for Header {}
+    ///
+    /// let header = Header { kind: 1, flags: 0, len: u32::to_be(5) };
+    /// let mut bytes = bytemuck::bytes_of(&header).to_vec();
+    /// bytes.extend_from_slice(b"hello");
+    /// bytes.extend_from_slice(bytemuck::bytes_of(&Header { kind: 2, ..header }));
+    /// let mut stream = BufStream::with_capacity(FromStd(std::io::Cursor::new(bytes)), 16);
+    ///
+    /// let header = *stream.view_pod::<Header>().unwrap();
+    /// assert_eq!((header.kind, u32::from_be(header.len)), (1, 5));
+    /// stream.consume(size_of::<Header>() + 5);
+    ///
+    /// // The second header wraps around the end of the input buffer, so is copied.
+    /// let next = stream.view_pod::<Header>().unwrap();
+    /// assert!(matches!(next, PodRef::Copied(_)));
+    /// assert_eq!(next.kind, 2);
+    /// ```
+    pub fn view_pod<T: Pod>(&mut self) -> Result<PodRef<'_, T>, PodError<<S as Read>::Error>> {
+        let size = size_of::<T>();
+        match self.fill_at_least(size).map_err(PodError::Read)? {
+            FillStatus::Eof(eof) => return Err(PodError::UnexpectedEof(eof)),
+            FillStatus::Full(buffered) => return Err(PodError::Full { buffered }),
+            FillStatus::Complete | FillStatus::Partial(_) => (),
+        }
+        let (lhs, _) = self.input.as_slices();
+        if size != 0 && lhs.len() >= size && lhs.as_ptr().cast::<T>().is_aligned() {
+            return Ok(PodRef::Borrowed(bytemuck::from_bytes(&lhs[..size])));
+        }
+        let mut value = T::zeroed();
+        for (slot, byte) in bytemuck::bytes_of_mut(&mut value).iter_mut().zip(self.input.iter()) {
+            *slot = *byte;
+        }
+        Ok(PodRef::Copied(value))
+    }
+}
+
+/// A value viewed in the input of a [`BufStream`]. See [`BufStream::view_pod`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PodRef<'a, T> {
+    /// The value is borrowed from the input buffer.
+    Borrowed(&'a T),
+    /// The bytes of the value were not contiguous or not aligned, so were copied.
+    Copied(T),
+}
+#[cfg(feature = "alloc")]
+impl<T> core::ops::Deref for PodRef<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        match self {
+            Self::Borrowed(value) => value,
+            Self::Copied(value) => value,
+        }
+    }
+}
+
+/// The error returned by [`ReadExt::read_pod`](super::ReadExt::read_pod) and
+/// [`BufStream::view_pod`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PodError<E> {
+    /// The reader failed.
+    Read(E),
+    /// The reader ended part way through the value.
+    UnexpectedEof(Eof),
+    /// The input buffer of a [`BufStream`] is full and could not grow to hold the value.
+    #[cfg(feature = "alloc")]
+    Full { buffered: usize },
+}
+impl<E: core::fmt::Display> core::fmt::Display for PodError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Read(error) => error.fmt(f),
+            Self::UnexpectedEof(eof) => write!(f, "{eof} of a value"),
+            #[cfg(feature = "alloc")]
+            Self::Full { buffered } => write!(f, "input buffer is full with {buffered} bytes, too few for a value"),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for PodError<E> {}
+impl<E> From<ReadExactError<E>> for PodError<E> {
+    fn from(error: ReadExactError<E>) -> Self {
+        match error {
+            ReadExactError::UnexpectedEof(eof) => Self::UnexpectedEof(eof),
+            ReadExactError::Read(error) => Self::Read(error),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+extern crate alloc;
+use alloc::vec::Vec;
+use crate::RingBuffer;
+use super::{BufStream, Eof, FillStatus, Read, Write};
+
+/// Where a response read by [`BufStream::request`] ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseSpec<'a> {
+    /// The response is exactly this many bytes.
+    Exact(usize),
+    /// The response ends at the first occurrence of this byte.
+    Until(u8),
+    /// The response ends at the first occurrence of this sequence of bytes.
+    UntilSeq(&'a [u8]),
+}
+
+/// The error returned by [`BufStream::request`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestError<E> {
+    /// The underlying stream failed.
+    Stream(E),
+    /// The stream stopped accepting bytes part way through the request.
+    WriteZero,
+    /// The stream ended before the end of the response, which stays buffered.
+    UnexpectedEof(Eof),
+    /// The response would be longer than the maximum, or did not fit in the input buffer.
+    TooLong,
+}
+impl<E: core::fmt::Display> core::fmt::Display for RequestError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Stream(error) => error.fmt(f),
+            Self::WriteZero => f.write_str("stream stopped accepting bytes part way through the request"),
+            Self::UnexpectedEof(eof) => write!(f, "{eof} of the response"),
+            Self::TooLong => f.write_str("response too long"),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for RequestError<E> {}
+impl<E> From<E> for RequestError<E> {
+    fn from(error: E) -> Self {
+        Self::Stream(error)
+    }
+}
+
+impl<E, S: Read<Error = E> + Write<Error = E>> BufStream<S> {
+    /// Write all of `request`, flush it, and then read the response described by `response`,
+    /// for simple protocols that alternate between the two.
+    ///
+    /// Returns the response payload, without any terminator, which is consumed. Fails with
+    /// [`RequestError::TooLong`] rather than buffer a payload of more than `max_len` bytes. Input
+    /// that was already buffered counts as the start of the response, and input after the
+    /// response stays buffered for the next call.
+    /// ```rust
+    /// # use std::io;
+    /// use circulate::{BufStream, Eof, FromStd, RequestError, ResponseSpec};
+    ///
+    /// /// Answers each line written with its length, ending in a blank line.
+    /// #[derive(Default)]
+    /// struct Peer { line: Vec<u8>, replies: io::Cursor<Vec<u8>> }
+    /// impl io::Write for Peer {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         for &byte in buf {
+    ///             match byte {
+    ///                 b'\n' => {
+    ///                     let reply = format!("len {}\r\n\r\n", self.line.len());
+    ///                     self.replies.get_mut().extend_from_slice(reply.as_bytes());
+    ///                     self.line.clear();
+    ///                 }
+    ///                 byte => self.line.push(byte),
+    ///             }
+    ///         }
+    ///         Ok(buf.len())
+    ///     }
+    ///     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// }
+    /// impl io::Read for Peer {
+    ///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.replies.read(buf) }
+    /// }
+    /// # use io::Read;
+    ///
+    /// let mut stream = BufStream::with_capacity(FromStd(Peer::default()), 64);
+    /// let reply = stream.request(b"hello\n", ResponseSpec::UntilSeq(b"\r\n\r\n"), 64).unwrap();
+    /// assert!(reply.iter().eq(b"len 5"));
+    /// let reply = stream.request(b"hi\n", ResponseSpec::Until(b'\n'), 64).unwrap();
+    /// assert!(reply.iter().eq(b"len 2\r"));
+    /// // The rest of the previous response is still buffered.
+    /// let reply = stream.request(b"", ResponseSpec::Exact(2), 64).unwrap();
+    /// assert!(reply.iter().eq(b"\r\n"));
+    ///
+    /// let error = stream.request(b"a longer line\n", ResponseSpec::UntilSeq(b"\r\n\r\n"), 4);
+    /// assert!(matches!(error, Err(RequestError::TooLong)));
+    /// let error = stream.request(b"", ResponseSpec::Exact(32), 32);
+    /// assert!(matches!(error, Err(RequestError::UnexpectedEof(Eof { needed: 32, .. }))));
+    /// ```
+    /// The terminator is found however the response is split across reads and across the end of
+    /// the input buffer, including where a partial match must fall back to a shorter one.
+    /// ```rust
+    /// # use std::io;
+    /// use circulate::{BufStream, FromStd, ResponseSpec};
+    ///
+    /// /// Returns each scripted fragment from its own read, ignoring writes.
+    /// struct Scripted(Vec<Vec<u8>>);
+    /// impl io::Read for Scripted {
+    ///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    ///         let Some(fragment) = self.0.first_mut() else { return Ok(0) };
+    ///         let len = fragment.len().min(buf.len());
+    ///         buf[..len].copy_from_slice(&fragment[..len]);
+    ///         fragment.drain(..len);
+    ///         if fragment.is_empty() {
+    ///             self.0.remove(0);
+    ///         }
+    ///         Ok(len)
+    ///     }
+    /// }
+    /// impl io::Write for Scripted {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { Ok(buf.len()) }
+    ///     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// }
+    ///
+    /// let terminator = b"abac";
+    /// let response = b"ab abab ababa!abac";
+    /// let data = [&b"0123456789"[..], response, b"rest"].concat();
+    /// for first in 0..=data.len() {
+    ///     for second in first..=data.len() {
+    ///         let fragments = [&data[..first], &data[first..second], &data[second..]];
+    ///         let fragments = fragments.into_iter().filter(|f| !f.is_empty()).map(<[u8]>::to_vec).collect();
+    ///         let mut stream = BufStream::with_capacity(FromStd(Scripted(fragments)), 15);
+    ///         // Consume a short response first, so that the next wraps in the input buffer.
+    ///         let lead = stream.request(b"", ResponseSpec::Exact(10), 10).unwrap();
+    ///         assert!(lead.iter().eq(b"0123456789"));
+    ///         let payload = stream.request(b"", ResponseSpec::UntilSeq(terminator), 64).unwrap();
+    ///         assert!(payload.iter().eq(&response[..response.len() - terminator.len()]));
+    ///         let rest = stream.request(b"", ResponseSpec::Exact(4), 4).unwrap();
+    ///         assert!(rest.iter().eq(b"rest"));
+    ///     }
+    /// }
+    /// ```
+    pub fn request(&mut self, mut request: &[u8], response: ResponseSpec<'_>, max_len: usize) -> Result<RingBuffer<u8>, RequestError<E>> {
+        while !request.is_empty() {
+            match self.write(request)? {
+                0 => return Err(RequestError::WriteZero),
+                count => request = &request[count..],
+            }
+        }
+        self.flush()?;
+
+        match response {
+            ResponseSpec::Exact(len) => {
+                if len > max_len {
+                    return Err(RequestError::TooLong);
+                }
+                match self.fill_at_least(len)? {
+                    FillStatus::Eof(eof) => Err(RequestError::UnexpectedEof(eof)),
+                    FillStatus::Full(_) => Err(RequestError::TooLong),
+                    _ => Ok(self.take_response(len, 0)),
+                }
+            }
+            ResponseSpec::Until(byte) => {
+                let mut searched = 0;
+                loop {
+                    if let Some(index) = self.input.find_byte(byte, searched) {
+                        if index > max_len {
+                            return Err(RequestError::TooLong);
+                        }
+                        return Ok(self.take_response(index, 1));
+                    }
+                    searched = self.input.len();
+                    self.fill_response(searched, max_len + 1)?;
+                }
+            }
+            ResponseSpec::UntilSeq(terminator) => {
+                let mut search = Search::new(terminator);
+                let mut searched: usize = 0;
+                loop {
+                    let (lhs, rhs) = self.input.as_slices();
+                    let unsearched = match searched.checked_sub(lhs.len()) {
+                        None => lhs[searched..].iter().chain(rhs),
+                        Some(index) => [].iter().chain(&rhs[index..]),
+                    };
+                    if let Some(end) = search.feed(unsearched).map(|index| searched + index) {
+                        let len = end - terminator.len();
+                        if len > max_len {
+                            return Err(RequestError::TooLong);
+                        }
+                        return Ok(self.take_response(len, terminator.len()));
+                    }
+                    searched = self.input.len();
+                    self.fill_response(searched, max_len + terminator.len())?;
+                }
+            }
+        }
+    }
+    /// Read more of a response of which `searched` bytes are buffered without finding its end,
+    /// when the end must be within the first `limit` bytes.
+    fn fill_response(&mut self, searched: usize, limit: usize) -> Result<(), RequestError<E>> {
+        if searched >= limit || (self.input.reserve(1).is_err() && self.input.is_full()) {
+            return Err(RequestError::TooLong);
+        }
+        match self.fill_input()? {
+            0 => Err(RequestError::UnexpectedEof(Eof { needed: searched + 1, got: searched })),
+            _ => Ok(()),
+        }
+    }
+    /// Take the first `len` bytes of input, and consume the `terminator` bytes after them.
+    fn take_response(&mut self, len: usize, terminator: usize) -> RingBuffer<u8> {
+        let payload = self.input.split_to(len);
+        self.pushback = self.pushback.saturating_sub(len);
+        self.consume(terminator);
+        payload
+    }
+}
+
+/// An incremental Knuth-Morris-Pratt search for a sequence of bytes, that may be fed the bytes
+/// searched in pieces.
+struct Search<'a> {
+    pattern: &'a [u8],
+    /// The length of the longest proper prefix of `pattern[..=i]` that is also its suffix.
+    fallback: Vec<usize>,
+    /// The length of the prefix of `pattern` matched by the end of the bytes fed so far.
+    matched: usize,
+}
+impl<'a> Search<'a> {
+    fn new(pattern: &'a [u8]) -> Self {
+        let mut fallback = Vec::with_capacity(pattern.len());
+        let mut len = 0;
+        for (i, &byte) in pattern.iter().enumerate() {
+            while len > 0 && byte != pattern[len] {
+                len = fallback[len - 1];
+            }
+            if i > 0 && byte == pattern[len] {
+                len += 1;
+            }
+            fallback.push(len);
+        }
+        Self { pattern, fallback, matched: 0 }
+    }
+    /// Search the next bytes, returning the index in them just past the first match.
+    fn feed<'b>(&mut self, bytes: impl Iterator<Item = &'b u8>) -> Option<usize> {
+        if self.pattern.is_empty() {
+            return Some(0);
+        }
+        for (index, &byte) in bytes.enumerate() {
+            while self.matched > 0 && byte != self.pattern[self.matched] {
+                self.matched = self.fallback[self.matched - 1];
+            }
+            if byte == self.pattern[self.matched] {
+                self.matched += 1;
+            }
+            if self.matched == self.pattern.len() {
+                return Some(index + 1);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,186 @@
+use core::mem::MaybeUninit;
+use crate::RingBuffer;
+use super::{IoVecCursor, IoVecMut, Read, Write};
+
+impl RingBuffer<u8> {
+    /// Read the buffered bytes, reporting the end of the stream once the buffer is empty.
+    /// See [`DrainReader`].
+    pub fn drain_reader(&mut self) -> DrainReader<'_> {
+        DrainReader(self)
+    }
+    /// Read the buffered bytes, failing with [`WouldBlock`] while the buffer is empty.
+    /// See [`TailReader`].
+    pub fn tail_reader(&mut self) -> TailReader<'_> {
+        TailReader(self)
+    }
+    /// Move buffered bytes to the buffers behind `cursor`, returning the number of bytes moved.
+    fn read_to_cursor(&mut self, cursor: &mut IoVecCursor) -> usize {
+        let (lhs, rhs) = self.as_slices();
+        let read = match cursor.write(lhs) {
+            len if len == lhs.len() => len + cursor.write(rhs),
+            len => len,
+        };
+        // Safety: No more than the buffered bytes were copied out.
+        unsafe { self.advance_read_unchecked(read) };
+        read
+    }
+}
+
+/// Takes bytes from the read end, reporting the end of the stream once the buffer is empty, the
+/// same as a [`DrainReader`]. Use [`RingBuffer::tail_reader`] where an empty buffer only means more
+/// bytes have not arrived yet.
+///
+/// Vectored reads fill the buffers in order, across the end of the allocation.
+/// ```rust
+/// use circulate::{IoVecMut, Read, RingBuffer};
+///
+/// let mut pipe = RingBuffer::with_exact_capacity(8);
+/// pipe.extend_from_slice(b"xxxxxabc").unwrap();
+/// pipe.pop_slice(&mut [0; 5]);
+/// pipe.extend_from_slice(b"defg").unwrap();
+///
+/// let (mut a, mut b) = ([0; 2], [0; 4]);
+/// assert_eq!(pipe.read_vectored(&mut [IoVecMut::new(&mut a), IoVecMut::new(&mut b)]), Ok(6));
+/// assert_eq!((&a, &b), (b"ab", b"cdef"));
+/// assert!(pipe.iter().eq(b"g"));
+/// ```
+impl Read for RingBuffer<u8> {
+    type Error = core::convert::Infallible;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        self.read_vectored(&mut [IoVecMut::maybe_uninit(buffer)])
+    }
+    fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        Ok(self.read_to_cursor(&mut IoVecCursor::new(buffers)))
+    }
+    fn is_buffered(&self) -> bool {
+        true
+    }
+}
+/// Appends bytes to the write end, growing the buffer as needed. Once the buffer has reached its
+/// maximum capacity writes are short, taking only the bytes that fit.
+/// ```rust
+/// use circulate::{BufStream, Read, RingBuffer, Write};
+///
+/// let mut bounded = RingBuffer::new();
+/// bounded.set_max_capacity(8);
+/// assert_eq!(bounded.write(b"hello"), Ok(5));
+/// assert_eq!(bounded.write(b"world"), Ok(2));
+/// assert!(bounded.iter().eq(b"hellowo"));
+///
+/// // A buffer as the stream of a `BufStream`, reading back what was written.
+/// let mut stream = BufStream::new(RingBuffer::new());
+/// stream.write(b"echo").unwrap();
+/// stream.flush().unwrap();
+/// let mut echoed = String::new();
+/// stream.read_to_string(&mut echoed).unwrap();
+/// assert_eq!(echoed, "echo");
+/// ```
+impl Write for RingBuffer<u8> {
+    type Error = core::convert::Infallible;
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        // Growing is best effort, as a bounded buffer still takes what fits.
+        let _ = self.reserve(slice.len());
+        Ok(self.with_spare_capacity(|spare| spare.extend_from_slice(slice)))
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// A reader that takes bytes from the front of a [`RingBuffer`], and reports the end of the
+/// stream once it is empty. See [`RingBuffer::drain_reader`].
+///
+/// This suits a buffer that the producer has finished with, so that reading until the end, such
+/// as with [`Read::read_to_end`], stops once every byte has been read.
+/// ```rust
+/// use circulate::{Read, RingBuffer};
+///
+/// let mut source = RingBuffer::with_capacity(8);
+/// source.extend_from_slice(b"abcdef").unwrap();
+/// source.pop_slice(&mut [0; 4]);
+/// source.extend_from_slice(b"ghij").unwrap();
+///
+/// let mut sink = RingBuffer::new();
+/// assert_eq!(source.drain_reader().read_to_end(&mut sink).unwrap(), 6);
+/// assert!(sink.iter().eq(b"efghij"));
+/// assert!(source.is_empty());
+/// ```
+pub struct DrainReader<'a>(&'a mut RingBuffer<u8>);
+impl Read for DrainReader<'_> {
+    type Error = core::convert::Infallible;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        self.read_vectored(&mut [IoVecMut::maybe_uninit(buffer)])
+    }
+    fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        Ok(self.0.read_to_cursor(&mut IoVecCursor::new(buffers)))
+    }
+    fn is_buffered(&self) -> bool {
+        true
+    }
+}
+
+/// A reader that takes bytes from the front of a [`RingBuffer`], and fails with [`WouldBlock`]
+/// while it is empty rather than reporting the end of the stream. See
+/// [`RingBuffer::tail_reader`].
+///
+/// This suits a buffer that is still being produced in to, such as a pipe between tasks, where
+/// running out of bytes only means more have not arrived yet. Reading until the end, such as with
+/// [`Read::read_to_end`], hands back control with the error once the buffer is empty, keeping
+/// the bytes read until then. With `std` the error converts to an [`std::io::Error`] of kind
+/// [`WouldBlock`](std::io::ErrorKind::WouldBlock).
+/// ```rust
+/// use core::mem::MaybeUninit;
+/// use circulate::{BufStream, Read, RingBuffer, WouldBlock};
+///
+/// let mut pipe = RingBuffer::new();
+/// pipe.extend_from_slice(b"partial ").unwrap();
+///
+/// let mut sink = RingBuffer::new();
+/// assert_eq!(pipe.tail_reader().read_to_end(&mut sink), Err(WouldBlock));
+/// assert!(sink.iter().eq(b"partial "));
+///
+/// // Reading in to no space is not blocked.
+/// assert_eq!(pipe.tail_reader().read(&mut []), Ok(0));
+///
+/// // More is produced, and reading carries on.
+/// pipe.extend_from_slice(b"line").unwrap();
+/// let mut stream = BufStream::new(pipe.tail_reader());
+/// let mut buffer = [MaybeUninit::uninit(); 16];
+/// assert_eq!(stream.read(&mut buffer), Ok(4));
+/// assert_eq!(stream.read(&mut buffer), Err(WouldBlock));
+///
+/// let error = std::io::Error::from(WouldBlock);
+/// assert_eq!(error.kind(), std::io::ErrorKind::WouldBlock);
+/// ```
+pub struct TailReader<'a>(&'a mut RingBuffer<u8>);
+impl Read for TailReader<'_> {
+    type Error = WouldBlock;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        self.read_vectored(&mut [IoVecMut::maybe_uninit(buffer)])
+    }
+    fn read_vectored(&mut self, buffers: &mut [IoVecMut]) -> Result<usize, Self::Error> {
+        if self.0.is_empty() && buffers.iter().any(|buffer| !buffer.is_empty()) {
+            return Err(WouldBlock);
+        }
+        Ok(self.0.read_to_cursor(&mut IoVecCursor::new(buffers)))
+    }
+    fn is_buffered(&self) -> bool {
+        true
+    }
+}
+
+/// The error returned by a [`TailReader`] while its buffer is empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WouldBlock;
+impl core::fmt::Display for WouldBlock {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("no bytes are buffered yet")
+    }
+}
+impl core::error::Error for WouldBlock {}
+#[cfg(not(feature = "no_std"))]
+impl From<WouldBlock> for std::io::Error {
+    fn from(_: WouldBlock) -> Self {
+        std::io::ErrorKind::WouldBlock.into()
+    }
+}
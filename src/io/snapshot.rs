@@ -0,0 +1,155 @@
+use crate::{CapacityError, RingBuffer};
+use super::{IoVecMut, Read, Write};
+
+/// Identifies a snapshot written by [`RingBuffer::save_to`].
+const MAGIC: [u8; 4] = *b"CIRC";
+/// The version of the snapshot format, incremented if the format changes.
+const VERSION: u8 = 1;
+/// The magic, version and little endian `u64` length.
+const HEADER_LEN: usize = MAGIC.len() + 1 + 8;
+/// The most bytes reserved at once while loading, so that a corrupted length cannot allocate
+/// more than the reader actually provides.
+const LOAD_CHUNK: usize = 64 * 1024;
+
+impl RingBuffer<u8> {
+    /// Write a snapshot of the buffered bytes to `writer`, to be restored by
+    /// [`RingBuffer::load_from`]. The buffer is not modified.
+    ///
+    /// The snapshot is a 13 byte header of the magic bytes `CIRC`, a version byte and the number
+    /// of bytes as a little endian `u64`, followed by the bytes in order from the read end.
+    /// Returns the number of bytes written, which is less than `len() + 13` only if the writer
+    /// stopped accepting bytes.
+    /// ```rust
+    /// use circulate::{LoadError, RingBuffer};
+    ///
+    /// let mut buffer = RingBuffer::with_capacity(7);
+    /// buffer.extend_from_slice(b"telem").unwrap();
+    /// buffer.pop_slice(&mut [0; 3]);
+    /// buffer.extend_from_slice(b"etry!").unwrap();
+    /// assert!(!buffer.as_mut_slices().1.is_empty());
+    ///
+    /// let mut snapshot = Vec::new();
+    /// assert_eq!(buffer.save_to(&mut snapshot).unwrap(), 13 + 7);
+    /// assert_eq!(snapshot[..13], *b"CIRC\x01\x07\0\0\0\0\0\0\0");
+    /// let restored = RingBuffer::load_from(&mut &snapshot[..]).unwrap();
+    /// assert!(restored.iter().eq(b"emetry!"));
+    ///
+    /// // An empty buffer round trips too.
+    /// let mut snapshot = Vec::new();
+    /// RingBuffer::new().save_to(&mut snapshot).unwrap();
+    /// assert!(RingBuffer::load_from(&mut &snapshot[..]).unwrap().is_empty());
+    ///
+    /// // Damaged snapshots are rejected.
+    /// let load = |bytes: &[u8]| RingBuffer::load_from(&mut &bytes[..]).err();
+    /// let mut snapshot = Vec::new();
+    /// buffer.save_to(&mut snapshot).unwrap();
+    /// assert!(matches!(load(&snapshot[..snapshot.len() - 1]), Some(LoadError::Truncated)));
+    /// assert!(matches!(load(&snapshot[..5]), Some(LoadError::Truncated)));
+    /// assert!(matches!(load(b"circ\x01\0\0\0\0\0\0\0\0"), Some(LoadError::BadMagic)));
+    /// assert!(matches!(load(b"CIRC\x02\0\0\0\0\0\0\0\0"), Some(LoadError::UnsupportedVersion(2))));
+    /// assert!(matches!(load(b"CIRC\x01\xff\xff\xff\xff\xff\xff\xff\xff"), Some(LoadError::TooLong(u64::MAX))));
+    /// // A length larger than the input allocates no more than the input provides.
+    /// assert!(matches!(load(b"CIRC\x01\0\0\0\0\0\0\0\x10abc"), Some(LoadError::Truncated)));
+    /// ```
+    pub fn save_to<W: Write + ?Sized>(&self, writer: &mut W) -> Result<usize, W::Error> {
+        let mut header = [0; HEADER_LEN];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4] = VERSION;
+        header[5..].copy_from_slice(&(self.len() as u64).to_le_bytes());
+        let (lhs, rhs) = self.as_slices();
+        let mut written = 0;
+        for mut slice in [&header[..], lhs, rhs] {
+            while !slice.is_empty() {
+                match writer.write(slice)?.min(slice.len()) {
+                    0 => return Ok(written),
+                    count => {
+                        written += count;
+                        slice = &slice[count..];
+                    }
+                }
+            }
+        }
+        Ok(written)
+    }
+    /// Restore a buffer from a snapshot written by [`RingBuffer::save_to`], reading exactly the
+    /// snapshot from `reader`. See [`RingBuffer::save_to`] for an example.
+    ///
+    /// The header is validated before anything is allocated, and the buffer then grows as the
+    /// bytes are read, so a damaged length fails with [`LoadError::Truncated`] rather than
+    /// allocating for bytes that never arrive.
+    pub fn load_from<R: Read + ?Sized>(reader: &mut R) -> Result<Self, LoadError<R::Error>> {
+        let mut header = [0; HEADER_LEN];
+        let mut filled = 0;
+        while filled < HEADER_LEN {
+            let mut buffer = IoVecMut::new(&mut header[filled..]);
+            match reader.read(buffer.as_maybe_uninit_slice()).map_err(LoadError::Read)? {
+                0 => return Err(LoadError::Truncated),
+                count => filled += count.min(HEADER_LEN - filled),
+            }
+        }
+        if header[..4] != MAGIC {
+            return Err(LoadError::BadMagic);
+        }
+        if header[4] != VERSION {
+            return Err(LoadError::UnsupportedVersion(header[4]));
+        }
+        let mut len = [0; 8];
+        len.copy_from_slice(&header[5..]);
+        let len = u64::from_le_bytes(len);
+        let len = match usize::try_from(len) {
+            Ok(len) if len < isize::MAX as usize => len,
+            _ => return Err(LoadError::TooLong(len)),
+        };
+
+        let mut buffer = RingBuffer::new();
+        while buffer.len() < len {
+            let remaining = len - buffer.len();
+            buffer.reserve(remaining.min(LOAD_CHUNK)).map_err(LoadError::Capacity)?;
+            let read = buffer.with_spare_capacity(|spare| {
+                let (lhs, rhs) = spare.unfilled_mut();
+                let lhs_len = lhs.len().min(remaining);
+                let rhs_len = rhs.len().min(remaining - lhs_len);
+                let count = reader
+                    .read_vectored(&mut [IoVecMut::maybe_uninit(&mut lhs[..lhs_len]), IoVecMut::maybe_uninit(&mut rhs[..rhs_len])])?
+                    .min(lhs_len + rhs_len);
+                // Safety: The reader initialized the bytes it read.
+                unsafe { spare.assume_filled(count) };
+                Ok(count)
+            });
+            if read.map_err(LoadError::Read)? == 0 {
+                return Err(LoadError::Truncated);
+            }
+        }
+        Ok(buffer)
+    }
+}
+
+/// The error returned by [`RingBuffer::load_from`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError<E> {
+    /// The reader failed.
+    Read(E),
+    /// The reader ended before the whole snapshot was read.
+    Truncated,
+    /// The input does not start with the magic bytes of a snapshot.
+    BadMagic,
+    /// The snapshot has a version of the format that is not supported.
+    UnsupportedVersion(u8),
+    /// The length in the header is too large for any buffer.
+    TooLong(u64),
+    /// The buffer could not grow to hold the bytes.
+    Capacity(CapacityError),
+}
+impl<E: core::fmt::Display> core::fmt::Display for LoadError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Read(error) => error.fmt(f),
+            Self::Truncated => f.write_str("snapshot is truncated"),
+            Self::BadMagic => f.write_str("input is not a ring buffer snapshot"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported snapshot version {version}"),
+            Self::TooLong(len) => write!(f, "snapshot length of {len} bytes is too large"),
+            Self::Capacity(error) => error.fmt(f),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for LoadError<E> {}
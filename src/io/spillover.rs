@@ -0,0 +1,195 @@
+extern crate alloc;
+use core::mem::MaybeUninit;
+use std::io::{self, Seek, SeekFrom};
+use crate::RingBuffer;
+use super::{append, FromStd, ReadExt};
+
+/// A byte queue that holds up to a fixed number of bytes in memory, spilling the rest to a
+/// backing store such as a temporary file, so that a slow consumer does not require unbounded
+/// memory.
+///
+/// Bytes are written with [`Write`](super::Write) and read back in the same order with
+/// [`Read`](super::Read), which moves spilled bytes back in to memory as it empties. Once
+/// anything has been spilled all later writes are spilled too, until the backing store has been
+/// drained. The backing store is then truncated and reused from the start, so the space taken by
+/// a backlog is given back once it has been read.
+/// ```rust
+/// use std::io::Cursor;
+/// use circulate::{Read, SpilloverBuffer, Write};
+///
+/// let pattern: Vec<u8> = (0..1 << 20).map(|i: u32| (i * 7 % 251) as u8).collect();
+/// let mut buffer = SpilloverBuffer::new(Cursor::new(Vec::new()), 63);
+/// let mut output = Vec::with_capacity(pattern.len());
+/// let mut chunk = [core::mem::MaybeUninit::uninit(); 100];
+/// for (i, slice) in pattern.chunks(4093).enumerate() {
+///     assert_eq!(buffer.write(slice).unwrap(), slice.len());
+///     assert!(buffer.in_memory_len() <= 63);
+///     // Consume slower than producing, draining completely now and then.
+///     let reads = if i % 16 == 0 { usize::MAX } else { 20 };
+///     for _ in 0..reads {
+///         let read = buffer.read(&mut chunk).unwrap();
+///         if read == 0 {
+///             assert_eq!(buffer.spilled_len(), 0);
+///             break;
+///         }
+///         output.extend(chunk[..read].iter().map(|byte| unsafe { byte.assume_init() }));
+///     }
+/// }
+/// let mut rest = circulate::RingBuffer::new();
+/// buffer.read_to_end(&mut rest).unwrap();
+/// output.extend(rest.iter());
+/// assert!(output == pattern);
+/// assert!(buffer.is_empty());
+/// assert!(buffer.into_inner().into_inner().is_empty());
+/// ```
+pub struct SpilloverBuffer<F> {
+    memory: RingBuffer<u8>,
+    store: F,
+    /// The offset of the next spilled byte to read back.
+    read: u64,
+    /// The offset after the last spilled byte.
+    write: u64,
+}
+impl<F: SpillStore> SpilloverBuffer<F> {
+    /// Create a buffer holding at least `memory_capacity` bytes in memory, rounded up as by
+    /// [`RingBuffer::capacity_for`], and spilling to `store` from its start.
+    ///
+    /// At least one byte is held in memory, as spilled bytes are read back through memory.
+    /// ```rust
+    /// use std::io::Cursor;
+    /// use circulate::{Read, RingBuffer, SpilloverBuffer, Write};
+    ///
+    /// let mut buffer = SpilloverBuffer::new(Cursor::new(Vec::new()), 0);
+    /// assert_eq!(buffer.write(b"spilled").unwrap(), 7);
+    /// assert!(buffer.spilled_len() != 0);
+    /// let mut output = RingBuffer::new();
+    /// buffer.read_to_end(&mut output).unwrap();
+    /// assert!(output.iter().eq(b"spilled"));
+    /// ```
+    pub fn new(store: F, memory_capacity: usize) -> Self {
+        let mut memory = RingBuffer::with_capacity(memory_capacity.max(1));
+        memory.set_max_capacity(memory.capacity());
+        Self { memory, store, read: 0, write: 0 }
+    }
+    /// Get the number of bytes held in memory.
+    pub fn in_memory_len(&self) -> usize {
+        self.memory.len()
+    }
+    /// Get the number of bytes spilled to the backing store.
+    pub fn spilled_len(&self) -> u64 {
+        self.write - self.read
+    }
+    /// Returns if no bytes are held in memory or spilled.
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty() && self.spilled_len() == 0
+    }
+    /// Get the backing store.
+    pub fn into_inner(self) -> F {
+        self.store
+    }
+    /// Move as many spilled bytes back in to memory as there is space for.
+    /// Returns the number of bytes moved.
+    ///
+    /// If the store fails after some bytes were moved, those are reported and the error is left
+    /// to the next refill.
+    pub fn refill(&mut self) -> io::Result<usize> {
+        let mut moved = 0;
+        while self.spilled_len() != 0 && !self.memory.is_full() {
+            let count = match self.read_spilled() {
+                Ok(count) => count,
+                Err(_) if moved != 0 => return Ok(moved),
+                Err(error) => return Err(error),
+            };
+            self.read += count as u64;
+            moved += count;
+        }
+        if self.spilled_len() == 0 && self.write != 0 {
+            // Give back the space of the drained bytes, and reuse the store from the start.
+            self.store.truncate(0)?;
+            self.read = 0;
+            self.write = 0;
+        }
+        Ok(moved)
+    }
+    /// Read the next spilled bytes in to memory with one read of the store.
+    fn read_spilled(&mut self) -> io::Result<usize> {
+        self.store.seek(SeekFrom::Start(self.read))?;
+        let spilled = usize::try_from(self.spilled_len()).unwrap_or(usize::MAX);
+        // Note: The store may hold stale bytes past the spilled bytes, so are never read.
+        match self.memory.fill_from(&mut FromStd(&mut self.store).take(spilled))? {
+            0 => Err(io::ErrorKind::UnexpectedEof.into()),
+            count => Ok(count),
+        }
+    }
+}
+impl<F: SpillStore> super::Write for SpilloverBuffer<F> {
+    type Error = io::Error;
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+        // Bytes may only be kept in memory while nothing is spilled, to preserve their order.
+        let kept = if self.spilled_len() == 0 {
+            append(&mut self.memory, slice)
+        } else {
+            0
+        };
+        if kept == slice.len() {
+            return Ok(kept);
+        }
+        let spilled = self.store.seek(SeekFrom::Start(self.write))
+            .and_then(|_| io::Write::write(&mut self.store, &slice[kept..]));
+        match spilled {
+            Ok(count) => {
+                self.write += count as u64;
+                Ok(kept + count)
+            }
+            // Report the bytes that were kept, leaving the error to the next write.
+            Err(_) if kept != 0 => Ok(kept),
+            Err(error) => Err(error),
+        }
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        io::Write::flush(&mut self.store)
+    }
+}
+impl<F: SpillStore> super::Read for SpilloverBuffer<F> {
+    type Error = io::Error;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        if self.memory.is_empty() {
+            self.refill()?;
+        }
+        Ok(self.memory.pop_slice_uninit(buffer))
+    }
+    fn is_buffered(&self) -> bool {
+        true
+    }
+}
+
+/// A backing store for a [`SpilloverBuffer`], which can be truncated once the bytes spilled to it
+/// have all been read back.
+pub trait SpillStore: io::Read + io::Write + Seek {
+    /// Shorten the store to `len` bytes, giving back the space of the rest.
+    fn truncate(&mut self, len: u64) -> io::Result<()>;
+}
+impl SpillStore for std::fs::File {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.set_len(len)
+    }
+}
+impl SpillStore for &std::fs::File {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.set_len(len)
+    }
+}
+impl SpillStore for io::Cursor<alloc::vec::Vec<u8>> {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        let len = usize::try_from(len).unwrap_or(usize::MAX);
+        self.get_mut().truncate(len);
+        Ok(())
+    }
+}
+impl SpillStore for io::Cursor<&mut alloc::vec::Vec<u8>> {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        let len = usize::try_from(len).unwrap_or(usize::MAX);
+        self.get_mut().truncate(len);
+        Ok(())
+    }
+}
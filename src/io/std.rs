@@ -1,43 +1,878 @@
-use core::mem::MaybeUninit;
+use core::{mem::MaybeUninit, ops::Deref};
 use std::io;
+#[cfg(feature = "alloc")]
+use std::{sync::Arc, time::Duration};
+#[cfg(feature = "alloc")]
+use crate::RingBuffer;
 
-impl<T: io::Read> super::Read for T {
-    type Error = io::Error;
-    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
-        // Currently there is no stable way to read in to an uninitialised buffer so pointlessly initlialise it.
-        // Safety:
-        // - A slice upholds the guarantees of write_bytes and 0 is a valid bit pattern for `MaybeUninit<u8>`.
-        // - After writing all 0's it is safe to reconstruct the slice as initialised.
-        unsafe {
+/// Read from a standard reader in to a possibly uninitialized `buffer`, zeroing it first if
+/// `zero` is set.
+///
+/// Only readers implementing [`ReadIntoUninit`] may be passed a `zero` of `false`.
+#[inline]
+fn read<R: io::Read + ?Sized>(reader: &mut R, buffer: &mut [MaybeUninit<u8>], zero: bool) -> io::Result<usize> {
+    // Note: There is no stable way to read in to an uninitialized buffer, so unless the reader
+    // promises not to look at it the buffer is pointlessly initialized.
+    // Safety:
+    // - A slice upholds the guarantees of write_bytes and 0 is a valid bit pattern for `MaybeUninit<u8>`.
+    // - The slice is either initialized or only written to by the reader, as `ReadIntoUninit`
+    //   requires.
+    unsafe {
+        if zero {
             core::ptr::write_bytes(buffer.as_mut_ptr(), 0, buffer.len());
-            <T as io::Read>::read(self, core::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len()))
         }
+        reader.read(core::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len()))
     }
-    fn read_vectored(&mut self, buffers: &mut [super::IoVecMut]) -> Result<usize, Self::Error> {
-        // Currently there is no stable way to read in to an uninitialised buffer so pointlessly initlialise it.
-        // Safety:
-        // - A slice upholds the guarantees of write_bytes and 0 is a valid bit pattern for `MaybeUninit<u8>`.
-        // - After writing all 0's it is safe to reconstruct the slice as initialised IoSliceMut's.
-        unsafe {
-            // TODO: use syslib IoVecMut's which could be made to allow a no-op conversion to std's IoSliceMut.
-            let mut buffers: Vec<_> = buffers.into_iter().map(|buffer| {
+}
+/// Read from a standard reader in to possibly uninitialized `buffers`, zeroing them first if
+/// `zero` is set.
+///
+/// Only readers implementing [`ReadIntoUninit`] may be passed a `zero` of `false`.
+#[cfg(unix)]
+#[inline]
+fn read_vectored<R: io::Read + ?Sized>(reader: &mut R, buffers: &mut [super::IoVecMut], zero: bool) -> io::Result<usize> {
+    // Safety:
+    // - A slice upholds the guarantees of write_bytes and 0 is a valid bit pattern for `MaybeUninit<u8>`.
+    // - On Unix `IoSliceMut` is guaranteed to be ABI compatible with `struct iovec`, as
+    //   `IoVecMut` is asserted to be in `os::unix`.
+    // - The slices are either initialized or only written to by the reader, as `ReadIntoUninit`
+    //   requires.
+    unsafe {
+        if zero {
+            for buffer in buffers.iter_mut() {
                 core::ptr::write_bytes(buffer.as_ptr(), 0, buffer.len());
-                let slice = core::slice::from_raw_parts_mut(buffer.as_ptr(), buffer.len());
-                io::IoSliceMut::new(slice)
-            }).collect();
-            <T as io::Read>::read_vectored(self, &mut buffers)
+            }
         }
+        reader.read_vectored(core::slice::from_raw_parts_mut(buffers.as_mut_ptr() as *mut io::IoSliceMut, buffers.len()))
     }
 }
+/// Read from a standard reader in to possibly uninitialized `buffers`, zeroing them first if
+/// `zero` is set.
+///
+/// Only readers implementing [`ReadIntoUninit`] may be passed a `zero` of `false`.
+#[cfg(not(unix))]
+fn read_vectored<R: io::Read + ?Sized>(reader: &mut R, buffers: &mut [super::IoVecMut], zero: bool) -> io::Result<usize> {
+    // Safety:
+    // - A slice upholds the guarantees of write_bytes and 0 is a valid bit pattern for `MaybeUninit<u8>`.
+    // - The slices are either initialized or only written to by the reader, as `ReadIntoUninit`
+    //   requires.
+    let slice = |buffer: &mut super::IoVecMut| unsafe {
+        if zero {
+            core::ptr::write_bytes(buffer.as_ptr(), 0, buffer.len());
+        }
+        io::IoSliceMut::new(core::slice::from_raw_parts_mut(buffer.as_ptr(), buffer.len()))
+    };
+    // A refill reads in to at most two buffers, either side of the end of the allocation, so
+    // those need no allocation.
+    match buffers {
+        [a] => reader.read_vectored(&mut [slice(a)]),
+        [a, b] => reader.read_vectored(&mut [slice(a), slice(b)]),
+        buffers => reader.read_vectored(&mut buffers.iter_mut().map(slice).collect::<Vec<_>>()),
+    }
+}
+/// Write `buffers` to a standard writer with a single vectored write.
+#[cfg(unix)]
+#[inline]
+fn write_vectored<W: io::Write + ?Sized>(writer: &mut W, buffers: &[super::IoVec]) -> io::Result<usize> {
+    // Safety:
+    // - On Unix `IoSlice` is guaranteed to be ABI compatible with `struct iovec`, as `IoVec` is
+    //   asserted to be in `os::unix`.
+    // - The buffers of a write hold the bytes to write, so are initialized.
+    let buffers = unsafe {
+        core::slice::from_raw_parts(buffers.as_ptr() as *const io::IoSlice, buffers.len())
+    };
+    writer.write_vectored(buffers)
+}
+/// Write `buffers` to a standard writer with a single vectored write.
+#[cfg(not(unix))]
+fn write_vectored<W: io::Write + ?Sized>(writer: &mut W, buffers: &[super::IoVec]) -> io::Result<usize> {
+    // Safety: The buffers of a write hold the bytes to write, so are initialized.
+    let buffers: Vec<_> = buffers.iter().map(|buffer| unsafe {
+        io::IoSlice::new(&*(buffer.as_maybe_uninit_slice() as *const [MaybeUninit<u8>] as *const [u8]))
+    }).collect();
+    writer.write_vectored(&buffers)
+}
 
-impl<T: io::Write> super::Write for T {
+/// An adapter implementing [`Read`](super::Read) and [`Write`](super::Write) for a standard
+/// reader or writer, such as a [`File`](std::fs::File) or [`TcpStream`](std::net::TcpStream).
+/// [`ToStd`] adapts the other way.
+///
+/// The traits of this crate are not implemented for every standard reader and writer, so that
+/// other types that implement [`std::io::Read`] or [`std::io::Write`] remain free to implement
+/// them differently, such as with another error type.
+/// ```rust
+/// use std::io::Cursor;
+/// use circulate::{BufStream, FromStd, Read, Write};
+///
+/// let mut stream = BufStream::new(FromStd(Cursor::new(b"request".to_vec())));
+/// let mut request = String::new();
+/// stream.read_to_string(&mut request).unwrap();
+/// assert_eq!(request, "request");
+/// stream.write(b" and response").unwrap();
+/// stream.flush().unwrap();
+/// let cursor = stream.take_stream().into_inner();
+/// assert_eq!(cursor.into_inner(), b"request and response");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FromStd<T>(pub T);
+impl<T: io::Read> super::Read for FromStd<T> {
+    type Error = io::Error;
+    #[inline]
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        read(&mut self.0, buffer, true)
+    }
+    #[inline]
+    fn read_vectored(&mut self, buffers: &mut [super::IoVecMut]) -> Result<usize, Self::Error> {
+        read_vectored(&mut self.0, buffers, true)
+    }
+}
+
+/// A standard reader that only ever writes to the buffer passed to [`std::io::Read::read`], so
+/// that it can be passed uninitialized memory. [`FromStdUninit`] reads from such readers without
+/// first zeroing the buffer, as [`FromStd`] must.
+///
+/// The standard library has no stable way to read in to uninitialized memory, so the buffer is
+/// passed as a `&mut [u8]` and it is up to the reader to never read it.
+/// # Safety
+/// The reader must not read any byte of a buffer passed to `read` or `read_vectored` before
+/// writing it, and must have written every byte it reports as read.
+pub unsafe trait ReadIntoUninit: io::Read {}
+macro_rules! read_into_uninit {
+    ($($ty:ty),* $(,)?) => {$(
+        // Safety: These readers only copy in to the buffer, or pass it to the OS to fill.
+        unsafe impl ReadIntoUninit for $ty {}
+    )*};
+}
+read_into_uninit!(
+    std::fs::File, &std::fs::File,
+    std::net::TcpStream, &std::net::TcpStream,
+    io::PipeReader, &io::PipeReader,
+    io::Stdin, io::StdinLock<'_>,
+    std::process::ChildStdout, std::process::ChildStderr,
+    io::Empty, io::Repeat, &[u8],
+);
+#[cfg(unix)]
+read_into_uninit!(std::os::unix::net::UnixStream, &std::os::unix::net::UnixStream);
+// Safety: A cursor only copies its bytes in to the buffer.
+unsafe impl<T: AsRef<[u8]>> ReadIntoUninit for io::Cursor<T> {}
+// Safety: Each of these only passes the buffer on to readers that implement `ReadIntoUninit`,
+// or copies in to it from a buffer of its own.
+unsafe impl<R: ReadIntoUninit + ?Sized> ReadIntoUninit for &mut R {}
+unsafe impl<R: ReadIntoUninit + ?Sized> ReadIntoUninit for Box<R> {}
+unsafe impl<R: ReadIntoUninit> ReadIntoUninit for io::Take<R> {}
+unsafe impl<R: ReadIntoUninit + ?Sized> ReadIntoUninit for io::BufReader<R> {}
+unsafe impl<A: ReadIntoUninit, B: ReadIntoUninit> ReadIntoUninit for io::Chain<A, B> {}
+
+/// An adapter implementing [`Read`](super::Read) and [`Write`](super::Write) for a standard
+/// reader or writer as [`FromStd`] does, but reading straight in to uninitialized buffers.
+///
+/// [`FromStd`] zeroes each buffer before reading in to it, as a standard reader may read from
+/// the buffer it is passed. For a reader that promises not to with [`ReadIntoUninit`], such as a
+/// [`File`](std::fs::File) or [`TcpStream`](std::net::TcpStream), that is wasted work that
+/// grows with the size of the read.
+/// ```rust
+/// use std::io;
+/// use circulate::{BufStream, FromStdUninit, Read, RingBuffer};
+///
+/// let (reader, mut writer) = io::pipe().unwrap();
+/// std::thread::spawn(move || io::copy(&mut io::Read::take(io::repeat(7), 1 << 20), &mut writer).unwrap());
+/// let mut stream = BufStream::with_capacity(FromStdUninit(reader), 1 << 16);
+/// let mut received = RingBuffer::new();
+/// stream.read_to_end(&mut received).unwrap();
+/// assert_eq!(received.len(), 1 << 20);
+/// assert!(received.iter().all(|byte| *byte == 7));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FromStdUninit<T>(pub T);
+impl<T: ReadIntoUninit> super::Read for FromStdUninit<T> {
+    type Error = io::Error;
+    #[inline]
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        read(&mut self.0, buffer, false)
+    }
+    #[inline]
+    fn read_vectored(&mut self, buffers: &mut [super::IoVecMut]) -> Result<usize, Self::Error> {
+        read_vectored(&mut self.0, buffers, false)
+    }
+}
+
+/// The parts of [`FromStd`] and [`FromStdUninit`] that do not depend on how they read.
+macro_rules! std_adapter {
+    ($($adapter:ident),*) => {$(
+        impl<T> $adapter<T> {
+            /// Get the standard reader or writer back.
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+            /// Get a reference to the standard reader or writer.
+            pub const fn get_ref(&self) -> &T {
+                &self.0
+            }
+            /// Get a mutable reference to the standard reader or writer.
+            pub fn get_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+        impl<T> From<T> for $adapter<T> {
+            #[inline]
+            fn from(inner: T) -> Self {
+                Self(inner)
+            }
+        }
+        impl<T: io::Write> super::Write for $adapter<T> {
+            type Error = io::Error;
+            #[inline]
+            fn flush(&mut self) -> Result<(), Self::Error> {
+                self.0.flush()
+            }
+            #[inline]
+            fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
+                self.0.write(slice)
+            }
+            #[inline]
+            fn write_vectored(&mut self, buffers: &[super::IoVec]) -> Result<usize, Self::Error> {
+                write_vectored(&mut self.0, buffers)
+            }
+        }
+        impl<T: io::Seek> io::Seek for $adapter<T> {
+            #[inline]
+            fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+                self.0.seek(pos)
+            }
+            #[inline]
+            fn stream_position(&mut self) -> io::Result<u64> {
+                self.0.stream_position()
+            }
+        }
+        impl super::Shutdown for $adapter<std::net::TcpStream> {
+            #[inline]
+            fn shutdown(&mut self) -> Result<(), Self::Error> {
+                std::net::TcpStream::shutdown(&self.0, std::net::Shutdown::Write)
+            }
+        }
+        #[cfg(unix)]
+        impl super::Shutdown for $adapter<std::os::unix::net::UnixStream> {
+            #[inline]
+            fn shutdown(&mut self) -> Result<(), Self::Error> {
+                std::os::unix::net::UnixStream::shutdown(&self.0, std::net::Shutdown::Write)
+            }
+        }
+        #[cfg(unix)]
+        impl<T: RawStream> RawStream for $adapter<T> {
+            #[inline]
+            fn raw_fd(&self) -> Option<std::os::fd::RawFd> {
+                self.0.raw_fd()
+            }
+        }
+    )*};
+}
+std_adapter!(FromStd, FromStdUninit);
+
+/// An adapter implementing [`Read`](super::Read) and [`Write`](super::Write) for a handle to a
+/// stream that can be read from and written to through a shared reference, such as
+/// [`TcpStream`](std::net::TcpStream).
+///
+/// As the handle only needs to be shared, one stream can be used from several places at once
+/// without locking, for example by cloning an [`Arc`] handle.
+/// ```rust
+/// use std::{net::{TcpListener, TcpStream}, sync::Arc};
+/// use circulate::{ByRef, Write};
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let stream = Arc::new(TcpStream::connect(listener.local_addr().unwrap()).unwrap());
+/// let mut a = ByRef(stream.clone());
+/// let mut b = ByRef(stream);
+/// a.write(b"one handle, ").unwrap();
+/// b.write(b"another handle").unwrap();
+///
+/// let (peer, _) = listener.accept().unwrap();
+/// drop((a, b));
+/// assert_eq!(std::io::read_to_string(peer).unwrap(), "one handle, another handle");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByRef<P>(pub P);
+impl<P: Deref> super::Read for ByRef<P>
+where
+    for<'a> &'a P::Target: io::Read,
+{
+    type Error = io::Error;
+    #[inline]
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        read(&mut &*self.0, buffer, true)
+    }
+    #[inline]
+    fn read_vectored(&mut self, buffers: &mut [super::IoVecMut]) -> Result<usize, Self::Error> {
+        read_vectored(&mut &*self.0, buffers, true)
+    }
+}
+impl<P: Deref> super::Write for ByRef<P>
+where
+    for<'a> &'a P::Target: io::Write,
+{
     type Error = io::Error;
     #[inline]
     fn flush(&mut self) -> Result<(), Self::Error> {
-        <T as io::Write>::flush(self)
+        io::Write::flush(&mut &*self.0)
     }
     #[inline]
     fn write(&mut self, slice: &[u8]) -> Result<usize, Self::Error> {
-        <T as io::Write>::write(self, slice)
+        io::Write::write(&mut &*self.0, slice)
+    }
+    #[inline]
+    fn write_vectored(&mut self, buffers: &[super::IoVec]) -> Result<usize, Self::Error> {
+        write_vectored(&mut &*self.0, buffers)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> super::BufStream<FromStd<T>>
+where
+    T: io::Read + io::Write,
+    for<'a> &'a T: io::Read + io::Write,
+{
+    /// Split the stream in to owned halves sharing the underlying stream, so that it can be read
+    /// from and written to concurrently.
+    ///
+    /// The first half takes the buffered input and the second the buffered output, along with
+    /// any observer set with [`BufStream::set_io_observer`](super::BufStream::set_io_observer).
+    /// Neither half locks the stream.
+    /// ```rust
+    /// use std::{mem::MaybeUninit, net::{TcpListener, TcpStream}, thread};
+    /// use circulate::{BufStream, FromStd, Read, Write};
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    /// let (server, _) = listener.accept().unwrap();
+    /// let echo = thread::spawn(move || std::io::copy(&mut &server, &mut &server).unwrap());
+    ///
+    /// let (mut reader, mut writer) = BufStream::with_capacity(FromStd(client), 64).into_split();
+    /// let send = thread::spawn(move || {
+    ///     for i in 0..10_000u32 {
+    ///         writer.write(&[i as u8]).unwrap();
+    ///     }
+    ///     writer.shutdown().unwrap();
+    /// });
+    /// let mut received = Vec::new();
+    /// let mut buffer = [MaybeUninit::uninit(); 256];
+    /// loop {
+    ///     let read = reader.read(&mut buffer).unwrap();
+    ///     if read == 0 {
+    ///         break;
+    ///     }
+    ///     received.extend(buffer[..read].iter().map(|byte| unsafe { byte.assume_init() }));
+    /// }
+    /// send.join().unwrap();
+    /// assert_eq!(echo.join().unwrap(), 10_000);
+    /// assert!(received.iter().enumerate().all(|(i, byte)| *byte == i as u8));
+    /// ```
+    ///
+    /// Splitting consumes the stream, so it cannot be used again afterwards.
+    /// ```rust,compile_fail,E0382
+    /// use std::net::{TcpListener, TcpStream};
+    /// use circulate::{BufStream, FromStd, Write};
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    /// let mut stream = BufStream::new(FromStd(client));
+    /// let (reader, writer) = stream.into_split();
+    /// stream.write(b"use after split").unwrap();
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn into_split(self) -> (super::BufStream<ByRef<Arc<T>>>, super::BufStream<ByRef<Arc<T>>>) {
+        let this = core::mem::ManuallyDrop::new(self);
+        // Safety: Only the fields that are `Copy` are used afterwards.
+        let (stream, input, output, chunks, observer) = unsafe { super::BufStream::take_fields(&this) };
+        let stream = Arc::new(stream.0);
+        let mut reader = super::BufStream::with_buffers(ByRef(stream.clone()), input, RingBuffer::new());
+        reader.shutdown = this.shutdown;
+        reader.pushback = this.pushback;
+        reader.pushback_limit = this.pushback_limit;
+        reader.read_ahead = this.read_ahead;
+        reader.adaptive = this.adaptive;
+        reader.vectored_refill = this.vectored_refill;
+        let mut writer = super::BufStream::with_buffers(ByRef(stream), RingBuffer::new(), output);
+        writer.shutdown = this.shutdown;
+        writer.pushback_limit = this.pushback_limit;
+        writer.chunks = chunks;
+        writer.output_ahead = this.output_ahead;
+        writer.flushed = this.flushed;
+        writer.received = this.received;
+        writer.observer = observer;
+        if this.flush_on_drop.is_armed() {
+            writer.flush_on_drop = super::DropFlush::ARMED;
+        }
+        writer.oldest_unflushed = this.oldest_unflushed;
+        (reader, writer)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<S> super::BufStream<S> {
+    /// Get how long ago the oldest output still waiting to be written to the stream was
+    /// buffered, or [`None`] if there is none.
+    ///
+    /// The time is taken when output is buffered while none is waiting, and forgotten once it
+    /// has all been written or discarded. Output left after a partial flush keeps the time of the
+    /// oldest output, as the times of later writes are not kept, so the age is never less than
+    /// that of the oldest byte left. See [`BufStream::flush_if_older_than`](super::BufStream::flush_if_older_than).
+    pub fn oldest_unflushed_age(&self) -> Option<Duration> {
+        self.oldest_unflushed.map(|instant| instant.elapsed())
+    }
+}
+#[cfg(feature = "alloc")]
+impl<S: super::Write> super::BufStream<S> {
+    /// Flush the buffered output if the oldest of it was buffered at least `age` ago, returning
+    /// whether it was flushed.
+    ///
+    /// This coalesces small writes for up to `age` while bounding how long any of them waits,
+    /// for interactive streams such as a terminal. Called on each tick of an event loop, it only
+    /// checks a timestamp until the output is due. Writes that go straight to the stream are not
+    /// buffered, so they do not start the clock.
+    /// ```rust
+    /// use std::{thread, time::Duration};
+    /// use circulate::{BufStream, Write};
+    ///
+    /// let mut stream = BufStream::new(Vec::new());
+    /// let age = Duration::from_millis(2);
+    /// assert!(!stream.flush_if_older_than(age).unwrap());
+    ///
+    /// stream.write(b"ls").unwrap();
+    /// stream.write(b" -l\n").unwrap();
+    /// assert!(stream.oldest_unflushed_age().unwrap() < Duration::from_secs(60));
+    /// while !stream.flush_if_older_than(age).unwrap() {
+    ///     thread::sleep(Duration::from_millis(1));
+    /// }
+    /// assert_eq!(stream.get_ref(), b"ls -l\n");
+    /// assert_eq!(stream.oldest_unflushed_age(), None);
+    /// ```
+    pub fn flush_if_older_than(&mut self, age: Duration) -> Result<bool, S::Error> {
+        match self.oldest_unflushed_age() {
+            Some(waited) if waited >= age => {
+                self.flush_for(super::FlushReason::Deadline)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+/// Seeking writes the buffered output first, so that it lands where it was written, and then
+/// discards the buffered input, which was read from before the new position.
+///
+/// The position accounts for the buffered bytes, as if the stream were unbuffered. Reading and
+/// writing share one position, so only one of the buffers should hold bytes at a time.
+/// ```rust
+/// use std::io::{Cursor, Seek, SeekFrom, Write};
+/// use circulate::{BufStream, FromStd};
+///
+/// // Compare against writing to a cursor directly.
+/// let mut reference = Cursor::new(Vec::new());
+/// let mut stream = BufStream::with_capacity(FromStd(Cursor::new(Vec::new())), 16);
+/// let mut state = 0x2545_f491_4f6c_dd1du64;
+/// let mut random = move |bound: u64| {
+///     state ^= state << 13;
+///     state ^= state >> 7;
+///     state ^= state << 17;
+///     state % bound
+/// };
+/// for round in 0..5_000u64 {
+///     match random(6) {
+///         0 | 1 => {
+///             // Note: A cursor extends its vector to the position even for an empty write.
+///             let bytes: Vec<u8> = (0..1 + random(40)).map(|i| (round + i) as u8).collect();
+///             reference.write_all(&bytes).unwrap();
+///             let mut rest = &bytes[..];
+///             while !rest.is_empty() {
+///                 rest = &rest[stream.write(rest).unwrap()..];
+///             }
+///         }
+///         2 => {
+///             let position = random(200);
+///             assert_eq!(stream.seek(SeekFrom::Start(position)).unwrap(), reference.seek(SeekFrom::Start(position)).unwrap());
+///         }
+///         3 => {
+///             let offset = random(20) as i64 - 10;
+///             let position = reference.stream_position().unwrap();
+///             if position as i64 + offset >= 0 {
+///                 assert_eq!(stream.seek(SeekFrom::Current(offset)).unwrap(), reference.seek(SeekFrom::Current(offset)).unwrap());
+///             }
+///         }
+///         4 => {
+///             let offset = -(random(10) as i64);
+///             if reference.get_ref().len() as i64 + offset >= 0 {
+///                 assert_eq!(stream.seek(SeekFrom::End(offset)).unwrap(), reference.seek(SeekFrom::End(offset)).unwrap());
+///             }
+///         }
+///         _ => {
+///             let offset = random(10) as i64;
+///             stream.seek_relative(offset).unwrap();
+///             reference.seek_relative(offset).unwrap();
+///         }
+///     }
+///     assert_eq!(stream.stream_position().unwrap(), reference.stream_position().unwrap());
+/// }
+/// stream.flush().unwrap();
+/// assert_eq!(stream.take_stream().0.into_inner(), reference.into_inner());
+/// ```
+/// Reads continue from the new position, and skipping forward over buffered input needs no seek.
+/// ```rust
+/// use std::io::{Cursor, Seek, SeekFrom};
+/// use core::mem::MaybeUninit;
+/// use circulate::{BufStream, FromStd, Read};
+///
+/// let mut stream = BufStream::with_capacity(FromStd(Cursor::new(b"0123456789abcdef".to_vec())), 7);
+/// let mut byte = [MaybeUninit::uninit()];
+/// stream.read(&mut byte).unwrap();
+/// assert_eq!(stream.stream_position().unwrap(), 1);
+/// assert_eq!(stream.get_ref().0.position(), 7);
+///
+/// stream.seek_relative(4).unwrap();
+/// assert_eq!(stream.get_ref().0.position(), 7);
+/// stream.read(&mut byte).unwrap();
+/// assert_eq!(unsafe { byte[0].assume_init() }, b'5');
+///
+/// assert_eq!(stream.seek(SeekFrom::Current(-3)).unwrap(), 3);
+/// stream.read(&mut byte).unwrap();
+/// assert_eq!(unsafe { byte[0].assume_init() }, b'3');
+/// ```
+#[cfg(feature = "alloc")]
+impl<T: io::Seek + super::Write<Error = io::Error>> io::Seek for super::BufStream<T> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.flush_output_to_completion()?;
+        if !self.output_is_flushed() {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        let pos = match pos {
+            // The stream is ahead of the reader by the buffered input.
+            io::SeekFrom::Current(offset) => io::SeekFrom::Current(
+                offset.checked_sub(self.input.len() as i64).ok_or(io::ErrorKind::InvalidInput)?,
+            ),
+            pos => pos,
+        };
+        let position = self.stream.seek(pos)?;
+        self.input.clear();
+        self.pushback = 0;
+        Ok(position)
+    }
+    /// Get the position without writing the buffered output.
+    fn stream_position(&mut self) -> io::Result<u64> {
+        let pending = (self.output.len() + self.chunks.len()) as u64;
+        self.stream.stream_position()?
+            .checked_add(pending)
+            .and_then(|position| position.checked_sub(self.input.len() as u64))
+            .ok_or_else(|| io::ErrorKind::InvalidData.into())
+    }
+    /// Seek forward within the buffered input without seeking the stream, if there is no
+    /// buffered output.
+    ///
+    /// Seeking within the buffered output still writes it first. Staging later writes over the
+    /// buffered bytes would leave the bytes after them to be written at a position behind where
+    /// the buffer ends, so the saving is not worth tracking the gap.
+    fn seek_relative(&mut self, offset: i64) -> io::Result<()> {
+        match usize::try_from(offset) {
+            Ok(skip) if skip <= self.input.len() && self.output_is_flushed() => self.consume(skip),
+            _ => drop(io::Seek::seek(self, io::SeekFrom::Current(offset))?),
+        }
+        Ok(())
+    }
+}
+/// Seeking discards the buffered bytes, which were read from before the new position.
+///
+/// The position accounts for the buffered bytes, as if the reader were unbuffered, and skipping
+/// forward over buffered bytes needs no seek.
+/// ```rust
+/// use std::io::{Cursor, Seek, SeekFrom};
+/// use core::mem::MaybeUninit;
+/// use circulate::{BufReader, FromStd, Read};
+///
+/// let mut reader = BufReader::with_capacity(FromStd(Cursor::new(b"0123456789abcdef".to_vec())), 7);
+/// let mut byte = [MaybeUninit::uninit()];
+/// reader.read(&mut byte).unwrap();
+/// assert_eq!(reader.stream_position().unwrap(), 1);
+/// reader.seek_relative(4).unwrap();
+/// assert_eq!(reader.get_ref().0.position(), 7);
+/// reader.read(&mut byte).unwrap();
+/// assert_eq!(unsafe { byte[0].assume_init() }, b'5');
+///
+/// assert_eq!(reader.seek(SeekFrom::Current(-3)).unwrap(), 3);
+/// reader.read(&mut byte).unwrap();
+/// assert_eq!(unsafe { byte[0].assume_init() }, b'3');
+/// ```
+#[cfg(feature = "alloc")]
+impl<T: io::Seek + super::Read> io::Seek for super::BufReader<T> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let buffered = self.buffered_len();
+        let pos = match pos {
+            // The reader is ahead by the buffered bytes.
+            io::SeekFrom::Current(offset) => io::SeekFrom::Current(
+                offset.checked_sub(buffered as i64).ok_or(io::ErrorKind::InvalidInput)?,
+            ),
+            pos => pos,
+        };
+        let position = self.get_mut().seek(pos)?;
+        self.consume(buffered);
+        Ok(position)
+    }
+    fn stream_position(&mut self) -> io::Result<u64> {
+        let buffered = self.buffered_len() as u64;
+        self.get_mut().stream_position()?
+            .checked_sub(buffered)
+            .ok_or_else(|| io::ErrorKind::InvalidData.into())
+    }
+    /// Seek forward within the buffered bytes without seeking the inner reader.
+    fn seek_relative(&mut self, offset: i64) -> io::Result<()> {
+        match usize::try_from(offset) {
+            Ok(skip) if skip <= self.buffered_len() => self.consume(skip),
+            _ => drop(io::Seek::seek(self, io::SeekFrom::Current(offset))?),
+        }
+        Ok(())
+    }
+}
+/// Seeking writes the buffered bytes first, so that they land where they were written.
+///
+/// The position accounts for the buffered bytes, as if the writer were unbuffered.
+/// ```rust
+/// use std::io::{Cursor, Seek, SeekFrom};
+/// use circulate::{BufWriter, FromStd, Write};
+///
+/// let mut writer = BufWriter::new(FromStd(Cursor::new(Vec::new())));
+/// writer.write(b"header").unwrap();
+/// assert_eq!(writer.stream_position().unwrap(), 6);
+/// assert_eq!(writer.get_ref().0.position(), 0);
+/// writer.seek(SeekFrom::Start(2)).unwrap();
+/// writer.write(b"AD").unwrap();
+/// let FromStd(cursor) = writer.into_inner().map_err(|error| error.into_error()).unwrap();
+/// assert_eq!(cursor.into_inner(), b"heADer");
+/// ```
+#[cfg(feature = "alloc")]
+impl<T: io::Seek + super::Write<Error = io::Error>> io::Seek for super::BufWriter<T> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        super::Write::flush(self)?;
+        if !self.buffer().is_empty() {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        self.get_mut().seek(pos)
+    }
+    /// Get the position without writing the buffered bytes.
+    fn stream_position(&mut self) -> io::Result<u64> {
+        let pending = self.buffer().len() as u64;
+        self.get_mut().stream_position()?
+            .checked_add(pending)
+            .ok_or_else(|| io::ErrorKind::InvalidData.into())
+    }
+}
+impl<P: Deref<Target = std::net::TcpStream>> super::Shutdown for ByRef<P> {
+    #[inline]
+    fn shutdown(&mut self) -> Result<(), Self::Error> {
+        std::net::TcpStream::shutdown(&self.0, std::net::Shutdown::Write)
+    }
+}
+
+/// An adapter implementing [`std::io::Read`] and [`std::io::Write`] for readers and writers of
+/// this crate that fail with [`io::Error`].
+///
+/// A [`BufStream`](super::BufStream) over a standard stream implements the standard traits
+/// itself, so only needs the adapter where both sets of traits are in scope, leaving its methods
+/// ambiguous.
+/// ```rust
+/// use std::io::{self, BufRead};
+/// use circulate::{BufStream, FromStd, ToStd};
+///
+/// let stream = BufStream::with_capacity(FromStd(io::Cursor::new(b"first\nsecond\n".to_vec())), 4);
+/// let mut output = Vec::new();
+/// io::copy(&mut ToStd(stream), &mut output).unwrap();
+/// assert_eq!(output, b"first\nsecond\n");
+///
+/// let stream = BufStream::new(FromStd(io::Cursor::new(b"first\nsecond\n".to_vec())));
+/// let lines: Vec<_> = io::BufReader::new(ToStd(stream)).lines().map(Result::unwrap).collect();
+/// assert_eq!(lines, ["first", "second"]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ToStd<T>(pub T);
+impl<T> ToStd<T> {
+    /// Get the reader or writer of this crate back.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+    /// Get a reference to the reader or writer of this crate.
+    pub const fn get_ref(&self) -> &T {
+        &self.0
+    }
+    /// Get a mutable reference to the reader or writer of this crate.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+impl<T> From<T> for ToStd<T> {
+    #[inline]
+    fn from(inner: T) -> Self {
+        Self(inner)
+    }
+}
+impl<T: super::Read<Error = io::Error>> io::Read for ToStd<T> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Safety: `MaybeUninit<u8>` has the same layout as `u8`, and readers must not de-initialize bytes.
+        let buffer = unsafe { core::slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut MaybeUninit<u8>, buf.len()) };
+        self.0.read(buffer)
+    }
+}
+impl<T: super::Write<Error = io::Error>> io::Write for ToStd<T> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// A [`BufStream`](super::BufStream) over a stream failing with [`io::Error`] is itself a standard
+/// reader, as through [`ToStd`].
+/// ```rust
+/// use std::io;
+/// use circulate::{BufStream, FromStd};
+///
+/// let mut stream = BufStream::with_capacity(FromStd(io::Cursor::new(b"abc".to_vec())), 2);
+/// let mut output = Vec::new();
+/// io::copy(&mut stream, &mut output).unwrap();
+/// assert_eq!(output, b"abc");
+/// ```
+#[cfg(feature = "alloc")]
+impl<S: super::Read<Error = io::Error>> io::Read for super::BufStream<S> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(&mut ToStd(self), buf)
+    }
+}
+/// A [`BufStream`](super::BufStream) over a stream failing with [`io::Error`] is itself a standard
+/// writer, as through [`ToStd`].
+/// ```rust
+/// use std::io::{self, BufRead, Write};
+/// use circulate::{BufStream, FromStd};
+///
+/// let mut stream = BufStream::with_capacity(FromStd(Vec::new()), 4);
+/// io::copy(&mut io::Cursor::new(b"first\nsecond\n"), &mut stream).unwrap();
+/// stream.flush().unwrap();
+/// let written = stream.take_stream().0;
+///
+/// let stream = BufStream::new(FromStd(io::Cursor::new(written)));
+/// let lines: Vec<_> = io::BufReader::new(stream).lines().map(Result::unwrap).collect();
+/// assert_eq!(lines, ["first", "second"]);
+/// ```
+#[cfg(feature = "alloc")]
+impl<S: super::Write<Error = io::Error>> io::Write for super::BufStream<S> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        super::Write::write(self, buf)
+    }
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        super::Write::flush(self)
+    }
+}
+
+/// A stream that may be backed by a file descriptor, letting [`BufStream::copy_to`] hand a copy
+/// between two such streams to the kernel, such as with `sendfile(2)` or `copy_file_range(2)`.
+///
+/// The descriptor is only a hint. By default a stream has none, so a copy goes through the
+/// buffers.
+///
+/// [`BufStream::copy_to`]: super::BufStream::copy_to
+#[cfg(unix)]
+pub trait RawStream {
+    /// Get the file descriptor backing the stream, if reading or writing the stream is the same
+    /// as reading or writing the descriptor.
+    fn raw_fd(&self) -> Option<std::os::fd::RawFd> {
+        None
+    }
+}
+#[cfg(unix)]
+macro_rules! raw_stream {
+    ($($ty:ty),*) => {$(
+        impl RawStream for $ty {
+            #[inline]
+            fn raw_fd(&self) -> Option<std::os::fd::RawFd> {
+                Some(std::os::fd::AsRawFd::as_raw_fd(self))
+            }
+        }
+    )*};
+}
+#[cfg(unix)]
+raw_stream!(std::fs::File, std::net::TcpStream, std::os::unix::net::UnixStream, io::Stdin, io::Stdout, io::Stderr);
+#[cfg(all(unix, feature = "alloc"))]
+impl<S: RawStream> RawStream for super::BufStream<S> {
+    #[inline]
+    fn raw_fd(&self) -> Option<std::os::fd::RawFd> {
+        self.stream.raw_fd()
+    }
+}
+
+#[cfg(all(unix, feature = "alloc"))]
+impl<S: io::Read + RawStream> super::BufStream<FromStd<S>> {
+    /// Copy the rest of this stream's input to `dst` until the stream ends, flushing `dst`, and
+    /// return the number of bytes copied.
+    ///
+    /// Input already buffered here and output already buffered in `dst` are written first,
+    /// through the buffers, so that nothing is reordered. If both streams are then backed by file
+    /// descriptors the rest is copied by [`io::copy`], which lets the kernel copy it directly
+    /// where the platform supports it. Otherwise the bytes are pumped through the buffers.
+    /// ```rust
+    /// use std::io::{self, Write};
+    /// use circulate::{BufStream, FromStd, RawStream};
+    ///
+    /// /// A stream with no file descriptor.
+    /// struct Memory(io::Cursor<Vec<u8>>);
+    /// impl io::Read for Memory {
+    ///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.0.read(buf) }
+    /// }
+    /// impl io::Write for Memory {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.write(buf) }
+    ///     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// }
+    /// impl RawStream for Memory {}
+    /// # use io::Read;
+    ///
+    /// let mut src = BufStream::with_capacity(FromStd(Memory(io::Cursor::new((0..=255).collect()))), 15);
+    /// let mut dst = BufStream::new(FromStd(Memory(io::Cursor::new(Vec::new()))));
+    /// src.buffer_read().unwrap();
+    /// src.consume(1);
+    /// circulate::Write::write(&mut dst, b"head").unwrap();
+    /// assert_eq!(src.copy_to(&mut dst).unwrap(), 255);
+    /// assert!(dst.output_is_flushed());
+    /// let copied = dst.take_stream().0.0.into_inner();
+    /// assert_eq!(copied[..4], *b"head");
+    /// assert!(copied[4..].iter().copied().eq(1..=255));
+    /// ```
+    pub fn copy_to<D: io::Write + RawStream>(&mut self, dst: &mut super::BufStream<FromStd<D>>) -> io::Result<u64> {
+        let mut copied = 0;
+        loop {
+            while !self.input.is_empty() {
+                match self.copy_buffered_to(dst)? {
+                    0 => {
+                        // The output buffer of `dst` is full, so make space in it.
+                        dst.flush_output_to_completion()?;
+                        if !dst.output_is_flushed() {
+                            return Err(io::ErrorKind::WriteZero.into());
+                        }
+                    }
+                    count => copied += count as u64,
+                }
+            }
+            dst.flush_output_to_completion()?;
+            if !dst.output_is_flushed() {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            if self.stream.raw_fd().is_some() && dst.stream.raw_fd().is_some() {
+                let count = io::copy(&mut self.stream.0, &mut dst.stream.0)?;
+                self.received += count;
+                dst.flushed += count;
+                dst.stream.0.flush()?;
+                return Ok(copied + count);
+            }
+            if self.fill_input()? == 0 {
+                return Ok(copied);
+            }
+        }
     }
 }
@@ -0,0 +1,253 @@
+use core::mem::MaybeUninit;
+use crate::RingBuffer;
+use super::Read;
+
+/// A streaming byte transformation, such as decompression or decryption, applied by a
+/// [`TransformReader`].
+///
+/// A transform may keep state between calls, such as the remainder of a run it had no room to
+/// write, and may leave input it cannot use yet unconsumed, such as half of a fixed size unit.
+/// Unconsumed input is passed again on the next call with more bytes after it.
+pub trait Transform {
+    /// Transform bytes from the start of `input` in to the start of `output`, returning the
+    /// number of bytes consumed from `input` and the number of bytes produced, and therefore
+    /// initialized, in `output`.
+    ///
+    /// `input` is empty at the end of the stream, and whenever all input has been consumed, so
+    /// that output held back by the transform can still be produced. Consuming and producing
+    /// nothing means the transform needs more input before it can make progress.
+    fn transform(&mut self, input: &[u8], output: &mut [MaybeUninit<u8>]) -> (usize, usize);
+}
+impl<T: Transform + ?Sized> Transform for &mut T {
+    fn transform(&mut self, input: &[u8], output: &mut [MaybeUninit<u8>]) -> (usize, usize) {
+        (**self).transform(input, output)
+    }
+}
+
+/// A reader that applies a [`Transform`] to the bytes read from an inner reader.
+///
+/// Input the transform leaves unconsumed is kept in a staging buffer and passed again once more
+/// has been read after it. The staging buffer is made contiguous before reading more, so the
+/// transform always sees everything staged when it asks for more input.
+///
+/// Reads always make progress. If the transform consumes and produces nothing while the staging
+/// buffer is full, the read fails with [`TransformError::Stalled`] instead of looping forever, and
+/// if it leaves input unconsumed at the end of the stream the read fails with
+/// [`TransformError::Truncated`].
+/// ```rust
+/// # #[cfg(feature = "test-util")] {
+/// use core::mem::MaybeUninit;
+/// use circulate::{test_util::MemoryStream, Read, RingBuffer, RleDecoder, TransformError, TransformReader, XorKeystream};
+///
+/// let trickle = |bytes: &[u8]| MemoryStream::with_max_read(bytes.to_vec(), 3);
+///
+/// // Runs of `count, byte`, split across reads and the wrapping staging buffer.
+/// let mut reader = TransformReader::with_capacity(trickle(b"\x03a\x01b\x05c\x00d\x02e"), RleDecoder::new(), 4);
+/// let mut buffer = RingBuffer::new();
+/// reader.read_to_end(&mut buffer).unwrap();
+/// assert!(buffer.iter().eq(b"aaabcccccee"));
+///
+/// // Runs longer than the output are produced over several reads.
+/// let mut reader = TransformReader::new(&b"\xffz"[..], RleDecoder::new());
+/// let mut output = [MaybeUninit::uninit(); 100];
+/// assert_eq!(reader.read(&mut output).unwrap(), 100);
+/// assert_eq!(reader.read(&mut output).unwrap(), 100);
+/// assert_eq!(reader.read(&mut output).unwrap(), 55);
+/// assert_eq!(reader.read(&mut output).unwrap(), 0);
+///
+/// // Applying the same keystream twice gives back the input.
+/// let key = || b"key".iter().copied().cycle();
+/// let mut encrypted = RingBuffer::new();
+/// TransformReader::new(trickle(b"attack at dawn"), XorKeystream::new(key())).read_to_end(&mut encrypted).unwrap();
+/// assert!(!encrypted.iter().eq(b"attack at dawn"));
+/// let mut decrypted = RingBuffer::new();
+/// TransformReader::new(encrypted.drain_reader(), XorKeystream::new(key())).read_to_end(&mut decrypted).unwrap();
+/// assert!(decrypted.iter().eq(b"attack at dawn"));
+///
+/// // Half a run at the end of the stream is an error.
+/// let mut reader = TransformReader::new(trickle(b"\x02a\x04"), RleDecoder::new());
+/// assert!(matches!(reader.read_to_end(&mut RingBuffer::new()), Err(TransformError::Truncated)));
+///
+/// // A transform that never consumes its input cannot fill the staging buffer forever.
+/// struct Stuck;
+/// impl circulate::Transform for Stuck {
+///     fn transform(&mut self, _: &[u8], _: &mut [MaybeUninit<u8>]) -> (usize, usize) {
+///         (0, 0)
+///     }
+/// }
+/// let mut reader = TransformReader::with_capacity(&[0; 64][..], Stuck, 16);
+/// assert!(matches!(reader.read(&mut output), Err(TransformError::Stalled)));
+/// # }
+/// ```
+pub struct TransformReader<R, T> {
+    inner: R,
+    transform: T,
+    /// Bytes read from `inner` that the transform has not consumed.
+    staging: RingBuffer<u8>,
+    /// Set once `inner` has reached the end of its stream.
+    eof: bool,
+}
+impl<R: Read, T: Transform> TransformReader<R, T> {
+    /// Create a reader that applies `transform` to the bytes read from `inner`, staging 4095
+    /// bytes of input.
+    pub fn new(inner: R, transform: T) -> Self {
+        Self::with_capacity(inner, transform, 4095)
+    }
+    /// Create a reader that applies `transform` to the bytes read from `inner`, staging at least
+    /// `capacity` bytes of input, rounded up as by [`RingBuffer::capacity_for`].
+    ///
+    /// The transform must be able to make progress with no more than the capacity of input.
+    pub fn with_capacity(inner: R, transform: T, capacity: usize) -> Self {
+        let mut staging = RingBuffer::with_capacity(capacity);
+        staging.set_max_capacity(staging.capacity());
+        Self { inner, transform, staging, eof: false }
+    }
+    /// Get a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Get a mutable reference to the inner reader.
+    ///
+    /// Reading from it directly skips those bytes past the transform.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Get a reference to the transform.
+    pub fn transform(&self) -> &T {
+        &self.transform
+    }
+    /// Get a mutable reference to the transform.
+    pub fn transform_mut(&mut self) -> &mut T {
+        &mut self.transform
+    }
+    /// Get the underlying reader and the bytes read from it that the transform has not consumed.
+    pub fn into_inner(self) -> (R, RingBuffer<u8>) {
+        (self.inner, self.staging)
+    }
+}
+impl<R: Read, T: Transform> Read for TransformReader<R, T> {
+    type Error = TransformError<R::Error>;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let (input, rhs) = self.staging.as_slices();
+            let wrapped = !rhs.is_empty();
+            let (consumed, produced) = self.transform.transform(input, buffer);
+            let consumed = consumed.min(input.len());
+            // Safety: No more than the staged bytes are consumed.
+            unsafe { self.staging.advance_read_unchecked(consumed) };
+            if produced != 0 {
+                return Ok(produced.min(buffer.len()));
+            }
+            if consumed != 0 {
+                continue;
+            }
+            // The transform needs more input than it was given.
+            if wrapped {
+                self.staging.make_contiguous();
+            } else if self.staging.is_full() {
+                return Err(TransformError::Stalled);
+            } else if self.eof {
+                return match self.staging.is_empty() {
+                    true => Ok(0),
+                    false => Err(TransformError::Truncated),
+                };
+            } else {
+                if self.staging.is_empty() {
+                    // Start from the beginning of the allocation, so the staged bytes stay contiguous.
+                    self.staging.clear();
+                }
+                self.eof = self.staging.fill_from(&mut self.inner).map_err(TransformError::Read)? == 0;
+            }
+        }
+    }
+}
+
+/// The error returned by a [`TransformReader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformError<E> {
+    /// The inner reader failed.
+    Read(E),
+    /// The transform made no progress even though the staging buffer is full.
+    Stalled,
+    /// The stream ended with input the transform did not consume.
+    Truncated,
+}
+impl<E: core::fmt::Display> core::fmt::Display for TransformError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Read(error) => error.fmt(f),
+            Self::Stalled => f.write_str("transform made no progress with a full staging buffer"),
+            Self::Truncated => f.write_str("stream ended part way through the transform's input"),
+        }
+    }
+}
+impl<E: core::error::Error> core::error::Error for TransformError<E> {}
+
+/// A [`Transform`] that XORs each byte with the next byte of a keystream, which both encrypts
+/// and decrypts. See [`TransformReader`] for an example.
+///
+/// Once the keystream runs out no more input is consumed.
+#[derive(Debug, Clone)]
+pub struct XorKeystream<K> {
+    keystream: K,
+}
+impl<K: Iterator<Item = u8>> XorKeystream<K> {
+    /// XOR the input with `keystream`.
+    pub const fn new(keystream: K) -> Self {
+        Self { keystream }
+    }
+}
+impl<K: Iterator<Item = u8>> Transform for XorKeystream<K> {
+    fn transform(&mut self, input: &[u8], output: &mut [MaybeUninit<u8>]) -> (usize, usize) {
+        let mut count = 0;
+        for (byte, out) in input.iter().zip(output) {
+            let Some(key) = self.keystream.next() else { break };
+            *out = MaybeUninit::new(byte ^ key);
+            count += 1;
+        }
+        (count, count)
+    }
+}
+
+/// A [`Transform`] that decodes runs encoded as a count byte followed by the byte to repeat.
+/// See [`TransformReader`] for an example.
+///
+/// Runs are only consumed whole, and a run longer than the output is produced over several
+/// calls.
+#[derive(Debug, Clone, Default)]
+pub struct RleDecoder {
+    /// The byte of the current run and the number of times it is still to be produced.
+    run: (u8, u8),
+}
+impl RleDecoder {
+    /// Create a decoder that has not started a run.
+    pub const fn new() -> Self {
+        Self { run: (0, 0) }
+    }
+}
+impl Transform for RleDecoder {
+    fn transform(&mut self, input: &[u8], output: &mut [MaybeUninit<u8>]) -> (usize, usize) {
+        let (mut consumed, mut produced) = (0, 0);
+        loop {
+            let (byte, remaining) = self.run;
+            let count = usize::from(remaining).min(output.len() - produced);
+            output[produced..produced + count].fill(MaybeUninit::new(byte));
+            produced += count;
+            self.run.1 -= count as u8;
+            if self.run.1 != 0 {
+                break;
+            }
+            match input[consumed..] {
+                [count, byte, ..] => {
+                    self.run = (byte, count);
+                    consumed += 2;
+                }
+                _ => break,
+            }
+        }
+        (consumed, produced)
+    }
+}
@@ -1,7 +1,87 @@
 #![cfg_attr(feature = "no_std", no_std)]
 
 mod io;
-pub use io::{BufReader, BufStream, BufWriter, IoVecMut, Read, Write};
+pub use io::{compat, fmt_adapter, os, BudgetError, Budgeted, CancelError, Chain, Eof, FlushReason, FmtAdapter, IoEvent, IoVec, IoVecCursor, IoVecMut, NewlineNormalizer, NewlineReader, Read, ReadExactError, ReadExt, Shutdown, Take, Write, WriteAllError, WriteExt};
+#[cfg(feature = "alloc")]
+pub use io::{BufConfig, BufReader, BufStream, BufWriter, CharReadError, Chars, ChunkedError, ChunkedReader, ChunkedWriter, DrainReader, FillStatus, FlushProgress, IntoInnerError, IoVecQueue, LoadError, LossyWriter, Middleware, MiddlewareBufStream, Mux, NullMiddleware, ReadToStringError, RequestError, ResponseSpec, RleDecoder, TailReader, Transform, TransformError, TransformReader, UnreadError, WouldBlock, XorKeystream};
 
+#[cfg(feature = "pod")]
+pub use io::PodError;
+#[cfg(all(feature = "pod", feature = "alloc"))]
+pub use io::PodRef;
+#[cfg(not(feature = "no_std"))]
+pub use io::{ByRef, FromStd, FromStdUninit, ReadIntoUninit, ToStd};
+#[cfg(all(unix, not(feature = "no_std")))]
+pub use io::RawStream;
+#[cfg(all(feature = "alloc", not(feature = "no_std")))]
+pub use io::{SpillStore, SpilloverBuffer};
+#[cfg(feature = "alloc")]
+mod guard;
+#[cfg(feature = "alloc")]
+mod raw_alloc;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(all(feature = "capi", not(feature = "no_std")))]
+pub mod capi;
+
+#[cfg(feature = "alloc")]
 mod ring_buffer;
-pub use ring_buffer::{Iter, IterMut, RingBuffer};
+#[cfg(feature = "alloc")]
+pub use ring_buffer::{AllocFailure, CapacityError, Cycle, Doubling, Drain, GrowthStrategy, InsufficientElements, Iter, IterMut, PopWhileValid, RingBuffer, SpareCapacity, ValidationFailed};
+
+#[cfg(all(feature = "rayon", not(feature = "no_std")))]
+mod par_iter;
+#[cfg(feature = "serde")]
+mod serialize;
+
+#[cfg(feature = "alloc")]
+mod mpsc;
+#[cfg(feature = "alloc")]
+pub use mpsc::{record_ring, RecordConsumer, RecordProducer};
+
+#[cfg(feature = "alloc")]
+mod spsc;
+#[cfg(feature = "alloc")]
+pub use spsc::{Consumer, Producer};
+
+#[cfg(feature = "alloc")]
+mod frame_ring;
+#[cfg(feature = "alloc")]
+pub use frame_ring::FrameRing;
+
+#[cfg(feature = "alloc")]
+mod sliding_window;
+#[cfg(feature = "alloc")]
+pub use sliding_window::SlidingWindow;
+
+#[cfg(feature = "alloc")]
+mod weighted;
+#[cfg(feature = "alloc")]
+pub use weighted::{WeightedIntoIter, WeightedRingBuffer};
+
+#[cfg(feature = "alloc")]
+mod ring_string;
+#[cfg(feature = "alloc")]
+pub use ring_string::RingString;
+
+mod sync;
+
+mod static_ring;
+pub use static_ring::{StaticConsumer, StaticProducer, StaticRing};
+
+#[cfg(feature = "alloc")]
+mod double_buffer;
+#[cfg(feature = "alloc")]
+pub use double_buffer::DoubleBuffer;
+
+#[cfg(all(feature = "alloc", not(feature = "no_std")))]
+mod channel;
+#[cfg(all(feature = "alloc", not(feature = "no_std")))]
+pub use channel::{mpsc_channel, MpscSender, Receiver, SendError};
+
+#[cfg(all(feature = "alloc", not(feature = "no_std")))]
+mod blocking;
+#[cfg(all(feature = "alloc", not(feature = "no_std")))]
+pub use blocking::{blocking_channel, BlockingReceiver, BlockingSender};
@@ -0,0 +1,182 @@
+extern crate alloc;
+use alloc::{boxed::Box, vec::Vec};
+use crate::sync::{Arc, AtomicU32, AtomicUsize, Ordering};
+
+/// Every record starts with a header word holding its length and state.
+const HEADER: usize = size_of::<u32>();
+/// Set once the record following the header has been completely written.
+const COMMITTED: u32 = 1 << 31;
+/// Set for records that only pad out the end of the buffer so the next record is contiguous.
+const PADDING: u32 = 1 << 30;
+const LEN_MASK: u32 = PADDING - 1;
+
+/// Create a byte-oriented multi-producer, single-consumer ring of variable length records,
+/// holding at least `capacity` bytes including the per-record overhead.
+///
+/// Producers claim space atomically, copy in their record and then commit it. The consumer only
+/// ever sees records that have been completely written and receives them in the order the space
+/// was claimed. Records that do not fit are rejected rather than blocking.
+/// ```rust
+/// use std::thread;
+/// let (producer, mut consumer) = circulate::record_ring(1024);
+/// let workers: Vec<_> = (0..4u8).map(|id| {
+///     let producer = producer.clone();
+///     thread::spawn(move || {
+///         for n in 0..1000u16 {
+///             let mut record = vec![id; 1 + n as usize % 31];
+///             let checksum = record.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+///             record.push(checksum);
+///             while !producer.push_record(&record) {
+///                 thread::yield_now();
+///             }
+///         }
+///     })
+/// }).collect();
+///
+/// let mut record = Vec::new();
+/// let mut received = 0;
+/// while received < 4000 {
+///     if consumer.pop_record(&mut record) {
+///         let (checksum, data) = record.split_last().unwrap();
+///         assert_eq!(*checksum, data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)));
+///         assert!(data.iter().all(|b| *b == data[0]));
+///         received += 1;
+///     }
+/// }
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// assert!(!consumer.pop_record(&mut record));
+/// ```
+pub fn record_ring(capacity: usize) -> (RecordProducer, RecordConsumer) {
+    let capacity = capacity.max(2 * HEADER).next_power_of_two();
+    let shared = Arc::new(Shared {
+        // Invariant: All bytes not claimed by a producer are zero.
+        buffer: (0..capacity / HEADER).map(|_| AtomicU32::new(0)).collect(),
+        reserved: AtomicUsize::new(0),
+        read: AtomicUsize::new(0),
+    });
+    (
+        RecordProducer { shared: shared.clone() },
+        RecordConsumer { shared, head: 0 },
+    )
+}
+
+struct Shared {
+    buffer: Box<[AtomicU32]>,
+    /// The end of the space claimed by producers.
+    /// This and `read` increase monotonically, wrapping at `usize::MAX`.
+    reserved: AtomicUsize,
+    /// The start of the space not yet released by the consumer.
+    read: AtomicUsize,
+}
+impl Shared {
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.buffer.len() * HEADER
+    }
+    #[inline]
+    fn header(&self, position: usize) -> &AtomicU32 {
+        &self.buffer[(position & (self.capacity() - 1)) / HEADER]
+    }
+    /// Get the words following the header of the record at `offset`, which never wrap.
+    #[inline]
+    fn words(&self, offset: usize, len: usize) -> &[AtomicU32] {
+        let start = (offset + HEADER) / HEADER;
+        &self.buffer[start..start + len.div_ceil(HEADER)]
+    }
+}
+
+/// The sending half of a [`record_ring`].
+/// It may be cloned to share the ring between many producers.
+#[derive(Clone)]
+pub struct RecordProducer {
+    shared: Arc<Shared>,
+}
+impl RecordProducer {
+    /// Append a record to the ring.
+    /// Returns `false` without modifying the ring if there is currently not enough space, or the
+    /// record could never fit.
+    pub fn push_record(&self, record: &[u8]) -> bool {
+        let shared = &*self.shared;
+        let capacity = shared.capacity();
+        let size = HEADER + record.len().next_multiple_of(HEADER);
+        if record.len() > LEN_MASK as usize || size > capacity {
+            return false;
+        }
+
+        let mut start = shared.reserved.load(Ordering::Relaxed);
+        let padding = loop {
+            let offset = start & (capacity - 1);
+            // Records never wrap, instead the rest of the buffer is padded.
+            let padding = if offset + size > capacity { capacity - offset } else { 0 };
+            let end = start.wrapping_add(padding + size);
+            // Acquire the consumer's release of the space, including the zeroed bytes.
+            let read = shared.read.load(Ordering::Acquire);
+            if end.wrapping_sub(read) > capacity {
+                return false;
+            }
+            match shared.reserved.compare_exchange_weak(start, end, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => break padding,
+                Err(current) => start = current,
+            }
+        };
+
+        if padding != 0 {
+            shared.header(start).store(COMMITTED | PADDING | padding as u32, Ordering::Release);
+            start = start.wrapping_add(padding);
+        }
+        // Note: The region is exclusively claimed, and the consumer will not read it until the
+        // header is committed, so the words need no ordering of their own.
+        for (word, bytes) in shared.words(start & (capacity - 1), record.len()).iter().zip(record.chunks(HEADER)) {
+            let mut padded = [0; HEADER];
+            padded[..bytes.len()].copy_from_slice(bytes);
+            word.store(u32::from_ne_bytes(padded), Ordering::Relaxed);
+        }
+        // Release the record bytes to the consumer.
+        shared.header(start).store(COMMITTED | record.len() as u32, Ordering::Release);
+        true
+    }
+}
+
+/// The receiving half of a [`record_ring`].
+pub struct RecordConsumer {
+    shared: Arc<Shared>,
+    /// The position of the next record header, only ever modified by the consumer.
+    head: usize,
+}
+impl RecordConsumer {
+    /// Take the next committed record from the ring, replacing the contents of `out`.
+    /// Returns `false` if no complete record is available.
+    pub fn pop_record(&mut self, out: &mut Vec<u8>) -> bool {
+        let shared = &*self.shared;
+        let capacity = shared.capacity();
+        loop {
+            let header = shared.header(self.head).load(Ordering::Acquire);
+            if header & COMMITTED == 0 {
+                return false;
+            }
+            let len = (header & LEN_MASK) as usize;
+            let offset = self.head & (capacity - 1);
+            let size = if header & PADDING != 0 {
+                len
+            } else {
+                out.clear();
+                for word in shared.words(offset, len) {
+                    out.extend_from_slice(&word.load(Ordering::Relaxed).to_ne_bytes());
+                }
+                out.truncate(len);
+                HEADER + len.next_multiple_of(HEADER)
+            };
+            for word in &shared.buffer[offset / HEADER..(offset + size) / HEADER] {
+                word.store(0, Ordering::Relaxed);
+            }
+            self.head = self.head.wrapping_add(size);
+            // Release the zeroed space back to the producers.
+            shared.read.store(self.head, Ordering::Release);
+            if header & PADDING == 0 {
+                return true;
+            }
+        }
+    }
+}
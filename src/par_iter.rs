@@ -0,0 +1,83 @@
+extern crate alloc;
+use alloc::vec::Vec;
+use rayon::{iter::Chain, prelude::*, slice};
+use crate::{CapacityError, RingBuffer};
+
+impl<T: Sync> RingBuffer<T> {
+    /// Returns a parallel iterator over the values in the buffer, in order.
+    ///
+    /// The iterator chains parallel iterators over the two contiguous parts of the buffer, so it
+    /// is indexed and may be zipped or enumerated.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// buffer.extend_from_slice(&[0; 5]).unwrap();
+    /// buffer.pop_slice(&mut [0; 5]);
+    /// buffer.extend_from_slice(&[1, 2, 3, 4, 5, 6]).unwrap();
+    /// assert_eq!(buffer.par_iter().sum::<u32>(), 21);
+    /// let (index, _) = buffer.par_iter().enumerate().find_any(|(_, value)| **value == 5).unwrap();
+    /// assert_eq!(index, 4);
+    /// ```
+    pub fn par_iter(&self) -> Chain<slice::Iter<'_, T>, slice::Iter<'_, T>> {
+        let (lhs, rhs) = self.as_slices();
+        lhs.par_iter().chain(rhs.par_iter())
+    }
+}
+impl<T: Send> RingBuffer<T> {
+    /// Returns a parallel iterator that allows mutating the values in the buffer, in order.
+    ///
+    /// Like [`RingBuffer::par_iter`], the iterator chains parallel iterators over the two
+    /// contiguous parts of the buffer, which never overlap.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// use rayon::prelude::*;
+    ///
+    /// const LEN: usize = 1_000_000;
+    /// let mut buffer = RingBuffer::with_capacity(LEN + 1);
+    /// buffer.extend_from_slice(&[0; LEN / 2]).unwrap();
+    /// buffer.pop_slice(&mut [0; LEN / 2]);
+    /// buffer.extend_from_slice(&(0..LEN as u64).collect::<Vec<_>>()).unwrap();
+    /// assert!(!buffer.as_mut_slices().1.is_empty());
+    ///
+    /// let mut expected = buffer.to_vec();
+    /// expected.iter_mut().for_each(|value| *value *= *value);
+    /// buffer.par_iter_mut().for_each(|value| *value *= *value);
+    /// assert!(buffer.iter().eq(&expected));
+    /// ```
+    pub fn par_iter_mut(&mut self) -> Chain<slice::IterMut<'_, T>, slice::IterMut<'_, T>> {
+        let (lhs, rhs) = self.as_mut_slices();
+        lhs.par_iter_mut().chain(rhs.par_iter_mut())
+    }
+    /// Append the values produced by a parallel iterator, in order.
+    ///
+    /// The values are collected first and the buffer then grows once to hold all of them, so on
+    /// failure the buffer is unchanged.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut buffer = RingBuffer::new();
+    /// buffer.push(0).unwrap();
+    /// buffer.par_extend((1..1000u32).into_par_iter().filter(|value| value % 3 == 0)).unwrap();
+    /// assert!(buffer.iter().copied().eq((0..1000).step_by(3)));
+    ///
+    /// let mut buffer = RingBuffer::with_capacity(3);
+    /// buffer.set_max_capacity(4);
+    /// assert!(buffer.par_extend((0..4u8).into_par_iter()).is_err());
+    /// assert!(buffer.is_empty());
+    /// ```
+    pub fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, iter: I) -> Result<(), CapacityError> {
+        let values: Vec<T> = iter.into_par_iter().collect();
+        self.reserve(values.len())?;
+        self.with_spare_capacity(|spare| {
+            for value in values {
+                if spare.push(value).is_err() {
+                    unreachable!("capacity was reserved for every value");
+                }
+            }
+        });
+        Ok(())
+    }
+}
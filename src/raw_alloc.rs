@@ -0,0 +1,56 @@
+extern crate alloc;
+use core::alloc::Layout;
+
+/// The allocator backing every [`RingBuffer`](crate::RingBuffer).
+pub(crate) trait RawAlloc {
+    /// # Safety
+    /// See [`alloc::alloc::alloc`].
+    unsafe fn alloc(layout: Layout) -> *mut u8;
+    /// # Safety
+    /// See [`alloc::alloc::dealloc`].
+    unsafe fn dealloc(ptr: *mut u8, layout: Layout);
+    /// # Safety
+    /// See [`alloc::alloc::realloc`].
+    unsafe fn realloc(ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8;
+}
+
+/// The global allocator.
+pub(crate) struct Global;
+impl RawAlloc for Global {
+    #[inline(always)]
+    unsafe fn alloc(layout: Layout) -> *mut u8 {
+        unsafe { alloc::alloc::alloc(layout) }
+    }
+    #[inline(always)]
+    unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        unsafe { alloc::alloc::dealloc(ptr, layout) }
+    }
+    #[inline(always)]
+    unsafe fn realloc(ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { alloc::alloc::realloc(ptr, layout, new_size) }
+    }
+}
+
+#[cfg(not(feature = "test-util"))]
+type Allocator = Global;
+#[cfg(feature = "test-util")]
+type Allocator = crate::test_util::Counting;
+
+/// # Safety
+/// See [`alloc::alloc::alloc`].
+#[inline(always)]
+pub(crate) unsafe fn alloc(layout: Layout) -> *mut u8 {
+    unsafe { Allocator::alloc(layout) }
+}
+/// # Safety
+/// See [`alloc::alloc::dealloc`].
+#[inline(always)]
+pub(crate) unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+    unsafe { Allocator::dealloc(ptr, layout) }
+}
+/// # Safety
+/// See [`alloc::alloc::realloc`].
+#[inline(always)]
+pub(crate) unsafe fn realloc(ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    unsafe { Allocator::realloc(ptr, layout, new_size) }
+}
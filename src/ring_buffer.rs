@@ -1,20 +1,146 @@
 
 extern crate alloc;
-use alloc::alloc::{alloc, dealloc};
-use core::{alloc::Layout, marker::PhantomData, mem::{size_of, align_of, MaybeUninit}, ptr::{NonNull, drop_in_place}};
+use alloc::{borrow::Cow, boxed::Box, vec::Vec};
+use crate::{guard::DropGuard, raw_alloc::{alloc, dealloc, realloc}, IoVec, Read, Write};
+use core::{alloc::Layout, cmp::Ordering, marker::PhantomData, mem::{size_of, size_of_val, align_of, MaybeUninit}, ptr::{NonNull, drop_in_place}};
+
+/// Evaluate `$body`, logging the change it makes to the cursors of `$buffer` as `$operation`
+/// with the `trace` feature. `$body` must not return early, or the change is not logged.
+macro_rules! traced {
+    ($buffer:expr, $operation:expr, $body:expr) => {{
+        #[cfg(feature = "trace")]
+        let before = $buffer.cursors();
+        let result = $body;
+        #[cfg(feature = "trace")]
+        crate::trace::emit($buffer, $operation, before);
+        result
+    }};
+}
+/// Define an `unsafe fn` as `const` unless the `trace` feature is enabled, as the trace sink
+/// cannot be called from a `const fn`.
+macro_rules! const_unless_traced {
+    ($(#[$attr:meta])* $vis:vis unsafe fn $($rest:tt)*) => {
+        #[cfg(not(feature = "trace"))]
+        $(#[$attr])* $vis const unsafe fn $($rest)*
+        #[cfg(feature = "trace")]
+        $(#[$attr])* $vis unsafe fn $($rest)*
+    };
+}
 
 /// A heap-allocated circular buffer.
 /// ```rust
 /// use circulate::RingBuffer;
 /// let mut fruit = RingBuffer::new();
-/// fruit.push("apples");
-/// fruit.push("oranges");
-/// fruit.push("pears");
-/// fruit.push("grapes");
+/// fruit.push("apples").unwrap();
+/// fruit.push("oranges").unwrap();
+/// fruit.push("pears").unwrap();
+/// fruit.push("grapes").unwrap();
 /// for f in fruit {
 ///     println!("Yummy {f}!")
 /// }
 /// ```
+/// # Small capacities
+/// One slot is always vacant, so a buffer holds `capacity() - 1` items before it grows.
+/// - [`RingBuffer::new`], [`RingBuffer::with_max_capacity`] and a capacity of 0 never allocate.
+///   The capacity is then 0 and the buffer is both empty and [full](RingBuffer::is_full), with no
+///   spare capacity.
+/// - [`RingBuffer::with_capacity`] holds at least the items asked for, rounded up so that the
+///   capacity is a power of two. A request for 1 item holds one, and for 2 items holds three.
+///   [`RingBuffer::with_exact_capacity`] always holds exactly the items asked for.
+/// - Removing items, reading and reserving space for no more items than are free never allocate
+///   or change the capacity, including on an empty or unallocated buffer.
+///
+/// Every operation behaves like a [`VecDeque`](std::collections::VecDeque) at each small
+/// capacity, length and position in the allocation.
+/// ```rust
+/// use std::collections::VecDeque;
+/// use circulate::RingBuffer;
+///
+/// let constructors: [fn(usize) -> RingBuffer<u8>; 2] = [RingBuffer::with_capacity, RingBuffer::with_exact_capacity];
+/// for (exact, new) in constructors.into_iter().enumerate() {
+///     for requested in 0..=2 {
+///         let capacity = new(requested).capacity();
+///         let holds = capacity.saturating_sub(1);
+///         assert_eq!(holds, if exact == 1 { requested } else { RingBuffer::<u8>::capacity_for(requested) });
+///         for len in 0..=holds {
+///             for offset in 0..capacity.max(1) {
+///                 let fresh = || {
+///                     let mut buffer = new(requested);
+///                     for _ in 0..offset {
+///                         buffer.push(0).unwrap();
+///                         buffer.pop();
+///                     }
+///                     let mut model = VecDeque::new();
+///                     for i in 1..=len as u8 {
+///                         buffer.push(i).unwrap();
+///                         model.push_back(i);
+///                     }
+///                     (buffer, model)
+///                 };
+///                 let check = |buffer: &mut RingBuffer<u8>, model: &VecDeque<u8>, capacity: usize| {
+///                     let context = format!("{exact} {requested} {len} {offset}");
+///                     assert!(buffer.iter().eq(model), "{context}");
+///                     assert_eq!(buffer.len(), model.len(), "{context}");
+///                     assert_eq!(buffer.is_empty(), model.is_empty(), "{context}");
+///                     assert_eq!(buffer.is_full(), buffer.len() + 1 >= buffer.capacity(), "{context}");
+///                     assert_eq!(buffer.capacity(), capacity, "{context}");
+///                     let (lhs, rhs) = buffer.spare_capacity_mut();
+///                     assert_eq!(lhs.len() + rhs.len(), capacity.saturating_sub(1) - model.len(), "{context}");
+///                     let (lhs, rhs) = buffer.spare_capacity_front_mut();
+///                     assert_eq!(lhs.len() + rhs.len(), capacity.saturating_sub(1) - model.len(), "{context}");
+///                 };
+///
+///                 let (mut buffer, model) = fresh();
+///                 check(&mut buffer, &model, capacity);
+///                 assert_eq!(buffer.is_full(), len == holds);
+///                 // Operations that never grow the buffer.
+///                 let operations: [fn(&mut RingBuffer<u8>, &mut VecDeque<u8>); 11] = [
+///                     |buffer, model| assert_eq!(buffer.pop(), model.pop_front()),
+///                     |buffer, model| {
+///                         assert_eq!(buffer.pop_latest(), model.pop_back());
+///                         model.clear();
+///                     },
+///                     |buffer, model| {
+///                         buffer.clear();
+///                         model.clear();
+///                     },
+///                     |buffer, model| assert_eq!(buffer.make_contiguous(), model.make_contiguous()),
+///                     |buffer, _| buffer.reserve(0).unwrap(),
+///                     |buffer, model| buffer.reserve(buffer.capacity().saturating_sub(1) - model.len()).unwrap(),
+///                     |buffer, _| buffer.extend_from_slice(&[]).unwrap(),
+///                     |buffer, model| {
+///                         assert_eq!(buffer.evict_while(|i| *i < 2), model.iter().take_while(|i| **i < 2).count());
+///                         model.retain(|i| *i >= 2);
+///                     },
+///                     |buffer, model| {
+///                         let index = model.iter().position(|i| *i == 2);
+///                         assert_eq!(buffer.find_and_remove(|i| *i == 2), index.and_then(|i| model.remove(i)));
+///                     },
+///                     |buffer, model| {
+///                         let mut out = [0; 2];
+///                         let popped = buffer.pop_slice(&mut out);
+///                         assert!(out[..popped].iter().copied().eq(model.drain(..popped)));
+///                     },
+///                     |buffer, model| assert_eq!(buffer.to_vec(), Vec::from(model.clone())),
+///                 ];
+///                 for operation in operations {
+///                     let (mut buffer, mut model) = fresh();
+///                     operation(&mut buffer, &mut model);
+///                     check(&mut buffer, &model, capacity);
+///                 }
+///                 // Pushing grows the buffer only once it is full.
+///                 let (mut buffer, mut model) = fresh();
+///                 let full = buffer.is_full();
+///                 buffer.push(0).unwrap();
+///                 model.push_back(0);
+///                 assert_eq!(buffer.capacity() != capacity, full);
+///                 let capacity = buffer.capacity();
+///                 check(&mut buffer, &model, capacity);
+///             }
+///         }
+///     }
+/// }
+/// ```
 pub struct RingBuffer<T> {
     data: NonNull<T>,
     // TODO: It may be better to store the mask (ie. capacity - 1) rather than the capacity.
@@ -23,105 +149,832 @@ pub struct RingBuffer<T> {
     read: usize,
     /// The index of the element to write next.
     write: usize,
+    /// The largest capacity the buffer may grow to, if bounded.
+    max_capacity: Option<usize>,
+    /// If the capacity is not rounded to a power of two, requiring indices to be wrapped by
+    /// comparison rather than masking.
+    exact: bool,
+    /// What to do when growing the buffer fails to allocate.
+    alloc_failure: AllocFailure,
+    /// Picks the capacity to grow to.
+    growth: &'static dyn GrowthStrategy,
+    /// The alignment of the allocation, which is at least that of `T`.
+    align: usize,
+    /// If consumed items and freed allocations are overwritten with zeros.
+    scrub: bool,
+    /// The total number of items pushed at either end, which less `popped` is always the length.
+    pushed: u64,
+    /// The total number of items popped.
+    popped: u64,
+    /// The absolute index of the item at the read end, which items pushed to the front are
+    /// numbered down from.
+    front: u64,
+    /// The fraction of the capacity below which popping shrinks the buffer, or 0 to never shrink.
+    shrink_threshold: f32,
+    /// The number of consecutive pops that left the buffer below the shrink threshold.
+    low_pops: usize,
     _phantom: PhantomData<T>,
 }
 impl<T> RingBuffer<T> {
-    pub const fn new() -> Self {
+    /// Create an empty buffer over an allocation of `capacity` slots aligned to `align`, with
+    /// every setting at its default.
+    const fn from_allocation(data: NonNull<T>, capacity: usize, align: usize) -> Self {
         Self {
-            data: NonNull::dangling(),
-            capacity: 0,
+            data,
+            capacity,
             read: 0,
             write: 0,
+            max_capacity: None,
+            exact: false,
+            alloc_failure: AllocFailure::Abort,
+            growth: &Doubling,
+            align,
+            scrub: false,
+            pushed: 0,
+            popped: 0,
+            front: 0,
+            shrink_threshold: 0.0,
+            low_pops: 0,
             _phantom: PhantomData,
         }
     }
-    fn alloc(capacity: usize) -> (NonNull<T>, usize) {
-        if let Some(layout) = Self::layout_for(capacity) {
-            // Safety: layout is non-zero.
-            let ptr = unsafe { alloc(layout) };
-            if ptr.is_null() {
-                alloc::alloc::handle_alloc_error(layout);
-            }
-            unsafe { (NonNull::new_unchecked(ptr).cast(), layout.size() / size_of::<T>()) }
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self::from_allocation(NonNull::dangling(), 0, align_of::<T>())
+    }
+    /// Get the number of items that [`RingBuffer::with_capacity`] makes space for when asked for
+    /// `requested`, before the buffer grows. This is at least `requested`, rounded up so that
+    /// `requested + 1` slots, including the one that is always vacant, is a power of two.
+    ///
+    /// Zero sized items are never allocated, so there is no space for them up front. Buffers of
+    /// them still grow as items are pushed, which only moves the cursors.
+    ///
+    /// # Panics
+    /// With "capacity overflow", the same as [`RingBuffer::with_capacity`].
+    /// ```rust
+    /// use circulate::RingBuffer;
+    ///
+    /// assert_eq!(RingBuffer::<u8>::capacity_for(0), 0);
+    /// assert_eq!(RingBuffer::<u8>::capacity_for(1), 1);
+    /// assert_eq!(RingBuffer::<u8>::capacity_for(2), 3);
+    /// assert_eq!(RingBuffer::<u8>::capacity_for(4), 7);
+    /// assert_eq!(RingBuffer::<u8>::capacity_for(4096), 8191);
+    ///
+    /// // Only the largest allocations depend on the size of the items.
+    /// fn check<T>() {
+    ///     for requested in (0..4100).chain([1 << 20, (1 << 20) + 1]) {
+    ///         let capacity = RingBuffer::<T>::capacity_for(requested);
+    ///         assert_eq!(capacity, RingBuffer::<u8>::capacity_for(requested));
+    ///         assert_eq!(RingBuffer::<T>::with_capacity(requested).capacity().saturating_sub(1), capacity);
+    ///     }
+    /// }
+    /// check::<u16>();
+    /// check::<[u8; 3]>();
+    /// check::<u128>();
+    /// check::<String>();
+    /// assert_eq!(RingBuffer::<()>::capacity_for(10), 0);
+    /// ```
+    pub const fn capacity_for(requested: usize) -> usize {
+        if size_of::<T>() == 0 {
+            return 0;
+        }
+        let slots = match Self::slots_for(requested).checked_next_power_of_two() {
+            Some(slots) if slots <= Self::max_capacity_for(align_of::<T>()) => slots,
+            _ => capacity_overflow(),
+        };
+        slots.saturating_sub(1)
+    }
+    /// Get the number of slots needed to hold `capacity` items, as one is always vacant.
+    const fn slots_for(capacity: usize) -> usize {
+        match capacity {
+            0 => 0,
+            capacity => match capacity.checked_add(1) {
+                Some(slots) => slots,
+                None => capacity_overflow(),
+            },
+        }
+    }
+    /// Allocate space for at least `capacity` items, or nothing if `capacity` is 0.
+    fn alloc(capacity: usize, align: usize) -> (NonNull<T>, usize) {
+        if capacity == 0 {
+            return (NonNull::dangling(), 0);
+        }
+        if let Some(layout) = Self::layout_for(capacity, align) {
+            Self::alloc_layout(layout)
         } else {
             (NonNull::dangling(), 0)
         }
     }
-    /// Create a new [`RingBuffer`] with space for at least `capacity` elements.
+    /// Allocate a non-zero sized `layout` returned by `layout_for` or `layout_for_exact`.
+    fn alloc_layout(layout: Layout) -> (NonNull<T>, usize) {
+        // Safety: layout is non-zero.
+        let ptr = unsafe { alloc(layout) };
+        if ptr.is_null() {
+            alloc::alloc::handle_alloc_error(layout);
+        }
+        unsafe { (NonNull::new_unchecked(ptr).cast(), layout.size() / size_of::<T>()) }
+    }
+    /// Create a new [`RingBuffer`] that holds at least `capacity` items before it grows.
+    ///
+    /// As one slot is always vacant, `capacity() - 1` items fit. The capacity is rounded up to a
+    /// power of two, so exactly [`RingBuffer::capacity_for`] items fit. A capacity of 0 does not
+    /// allocate, the same as [`RingBuffer::new`].
+    /// ```rust
+    /// use circulate::RingBuffer;
+    ///
+    /// for requested in 0..1000 {
+    ///     let buffer = RingBuffer::<u8>::with_capacity(requested);
+    ///     assert_eq!(buffer.capacity().saturating_sub(1), RingBuffer::<u8>::capacity_for(requested));
+    ///     assert!(buffer.capacity().saturating_sub(1) >= requested);
+    /// }
+    /// // Asking for one less than a power of two fills the allocation exactly.
+    /// let mut buffer = RingBuffer::with_capacity(7);
+    /// assert_eq!(buffer.capacity(), 8);
+    /// for i in 0..7u64 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// assert_eq!(buffer.capacity(), 8);
+    /// ```
+    ///
+    /// # Panics
+    /// The capacity of any buffer is limited so that its allocation spans at most `isize::MAX`
+    /// bytes. Panics with "capacity overflow" if `capacity` rounded up to a power of two is beyond
+    /// that limit, rather than allocating less than was asked for. Growing the buffer later is
+    /// limited in the same way, see [`RingBuffer::reserve`].
+    /// ```rust,should_panic
+    /// # use circulate::RingBuffer;
+    /// RingBuffer::<u8>::with_capacity(usize::MAX);
+    /// ```
+    /// ```rust,should_panic
+    /// # use circulate::RingBuffer;
+    /// // Rounds up to a capacity of `isize::MAX as usize + 1`.
+    /// RingBuffer::<u8>::with_capacity(isize::MAX as usize / 2 + 2);
+    /// ```
     pub fn with_capacity(capacity: usize) -> Self {
-        let (data, capacity) = Self::alloc(capacity);
-        Self {
-            data,
-            capacity,
-            read: 0,
-            write: 0,
-            _phantom: PhantomData,
+        let (data, capacity) = Self::alloc(Self::slots_for(capacity), align_of::<T>());
+        Self::from_allocation(data, capacity, align_of::<T>())
+    }
+    /// Create a new [`RingBuffer`] with space for exactly `capacity` elements.
+    /// Unlike [`RingBuffer::with_capacity`] the capacity is not rounded up to a power of two, at the
+    /// cost of slightly slower index arithmetic. When the buffer grows its capacity is doubled, or
+    /// set to exactly the required capacity if that is larger, unless another
+    /// [`GrowthStrategy`] is set.
+    /// A capacity of 0 does not allocate, the same as [`RingBuffer::new`].
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut samples = RingBuffer::with_exact_capacity(5);
+    /// assert_eq!(samples.capacity(), 6);
+    /// for sample in 0..5i16 {
+    ///     samples.push(sample).unwrap();
+    /// }
+    /// assert!(samples.is_full());
+    /// samples.pop();
+    /// samples.pop();
+    /// samples.push(5).unwrap();
+    /// samples.push(6).unwrap();
+    /// assert!(samples.iter().eq(&[2, 3, 4, 5, 6]));
+    ///
+    /// samples.push(7).unwrap();
+    /// assert_eq!(samples.capacity(), 12);
+    /// assert!(samples.iter().eq(&[2, 3, 4, 5, 6, 7]));
+    /// ```
+    pub fn with_exact_capacity(capacity: usize) -> Self {
+        // Note: One slot must always remain vacant.
+        let layout = match capacity {
+            0 => None,
+            capacity => Self::layout_for_exact(capacity.checked_add(1).unwrap_or_else(|| capacity_overflow()), align_of::<T>()),
+        };
+        let (data, capacity) = match layout {
+            Some(layout) => Self::alloc_layout(layout),
+            None => (NonNull::dangling(), 0),
+        };
+        let mut buffer = Self::from_allocation(data, capacity, align_of::<T>());
+        // Note: Zero sized items are never allocated, so the masked arithmetic is correct.
+        buffer.exact = size_of::<T>() != 0;
+        buffer
+    }
+    /// Create a new [`RingBuffer`] with space for at least `capacity` elements, in an allocation
+    /// aligned to at least `align` bytes.
+    ///
+    /// The alignment is kept when the buffer grows, so the start of the allocation is always
+    /// aligned. Enough space is allocated that the allocation spans at least `align` bytes, and
+    /// for element sizes that are a power of two its size is a multiple of `align`. Zero sized
+    /// elements are never allocated, so their alignment is unchanged.
+    ///
+    /// Only the first of the slices returned by [`RingBuffer::as_mut_slices`] is guaranteed to
+    /// start at the aligned address, and only once the buffer is contiguous. The items wrap at the
+    /// end of the allocation, so a DMA transfer cannot assume the whole buffer is physically
+    /// contiguous. Transfer in to or out of each slice of [`RingBuffer::spare_capacity_mut`] or
+    /// [`RingBuffer::as_mut_slices`] separately instead.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::<u8>::with_capacity_aligned(16, 32);
+    /// assert_eq!(buffer.capacity(), 32);
+    /// for byte in 0..255 {
+    ///     buffer.push(byte).unwrap();
+    ///     buffer.make_contiguous();
+    ///     assert_eq!(buffer.as_mut_slices().0.as_ptr() as usize % 32, 0);
+    /// }
+    /// assert_eq!(buffer.capacity(), 256);
+    ///
+    /// let mut samples = RingBuffer::<u16>::with_capacity_aligned(4, 64);
+    /// samples.reserve(100).unwrap();
+    /// assert_eq!(samples.as_mut_slices().0.as_ptr() as usize % 64, 0);
+    /// ```
+    /// # Panics
+    /// Panics if `align` is not a power of two.
+    pub fn with_capacity_aligned(capacity: usize, align: usize) -> Self {
+        assert!(align.is_power_of_two(), "align must be a power of two");
+        let align = align.max(align_of::<T>());
+        let capacity = match size_of::<T>() {
+            size if size != 0 && capacity != 0 => Self::slots_for(capacity).max(align.div_ceil(size)),
+            _ => capacity,
+        };
+        let (data, capacity) = Self::alloc(capacity, align);
+        Self::from_allocation(data, capacity, align)
+    }
+    /// Create a new, empty [`RingBuffer`] that will never grow beyond a capacity of `max` elements.
+    /// See [`RingBuffer::set_max_capacity`].
+    pub const fn with_max_capacity(max: usize) -> Self {
+        let mut buffer = Self::from_allocation(NonNull::dangling(), 0, align_of::<T>());
+        buffer.max_capacity = Some(max);
+        buffer
+    }
+    /// Create a [`RingBuffer`] from the raw components of another buffer, taking ownership of the
+    /// allocation and the initialized elements.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// for fruit in ["apples", "oranges", "pears"] {
+    ///     buffer.push(fruit.to_owned()).unwrap();
+    /// }
+    /// buffer.pop();
+    /// let (ptr, capacity, read, write) = buffer.into_raw_parts();
+    /// let buffer = unsafe { RingBuffer::from_raw_parts(ptr, capacity, read, write) };
+    /// assert!(buffer.iter().eq(["oranges", "pears"]));
+    /// ```
+    /// # Safety
+    /// - `capacity` must be 0, or a power of two no smaller than 2.
+    /// - If `capacity` is not 0 and `T` is not zero-sized, `ptr` must have been allocated by the
+    ///   global allocator with the layout of `[T; capacity]`. Otherwise `ptr` must be non-null and
+    ///   aligned, such as [`NonNull::dangling`].
+    /// - `read` and `write` must be less than `capacity`, or both 0 if `capacity` is 0.
+    /// - The elements from `read` up to `write`, wrapping at `capacity`, must be initialized.
+    ///   At least one slot must be vacant, so `read == write` describes an empty buffer.
+    /// - Ownership of the allocation and the initialized elements is transferred to the
+    ///   [`RingBuffer`]; nothing else may use or free them afterwards.
+    pub unsafe fn from_raw_parts(ptr: *mut T, capacity: usize, read: usize, write: usize) -> Self {
+        // Safety: The caller guarantees `ptr` is non-null.
+        let mut buffer = Self::from_allocation(unsafe { NonNull::new_unchecked(ptr) }, capacity, align_of::<T>());
+        buffer.read = read;
+        buffer.write = write;
+        buffer.pushed = buffer.len() as u64;
+        buffer
+    }
+    /// Decompose the [`RingBuffer`] in to its raw pointer, capacity, read cursor and write cursor.
+    /// The caller becomes responsible for the allocation and the initialized elements, which can be
+    /// returned with [`RingBuffer::from_raw_parts`].
+    pub fn into_raw_parts(self) -> (*mut T, usize, usize, usize) {
+        let this = core::mem::ManuallyDrop::new(self);
+        (this.data.as_ptr(), this.capacity, this.read, this.write)
+    }
+
+    /// Limit the capacity of the buffer to at most `max` elements.
+    /// As the capacity is always a power of two, the buffer will not grow past the largest
+    /// power of two no greater than `max`. Once the limit is reached [`RingBuffer::push`] hands the
+    /// value back and [`RingBuffer::reserve`] fails rather than growing.
+    /// An existing allocation larger than `max` is kept.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::new();
+    /// buffer.set_max_capacity(4);
+    /// for i in 0..3u8 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// assert_eq!(buffer.push(3), Err(3));
+    /// assert!(buffer.iter().eq(&[0, 1, 2]));
+    /// assert!(buffer.reserve(1).is_err());
+    /// assert_eq!(buffer.capacity(), 4);
+    /// ```
+    pub const fn set_max_capacity(&mut self, max: usize) {
+        self.max_capacity = Some(max);
+    }
+    /// Set what happens when the buffer fails to allocate while growing.
+    ///
+    /// By default the process is aborted. With [`AllocFailure::Saturate`] the buffer instead
+    /// stays at its current capacity, as if it had reached its maximum capacity: growing fails
+    /// with a [`CapacityError`] and [`RingBuffer::push`] hands the value back.
+    pub const fn set_alloc_failure_mode(&mut self, mode: AllocFailure) {
+        self.alloc_failure = mode;
+    }
+    /// Get what happens when the buffer fails to allocate while growing.
+    pub const fn alloc_failure_mode(&self) -> AllocFailure {
+        self.alloc_failure
+    }
+    /// Set how the buffer picks the capacity to grow to. The default is [`Doubling`].
+    ///
+    /// Buffers created by [`RingBuffer::with_exact_capacity`] grow to exactly the capacity picked,
+    /// while others round it up to a power of two.
+    pub const fn set_growth_strategy(&mut self, strategy: &'static dyn GrowthStrategy) {
+        self.growth = strategy;
+    }
+    /// Get how the buffer picks the capacity to grow to.
+    pub const fn growth_strategy(&self) -> &'static dyn GrowthStrategy {
+        self.growth
+    }
+    /// Get the largest capacity the buffer may grow to, or [`None`] if it is unbounded.
+    pub const fn max_capacity(&self) -> Option<usize> {
+        self.max_capacity
+    }
+    /// Shrink the buffer as items are popped once it stays less than `threshold` full, so that
+    /// memory taken by a burst of items is given back. A threshold of 0, the default, never shrinks.
+    ///
+    /// The buffer shrinks once [`RingBuffer::pop`] has left it below the threshold more times in a
+    /// row than it has items, so the cost of moving the items is spread over those pops. It shrinks
+    /// to twice the length, leaving room to hover around the same length without growing again.
+    /// # Panics
+    /// Panics if `threshold` is not between 0 and 1.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// // A burst of items, followed by hovering around a handful, pushing one for each popped.
+    /// let burst_then_hover = |threshold| {
+    ///     let mut buffer = RingBuffer::new();
+    ///     buffer.set_auto_shrink(threshold);
+    ///     for i in 0..1000 {
+    ///         buffer.push(i).unwrap();
+    ///     }
+    ///     assert_eq!(buffer.capacity(), 1024);
+    ///     while buffer.len() > 8 {
+    ///         buffer.pop();
+    ///     }
+    ///     for i in 0..10_000 {
+    ///         buffer.push(i).unwrap();
+    ///         buffer.pop();
+    ///     }
+    ///     assert_eq!(buffer.len(), 8);
+    ///     buffer.capacity()
+    /// };
+    /// assert_eq!(burst_then_hover(0.25), 16);
+    /// assert_eq!(burst_then_hover(0.0), 1024);
+    /// ```
+    #[track_caller]
+    pub fn set_auto_shrink(&mut self, threshold: f32) {
+        assert!((0.0..=1.0).contains(&threshold), "shrink threshold {threshold} is not between 0 and 1");
+        self.shrink_threshold = threshold;
+        self.low_pops = 0;
+    }
+    /// Get the fraction of the capacity below which popping shrinks the buffer, or 0 if it never shrinks.
+    pub const fn auto_shrink(&self) -> f32 {
+        self.shrink_threshold
+    }
+    /// Count a pop towards shrinking the buffer, and shrink it if it has been below the threshold
+    /// for long enough.
+    fn shrink_after_pop(&mut self) {
+        if self.shrink_threshold == 0.0 {
+            return;
+        }
+        if self.len() as f32 >= self.capacity as f32 * self.shrink_threshold {
+            self.low_pops = 0;
+            return;
+        }
+        self.low_pops += 1;
+        if self.low_pops > self.len() {
+            self.low_pops = 0;
+            self.shrink_to(self.len() * 2);
+        }
+    }
+    /// Move the items in to a smaller allocation with a capacity of at least `capacity`, if that
+    /// would be smaller than the current one. Failing to allocate leaves the buffer as it is.
+    pub(crate) fn shrink_to(&mut self, capacity: usize) {
+        // Note: One slot must always remain vacant.
+        let required = capacity.max(self.len() + 1);
+        let layout = if self.exact {
+            Self::layout_for_exact(required, self.align)
+        } else {
+            Self::layout_for(required, self.align)
+        };
+        if let Some(layout) = layout.filter(|layout| layout.size() / size_of::<T>() < self.capacity) {
+            self.reallocate(layout);
+        }
+    }
+    /// Move the items in to the smallest allocation that holds them, giving back memory after a
+    /// burst. Capacities are rounded up to a power of two, unless the buffer has an exact capacity.
+    /// Failing to allocate leaves the buffer as it is.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::new();
+    /// buffer.extend(0..1000);
+    /// assert_eq!(buffer.capacity(), 1024);
+    /// buffer.drain(995);
+    /// buffer.shrink_to_fit();
+    /// assert_eq!(buffer.capacity(), 8);
+    /// assert!(buffer.iter().eq(&[995, 996, 997, 998, 999]));
+    ///
+    /// let mut buffer = RingBuffer::with_exact_capacity(100);
+    /// buffer.extend(0..5);
+    /// buffer.shrink_to_fit();
+    /// assert_eq!(buffer.capacity(), 6);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+    /// Get the largest capacity the buffer can actually grow to within its maximum capacity,
+    /// which for a capacity rounded to a power of two may be less than the maximum.
+    pub(crate) const fn reachable_capacity(&self) -> usize {
+        let reachable = match self.max_capacity {
+            None => return usize::MAX,
+            Some(max) if self.exact || max == 0 => max,
+            Some(max) => 1 << max.ilog2(),
+        };
+        if reachable > self.capacity {
+            reachable
+        } else {
+            self.capacity
         }
     }
 
     /// Ensure there is space for at least `count` more elements.
-    pub fn reserve(&mut self, count: usize) {
-        let Some(layout) = Self::layout_for(self.capacity + count) else {
-            // Reserved 0 bytes while empty.
-            return;
+    /// Fails without modifying the buffer if this requires growing beyond the maximum capacity.
+    /// Reserving space for 0 elements never allocates.
+    ///
+    /// # Panics
+    /// Panics if no allocation could hold the items, as described for
+    /// [`RingBuffer::with_capacity`], unless the buffer has a maximum capacity, in which case
+    /// reserving fails instead.
+    /// ```rust
+    /// use circulate::{AllocFailure, RingBuffer};
+    /// let mut buffer = RingBuffer::<u64>::with_max_capacity(1024);
+    /// buffer.push(1).unwrap();
+    /// for count in [usize::MAX, usize::MAX - 1, usize::MAX / 2, isize::MAX as usize / 8] {
+    ///     assert_eq!(buffer.reserve(count).unwrap_err().requested(), usize::MAX);
+    /// }
+    /// assert_eq!(buffer.reserve(2000).unwrap_err().requested(), 2048);
+    /// assert!(buffer.iter().eq(&[1]));
+    ///
+    /// /// An item so large that only a few fit in the address space.
+    /// struct Huge([u8; isize::MAX as usize / 4]);
+    /// let mut buffer = RingBuffer::<Huge>::with_max_capacity(1024);
+    /// buffer.set_alloc_failure_mode(AllocFailure::Saturate);
+    /// assert!(buffer.reserve(2).unwrap_err().allocation_failed());
+    /// assert_eq!(buffer.reserve(4).unwrap_err().requested(), usize::MAX);
+    /// ```
+    /// ```rust,should_panic
+    /// # use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::<u8>::with_capacity(8);
+    /// buffer.push(0).unwrap();
+    /// // The required capacity overflows `usize`.
+    /// buffer.reserve(usize::MAX - 1).ok();
+    /// ```
+    /// ```rust,should_panic
+    /// # use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::<u8>::with_exact_capacity(8);
+    /// // The required capacity fits in `usize` but exceeds `isize::MAX` bytes.
+    /// buffer.reserve(usize::MAX - 16).ok();
+    /// ```
+    pub fn reserve(&mut self, count: usize) -> Result<(), CapacityError> {
+        self.reserve_for(count, true)
+    }
+    /// Ensure there is space for at least `count` more elements, growing to no more than is
+    /// needed rather than as the [`GrowthStrategy`] picks. Capacities are still rounded up to a
+    /// power of two, unless the buffer has an exact capacity.
+    ///
+    /// Prefer [`RingBuffer::reserve`] when more items may follow, so that growth is amortized.
+    /// Fails and panics as [`RingBuffer::reserve`] does.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_exact_capacity(4);
+    /// buffer.extend_from_slice(&[1, 2, 3, 4]).unwrap();
+    /// buffer.reserve_exact(3).unwrap();
+    /// assert_eq!(buffer.capacity(), 8);
+    /// // There is already space.
+    /// buffer.reserve_exact(3).unwrap();
+    /// assert_eq!(buffer.capacity(), 8);
+    /// buffer.reserve(4).unwrap();
+    /// assert_eq!(buffer.capacity(), 16);
+    ///
+    /// let mut buffer = RingBuffer::<u8>::with_capacity(64);
+    /// buffer.reserve_exact(100).unwrap();
+    /// assert_eq!(buffer.capacity(), 128);
+    /// ```
+    pub fn reserve_exact(&mut self, count: usize) -> Result<(), CapacityError> {
+        self.reserve_for(count, false)
+    }
+    fn reserve_for(&mut self, count: usize, use_strategy: bool) -> Result<(), CapacityError> {
+        // Note: One slot must always remain vacant, and the length is always less than the
+        // capacity unless both are 0.
+        if count == 0 || count < self.capacity - self.len() {
+            return Ok(());
+        }
+        let grown = (self.len() + 1).checked_add(count).map_or(Ok(None), |required| self.grown_capacity(required, use_strategy));
+        let capacity = match grown {
+            Ok(Some(capacity)) => capacity,
+            Ok(None) => return match self.max_capacity {
+                Some(max) => Err(CapacityError { requested: usize::MAX, max, reason: CapacityErrorReason::Max }),
+                None => capacity_overflow(),
+            },
+            Err(required) => return Err(CapacityError {
+                requested: required,
+                max: self.capacity,
+                reason: CapacityErrorReason::Strategy,
+            }),
+        };
+        if let Some(max) = self.max_capacity {
+            if capacity > max {
+                return Err(CapacityError { requested: capacity, max, reason: CapacityErrorReason::Max });
+            }
+        }
+        if size_of::<T>() == 0 {
+            // Zero sized items need no allocation, so only the cursors move, as when reallocating.
+            traced!(self, crate::trace::Operation::Reallocate, {
+                self.write = self.len();
+                self.read = 0;
+                self.capacity = capacity;
+            });
+            return Ok(());
+        }
+        let Some(layout) = Self::layout_for_exact(capacity, self.align) else {
+            unreachable!("the capacity is non-zero and items are not zero sized")
         };
+        if self.reallocate(layout) {
+            return Ok(());
+        }
+        match self.alloc_failure {
+            AllocFailure::Abort => alloc::alloc::handle_alloc_error(layout),
+            AllocFailure::Saturate => Err(CapacityError {
+                requested: capacity,
+                max: self.capacity,
+                reason: CapacityErrorReason::Allocation,
+            }),
+        }
+    }
+    /// Get the capacity to grow to so that at least `required` items fit, or [`None`] if no
+    /// allocation could hold that many. Fails with `required` if the growth strategy picked less.
+    /// Without `use_strategy` only `required` is rounded.
+    fn grown_capacity(&self, required: usize, use_strategy: bool) -> Result<Option<usize>, usize> {
+        let max = Self::max_capacity_for(self.align);
+        let picked = if use_strategy { self.growth.next_capacity(self.capacity, required) } else { required };
+        if picked < required {
+            return Err(required);
+        }
+        // Growing beyond what is required is only an optimization, so it is limited rather than
+        // failing.
+        let capacity = required.max(picked.min(max));
+        let capacity = if self.exact {
+            Some(capacity)
+        } else {
+            // Note: A capacity of 1 is an effective capacity of 0.
+            capacity.checked_next_power_of_two().map(|capacity| capacity.max(2))
+        };
+        Ok(capacity.filter(|&capacity| capacity <= max))
+    }
+    /// Move the items in to a new allocation with `layout`, which must be large enough to hold
+    /// them and a vacant slot. Returns `false` without modifying the buffer if allocating fails.
+    fn reallocate(&mut self, layout: Layout) -> bool {
         // Note: If `realloc()` is used the data may need an extra move, it may be more efficient to
         // just use `alloc()` and `dealloc()` so only the necessary data is copied.
         // Safety: layout is non-zero.
         let ptr = unsafe { alloc(layout) };
         if ptr.is_null() {
-            alloc::alloc::handle_alloc_error(layout);
+            return false;
         }
         let data = unsafe { NonNull::new_unchecked(ptr).cast() };
         let capacity = layout.size() / size_of::<T>();
 
-        let Some(old_layout) = self.layout() else {
-            // No previous allocation
-            self.data = data.cast();
-            self.capacity = capacity;
-            return;
-        };
+        traced!(self, crate::trace::Operation::Reallocate, {
+            // Note: Without a previous allocation there are no items to move.
+            if let Some(old_layout) = self.layout() {
+                let (data_lhs, data_rhs) = self.as_mut_slices();
+                let lhs_bytes = size_of_val(data_lhs);
+                let rhs_bytes = size_of_val(data_rhs);
+                // Safety: The new `data` pointer points to an area large enough for the items.
+                unsafe {
+                    <*mut u8>::copy_from(data.as_ptr(), data_lhs.as_ptr().cast(), lhs_bytes);
+                    <*mut u8>::copy_from(data.as_ptr().add(lhs_bytes), data_rhs.as_ptr().cast(), rhs_bytes);
+                }
+                let len = data_lhs.len() + data_rhs.len();
+                self.read = 0;
+                self.write = len;
 
-        {
-            let (data_lhs, data_rhs) = self.as_mut_slices();
-            let lhs_bytes = data_lhs.len() * size_of::<T>();
-            let rhs_bytes = data_rhs.len() * size_of::<T>();
-            // Safety: The new `data` pointer points to a larger area than the old data.
-            unsafe {
-                <*mut u8>::copy_from(data.as_ptr(), data_lhs.as_ptr().cast(), lhs_bytes);
-                <*mut u8>::copy_from(data.as_ptr().offset(lhs_bytes as isize), data_rhs.as_ptr().cast(), rhs_bytes);
+                unsafe {
+                    self.scrub_allocation();
+                    dealloc(self.data.as_ptr().cast(), old_layout);
+                }
             }
-            self.read = 0;
-            self.write = lhs_bytes + rhs_bytes;
-        }
-
-        unsafe {
-            dealloc(self.data.as_ptr().cast(), old_layout);
-        }
-        self.data = data.cast();
-        self.capacity = capacity;
+            self.data = data.cast();
+            self.capacity = capacity;
+        });
+        true
     }
 
     /// Remove all values from the [`RingBuffer`].
     /// The previous capacity will be retained.
+    ///
+    /// If dropping an item panics, the rest are still dropped and the buffer is left empty.
     pub fn clear(&mut self) {
-        let (left, right) = self.as_mut_slices();
-        // Safety: Slices have the same requirements as `drop_in_place()`.
-        unsafe {
-            drop_in_place(left);
-            drop_in_place(right);
+        traced!(self, crate::trace::Operation::Clear, {
+            let (left, right) = self.as_mut_slices();
+            let (left, right): (*mut [T], *mut [T]) = (left, right);
+            let len = self.len();
+            // Empty the buffer once the items are dropped, even if dropping one panics.
+            let _empty = DropGuard::new(&mut *self, |buffer| {
+                if buffer.scrub {
+                    // Safety: The items were dropped, so only vacant slots are overwritten.
+                    unsafe { buffer.scrub_front(len) };
+                }
+                buffer.popped = buffer.popped.wrapping_add(len as u64);
+                buffer.front = buffer.front.wrapping_add(len as u64);
+                buffer.read = 0;
+                buffer.write = 0;
+            });
+            // Safety: The slices are of the items, which are then forgotten by the buffer.
+            unsafe { drop_runs(left, right) };
+        })
+    }
+    /// Drop the items and turn the empty buffer in to a buffer of `U`, keeping the allocation
+    /// where it can hold a whole number of `U` at the alignment it was made with. Nothing is
+    /// copied.
+    ///
+    /// The allocation of `capacity * size_of::<T>()` bytes is kept, as a capacity of that many
+    /// bytes divided by `size_of::<U>()`, if:
+    /// - `U` is not zero sized, and the bytes divide exactly by its size.
+    /// - The alignment of the allocation, that of `T` or that given to
+    ///   [`RingBuffer::with_capacity_aligned`], is at least that of `U`.
+    /// - The new capacity is at least 2, and a power of two unless the buffer was made by
+    ///   [`RingBuffer::with_exact_capacity`].
+    ///
+    /// Otherwise the allocation is freed and the buffer returned has no capacity. The maximum
+    /// capacity is in items of `T` so is not kept, while the other settings are.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    ///
+    /// // Allocated aligned for the words it is also used for.
+    /// let mut bytes = RingBuffer::<u8>::with_capacity_aligned(63, 4);
+    /// let allocation = bytes.spare_capacity_mut().0.as_ptr() as usize;
+    /// bytes.extend_from_slice(b"scratch").unwrap();
+    ///
+    /// // 64 bytes hold 16 words.
+    /// let mut words = bytes.into_empty_retyped::<u32>();
+    /// assert_eq!(words.capacity(), 16);
+    /// assert_eq!(words.spare_capacity_mut().0.as_ptr() as usize, allocation);
+    /// words.extend_from_slice(&[1, 2, 3]).unwrap();
+    /// // And back again.
+    /// let bytes = words.into_empty_retyped::<u8>();
+    /// assert_eq!(bytes.capacity(), 64);
+    /// assert!(bytes.is_empty());
+    ///
+    /// // Words are only aligned to 4 bytes, too little for items needing 8 byte alignment.
+    /// let words = RingBuffer::<u32>::with_capacity(15);
+    /// assert_eq!(words.into_empty_retyped::<u64>().capacity(), 0);
+    /// let mut words = RingBuffer::<u32>::with_capacity_aligned(15, 8);
+    /// let allocation = words.spare_capacity_mut().0.as_ptr() as usize;
+    /// let mut longs = words.into_empty_retyped::<u64>();
+    /// assert_eq!(longs.capacity(), 8);
+    /// assert_eq!(longs.spare_capacity_mut().0.as_ptr() as usize, allocation);
+    ///
+    /// // 64 bytes do not divide in to 3 byte items, and 6 exact bytes give 3 pairs, but not 4 bytes.
+    /// assert_eq!(RingBuffer::<u8>::with_capacity(63).into_empty_retyped::<[u8; 3]>().capacity(), 0);
+    /// let exact = RingBuffer::<u8>::with_exact_capacity(5);
+    /// assert_eq!(exact.into_empty_retyped::<[u8; 2]>().capacity(), 3);
+    /// let exact = RingBuffer::<u8>::with_exact_capacity(5);
+    /// assert_eq!(exact.into_empty_retyped::<u32>().capacity(), 0);
+    /// ```
+    pub fn into_empty_retyped<U>(mut self) -> RingBuffer<U> {
+        self.clear();
+        let mut retyped = RingBuffer::<U>::new();
+        retyped.alloc_failure = self.alloc_failure;
+        retyped.growth = self.growth;
+        retyped.scrub = self.scrub;
+        retyped.shrink_threshold = self.shrink_threshold;
+        let Some(layout) = self.layout() else {
+            return retyped;
+        };
+        let capacity = layout.size().checked_div(size_of::<U>()).unwrap_or(0);
+        let compatible = capacity * size_of::<U>() == layout.size()
+            && align_of::<U>() <= self.align
+            && capacity >= 2
+            && (self.exact || capacity.is_power_of_two());
+        if !compatible {
+            // Note: Dropping `self` frees the allocation.
+            return retyped;
         }
-        self.read = 0;
-        self.write = 0;
+        let this = core::mem::ManuallyDrop::new(self);
+        // The allocation of `layout` is handed over, and is freed by the new buffer with the same
+        // layout as `capacity` items of `U`, aligned to `align`.
+        retyped.data = this.data.cast();
+        retyped.capacity = capacity;
+        retyped.align = this.align;
+        retyped.exact = this.exact;
+        retyped
+    }
+    /// Create an empty buffer with the same settings and capacity, such as to replace a buffer
+    /// that was taken.
+    pub(crate) fn empty_like(&self) -> Self {
+        let mut empty = Self::new();
+        empty.adopt_settings(self);
+        empty.exact = self.exact;
+        empty.align = self.align;
+        let _ = empty.reserve(self.capacity.saturating_sub(1));
+        empty
+    }
+    /// Take on the settings of `other` that are kept as the buffer is used, such as its maximum
+    /// capacity and growth strategy, keeping the allocation and items of `self`.
+    pub(crate) fn adopt_settings(&mut self, other: &Self) {
+        self.max_capacity = other.max_capacity;
+        self.alloc_failure = other.alloc_failure;
+        self.growth = other.growth;
+        self.scrub = other.scrub;
+        self.shrink_threshold = other.shrink_threshold;
+    }
+    /// Drop up to `count` items from the read end in bulk. Returns the number of items dropped.
+    fn drop_front(&mut self, count: usize) -> usize {
+        let (lhs, rhs) = self.as_mut_slices();
+        let lhs_len = lhs.len().min(count);
+        let rhs_len = rhs.len().min(count - lhs_len);
+        let (lhs, rhs): (*mut [T], *mut [T]) = (&mut lhs[..lhs_len], &mut rhs[..rhs_len]);
+        // Skip over the items once they are dropped, even if dropping one panics.
+        // Safety: The items are dropped before the read cursor skips over them.
+        let _skip = DropGuard::new(&mut *self, |buffer| unsafe { buffer.advance_read_unchecked(lhs_len + rhs_len) });
+        // Safety: The slices are of the items, which are then skipped over by the read cursor.
+        unsafe { drop_runs(lhs, rhs) };
+        lhs_len + rhs_len
+    }
+    /// Drop items from the write end in bulk until at most `len` remain, keeping the oldest.
+    /// Nothing happens if there are no more than `len` items.
+    ///
+    /// If dropping an item panics, the rest are still dropped and the buffer is left with `len`
+    /// items. The dropped items count as popped, as by [`RingBuffer::pop_back`].
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_exact_capacity(4);
+    /// buffer.extend([0, 0, 1, 2]);
+    /// buffer.pop();
+    /// buffer.pop();
+    /// // The items wrap around the end of the allocation.
+    /// buffer.extend([3, 4]);
+    /// buffer.truncate(1);
+    /// assert!(buffer.iter().eq(&[1]));
+    /// buffer.truncate(5);
+    /// assert_eq!(buffer.len(), 1);
+    /// ```
+    pub fn truncate(&mut self, len: usize) {
+        let Some(excess) = self.len().checked_sub(len) else {
+            return;
+        };
+        traced!(self, crate::trace::Operation::Truncate(len), {
+            let (lhs, rhs) = self.as_mut_slices();
+            let rhs_len = rhs.len().min(excess);
+            let lhs_len = excess - rhs_len;
+            let (lhs_start, rhs_start) = (lhs.len() - lhs_len, rhs.len() - rhs_len);
+            let (lhs, rhs): (*mut [T], *mut [T]) = (&mut lhs[lhs_start..], &mut rhs[rhs_start..]);
+            // Forget the items once they are dropped, even if dropping one panics.
+            let _forget = DropGuard::new(&mut *self, |buffer| {
+                // Note: The dropped items count as popped from the back, so the next push reuses
+                // their absolute indices.
+                buffer.popped = buffer.popped.wrapping_add(excess as u64);
+                buffer.write = buffer.wrap(buffer.read + len);
+                if buffer.scrub {
+                    let data = buffer.data.as_ptr();
+                    // Safety: The items were dropped, so only vacant slots are overwritten.
+                    unsafe {
+                        data.add(lhs_start).add(buffer.read).write_bytes(0, lhs_len);
+                        data.add(rhs_start).write_bytes(0, rhs_len);
+                    }
+                }
+            });
+            // Safety: The slices are of the items, which are then forgotten by the buffer.
+            unsafe { drop_runs(lhs, rhs) };
+        })
     }
 
     /// Returns if there are no items in the buffer.
-    pub const fn empty(&self) -> bool {
+    pub const fn is_empty(&self) -> bool {
         self.read == self.write
     }
-    /// Returns if the length of the buffer has reached its capacity.
+    /// Returns if the buffer must grow before another item can be pushed, as its length has
+    /// reached its capacity. A buffer that has not allocated is always full.
+    pub const fn is_full(&self) -> bool {
+        // Note: One slot must always remain vacant.
+        self.len() + 1 >= self.capacity
+    }
+    /// Returns if there are no items in the buffer.
+    /// ```rust
+    /// # #![allow(deprecated)]
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(2);
+    /// for _ in 0..3 {
+    ///     assert_eq!(buffer.empty(), buffer.is_empty());
+    ///     assert_eq!(buffer.full(), buffer.is_full());
+    ///     buffer.push(1).unwrap();
+    /// }
+    /// ```
+    #[deprecated(note = "renamed to `RingBuffer::is_empty`")]
+    pub const fn empty(&self) -> bool {
+        self.is_empty()
+    }
+    /// Returns if the buffer must grow before another item can be pushed.
+    #[deprecated(note = "renamed to `RingBuffer::is_full`")]
     pub const fn full(&self) -> bool {
-        (self.write + 1) & self.mask() == self.read
+        self.is_full()
     }
     
     /// Get the number of items in the [`RingBuffer`].
@@ -138,28 +991,272 @@ impl<T> RingBuffer<T> {
     pub const fn capacity(&self) -> usize {
         self.capacity
     }
+    /// Forget the items, leaving the buffer empty but keeping its allocation, and return the
+    /// allocation along with the read and write cursors of the items.
+    ///
+    /// The caller becomes responsible for dropping the items.
+    pub(crate) fn forget_items(&mut self) -> (NonNull<T>, usize, usize) {
+        let parts = (self.data, self.read, self.write);
+        self.popped = self.pushed;
+        self.front = self.front.wrapping_add(self.len() as u64);
+        self.read = 0;
+        self.write = 0;
+        parts
+    }
+    /// Get the cursors and capacity, as logged by the `trace` feature.
+    #[cfg(feature = "trace")]
+    pub(crate) const fn cursors(&self) -> crate::trace::Cursors {
+        crate::trace::Cursors { read: self.read, write: self.write, capacity: self.capacity }
+    }
 
-    /// Set the read cursor to point to `count` items past the current location.
+    /// Get the total number of items ever pushed to the buffer, at either end.
+    /// Items written through [`RingBuffer::advance_write_unchecked`] or prepended with
+    /// [`RingBuffer::set_read_cursor_back`] count as pushed.
+    pub const fn total_pushed(&self) -> u64 {
+        self.pushed
+    }
+    /// Get the total number of items ever popped from the buffer.
+    ///
+    /// Items consumed in any way count as popped, including by [`RingBuffer::clear`],
+    /// [`RingBuffer::pop_back`] and items removed from the middle of the buffer. Both totals only
+    /// ever grow, so their difference is the length.
+    pub const fn total_popped(&self) -> u64 {
+        self.popped
+    }
+    /// Get the absolute index of the item at `logical` index, which counts the items pushed
+    /// before it, or [`None`] if the index is out of bounds.
+    ///
+    /// Absolute indices identify items while items are popped and pushed. Items pushed to the
+    /// front are numbered down from the item after them, wrapping below 0. Removing an item from
+    /// the middle of the buffer renumbers the items before it.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(4);
+    /// for i in 0..3 {
+    ///     buffer.push(i * 10).unwrap();
+    /// }
+    /// assert_eq!(buffer.absolute_index_of(2), Some(2));
+    /// buffer.pop();
+    /// buffer.push(30).unwrap();
+    /// assert_eq!(buffer.absolute_index_of(1), Some(2));
+    /// assert_eq!(buffer.logical_index_of(2), Some(1));
+    /// assert_eq!(buffer.logical_index_of(0), None);
+    /// assert_eq!(buffer.absolute_index_of(3), None);
+    ///
+    /// buffer.extend_from_slice(&[40, 50, 60]).unwrap();
+    /// buffer.pop_slice(&mut [0; 4]);
+    /// assert!(buffer.iter().eq(&[50, 60]));
+    /// assert_eq!(buffer.absolute_index_of(0), Some(5));
+    /// buffer.clear();
+    /// buffer.push(70).unwrap();
+    /// assert_eq!(buffer.absolute_index_of(0), Some(7));
+    /// assert_eq!((buffer.total_pushed(), buffer.total_popped()), (8, 7));
+    ///
+    /// let mut items = buffer.into_iter();
+    /// assert_eq!(items.next(), Some(70));
+    /// ```
+    pub const fn absolute_index_of(&self, logical: usize) -> Option<u64> {
+        if logical < self.len() {
+            Some(self.front.wrapping_add(logical as u64))
+        } else {
+            None
+        }
+    }
+    /// Get the logical index of the item with an absolute index, or [`None`] if the item is no
+    /// longer or not yet in the buffer. See [`RingBuffer::absolute_index_of`].
+    pub const fn logical_index_of(&self, absolute: u64) -> Option<usize> {
+        let logical = absolute.wrapping_sub(self.front);
+        if logical < self.len() as u64 {
+            Some(logical as usize)
+        } else {
+            None
+        }
+    }
+    const_unless_traced! {
+        /// Advance the read cursor by `count` items, consuming them in place without dropping them.
+        ///
+        /// This is a low level method for consuming items in place. Prefer the safe methods that pop
+        /// or drain items.
+        /// # Safety
+        /// The buffer must be readable for `count` more elements.
+        /// The `count` must not overflow one less than the remaining `capacity`,
+        /// an equal read and write cursor indicates an empty [`RingBuffer`].
+        pub unsafe fn advance_read_unchecked(&mut self, count: usize) {
+            traced!(self, crate::trace::Operation::SetReadCursor(count), {
+                if self.scrub {
+                    // Safety: The caller guarantees the `count` items are readable, so they are allocated.
+                    unsafe { self.scrub_front(count) };
+                }
+                self.popped = self.popped.wrapping_add(count as u64);
+                self.front = self.front.wrapping_add(count as u64);
+                self.read = self.wrap(self.read + count);
+            })
+        }
+    }
+    /// Overwrite the `count` slots from the read cursor with zeros.
+    /// # Safety
+    /// The slots must be allocated and must not hold items that still need to be dropped.
+    const unsafe fn scrub_front(&mut self, count: usize) {
+        let end = self.capacity - self.read;
+        let lhs = if count < end { count } else { end };
+        // Safety: The caller guarantees the slots are allocated, wrapping to the start.
+        unsafe {
+            self.data.as_ptr().add(self.read).write_bytes(0, lhs);
+            self.data.as_ptr().write_bytes(0, count - lhs);
+        }
+    }
+    /// Overwrite the whole allocation with zeros before it is freed, if scrubbing is enabled.
     /// # Safety
-    /// The buffer must be readable for `count` more elements.
-    /// The `count` must not overflow one less than the remaining `capacity`,
-    /// an equal read and write cursor indicates an empty [`RingBuffer`].
-    pub unsafe fn set_read_cursor(&mut self, count: usize) {
-        self.read = (self.read + count) & self.mask();
+    /// The allocation must not hold items that still need to be dropped.
+    unsafe fn scrub_allocation(&mut self) {
+        if self.scrub {
+            // Safety: The caller guarantees every slot is vacant.
+            unsafe { self.data.as_ptr().write_bytes(0, self.capacity) };
+            // The allocation is freed next, so keep the stores from being optimised away.
+            core::hint::black_box(self.data);
+        }
+    }
+    const_unless_traced! {
+        /// Advance the write cursor by `count` items, committing them to the buffer.
+        ///
+        /// This is a low level method for committing items written through
+        /// [`RingBuffer::spare_capacity_mut`]. Nothing stops the buffer being modified between the
+        /// two calls, so prefer [`RingBuffer::with_spare_capacity`].
+        /// # Safety
+        /// The buffer must be writable for `count` more elements.
+        /// The `count` must not overflow one less than the remaining `capacity`,
+        /// an equal read and write cursor indicates an empty [`RingBuffer`].
+        ///
+        /// Advancing the write cursor further wraps it past the read cursor, after which [`RingBuffer::len`]
+        /// no longer counts the elements that were written and elements may be dropped twice.
+        /// Use [`RingBuffer::repair`] when a producer may have written more than the remaining capacity.
+        pub unsafe fn advance_write_unchecked(&mut self, count: usize) {
+            traced!(self, crate::trace::Operation::SetWriteCursor(count), {
+                self.pushed = self.pushed.wrapping_add(count as u64);
+                self.write = self.wrap(self.write + count);
+            })
+        }
+    }
+    const_unless_traced! {
+        /// Set the read cursor to point to `count` items past the current location.
+        /// # Safety
+        /// See [`RingBuffer::advance_read_unchecked`].
+        /// ```rust
+        /// # #![allow(deprecated)]
+        /// use circulate::RingBuffer;
+        /// let mut old = RingBuffer::<u8>::with_capacity(8);
+        /// let mut new = RingBuffer::<u8>::with_capacity(8);
+        /// for (buffer, rename) in [(&mut old, false), (&mut new, true)] {
+        ///     let spare = buffer.spare_capacity_mut();
+        ///     for (slot, byte) in spare.0.iter_mut().zip(b"abcde") {
+        ///         slot.write(*byte);
+        ///     }
+        ///     // Safety: The first 5 slots were written, then 2 of them are consumed.
+        ///     unsafe {
+        ///         if rename {
+        ///             buffer.advance_write_unchecked(5);
+        ///             buffer.advance_read_unchecked(2);
+        ///         } else {
+        ///             buffer.set_write_cursor(5);
+        ///             buffer.set_read_cursor(2);
+        ///         }
+        ///     }
+        /// }
+        /// assert!(old.iter().eq(b"cde"));
+        /// assert!(old.iter().eq(new.iter()));
+        /// assert_eq!((old.total_pushed(), old.total_popped()), (new.total_pushed(), new.total_popped()));
+        /// ```
+        #[deprecated(note = "renamed to `RingBuffer::advance_read_unchecked`")]
+        pub unsafe fn set_read_cursor(&mut self, count: usize) {
+            // Safety: The caller upholds the same contract.
+            unsafe { self.advance_read_unchecked(count) }
+        }
+    }
+    const_unless_traced! {
+        /// Set the write cursor to point to `count` items past the current location.
+        /// # Safety
+        /// See [`RingBuffer::advance_write_unchecked`].
+        #[deprecated(note = "renamed to `RingBuffer::advance_write_unchecked`")]
+        pub unsafe fn set_write_cursor(&mut self, count: usize) {
+            // Safety: The caller upholds the same contract.
+            unsafe { self.advance_write_unchecked(count) }
+        }
     }
-    /// Set the write cursor to point to `count` items past the current location.
+    const_unless_traced! {
+        /// Move the read cursor back by `count` items, prepending them to the buffer.
+        /// # Safety
+        /// The `count` items preceding the read cursor must be initialized, such as through
+        /// [`RingBuffer::spare_capacity_front_mut`].
+        /// The `count` must not exceed the remaining capacity.
+        pub unsafe fn set_read_cursor_back(&mut self, count: usize) {
+            traced!(self, crate::trace::Operation::SetReadCursorBack(count), {
+                self.pushed = self.pushed.wrapping_add(count as u64);
+                self.front = self.front.wrapping_sub(count as u64);
+                self.read = self.wrap(self.read + self.capacity - count);
+            })
+        }
+    }
+    /// Advance the write cursor past `produced` elements written directly in to the buffer, such
+    /// as by DMA, where the producer may have written over unread elements.
+    /// Only the newest `capacity() - 1` elements are kept and the read cursor is advanced past the
+    /// rest. Returns the number of elements that were lost.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut ring = RingBuffer::<u8>::with_capacity(7);
+    /// for i in 0..3 {
+    ///     ring.push(i).unwrap();
+    /// }
+    /// // Simulate a producer writing 10 more values, wrapping over the unread ones.
+    /// let (ptr, capacity, read, write) = ring.into_raw_parts();
+    /// for i in 0..10 {
+    ///     unsafe { ptr.add((write + i) % capacity).write(3 + i as u8) }
+    /// }
+    /// let mut ring = unsafe { RingBuffer::from_raw_parts(ptr, capacity, read, write) };
+    /// assert_eq!(unsafe { ring.repair(10) }, 6);
+    /// assert_eq!(ring.len(), 7);
+    /// assert!(ring.iter().eq(&[6, 7, 8, 9, 10, 11, 12]));
+    /// ```
     /// # Safety
-    /// The buffer must be writable for `count` more elements.
-    /// The `count` must not overflow one less than the remaining `capacity`,
-    /// an equal read and write cursor indicates an empty [`RingBuffer`].
-    pub unsafe fn set_write_cursor(&mut self, count: usize) {
-        self.write = (self.write + count) & self.mask();
+    /// The `produced` elements following the write cursor, wrapping at `capacity`, must have been
+    /// initialized. If the buffer has no capacity then `produced` must be 0.
+    pub unsafe fn repair(&mut self, produced: usize) -> usize
+    where
+        T: Copy,
+    {
+        traced!(self, crate::trace::Operation::Repair(produced), {
+            let len = self.len().saturating_add(produced);
+            let kept = len.min(self.capacity.saturating_sub(1));
+            self.pushed = self.pushed.wrapping_add(produced as u64);
+            self.popped = self.popped.wrapping_add((len - kept) as u64);
+            self.front = self.front.wrapping_add((len - kept) as u64);
+            let produced = if self.exact && self.capacity != 0 {
+                produced % self.capacity
+            } else {
+                produced & self.mask()
+            };
+            self.write = self.wrap(self.write + produced);
+            self.read = self.wrap(self.write + self.capacity - kept);
+            len - kept
+        })
     }
 
     #[inline(always)]
     const fn mask(&self) -> usize {
         self.capacity.saturating_sub(1)
     }
+    /// Wrap an index less than twice the capacity in to range.
+    #[inline(always)]
+    const fn wrap(&self, index: usize) -> usize {
+        if self.exact {
+            if index >= self.capacity {
+                index - self.capacity
+            } else {
+                index
+            }
+        } else {
+            index & self.mask()
+        }
+    }
     /// Index the read pointer by `index` items.
     /// The returned pointer my not be valid for reads if index does
     /// not refer to an initialized element.
@@ -167,186 +1264,2442 @@ impl<T> RingBuffer<T> {
     const fn read_ptr(&self, index: usize) -> *mut T {
         // Safety:
         // - Capacity is guaranteed to be smaller than `isize::MAX`.
-        // - Masking by capacity ensures the computed offset is in range.
+        // - Wrapping by capacity ensures the computed offset is in range.
         unsafe {
-            self.data.as_ptr().offset(((self.read + index) & self.mask()) as isize)
+            self.data.as_ptr().add(self.wrap(self.read + index))
         }
     }
-    
-    pub fn get(&self, index: usize) -> Option<&T> {
-        if self.empty() {
-            None
-        } else {
-            // Safety: `read` must be pointing at an initialized element.
-            unsafe {
-                Some(&*self.read_ptr(index))
-            }
+
+    /// Get a reference to the item `index` items from the read end, or [`None`] if there are not
+    /// that many items.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(4);
+    /// buffer.extend_from_slice(&[1, 2, 3]).unwrap();
+    /// buffer.pop();
+    /// assert_eq!(buffer.get(0), Some(&2));
+    /// assert_eq!(buffer.get(1), Some(&3));
+    /// // Slots that were popped, or never pushed, are not reachable.
+    /// assert_eq!(buffer.get(2), None);
+    /// assert_eq!(buffer.get(buffer.capacity() - 1), None);
+    /// ```
+    pub const fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
         }
+        // Safety: The first `len` items from `read` are initialized.
+        unsafe { Some(&*self.read_ptr(index)) }
     }
-    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
-        if self.empty() {
-            None
-        } else {
-            // Safety: `read` must be pointing at an initialized element.
-            unsafe {
-                Some(&mut *self.read_ptr(index))
-            }
+    /// Get a mutable reference to the item `index` items from the read end, or [`None`] if there
+    /// are not that many items.
+    pub const fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len() {
+            return None;
+        }
+        // Safety: The first `len` items from `read` are initialized.
+        unsafe { Some(&mut *self.read_ptr(index)) }
+    }
+    /// Get a reference to the item at the read end, which is popped next.
+    pub const fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+    /// Get a reference to the item at the write end, which was pushed last.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::new();
+    /// assert_eq!((buffer.front(), buffer.back()), (None, None));
+    /// buffer.extend_from_slice(b"abc").unwrap();
+    /// assert_eq!((buffer.front(), buffer.back()), (Some(&b'a'), Some(&b'c')));
+    /// ```
+    pub const fn back(&self) -> Option<&T> {
+        match self.len().checked_sub(1) {
+            Some(index) => self.get(index),
+            None => None,
+        }
+    }
+    /// Get a mutable reference to the item at the read end, which is popped next.
+    pub const fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+    /// Get a mutable reference to the item at the write end, which was pushed last.
+    pub const fn back_mut(&mut self) -> Option<&mut T> {
+        match self.len().checked_sub(1) {
+            Some(index) => self.get_mut(index),
+            None => None,
+        }
+    }
+    /// Get the items in `range` of indices from the read end, or [`None`] if the range is out of
+    /// bounds. As the items may wrap around the end of the allocation they are returned in two
+    /// slices, the first holding all of them where they are contiguous.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(4);
+    /// buffer.extend_from_slice(b"xxab").unwrap();
+    /// buffer.pop_slice(&mut [0; 2]);
+    /// buffer.extend_from_slice(b"cd").unwrap();
+    /// assert!(buffer.iter().eq(b"abcd"));
+    ///
+    /// let (lhs, rhs) = buffer.get_range(1..3).unwrap();
+    /// assert_eq!([lhs, rhs].concat(), b"bc");
+    /// assert_eq!(buffer.get_range(..).map(|(lhs, rhs)| lhs.len() + rhs.len()), Some(4));
+    /// assert_eq!(buffer.get_range(2..), Some((&b"cd"[..], &[][..])));
+    /// assert_eq!(buffer.get_range(3..5), None);
+    /// assert_eq!(buffer.get_range(3..2), None);
+    /// ```
+    pub fn get_range<R: core::ops::RangeBounds<usize>>(&self, range: R) -> Option<(&[T], &[T])> {
+        use core::ops::Bound;
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1)?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1)?,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        if start > end || end > self.len() {
+            return None;
         }
+        let (lhs, rhs) = self.as_slices();
+        Some(if end <= lhs.len() {
+            (&lhs[start..end], &[])
+        } else if start >= lhs.len() {
+            (&rhs[start - lhs.len()..end - lhs.len()], &[])
+        } else {
+            (&lhs[start..], &rhs[..end - lhs.len()])
+        })
     }
 
-    /// Returns an iterator over the values in the buffer.
-    pub fn iter(&self) -> Iter<'_, T> {
+    /// Returns an iterator over the values in the buffer, from the read end.
+    ///
+    /// The iterator is double-ended, so reversing it visits the most recently pushed items first.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut log = RingBuffer::with_exact_capacity(4);
+    /// for entry in ["boot", "mount", "login", "crash", "reboot"] {
+    ///     log.push_overwrite(entry);
+    /// }
+    /// assert!(log.iter().rev().take(2).eq(&["reboot", "crash"]));
+    /// assert_eq!(log.iter().len(), 4);
+    /// ```
+    pub const fn iter(&self) -> Iter<'_, T> {
         Iter {
             data: self.data,
-            mask: self.mask(),
+            capacity: self.capacity,
             len: self.len(),
             cursor: self.read,
             _marker: PhantomData
         }
     }
+    /// Returns an iterator that takes up to `count` items from the read end.
+    ///
+    /// Items that are not taken by the time the iterator is dropped are dropped with it, so that
+    /// exactly `count` items, or all of them if there are fewer, are removed from the buffer. If
+    /// the iterator is forgotten instead, the items it did not yield stay in the buffer.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer: RingBuffer<_> = (0..10).collect();
+    /// let mut prefix = buffer.drain(4);
+    /// assert_eq!(prefix.len(), 4);
+    /// assert_eq!(prefix.next(), Some(0));
+    /// drop(prefix);
+    /// assert!(buffer.iter().copied().eq(4..10));
+    ///
+    /// assert!(buffer.drain(100).eq(4..10));
+    /// assert!(buffer.is_empty());
+    /// ```
+    pub fn drain(&mut self, count: usize) -> Drain<'_, T> {
+        let remaining = count.min(self.len());
+        Drain { buffer: self, remaining }
+    }
+    /// Returns an iterator that endlessly repeats the values in the buffer.
+    /// # Panics
+    /// Panics if the buffer is empty.
+    /// ```rust,should_panic
+    /// # use circulate::RingBuffer;
+    /// RingBuffer::<u8>::new().cycle();
+    /// ```
+    /// # Examples
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::new();
+    /// for i in 0..3u8 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// assert!(buffer.cycle().take(9).eq(&[0, 1, 2, 0, 1, 2, 0, 1, 2]));
+    /// ```
+    /// The buffer is borrowed for as long as the iterator is in use.
+    /// ```rust,compile_fail
+    /// # use circulate::RingBuffer;
+    /// # let mut buffer = RingBuffer::new();
+    /// # buffer.push(0u8).unwrap();
+    /// let mut cycle = buffer.cycle();
+    /// buffer.push(1).unwrap();
+    /// cycle.next();
+    /// ```
+    pub fn cycle(&self) -> Cycle<'_, T> {
+        assert!(!self.is_empty(), "cannot cycle an empty RingBuffer");
+        let iter = self.iter();
+        Cycle {
+            original: iter.clone(),
+            iter,
+        }
+    }
     /// Returns an iterator that allows mutating the values in the buffer.
-    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+    pub const fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             data: self.data,
-            mask: self.mask(),
+            capacity: self.capacity,
             len: self.len(),
             cursor: self.read,
             _marker: PhantomData
         }
     }
 
+    /// Ensure there is space to write at least one more item, growing only if there is none.
+    /// Returns false if the buffer could not grow.
+    #[inline]
+    fn ensure_one_free(&mut self) -> bool {
+        // Note: One slot must always remain vacant.
+        self.len() + 1 < self.capacity || self.grow_one()
+    }
+    /// Grow to make space for one more item, kept out of line so that pushing with space to spare
+    /// only checks the length.
+    #[cold]
+    #[inline(never)]
+    fn grow_one(&mut self) -> bool {
+        self.reserve(1).is_ok()
+    }
     /// Push an item to the write end of the [`RingBuffer`].
-    pub fn push(&mut self, value: T) {
-        if self.full() {
-            self.reserve(1)
+    /// If the buffer is full and has reached its maximum capacity the value is handed back.
+    ///
+    /// Items are only moved when the buffer is out of space and grows, so space freed by popping
+    /// is reused first.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(15);
+    /// for i in 0..10 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// for _ in 0..5 {
+    ///     buffer.pop();
+    /// }
+    /// let survivors: Vec<*const i32> = buffer.iter().map(|i| i as *const _).collect();
+    /// for i in 10..20 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// assert_eq!(buffer.capacity(), 16);
+    /// assert!(buffer.iter().map(|i| i as *const _).take(5).eq(survivors));
+    /// assert!(buffer.iter().copied().eq(5..20));
+    /// ```
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if !self.ensure_one_free() {
+            return Err(value);
         }
-        // Safety: Space was reserved for at least one more write and write is always a valid offset.
-        unsafe {
-            self.data.as_ptr().offset(self.write as isize).write(value);
+        traced!(self, crate::trace::Operation::Push, {
+            // Safety: Space was reserved for at least one more write and write is always a valid offset.
+            unsafe {
+                self.data.as_ptr().add(self.write).write(value);
+            }
+            self.pushed = self.pushed.wrapping_add(1);
+            self.write = self.wrap(self.write + 1);
+        });
+        Ok(())
+    }
+    /// Push an item to the read end of the [`RingBuffer`], so that it is popped next.
+    /// If the buffer is full and has reached its maximum capacity the value is handed back.
+    ///
+    /// Together with [`RingBuffer::pop_back`] this makes the buffer a double-ended queue. The item
+    /// counts as pushed and is given the absolute index before that of the item after it, so the
+    /// absolute indices of the items after it do not change.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut deque = RingBuffer::with_capacity(3);
+    /// deque.push(2).unwrap();
+    /// deque.push_front(1).unwrap();
+    /// deque.push_front(0).unwrap();
+    /// deque.push(3).unwrap();
+    /// assert!(deque.iter().copied().eq(0..4));
+    /// assert_eq!(deque.pop_back(), Some(3));
+    /// assert_eq!(deque.pop(), Some(0));
+    /// assert_eq!((deque.front(), deque.back()), (Some(&1), Some(&2)));
+    /// ```
+    pub fn push_front(&mut self, value: T) -> Result<(), T> {
+        if !self.ensure_one_free() {
+            return Err(value);
         }
-        self.write = (self.write + 1) & self.mask();
+        traced!(self, crate::trace::Operation::PushFront, {
+            let read = self.wrap(self.read + self.capacity - 1);
+            // Safety: Space was reserved for at least one more item, so the slot before `read` is vacant.
+            unsafe {
+                self.data.as_ptr().add(read).write(value);
+            }
+            self.pushed = self.pushed.wrapping_add(1);
+            self.front = self.front.wrapping_sub(1);
+            self.read = read;
+        });
+        Ok(())
     }
     /// Take the next item from the read end of the [`RingBuffer`], or return [`None`] if the buffer is empty.
+    ///
+    /// The buffer may shrink afterwards if [`RingBuffer::set_auto_shrink`] is set.
     pub fn pop(&mut self) -> Option<T> {
-        if self.empty() {
+        let value = self.take_front();
+        if value.is_some() {
+            self.shrink_after_pop();
+        }
+        value
+    }
+    /// Take the last item pushed to the write end of the [`RingBuffer`], or return [`None`] if the
+    /// buffer is empty. The item counts as popped, and its absolute index is given to the next item
+    /// pushed.
+    ///
+    /// The buffer may shrink afterwards if [`RingBuffer::set_auto_shrink`] is set.
+    pub fn pop_back(&mut self) -> Option<T> {
+        let value = self.take_back();
+        if value.is_some() {
+            self.shrink_after_pop();
+        }
+        value
+    }
+    /// Take the item at the write end, without shrinking.
+    fn take_back(&mut self) -> Option<T> {
+        if self.is_empty() {
             return None;
         }
-
-        let read = self.read;
-        self.read = (self.read + 1) & self.mask();
-        
-        // Safety: The capacity will not exceed `isize::MAX` so `read` is a valid offset.
-        unsafe {
-            Some(self.data.as_ptr().offset(read as isize).read())
+        Some(traced!(self, crate::trace::Operation::PopBack, {
+            self.write = self.wrap(self.write + self.capacity - 1);
+            self.popped = self.popped.wrapping_add(1);
+            let slot = self.data.as_ptr().wrapping_add(self.write);
+            // Safety: The buffer was not empty, so the slot before the old write cursor held an item.
+            let value = unsafe { slot.read() };
+            if self.scrub {
+                // Safety: The item was moved out, so the slot is vacant.
+                unsafe { slot.write_bytes(0, 1) };
+            }
+            value
+        }))
+    }
+    /// Take the next item from the read end only if `validate` accepts it, or return
+    /// [`None`] if the buffer is empty.
+    ///
+    /// The item is inspected in place. If it is rejected it is left at the front of the buffer, so
+    /// it can be inspected or repaired through [`RingBuffer::get_mut`] before trying again or
+    /// discarding it.
+    ///
+    /// # Panics
+    /// If `validate` panics the item is left in the buffer.
+    /// ```rust
+    /// use circulate::{RingBuffer, ValidationFailed};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Record {
+    ///     len: u16,
+    ///     checksum: u16,
+    /// }
+    /// let valid = |record: &Record| record.len ^ 0xffff == record.checksum;
+    ///
+    /// let mut queue = RingBuffer::with_capacity(4);
+    /// queue.push(Record { len: 5, checksum: 5 ^ 0xffff }).unwrap();
+    /// queue.push(Record { len: 6, checksum: 0 }).unwrap();
+    ///
+    /// assert_eq!(queue.pop_checked(valid), Ok(Some(Record { len: 5, checksum: 5 ^ 0xffff })));
+    /// assert_eq!(queue.pop_checked(valid), Err(ValidationFailed));
+    /// // The rejected record stays at the front, to be repaired.
+    /// assert_eq!(queue.len(), 1);
+    /// queue.get_mut(0).unwrap().checksum = 6 ^ 0xffff;
+    /// assert_eq!(queue.pop_checked(valid), Ok(Some(Record { len: 6, checksum: 6 ^ 0xffff })));
+    /// assert_eq!(queue.pop_checked(valid), Ok(None));
+    /// ```
+    pub fn pop_checked(&mut self, validate: impl FnOnce(&T) -> bool) -> Result<Option<T>, ValidationFailed> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        // Safety: The buffer is not empty, so the front item is initialized.
+        if !validate(unsafe { &*self.read_ptr(0) }) {
+            return Err(ValidationFailed);
         }
+        Ok(self.pop())
     }
+    /// Take items from the read end for as long as `validate` accepts them, through an iterator.
+    ///
+    /// Each item is inspected in place and taken as the iterator reaches it. The iterator ends at
+    /// the first rejected item, which is left at the front of the buffer along with every item after
+    /// it. Dropping the iterator early leaves the items not yet reached.
+    ///
+    /// # Panics
+    /// If `validate` panics the item it was inspecting, and those after it, are left in the buffer.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    ///
+    /// let mut queue = RingBuffer::with_capacity(8);
+    /// queue.extend_from_slice(&[2, 4, 6, 7, 8]).unwrap();
+    ///
+    /// let even: Vec<i32> = queue.pop_while_valid(|value| value % 2 == 0).collect();
+    /// assert_eq!(even, [2, 4, 6]);
+    /// assert!(queue.iter().eq(&[7, 8]));
+    ///
+    /// // Items are only taken as the iterator reaches them.
+    /// assert_eq!(queue.pop_while_valid(|_| true).next(), Some(7));
+    /// assert!(queue.iter().eq(&[8]));
+    /// ```
+    pub fn pop_while_valid<F: FnMut(&T) -> bool>(&mut self, validate: F) -> PopWhileValid<'_, T, F> {
+        PopWhileValid { buffer: self, validate, done: false }
+    }
+    /// Take the next item from the read end, never shrinking the buffer.
+    fn take_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
 
-    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
-        if self.read <= self.write {
-            unsafe {(
-                core::slice::from_raw_parts_mut(self.data.as_ptr().offset(self.read as isize), self.write - self.read),
-                &mut []
-            )}
-        } else {
-            unsafe {(
-                core::slice::from_raw_parts_mut(self.data.as_ptr().offset(self.read as isize), self.capacity - self.read),
-                core::slice::from_raw_parts_mut(self.data.as_ptr(), self.write)
-            )}
+        // Safety:
+        // - The capacity will not exceed `isize::MAX` so `read` is a valid offset.
+        // - The buffer is not empty so one item is readable.
+        unsafe {
+            let value = self.data.as_ptr().add(self.read).read();
+            self.advance_read_unchecked(1);
+            Some(value)
         }
     }
-    /// Get slices over the uninitialized items.
-    pub fn spare_capacity_mut(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
-        let read = self.read.wrapping_sub(1) & self.mask();
-        if read < self.write {
-            // Safety: It is guaranteed that the offsets cannot overflow an isize.
-            unsafe {
-                (
-                    core::slice::from_raw_parts_mut(
-                        self.data.as_ptr().offset(self.write as isize) as *mut MaybeUninit<T>,
-                        self.capacity - self.write
-                    ),
-                    core::slice::from_raw_parts_mut(
-                        self.data.as_ptr() as *mut MaybeUninit<T>,
-                        read
-                    )
-                )
-            }
+    /// Push an item to the write end of the [`RingBuffer`], dropping the oldest item if the buffer
+    /// is full instead of growing it.
+    ///
+    /// This suits buffers of state snapshots where only the newest values matter. A buffer with
+    /// a capacity of 1 holds just the latest value. An unallocated buffer still grows to hold its
+    /// first item, and the value is dropped if its maximum capacity does not allow it.
+    /// ```rust
+    /// use std::{cell::Cell, rc::Rc};
+    /// use circulate::RingBuffer;
+    ///
+    /// struct Snapshot(u32, Rc<Cell<usize>>);
+    /// impl Drop for Snapshot {
+    ///     fn drop(&mut self) { self.1.set(self.1.get() + 1) }
+    /// }
+    ///
+    /// let drops = Rc::new(Cell::new(0));
+    /// let mut mailbox = RingBuffer::with_capacity(1);
+    /// for i in 0..10 {
+    ///     mailbox.push_latest(Snapshot(i, drops.clone()));
+    /// }
+    /// assert_eq!(drops.get(), 9);
+    /// assert_eq!(mailbox.pop().map(|snapshot| snapshot.0), Some(9));
+    /// assert_eq!(drops.get(), 10);
+    ///
+    /// let mut history = RingBuffer::with_capacity(4);
+    /// for i in 0..10 {
+    ///     history.push_latest(Snapshot(i, drops.clone()));
+    /// }
+    /// assert_eq!(drops.get(), 10 + 10 - (history.capacity() - 1));
+    /// assert_eq!(history.pop_latest().map(|snapshot| snapshot.0), Some(9));
+    /// assert_eq!(drops.get(), 20);
+    /// assert!(history.is_empty());
+    /// ```
+    pub fn push_latest(&mut self, value: T) {
+        drop(self.push_or_overwrite(value));
+    }
+    /// Push an item to the write end of the [`RingBuffer`], overwriting the oldest item if the
+    /// buffer is full instead of growing it, like [`RingBuffer::push_latest`].
+    ///
+    /// Returns the item that was overwritten, if any. If the buffer is unallocated and its
+    /// maximum capacity does not allow it to grow, `value` itself is handed back.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(3);
+    /// for i in 0..3 {
+    ///     assert_eq!(buffer.push_or_overwrite(i), None);
+    /// }
+    /// assert_eq!(buffer.push_or_overwrite(3), Some(0));
+    /// assert!(buffer.iter().eq(&[1, 2, 3]));
+    /// ```
+    pub fn push_or_overwrite(&mut self, value: T) -> Option<T> {
+        // Note: Overwriting keeps the length, so should not count towards shrinking.
+        let overwritten = if self.is_full() && self.capacity != 0 {
+            self.take_front()
         } else {
-            // Safety: It is guaranteed that the offsets cannot overflow an isize.
-            unsafe {
-                (
-                    core::slice::from_raw_parts_mut(
-                        self.data.as_ptr().offset(self.write as isize) as *mut MaybeUninit<T>,
-                        read - self.write
-                    ),
-                    &mut []
-                )
-            }
+            None
+        };
+        // Only an unallocated buffer can fail to take the value.
+        self.push(value).err().or(overwritten)
+    }
+    /// Push an item to the write end of the [`RingBuffer`] if there is space for it, handing it
+    /// back if the buffer is full. The buffer never grows, so this never allocates.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_exact_capacity(2);
+    /// assert_eq!(buffer.try_push(1), Ok(()));
+    /// assert_eq!(buffer.try_push(2), Ok(()));
+    /// assert_eq!(buffer.try_push(3), Err(3));
+    /// assert_eq!(buffer.capacity(), 3);
+    ///
+    /// // An unallocated buffer has no space.
+    /// assert_eq!(RingBuffer::new().try_push(0), Err(0));
+    /// ```
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(value);
         }
+        // Note: Pushing to a buffer that is not full never grows it.
+        self.push(value)
     }
-
-    pub const fn layout(&self) -> Option<Layout> {
-        if size_of::<T>() == 0 || self.capacity == 0 {
-            None
+    /// Push an item to the write end of the [`RingBuffer`], overwriting the oldest item if the
+    /// buffer is full. Unlike [`RingBuffer::push_or_overwrite`] an unallocated buffer does not
+    /// grow either, so this never allocates, which suits a log of the last samples in a buffer
+    /// allocated once up front.
+    ///
+    /// Returns the item that was overwritten, if any. If the buffer has no space for any items
+    /// `value` itself is handed back.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut samples = RingBuffer::with_exact_capacity(3);
+    /// for i in 0..3 {
+    ///     assert_eq!(samples.push_overwrite(i), None);
+    /// }
+    /// assert_eq!(samples.push_overwrite(3), Some(0));
+    /// assert_eq!(samples.push_overwrite(4), Some(1));
+    /// assert!(samples.iter().eq(&[2, 3, 4]));
+    ///
+    /// assert_eq!(RingBuffer::new().push_overwrite(5), Some(5));
+    /// ```
+    pub fn push_overwrite(&mut self, value: T) -> Option<T> {
+        // Note: Overwriting keeps the length, so should not count towards shrinking.
+        let overwritten = if self.is_full() { self.take_front() } else { None };
+        // Only a buffer without space for any items can fail to take the value.
+        self.try_push(value).err().or(overwritten)
+    }
+    /// Push an item to the write end of the [`RingBuffer`] if there is space for it, without
+    /// growing the buffer. Returns if the item was pushed; otherwise it is dropped.
+    /// ```rust
+    /// use std::{cell::Cell, rc::Rc};
+    /// use circulate::RingBuffer;
+    ///
+    /// struct Sample(u32, Rc<Cell<usize>>);
+    /// impl Drop for Sample {
+    ///     fn drop(&mut self) { self.1.set(self.1.get() + 1) }
+    /// }
+    ///
+    /// let drops = Rc::new(Cell::new(0));
+    /// let mut buffer = RingBuffer::with_capacity(3);
+    /// for i in 0..3 {
+    ///     assert!(buffer.push_or_discard(Sample(i, drops.clone())));
+    /// }
+    /// assert!(!buffer.push_or_discard(Sample(3, drops.clone())));
+    /// assert_eq!(drops.get(), 1);
+    /// assert_eq!(buffer.capacity(), 4);
+    /// assert!(buffer.iter().map(|sample| sample.0).eq([0, 1, 2]));
+    ///
+    /// // An unallocated buffer has no space.
+    /// assert!(!RingBuffer::new().push_or_discard(0));
+    /// ```
+    pub fn push_or_discard(&mut self, value: T) -> bool {
+        if self.is_full() {
+            drop(value);
+            false
         } else {
-            assert!(size_of::<T>() % align_of::<T>() == 0);
-            // Safety:
-            // - Rust types are asserted to have a matching size and stride.
-            // - `align_of` will always return a power of 2.
-            // - The capacity in bytes will never overflow an isize.
-            unsafe {
-                let align = align_of::<T>();
-                let size = size_of::<T>() * self.capacity;
-                let layout = Layout::from_size_align_unchecked(size, align);
-                Some(layout)
-            }
+            // Note: Pushing to a buffer that is not full never grows it, so cannot fail.
+            let _ = self.push(value);
+            true
         }
     }
-    /// Get a layout valid for the ring buffer with a size of at least `capacity` items.
-    /// It provides the following guarantees:
-    /// - The layout will allow indexing by `data.offset()`.
-    /// - The layout size in items will be a power of two.
-    /// To guard against misuse, [`None`] is returned if the layout would have a 0 size.
-    fn layout_for(capacity: usize) -> Option<Layout> {
-        let capacity = capacity.next_power_of_two();
-
-        const fn max_size_for_align(align: usize) -> usize {
-            isize::MAX as usize - (align - 1)
+    /// Take the newest item from the [`RingBuffer`], dropping all older items, or return [`None`]
+    /// if the buffer is empty.
+    pub fn pop_latest(&mut self) -> Option<T> {
+        self.drop_front(self.len().saturating_sub(1));
+        self.pop()
+    }
+    /// Take the next `N` items from the read end of the [`RingBuffer`] as an array.
+    /// Fails without modifying the buffer if it holds fewer than `N` items.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(4);
+    /// for i in 0..3 {
+    ///     buffer.push(i.to_string()).unwrap();
+    /// }
+    /// let error = buffer.try_pop_n::<5>().unwrap_err();
+    /// assert_eq!((error.available(), error.requested()), (3, 5));
+    /// assert_eq!(buffer.len(), 3);
+    /// assert_eq!(buffer.try_pop_n::<0>(), Ok([]));
+    /// assert_eq!(buffer.try_pop_n(), Ok(["0".to_owned(), "1".to_owned(), "2".to_owned()]));
+    ///
+    /// let error = buffer.try_pop_n::<1>().unwrap_err();
+    /// assert_eq!(error.available(), 0);
+    /// assert_eq!(buffer.try_pop_n::<0>(), Ok([]));
+    /// ```
+    pub fn try_pop_n<const N: usize>(&mut self) -> Result<[T; N], InsufficientElements> {
+        if self.len() < N {
+            return Err(InsufficientElements { available: self.len(), requested: N });
         }
-        
-        if size_of::<T>() == 0 || capacity == 0 {
-            return None;
+        let mut items = [const { MaybeUninit::<T>::uninit() }; N];
+        let (lhs, rhs) = self.as_slices();
+        let lhs_len = lhs.len().min(N);
+        // Safety:
+        // - At least `N` items are initialized, which are moved in to the array.
+        // - The read cursor is advanced past the moved items so they are not dropped twice.
+        // - `MaybeUninit<T>` has the same layout as `T`, so the initialized array is an `[T; N]`.
+        unsafe {
+            let ptr = items.as_mut_ptr().cast::<T>();
+            ptr.copy_from_nonoverlapping(lhs.as_ptr(), lhs_len);
+            ptr.add(lhs_len).copy_from_nonoverlapping(rhs.as_ptr(), N - lhs_len);
+            self.advance_read_unchecked(N);
+            Ok(items.as_ptr().cast::<[T; N]>().read())
         }
-        if capacity > max_size_for_align(align_of::<T>()) / size_of::<T>() {
-            capacity_overflow()
+    }
+    /// Pair up the items of this buffer with those of `other`, stopping at the end of the shorter
+    /// buffer. Unpaired items are dropped.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut numbers = RingBuffer::new();
+    /// let mut names = RingBuffer::new();
+    /// for (number, name) in [(1, "one"), (2, "two"), (3, "three")] {
+    ///     numbers.push(number).unwrap();
+    ///     names.push(name).unwrap();
+    /// }
+    /// names.pop();
+    /// let pairs = numbers.zip(names);
+    /// assert!(pairs.iter().eq(&[(1, "two"), (2, "three")]));
+    ///
+    /// let (numbers, names) = pairs.unzip();
+    /// assert!(numbers.iter().eq(&[1, 2]));
+    /// assert!(names.iter().eq(&["two", "three"]));
+    /// assert!(numbers.zip(names).iter().eq(&[(1, "two"), (2, "three")]));
+    ///
+    /// let mut some = RingBuffer::new();
+    /// some.push(1).unwrap();
+    /// assert!(RingBuffer::<u8>::new().zip(some).is_empty());
+    /// ```
+    pub fn zip<U>(self, other: RingBuffer<U>) -> RingBuffer<(T, U)> {
+        let mut zipped = RingBuffer::with_capacity(self.len().min(other.len()));
+        for pair in self.into_iter().zip(other) {
+            // The buffer is unbounded so pushing cannot fail.
+            let _ = zipped.push(pair);
         }
-
+        zipped
+    }
+    /// Remove the item at `index` and return it, replacing it with the item at the read end.
+    /// This does not preserve ordering but is O(1).
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::new();
+    /// for i in 0..4u8 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// assert_eq!(buffer.swap_remove_front(2), 2);
+    /// assert!(buffer.iter().eq(&[1, 0, 3]));
+    /// assert_eq!(buffer.swap_remove_front(0), 1);
+    /// assert_eq!(buffer.swap_remove_front(1), 3);
+    /// assert!(buffer.iter().eq(&[0]));
+    ///
+    /// // Wrapped around the end of the allocation.
+    /// let mut buffer = RingBuffer::with_capacity(4);
+    /// for i in 0..3u8 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// buffer.pop();
+    /// buffer.pop();
+    /// buffer.push(3).unwrap();
+    /// buffer.push(4).unwrap();
+    /// assert_eq!(buffer.swap_remove_front(1), 3);
+    /// assert!(buffer.iter().eq(&[2, 4]));
+    /// ```
+    pub fn swap_remove_front(&mut self, index: usize) -> T {
+        let len = self.len();
+        if index >= len {
+            panic!("swap_remove_front index (is {index}) should be < len (is {len})");
+        }
+        // Safety: Both the front and `index` point to initialized elements.
+        unsafe {
+            core::ptr::swap(self.read_ptr(0), self.read_ptr(index));
+        }
+        // Safety: The buffer is not empty.
+        unsafe { self.pop().unwrap_unchecked() }
+    }
+    /// Remove and return the first item matching `predicate`, shifting the items after it
+    /// forward to close the gap. Returns [`None`] and leaves the buffer unchanged if no item matches.
+    ///
+    /// If `predicate` panics, the buffer is left unchanged.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// for i in 0..6u8 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// assert_eq!(buffer.find_and_remove(|i| *i == 0), Some(0));
+    /// assert_eq!(buffer.find_and_remove(|i| *i == 5), Some(5));
+    /// assert!(buffer.iter().eq(&[1, 2, 3, 4]));
+    ///
+    /// // Wrapped around the end of the allocation.
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// for i in 0..7u8 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// for _ in 0..4 {
+    ///     buffer.pop();
+    /// }
+    /// for i in 7..10u8 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// assert_eq!(buffer.find_and_remove(|i| *i == 5), Some(5));
+    /// assert!(buffer.iter().eq(&[4, 6, 7, 8, 9]));
+    /// assert_eq!(buffer.find_and_remove(|i| *i > 7), Some(8));
+    /// assert!(buffer.iter().eq(&[4, 6, 7, 9]));
+    /// buffer.push(10).unwrap();
+    /// assert!(buffer.iter().eq(&[4, 6, 7, 9, 10]));
+    ///
+    /// assert_eq!(buffer.find_and_remove(|i| *i == 3), None);
+    /// assert!(buffer.iter().eq(&[4, 6, 7, 9, 10]));
+    /// ```
+    pub fn find_and_remove<F: FnMut(&T) -> bool>(&mut self, predicate: F) -> Option<T> {
+        let index = self.iter().position(predicate)?;
+        Some(traced!(self, crate::trace::Operation::Remove, {
+            let data = self.data.as_ptr();
+            let mut hole = self.wrap(self.read + index);
+            let mut remaining = self.len() - index - 1;
+            // Safety: `index` is in bounds so `hole` points to an initialized element.
+            let value = unsafe { data.add(hole).read() };
+            // Move the hole to the write end, one physically contiguous run at a time.
+            while remaining != 0 {
+                let next = self.wrap(hole + 1);
+                // The hole only precedes `next` in memory if it is not at the end of the allocation.
+                let run = if next == 0 { 1 } else { remaining.min(self.capacity - next) };
+                // Safety:
+                // - The `run` elements from `next` are initialized and lie within the allocation.
+                // - The hole is the slot before `next`, so the elements shift in to it and leave a new
+                //   hole at the end of the run.
+                unsafe { core::ptr::copy(data.add(next), data.add(hole), run) };
+                hole = self.wrap(next + run - 1);
+                remaining -= run;
+            }
+            // Note: The removed item counts as popped.
+            self.popped = self.popped.wrapping_add(1);
+            self.front = self.front.wrapping_add(1);
+            self.write = hole;
+            value
+        }))
+    }
+    /// Drop items from the read end while `predicate` holds, stopping at the first item it does
+    /// not hold for. Returns the number of items dropped.
+    ///
+    /// Only the dropped items and the one after them are visited, so evicting expired entries
+    /// from a buffer ordered by time is O(evicted). The items are dropped in bulk afterwards, and
+    /// the buffer may then shrink if [`RingBuffer::set_auto_shrink`] is set.
+    ///
+    /// If `predicate` panics, the items it held for so far are still dropped.
+    /// ```rust
+    /// use std::{cell::Cell, rc::Rc};
+    /// use circulate::RingBuffer;
+    ///
+    /// /// A sample taken at a time in seconds.
+    /// #[derive(Debug)]
+    /// struct Sample(u64, Rc<Cell<usize>>);
+    /// impl Drop for Sample {
+    ///     fn drop(&mut self) { self.1.set(self.1.get() + 1) }
+    /// }
+    ///
+    /// let drops = Rc::new(Cell::new(0));
+    /// let mut samples = RingBuffer::with_capacity(7);
+    /// for time in 0..5 {
+    ///     samples.push(Sample(time, drops.clone())).unwrap();
+    /// }
+    /// for time in 0..3 {
+    ///     assert_eq!(samples.pop().unwrap().0, time);
+    /// }
+    /// for time in 5..10 {
+    ///     samples.push(Sample(time, drops.clone())).unwrap();
+    /// }
+    /// // The samples wrap around the end of the allocation.
+    /// assert!(!samples.as_mut_slices().1.is_empty());
+    ///
+    /// // Keep the samples from the last 3 seconds, across the wrap.
+    /// assert_eq!(samples.evict_while(|sample| sample.0 + 3 <= 11), 6);
+    /// assert_eq!(drops.get(), 3 + 6);
+    /// assert!(samples.iter().map(|sample| sample.0).eq([9]));
+    ///
+    /// // Nothing is evicted if the oldest item does not match, even if later items would.
+    /// samples.push(Sample(0, drops.clone())).unwrap();
+    /// assert_eq!(samples.evict_while(|sample| sample.0 == 0), 0);
+    /// assert_eq!(samples.evict_while(|sample| sample.0 > 0), 1);
+    /// assert_eq!(samples.pop().unwrap().0, 0);
+    /// assert_eq!(samples.evict_while(|_| true), 0);
+    /// assert_eq!(drops.get(), 11);
+    /// ```
+    pub fn evict_while<F: FnMut(&T) -> bool>(&mut self, mut predicate: F) -> usize {
+        // Drop the items the predicate held for even if it panics on a later one, so that
+        // wrappers accounting for each evicted item as it is accepted stay consistent.
+        let mut accepted = DropGuard::new((&mut *self, 0), |(buffer, count)| {
+            buffer.drop_front(*count);
+        });
+        while accepted.1 < accepted.0.len() {
+            // Safety: The index is in bounds.
+            let item = unsafe { &*accepted.0.read_ptr(accepted.1) };
+            if !predicate(item) {
+                break;
+            }
+            accepted.1 += 1;
+        }
+        let dropped = accepted.1;
+        drop(accepted);
+        if dropped != 0 {
+            self.shrink_after_pop();
+        }
+        dropped
+    }
+    /// Sort the items with a comparator, preserving the order of equal items.
+    ///
+    /// Each physically contiguous half of the buffer is sorted in place and the halves are then
+    /// merged, so a wrapped buffer is never rotated.
+    ///
+    /// If `compare` panics, the buffer holds the same items in an unspecified order.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(16);
+    /// for i in 0..10u32 {
+    ///     buffer.push((0, i)).unwrap();
+    /// }
+    /// for _ in 0..8 {
+    ///     buffer.pop();
+    /// }
+    /// for i in 0..12u32 {
+    ///     buffer.push(((i * 7) % 5, i)).unwrap();
+    /// }
+    /// let mut expected: Vec<_> = buffer.iter().copied().collect();
+    /// expected.sort_by_key(|(key, _)| *key);
+    ///
+    /// buffer.sort_by(|a, b| a.0.cmp(&b.0));
+    /// assert!(buffer.iter().eq(&expected));
+    /// assert!(buffer.is_sorted_by(|a, b| a.0 <= b.0));
+    /// ```
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        let (lhs, rhs) = self.as_mut_slices();
+        lhs.sort_by(&mut compare);
+        rhs.sort_by(&mut compare);
+        self.merge_halves(compare);
+    }
+    /// Sort the items with a comparator, without preserving the order of equal items.
+    ///
+    /// Each physically contiguous half of the buffer is sorted in place and the halves are then
+    /// merged, so a wrapped buffer is never rotated.
+    ///
+    /// If `compare` panics, the buffer holds the same items in an unspecified order.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(16);
+    /// for i in 0..13u32 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// for _ in 0..11 {
+    ///     buffer.pop();
+    /// }
+    /// for i in 0..12u32 {
+    ///     buffer.push((i * 7919) % 13).unwrap();
+    /// }
+    /// let mut expected: Vec<_> = buffer.iter().copied().collect();
+    /// expected.sort_unstable_by(|a, b| b.cmp(a));
+    ///
+    /// assert!(!buffer.is_sorted_by(|a, b| a >= b));
+    /// buffer.sort_unstable_by(|a, b| b.cmp(a));
+    /// assert!(buffer.iter().eq(&expected));
+    /// assert!(buffer.is_sorted_by(|a, b| a >= b));
+    /// ```
+    pub fn sort_unstable_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        let (lhs, rhs) = self.as_mut_slices();
+        lhs.sort_unstable_by(&mut compare);
+        rhs.sort_unstable_by(&mut compare);
+        self.merge_halves(compare);
+    }
+    /// Merge the sorted halves of the buffer, taking from the left half first for equal items.
+    fn merge_halves<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        let len = self.len();
+        let (lhs, rhs) = self.as_mut_slices();
+        let (lhs, rhs) = (&*lhs, &*rhs);
+        match (lhs.last(), rhs.first()) {
+            (Some(last), Some(first)) if compare(last, first) == Ordering::Greater => (),
+            // Already in order.
+            _ => return,
+        }
+        // Decide the merged order before moving anything, so a panicking `compare` leaves the
+        // buffer intact.
+        let mut order = Vec::with_capacity(len);
+        let (mut l, mut r) = (0, 0);
+        while l < lhs.len() && r < rhs.len() {
+            if compare(&rhs[r], &lhs[l]) == Ordering::Less {
+                order.push(lhs.len() + r);
+                r += 1;
+            } else {
+                order.push(l);
+                l += 1;
+            }
+        }
+        order.extend(l..lhs.len());
+        order.extend(lhs.len() + r..len);
+
+        let mut scratch = Vec::with_capacity(len);
+        // Safety:
+        // - `order` is a permutation of `0..len`, so every item is moved out exactly once and
+        //   every slot is written exactly once.
+        // - Nothing can panic while items are moved.
+        unsafe {
+            for index in order {
+                scratch.push(self.read_ptr(index).read());
+            }
+            for (index, value) in scratch.into_iter().enumerate() {
+                self.read_ptr(index).write(value);
+            }
+        }
+    }
+    /// Returns if the items are sorted in ascending order.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(4);
+    /// assert!(buffer.is_sorted());
+    /// buffer.push(2).unwrap();
+    /// assert!(buffer.is_sorted());
+    /// buffer.push(2).unwrap();
+    /// assert!(buffer.is_sorted());
+    /// buffer.push(3).unwrap();
+    /// assert!(buffer.is_sorted());
+    /// assert!(!buffer.is_sorted_by(|a, b| a >= b));
+    ///
+    /// // A pair straddling the end of the allocation.
+    /// let straddling = |a, b| {
+    ///     let mut buffer = RingBuffer::with_capacity(4);
+    ///     for _ in 0..3 {
+    ///         buffer.push(0).unwrap();
+    ///         buffer.pop();
+    ///     }
+    ///     buffer.push(a).unwrap();
+    ///     buffer.push(b).unwrap();
+    ///     buffer
+    /// };
+    /// assert!(straddling(1, 2).is_sorted());
+    /// assert!(!straddling(2, 1).is_sorted());
+    /// assert!(straddling(2, 1).is_sorted_by(|a, b| a >= b));
+    /// ```
+    pub fn is_sorted(&self) -> bool
+    where
+        T: PartialOrd,
+    {
+        self.iter().is_sorted()
+    }
+    /// Returns if the items are sorted, where `compare` returns if its arguments are in order.
+    pub fn is_sorted_by<F: FnMut(&T, &T) -> bool>(&self, mut compare: F) -> bool {
+        self.iter().is_sorted_by(|a, b| compare(a, b))
+    }
+    /// Returns if the items are equal to `other`, comparing each contiguous half of the buffer as
+    /// a slice.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(7);
+    /// // Keep the last 7 bytes, wrapping around the end of the allocation.
+    /// for byte in b"GET / HTTP/1.1" {
+    ///     buffer.push_latest(*byte);
+    /// }
+    /// assert!(buffer.eq_slice(b"TTP/1.1"));
+    /// assert!(!buffer.eq_slice(b"TTP/1.0"));
+    /// assert!(!buffer.eq_slice(b"TTP/1."));
+    /// assert!(buffer.starts_with(b"TTP"));
+    /// assert!(buffer.ends_with(b"/1.1"));
+    /// assert!(buffer.starts_with(b"") && buffer.ends_with(b""));
+    /// assert!(!buffer.starts_with(b"HTTP/1.1"));
+    /// ```
+    pub fn eq_slice(&self, other: &[T]) -> bool
+    where
+        T: PartialEq,
+    {
+        other.len() == self.len() && self.range_eq(0, other)
+    }
+    /// Returns if the items at the read end are equal to `prefix`.
+    pub fn starts_with(&self, prefix: &[T]) -> bool
+    where
+        T: PartialEq,
+    {
+        prefix.len() <= self.len() && self.range_eq(0, prefix)
+    }
+    /// Returns if the items at the write end are equal to `suffix`.
+    pub fn ends_with(&self, suffix: &[T]) -> bool
+    where
+        T: PartialEq,
+    {
+        let len = self.len();
+        suffix.len() <= len && self.range_eq(len - suffix.len(), suffix)
+    }
+    /// Compare the items from `start` against `other`, which must not extend past the write end.
+    fn range_eq(&self, start: usize, other: &[T]) -> bool
+    where
+        T: PartialEq,
+    {
+        let (lhs, rhs) = self.as_slices();
+        if start >= lhs.len() {
+            return rhs[start - lhs.len()..][..other.len()] == *other;
+        }
+        let lhs = &lhs[start..];
+        let split = lhs.len().min(other.len());
+        lhs[..split] == other[..split] && rhs[..other.len() - split] == other[split..]
+    }
+
+    /// Rearrange the items in place so they are contiguous in memory, returning them as one slice.
+    /// Does nothing if the items are already contiguous.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// for i in 0..7 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// for _ in 0..5 {
+    ///     buffer.pop();
+    /// }
+    /// for i in 7..10 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// assert_eq!(buffer.make_contiguous(), &[5, 6, 7, 8, 9]);
+    /// assert_eq!(buffer.make_contiguous(), &[5, 6, 7, 8, 9]);
+    ///
+    /// // Too little free space to move the items around the end of the allocation.
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// for i in 0..7 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// buffer.pop();
+    /// buffer.pop();
+    /// buffer.push(7).unwrap();
+    /// buffer.push(8).unwrap();
+    /// assert_eq!(buffer.make_contiguous(), &[2, 3, 4, 5, 6, 7, 8]);
+    /// assert!(buffer.iter().eq(&[2, 3, 4, 5, 6, 7, 8]));
+    /// ```
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        let len = self.len();
+        if self.read > self.write {
+            traced!(self, crate::trace::Operation::MakeContiguous, {
+                let data = self.data.as_ptr();
+                let head = self.capacity - self.read;
+                let tail = self.write;
+                // Safety:
+                // - The `head` items from the read cursor and the `tail` items from the start of the
+                //   allocation are initialized, and every copy lies within the allocation.
+                // - Items are only moved, never duplicated, and nothing can panic while they are moved.
+                unsafe {
+                    if self.capacity - len >= head {
+                        // Shift the tail up past where the head will go, which stays below the read cursor.
+                        core::ptr::copy(data, data.add(head), tail);
+                        core::ptr::copy_nonoverlapping(data.add(self.read), data, head);
+                    } else {
+                        // Close the gap by moving the head down to the tail and then rotate it to the front.
+                        core::ptr::copy(data.add(self.read), data.add(tail), head);
+                        core::slice::from_raw_parts_mut(data, len).rotate_left(tail);
+                    }
+                }
+                self.read = 0;
+                self.write = len;
+            })
+        }
+        // Safety: The `len` items from the read cursor are initialized and contiguous.
+        unsafe { core::slice::from_raw_parts_mut(self.data.as_ptr().add(self.read), len) }
+    }
+    /// Rearrange the items in place so they are contiguous and start at the beginning of the
+    /// allocation, giving every buffer with the same items the same physical layout.
+    ///
+    /// This is only needed to inspect the allocation directly, such as through
+    /// [`RingBuffer::into_raw_parts`]. Iteration and every other view of the items is in logical
+    /// order from the read end, whatever their layout.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    ///
+    /// // The same items at every offset in the allocation, some grown while wrapped and some with
+    /// // a capacity that is not a power of two.
+    /// let content: Vec<u8> = (0..11).map(|i| i * 7 + b'a').collect();
+    /// let layouts = (0..44).map(|offset| {
+    ///     let (mut buffer, offset) = match offset {
+    ///         0..32 => (RingBuffer::with_capacity(16), offset),
+    ///         _ => (RingBuffer::with_exact_capacity(11), offset - 32),
+    ///     };
+    ///     for i in 0..offset % 16 {
+    ///         buffer.push(i as u8).unwrap();
+    ///         buffer.pop();
+    ///     }
+    ///     if offset < 16 {
+    ///         buffer.extend_from_slice(&content).unwrap();
+    ///     } else {
+    ///         buffer.extend_from_slice(&content[..8]).unwrap();
+    ///         buffer.reserve(64).unwrap();
+    ///         buffer.extend_from_slice(&content[8..]).unwrap();
+    ///     }
+    ///     buffer
+    /// });
+    ///
+    /// let windows = |buffer: &RingBuffer<u8>| {
+    ///     let mut windows = Vec::new();
+    ///     buffer.for_each_window(3, |lhs, rhs| windows.push([lhs, rhs].concat()));
+    ///     windows
+    /// };
+    /// let expected_windows: Vec<_> = content.windows(3).map(<[u8]>::to_vec).collect();
+    /// for mut buffer in layouts {
+    ///     assert_eq!(buffer.len(), content.len());
+    ///     assert!(buffer.iter().eq(&content));
+    ///     assert_eq!(buffer.iter().fold(Vec::new(), |mut v, x| { v.push(*x); v }), content);
+    ///     let (lhs, rhs) = buffer.iter().as_slices();
+    ///     assert_eq!([lhs, rhs].concat(), content);
+    ///     for (i, item) in content.iter().enumerate() {
+    ///         assert_eq!(buffer.get(i), Some(item));
+    ///         assert_eq!(buffer.iter().nth(i), Some(item));
+    ///     }
+    ///     assert_eq!(buffer.to_vec(), content);
+    ///     assert_eq!(&*buffer.as_contiguous(), &content);
+    ///     assert_eq!(buffer.display_lossy().to_string(), String::from_utf8_lossy(&content));
+    ///     assert_eq!(windows(&buffer), expected_windows);
+    ///     assert!(buffer.eq_slice(&content));
+    ///     assert!(buffer.starts_with(&content[..5]) && buffer.ends_with(&content[5..]));
+    ///     assert!(buffer.is_sorted());
+    ///
+    ///     buffer.normalize();
+    ///     assert_eq!(buffer.iter().as_slices(), (&content[..], &[][..]));
+    ///     let (ptr, capacity, read, write) = buffer.into_raw_parts();
+    ///     assert_eq!((read, write), (0, content.len()));
+    ///     assert_eq!(unsafe { core::slice::from_raw_parts(ptr, write) }, content);
+    ///     drop(unsafe { RingBuffer::from_raw_parts(ptr, capacity, read, write) });
+    /// }
+    /// ```
+    pub fn normalize(&mut self) {
+        let len = self.make_contiguous().len();
+        traced!(self, crate::trace::Operation::Normalize, {
+            if self.read != 0 {
+                // Safety: The `len` items from the read cursor are initialized and contiguous, so can
+                // be moved to the start of the allocation.
+                unsafe { core::ptr::copy(self.data.as_ptr().add(self.read), self.data.as_ptr(), len) };
+            }
+            self.read = 0;
+            self.write = len;
+        })
+    }
+    /// Convert the buffer in to a boxed slice of its items, in order.
+    ///
+    /// The items are moved to the start of the allocation, which is then shrunk to fit them. The
+    /// allocation is reused, so the items are only copied if the allocator moves it when shrinking.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// for i in 0..7 {
+    ///     buffer.push(i.to_string()).unwrap();
+    /// }
+    /// for _ in 0..4 {
+    ///     buffer.pop();
+    /// }
+    /// for i in 7..10 {
+    ///     buffer.push(i.to_string()).unwrap();
+    /// }
+    /// assert_eq!(&*buffer.into_boxed_slice(), ["4", "5", "6", "7", "8", "9"]);
+    /// assert!(RingBuffer::<String>::new().into_boxed_slice().is_empty());
+    ///
+    /// let mut buffer = RingBuffer::with_exact_capacity(5);
+    /// for i in 0..5u64 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// let data = buffer.as_mut_slices().0.as_ptr();
+    /// let slice = buffer.into_boxed_slice();
+    /// assert_eq!(*slice, [0, 1, 2, 3, 4]);
+    /// // Shrinking away the vacant slot happens in place for this allocator.
+    /// assert_eq!(slice.as_ptr(), data);
+    /// ```
+    pub fn into_boxed_slice(mut self) -> Box<[T]> {
+        let len = self.len();
+        let Some(layout) = self.layout().filter(|_| len != 0 && self.align == align_of::<T>() && !self.scrub) else {
+            // Nothing needs to be kept, zero sized items need no allocation, a box cannot own an
+            // over-aligned allocation, and reallocating could leave unscrubbed copies behind.
+            return self.collect_boxed_slice();
+        };
+        self.make_contiguous();
+        let data = self.data.as_ptr();
+        // Safety: The `len` items from the read cursor are initialized and contiguous.
+        unsafe { core::ptr::copy(data.add(self.read), data, len) };
+        let this = core::mem::ManuallyDrop::new(self);
+        let size = len * size_of::<T>();
+        // Safety:
+        // - The allocation was made with `layout` and `size` is non-zero and smaller.
+        // - Ownership of the allocation and items is taken from the forgotten buffer.
+        unsafe {
+            let ptr = realloc(this.data.as_ptr().cast(), layout, size);
+            if ptr.is_null() {
+                alloc::alloc::handle_alloc_error(Layout::from_size_align_unchecked(size, layout.align()));
+            }
+            Box::from_raw(core::ptr::slice_from_raw_parts_mut(ptr.cast::<T>(), len))
+        }
+    }
+    fn collect_boxed_slice(self) -> Box<[T]> {
+        let mut items = Vec::with_capacity(self.len());
+        items.extend(self);
+        items.into_boxed_slice()
+    }
+    /// Reorder the items so that the item at `index` is the one that would be there if the buffer
+    /// were sorted, with every item before it less than or equal and every item after it greater
+    /// than or equal. Returns the item at `index`.
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(16);
+    /// for i in 0..10 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// for _ in 0..10 {
+    ///     buffer.pop();
+    /// }
+    /// for i in 0..11u32 {
+    ///     buffer.push((i * 7) % 11).unwrap();
+    /// }
+    /// assert_eq!(*buffer.select_nth_unstable(5), 5);
+    /// let items: Vec<_> = buffer.iter().copied().collect();
+    /// assert!(items[..5].iter().all(|i| *i <= 5) && items[6..].iter().all(|i| *i >= 5));
+    /// assert_eq!(*buffer.select_nth_unstable(0), 0);
+    /// assert_eq!(*buffer.select_nth_unstable(10), 10);
+    /// ```
+    pub fn select_nth_unstable(&mut self, index: usize) -> &mut T
+    where
+        T: Ord,
+    {
+        let len = self.len();
+        if index >= len {
+            panic!("select_nth_unstable index (is {index}) should be < len (is {len})");
+        }
+        self.make_contiguous().select_nth_unstable(index).1
+    }
+    /// Reserve space for `count` more items, or if that fails for as many as the buffer can grow
+    /// to hold. Returns the number of items there is space for, up to `count`.
+    pub(crate) fn reserve_up_to(&mut self, count: usize) -> usize {
+        if self.reserve(count).is_ok() {
+            return count;
+        }
+        // Grow as far as the maximum capacity allows.
+        if self.max_capacity.is_some() {
+            let room = self.reachable_capacity().saturating_sub(1) - self.len();
+            let _ = self.reserve(room);
+        }
+        count.min(self.capacity.saturating_sub(1) - self.len())
+    }
+    /// Move up to `max` items from the read end of `src` to the write end of this buffer,
+    /// growing it once if needed. Returns the number of items moved.
+    ///
+    /// The items are moved with at most four copies, without cloning or dropping any. If this
+    /// buffer reaches its maximum capacity, or fails to allocate, only as many items as fit are
+    /// moved.
+    /// ```rust
+    /// use std::rc::Rc;
+    /// use circulate::RingBuffer;
+    /// let item = Rc::new(());
+    /// let mut src = RingBuffer::with_capacity(8);
+    /// let mut dst = RingBuffer::with_capacity(8);
+    /// // Wrap both buffers around the end of their allocations.
+    /// for _ in 0..5 {
+    ///     src.push((0, item.clone())).unwrap();
+    ///     dst.push((0, item.clone())).unwrap();
+    /// }
+    /// for i in 0..5 {
+    ///     src.pop();
+    ///     dst.pop();
+    ///     src.push((i, item.clone())).unwrap();
+    /// }
+    /// dst.push((-1, item.clone())).unwrap();
+    /// assert_eq!(Rc::strong_count(&item), 7);
+    ///
+    /// assert_eq!(dst.move_from(&mut src, 4), 4);
+    /// assert!(dst.iter().map(|(i, _)| *i).eq([-1, 0, 1, 2, 3]));
+    /// assert!(src.iter().map(|(i, _)| *i).eq([4]));
+    /// assert_eq!(Rc::strong_count(&item), 7);
+    ///
+    /// // Only as many items as fit are moved.
+    /// let mut bounded = RingBuffer::with_max_capacity(4);
+    /// assert_eq!(bounded.move_from(&mut dst, 10), 3);
+    /// assert!(bounded.iter().map(|(i, _)| *i).eq([-1, 0, 1]));
+    /// assert!(dst.iter().map(|(i, _)| *i).eq([2, 3]));
+    /// assert_eq!(Rc::strong_count(&item), 7);
+    /// drop((src, dst, bounded));
+    /// assert_eq!(Rc::strong_count(&item), 1);
+    /// ```
+    pub fn move_from(&mut self, src: &mut RingBuffer<T>, max: usize) -> usize {
+        let count = self.reserve_up_to(src.len().min(max));
+        let (src_lhs, src_rhs) = src.as_slices();
+        let src_lhs = &src_lhs[..src_lhs.len().min(count)];
+        let src_rhs = &src_rhs[..count - src_lhs.len()];
+        let (dst_lhs, dst_rhs) = self.spare_capacity_mut();
+        let dst_lhs_len = dst_lhs.len();
+        let (dst_lhs, dst_rhs) = (dst_lhs.as_mut_ptr().cast::<T>(), dst_rhs.as_mut_ptr().cast::<T>());
+        let mut moved = 0;
+        for part in [src_lhs, src_rhs] {
+            // The part is split between the rest of the first spare region and the second.
+            let lhs_len = part.len().min(dst_lhs_len.saturating_sub(moved));
+            let rhs_offset = (moved + lhs_len).saturating_sub(dst_lhs_len);
+            // Safety:
+            // - Space was reserved for `count` items, which the spare regions hold in order.
+            // - The buffers are distinct so the items cannot overlap.
+            unsafe {
+                dst_lhs.add(moved.min(dst_lhs_len)).copy_from_nonoverlapping(part.as_ptr(), lhs_len);
+                dst_rhs.add(rhs_offset).copy_from_nonoverlapping(part.as_ptr().add(lhs_len), part.len() - lhs_len);
+            }
+            moved += part.len();
+        }
+        // Safety: The `count` items were moved, so are no longer owned by `src` but by this buffer.
+        unsafe {
+            src.advance_read_unchecked(count);
+            self.advance_write_unchecked(count);
+        }
+        count
+    }
+    /// Call `f` with the index and a mutable reference of each item, from the oldest to the newest.
+    ///
+    /// The items are visited as two slices, so loops over them can be vectorized where stepping
+    /// through [`RingBuffer::iter_mut`] one item at a time cannot.
+    ///
+    /// If `f` panics, the items keep the changes made so far.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// // The same samples at every offset in the allocation.
+    /// for offset in 0..16 {
+    ///     let mut buffer = RingBuffer::with_capacity(16);
+    ///     for _ in 0..offset {
+    ///         buffer.push(0.0).unwrap();
+    ///         buffer.pop();
+    ///     }
+    ///     for i in 0..12 {
+    ///         buffer.push(i as f32).unwrap();
+    ///     }
+    ///     // Apply a ramp to the samples.
+    ///     let mut stepped = Vec::new();
+    ///     for (i, sample) in buffer.iter_mut().enumerate() {
+    ///         stepped.push(*sample * (i as f32 / 12.0));
+    ///     }
+    ///     buffer.for_each_mut(|i, sample| *sample *= i as f32 / 12.0);
+    ///     assert!(buffer.iter().eq(&stepped));
+    /// }
+    /// ```
+    #[inline]
+    pub fn for_each_mut<F: FnMut(usize, &mut T)>(&mut self, mut f: F) {
+        let (lhs, rhs) = self.as_mut_slices();
+        let offset = lhs.len();
+        for (i, item) in lhs.iter_mut().enumerate() {
+            f(i, item);
+        }
+        for (i, item) in rhs.iter_mut().enumerate() {
+            f(offset + i, item);
+        }
+    }
+    /// Call `f` with each overlapping window of `size` items, from the oldest to the newest.
+    ///
+    /// A window that wraps around the end of the allocation is passed as two slices, in order,
+    /// so that no items are copied. Otherwise the second slice is empty. There are no windows
+    /// when `size` is greater than the length.
+    /// # Panics
+    /// Panics if `size` is 0.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(7);
+    /// for i in 0..6 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// for _ in 0..4 {
+    ///     buffer.pop();
+    /// }
+    /// for i in 6..10 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    ///
+    /// // A moving average over 3 items.
+    /// let mut averages = Vec::new();
+    /// buffer.for_each_window(3, |lhs, rhs| {
+    ///     averages.push(lhs.iter().chain(rhs).sum::<i32>() / 3);
+    /// });
+    /// assert_eq!(averages, [5, 6, 7, 8]);
+    ///
+    /// let mut windows = Vec::new();
+    /// buffer.for_each_window(5, |lhs, rhs| windows.push((lhs.to_vec(), rhs.to_vec())));
+    /// assert_eq!(windows, [(vec![4, 5, 6, 7], vec![8]), (vec![5, 6, 7], vec![8, 9])]);
+    ///
+    /// let mut count = 0;
+    /// buffer.for_each_window(6, |_, _| count += 1);
+    /// assert_eq!(count, 1);
+    /// buffer.for_each_window(7, |_, _| count += 1);
+    /// assert_eq!(count, 1);
+    /// ```
+    #[inline]
+    pub fn for_each_window<F: FnMut(&[T], &[T])>(&self, size: usize, mut f: F) {
+        assert!(size != 0, "window size must be non-zero");
+        let (lhs, rhs) = self.as_slices();
+        if size > lhs.len() + rhs.len() {
+            return;
+        }
+        lhs.windows(size).for_each(|window| f(window, &[]));
+        // Windows that straddle the wrap, starting from the latest that can.
+        for start in lhs.len().saturating_sub(size - 1)..lhs.len() {
+            let end = start + size;
+            if end - lhs.len() <= rhs.len() {
+                f(&lhs[start..], &rhs[..end - lhs.len()]);
+            }
+        }
+        rhs.windows(size).for_each(|window| f(window, &[]));
+    }
+
+    /// Get the items as two slices in order: those from the read cursor to the end of the
+    /// allocation, and those that wrap around to its start. The second slice is empty unless the
+    /// items wrap. [`RingBuffer::make_contiguous`] moves them in to a single slice.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_exact_capacity(3);
+    /// buffer.extend_from_slice(&[1, 2, 3]).unwrap();
+    /// assert_eq!(buffer.as_slices(), (&[1, 2, 3][..], &[][..]));
+    /// for i in 4..6 {
+    ///     buffer.pop();
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// assert_eq!(buffer.as_slices(), (&[3, 4][..], &[5][..]));
+    /// ```
+    pub const fn as_slices(&self) -> (&[T], &[T]) {
+        if self.read <= self.write {
+            unsafe {(
+                core::slice::from_raw_parts(self.data.as_ptr().add(self.read), self.write - self.read),
+                &[]
+            )}
+        } else {
+            unsafe {(
+                core::slice::from_raw_parts(self.data.as_ptr().add(self.read), self.capacity - self.read),
+                core::slice::from_raw_parts(self.data.as_ptr(), self.write)
+            )}
+        }
+    }
+    pub const fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        if self.read <= self.write {
+            unsafe {(
+                core::slice::from_raw_parts_mut(self.data.as_ptr().add(self.read), self.write - self.read),
+                &mut []
+            )}
+        } else {
+            unsafe {(
+                core::slice::from_raw_parts_mut(self.data.as_ptr().add(self.read), self.capacity - self.read),
+                core::slice::from_raw_parts_mut(self.data.as_ptr(), self.write)
+            )}
+        }
+    }
+    /// Get slices over the uninitialized items.
+    ///
+    /// Nothing is reserved, so both slices are empty if the buffer is full, including if it has
+    /// not allocated. Call [`RingBuffer::reserve`] first to make space.
+    pub fn spare_capacity_mut(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        let read = self.wrap(self.read + self.mask());
+        if read < self.write {
+            // Safety: It is guaranteed that the offsets cannot overflow an isize.
+            unsafe {
+                (
+                    core::slice::from_raw_parts_mut(
+                        self.data.as_ptr().add(self.write) as *mut MaybeUninit<T>,
+                        self.capacity - self.write
+                    ),
+                    core::slice::from_raw_parts_mut(
+                        self.data.as_ptr() as *mut MaybeUninit<T>,
+                        read
+                    )
+                )
+            }
+        } else {
+            // Safety: It is guaranteed that the offsets cannot overflow an isize.
+            unsafe {
+                (
+                    core::slice::from_raw_parts_mut(
+                        self.data.as_ptr().add(self.write) as *mut MaybeUninit<T>,
+                        read - self.write
+                    ),
+                    &mut []
+                )
+            }
+        }
+    }
+    /// Initialize items in the spare capacity through a [`SpareCapacity`] and commit the first of
+    /// them to the buffer. The closure returns the number of items to commit, and the rest of
+    /// the items it initialized are dropped.
+    ///
+    /// If `f` panics, nothing is committed and the items it initialized are dropped.
+    /// # Panics
+    /// Panics if the returned count exceeds the number of items initialized.
+    /// ```rust,should_panic
+    /// # use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::<u8>::with_capacity(4);
+    /// buffer.write_spare(|spare| spare.filled() + 1);
+    /// ```
+    /// # Examples
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// buffer.write_spare(|spare| {
+    ///     for i in 0..5 {
+    ///         spare.push(i * 2).unwrap();
+    ///     }
+    ///     spare.filled()
+    /// });
+    /// assert!(buffer.iter().eq(&[0, 2, 4, 6, 8]));
+    /// buffer.write_spare(|_| 0);
+    /// assert_eq!(buffer.len(), 5);
+    ///
+    /// // Only the first items are committed.
+    /// let text = String::from("dropped");
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// buffer.write_spare(|spare| {
+    ///     spare.push(String::from("kept")).unwrap();
+    ///     spare.push(text.clone()).unwrap();
+    ///     1
+    /// });
+    /// assert!(buffer.iter().eq(&["kept"]));
+    /// ```
+    pub fn write_spare<F: FnOnce(&mut SpareCapacity<'_, T>) -> usize>(&mut self, f: F) {
+        let (lhs, rhs) = self.spare_capacity_mut();
+        // Drop the items that are not committed, which is all of them if `f` panics.
+        let mut spare = DropGuard::new((SpareCapacity { lhs, rhs, filled: 0 }, 0), |(spare, kept)| spare.truncate(*kept));
+        let count = f(&mut spare.0);
+        let filled = spare.0.filled;
+        assert!(count <= filled, "write_spare count (is {count}) should be <= the items filled (is {filled})");
+        spare.1 = count;
+        drop(spare);
+        // Safety: The first `count` items of the spare capacity were initialized and kept.
+        unsafe {
+            self.advance_write_unchecked(count);
+        }
+    }
+    /// Initialize items in the spare capacity and commit them to the buffer, without tracking
+    /// which are initialized as [`RingBuffer::write_spare`] does.
+    /// The closure is given the slices from [`RingBuffer::spare_capacity_mut`] and returns the
+    /// number of items it initialized, in order from the start of the first slice.
+    /// Unlike calling [`RingBuffer::advance_write_unchecked`] separately, the buffer cannot be modified
+    /// between initializing and committing the items. [`RingBuffer::with_spare_capacity`] is the
+    /// safe alternative.
+    /// # Panics
+    /// Panics if the returned count exceeds the spare capacity.
+    /// ```rust,should_panic
+    /// # use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::<u8>::with_capacity(4);
+    /// unsafe { buffer.write_spare_unchecked(|lhs, rhs| lhs.len() + rhs.len() + 1) };
+    /// ```
+    /// # Examples
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// unsafe {
+    ///     buffer.write_spare_unchecked(|lhs, rhs| {
+    ///         for (i, slot) in lhs.iter_mut().chain(rhs.iter_mut()).take(5).enumerate() {
+    ///             slot.write(i as u8 * 2);
+    ///         }
+    ///         5
+    ///     });
+    /// }
+    /// assert!(buffer.iter().eq(&[0, 2, 4, 6, 8]));
+    /// ```
+    /// # Safety
+    /// The closure must have initialized as many items as the count it returns. If it panics,
+    /// nothing is committed and any items it initialized are leaked.
+    pub unsafe fn write_spare_unchecked<F: FnOnce(&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) -> usize>(&mut self, f: F) {
+        let (lhs, rhs) = self.spare_capacity_mut();
+        let spare = lhs.len() + rhs.len();
+        let count = f(lhs, rhs);
+        assert!(count <= spare, "write_spare_unchecked count (is {count}) should be <= spare capacity (is {spare})");
+        // Safety: The closure initialized `count` items of the spare capacity.
+        unsafe {
+            self.advance_write_unchecked(count);
+        }
+    }
+    /// Initialize items in the spare capacity through a [`SpareCapacity`] and commit them to the
+    /// buffer, returning the result of the closure.
+    ///
+    /// Only the items initialized through the [`SpareCapacity`] are committed, so unlike
+    /// [`RingBuffer::write_spare_unchecked`] this needs no unsafe code, and the buffer cannot be modified
+    /// between initializing and committing the items. Nothing is reserved, so the spare capacity
+    /// may be empty.
+    ///
+    /// If `f` panics, the items it initialized so far are still committed.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    ///
+    /// let mut buffer = RingBuffer::with_capacity(7);
+    /// buffer.extend_from_slice(&[1, 2, 3, 4, 5]).unwrap();
+    /// for _ in 0..4 {
+    ///     buffer.pop();
+    /// }
+    /// // The spare capacity wraps around the end of the allocation.
+    /// let pushed = buffer.with_spare_capacity(|spare| {
+    ///     assert_eq!(spare.capacity(), 6);
+    ///     assert_eq!(spare.extend_from_slice(&[6, 7, 8, 9]), 4);
+    ///     spare.push(10).unwrap();
+    ///     spare.filled()
+    /// });
+    /// assert_eq!(pushed, 5);
+    /// assert!(buffer.iter().eq(&[5, 6, 7, 8, 9, 10]));
+    ///
+    /// // Only as many items as fit are initialized.
+    /// buffer.with_spare_capacity(|spare| {
+    ///     assert_eq!(spare.extend_from_slice(&[11, 12]), 1);
+    ///     assert_eq!(spare.push(13), Err(13));
+    /// });
+    /// assert!(buffer.iter().eq(&[5, 6, 7, 8, 9, 10, 11]));
+    /// ```
+    pub fn with_spare_capacity<R, F: FnOnce(&mut SpareCapacity<'_, T>) -> R>(&mut self, f: F) -> R {
+        let (lhs, rhs) = self.spare_capacity_mut();
+        let (lhs, rhs): (*mut [MaybeUninit<T>], *mut [MaybeUninit<T>]) = (lhs, rhs);
+        // Safety: The slices are of the allocation rather than the buffer, which the guard only
+        // uses to move the write cursor, so they remain valid and unaliased while it is held.
+        let spare = unsafe { SpareCapacity { lhs: &mut *lhs, rhs: &mut *rhs, filled: 0 } };
+        // Commit the items initialized even if `f` panics, so they are dropped with the buffer.
+        // Safety: `SpareCapacity` only counts the items it initialized, in order from the start
+        // of the spare capacity.
+        let mut spare = DropGuard::new((self, spare), |(buffer, spare)| unsafe { buffer.advance_write_unchecked(spare.filled) });
+        f(&mut spare.1)
+    }
+    /// Get slices over the uninitialized items preceding the read cursor.
+    /// Taken in order the slices end immediately before the first item, so prepending `n` items
+    /// means initializing the last `n` items of the slices and then calling
+    /// [`RingBuffer::set_read_cursor_back`].
+    ///
+    /// An unallocated buffer has no spare capacity, so both slices are empty.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let constructors: [fn(usize) -> RingBuffer<u8>; 2] = [RingBuffer::with_capacity, RingBuffer::with_exact_capacity];
+    /// for new in constructors {
+    ///     let mut buffer = new(0);
+    ///     let (lhs, rhs) = buffer.spare_capacity_front_mut();
+    ///     assert!(lhs.is_empty() && rhs.is_empty());
+    /// }
+    /// // Power of two capacities, and exact capacities that are not.
+    /// for (capacity, exact) in [(2, false), (4, false), (8, false), (2, true), (3, true), (6, true)] {
+    ///     for start in 0..capacity {
+    ///         for len in 0..capacity {
+    ///             let mut buffer = if exact {
+    ///                 RingBuffer::with_exact_capacity(capacity - 1)
+    ///             } else {
+    ///                 RingBuffer::with_capacity(capacity - 1)
+    ///             };
+    ///             assert_eq!(buffer.capacity(), capacity);
+    ///             for _ in 0..start {
+    ///                 buffer.push(0u8).unwrap();
+    ///                 buffer.pop();
+    ///             }
+    ///             for i in 0..len {
+    ///                 buffer.push(i as u8 + 1).unwrap();
+    ///             }
+    ///             let free = capacity - 1 - len;
+    ///             let (lhs, rhs) = buffer.spare_capacity_mut();
+    ///             assert_eq!(lhs.len() + rhs.len(), free);
+    ///
+    ///             let (lhs, rhs) = buffer.spare_capacity_front_mut();
+    ///             assert_eq!(lhs.len() + rhs.len(), free);
+    ///             for (i, slot) in lhs.iter_mut().chain(rhs).enumerate() {
+    ///                 slot.write(100 + i as u8);
+    ///             }
+    ///             unsafe { buffer.set_read_cursor_back(free) };
+    ///             assert!(buffer.is_full());
+    ///             let expected = (0..free).map(|i| 100 + i as u8).chain((1..=len).map(|i| i as u8));
+    ///             assert!(buffer.iter().copied().eq(expected));
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn spare_capacity_front_mut(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        // Note: An unallocated buffer has no vacant slot, and `wrap` would leave `write + 1` out
+        // of range.
+        if self.capacity == 0 {
+            return (&mut [], &mut []);
+        }
+        // Note: The slot at the write cursor remains vacant. The write cursor is less than the
+        // capacity, so `write + 1` wraps in to range for exact capacities too.
+        let start = self.wrap(self.write + 1);
+        if start <= self.read {
+            // Safety: It is guaranteed that the offsets cannot overflow an isize.
+            unsafe {
+                (
+                    core::slice::from_raw_parts_mut(
+                        self.data.as_ptr().add(start) as *mut MaybeUninit<T>,
+                        self.read - start
+                    ),
+                    &mut []
+                )
+            }
+        } else {
+            // Safety: It is guaranteed that the offsets cannot overflow an isize.
+            unsafe {
+                (
+                    core::slice::from_raw_parts_mut(
+                        self.data.as_ptr().add(start) as *mut MaybeUninit<T>,
+                        self.capacity - start
+                    ),
+                    core::slice::from_raw_parts_mut(
+                        self.data.as_ptr() as *mut MaybeUninit<T>,
+                        self.read
+                    )
+                )
+            }
+        }
+    }
+
+    pub const fn layout(&self) -> Option<Layout> {
+        if size_of::<T>() == 0 || self.capacity == 0 {
+            None
+        } else {
+            assert!(size_of::<T>().is_multiple_of(align_of::<T>()));
+            // Safety:
+            // - Rust types are asserted to have a matching size and stride.
+            // - The alignment is always a power of 2.
+            // - The capacity in bytes will never overflow an isize.
+            unsafe {
+                let align = self.align;
+                let size = size_of::<T>() * self.capacity;
+                let layout = Layout::from_size_align_unchecked(size, align);
+                Some(layout)
+            }
+        }
+    }
+    /// Get a layout valid for the ring buffer with a size of at least `capacity` items.
+    /// It provides the following guarantees:
+    /// - The layout will allow indexing by `data.offset()`.
+    /// - The layout size in items will be a power of two.
+    ///
+    /// To guard against misuse, [`None`] is returned if the layout would have a 0 size.
+    ///
+    /// Panics if the capacity rounded up to a power of two is too large for any allocation.
+    fn layout_for(capacity: usize, align: usize) -> Option<Layout> {
         // Note: A capacity of 1 is an effective capacity of 0.
+        let capacity = capacity.checked_next_power_of_two().unwrap_or_else(|| capacity_overflow());
+        Self::layout_for_exact(capacity.max(2), align)
+    }
+    /// Get a layout valid for the ring buffer with a size of exactly `capacity` items, aligned to
+    /// `align`, which must be a power of two no smaller than the alignment of `T`.
+    /// To guard against misuse, [`None`] is returned if the layout would have a 0 size.
+    ///
+    /// Panics if the capacity is larger than [`RingBuffer::max_capacity_for`].
+    fn layout_for_exact(capacity: usize, align: usize) -> Option<Layout> {
+        if size_of::<T>() == 0 || capacity == 0 {
+            return None;
+        }
+        if capacity > Self::max_capacity_for(align) {
+            capacity_overflow()
+        }
+
         // Safety:
         // - `size` > 0.
         // - `capacity` does not overflow `isize::MAX`.
-        let size = capacity.max(2) * size_of::<T>();
+        let size = capacity * size_of::<T>();
+        unsafe {
+            Some(Layout::from_size_align_unchecked(size, align))
+        }
+    }
+    /// Get the largest capacity of an allocation aligned to `align`, as the size of an allocation
+    /// rounded up to its alignment may not exceed `isize::MAX` bytes.
+    const fn max_capacity_for(align: usize) -> usize {
+        match size_of::<T>() {
+            0 => usize::MAX,
+            size => (isize::MAX as usize - (align - 1)) / size,
+        }
+    }
+}
+impl<A, B> RingBuffer<(A, B)> {
+    /// Split a buffer of pairs in to a buffer of the first items and a buffer of the second items.
+    pub fn unzip(self) -> (RingBuffer<A>, RingBuffer<B>) {
+        let mut a = RingBuffer::with_capacity(self.len());
+        let mut b = RingBuffer::with_capacity(self.len());
+        for (first, second) in self {
+            // The buffers are unbounded so pushing cannot fail.
+            let _ = a.push(first);
+            let _ = b.push(second);
+        }
+        (a, b)
+    }
+}
+impl<T: Clone> RingBuffer<T> {
+    /// Clone the items in to a [`Vec`], in order.
+    ///
+    /// Items are cloned a slice at a time, so [`Copy`] items are copied in at most two copies
+    /// rather than one at a time as with `iter().cloned().collect()`.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// buffer.extend_from_slice(&[0.0f32; 6]).unwrap();
+    /// buffer.pop_slice(&mut [0.0; 6]);
+    /// for i in 0..7 {
+    ///     buffer.push(i as f32).unwrap();
+    /// }
+    /// assert_eq!(buffer.to_vec(), buffer.iter().copied().collect::<Vec<_>>());
+    /// assert_eq!(buffer.to_vec(), [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    /// assert_eq!(buffer.iter().fold(0.0, |sum, sample| sum + sample), 21.0);
+    ///
+    /// let mut names = RingBuffer::new();
+    /// names.push("a".to_owned()).unwrap();
+    /// names.push("b".to_owned()).unwrap();
+    /// assert_eq!(names.to_vec(), names.iter().cloned().collect::<Vec<_>>());
+    /// ```
+    pub fn to_vec(&self) -> Vec<T> {
+        let (lhs, rhs) = self.as_slices();
+        let mut items = Vec::with_capacity(lhs.len() + rhs.len());
+        items.extend_from_slice(lhs);
+        items.extend_from_slice(rhs);
+        items
+    }
+    /// Get the items as a single slice, borrowed if they are contiguous in the allocation and
+    /// otherwise cloned in to a [`Vec`]. Unlike [`RingBuffer::make_contiguous`] the buffer is not
+    /// modified.
+    /// ```rust
+    /// use std::borrow::Cow;
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(3);
+    /// buffer.extend_from_slice(b"abc").unwrap();
+    /// assert!(matches!(buffer.as_contiguous(), Cow::Borrowed(b"abc")));
+    /// buffer.pop_slice(&mut [0; 2]);
+    /// buffer.extend_from_slice(b"de").unwrap();
+    /// assert!(matches!(buffer.as_contiguous(), Cow::Owned(bytes) if bytes == b"cde"));
+    /// ```
+    pub fn as_contiguous(&self) -> Cow<'_, [T]> {
+        match self.as_slices() {
+            (lhs, []) => Cow::Borrowed(lhs),
+            _ => Cow::Owned(self.to_vec()),
+        }
+    }
+    /// Clone all of `slice` to the write end of the [`RingBuffer`], growing it once if needed.
+    /// Fails without modifying the buffer if this requires growing beyond the maximum capacity.
+    ///
+    /// [`RingBuffer::extend_from_slice`] copies [`Copy`] items instead. If a clone panics, the
+    /// items cloned before it are kept.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut names = RingBuffer::with_capacity(2);
+    /// names.push("a".to_owned()).unwrap();
+    /// names.extend_from_slice_clone(&["b".to_owned(), "c".to_owned()]).unwrap();
+    /// assert_eq!(names.to_vec(), ["a", "b", "c"]);
+    ///
+    /// names.set_max_capacity(names.capacity());
+    /// assert!(names.extend_from_slice_clone(&vec!["d".to_owned(); 8]).is_err());
+    /// assert_eq!(names.len(), 3);
+    /// ```
+    pub fn extend_from_slice_clone(&mut self, slice: &[T]) -> Result<(), CapacityError> {
+        self.reserve(slice.len())?;
+        self.with_spare_capacity(|spare| {
+            for item in slice {
+                // Note: Space was reserved for all of `slice` so pushing cannot fail.
+                let _ = spare.push(item.clone());
+            }
+        });
+        Ok(())
+    }
+}
+impl<T: Copy> RingBuffer<T> {
+    /// Copy all of `slice` to the write end of the [`RingBuffer`], growing it if needed.
+    /// Fails without modifying the buffer if this requires growing beyond the maximum capacity.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(7);
+    /// buffer.extend_from_slice(b"hello").unwrap();
+    /// let mut word = [0; 4];
+    /// assert_eq!(buffer.pop_slice(&mut word), 4);
+    /// assert_eq!(&word, b"hell");
+    ///
+    /// // The copy wraps around the end of the allocation.
+    /// buffer.extend_from_slice(b" world").unwrap();
+    /// assert_eq!(buffer.capacity(), 8);
+    /// let mut rest = [0; 16];
+    /// assert_eq!(buffer.pop_slice(&mut rest), 7);
+    /// assert_eq!(&rest[..7], b"o world");
+    /// assert_eq!(buffer.pop_slice(&mut rest), 0);
+    ///
+    /// buffer.set_max_capacity(8);
+    /// assert!(buffer.extend_from_slice(&[0; 8]).is_err());
+    /// assert!(buffer.is_empty());
+    /// ```
+    pub fn extend_from_slice(&mut self, slice: &[T]) -> Result<(), CapacityError> {
+        self.reserve(slice.len())?;
+        let (lhs, rhs) = self.spare_capacity_mut();
+        let lhs_len = lhs.len().min(slice.len());
+        let rhs_len = slice.len() - lhs_len;
+        // Safety:
+        // - Space was reserved for all of `slice`, so `rhs` holds at least `rhs_len` items.
+        // - `T: Copy` so the items can be duplicated bitwise.
+        unsafe {
+            lhs.as_mut_ptr().cast::<T>().copy_from_nonoverlapping(slice.as_ptr(), lhs_len);
+            rhs.as_mut_ptr().cast::<T>().copy_from_nonoverlapping(slice.as_ptr().add(lhs_len), rhs_len);
+            self.advance_write_unchecked(slice.len());
+        }
+        Ok(())
+    }
+    /// Copy up to `max` items from the read end of `src` to the write end of this buffer, growing
+    /// it if needed, without modifying `src`. Returns the number of items copied.
+    ///
+    /// If this buffer reaches its maximum capacity, or fails to allocate, only as many items as
+    /// fit are copied.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut src = RingBuffer::with_capacity(4);
+    /// src.extend_from_slice(&[1, 2, 3]).unwrap();
+    ///
+    /// let mut dst = RingBuffer::new();
+    /// assert_eq!(dst.copy_from(&src, 2), 2);
+    /// assert!(dst.iter().eq(&[1, 2]));
+    /// assert_eq!(dst.copy_from(&src, 0), 0);
+    /// assert_eq!(dst.copy_from(&src, 10), 3);
+    /// assert!(dst.iter().eq(&[1, 2, 1, 2, 3]));
+    /// assert!(src.iter().eq(&[1, 2, 3]));
+    ///
+    /// let mut bounded = RingBuffer::with_max_capacity(4);
+    /// assert_eq!(bounded.copy_from(&dst, 5), 3);
+    /// assert!(bounded.iter().eq(&[1, 2, 1]));
+    /// ```
+    pub fn copy_from(&mut self, src: &RingBuffer<T>, max: usize) -> usize {
+        let count = self.reserve_up_to(src.len().min(max));
+        let (lhs, rhs) = src.as_slices();
+        let lhs_len = lhs.len().min(count);
+        // Note: Space was reserved for `count` items so extending cannot fail.
+        let _ = self.extend_from_slice(&lhs[..lhs_len]);
+        let _ = self.extend_from_slice(&rhs[..count - lhs_len]);
+        count
+    }
+    /// Detach the first `index` items in to a new buffer, leaving the rest in this one.
+    ///
+    /// Only the detached items are copied. The new buffer has just enough capacity for them and
+    /// none of the settings of this buffer.
+    /// # Panics
+    /// Panics if `index` is greater than the length of the buffer.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// buffer.extend_from_slice(b"abcdef").unwrap();
+    /// buffer.pop_slice(&mut [0; 4]);
+    /// buffer.extend_from_slice(b"ghij").unwrap();
+    ///
+    /// // The detached items wrap around the end of the allocation.
+    /// let head = buffer.split_to(5);
+    /// assert!(head.iter().eq(b"efghi"));
+    /// assert!(buffer.iter().eq(b"j"));
+    /// assert!(buffer.split_to(0).is_empty());
+    /// assert!(buffer.split_to(1).iter().eq(b"j"));
+    /// assert!(buffer.is_empty());
+    /// ```
+    #[track_caller]
+    pub fn split_to(&mut self, index: usize) -> RingBuffer<T> {
+        let len = self.len();
+        assert!(index <= len, "cannot split at {index} when only {len} items are buffered");
+        let mut head = RingBuffer::new();
+        let copied = head.copy_from(self, index);
+        // Safety: The copied items are readable, and being `Copy` need no dropping.
+        unsafe { self.advance_read_unchecked(copied) };
+        head
+    }
+    /// Take items from the read end of the [`RingBuffer`] to fill `slice`.
+    /// Returns the number of items taken, which is less than the length of `slice` if the buffer
+    /// runs out of items.
+    pub fn pop_slice(&mut self, slice: &mut [T]) -> usize {
+        // Safety: Only initialized items are written to `slice`.
+        self.pop_slice_uninit(unsafe { &mut *(slice as *mut [T] as *mut [MaybeUninit<T>]) })
+    }
+    /// Take items from the read end of the [`RingBuffer`] to initialize the start of `slice`, in at
+    /// most two copies. Returns the number of items taken, which is less than the length of `slice`
+    /// if the buffer runs out of items.
+    /// ```rust
+    /// use core::mem::MaybeUninit;
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(4);
+    /// buffer.extend_from_slice(b"ab").unwrap();
+    /// buffer.pop();
+    /// buffer.extend_from_slice(b"cd").unwrap();
+    ///
+    /// let mut out = [MaybeUninit::uninit(); 8];
+    /// assert_eq!(buffer.pop_slice_uninit(&mut out), 3);
+    /// // Safety: The first 3 bytes were initialized.
+    /// assert_eq!(out[..3].iter().map(|byte| unsafe { byte.assume_init() }).collect::<Vec<_>>(), b"bcd");
+    /// assert!(buffer.is_empty());
+    /// ```
+    pub fn pop_slice_uninit(&mut self, slice: &mut [MaybeUninit<T>]) -> usize {
+        let (lhs, rhs) = self.as_slices();
+        let lhs_len = lhs.len().min(slice.len());
+        let rhs_len = rhs.len().min(slice.len() - lhs_len);
+        // Safety:
+        // - `slice` has space for `lhs_len + rhs_len` items, which are all initialized.
+        // - `T: Copy` so the items do not need to be dropped once taken.
         unsafe {
-            Some(Layout::from_size_align_unchecked(size, align_of::<T>()))
+            let ptr = slice.as_mut_ptr().cast::<T>();
+            ptr.copy_from_nonoverlapping(lhs.as_ptr(), lhs_len);
+            ptr.add(lhs_len).copy_from_nonoverlapping(rhs.as_ptr(), rhs_len);
+            self.advance_read_unchecked(lhs_len + rhs_len);
         }
+        lhs_len + rhs_len
     }
 }
+impl RingBuffer<u8> {
+    /// Set if bytes are overwritten with zeros as soon as they are consumed, so that sensitive data
+    /// such as credentials does not linger in memory after it has been read.
+    ///
+    /// Bytes are scrubbed when the read cursor moves past them, when the buffer is cleared and
+    /// when an allocation is freed, including the old allocation when the buffer grows. Scrubbing
+    /// is disabled by default.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// /// Get the spare slots before the read cursor, which held the consumed bytes.
+    /// fn consumed(buffer: &mut RingBuffer<u8>) -> Vec<u8> {
+    ///     let (_, rhs) = buffer.spare_capacity_mut();
+    ///     // Safety: The slots from the start of the allocation were written before being consumed.
+    ///     rhs.iter().map(|byte| unsafe { byte.assume_init() }).collect()
+    /// }
+    ///
+    /// let mut buffer = RingBuffer::with_capacity(16);
+    /// buffer.extend_from_slice(b"password=hunter").unwrap();
+    /// buffer.pop_slice(&mut [0; 15]);
+    /// assert_eq!(consumed(&mut buffer), b"password=hunte");
+    ///
+    /// let mut buffer = RingBuffer::with_capacity(16);
+    /// buffer.set_scrub_consumed(true);
+    /// buffer.extend_from_slice(b"password=hunter").unwrap();
+    /// buffer.pop_slice(&mut [0; 9]);
+    /// assert_eq!(consumed(&mut buffer), [0; 8]);
+    /// buffer.pop();
+    /// assert_eq!(consumed(&mut buffer), [0; 9]);
+    ///
+    /// buffer.clear();
+    /// let (lhs, _) = buffer.spare_capacity_mut();
+    /// assert!(lhs.iter().all(|byte| unsafe { byte.assume_init() } == 0));
+    /// ```
+    pub const fn set_scrub_consumed(&mut self, scrub: bool) {
+        self.scrub = scrub;
+    }
+    /// Get if bytes are overwritten with zeros as soon as they are consumed.
+    pub const fn scrub_consumed(&self) -> bool {
+        self.scrub
+    }
+    /// Display the bytes as UTF-8 text, replacing invalid sequences with
+    /// [`U+FFFD REPLACEMENT CHARACTER`](char::REPLACEMENT_CHARACTER) as
+    /// [`String::from_utf8_lossy`] does, without allocating.
+    ///
+    /// A character split across the end of the allocation is displayed as a whole.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let text = "a€😀é";
+    /// for offset in 0..16 {
+    ///     let mut buffer = RingBuffer::with_capacity(16);
+    ///     buffer.extend_from_slice(&[0; 16][..offset]).unwrap();
+    ///     buffer.pop_slice(&mut [0; 16][..offset]);
+    ///     buffer.extend_from_slice(text.as_bytes()).unwrap();
+    ///     assert_eq!(buffer.display_lossy().to_string(), text);
+    /// }
+    ///
+    /// // Invalid and incomplete sequences, including ones straddling the wrap.
+    /// let bytes = b"\xe2\x82a\xf0\x9f\x98\xff\xc3";
+    /// for offset in 0..16 {
+    ///     let mut buffer = RingBuffer::with_capacity(16);
+    ///     buffer.extend_from_slice(&[0; 16][..offset]).unwrap();
+    ///     buffer.pop_slice(&mut [0; 16][..offset]);
+    ///     buffer.extend_from_slice(bytes).unwrap();
+    ///     assert_eq!(buffer.display_lossy().to_string(), String::from_utf8_lossy(bytes));
+    ///     assert_eq!(buffer.display_lossy().to_string(), "\u{fffd}a\u{fffd}\u{fffd}\u{fffd}");
+    /// }
+    /// ```
+    pub fn display_lossy(&self) -> impl core::fmt::Display + '_ {
+        DisplayLossy(self)
+    }
+    /// Copy `slice` to the write end if it fits in the spare capacity before the allocation wraps,
+    /// without growing the buffer. Returns if it was copied.
+    ///
+    /// This is the fast path for buffering many small writes, so it only bumps the write cursor.
+    #[inline]
+    pub(crate) fn try_extend_contiguous(&mut self, slice: &[u8]) -> bool {
+        // Note: One slot must always remain vacant.
+        let end = if self.read > self.write {
+            self.read - 1
+        } else if self.read == 0 {
+            self.capacity.saturating_sub(1)
+        } else {
+            self.capacity
+        };
+        if slice.len() > end - self.write {
+            return false;
+        }
+        traced!(self, crate::trace::Operation::ExtendContiguous(slice.len()), {
+            // Safety:
+            // - The slots from the write cursor up to `end` are allocated and vacant.
+            // - The slice cannot overlap the vacant slots borrowed mutably through `self`.
+            unsafe {
+                self.data.as_ptr().add(self.write).copy_from_nonoverlapping(slice.as_ptr(), slice.len());
+            }
+            self.pushed = self.pushed.wrapping_add(slice.len() as u64);
+            self.write = self.wrap(self.write + slice.len());
+        });
+        true
+    }
+    /// Append `count` copies of `byte` to the buffer, growing it as needed.
+    ///
+    /// Nothing is appended if the buffer cannot grow to fit all of them.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// buffer.extend_from_slice(b"header").unwrap();
+    /// buffer.pop_slice(&mut [0; 4]);
+    /// // Wraps around the end of the allocation and then grows.
+    /// buffer.fill_bytes(0, 3).unwrap();
+    /// assert!(buffer.iter().eq(b"er\0\0\0"));
+    /// buffer.fill_bytes(b'-', 1 << 20).unwrap();
+    /// assert_eq!(buffer.len(), 5 + (1 << 20));
+    /// assert!(buffer.iter().skip(5).all(|byte| *byte == b'-'));
+    ///
+    /// buffer.set_max_capacity(buffer.capacity());
+    /// assert!(buffer.fill_bytes(0, buffer.capacity()).is_err());
+    /// assert_eq!(buffer.len(), 5 + (1 << 20));
+    /// ```
+    pub fn fill_bytes(&mut self, byte: u8, count: usize) -> Result<(), CapacityError> {
+        self.reserve(count)?;
+        let (lhs, rhs) = self.spare_capacity_mut();
+        let lhs_len = lhs.len().min(count);
+        // Safety:
+        // - Space was reserved for `count` bytes, so `rhs` holds at least `count - lhs_len`.
+        // - Any byte is a valid `u8`.
+        unsafe {
+            lhs.as_mut_ptr().write_bytes(byte, lhs_len);
+            rhs.as_mut_ptr().write_bytes(byte, count - lhs_len);
+            self.advance_write_unchecked(count);
+        }
+        Ok(())
+    }
+    /// Perform a single read from `reader` in to the spare capacity, growing the buffer first if
+    /// it is full. Returns the number of bytes read.
+    ///
+    /// Nothing is read and 0 is returned if the buffer is full and cannot grow.
+    /// ```rust
+    /// # use std::io;
+    /// # struct Chunked<'a>(&'a [u8]);
+    /// # impl io::Read for Chunked<'_> {
+    /// #     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    /// #         let len = buf.len().min(self.0.len()).min(3);
+    /// #         buf[..len].copy_from_slice(&self.0[..len]);
+    /// #         self.0 = &self.0[len..];
+    /// #         Ok(len)
+    /// #     }
+    /// # }
+    /// # struct Short(Vec<u8>);
+    /// # impl io::Write for Short {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    /// #         let len = buf.len().min(2);
+    /// #         self.0.extend_from_slice(&buf[..len]);
+    /// #         Ok(len)
+    /// #     }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// use circulate::{FromStd, RingBuffer};
+    /// // Reads at most 3 bytes at a time.
+    /// let mut reader = FromStd(Chunked(b"abcdefgh"));
+    /// // Writes at most 2 bytes at a time.
+    /// let mut writer = FromStd(Short(Vec::new()));
+    ///
+    /// let mut buffer = RingBuffer::with_capacity(3);
+    /// assert_eq!(buffer.fill_from(&mut reader).unwrap(), 3);
+    /// assert_eq!(buffer.drain_to(&mut writer).unwrap(), 2);
+    /// // The free space now wraps around the end of the allocation.
+    /// assert_eq!(buffer.fill_from(&mut reader).unwrap(), 1);
+    /// assert_eq!(buffer.fill_from(&mut reader).unwrap(), 1);
+    /// assert!(buffer.iter().eq(b"cde"));
+    /// while buffer.fill_from(&mut reader).unwrap() != 0 {}
+    /// while buffer.drain_to(&mut writer).unwrap() != 0 {}
+    /// assert_eq!(writer.0.0, b"abcdefgh");
+    /// ```
+    pub fn fill_from<R: Read + ?Sized>(&mut self, reader: &mut R) -> Result<usize, R::Error> {
+        // TODO: a smarter growth strategy
+        if !self.ensure_one_free() {
+            return Ok(0);
+        }
+        self.with_spare_capacity(|spare| spare.read_from(reader))
+    }
+    /// Perform a single write of the buffered bytes to `writer`, consuming the bytes that were
+    /// written. Returns the number of bytes written.
+    ///
+    /// When the bytes wrap around the end of the buffer both parts are passed to a single
+    /// [`Write::write_vectored`].
+    /// ```rust
+    /// # use std::io;
+    /// # /// Accepts at most 3 bytes per call, recording the slices of each vectored write.
+    /// # struct Gather(Vec<u8>, Vec<usize>);
+    /// # impl io::Write for Gather {
+    /// #     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.write_vectored(&[io::IoSlice::new(buf)]) }
+    /// #     fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+    /// #         self.1.push(bufs.len());
+    /// #         let bytes: Vec<u8> = bufs.iter().flat_map(|buf| buf.iter().copied()).take(3).collect();
+    /// #         self.0.extend_from_slice(&bytes);
+    /// #         Ok(bytes.len())
+    /// #     }
+    /// #     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// # }
+    /// use circulate::{FromStd, RingBuffer};
+    ///
+    /// let mut buffer = RingBuffer::with_exact_capacity(4);
+    /// buffer.extend_from_slice(b"ab").unwrap();
+    /// buffer.pop();
+    /// buffer.pop();
+    /// buffer.extend_from_slice(b"cdef").unwrap();
+    /// let mut writer = FromStd(Gather(Vec::new(), Vec::new()));
+    /// // The write ends part way through the second slice.
+    /// assert_eq!(buffer.drain_to(&mut writer).unwrap(), 3);
+    /// assert!(buffer.iter().eq(b"f"));
+    /// assert_eq!(buffer.drain_to(&mut writer).unwrap(), 1);
+    /// assert_eq!(writer.0.0, b"cdef");
+    /// assert_eq!(writer.0.1, [2, 1]);
+    /// ```
+    pub fn drain_to<W: Write + ?Sized>(&mut self, writer: &mut W) -> Result<usize, W::Error> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+        let (lhs, rhs) = self.as_slices();
+        let count = if rhs.is_empty() {
+            writer.write(lhs)?
+        } else {
+            writer.write_vectored(&[IoVec::new(lhs), IoVec::new(rhs)])?
+        }.min(self.len());
+        // Safety: No more than the readable bytes are consumed.
+        unsafe {
+            self.advance_read_unchecked(count);
+        }
+        Ok(count)
+    }
+    /// Detach the bytes up to and including the first `byte` in to a new buffer, or return
+    /// `None` without modifying the buffer if it holds no `byte`. See [`RingBuffer::split_to`].
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// buffer.extend_from_slice(b"\nab\n").unwrap();
+    /// assert!(buffer.take_until(b'\n').unwrap().iter().eq(b"\n"));
+    /// assert!(buffer.take_until(b'\n').unwrap().iter().eq(b"ab\n"));
+    /// assert!(buffer.take_until(b'\n').is_none());
+    ///
+    /// // A delimiter at the last slot of the allocation, and one past the wrap.
+    /// buffer.extend_from_slice(b"cde\nf\n").unwrap();
+    /// assert!(buffer.take_until(b'\n').unwrap().iter().eq(b"cde\n"));
+    /// assert!(buffer.take_until(b'\n').unwrap().iter().eq(b"f\n"));
+    ///
+    /// buffer.extend_from_slice(b"no end").unwrap();
+    /// assert!(buffer.take_until(b'\n').is_none());
+    /// assert!(buffer.iter().eq(b"no end"));
+    /// ```
+    pub fn take_until(&mut self, byte: u8) -> Option<RingBuffer<u8>> {
+        let index = self.find_byte(byte, 0)?;
+        Some(self.split_to(index + 1))
+    }
+    /// Get the index of the first occurrence of `needle` from the read end, including one that
+    /// straddles the end of the allocation, without copying. An empty `needle` is found at 0.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    ///
+    /// let mut state = 0x2545_f491_4f6c_dd1du64;
+    /// let mut random = move |bound: usize| {
+    ///     state ^= state << 13;
+    ///     state ^= state >> 7;
+    ///     state ^= state << 17;
+    ///     (state % bound as u64) as usize
+    /// };
+    /// for _ in 0..5_000 {
+    ///     // A small alphabet, so that needles often match and often partially match.
+    ///     let haystack: Vec<u8> = (0..random(24)).map(|_| b'a' + random(3) as u8).collect();
+    ///     let needle: Vec<u8> = (0..random(5)).map(|_| b'a' + random(3) as u8).collect();
+    ///     // Wrap the haystack at a random offset.
+    ///     let mut buffer = RingBuffer::with_capacity(31);
+    ///     let offset = random(32);
+    ///     buffer.extend_from_slice(&vec![0; offset]).unwrap();
+    ///     buffer.pop_slice(&mut vec![0; offset]);
+    ///     buffer.extend_from_slice(&haystack).unwrap();
+    ///
+    ///     let expected = match needle.len() {
+    ///         0 => Some(0),
+    ///         len => haystack.windows(len).position(|window| window == needle),
+    ///     };
+    ///     assert_eq!(buffer.find(&needle), expected, "{needle:?} in {haystack:?} at {offset}");
+    /// }
+    /// ```
+    pub fn find(&self, needle: &[u8]) -> Option<usize> {
+        /// Get the index of the first occurrence of a non-empty `needle` in `haystack`.
+        fn find_in(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+            let last_start = haystack.len().checked_sub(needle.len())?;
+            let mut start = 0;
+            while start <= last_start {
+                let index = start + haystack[start..=last_start].iter().position(|&b| b == needle[0])?;
+                if haystack[index..index + needle.len()] == *needle {
+                    return Some(index);
+                }
+                start = index + 1;
+            }
+            None
+        }
+
+        if needle.is_empty() {
+            return Some(0);
+        }
+        let (lhs, rhs) = self.as_slices();
+        if let Some(index) = find_in(lhs, needle) {
+            return Some(index);
+        }
+        // Matches starting in `lhs` and ending in `rhs`, with `split` bytes of the needle in `lhs`.
+        for split in (1..needle.len().min(lhs.len() + 1)).rev() {
+            let (head, tail) = needle.split_at(split);
+            if lhs.ends_with(head) && rhs.starts_with(tail) {
+                return Some(lhs.len() - split);
+            }
+        }
+        find_in(rhs, needle).map(|index| lhs.len() + index)
+    }
+    /// Get the index of the first `byte` at or after `start`.
+    pub(crate) fn find_byte(&self, byte: u8, start: usize) -> Option<usize> {
+        let (lhs, rhs) = self.as_slices();
+        if start < lhs.len() {
+            if let Some(index) = lhs[start..].iter().position(|&b| b == byte) {
+                return Some(start + index);
+            }
+        }
+        let skip = start.saturating_sub(lhs.len()).min(rhs.len());
+        rhs[skip..].iter().position(|&b| b == byte).map(|index| lhs.len() + skip + index)
+    }
+}
+// Safety: The buffer uniquely owns its items, the same as a `Vec<T>`.
+unsafe impl<T: Send> Send for RingBuffer<T> {}
+// Safety: Items are only reachable through a shared reference to the buffer by shared reference.
+unsafe impl<T: Sync> Sync for RingBuffer<T> {}
 impl<T> Drop for RingBuffer<T> {
     fn drop(&mut self) {
         let (left, right) = self.as_mut_slices();
-        // Safety: Slices have the same requirements as `drop_in_place()`.
-        unsafe {
-            drop_in_place(left);
-            drop_in_place(right);
+        let (left, right): (*mut [T], *mut [T]) = (left, right);
+        // Free the allocation once the items are dropped, even if dropping one panics.
+        let _free = DropGuard::new(self, |buffer| {
+            if let Some(layout) = buffer.layout() {
+                // Safety:
+                // - The pointer must point to owned memory of the layout if `layout()` returns `Some`.
+                // - The items were dropped.
+                unsafe {
+                    buffer.scrub_allocation();
+                    dealloc(buffer.data.as_ptr().cast(), layout)
+                }
+            }
+        });
+        // Safety: The slices are of the items, which are not used again.
+        unsafe { drop_runs(left, right) };
+    }
+}
+/// Drop the items of `lhs` and then of `rhs`, still dropping those of `rhs` if dropping one of
+/// `lhs` panics.
+/// # Safety
+/// The slices have the same requirements as `drop_in_place()`.
+unsafe fn drop_runs<T>(lhs: *mut [T], rhs: *mut [T]) {
+    // Safety: The caller guarantees the slices can be dropped in place.
+    let _rhs = DropGuard::new(rhs, |rhs| unsafe { drop_in_place(*rhs) });
+    unsafe { drop_in_place(lhs) };
+}
+
+impl<T> core::ops::Index<usize> for RingBuffer<T> {
+    type Output = T;
+    /// Get the item `index` items from the read end.
+    /// # Panics
+    /// Panics if there are not more than `index` items.
+    /// ```rust,should_panic
+    /// # use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(8);
+    /// buffer.extend_from_slice(&[1, 2]).unwrap();
+    /// assert_eq!(buffer[1], 2);
+    /// buffer[2];
+    /// ```
+    #[track_caller]
+    fn index(&self, index: usize) -> &T {
+        let len = self.len();
+        match self.get(index) {
+            Some(item) => item,
+            None => panic!("index {index} is out of range for a RingBuffer of length {len}"),
         }
+    }
+}
+impl<T> core::ops::IndexMut<usize> for RingBuffer<T> {
+    #[track_caller]
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len();
+        match self.get_mut(index) {
+            Some(item) => item,
+            None => panic!("index {index} is out of range for a RingBuffer of length {len}"),
+        }
+    }
+}
 
-        if let Some(layout) = self.layout() {
-            // Safety:
-            // - The pointer must point to owned memory of the layout if `layout()` returns `Some`.
-            unsafe {
-                dealloc(self.data.as_ptr().cast(), layout)
+/// Clones into a new buffer with the same capacity and settings, with the items from the read end
+/// at the start of the allocation.
+/// ```rust
+/// use circulate::RingBuffer;
+/// let mut buffer = RingBuffer::with_exact_capacity(3);
+/// buffer.extend(["a", "b", "c"].map(String::from));
+/// buffer.pop();
+/// buffer.push("d".to_string()).unwrap();
+///
+/// let clone = buffer.clone();
+/// assert_eq!(clone, buffer);
+/// assert_eq!(clone.capacity(), buffer.capacity());
+/// assert_eq!(format!("{clone:?}"), r#"["b", "c", "d"]"#);
+/// ```
+impl<T: Clone> Clone for RingBuffer<T> {
+    fn clone(&self) -> Self {
+        let mut clone = self.empty_like();
+        for item in self {
+            if clone.push(item.clone()).is_err() {
+                panic!("failed to allocate a clone of a RingBuffer");
             }
         }
+        clone
+    }
+}
+/// Formats the items as a list, from the read end.
+impl<T: core::fmt::Debug> core::fmt::Debug for RingBuffer<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+/// Buffers are equal if they hold equal items in the same order from the read end, wherever the
+/// items sit in their allocations.
+/// ```rust
+/// use circulate::RingBuffer;
+/// let mut wrapped = RingBuffer::with_exact_capacity(3);
+/// wrapped.extend([0, 1, 2]);
+/// wrapped.pop();
+/// wrapped.pop();
+/// wrapped.extend([3, 4]);
+///
+/// let contiguous: RingBuffer<_> = [2, 3, 4].into_iter().collect();
+/// assert_eq!(wrapped, contiguous);
+/// assert_ne!(wrapped, RingBuffer::from_iter([2, 3]));
+/// ```
+impl<T: PartialEq> PartialEq for RingBuffer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl<T: Eq> Eq for RingBuffer<T> {}
+impl<T> Default for RingBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+/// Pushes each item to the write end, reserving space for the lower bound of the size hint first.
+/// # Panics
+/// Panics if the buffer cannot grow to hold an item, as it has reached its maximum capacity.
+/// ```rust,should_panic
+/// # use circulate::RingBuffer;
+/// let mut buffer = RingBuffer::new();
+/// buffer.set_max_capacity(4);
+/// buffer.extend(0..4);
+/// ```
+impl<T> Extend<T> for RingBuffer<T> {
+    #[track_caller]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        // Reserving is only an optimization, so a buffer with a maximum capacity still takes what fits.
+        let _ = self.reserve(iter.size_hint().0);
+        for item in iter {
+            if self.push(item).is_err() {
+                panic!("RingBuffer reached its maximum capacity");
+            }
+        }
+    }
+}
+impl<'a, T: Copy + 'a> Extend<&'a T> for RingBuffer<T> {
+    #[track_caller]
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+impl<T> FromIterator<T> for RingBuffer<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut buffer = Self::new();
+        buffer.extend(iter);
+        buffer
     }
 }
 
@@ -357,21 +3710,165 @@ impl<T> IntoIterator for RingBuffer<T> {
         IntoIter(self)
     }
 }
+impl<'a, T> IntoIterator for &'a RingBuffer<T> {
+    type IntoIter = Iter<'a, T>;
+    type Item = &'a T;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl<'a, T> IntoIterator for &'a mut RingBuffer<T> {
+    type IntoIter = IterMut<'a, T>;
+    type Item = &'a mut T;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
 
+/// An iterator that moves items out of a [`RingBuffer`].
+///
+/// Skipped items are dropped in bulk, and any items left when the iterator is dropped are
+/// dropped exactly once.
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use circulate::RingBuffer;
+///
+/// static DROPPED: AtomicUsize = AtomicUsize::new(0);
+/// #[derive(Debug)]
+/// struct Counted(usize);
+/// impl Drop for Counted {
+///     fn drop(&mut self) {
+///         DROPPED.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// // Twelve items that wrap around the end of the allocation.
+/// let wrapped = || {
+///     let mut buffer = RingBuffer::with_capacity(16);
+///     for i in 0..10 {
+///         buffer.push(Counted(i)).unwrap();
+///         buffer.pop();
+///     }
+///     for i in 0..12 {
+///         buffer.push(Counted(i)).unwrap();
+///     }
+///     DROPPED.store(0, Ordering::Relaxed);
+///     buffer
+/// };
+///
+/// let mut items = wrapped().into_iter();
+/// assert_eq!(items.next().unwrap().0, 0);
+/// assert_eq!(items.nth(7).unwrap().0, 8);
+/// assert_eq!(DROPPED.load(Ordering::Relaxed), 9);
+/// drop(items);
+/// assert_eq!(DROPPED.load(Ordering::Relaxed), 12);
+///
+/// let mut items = wrapped().into_iter();
+/// items.truncate(4);
+/// assert_eq!(DROPPED.load(Ordering::Relaxed), 8);
+/// assert!(items.map(|item| item.0).eq(0..4));
+/// assert_eq!(DROPPED.load(Ordering::Relaxed), 12);
+///
+/// assert!(wrapped().into_iter().nth(20).is_none());
+/// assert_eq!(DROPPED.load(Ordering::Relaxed), 12);
+/// ```
 pub struct IntoIter<T>(RingBuffer<T>);
+impl<T> IntoIter<T> {
+    /// Keep only the next `len` items, dropping the rest in bulk.
+    ///
+    /// If dropping an item panics, the rest are still dropped.
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len)
+    }
+}
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.pop()
+        self.0.take_front()
     }
     fn count(self) -> usize {
         self.0.len()
     }
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.0.drop_front(n);
+        self.0.take_front()
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        let n = self.0.len().checked_sub(1)?;
+        self.nth(n)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
+    }
+}
+impl<T> ExactSizeIterator for IntoIter<T> {}
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.take_back()
+    }
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.0.truncate(self.0.len().saturating_sub(n));
+        self.0.take_back()
+    }
 }
+impl<T> core::iter::FusedIterator for IntoIter<T> {}
 
+/// An iterator that takes items from the read end of a [`RingBuffer`]. See [`RingBuffer::drain`].
+///
+/// Items are taken from the buffer as they are yielded, so the buffer is consistent throughout.
+/// Items left when the iterator is dropped are dropped in bulk, or stay in the buffer if the
+/// iterator is forgotten.
+pub struct Drain<'a, T> {
+    buffer: &'a mut RingBuffer<T>,
+    remaining: usize,
+}
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        self.buffer.take_front()
+    }
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let skipped = self.buffer.drop_front(n.min(self.remaining));
+        self.remaining -= skipped;
+        self.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+impl<T> core::iter::FusedIterator for Drain<'_, T> {}
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        self.buffer.drop_front(self.remaining);
+    }
+}
+
+/// An iterator over references to the items of a [`RingBuffer`], from the read end.
+///
+/// Like a slice iterator, it is covariant in `T` and may be shared with or sent to other threads
+/// when `T` is [`Sync`].
+/// ```rust
+/// use circulate::{Iter, RingBuffer};
+///
+/// fn shorten<'a, 'b>(iter: Iter<'a, &'static str>) -> Iter<'a, &'b str> {
+///     iter
+/// }
+///
+/// let mut buffer = RingBuffer::with_capacity(3);
+/// buffer.extend_from_slice(&["a", "b", "c"]).unwrap();
+/// let iter = shorten(buffer.iter());
+/// assert_eq!(format!("{iter:?}"), "Iter { len: 3 }");
+/// std::thread::scope(|scope| {
+///     let copy = iter.clone();
+///     scope.spawn(move || assert!(copy.eq(&["a", "b", "c"])));
+///     scope.spawn(|| assert_eq!(iter.len(), 3));
+/// });
+/// ```
 pub struct Iter<'a, T> {
     data: NonNull<T>,
-    mask: usize,
+    capacity: usize,
     len: usize,
     cursor: usize,
     _marker: PhantomData<&'a T>,
@@ -384,24 +3881,341 @@ impl<'a, T> Iterator for Iter<'a, T> {
         } else {
             // Safety:
             // - Capacity is guaranteed to be smaller than `isize::MAX`.
-            // - The cursor is masked by capacity ensuring the computed offset is in range.
+            // - The cursor is wrapped by capacity ensuring the computed offset is in range.
             // - The cursor is in range of the initialized `len`.
             unsafe {
-                let ptr = self.data.as_ptr().offset(self.cursor as isize);
+                let ptr = self.data.as_ptr().add(self.cursor);
                 self.len -= 1;
-                self.cursor = (self.cursor + 1) & self.mask;
+                self.cursor += 1;
+                if self.cursor == self.capacity {
+                    self.cursor = 0;
+                }
                 Some(&*ptr)
             }
         }
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+    fn count(self) -> usize {
+        self.len
+    }
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.skip_items(n);
+        self.next()
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        let n = self.len.checked_sub(1)?;
+        self.nth(n)
+    }
+    fn fold<B, F: FnMut(B, Self::Item) -> B>(self, init: B, f: F) -> B {
+        let (lhs, rhs) = self.as_slices();
+        lhs.iter().chain(rhs).fold(init, f)
+    }
+}
+impl<'a, T> Iter<'a, T> {
+    /// Get the next item without advancing the iterator.
+    ///
+    /// Unlike wrapping the iterator in [`core::iter::Peekable`], this keeps
+    /// [`Iter::as_slices`] available.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(3);
+    /// buffer.extend_from_slice(&[1, 2, 3]).unwrap();
+    ///
+    /// let mut iter = buffer.iter();
+    /// let mut runs = Vec::new();
+    /// while let Some(&first) = iter.next() {
+    ///     let mut len = 1;
+    ///     while iter.peek() == Some(&(first + len)) {
+    ///         iter.next();
+    ///         len += 1;
+    ///     }
+    ///     runs.push((first, len));
+    /// }
+    /// assert_eq!(runs, [(1, 3)]);
+    ///
+    /// let mut iter = buffer.iter();
+    /// assert_eq!(iter.peek(), Some(&1));
+    /// assert_eq!(iter.as_slices().0[0], 1);
+    /// assert_eq!(iter.nth(2), Some(&3));
+    /// assert_eq!(iter.peek(), None);
+    /// ```
+    pub fn peek(&self) -> Option<&'a T> {
+        self.as_slices().0.first()
+    }
+    /// Skip the next `n` items, or all of them if there are fewer.
+    fn skip_items(&mut self, n: usize) {
+        let n = n.min(self.len);
+        self.len -= n;
+        self.cursor += n;
+        if self.cursor >= self.capacity {
+            self.cursor -= self.capacity;
+        }
+    }
+    /// Get the items not yet visited as two slices, in order, such as to bulk-process the rest
+    /// of the items after handling the first few individually.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(7);
+    /// for i in 0..6 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// for _ in 0..4 {
+    ///     buffer.pop();
+    /// }
+    /// for i in 6..11 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    ///
+    /// let mut iter = buffer.iter();
+    /// iter.by_ref().take(3).for_each(drop);
+    /// let (lhs, rhs) = iter.as_slices();
+    /// assert!(lhs.iter().chain(rhs).eq(buffer.iter().skip(3)));
+    /// assert_eq!((lhs, rhs), (&[7][..], &[8, 9, 10][..]));
+    /// // The slices outlive the iterator.
+    /// drop(iter);
+    /// assert_eq!(lhs, [7]);
+    /// ```
+    pub fn as_slices(&self) -> (&'a [T], &'a [T]) {
+        let lhs_len = self.len.min(self.capacity - self.cursor);
+        // Safety: The remaining items start at the cursor and wrap to the start of the allocation.
+        unsafe {(
+            core::slice::from_raw_parts(self.data.as_ptr().add(self.cursor), lhs_len),
+            core::slice::from_raw_parts(self.data.as_ptr(), self.len - lhs_len),
+        )}
+    }
+}
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.len = self.len.checked_sub(1)?;
+        let mut index = self.cursor + self.len;
+        if index >= self.capacity {
+            index -= self.capacity;
+        }
+        // Safety: The item `len` items from the cursor, wrapping at the capacity, is initialized
+        // and is no longer in range of the iterator.
+        unsafe { Some(&*self.data.as_ptr().add(index)) }
+    }
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.len -= n.min(self.len);
+        self.next_back()
+    }
+}
+impl<T> core::iter::FusedIterator for Iter<'_, T> {}
+impl<T> core::fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Iter").field("len", &self.len).finish()
+    }
+}
+// Safety: The iterator only hands out shared references to the items, the same as `&[T]`.
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+// Safety: The iterator only hands out shared references to the items, the same as `&[T]`.
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data,
+            capacity: self.capacity,
+            len: self.len,
+            cursor: self.cursor,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// An iterator that endlessly repeats the values of a [`RingBuffer`].
+/// See [`RingBuffer::cycle`].
+pub struct Cycle<'a, T> {
+    original: Iter<'a, T>,
+    iter: Iter<'a, T>,
+}
+impl<'a, T> Iterator for Cycle<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.iter.next() {
+            None => {
+                self.iter = self.original.clone();
+                self.iter.next()
+            }
+            value => value,
+        }
+    }
+}
+
+/// An iterator taking items from the read end of a [`RingBuffer`] until one is rejected.
+/// See [`RingBuffer::pop_while_valid`].
+pub struct PopWhileValid<'a, T, F: FnMut(&T) -> bool> {
+    buffer: &'a mut RingBuffer<T>,
+    validate: F,
+    /// If an item was rejected, after which the iterator always ends.
+    done: bool,
+}
+impl<T, F: FnMut(&T) -> bool> Iterator for PopWhileValid<'_, T, F> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        if self.done {
+            return None;
+        }
+        match self.buffer.pop_checked(&mut self.validate) {
+            Ok(value) => value,
+            Err(ValidationFailed) => {
+                self.done = true;
+                None
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.done {
+            true => (0, Some(0)),
+            false => (0, Some(self.buffer.len())),
+        }
+    }
 }
+impl<T, F: FnMut(&T) -> bool> core::iter::FusedIterator for PopWhileValid<'_, T, F> {}
 
+/// An iterator over mutable references to the items of a [`RingBuffer`], from the read end.
+///
+/// Like a mutable slice iterator, it is invariant in `T`, so a longer lived item can never be
+/// overwritten with a shorter lived one.
+/// ```rust,compile_fail
+/// use circulate::IterMut;
+///
+/// fn shorten<'a, 'b>(iter: IterMut<'a, &'static str>) -> IterMut<'a, &'b str> {
+///     iter
+/// }
+/// ```
+/// It may be sent to another thread when `T` is [`Send`], and shared when `T` is [`Sync`].
+/// ```rust
+/// use circulate::RingBuffer;
+///
+/// let mut buffer = RingBuffer::with_capacity(3);
+/// buffer.extend_from_slice(&[1, 2, 3]).unwrap();
+/// let iter = buffer.iter_mut();
+/// std::thread::scope(|scope| {
+///     scope.spawn(move || iter.for_each(|value| *value *= 10));
+/// });
+/// assert!(buffer.iter().eq(&[10, 20, 30]));
+/// ```
 pub struct IterMut<'a, T> {
     data: NonNull<T>,
-    mask: usize,
+    capacity: usize,
     len: usize,
     cursor: usize,
-    _marker: PhantomData<&'a T>,
+    _marker: PhantomData<&'a mut T>,
+}
+// Safety: The iterator uniquely borrows the items it has not yet visited, the same as `&mut [T]`.
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+// Safety: Only the slices of the remaining items are reachable through a shared reference to the
+// iterator, the same as `&mut [T]`.
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+impl<T> IterMut<'_, T> {
+    /// Get the items not yet visited as two slices, in order.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(7);
+    /// for i in 0..6 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// buffer.pop();
+    /// buffer.pop();
+    /// for i in 6..9 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// let expected: Vec<_> = buffer.iter().copied().collect();
+    ///
+    /// let mut iter = buffer.iter_mut();
+    /// let (lhs, rhs) = iter.as_slice();
+    /// assert_eq!([lhs, rhs].concat(), expected);
+    /// assert!(!rhs.is_empty());
+    ///
+    /// let first = iter.next().unwrap();
+    /// let second = iter.next().unwrap();
+    /// let (lhs, rhs) = iter.as_slice();
+    /// assert_eq!([lhs, rhs].concat(), expected[2..]);
+    /// // Previously visited items remain mutable.
+    /// *first += rhs[0];
+    /// *second = 0;
+    /// assert!(buffer.iter().eq(&[2 + 8, 0, 4, 5, 6, 7, 8]));
+    /// ```
+    pub fn as_slice(&self) -> (&[T], &[T]) {
+        let lhs_len = self.len.min(self.capacity - self.cursor);
+        // Safety:
+        // - The `len` items from the cursor, wrapping at the capacity, are initialized.
+        // - Only items already visited have been handed out mutably, which these slices exclude.
+        unsafe {
+            (
+                core::slice::from_raw_parts(self.data.as_ptr().add(self.cursor), lhs_len),
+                core::slice::from_raw_parts(self.data.as_ptr(), self.len - lhs_len),
+            )
+        }
+    }
+}
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.len = self.len.checked_sub(1)?;
+        let mut index = self.cursor + self.len;
+        if index >= self.capacity {
+            index -= self.capacity;
+        }
+        // Safety: The item `len` items from the cursor, wrapping at the capacity, is initialized
+        // and is no longer in range of the iterator.
+        unsafe { Some(&mut *self.data.as_ptr().add(index)) }
+    }
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.len -= n.min(self.len);
+        self.next_back()
+    }
+}
+impl<T> core::iter::FusedIterator for IterMut<'_, T> {}
+impl<'a, T> IterMut<'a, T> {
+    /// Skip the next `n` items, or all of them if there are fewer.
+    fn skip_items(&mut self, n: usize) {
+        let n = n.min(self.len);
+        self.len -= n;
+        self.cursor += n;
+        if self.cursor >= self.capacity {
+            self.cursor -= self.capacity;
+        }
+    }
+    /// Get the items not yet visited as two mutable slices, in order, consuming the iterator so
+    /// that they cannot alias items it would yield.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::with_capacity(7);
+    /// for i in 0..6 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    /// for _ in 0..4 {
+    ///     buffer.pop();
+    /// }
+    /// for i in 6..11 {
+    ///     buffer.push(i).unwrap();
+    /// }
+    ///
+    /// let mut iter = buffer.iter_mut();
+    /// for item in iter.by_ref().take(3) {
+    ///     *item = 0;
+    /// }
+    /// let (lhs, rhs) = iter.into_slices();
+    /// assert_eq!((&*lhs, &*rhs), (&[7][..], &[8, 9, 10][..]));
+    /// lhs.iter_mut().chain(rhs).for_each(|item| *item *= 10);
+    /// assert!(buffer.iter().eq(&[0, 0, 0, 70, 80, 90, 100]));
+    /// ```
+    pub fn into_slices(self) -> (&'a mut [T], &'a mut [T]) {
+        let lhs_len = self.len.min(self.capacity - self.cursor);
+        // Safety:
+        // - The `len` items from the cursor, wrapping at the capacity, are initialized.
+        // - The iterator is consumed, so the slices are the only references to the items it
+        //   has not yet visited.
+        unsafe {(
+            core::slice::from_raw_parts_mut(self.data.as_ptr().add(self.cursor), lhs_len),
+            core::slice::from_raw_parts_mut(self.data.as_ptr(), self.len - lhs_len),
+        )}
+    }
 }
 impl<'a, T> Iterator for IterMut<'a, T> {
     type Item = &'a mut T;
@@ -411,17 +4225,400 @@ impl<'a, T> Iterator for IterMut<'a, T> {
         } else {
             // Safety:
             // - Capacity is guaranteed to be smaller than `isize::MAX`.
-            // - The cursor is masked by capacity ensuring the computed offset is in range.
+            // - The cursor is wrapped by capacity ensuring the computed offset is in range.
             // - The cursor is in range of the initialized `len`.
             unsafe {
-                let ptr = self.data.as_ptr().offset(self.cursor as isize);
+                let ptr = self.data.as_ptr().add(self.cursor);
                 self.len -= 1;
-                self.cursor = (self.cursor + 1) & self.mask;
+                self.cursor += 1;
+                if self.cursor == self.capacity {
+                    self.cursor = 0;
+                }
                 Some(&mut *ptr)
             }
         }
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+    fn count(self) -> usize {
+        self.len
+    }
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.skip_items(n);
+        self.next()
+    }
+    fn last(mut self) -> Option<Self::Item> {
+        let n = self.len.checked_sub(1)?;
+        self.nth(n)
+    }
+    fn fold<B, F: FnMut(B, Self::Item) -> B>(self, init: B, f: F) -> B {
+        let (lhs, rhs) = self.into_slices();
+        lhs.iter_mut().chain(rhs).fold(init, f)
+    }
+}
+
+/// The spare capacity of a [`RingBuffer`], tracking how many items have been initialized from
+/// its start. See [`RingBuffer::with_spare_capacity`].
+pub struct SpareCapacity<'a, T> {
+    lhs: &'a mut [MaybeUninit<T>],
+    rhs: &'a mut [MaybeUninit<T>],
+    /// The number of items initialized from the start of `lhs`, continuing in to `rhs`.
+    filled: usize,
+}
+impl<T> SpareCapacity<'_, T> {
+    /// Get the total number of items that fit in the spare capacity.
+    pub const fn capacity(&self) -> usize {
+        self.lhs.len() + self.rhs.len()
+    }
+    /// Get the number of items initialized so far.
+    pub const fn filled(&self) -> usize {
+        self.filled
+    }
+    /// Get the number of items that may still be initialized.
+    pub const fn remaining(&self) -> usize {
+        self.capacity() - self.filled
+    }
+    /// Initialize the next item with `value`, or return it if the spare capacity is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let slot = match self.filled.checked_sub(self.lhs.len()) {
+            None => &mut self.lhs[self.filled],
+            Some(index) => match self.rhs.get_mut(index) {
+                Some(slot) => slot,
+                None => return Err(value),
+            },
+        };
+        slot.write(value);
+        self.filled += 1;
+        Ok(())
+    }
+    /// Get the items that are not yet initialized as two slices, in order.
+    ///
+    /// Writing to the slices does not count the items as initialized, see
+    /// [`SpareCapacity::assume_filled`].
+    pub fn unfilled_mut(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        match self.filled.checked_sub(self.lhs.len()) {
+            None => (&mut self.lhs[self.filled..], &mut *self.rhs),
+            Some(index) => (&mut [], &mut self.rhs[index..]),
+        }
+    }
+    /// Count the next `count` items as initialized, after writing them through
+    /// [`SpareCapacity::unfilled_mut`].
+    /// # Panics
+    /// Panics if `count` exceeds the remaining capacity, rather than committing items that were
+    /// never allocated.
+    /// ```rust,should_panic
+    /// # use circulate::RingBuffer;
+    /// let mut buffer = RingBuffer::<u8>::with_capacity(4);
+    /// buffer.with_spare_capacity(|spare| {
+    ///     let (lhs, rhs) = spare.unfilled_mut();
+    ///     lhs.iter_mut().chain(rhs).for_each(|slot| { slot.write(0); });
+    ///     unsafe { spare.assume_filled(spare.remaining() + 1) };
+    /// });
+    /// ```
+    /// # Safety
+    /// The next `count` items must have been initialized.
+    #[track_caller]
+    pub unsafe fn assume_filled(&mut self, count: usize) {
+        let remaining = self.remaining();
+        assert!(count <= remaining, "cannot fill {count} items when only {remaining} remain");
+        self.filled += count;
+    }
+    /// Drop the items initialized past the first `len`.
+    fn truncate(&mut self, len: usize) {
+        let (filled, lhs_len) = (self.filled, self.lhs.len());
+        let len = len.min(filled);
+        // Note: The items are no longer counted before they are dropped, so a panic cannot drop them twice.
+        self.filled = len;
+        let lhs = &mut self.lhs[len.min(lhs_len)..filled.min(lhs_len)];
+        let rhs = &mut self.rhs[len.saturating_sub(lhs_len)..filled.saturating_sub(lhs_len)];
+        // Safety: The items of both slices were initialized, and are no longer counted as filled.
+        unsafe { drop_runs(lhs as *mut [MaybeUninit<T>] as *mut [T], rhs as *mut [MaybeUninit<T>] as *mut [T]) };
+    }
+}
+impl<T: Copy> SpareCapacity<'_, T> {
+    /// Initialize as many of the next items as fit by copying them from `slice`.
+    /// Returns the number of items copied.
+    pub fn extend_from_slice(&mut self, slice: &[T]) -> usize {
+        let (lhs, rhs) = self.unfilled_mut();
+        let lhs_len = slice.len().min(lhs.len());
+        let rhs_len = (slice.len() - lhs_len).min(rhs.len());
+        // Safety:
+        // - `lhs` and `rhs` are valid for at least `lhs_len` and `rhs_len` writes respectively.
+        // - `slice` cannot alias the spare capacity as it is mutably borrowed.
+        // - `T: Copy` so the items can be duplicated bitwise.
+        unsafe {
+            lhs.as_mut_ptr().cast::<T>().copy_from_nonoverlapping(slice.as_ptr(), lhs_len);
+            rhs.as_mut_ptr().cast::<T>().copy_from_nonoverlapping(slice.as_ptr().add(lhs_len), rhs_len);
+        }
+        self.filled += lhs_len + rhs_len;
+        lhs_len + rhs_len
+    }
+}
+impl SpareCapacity<'_, u8> {
+    /// Perform a single read from `reader` in to the items that are not yet initialized.
+    /// Returns the number of bytes read.
+    pub fn read_from<R: Read + ?Sized>(&mut self, reader: &mut R) -> Result<usize, R::Error> {
+        let (lhs, rhs) = self.unfilled_mut();
+        let parts = if rhs.is_empty() { 1 } else { 2 };
+        let len = lhs.len() + rhs.len();
+        let count = reader.read_vectored(&mut [lhs.into(), rhs.into()][..parts])?.min(len);
+        // The reader initialized the bytes it read.
+        self.filled += count;
+        Ok(count)
+    }
+}
+
+/// See [`RingBuffer::display_lossy`].
+struct DisplayLossy<'a>(&'a RingBuffer<u8>);
+impl core::fmt::Display for DisplayLossy<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use core::fmt::Write;
+        let (lhs, mut rhs) = self.0.as_slices();
+        let tail = write_lossy(f, lhs)?;
+        if !tail.is_empty() {
+            // Complete the character split across the wrap with the bytes that follow it.
+            let width = match tail[0].leading_ones() {
+                len @ 2..=4 => len as usize,
+                _ => unreachable!("an incomplete sequence starts with a leading byte"),
+            };
+            let mut bytes = [0; 4];
+            let needed = (width - tail.len()).min(rhs.len());
+            bytes[..tail.len()].copy_from_slice(tail);
+            bytes[tail.len()..tail.len() + needed].copy_from_slice(&rhs[..needed]);
+            match core::str::from_utf8(&bytes[..tail.len() + needed]) {
+                Ok(text) => {
+                    f.write_str(text)?;
+                    rhs = &rhs[needed..];
+                }
+                Err(error) => {
+                    // Only the bytes that were part of the invalid sequence are replaced.
+                    f.write_char(char::REPLACEMENT_CHARACTER)?;
+                    let invalid = error.error_len().unwrap_or(tail.len() + needed);
+                    rhs = &rhs[invalid - tail.len()..];
+                }
+            }
+        }
+        if !write_lossy(f, rhs)?.is_empty() {
+            f.write_char(char::REPLACEMENT_CHARACTER)?;
+        }
+        Ok(())
+    }
+}
+/// Write `bytes` as UTF-8, replacing invalid sequences, and return any incomplete sequence at
+/// the end without writing it.
+fn write_lossy<'a>(f: &mut core::fmt::Formatter<'_>, mut bytes: &'a [u8]) -> Result<&'a [u8], core::fmt::Error> {
+    loop {
+        match core::str::from_utf8(bytes) {
+            Ok(text) => {
+                f.write_str(text)?;
+                return Ok(&[]);
+            }
+            Err(error) => {
+                let (valid, rest) = bytes.split_at(error.valid_up_to());
+                // Safety: The bytes up to `valid_up_to` are valid UTF-8.
+                f.write_str(unsafe { core::str::from_utf8_unchecked(valid) })?;
+                let Some(invalid) = error.error_len() else {
+                    return Ok(rest);
+                };
+                core::fmt::Write::write_char(f, char::REPLACEMENT_CHARACTER)?;
+                bytes = &rest[invalid..];
+            }
+        }
+    }
+}
+
+/// What a [`RingBuffer`] does when it fails to allocate while growing.
+/// See [`RingBuffer::set_alloc_failure_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AllocFailure {
+    /// Abort the process through [`handle_alloc_error`](alloc::alloc::handle_alloc_error).
+    #[default]
+    Abort,
+    /// Keep the current capacity and fail the operation that needed to grow the buffer.
+    Saturate,
+}
+
+/// Picks the capacity a [`RingBuffer`] grows to. See [`RingBuffer::set_growth_strategy`].
+///
+/// The capacity picked must be at least `required`, otherwise growing fails with a
+/// [`CapacityError`] rather than trusting it. A capacity larger than any allocation could hold is
+/// limited to the largest that can, and power of two buffers round it up to a power of two.
+/// ```rust
+/// use circulate::{GrowthStrategy, RingBuffer};
+///
+/// /// Grows to a whole number of pages.
+/// struct PageAligned {
+///     page: usize,
+/// }
+/// impl GrowthStrategy for PageAligned {
+///     fn next_capacity(&self, _current: usize, required: usize) -> usize {
+///         required.checked_next_multiple_of(self.page).unwrap_or(usize::MAX)
+///     }
+/// }
+///
+/// let mut buffer = RingBuffer::<u8>::with_exact_capacity(100);
+/// buffer.set_growth_strategy(&PageAligned { page: 4096 });
+/// let mut capacities = vec![buffer.capacity()];
+/// for byte in 0..10_000u32 {
+///     buffer.push(byte as u8).unwrap();
+///     if capacities.last() != Some(&buffer.capacity()) {
+///         capacities.push(buffer.capacity());
+///     }
+/// }
+/// assert_eq!(capacities, [101, 4096, 8192, 12288]);
+///
+/// // A buffer with a power of two capacity rounds what is picked up.
+/// let mut buffer = RingBuffer::<u8>::new();
+/// buffer.set_growth_strategy(&PageAligned { page: 3000 });
+/// buffer.reserve(1).unwrap();
+/// assert_eq!(buffer.capacity(), 4096);
+/// buffer.reserve(5000).unwrap();
+/// assert_eq!(buffer.capacity(), 8192);
+///
+/// // A strategy picking too little fails to grow the buffer.
+/// struct Stuck;
+/// impl GrowthStrategy for Stuck {
+///     fn next_capacity(&self, current: usize, _required: usize) -> usize {
+///         current
+///     }
+/// }
+/// let mut buffer = RingBuffer::with_capacity(3);
+/// buffer.set_growth_strategy(&Stuck);
+/// for i in 0..3 {
+///     buffer.push(i).unwrap();
+/// }
+/// assert_eq!(buffer.push(3), Err(3));
+/// let error = buffer.reserve(1).unwrap_err();
+/// assert!(error.strategy_failed());
+/// assert_eq!((error.requested(), error.max()), (5, 4));
+/// ```
+pub trait GrowthStrategy: Sync {
+    /// Get the capacity to grow to from a capacity of `current` so that at least `required` slots,
+    /// including the one that is always vacant, are available.
+    fn next_capacity(&self, current: usize, required: usize) -> usize;
+}
+
+/// The default [`GrowthStrategy`], doubling the capacity or growing to the required capacity if
+/// that is larger.
+/// ```rust
+/// use circulate::{Doubling, RingBuffer};
+/// // The capacities a buffer takes as items are added in chunks of `chunk` and some are popped.
+/// let capacities = |mut buffer: RingBuffer<u32>, chunk: usize, doubling: bool| {
+///     if doubling {
+///         buffer.set_growth_strategy(&Doubling);
+///     }
+///     let mut capacities = vec![buffer.capacity()];
+///     for i in 0..60 {
+///         let items: Vec<u32> = (0..(chunk * (i % 3 + 1)) as u32).collect();
+///         buffer.extend_from_slice(&items).unwrap();
+///         if i % 4 == 0 {
+///             for _ in 0..chunk {
+///                 buffer.pop();
+///             }
+///         }
+///         if capacities.last() != Some(&buffer.capacity()) {
+///             capacities.push(buffer.capacity());
+///         }
+///     }
+///     capacities
+/// };
+/// for doubling in [false, true] {
+///     assert_eq!(capacities(RingBuffer::new(), 1, doubling), [0, 2, 4, 8, 16, 32, 64, 128]);
+///     assert_eq!(capacities(RingBuffer::with_capacity(5), 7, doubling), [8, 16, 64, 128, 256, 512, 1024]);
+///     assert_eq!(capacities(RingBuffer::with_exact_capacity(5), 1, doubling), [6, 12, 24, 48, 96, 192]);
+///     assert_eq!(
+///         capacities(RingBuffer::with_exact_capacity(5), 37, doubling),
+///         [6, 38, 76, 186, 372, 744, 1488, 2976, 5952],
+///     );
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Doubling;
+impl GrowthStrategy for Doubling {
+    fn next_capacity(&self, current: usize, required: usize) -> usize {
+        required.max(current.saturating_mul(2))
+    }
+}
+
+/// The error returned when a [`RingBuffer`] would have to grow beyond its maximum capacity, failed
+/// to allocate while growing, or its [`GrowthStrategy`] picked too small a capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError {
+    requested: usize,
+    max: usize,
+    reason: CapacityErrorReason,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CapacityErrorReason {
+    Max,
+    Allocation,
+    Strategy,
+}
+impl CapacityError {
+    /// The capacity the buffer needed to grow to.
+    pub const fn requested(&self) -> usize {
+        self.requested
+    }
+    /// The maximum capacity of the buffer.
+    /// If allocating failed, or the growth strategy picked too small a capacity, this is the
+    /// capacity the buffer stayed at.
+    pub const fn max(&self) -> usize {
+        self.max
+    }
+    /// Returns if the buffer failed to allocate rather than reaching its maximum capacity.
+    pub const fn allocation_failed(&self) -> bool {
+        matches!(self.reason, CapacityErrorReason::Allocation)
+    }
+    /// Returns if the growth strategy picked a capacity less than [`CapacityError::requested`].
+    pub const fn strategy_failed(&self) -> bool {
+        matches!(self.reason, CapacityErrorReason::Strategy)
+    }
+}
+impl core::fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.reason {
+            CapacityErrorReason::Max => write!(f, "capacity of {} exceeds the maximum of {}", self.requested, self.max),
+            CapacityErrorReason::Allocation => write!(f, "failed to allocate a capacity of {}", self.requested),
+            CapacityErrorReason::Strategy => write!(f, "growth strategy picked less than the required capacity of {}", self.requested),
+        }
+    }
+}
+impl core::error::Error for CapacityError {}
+
+/// The error returned when the item at the front of a [`RingBuffer`] was rejected by a validator,
+/// and so was left in the buffer. See [`RingBuffer::pop_checked`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationFailed;
+impl core::fmt::Display for ValidationFailed {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("the item at the front of the buffer failed validation")
+    }
+}
+impl core::error::Error for ValidationFailed {}
+
+/// The error returned when a [`RingBuffer`] holds fewer items than were requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsufficientElements {
+    available: usize,
+    requested: usize,
+}
+impl InsufficientElements {
+    /// The number of items the buffer held.
+    pub const fn available(&self) -> usize {
+        self.available
+    }
+    /// The number of items that were requested.
+    pub const fn requested(&self) -> usize {
+        self.requested
+    }
+}
+impl core::fmt::Display for InsufficientElements {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "requested {} items but only {} are available", self.requested, self.available)
+    }
 }
+impl core::error::Error for InsufficientElements {}
 
 const fn capacity_overflow() -> ! {
     panic!("capacity overflow")
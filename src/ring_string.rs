@@ -0,0 +1,116 @@
+use crate::RingBuffer;
+
+/// Keeps the most recent text pushed to it, up to a number of bytes, evicting whole characters
+/// from the front.
+///
+/// This suits rolling logs, such as keeping the end of a program's log in memory to attach to
+/// crash reports. The text is always valid UTF-8 and never starts part way through a character.
+/// ```rust
+/// use core::fmt::Write;
+/// use circulate::RingString;
+///
+/// let mut log = RingString::new(16);
+/// writeln!(log, "started").unwrap();
+/// writeln!(log, "héllo wörld").unwrap();
+/// // The oldest text is evicted, leaving no more than 16 bytes.
+/// assert_eq!(log.as_contiguous_str(), "d\nhéllo wörld\n");
+/// assert!(log.lines().eq(["d", "héllo wörld"]));
+///
+/// // Pushing text of 1 to 4 bytes per character never splits one, and keeps the newest text.
+/// let mut log = RingString::new(64);
+/// let mut all = String::new();
+/// for i in 0..500 {
+///     let text = ["a", "é", "€", "🦀", "line\n"][i % 5].repeat(i % 7);
+///     log.push_str(&text);
+///     all.push_str(&text);
+///     let kept = log.as_contiguous_str();
+///     assert!(all.ends_with(kept));
+///     assert!(kept.len() <= 64);
+///     // At most 3 bytes of a character are evicted beyond what was needed.
+///     assert!(kept.len() >= all.len().min(64 - 3));
+/// }
+///
+/// // Text longer than the limit keeps only its end.
+/// log.push_str(&"🦀".repeat(100));
+/// assert_eq!(log.as_contiguous_str(), "🦀".repeat(16));
+/// ```
+pub struct RingString {
+    buffer: RingBuffer<u8>,
+    max_len: usize,
+}
+impl RingString {
+    /// Create an empty string holding at most `max_len` bytes of text.
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            buffer: RingBuffer::with_capacity(max_len),
+            max_len,
+        }
+    }
+
+    /// Append `text`, first evicting the oldest characters that no longer fit.
+    ///
+    /// Just enough bytes are evicted for the text to fit, and then the rest of a partly evicted
+    /// character. If `text` is longer than the limit only its end is kept.
+    pub fn push_str(&mut self, text: &str) {
+        let text = match text.len().checked_sub(self.max_len) {
+            Some(excess) => {
+                self.buffer.clear();
+                // Note: The end of the text is a boundary, so this finds one.
+                let start = (excess..=text.len()).find(|&index| text.is_char_boundary(index)).unwrap_or(text.len());
+                &text[start..]
+            }
+            None => text,
+        };
+        let mut excess = (self.buffer.len() + text.len()).saturating_sub(self.max_len);
+        if excess != 0 {
+            self.buffer.evict_while(|byte| match excess {
+                0 => is_continuation(*byte),
+                _ => {
+                    excess -= 1;
+                    true
+                }
+            });
+        }
+        if self.buffer.extend_from_slice(text.as_bytes()).is_err() {
+            unreachable!()
+        }
+    }
+    /// Get the text, first moving it to be contiguous in memory.
+    pub fn as_contiguous_str(&mut self) -> &str {
+        // Safety: Only whole characters are pushed and evicted, so the bytes are valid UTF-8.
+        unsafe { core::str::from_utf8_unchecked(self.buffer.make_contiguous()) }
+    }
+    /// Iterate over the lines of the text, as by [`str::lines`], first moving it to be contiguous
+    /// in memory.
+    pub fn lines(&mut self) -> core::str::Lines<'_> {
+        self.as_contiguous_str().lines()
+    }
+    /// Get the length of the text in bytes.
+    pub const fn len(&self) -> usize {
+        self.buffer.len()
+    }
+    /// Returns if there is no text.
+    pub const fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+    /// Get the most bytes of text kept.
+    pub const fn max_len(&self) -> usize {
+        self.max_len
+    }
+    /// Remove all the text.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+impl core::fmt::Write for RingString {
+    /// Append `text`, evicting the oldest characters that no longer fit. Never fails.
+    fn write_str(&mut self, text: &str) -> core::fmt::Result {
+        self.push_str(text);
+        Ok(())
+    }
+}
+
+/// Returns if `byte` continues a UTF-8 encoded character rather than starting one.
+const fn is_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
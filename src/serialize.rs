@@ -0,0 +1,60 @@
+use core::{fmt, marker::PhantomData, mem::size_of};
+use serde::{de::{SeqAccess, Visitor}, Deserialize, Deserializer, Serialize, Serializer};
+use crate::RingBuffer;
+
+/// The most memory reserved up front for the items of a sequence, as its length comes from the
+/// input and may not be trusted.
+const MAX_PREALLOCATION: usize = 1024 * 1024;
+
+/// Serialized as a sequence of the items in order from the read end, the same as a slice holding
+/// them. Where the items lie in the allocation, and settings such as the maximum capacity, are
+/// not serialized.
+/// ```rust
+/// use circulate::RingBuffer;
+/// let mut buffer = RingBuffer::with_capacity(4);
+/// buffer.extend_from_slice(&[1, 2, 3]).unwrap();
+/// buffer.pop();
+/// buffer.extend_from_slice(&[4]).unwrap();
+/// assert_eq!(serde_json::to_string(&buffer).unwrap(), "[2,3,4]");
+/// ```
+impl<T: Serialize> Serialize for RingBuffer<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Deserialized from a sequence of items, pushed in order so that the first is at the read end.
+///
+/// Space is reserved up front for the number of items the format reports, and the buffer grows
+/// as needed from there. The buffer has no maximum capacity, so set one afterwards to bound it
+/// again.
+/// ```rust
+/// use circulate::RingBuffer;
+/// let mut buffer: RingBuffer<u32> = serde_json::from_str("[1, 2, 3]").unwrap();
+/// assert!(buffer.iter().eq(&[1, 2, 3]));
+/// assert_eq!(buffer.capacity(), 4);
+/// buffer.set_max_capacity(4);
+/// assert_eq!(buffer.push(4), Err(4));
+/// ```
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for RingBuffer<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(ItemsVisitor(PhantomData))
+    }
+}
+
+struct ItemsVisitor<T>(PhantomData<T>);
+impl<'de, T: Deserialize<'de>> Visitor<'de> for ItemsVisitor<T> {
+    type Value = RingBuffer<T>;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let hint = seq.size_hint().unwrap_or(0).min(MAX_PREALLOCATION / size_of::<T>().max(1));
+        let mut buffer = RingBuffer::with_capacity(hint);
+        while let Some(item) = seq.next_element()? {
+            // Note: The buffer is unbounded so pushing cannot fail.
+            let _ = buffer.push(item);
+        }
+        Ok(buffer)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{Iter, RingBuffer};
+
+/// Keeps the most recent `n` values pushed to it, evicting the oldest.
+/// ```rust
+/// use circulate::SlidingWindow;
+/// let mut window = SlidingWindow::with_statistics(3);
+/// for sample in [1.0, 2.0, 3.0, 4.0] {
+///     window.push(sample);
+/// }
+/// assert_eq!(window.oldest(), Some(&2.0));
+/// assert_eq!(window.latest(), Some(&4.0));
+/// assert_eq!(window.sum(), 9.0);
+/// assert_eq!(window.mean(), Some(3.0));
+/// ```
+pub struct SlidingWindow<T> {
+    buffer: RingBuffer<T>,
+    window: usize,
+    statistics: Option<Statistics<T>>,
+}
+/// A running sum that is updated as values enter and leave the window.
+struct Statistics<T> {
+    sum: f64,
+    /// The number of updates applied to `sum` since it was last recomputed.
+    updates: usize,
+    value: fn(&T) -> f64,
+}
+impl<T> SlidingWindow<T> {
+    /// Create a window holding at most `n` values.
+    pub fn new(n: usize) -> Self {
+        Self {
+            buffer: RingBuffer::with_capacity(n),
+            window: n,
+            statistics: None,
+        }
+    }
+
+    /// Push a value, returning the oldest value if it was evicted to make room.
+    pub fn push(&mut self, value: T) -> Option<T> {
+        if self.window == 0 {
+            return Some(value);
+        }
+        let evicted = if self.buffer.len() == self.window {
+            self.buffer.pop()
+        } else {
+            None
+        };
+        if let Some(statistics) = &mut self.statistics {
+            statistics.sum += (statistics.value)(&value);
+            if let Some(evicted) = &evicted {
+                statistics.sum -= (statistics.value)(evicted);
+            }
+            statistics.updates += 1;
+        }
+        // The buffer was allocated with space for the whole window up front and is unbounded.
+        if self.buffer.push(value).is_err() {
+            unreachable!()
+        }
+        self.recompute_if_drifted();
+        evicted
+    }
+
+    /// Drop the oldest values while `predicate` holds, stopping at the first value it does not
+    /// hold for. Returns the number of values dropped.
+    ///
+    /// This suits windows bounded by time as well as count, where values are pushed in time
+    /// order and those older than a cutoff are evicted before reading the window. If `predicate`
+    /// panics, the running statistics still match the values left.
+    /// ```rust
+    /// use circulate::SlidingWindow;
+    ///
+    /// // Samples of (seconds, value), keeping those from the last 60 seconds.
+    /// let mut window = SlidingWindow::new(100);
+    /// for (time, value) in [(0, 1.0), (30, 2.0), (45, 3.0), (70, 4.0), (95, 5.0)] {
+    ///     window.push((time, value));
+    ///     window.evict_while(|(sampled, _)| sampled + 60 <= time);
+    /// }
+    /// assert!(window.iter().map(|(time, _)| *time).eq([45, 70, 95]));
+    ///
+    /// // The running sum follows evictions.
+    /// let mut window = SlidingWindow::with_statistics(4);
+    /// for value in [1u8, 2, 3, 4, 5, 6] {
+    ///     window.push(value);
+    /// }
+    /// assert_eq!(window.evict_while(|value| *value < 5), 2);
+    /// assert_eq!(window.sum(), 11.0);
+    /// assert_eq!(window.evict_while(|_| true), 2);
+    /// assert_eq!(window.mean(), None);
+    /// ```
+    pub fn evict_while<F: FnMut(&T) -> bool>(&mut self, mut predicate: F) -> usize {
+        let statistics = &mut self.statistics;
+        let evicted = self.buffer.evict_while(|value| {
+            let evict = predicate(value);
+            if let Some(statistics) = statistics.as_mut().filter(|_| evict) {
+                statistics.sum -= (statistics.value)(value);
+                statistics.updates += 1;
+            }
+            evict
+        });
+        self.recompute_if_drifted();
+        evicted
+    }
+
+    /// Once every value in the window has been replaced, the running sum has accumulated as much
+    /// rounding error as recomputing it would cost, so recompute it from scratch.
+    fn recompute_if_drifted(&mut self) {
+        if let Some(statistics) = &mut self.statistics {
+            if statistics.updates >= self.window {
+                statistics.sum = self.buffer.iter().map(statistics.value).sum();
+                statistics.updates = 0;
+            }
+        }
+    }
+
+    /// Get the number of values in the window.
+    pub const fn len(&self) -> usize {
+        self.buffer.len()
+    }
+    /// Returns if no values have been pushed.
+    pub const fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+    /// Get the maximum number of values kept.
+    pub const fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Returns an iterator over the values from oldest to newest.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.buffer.iter()
+    }
+    /// Get the most recently pushed value.
+    pub fn latest(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|index| self.buffer.get(index))
+    }
+    /// Get the oldest value still in the window.
+    pub fn oldest(&self) -> Option<&T> {
+        self.buffer.get(0)
+    }
+}
+impl<T: Copy + Into<f64>> SlidingWindow<T> {
+    /// Create a window holding at most `n` values that maintains a running sum, making
+    /// [`SlidingWindow::sum`] and [`SlidingWindow::mean`] O(1).
+    ///
+    /// To bound floating point drift the sum is recomputed after every `n` pushes.
+    /// ```rust
+    /// use circulate::SlidingWindow;
+    /// let mut window = SlidingWindow::with_statistics(7);
+    /// for i in 0..1000u32 {
+    ///     window.push(((i * 7919) % 1013) as f32 * 0.1);
+    ///     let naive: f64 = window.iter().map(|v| f64::from(*v)).sum();
+    ///     assert!((window.sum() - naive).abs() < 1e-9);
+    /// }
+    /// ```
+    pub fn with_statistics(n: usize) -> Self {
+        Self {
+            statistics: Some(Statistics {
+                sum: 0.0,
+                updates: 0,
+                value: |value| (*value).into(),
+            }),
+            ..Self::new(n)
+        }
+    }
+
+    /// Get the sum of the values in the window.
+    /// This is O(1) if the window was created with [`SlidingWindow::with_statistics`].
+    pub fn sum(&self) -> f64 {
+        match &self.statistics {
+            Some(statistics) => statistics.sum,
+            None => self.iter().map(|value| (*value).into()).sum(),
+        }
+    }
+    /// Get the mean of the values in the window, or [`None`] if it is empty.
+    /// This is O(1) if the window was created with [`SlidingWindow::with_statistics`].
+    pub fn mean(&self) -> Option<f64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.sum() / self.len() as f64)
+        }
+    }
+}
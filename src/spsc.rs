@@ -0,0 +1,222 @@
+use core::ptr::NonNull;
+use crate::{sync::{Arc, AtomicUsize, Ordering}, RingBuffer};
+
+impl<T> RingBuffer<T> {
+    /// Split the buffer in to a [`Producer`] and a [`Consumer`] that may be used from different
+    /// threads, or from an interrupt handler and the main loop, without locking.
+    ///
+    /// The items already in the buffer are kept, and the capacity is fixed at that of the buffer,
+    /// so reserve space before splitting. Pushing and popping never allocate. The allocation is
+    /// freed, and any items left in it dropped, once both halves have been dropped.
+    /// ```rust
+    /// use std::thread;
+    /// use circulate::RingBuffer;
+    ///
+    /// let mut buffer = RingBuffer::with_capacity(64);
+    /// buffer.push(0u32).unwrap();
+    /// let (mut producer, mut consumer) = buffer.split();
+    /// let pusher = thread::spawn(move || {
+    ///     for i in 1..1000 {
+    ///         let mut item = i;
+    ///         while let Err(returned) = producer.push(item) {
+    ///             item = returned;
+    ///             thread::yield_now();
+    ///         }
+    ///     }
+    /// });
+    /// let mut expected = 0;
+    /// while expected < 1000 {
+    ///     match consumer.pop() {
+    ///         Some(item) => {
+    ///             assert_eq!(item, expected);
+    ///             expected += 1;
+    ///         }
+    ///         None => thread::yield_now(),
+    ///     }
+    /// }
+    /// pusher.join().unwrap();
+    /// assert_eq!(consumer.peek(), None);
+    /// ```
+    pub fn split(mut self) -> (Producer<T>, Consumer<T>) {
+        let (data, read, write) = self.forget_items();
+        let shared = Arc::new(Shared {
+            data,
+            capacity: self.capacity(),
+            read: AtomicUsize::new(read),
+            write: AtomicUsize::new(write),
+            _buffer: self,
+        });
+        (Producer { shared: shared.clone() }, Consumer { shared })
+    }
+}
+
+/// The state shared by a [`Producer`] and [`Consumer`].
+///
+/// # Safety
+/// Each slot is owned by exactly one side at a time. Slots from the read cursor up to the write
+/// cursor belong to the consumer and the rest to the producer. Each side only ever stores to its
+/// own cursor, after it has finished with the slot it is handing over, with release ordering that
+/// the other side acquires before touching the slot.
+struct Shared<T> {
+    data: NonNull<T>,
+    capacity: usize,
+    read: AtomicUsize,
+    write: AtomicUsize,
+    /// Owns the allocation, but none of the items in it, which are tracked by the cursors.
+    _buffer: RingBuffer<T>,
+}
+impl<T> Shared<T> {
+    #[inline]
+    fn next(&self, index: usize) -> usize {
+        if index + 1 >= self.capacity {
+            0
+        } else {
+            index + 1
+        }
+    }
+    #[inline]
+    fn len(&self, read: usize, write: usize) -> usize {
+        if read <= write {
+            write - read
+        } else {
+            self.capacity - (read - write)
+        }
+    }
+    #[inline]
+    fn slot(&self, index: usize) -> *mut T {
+        // Safety: Cursors are always less than the capacity, so within the allocation.
+        unsafe { self.data.as_ptr().add(index) }
+    }
+}
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        // Note: Both halves have been dropped, so they have finished with the items.
+        let mut read = self.read.load(Ordering::Relaxed);
+        let write = self.write.load(Ordering::Relaxed);
+        while read != write {
+            // Safety: Slots from the read cursor up to the write cursor are initialized.
+            unsafe { self.slot(read).drop_in_place() };
+            read = self.next(read);
+        }
+        // The buffer frees the allocation once it is dropped after this.
+    }
+}
+
+/// The pushing half of a [`RingBuffer`] split with [`RingBuffer::split`].
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+// Safety: Items are moved to the consumer, which is sound if they can be sent.
+unsafe impl<T: Send> Send for Producer<T> {}
+impl<T> Producer<T> {
+    /// Push an item, handing it back if the buffer is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let shared = &*self.shared;
+        let write = shared.write.load(Ordering::Relaxed);
+        let next = shared.next(write);
+        // Acquire the consumer's release of the slot.
+        if next == shared.read.load(Ordering::Acquire) {
+            return Err(value);
+        }
+        // Safety: The slot at the write cursor is vacant and owned by the producer.
+        unsafe { shared.slot(write).write(value) };
+        // Release the item to the consumer.
+        shared.write.store(next, Ordering::Release);
+        Ok(())
+    }
+    /// Push an item if there is space for it, handing it back if the buffer is full, as
+    /// [`RingBuffer::try_push`] does. The buffer cannot grow once split, so this is the same as
+    /// [`Producer::push`].
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        self.push(value)
+    }
+    /// Returns if the buffer currently has no space for another item.
+    pub fn is_full(&self) -> bool {
+        let shared = &*self.shared;
+        shared.next(shared.write.load(Ordering::Relaxed)) == shared.read.load(Ordering::Acquire)
+    }
+    /// Get the total number of slots, one of which always remains vacant, as with
+    /// [`RingBuffer::capacity`].
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+/// The popping half of a [`RingBuffer`] split with [`RingBuffer::split`].
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+}
+// Safety: Items are moved from the producer, and only borrowed by the consumer, which is sound
+// if they can be sent.
+unsafe impl<T: Send> Send for Consumer<T> {}
+impl<T> Consumer<T> {
+    /// Take the oldest item, or return [`None`] if the buffer is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let shared = &*self.shared;
+        let read = shared.read.load(Ordering::Relaxed);
+        // Acquire the producer's release of the item.
+        if read == shared.write.load(Ordering::Acquire) {
+            return None;
+        }
+        // Safety: The slot at the read cursor is initialized and owned by the consumer.
+        let value = unsafe { shared.slot(read).read() };
+        // Release the slot back to the producer.
+        shared.read.store(shared.next(read), Ordering::Release);
+        Some(value)
+    }
+    /// Take the newest item, dropping every older one, or return [`None`] if the buffer is empty.
+    ///
+    /// This suits a producer publishing snapshots of some state, where only the latest matters.
+    /// Items pushed while the older ones are dropped are left for the next call.
+    /// ```rust
+    /// use circulate::RingBuffer;
+    ///
+    /// let (mut producer, mut consumer) = RingBuffer::with_capacity(4).split();
+    /// for snapshot in 0..3 {
+    ///     producer.push(snapshot).unwrap();
+    /// }
+    /// assert_eq!(consumer.pop_latest(), Some(2));
+    /// assert!(consumer.is_empty());
+    /// assert_eq!(consumer.pop_latest(), None);
+    /// ```
+    pub fn pop_latest(&mut self) -> Option<T> {
+        let shared = &*self.shared;
+        let mut read = shared.read.load(Ordering::Relaxed);
+        // Acquire the producer's release of every item up to the write cursor.
+        let write = shared.write.load(Ordering::Acquire);
+        while read != write {
+            let next = shared.next(read);
+            // Safety: The slot at the read cursor is initialized and owned by the consumer.
+            let value = unsafe { shared.slot(read).read() };
+            // Release the slot before dropping the item, so a panicking drop leaves the rest.
+            shared.read.store(next, Ordering::Release);
+            if next == write {
+                return Some(value);
+            }
+            drop(value);
+            read = next;
+        }
+        None
+    }
+    /// Get a reference to the oldest item without taking it, or [`None`] if the buffer is empty.
+    pub fn peek(&self) -> Option<&T> {
+        let shared = &*self.shared;
+        let read = shared.read.load(Ordering::Relaxed);
+        // Acquire the producer's release of the item.
+        if read == shared.write.load(Ordering::Acquire) {
+            return None;
+        }
+        // Safety: The slot at the read cursor is initialized and owned by the consumer, which
+        // cannot pop it while it is borrowed.
+        Some(unsafe { &*shared.slot(read) })
+    }
+    /// Get the number of items currently in the buffer.
+    pub fn len(&self) -> usize {
+        let shared = &*self.shared;
+        shared.len(shared.read.load(Ordering::Relaxed), shared.write.load(Ordering::Acquire))
+    }
+    /// Returns if the buffer currently holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
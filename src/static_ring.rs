@@ -0,0 +1,292 @@
+use core::mem::MaybeUninit;
+use crate::sync::{AtomicBool, AtomicUsize, Ordering, UnsafeCell};
+
+/// A fixed capacity single-producer, single-consumer ring that can be placed in a `static`.
+///
+/// The ring holds up to `N - 1` items inline, as one slot always remains vacant. It is split once
+/// in to a [`StaticProducer`] and a [`StaticConsumer`], which may live in different contexts such
+/// as an interrupt handler and the main loop. Pushing and popping only ever load and store atomics,
+/// so the handles work on targets without atomic read-modify-write operations. Splitting does need
+/// one, which the `critical-section` feature replaces with a critical section, or which the
+/// `portable-atomic` feature emulates.
+/// ```rust
+/// use circulate::StaticRing;
+///
+/// static QUEUE: StaticRing<u8, 4> = StaticRing::new();
+///
+/// let (mut producer, mut consumer) = QUEUE.split().unwrap();
+/// assert!(QUEUE.split().is_none());
+/// for byte in b"abc" {
+///     producer.push(*byte).unwrap();
+/// }
+/// assert_eq!(producer.push(b'd'), Err(b'd'));
+/// assert_eq!(consumer.pop(), Some(b'a'));
+/// producer.push(b'd').unwrap();
+/// assert_eq!(consumer.len(), 3);
+/// assert_eq!([consumer.pop(), consumer.pop(), consumer.pop(), consumer.pop()], [Some(b'b'), Some(b'c'), Some(b'd'), None]);
+/// ```
+///
+/// # Safety
+/// Each slot is owned by exactly one side at a time. Slots from the read cursor up to the write
+/// cursor belong to the consumer and the rest to the producer. Each side only ever stores to its
+/// own cursor, after it has finished with the slot it is handing over, with release ordering that
+/// the other side acquires before touching the slot. As the ring can only be split once there is
+/// never more than one producer or consumer.
+pub struct StaticRing<T, const N: usize> {
+    buffer: [UnsafeCell<MaybeUninit<T>>; N],
+    read: AtomicUsize,
+    write: AtomicUsize,
+    /// Set once the ring has been split.
+    split: AtomicBool,
+}
+// Safety: Items are moved between the producer and consumer, which is sound if they can be sent.
+unsafe impl<T: Send, const N: usize> Sync for StaticRing<T, N> {}
+impl<T, const N: usize> StaticRing<T, N> {
+    /// Create an empty ring with space for `N - 1` items.
+    /// Fails to compile if `N` is less than 2.
+    #[cfg(not(loom))]
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        const { assert!(N > 1, "a StaticRing needs at least 2 slots") };
+        Self {
+            buffer: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            read: AtomicUsize::new(0),
+            write: AtomicUsize::new(0),
+            split: AtomicBool::new(false),
+        }
+    }
+    /// Create an empty ring with space for `N - 1` items.
+    ///
+    /// The `loom` primitives cannot be created in a constant, so neither can the ring.
+    #[cfg(loom)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        const { assert!(N > 1, "a StaticRing needs at least 2 slots") };
+        Self {
+            buffer: core::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+            read: AtomicUsize::new(0),
+            write: AtomicUsize::new(0),
+            split: AtomicBool::new(false),
+        }
+    }
+    /// Get the number of items the ring can hold.
+    pub const fn capacity(&self) -> usize {
+        N - 1
+    }
+    /// Split the ring in to its producer and consumer handles.
+    /// Returns [`None`] if the ring has already been split.
+    pub fn split(&self) -> Option<(StaticProducer<'_, T, N>, StaticConsumer<'_, T, N>)> {
+        if self.take_split() {
+            Some((StaticProducer { ring: self }, StaticConsumer { ring: self }))
+        } else {
+            None
+        }
+    }
+    #[cfg(any(feature = "portable-atomic", not(feature = "critical-section")))]
+    fn take_split(&self) -> bool {
+        !self.split.swap(true, Ordering::Relaxed)
+    }
+    #[cfg(all(not(feature = "portable-atomic"), feature = "critical-section"))]
+    fn take_split(&self) -> bool {
+        critical_section::with(|_| {
+            let split = self.split.load(Ordering::Relaxed);
+            self.split.store(true, Ordering::Relaxed);
+            !split
+        })
+    }
+    const fn next(index: usize) -> usize {
+        if index + 1 == N {
+            0
+        } else {
+            index + 1
+        }
+    }
+}
+impl<T, const N: usize> Drop for StaticRing<T, N> {
+    fn drop(&mut self) {
+        // Note: Both handles borrowed the ring, so they have finished with it.
+        let mut read = self.read.load(Ordering::Relaxed);
+        let write = self.write.load(Ordering::Relaxed);
+        while read != write {
+            // Safety: Slots from the read cursor up to the write cursor are initialized.
+            self.buffer[read].with_mut(|slot| unsafe { (*slot).assume_init_drop() });
+            read = Self::next(read);
+        }
+    }
+}
+
+/// The pushing half of a [`StaticRing`].
+pub struct StaticProducer<'a, T, const N: usize> {
+    ring: &'a StaticRing<T, N>,
+}
+impl<T, const N: usize> StaticProducer<'_, T, N> {
+    /// Push an item to the ring, handing it back if the ring is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let write = self.ring.write.load(Ordering::Relaxed);
+        let next = StaticRing::<T, N>::next(write);
+        // Acquire the consumer's release of the slot.
+        if next == self.ring.read.load(Ordering::Acquire) {
+            return Err(value);
+        }
+        // Safety: The slot at the write cursor is vacant and owned by the producer.
+        self.ring.buffer[write].with_mut(|slot| unsafe { (*slot).write(value) });
+        // Release the item to the consumer.
+        self.ring.write.store(next, Ordering::Release);
+        Ok(())
+    }
+    /// Returns if the ring currently has no space for another item.
+    pub fn is_full(&self) -> bool {
+        StaticRing::<T, N>::next(self.ring.write.load(Ordering::Relaxed)) == self.ring.read.load(Ordering::Acquire)
+    }
+    /// Returns if the ring currently has no space for another item.
+    #[deprecated(note = "renamed to `StaticProducer::is_full`")]
+    pub fn full(&self) -> bool {
+        self.is_full()
+    }
+}
+
+/// The popping half of a [`StaticRing`].
+pub struct StaticConsumer<'a, T, const N: usize> {
+    ring: &'a StaticRing<T, N>,
+}
+impl<T, const N: usize> StaticConsumer<'_, T, N> {
+    /// Take the oldest item from the ring, or return [`None`] if it is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let read = self.ring.read.load(Ordering::Relaxed);
+        // Acquire the producer's release of the item.
+        if read == self.ring.write.load(Ordering::Acquire) {
+            return None;
+        }
+        // Safety: The slot at the read cursor is initialized and owned by the consumer.
+        let value = self.ring.buffer[read].with(|slot| unsafe { (*slot).assume_init_read() });
+        // Release the slot back to the producer.
+        self.ring.read.store(StaticRing::<T, N>::next(read), Ordering::Release);
+        Some(value)
+    }
+    /// Take the newest item, dropping every older one, or return [`None`] if the ring is empty.
+    /// Items pushed while the older ones are dropped are left for the next call.
+    /// ```rust
+    /// use circulate::StaticRing;
+    ///
+    /// static STATE: StaticRing<u16, 4> = StaticRing::new();
+    ///
+    /// let (mut producer, mut consumer) = STATE.split().unwrap();
+    /// for reading in [10, 20, 30] {
+    ///     producer.push(reading).unwrap();
+    /// }
+    /// assert_eq!(consumer.pop_latest(), Some(30));
+    /// assert_eq!(consumer.pop_latest(), None);
+    /// ```
+    pub fn pop_latest(&mut self) -> Option<T> {
+        let mut read = self.ring.read.load(Ordering::Relaxed);
+        // Acquire the producer's release of every item up to the write cursor.
+        let write = self.ring.write.load(Ordering::Acquire);
+        while read != write {
+            let next = StaticRing::<T, N>::next(read);
+            // Safety: The slot at the read cursor is initialized and owned by the consumer.
+            let value = self.ring.buffer[read].with(|slot| unsafe { (*slot).assume_init_read() });
+            // Release the slot before dropping the item, so a panicking drop leaves the rest.
+            self.ring.read.store(next, Ordering::Release);
+            if next == write {
+                return Some(value);
+            }
+            drop(value);
+            read = next;
+        }
+        None
+    }
+    /// Move up to `max` of the oldest items in to `dst` at once, growing it if needed and able.
+    /// Returns the number of items moved, which is fewer than `max` if the ring held fewer or
+    /// `dst` could not hold them all.
+    ///
+    /// This suits a consumer that takes work in batches, such as a worker stealing from a queue
+    /// that another context pushes to. However many items are moved, the producer's cursor is
+    /// loaded once and the consumer's cursor stored once, where popping each item in turn loads
+    /// and stores them for every item.
+    /// ```rust
+    /// use circulate::{RingBuffer, StaticRing};
+    ///
+    /// static QUEUE: StaticRing<u32, 8> = StaticRing::new();
+    ///
+    /// let (mut producer, mut consumer) = QUEUE.split().unwrap();
+    /// for job in 0..7 {
+    ///     producer.push(job).unwrap();
+    /// }
+    /// let mut batch = RingBuffer::new();
+    /// assert_eq!(consumer.steal_batch_into(&mut batch, 4), 4);
+    /// assert!(batch.iter().eq(&[0, 1, 2, 3]));
+    ///
+    /// // The items wrap around the end of the ring.
+    /// for job in 7..10 {
+    ///     producer.push(job).unwrap();
+    /// }
+    /// assert_eq!(consumer.steal_batch_into(&mut batch, usize::MAX), 6);
+    /// assert!(batch.iter().eq(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]));
+    /// assert_eq!(consumer.steal_batch_into(&mut batch, usize::MAX), 0);
+    ///
+    /// // A bounded buffer takes only what fits.
+    /// let mut bounded = RingBuffer::new();
+    /// bounded.set_max_capacity(4);
+    /// for job in 10..14 {
+    ///     producer.push(job).unwrap();
+    /// }
+    /// assert_eq!(consumer.steal_batch_into(&mut bounded, usize::MAX), 3);
+    /// assert_eq!(consumer.pop(), Some(13));
+    /// ```
+    ///
+    /// # Memory ordering
+    /// The acquire load of the write cursor synchronizes with the producer's release store of the
+    /// value it reads, which the producer made after writing every item before that cursor. So one
+    /// load hands over all of the items it covers, rather than one load per item.
+    ///
+    /// The items are then moved out before the read cursor is stored with release ordering, past
+    /// all of them at once. The producer acquires the read cursor before writing to a slot, so it
+    /// cannot reuse any of the slots until every item has been moved out, and cannot observe a
+    /// cursor part way through the batch.
+    #[cfg(feature = "alloc")]
+    pub fn steal_batch_into(&mut self, dst: &mut crate::RingBuffer<T>, max: usize) -> usize {
+        let read = self.ring.read.load(Ordering::Relaxed);
+        // Acquire the producer's release of every item up to the write cursor.
+        let write = self.ring.write.load(Ordering::Acquire);
+        let available = if read <= write { write - read } else { N - (read - write) };
+        let wanted = available.min(max);
+        if wanted == 0 {
+            return 0;
+        }
+        // Take only what fits if `dst` cannot grow enough, growing it as far as it may.
+        if dst.reserve(wanted).is_err() {
+            if let Some(max) = dst.max_capacity() {
+                let _ = dst.reserve(max.saturating_sub(dst.len() + 1));
+            }
+        }
+        let (lhs, rhs) = dst.spare_capacity_mut();
+        let mut index = read;
+        let mut count = 0;
+        for slot in lhs.iter_mut().chain(rhs).take(wanted) {
+            // Safety: Slots from the read cursor up to the write cursor are initialized and owned
+            // by the consumer, and the read cursor is moved past each item taken.
+            slot.write(self.ring.buffer[index].with(|item| unsafe { (*item).assume_init_read() }));
+            index = StaticRing::<T, N>::next(index);
+            count += 1;
+        }
+        // Safety: The first `count` spare slots were just initialized.
+        unsafe { dst.advance_write_unchecked(count) };
+        // Release every slot moved out of back to the producer at once.
+        self.ring.read.store(index, Ordering::Release);
+        count
+    }
+    /// Get the number of items currently in the ring.
+    pub fn len(&self) -> usize {
+        let read = self.ring.read.load(Ordering::Relaxed);
+        let write = self.ring.write.load(Ordering::Acquire);
+        if read <= write {
+            write - read
+        } else {
+            N - (read - write)
+        }
+    }
+    /// Returns if the ring currently holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
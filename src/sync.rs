@@ -0,0 +1,54 @@
+//! The synchronization primitives used by the lock-free rings, which are swapped for the model
+//! checked versions from `loom` when built with `--cfg loom`, or for those of `portable-atomic`
+//! with the `portable-atomic` feature.
+//!
+//! Code using these must access cells through [`UnsafeCell::with`] and [`UnsafeCell::with_mut`]
+//! so that `loom` can check each access for data races.
+
+#[cfg(loom)]
+pub(crate) use loom::{cell::UnsafeCell, sync::atomic::{AtomicBool, AtomicUsize, Ordering}};
+#[cfg(all(not(loom), feature = "portable-atomic"))]
+pub(crate) use portable_atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(all(not(loom), not(feature = "portable-atomic")))]
+pub(crate) use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+// The words of a record ring, only built with an allocator.
+#[cfg(all(loom, feature = "alloc"))]
+pub(crate) use loom::sync::atomic::AtomicU32;
+#[cfg(all(not(loom), feature = "alloc", feature = "portable-atomic"))]
+pub(crate) use portable_atomic::AtomicU32;
+#[cfg(all(not(loom), feature = "alloc", not(feature = "portable-atomic")))]
+pub(crate) use core::sync::atomic::AtomicU32;
+
+// The shared state of a split buffer, whose release `loom` must see to check the final drop.
+#[cfg(all(loom, feature = "alloc"))]
+pub(crate) use loom::sync::Arc;
+#[cfg(all(not(loom), feature = "alloc"))]
+pub(crate) use alloc::sync::Arc;
+#[cfg(all(not(loom), feature = "alloc"))]
+extern crate alloc;
+
+// Splitting a ring swaps an atomic, which targets such as `thumbv6m-none-eabi` cannot do natively.
+#[cfg(all(not(target_has_atomic = "ptr"), not(feature = "portable-atomic"), not(feature = "critical-section")))]
+compile_error!("this target has no atomic read-modify-write operations, enable the `critical-section` or `portable-atomic` feature");
+
+/// A [`core::cell::UnsafeCell`] with the same interface as the one from `loom`.
+#[cfg(not(loom))]
+#[repr(transparent)]
+pub(crate) struct UnsafeCell<T>(core::cell::UnsafeCell<T>);
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Self(core::cell::UnsafeCell::new(value))
+    }
+    /// Get a pointer to read the value through for the duration of `f`.
+    #[inline(always)]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+    /// Get a pointer to write the value through for the duration of `f`.
+    #[inline(always)]
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
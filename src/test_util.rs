@@ -0,0 +1,393 @@
+//! Hooks for observing the allocations made by the crate, for testing growth behaviour, and
+//! deterministic in-memory streams for tests and benchmarks.
+//! Requires `std`.
+//!
+//! The examples of the crate that need a stream with short reads, short writes or errors use the
+//! streams here, so run the doc tests with `cargo test --features test-util --doc`.
+//!
+//! ```rust
+//! use circulate::{test_util, BufStream, FromStd, Read, RingBuffer};
+//!
+//! test_util::reset_allocation_count();
+//! let mut buffer = RingBuffer::new();
+//! for i in 0..1000u32 {
+//!     buffer.push(i).unwrap();
+//! }
+//! // Growth is amortized, doubling the capacity each time.
+//! assert!(test_util::allocation_count() <= 10);
+//!
+//! // Reserving within the current capacity does not allocate.
+//! test_util::reset_allocation_count();
+//! buffer.reserve(10).unwrap();
+//! assert_eq!(test_util::allocation_count(), 0);
+//!
+//! // Reading less than the capacity of a stream allocates once for each buffer up front.
+//! let input = vec![0xa5u8; 1 << 16];
+//! test_util::reset_allocation_count();
+//! let mut stream = BufStream::with_capacity(FromStd(std::io::Cursor::new(input)), 1 << 17);
+//! assert_eq!(test_util::allocation_count(), 2);
+//! let mut chunk = [core::mem::MaybeUninit::uninit(); 4096];
+//! while stream.read(&mut chunk).unwrap() != 0 {}
+//! assert_eq!(test_util::allocation_count(), 2);
+//!
+//! // Buffering an already buffered stream allocates no input buffer of its own.
+//! test_util::reset_allocation_count();
+//! let inner = BufStream::with_capacity(FromStd(std::io::Cursor::new(vec![0xa5u8; 1 << 16])), 1 << 10);
+//! let mut stream = BufStream::new(inner);
+//! while stream.read(&mut chunk).unwrap() != 0 {}
+//! assert_eq!(test_util::allocation_count(), 2);
+//! ```
+extern crate std;
+use core::{alloc::Layout, cell::Cell};
+use std::{collections::VecDeque, io, vec::Vec};
+use crate::raw_alloc::{Global, RawAlloc};
+
+std::thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static FAIL_AFTER: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Get the number of allocations made by the crate on the current thread since the last call to
+/// [`reset_allocation_count`].
+pub fn allocation_count() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+/// Reset the allocation count of the current thread to 0.
+pub fn reset_allocation_count() {
+    ALLOCATIONS.with(|count| count.set(0))
+}
+
+/// Make allocations by the crate on the current thread fail once `count` more have succeeded,
+/// or stop failing allocations if `count` is [`None`].
+/// ```rust
+/// use circulate::{test_util, AllocFailure, BufStream, FillStatus, FromStd, RingBuffer, Write};
+///
+/// let mut buffer = RingBuffer::with_capacity(3);
+/// buffer.set_alloc_failure_mode(AllocFailure::Saturate);
+/// test_util::fail_allocations_after(Some(0));
+/// for i in 0..3 {
+///     buffer.push(i).unwrap();
+/// }
+/// assert_eq!(buffer.push(3), Err(3));
+/// assert!(buffer.reserve(1).unwrap_err().allocation_failed());
+/// assert_eq!(buffer.capacity(), 4);
+///
+/// // Streams report running out of memory as backpressure.
+/// test_util::fail_allocations_after(None);
+/// let mut stream = BufStream::with_capacity(FromStd(std::io::Cursor::new(vec![0; 64])), 7);
+/// stream.set_alloc_failure_mode(AllocFailure::Saturate);
+/// test_util::fail_allocations_after(Some(0));
+/// assert_eq!(stream.write(b"0123456789").unwrap(), 7);
+/// assert_eq!(stream.write(b"!").unwrap(), 0);
+/// assert_eq!(stream.try_fill(16).unwrap(), FillStatus::Partial(7));
+/// assert_eq!(stream.try_fill(16).unwrap(), FillStatus::Full(7));
+/// assert!(stream.unread(b"!").is_err());
+/// test_util::fail_allocations_after(None);
+/// ```
+pub fn fail_allocations_after(count: Option<usize>) {
+    FAIL_AFTER.with(|fail_after| fail_after.set(count))
+}
+
+/// Counts allocations before deferring to the global allocator.
+pub(crate) struct Counting;
+impl RawAlloc for Counting {
+    unsafe fn alloc(layout: Layout) -> *mut u8 {
+        match FAIL_AFTER.get() {
+            Some(0) => return core::ptr::null_mut(),
+            Some(count) => FAIL_AFTER.set(Some(count - 1)),
+            None => (),
+        }
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { Global::alloc(layout) }
+    }
+    unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        unsafe { Global::dealloc(ptr, layout) }
+    }
+    unsafe fn realloc(ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        match FAIL_AFTER.get() {
+            Some(0) => return core::ptr::null_mut(),
+            Some(count) => FAIL_AFTER.set(Some(count - 1)),
+            None => (),
+        }
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { Global::realloc(ptr, layout, new_size) }
+    }
+}
+
+/// An in-memory stream that reads from a fixed input and records everything written to it.
+///
+/// Each read and write can be limited to a number of bytes, to exercise short reads and writes
+/// deterministically.
+/// ```rust
+/// use std::io::{Read, Write};
+/// use circulate::test_util::MemoryStream;
+///
+/// let mut stream = MemoryStream::new(b"0123456789".to_vec());
+/// stream.set_max_read(4);
+/// stream.set_max_write(3);
+/// let mut buffer = [0; 16];
+/// assert_eq!(stream.read(&mut buffer).unwrap(), 4);
+/// assert_eq!(stream.write(b"hello").unwrap(), 3);
+/// assert_eq!(stream.remaining_input(), b"456789");
+/// assert_eq!(stream.output(), b"hel");
+///
+/// stream.rewind();
+/// assert_eq!(stream.remaining_input(), b"0123456789");
+/// assert!(stream.output().is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStream {
+    input: Vec<u8>,
+    position: usize,
+    output: Vec<u8>,
+    max_read: usize,
+    max_write: usize,
+}
+impl MemoryStream {
+    /// Create a stream that reads `input` and then reports the end of the stream.
+    pub fn new(input: Vec<u8>) -> Self {
+        Self { input, position: 0, output: Vec::new(), max_read: usize::MAX, max_write: usize::MAX }
+    }
+    /// Create a stream that reads `input` at most `max` bytes at a time, as
+    /// [`MemoryStream::set_max_read`] does.
+    pub fn with_max_read(input: Vec<u8>, max: usize) -> Self {
+        Self { max_read: max, ..Self::new(input) }
+    }
+    /// Limit each read to at most `max` bytes.
+    pub fn set_max_read(&mut self, max: usize) {
+        self.max_read = max;
+    }
+    /// Limit each write to at most `max` bytes.
+    pub fn set_max_write(&mut self, max: usize) {
+        self.max_write = max;
+    }
+    /// Get the input that has not yet been read.
+    pub fn remaining_input(&self) -> &[u8] {
+        &self.input[self.position..]
+    }
+    /// Get everything written to the stream.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+    /// Start reading the input from the beginning again and discard the output, keeping its allocation.
+    pub fn rewind(&mut self) {
+        self.position = 0;
+        self.output.clear();
+    }
+}
+impl io::Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.remaining_input();
+        let len = buf.len().min(remaining.len()).min(self.max_read);
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+impl io::Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.max_write);
+        self.output.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+    /// Writes from every buffer at once, up to the write limit, as a vectored write to a file or
+    /// socket would.
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        let mut len = 0;
+        for buf in bufs {
+            let take = buf.len().min(self.max_write - len);
+            self.output.extend_from_slice(&buf[..take]);
+            len += take;
+        }
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Implement the [`Read`](crate::Read) and [`Write`](crate::Write) of this crate for test streams
+/// as well as those of `std`, so they can be used with either directly. They go through
+/// [`FromStd`](crate::FromStd), so are only implemented without the `no_std` feature.
+#[cfg(not(feature = "no_std"))]
+macro_rules! impl_circulate_io {
+    ($(impl$([$($generics:tt)*])? for $ty:ty;)*) => {$(
+        impl$(<$($generics)*>)? crate::Read for $ty {
+            type Error = io::Error;
+            #[inline]
+            fn read(&mut self, buffer: &mut [core::mem::MaybeUninit<u8>]) -> io::Result<usize> {
+                crate::Read::read(&mut crate::FromStd(self), buffer)
+            }
+            #[inline]
+            fn read_vectored(&mut self, buffers: &mut [crate::IoVecMut]) -> io::Result<usize> {
+                crate::Read::read_vectored(&mut crate::FromStd(self), buffers)
+            }
+        }
+        impl$(<$($generics)*>)? crate::Write for $ty {
+            type Error = io::Error;
+            #[inline]
+            fn write(&mut self, slice: &[u8]) -> io::Result<usize> {
+                crate::Write::write(&mut crate::FromStd(self), slice)
+            }
+            #[inline]
+            fn flush(&mut self) -> io::Result<()> {
+                crate::Write::flush(&mut crate::FromStd(self))
+            }
+            #[inline]
+            fn write_vectored(&mut self, buffers: &[crate::IoVec]) -> io::Result<usize> {
+                crate::Write::write_vectored(&mut crate::FromStd(self), buffers)
+            }
+        }
+    )*};
+}
+#[cfg(not(feature = "no_std"))]
+impl_circulate_io! {
+    impl for MemoryStream;
+    impl for ScriptedStream;
+    impl[R: io::Read] for TruncatedReader<R>;
+}
+
+/// What a [`ScriptedStream`] does on one read or write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Read or write at most this many bytes, which is at least 1.
+    Limit(usize),
+    /// Fail with an error of this kind without reading or writing.
+    Fail(io::ErrorKind),
+}
+impl Step {
+    /// Decode a step from any byte, such as one chosen by a fuzzer. Most bytes are a limit of
+    /// 1 to 240 bytes, and the rest fail with an interruption, a would block error or another
+    /// error.
+    pub const fn from_byte(byte: u8) -> Self {
+        match byte {
+            0..=0xef => Self::Limit(byte as usize + 1),
+            0xf0..=0xf7 => Self::Fail(io::ErrorKind::Interrupted),
+            0xf8..=0xfb => Self::Fail(io::ErrorKind::WouldBlock),
+            _ => Self::Fail(io::ErrorKind::Other),
+        }
+    }
+}
+
+/// A [`MemoryStream`] whose reads and writes take turns following a script of [`Step`]s, to
+/// exercise short reads, short writes and errors in any order. Once the script runs out, reads
+/// and writes are no longer limited and never fail.
+/// ```rust
+/// use std::io::{ErrorKind, Read, Write};
+/// use circulate::test_util::{MemoryStream, ScriptedStream, Step};
+///
+/// let steps = [Step::Limit(2), Step::Fail(ErrorKind::Interrupted), Step::Limit(3)];
+/// let mut stream = ScriptedStream::new(MemoryStream::new(b"0123456789".to_vec()), steps);
+/// let mut buffer = [0; 16];
+/// assert_eq!(stream.read(&mut buffer).unwrap(), 2);
+/// assert_eq!(stream.write(b"hello").unwrap_err().kind(), ErrorKind::Interrupted);
+/// assert_eq!(stream.write(b"hello").unwrap(), 3);
+/// assert_eq!(stream.read(&mut buffer).unwrap(), 8);
+/// assert_eq!(stream.get_ref().output(), b"hel");
+///
+/// // Any bytes decode to a script.
+/// let stream = ScriptedStream::from_script(Vec::new(), &[0, 0xf0, 0xff]);
+/// assert_eq!(stream.remaining_steps(), 3);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedStream {
+    stream: MemoryStream,
+    steps: VecDeque<Step>,
+}
+impl ScriptedStream {
+    /// Create a stream following `steps` over `stream`.
+    pub fn new(stream: MemoryStream, steps: impl IntoIterator<Item = Step>) -> Self {
+        Self { stream, steps: steps.into_iter().collect() }
+    }
+    /// Create a stream reading `input` and following the steps decoded from each byte of
+    /// `script` by [`Step::from_byte`].
+    pub fn from_script(input: Vec<u8>, script: &[u8]) -> Self {
+        Self::new(MemoryStream::new(input), script.iter().map(|&byte| Step::from_byte(byte)))
+    }
+    /// Get the number of steps not yet taken.
+    pub fn remaining_steps(&self) -> usize {
+        self.steps.len()
+    }
+    /// Get the underlying stream.
+    pub fn get_ref(&self) -> &MemoryStream {
+        &self.stream
+    }
+    /// Take the next step, returning the most bytes to read or write.
+    fn step(&mut self) -> io::Result<usize> {
+        match self.steps.pop_front() {
+            None => Ok(usize::MAX),
+            Some(Step::Limit(limit)) => Ok(limit.max(1)),
+            Some(Step::Fail(kind)) => Err(kind.into()),
+        }
+    }
+}
+impl io::Read for ScriptedStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let limit = self.step()?;
+        self.stream.set_max_read(limit);
+        self.stream.read(buf)
+    }
+}
+impl io::Write for ScriptedStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let limit = self.step()?;
+        self.stream.set_max_write(limit);
+        self.stream.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A stream that reads from `inner` until `end` bytes have been read, and then reports the end
+/// of the stream, for checking how reads handle a stream that ends too early.
+///
+/// Writes are accepted and discarded, so that request and response APIs can be driven too.
+/// ```rust
+/// use std::io::{self, Read};
+/// use circulate::test_util::TruncatedReader;
+///
+/// let mut reader = TruncatedReader::new(io::Cursor::new(b"0123456789".to_vec()), 6);
+/// reader.set_max_read(4);
+/// let mut buffer = [0; 16];
+/// assert_eq!(reader.read(&mut buffer).unwrap(), 4);
+/// assert_eq!(reader.read(&mut buffer).unwrap(), 2);
+/// assert_eq!(reader.read(&mut buffer).unwrap(), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TruncatedReader<R> {
+    inner: R,
+    remaining: usize,
+    max_read: usize,
+}
+impl<R> TruncatedReader<R> {
+    /// Create a stream that ends after reading `end` bytes of `inner`, or where `inner` ends if
+    /// that is sooner.
+    pub fn new(inner: R, end: usize) -> Self {
+        Self { inner, remaining: end, max_read: usize::MAX }
+    }
+    /// Limit each read to at most `max` bytes.
+    pub fn set_max_read(&mut self, max: usize) {
+        self.max_read = max;
+    }
+    /// Get the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+impl<R: io::Read> io::Read for TruncatedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.remaining).min(self.max_read);
+        let read = self.inner.read(&mut buf[..len])?;
+        self.remaining -= read;
+        Ok(read)
+    }
+}
+impl<R> io::Write for TruncatedReader<R> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! Logging of every change to the cursors of a [`RingBuffer`](crate::RingBuffer), for debugging
+//! cursor bugs and for showing how the buffer works. Requires `std`.
+//!
+//! Each operation that moves a cursor or reallocates calls the sink set on the current thread
+//! with a [`TraceEvent`] holding the cursors before and after. Operations built from others, such
+//! as a pop that advances the read cursor, also log each step. Without the `trace` feature the
+//! logging is compiled out entirely.
+//!
+//! The sink may itself use buffers, such as to format a message or to write through a
+//! [`BufStream`](crate::BufStream). Changes made while the sink is running are not logged, so the
+//! sink never recurses in to itself.
+//! ```rust
+//! use std::{cell::RefCell, rc::Rc};
+//! use circulate::{trace::{self, Cursors, Operation}, RingBuffer};
+//!
+//! let events = Rc::new(RefCell::new(Vec::new()));
+//! let log = events.clone();
+//! trace::set_trace_sink(move |event| log.borrow_mut().push(*event));
+//!
+//! // Wrap the buffer, so the items are split across the end of the allocation.
+//! let mut buffer = RingBuffer::with_capacity(3);
+//! buffer.extend_from_slice(&[1, 2, 3]).unwrap();
+//! buffer.pop();
+//! buffer.pop();
+//! buffer.extend_from_slice(&[4, 5]).unwrap();
+//!
+//! // Growing must move the wrapped items to the start of the new allocation, and the cursors
+//! // with them.
+//! events.borrow_mut().clear();
+//! buffer.reserve(8).unwrap();
+//! let events = events.take();
+//! assert_eq!(events.len(), 1);
+//! assert_eq!(events[0].operation, Operation::Reallocate);
+//! assert_eq!(events[0].before, Cursors { read: 2, write: 1, capacity: 4 });
+//! assert_eq!(events[0].after, Cursors { read: 0, write: 3, capacity: 16 });
+//! assert!(buffer.iter().eq(&[3, 4, 5]));
+//! # trace::clear_trace_sink();
+//! ```
+//! A sink that buffers its own output is not traced.
+//! ```rust
+//! use std::{cell::RefCell, rc::Rc};
+//! use circulate::{trace, RingBuffer};
+//!
+//! let lines = Rc::new(RefCell::new(RingBuffer::new()));
+//! let log = lines.clone();
+//! trace::set_trace_sink(move |event| {
+//!     log.borrow_mut().extend_from_slice(format!("{:?}\n", event.operation).as_bytes()).unwrap();
+//! });
+//! let mut buffer = RingBuffer::new();
+//! buffer.push(1).unwrap();
+//! trace::clear_trace_sink();
+//!
+//! // Only the traced buffer is logged, not the growth of `lines`.
+//! assert!(lines.borrow().iter().eq(b"Reallocate\nPush\n"));
+//! ```
+extern crate std;
+use core::cell::RefCell;
+use std::boxed::Box;
+use crate::RingBuffer;
+
+/// The sink of the current thread, borrowed while it is running.
+type Sink = RefCell<Option<Box<dyn FnMut(&TraceEvent)>>>;
+
+std::thread_local! {
+    static SINK: Sink = const { RefCell::new(None) };
+}
+
+/// Log every change to the cursors of a buffer made on the current thread to `sink`, replacing
+/// any previous sink.
+///
+/// To log through `log` or `defmt`, call their macros from the sink. Setting the sink from within
+/// the sink has no effect.
+pub fn set_trace_sink<F: FnMut(&TraceEvent) + 'static>(sink: F) {
+    let sink: Box<dyn FnMut(&TraceEvent)> = Box::new(sink);
+    // The old sink is dropped after the borrow ends, as dropping it may change a buffer.
+    drop(SINK.with(|current| current.try_borrow_mut().map(|mut current| current.replace(sink))));
+}
+/// Stop logging changes made on the current thread. Has no effect from within the sink.
+pub fn clear_trace_sink() {
+    drop(SINK.with(|current| current.try_borrow_mut().map(|mut current| current.take())));
+}
+
+/// Pass an event to the sink, unless there is none or it is already running.
+pub(crate) fn emit<T>(buffer: &RingBuffer<T>, operation: Operation, before: Cursors) {
+    let event = TraceEvent { buffer: buffer as *const _ as usize, operation, before, after: buffer.cursors() };
+    // Note: The thread local may already be destroyed if a buffer is dropped during thread exit.
+    let _ = SINK.try_with(|sink| {
+        if let Ok(mut sink) = sink.try_borrow_mut() {
+            if let Some(sink) = sink.as_mut() {
+                sink(&event);
+            }
+        }
+    });
+}
+
+/// A change to the cursors of a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// The address of the buffer, to tell buffers apart. A buffer keeps its address only while
+    /// it is not moved.
+    pub buffer: usize,
+    /// The operation that changed the cursors.
+    pub operation: Operation,
+    /// The cursors before the operation.
+    pub before: Cursors,
+    /// The cursors after the operation.
+    pub after: Cursors,
+}
+
+/// The cursors and capacity of a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Cursors {
+    /// The index of the item at the read end.
+    pub read: usize,
+    /// The index of the slot the next item is written to.
+    pub write: usize,
+    /// The number of slots in the allocation.
+    pub capacity: usize,
+}
+
+/// An operation that changed the cursors of a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// An item was pushed.
+    Push,
+    /// An item was pushed to the read end, taking it off the count of popped items.
+    PushFront,
+    /// An item was popped from the write end, taking it off the count of pushed items.
+    PopBack,
+    /// The read cursor advanced past this many items, such as by a pop.
+    SetReadCursor(usize),
+    /// The write cursor advanced past this many items, such as by extending from a slice.
+    SetWriteCursor(usize),
+    /// The read cursor moved back over this many prepended items.
+    SetReadCursorBack(usize),
+    /// The write cursor advanced past this many items written directly in to the buffer.
+    Repair(usize),
+    /// The items moved to a new allocation, as the buffer grew or shrank.
+    Reallocate,
+    /// Every item was dropped.
+    Clear,
+    /// Items were dropped from the write end until this many remained.
+    Truncate(usize),
+    /// An item was removed from the middle of the buffer.
+    Remove,
+    /// The items were moved so that they are contiguous.
+    MakeContiguous,
+    /// The items were moved to the start of the allocation.
+    Normalize,
+    /// This many bytes were copied to the write end without growing.
+    ExtendContiguous(usize),
+}
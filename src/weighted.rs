@@ -0,0 +1,265 @@
+use crate::{Iter, RingBuffer};
+
+/// A [`RingBuffer`] that keeps a running total of a weight given to each item, such as the
+/// number of payload bytes in each chunk, so that it can be bounded by total weight rather than
+/// by the number of items.
+///
+/// The total is updated by every method that adds or removes items, including moving items
+/// between buffers and iterating by value. Items are only ever lent out by shared reference, but
+/// the weigher must still give the same weight for an item each time it is called, so an item
+/// whose weight depends on interior mutability such as a [`Cell`](core::cell::Cell) must not
+/// change it while buffered. If it does, the total is no longer the sum of the weights, though
+/// removing items saturates at zero rather than overflowing.
+/// ```rust
+/// use circulate::WeightedRingBuffer;
+///
+/// let mut queue = WeightedRingBuffer::new(|chunk: &Box<[u8]>| chunk.len());
+/// queue.push(Box::from(&b"hello"[..])).unwrap();
+/// queue.push(Box::from(&b", "[..])).unwrap();
+/// assert_eq!(queue.total_weight(), 7);
+///
+/// // Chunks are refused once they would take the total over the bound.
+/// assert!(queue.push_bounded(Box::from(&b"world!"[..]), 12).is_err());
+/// queue.push_bounded(Box::from(&b"world"[..]), 12).unwrap();
+/// assert_eq!(queue.total_weight(), 12);
+///
+/// // Or the oldest chunks are evicted to make room.
+/// assert_eq!(queue.pop_until_weight(8), 1);
+/// assert!(queue.iter().map(|chunk| &chunk[..]).eq([&b", "[..], b"world"]));
+/// assert_eq!(queue.total_weight(), 7);
+/// ```
+/// The total stays equal to the sum of the weights of the items through any sequence of
+/// operations.
+/// ```rust
+/// use circulate::WeightedRingBuffer;
+///
+/// let weigh = |chunk: &Vec<u8>| chunk.len();
+/// let mut state = 0x2545_f491_4f6c_dd1du64;
+/// let mut random = move |bound: usize| {
+///     state ^= state << 13;
+///     state ^= state >> 7;
+///     state ^= state << 17;
+///     (state % bound as u64) as usize
+/// };
+/// let mut a = WeightedRingBuffer::with_capacity(8, weigh);
+/// let mut b = WeightedRingBuffer::new(weigh);
+/// for _ in 0..10_000 {
+///     let chunk = vec![0; random(64)];
+///     match random(10) {
+///         0 | 1 => drop(a.push(chunk)),
+///         2 => drop(a.push_bounded(chunk, 256)),
+///         3 => drop(a.pop()),
+///         4 => drop(a.pop_latest()),
+///         5 => drop(a.pop_until_weight(random(512))),
+///         6 => drop(a.evict_while(|chunk| chunk.len() < 32)),
+///         7 => drop(a.find_and_remove(|chunk| chunk.len() % 7 == 0)),
+///         8 => drop(b.move_from(&mut a, random(8))),
+///         _ => match random(4) {
+///             0 => a.clear(),
+///             1 => a.truncate(random(8)),
+///             2 => drop(a.move_from(&mut b, random(8))),
+///             _ => {
+///                 // Take some items by value, and carry on with the rest.
+///                 let mut items = core::mem::replace(&mut a, WeightedRingBuffer::new(weigh)).into_iter();
+///                 for _ in 0..random(4) {
+///                     let expected = items.total_weight() - items.next().map_or(0, |chunk| chunk.len());
+///                     assert_eq!(items.total_weight(), expected);
+///                 }
+///                 a = items.into_inner();
+///             }
+///         },
+///     }
+///     for buffer in [&a, &b] {
+///         assert_eq!(buffer.total_weight(), buffer.iter().map(Vec::len).sum::<usize>());
+///     }
+/// }
+/// ```
+/// An item whose weight grows while buffered does not overflow the total when it is removed.
+/// ```rust
+/// use std::{cell::Cell, rc::Rc};
+/// use circulate::WeightedRingBuffer;
+///
+/// let mut buffer = WeightedRingBuffer::with_capacity(4, |value: &Rc<Cell<usize>>| value.get());
+/// let item = Rc::new(Cell::new(1));
+/// buffer.push(item.clone()).unwrap();
+/// buffer.push(Rc::new(Cell::new(2))).unwrap();
+/// item.set(10);
+/// assert!(buffer.pop().is_some());
+/// assert_eq!(buffer.total_weight(), 0);
+/// buffer.clear();
+/// assert_eq!(buffer.total_weight(), 0);
+/// ```
+pub struct WeightedRingBuffer<T, W = fn(&T) -> usize> {
+    buffer: RingBuffer<T>,
+    weigher: W,
+    /// The sum of the weights of the items in `buffer`.
+    total: usize,
+}
+impl<T, W: Fn(&T) -> usize> WeightedRingBuffer<T, W> {
+    /// Create an empty buffer that weighs each item with `weigher`.
+    pub const fn new(weigher: W) -> Self {
+        Self { buffer: RingBuffer::new(), weigher, total: 0 }
+    }
+    /// Create an empty buffer with space for at least `capacity` items, that weighs each item
+    /// with `weigher`.
+    pub fn with_capacity(capacity: usize, weigher: W) -> Self {
+        Self { buffer: RingBuffer::with_capacity(capacity), weigher, total: 0 }
+    }
+    /// Wrap an existing buffer, weighing the items already in it.
+    pub fn from_ring_buffer(buffer: RingBuffer<T>, weigher: W) -> Self {
+        let total = buffer.iter().map(&weigher).sum();
+        Self { buffer, weigher, total }
+    }
+    /// Get the underlying buffer back.
+    pub fn into_inner(self) -> RingBuffer<T> {
+        self.buffer
+    }
+    /// Get a reference to the underlying buffer.
+    pub const fn as_ring_buffer(&self) -> &RingBuffer<T> {
+        &self.buffer
+    }
+
+    /// Get the sum of the weights of the buffered items.
+    pub const fn total_weight(&self) -> usize {
+        self.total
+    }
+    /// Get the number of buffered items.
+    pub const fn len(&self) -> usize {
+        self.buffer.len()
+    }
+    /// Returns if no items are buffered.
+    pub const fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+    /// Get a reference to the item at `index` from the read end.
+    pub const fn get(&self, index: usize) -> Option<&T> {
+        self.buffer.get(index)
+    }
+    /// Returns an iterator over the items from the read end to the write end.
+    pub const fn iter(&self) -> Iter<'_, T> {
+        self.buffer.iter()
+    }
+
+    /// Push an item to the write end, returning it if the buffer could not grow to hold it.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let weight = (self.weigher)(&value);
+        self.buffer.push(value)?;
+        self.total += weight;
+        Ok(())
+    }
+    /// Push an item to the write end only if the total weight would then be no more than
+    /// `max_weight`, otherwise returning it. See [`WeightedRingBuffer`] for an example.
+    pub fn push_bounded(&mut self, value: T, max_weight: usize) -> Result<(), T> {
+        let weight = (self.weigher)(&value);
+        match self.total.checked_add(weight) {
+            Some(total) if total <= max_weight => {
+                self.buffer.push(value)?;
+                self.total = total;
+                Ok(())
+            }
+            _ => Err(value),
+        }
+    }
+    /// Take the item at the read end.
+    pub fn pop(&mut self) -> Option<T> {
+        let value = self.buffer.pop()?;
+        self.total = self.total.saturating_sub((self.weigher)(&value));
+        Some(value)
+    }
+    /// Take the item at the write end, dropping all older items.
+    pub fn pop_latest(&mut self) -> Option<T> {
+        let value = self.buffer.pop_latest()?;
+        self.total = 0;
+        Some(value)
+    }
+    /// Drop items from the read end until the total weight is no more than `max_weight`.
+    /// Returns the number of items dropped. See [`WeightedRingBuffer`] for an example.
+    ///
+    /// If the weigher panics, the total still matches the items left.
+    pub fn pop_until_weight(&mut self, max_weight: usize) -> usize {
+        let Self { buffer, weigher, total } = self;
+        buffer.evict_while(|value| {
+            let evict = *total > max_weight;
+            if evict {
+                *total = total.saturating_sub(weigher(value));
+            }
+            evict
+        })
+    }
+    /// Drop items from the read end while `predicate` holds, stopping at the first item it does
+    /// not hold for. Returns the number of items dropped. See [`RingBuffer::evict_while`].
+    ///
+    /// If `predicate` or the weigher panics, the total still matches the items left.
+    pub fn evict_while<F: FnMut(&T) -> bool>(&mut self, mut predicate: F) -> usize {
+        let Self { buffer, weigher, total } = self;
+        buffer.evict_while(|value| {
+            let evict = predicate(value);
+            if evict {
+                *total = total.saturating_sub(weigher(value));
+            }
+            evict
+        })
+    }
+    /// Remove and return the first item matching `predicate`. See
+    /// [`RingBuffer::find_and_remove`].
+    pub fn find_and_remove<F: FnMut(&T) -> bool>(&mut self, predicate: F) -> Option<T> {
+        let value = self.buffer.find_and_remove(predicate)?;
+        self.total = self.total.saturating_sub((self.weigher)(&value));
+        Some(value)
+    }
+    /// Keep only the first `len` items, dropping the rest.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.buffer.len() {
+            self.total = self.total.saturating_sub(self.buffer.iter().skip(len).map(&self.weigher).sum::<usize>());
+            self.buffer.truncate(len);
+        }
+    }
+    /// Drop every item.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.total = 0;
+    }
+    /// Move at most `max` items from the read end of `src` to the write end of this buffer,
+    /// returning the number of items moved. See [`RingBuffer::move_from`].
+    ///
+    /// The moved items are weighed by each buffer's own weigher.
+    pub fn move_from<V: Fn(&T) -> usize>(&mut self, src: &mut WeightedRingBuffer<T, V>, max: usize) -> usize {
+        let count = self.buffer.move_from(&mut src.buffer, max);
+        for value in self.buffer.iter().skip(self.buffer.len() - count) {
+            self.total += (self.weigher)(value);
+            src.total = src.total.saturating_sub((src.weigher)(value));
+        }
+        count
+    }
+}
+impl<T, W: Fn(&T) -> usize> IntoIterator for WeightedRingBuffer<T, W> {
+    type IntoIter = WeightedIntoIter<T, W>;
+    type Item = T;
+    fn into_iter(self) -> Self::IntoIter {
+        WeightedIntoIter(self)
+    }
+}
+
+/// An iterator that moves items out of a [`WeightedRingBuffer`], keeping track of the weight
+/// of the items left.
+pub struct WeightedIntoIter<T, W>(WeightedRingBuffer<T, W>);
+impl<T, W: Fn(&T) -> usize> WeightedIntoIter<T, W> {
+    /// Get the sum of the weights of the items not yet taken.
+    pub const fn total_weight(&self) -> usize {
+        self.0.total
+    }
+    /// Get back a buffer of the items not yet taken.
+    pub fn into_inner(self) -> WeightedRingBuffer<T, W> {
+        self.0
+    }
+}
+impl<T, W: Fn(&T) -> usize> Iterator for WeightedIntoIter<T, W> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
+    }
+}
+impl<T, W: Fn(&T) -> usize> ExactSizeIterator for WeightedIntoIter<T, W> {}
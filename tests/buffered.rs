@@ -0,0 +1,417 @@
+//! Checks that `BufReader` and `BufWriter` pass every byte through in order when the inner reader
+//! returns short reads and the inner writer accepts one byte at a time, including around pushing
+//! bytes back, seeking, failed flushes and the options of a `BufConfig`.
+#![cfg(all(feature = "alloc", not(feature = "no_std")))]
+
+use core::mem::MaybeUninit;
+use std::{io::{self, Seek, SeekFrom}, time::Duration};
+use circulate::{BufConfig, BufReader, BufWriter, FromStd, IoVecMut, Read, RingBuffer, Write};
+
+mod common;
+use common::Rng;
+
+/// Reads at most `limit` bytes at a time, counting the reads.
+struct Trickle {
+    bytes: io::Cursor<Vec<u8>>,
+    limit: usize,
+    reads: usize,
+}
+impl io::Read for Trickle {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads += 1;
+        let len = buf.len().min(self.limit);
+        io::Read::read(&mut self.bytes, &mut buf[..len])
+    }
+}
+
+/// Accepts one byte per write, and fails once `fail_at` bytes have been written.
+struct OneByte {
+    written: Vec<u8>,
+    writes: usize,
+    fail_at: Option<usize>,
+}
+impl io::Write for OneByte {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        if self.fail_at == Some(self.written.len()) {
+            self.fail_at = None;
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+        self.written.extend_from_slice(&buf[..buf.len().min(1)]);
+        Ok(buf.len().min(1))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn data() -> Vec<u8> {
+    (0..=255).cycle().take(3000).collect()
+}
+
+fn trickle(limit: usize) -> FromStd<Trickle> {
+    FromStd(Trickle { bytes: io::Cursor::new(data()), limit, reads: 0 })
+}
+
+fn one_byte(fail_at: Option<usize>) -> FromStd<OneByte> {
+    FromStd(OneByte { written: Vec::new(), writes: 0, fail_at })
+}
+
+#[test]
+fn short_reads() {
+    for limit in [1, 7, 100, 5000] {
+        for chunk in [1, 3, 16, 64, 1000] {
+            let mut reader = BufReader::with_capacity(trickle(limit), 16);
+            let mut read = Vec::new();
+            let mut buffer = vec![MaybeUninit::uninit(); chunk];
+            loop {
+                let got = reader.read(&mut buffer).unwrap();
+                if got == 0 {
+                    break;
+                }
+                read.extend(buffer[..got].iter().map(|byte| unsafe { byte.assume_init() }));
+            }
+            assert_eq!(read, data(), "limit {limit}, chunk {chunk}");
+        }
+    }
+}
+
+#[test]
+fn short_vectored_reads() {
+    for limit in [1, 7, 100] {
+        let mut reader = BufReader::with_capacity(trickle(limit), 16);
+        let mut read = Vec::new();
+        loop {
+            let (mut a, mut b) = ([0u8; 5], [0u8; 9]);
+            let got = reader.read_vectored(&mut [IoVecMut::new(&mut a), IoVecMut::new(&mut b)]).unwrap();
+            if got == 0 {
+                break;
+            }
+            read.extend(a.into_iter().chain(b).take(got));
+        }
+        assert_eq!(read, data(), "limit {limit}");
+    }
+}
+
+#[test]
+fn peek_and_consume() {
+    let mut reader = BufReader::with_capacity(trickle(3), 16);
+    assert_eq!(reader.buffer(), (&[][..], &[][..]));
+    assert_eq!(reader.fill_buf().unwrap(), (&[0, 1, 2][..], &[][..]));
+    // Nothing more is read while bytes are buffered.
+    assert_eq!(reader.fill_buf().unwrap().0.len(), 3);
+    assert_eq!(reader.get_ref().0.reads, 1);
+    reader.consume(2);
+    assert_eq!(reader.buffer(), (&[2][..], &[][..]));
+
+    let mut rest = RingBuffer::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert!(rest.iter().eq(&data()[2..]));
+}
+
+#[test]
+fn zero_capacity_still_buffers() {
+    let mut reader = BufReader::with_capacity(trickle(3), 0);
+    assert_eq!(reader.fill_buf().unwrap(), (&[0][..], &[][..]));
+    reader.consume(1);
+    let mut rest = RingBuffer::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert!(rest.iter().eq(&data()[1..]));
+}
+
+#[test]
+fn large_reads_bypass_the_buffer() {
+    let mut reader = BufReader::with_capacity(trickle(5000), 16);
+    let mut buffer = vec![MaybeUninit::uninit(); 1000];
+    assert_eq!(reader.read(&mut buffer).unwrap(), 1000);
+    assert_eq!(reader.buffer(), (&[][..], &[][..]));
+    assert_eq!(reader.into_inner().0.bytes.position(), 1000);
+}
+
+#[test]
+fn one_byte_writes() {
+    for chunk in [1, 5, 15, 16, 100] {
+        let mut writer = BufWriter::with_capacity(one_byte(None), 16);
+        for slice in data().chunks(chunk) {
+            let mut slice = slice;
+            while !slice.is_empty() {
+                let written = writer.write(slice).unwrap();
+                assert_ne!(written, 0);
+                slice = &slice[written..];
+            }
+        }
+        let inner = writer.into_inner().map_err(|error| error.into_error()).unwrap();
+        assert_eq!(inner.0.written, data(), "chunk {chunk}");
+    }
+}
+
+#[test]
+fn flush_on_drop() {
+    let mut inner = one_byte(None);
+    {
+        let mut writer = BufWriter::new(&mut inner);
+        writer.write(b"dropped").unwrap();
+        assert_eq!(writer.get_ref().0.writes, 0);
+    }
+    assert_eq!(inner.0.written, b"dropped");
+    assert_eq!(inner.0.writes, 7);
+}
+
+#[test]
+fn into_inner_failure() {
+    let mut writer = BufWriter::with_capacity(one_byte(Some(3)), 16);
+    writer.write(b"abcdef").unwrap();
+    let Err(error) = writer.into_inner() else { panic!("the fourth write fails") };
+    assert_eq!(error.error().kind(), io::ErrorKind::Interrupted);
+    let writer = error.into_stream();
+    assert_eq!(writer.get_ref().0.written, b"abc");
+    assert!(writer.buffer().iter().eq(b"def"));
+
+    let (inner, unwritten) = writer.into_parts();
+    assert_eq!(inner.0.written, b"abc");
+    assert!(unwritten.iter().eq(b"def"));
+}
+
+/// Accepts `limit` bytes in total and then fails every write.
+struct Broken {
+    sent: Vec<u8>,
+    limit: usize,
+}
+impl io::Write for Broken {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.limit - self.sent.len());
+        if len == 0 && !buf.is_empty() {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        self.sent.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn read_byte<R: Read<Error = io::Error>>(reader: &mut R) -> Option<u8> {
+    let mut byte = [MaybeUninit::uninit()];
+    match reader.read(&mut byte).unwrap() {
+        0 => None,
+        _ => Some(unsafe { byte[0].assume_init() }),
+    }
+}
+
+#[test]
+fn unread_across_the_wrap() {
+    let data = data();
+    for skip in 0..40 {
+        let mut reader = BufReader::with_capacity(trickle(5), 15);
+        reader.set_pushback_limit(4);
+        for _ in 0..skip {
+            read_byte(&mut reader).unwrap();
+        }
+        let back = skip.min(4);
+        reader.unread(&data[skip - back..skip]).unwrap();
+        if back == 4 {
+            assert!(reader.unread(b"!").is_err());
+        }
+        let mut rest = RingBuffer::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert!(rest.iter().eq(&data[skip - back..]), "skip {skip}");
+    }
+
+    // Without a limit, only the space in the buffer bounds the bytes pushed back.
+    let mut reader = BufReader::with_capacity(trickle(5), 15);
+    read_byte(&mut reader).unwrap();
+    reader.unread(&[7; 11]).unwrap();
+    assert!(reader.unread(&[7]).is_err());
+    assert_eq!(reader.buffer().0.len() + reader.buffer().1.len(), 15);
+}
+
+#[test]
+fn already_buffered_reads_through() {
+    let mut reader = BufReader::with_capacity(BufReader::with_capacity(trickle(100), 64), 16);
+    let mut read = Vec::new();
+    let mut chunk = [MaybeUninit::uninit(); 3];
+    loop {
+        let got = reader.read(&mut chunk).unwrap();
+        if got == 0 {
+            break;
+        }
+        read.extend(chunk[..got].iter().map(|byte| unsafe { byte.assume_init() }));
+        assert_eq!(reader.buffer(), (&[][..], &[][..]));
+    }
+    assert_eq!(read, data());
+    // Only the inner buffer read from the source, each read as large as the source allows.
+    assert_eq!(reader.get_ref().get_ref().0.reads, data().len().div_ceil(100) + 1);
+}
+
+#[test]
+fn config_options() {
+    type Check = fn(&mut BufReader<FromStd<Trickle>>) -> bool;
+    let cases: [(BufConfig, Check); 4] = [
+        (BufConfig::new().input_capacity(15), |reader| {
+            reader.fill_buf().unwrap();
+            reader.buffer().0.len() == 15
+        }),
+        (BufConfig::new().max_input_capacity(16), |reader| {
+            reader.fill_buf().unwrap();
+            reader.buffer().0.len() == 15
+        }),
+        (BufConfig::new().pushback_limit(2), |reader| reader.unread(b"abc").is_err()),
+        (BufConfig::new().scrub_consumed(true), |reader| reader.scrub_consumed()),
+    ];
+    for (config, check) in cases {
+        assert!(check(&mut BufReader::with_config(trickle(5000), config.clone())), "{config:?}");
+        assert!(!check(&mut BufReader::with_config(trickle(5000), BufConfig::default())), "{config:?}");
+        assert!(!check(&mut BufReader::new(trickle(5000))), "{config:?}");
+    }
+
+    type WriteCheck = fn(&mut BufWriter<FromStd<OneByte>>) -> bool;
+    let cases: [(BufConfig, WriteCheck); 2] = [
+        (BufConfig::new().output_capacity(15), |writer| {
+            writer.write(&[7; 15]).unwrap();
+            writer.write(&[7]).unwrap();
+            writer.get_ref().0.writes != 0
+        }),
+        (BufConfig::new().max_output_capacity(16), |writer| {
+            writer.write(&[7; 16]).unwrap() == 1
+        }),
+    ];
+    for (config, check) in cases {
+        assert!(check(&mut BufWriter::with_config(one_byte(None), config.clone())), "{config:?}");
+        assert!(!check(&mut BufWriter::with_config(one_byte(None), BufConfig::default())), "{config:?}");
+        assert!(!check(&mut BufWriter::new(one_byte(None))), "{config:?}");
+    }
+}
+
+#[test]
+fn recovering_from_a_failed_flush() {
+    // Retrying the flush on a new writer sends the rest of the bytes.
+    let mut writer = BufWriter::with_capacity(FromStd(Broken { sent: Vec::new(), limit: 4 }), 16);
+    writer.write(b"abcdefgh").unwrap();
+    assert_eq!(writer.flush().unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(writer.flushed_bytes(), 4);
+    assert!(writer.buffer().iter().eq(b"efgh"));
+    writer.get_mut().0 = Broken { sent: Vec::new(), limit: usize::MAX };
+    writer.flush().unwrap();
+    assert_eq!(writer.get_ref().0.sent, b"efgh");
+    assert_eq!(writer.flushed_bytes(), 8);
+
+    // Or the bytes are discarded and the writer reused.
+    writer.get_mut().0 = Broken { sent: Vec::new(), limit: 2 };
+    writer.write(b"ijkl").unwrap();
+    assert!(writer.flush().is_err());
+    assert_eq!(writer.discard_buffer(), 2);
+    assert_eq!(writer.discard_buffer(), 0);
+    writer.get_mut().0 = Broken { sent: Vec::new(), limit: usize::MAX };
+    writer.write(b"mn").unwrap();
+    let inner = writer.into_inner().map_err(|error| error.into_error()).unwrap();
+    assert_eq!(inner.0.sent, b"mn");
+}
+
+#[test]
+fn deadline_bookkeeping() {
+    let hour = Duration::from_secs(3600);
+    let mut writer = BufWriter::with_capacity(FromStd(Broken { sent: Vec::new(), limit: 3 }), 16);
+    assert_eq!(writer.oldest_unflushed_age(), None);
+    // Writes that skip the buffer do not start the clock.
+    writer.write(&[7; 2]).unwrap();
+    writer.flush().unwrap();
+    assert_eq!(writer.oldest_unflushed_age(), None);
+    writer.get_mut().0.limit = usize::MAX;
+    writer.write(&[7; 100]).unwrap();
+    assert_eq!(writer.oldest_unflushed_age(), None);
+
+    // A partial flush keeps the time of the oldest byte left.
+    writer.get_mut().0 = Broken { sent: Vec::new(), limit: 2 };
+    writer.write(b"abcd").unwrap();
+    let age = writer.oldest_unflushed_age().unwrap();
+    assert!(writer.flush().is_err());
+    assert!(writer.oldest_unflushed_age().unwrap() >= age);
+    assert!(!writer.flush_if_older_than(hour).unwrap());
+    assert!(writer.flush_if_older_than(Duration::ZERO).is_err());
+
+    // Discarding the bytes stops the clock, and flushing them all does too.
+    writer.discard_buffer();
+    assert_eq!(writer.oldest_unflushed_age(), None);
+    writer.get_mut().0.limit = usize::MAX;
+    writer.write(b"ef").unwrap();
+    assert!(writer.flush_if_older_than(Duration::ZERO).unwrap());
+    assert_eq!(writer.oldest_unflushed_age(), None);
+    assert!(!writer.flush_if_older_than(Duration::ZERO).unwrap());
+}
+
+#[test]
+fn seeks_match_an_unbuffered_cursor() {
+    let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+    let mut random = |n: usize| rng.below(n) as u64;
+    let mut writer = BufWriter::with_capacity(FromStd(io::Cursor::new(Vec::new())), 16);
+    let mut reference = io::Cursor::new(Vec::new());
+    for _ in 0..2000 {
+        match random(4) {
+            0 | 1 => {
+                let bytes: Vec<u8> = (0..random(40) as u8).collect();
+                let mut rest = &bytes[..];
+                while !rest.is_empty() {
+                    rest = &rest[writer.write(rest).unwrap()..];
+                }
+                io::Write::write_all(&mut reference, &bytes).unwrap();
+            }
+            2 => {
+                let position = random(200);
+                assert_eq!(writer.seek(SeekFrom::Start(position)).unwrap(), reference.seek(SeekFrom::Start(position)).unwrap());
+            }
+            _ => {
+                let offset = random(20) as i64 - 10;
+                if reference.stream_position().unwrap() as i64 + offset >= 0 {
+                    assert_eq!(writer.seek(SeekFrom::Current(offset)).unwrap(), reference.seek(SeekFrom::Current(offset)).unwrap());
+                }
+            }
+        }
+        assert_eq!(writer.stream_position().unwrap(), reference.stream_position().unwrap());
+    }
+    let FromStd(written) = writer.into_inner().map_err(|error| error.into_error()).unwrap();
+    let contents = reference.into_inner();
+    assert_eq!(written.into_inner(), contents);
+
+    let mut reader = BufReader::with_capacity(FromStd(io::Cursor::new(contents.clone())), 16);
+    let mut reference = io::Cursor::new(contents);
+    for _ in 0..2000 {
+        match random(4) {
+            0 | 1 => {
+                let mut byte = [0];
+                let expected = io::Read::read(&mut reference, &mut byte).unwrap();
+                assert_eq!(read_byte(&mut reader), (expected == 1).then_some(byte[0]));
+            }
+            2 => {
+                let offset = random(20) as i64 - 5;
+                if reference.stream_position().unwrap() as i64 + offset >= 0 {
+                    reader.seek_relative(offset).unwrap();
+                    reference.seek_relative(offset).unwrap();
+                }
+            }
+            _ => {
+                let position = random(200);
+                assert_eq!(reader.seek(SeekFrom::Start(position)).unwrap(), reference.seek(SeekFrom::Start(position)).unwrap());
+            }
+        }
+        assert_eq!(reader.stream_position().unwrap(), reference.stream_position().unwrap());
+    }
+}
+
+#[test]
+fn small_writes_spill_to_the_slow_path() {
+    let mut writer = BufWriter::with_capacity(FromStd(io::Cursor::new(Vec::new())), 15);
+    let mut expected = Vec::new();
+    for record in 0..200u32 {
+        let record = record.to_le_bytes();
+        // Records of 3 bytes reach the end of the buffer at different offsets.
+        let mut rest = &record[..3];
+        while !rest.is_empty() {
+            rest = &rest[writer.write(rest).unwrap()..];
+        }
+        expected.extend_from_slice(&record[..3]);
+        assert!(writer.buffer().len() <= 15);
+    }
+    let FromStd(written) = writer.into_inner().map_err(|error| error.into_error()).unwrap();
+    assert_eq!(written.into_inner(), expected);
+}
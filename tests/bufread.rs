@@ -0,0 +1,114 @@
+//! Checks parsing lines from a `BufStream` through `fill_buf` and `consume`, and with
+//! `read_until`, while the buffered input wraps around the end of a small input buffer.
+#![cfg(all(feature = "alloc", not(feature = "no_std")))]
+
+use std::io;
+use circulate::{BufStream, FromStd};
+
+/// Reads at most `limit` bytes at a time, across all the buffers of a vectored read.
+struct Trickle {
+    bytes: io::Cursor<Vec<u8>>,
+    limit: usize,
+}
+impl io::Read for Trickle {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.limit);
+        io::Read::read(&mut self.bytes, &mut buf[..len])
+    }
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut]) -> io::Result<usize> {
+        let mut read = 0;
+        for buf in bufs {
+            let len = buf.len().min(self.limit - read);
+            let count = io::Read::read(&mut self.bytes, &mut buf[..len])?;
+            read += count;
+            if count < len || read == self.limit {
+                break;
+            }
+        }
+        Ok(read)
+    }
+}
+impl io::Write for Trickle {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn lines() -> (Vec<u8>, Vec<Vec<u8>>) {
+    let lines: Vec<Vec<u8>> = (0..200).map(|n| format!("line {n}{}\n", "x".repeat(n % 13)).into_bytes()).collect();
+    (lines.concat(), lines)
+}
+
+fn stream(data: Vec<u8>, limit: usize, max: Option<usize>) -> BufStream<FromStd<Trickle>> {
+    let mut stream = BufStream::with_capacity(FromStd(Trickle { bytes: io::Cursor::new(data), limit }), 7);
+    if let Some(max) = max {
+        stream.set_max_input_capacity(max);
+    }
+    stream
+}
+
+#[test]
+fn fill_buf_and_consume() {
+    let (data, expected) = lines();
+    let mut wrapped = 0;
+    for limit in [1, 5, 64] {
+        let mut stream = stream(data.clone(), limit, Some(8));
+        let mut lines = Vec::new();
+        let mut line = Vec::new();
+        loop {
+            let (lhs, rhs) = stream.fill_buf().unwrap();
+            if lhs.is_empty() {
+                break;
+            }
+            wrapped += usize::from(!rhs.is_empty());
+            // Take at most a line, and at most a few bytes, from the buffered bytes.
+            let buffered = [lhs, rhs].concat();
+            let count = buffered.iter().position(|byte| *byte == b'\n').map_or(buffered.len(), |index| index + 1).min(3);
+            line.extend_from_slice(&buffered[..count]);
+            stream.consume(count);
+            if line.ends_with(b"\n") {
+                lines.push(std::mem::take(&mut line));
+            }
+            // Top up the rest of the buffer, after the bytes still buffered.
+            stream.buffer_read().unwrap();
+        }
+        assert_eq!(lines, expected, "limit {limit}");
+    }
+    assert!(wrapped != 0);
+}
+
+#[test]
+fn read_until_across_wraps() {
+    let (data, expected) = lines();
+    let mut wrapped = 0;
+    for limit in [1, 3, 64] {
+        for max in [None, Some(4), Some(16)] {
+            let mut stream = stream(data.clone(), limit, max);
+            let mut lines = Vec::new();
+            loop {
+                let mut line = Vec::new();
+                if stream.read_until(b'\n', &mut line).unwrap() == 0 {
+                    break;
+                }
+                lines.push(line);
+                // Top up the input after the bytes left from the last read, so the next line wraps.
+                stream.buffer_read().unwrap();
+                wrapped += usize::from(!stream.buffered_input().1.is_empty());
+            }
+            assert_eq!(lines, expected, "limit {limit}, max {max:?}");
+        }
+    }
+    assert!(wrapped != 0);
+}
+
+#[test]
+#[should_panic(expected = "cannot consume 4 bytes when only 3 are buffered")]
+fn consume_past_fill_buf() {
+    let mut stream = stream(b"abcdef".to_vec(), 3, None);
+    let (lhs, rhs) = stream.fill_buf().unwrap();
+    assert_eq!(lhs.len() + rhs.len(), 3);
+    stream.consume(4);
+}
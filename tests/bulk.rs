@@ -0,0 +1,127 @@
+//! Checks the bulk copies in to and out of a [`RingBuffer`] against a `VecDeque`, where the spare
+//! capacity wraps around the end of the allocation and where a slice only fits once the buffer
+//! has grown.
+#![cfg(feature = "alloc")]
+
+use core::mem::MaybeUninit;
+use std::{collections::VecDeque, sync::atomic::{AtomicUsize, Ordering}};
+use circulate::{Doubling, GrowthStrategy, RingBuffer};
+
+/// Counts how many times the buffers using it grow.
+struct Counting(AtomicUsize);
+impl GrowthStrategy for Counting {
+    fn next_capacity(&self, current: usize, required: usize) -> usize {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Doubling.next_capacity(current, required)
+    }
+}
+
+/// Make a buffer of `capacity` slots holding `len` items starting `offset` slots in to the
+/// allocation, along with the items it holds.
+fn offset_buffer(capacity: usize, offset: usize, len: usize) -> (RingBuffer<u32>, VecDeque<u32>) {
+    let mut buffer = RingBuffer::with_exact_capacity(capacity - 1);
+    for value in 0..offset as u32 {
+        buffer.push(value).unwrap();
+        buffer.pop();
+    }
+    let expected: VecDeque<u32> = (100..100 + len as u32).collect();
+    for &value in &expected {
+        buffer.push(value).unwrap();
+    }
+    (buffer, expected)
+}
+
+#[test]
+fn extend_wrapped() {
+    let mut wrapped = 0;
+    for offset in 0..8 {
+        for len in 0..8 {
+            for extra in 0..8 - len {
+                let (mut copied, mut expected) = offset_buffer(8, offset, len);
+                let (mut cloned, _) = offset_buffer(8, offset, len);
+                if !copied.spare_capacity_mut().1.is_empty() && extra > copied.spare_capacity_mut().0.len() {
+                    wrapped += 1;
+                }
+                let slice: Vec<u32> = (0..extra as u32).collect();
+                copied.extend_from_slice(&slice).unwrap();
+                cloned.extend_from_slice_clone(&slice).unwrap();
+                expected.extend(&slice);
+                for buffer in [&copied, &cloned] {
+                    // The slice fits without growing.
+                    assert_eq!(buffer.capacity(), 8);
+                    assert!(buffer.iter().eq(&expected), "offset {offset}, len {len}, extra {extra}");
+                }
+            }
+        }
+    }
+    assert!(wrapped != 0);
+}
+
+#[test]
+fn extend_grows_once() {
+    for offset in [0, 3, 7] {
+        static GROWS: Counting = Counting(AtomicUsize::new(0));
+        let (mut copied, mut expected) = offset_buffer(8, offset, 5);
+        let (mut cloned, _) = offset_buffer(8, offset, 5);
+        copied.set_growth_strategy(&GROWS);
+        cloned.set_growth_strategy(&GROWS);
+        let slice: Vec<u32> = (0..40).collect();
+        copied.extend_from_slice(&slice).unwrap();
+        cloned.extend_from_slice_clone(&slice).unwrap();
+        expected.extend(&slice);
+        assert_eq!(GROWS.0.swap(0, Ordering::Relaxed), 2, "each buffer grows once");
+        for buffer in [&copied, &cloned] {
+            assert_eq!(buffer.capacity(), 46);
+            assert!(buffer.iter().eq(&expected), "offset {offset}");
+        }
+    }
+
+    let mut bounded = RingBuffer::with_max_capacity(8);
+    bounded.extend_from_slice(&[1, 2]).unwrap();
+    assert!(bounded.extend_from_slice(&[0; 7]).is_err());
+    assert!(bounded.extend_from_slice_clone(&[0; 7]).is_err());
+    assert!(bounded.iter().eq(&[1, 2]));
+}
+
+#[test]
+fn pop_wrapped() {
+    for offset in 0..8 {
+        for len in 0..8 {
+            for out_len in [0, 1, len / 2, len, len + 3] {
+                let (mut buffer, mut expected) = offset_buffer(8, offset, len);
+                let (mut uninit_buffer, _) = offset_buffer(8, offset, len);
+                let mut out = vec![0; out_len];
+                let mut uninit = vec![MaybeUninit::uninit(); out_len];
+                let popped = buffer.pop_slice(&mut out);
+                assert_eq!(uninit_buffer.pop_slice_uninit(&mut uninit), popped);
+                assert_eq!(popped, out_len.min(len));
+                let taken: Vec<u32> = expected.drain(..popped).collect();
+                assert_eq!(out[..popped], taken);
+                // Safety: The first `popped` items were initialized.
+                assert!(uninit[..popped].iter().map(|item| unsafe { item.assume_init() }).eq(taken));
+                assert!(buffer.iter().eq(&expected));
+                assert!(uninit_buffer.iter().eq(&expected));
+            }
+        }
+    }
+}
+
+#[test]
+fn clone_panic_keeps_cloned_items() {
+    use std::{panic, rc::Rc};
+    struct Bomb(Rc<()>, bool);
+    impl Clone for Bomb {
+        fn clone(&self) -> Self {
+            assert!(!self.1, "clone failed");
+            Bomb(self.0.clone(), false)
+        }
+    }
+    let count = Rc::new(());
+    let mut buffer = RingBuffer::new();
+    let slice = [Bomb(count.clone(), false), Bomb(count.clone(), false), Bomb(count.clone(), true)];
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| buffer.extend_from_slice_clone(&slice)));
+    assert!(result.is_err());
+    assert_eq!(buffer.len(), 2);
+    drop((buffer, slice));
+    assert_eq!(Rc::strong_count(&count), 1);
+}
@@ -0,0 +1,68 @@
+//! A smoke test of the C interface from C, compiled against the generated header.
+#![cfg(all(feature = "capi", not(feature = "no_std"), target_os = "linux"))]
+
+use std::{ffi::{c_char, c_int, c_void, CStr, CString}, path::Path};
+use circulate::capi::*;
+
+/// The table of functions `tests/capi/smoke.c` calls, as the test binary does not export them.
+#[repr(C)]
+struct Api {
+    new: extern "C" fn(usize) -> *mut circulate_ring_t,
+    free: unsafe extern "C" fn(*mut circulate_ring_t),
+    push: unsafe extern "C" fn(*mut circulate_ring_t, *const u8, usize) -> isize,
+    pop: unsafe extern "C" fn(*mut circulate_ring_t, *mut u8, usize) -> isize,
+    len: unsafe extern "C" fn(*mut circulate_ring_t) -> isize,
+    capacity: unsafe extern "C" fn(*mut circulate_ring_t) -> isize,
+    grant: unsafe extern "C" fn(*mut circulate_ring_t, *mut *mut u8) -> isize,
+    commit: unsafe extern "C" fn(*mut circulate_ring_t, usize) -> isize,
+}
+
+extern "C" {
+    fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlerror() -> *const c_char;
+}
+const RTLD_NOW: c_int = 2;
+
+#[test]
+fn smoke() {
+    let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/capi/smoke.c");
+    let library = std::env::temp_dir().join(format!("circulate-capi-{}.so", std::process::id()));
+    let compiler = cc::Build::new()
+        .target(env!("TARGET"))
+        .host(env!("HOST"))
+        .include(env!("OUT_DIR"))
+        .opt_level(0)
+        .cargo_metadata(false)
+        .warnings_into_errors(true)
+        .get_compiler();
+    let status = compiler.to_command()
+        .args(["-shared", "-fPIC", "-o"])
+        .arg(&library)
+        .arg(&source)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let api = Api {
+        new: circulate_ring_new,
+        free: circulate_ring_free,
+        push: circulate_ring_push,
+        pop: circulate_ring_pop,
+        len: circulate_ring_len,
+        capacity: circulate_ring_capacity,
+        grant: circulate_ring_grant,
+        commit: circulate_ring_commit,
+    };
+    let path = CString::new(library.to_str().unwrap()).unwrap();
+    let failed = unsafe {
+        let handle = dlopen(path.as_ptr(), RTLD_NOW);
+        assert!(!handle.is_null(), "{:?}", CStr::from_ptr(dlerror()));
+        let smoke = dlsym(handle, c"circulate_smoke".as_ptr());
+        assert!(!smoke.is_null());
+        let smoke: unsafe extern "C" fn(*const Api) -> c_int = std::mem::transmute(smoke);
+        smoke(&api)
+    };
+    std::fs::remove_file(&library).unwrap();
+    assert_eq!(failed, 0, "check failed on line {failed} of smoke.c");
+}
@@ -0,0 +1,62 @@
+/* Exercises the C interface through a table of its functions, filled in by `tests/capi.rs`. */
+#include <stdint.h>
+#include <string.h>
+#include "circulate.h"
+
+struct circulate_api {
+    __typeof__(circulate_ring_new) *new_;
+    __typeof__(circulate_ring_free) *free;
+    __typeof__(circulate_ring_push) *push;
+    __typeof__(circulate_ring_pop) *pop;
+    __typeof__(circulate_ring_len) *len;
+    __typeof__(circulate_ring_capacity) *capacity;
+    __typeof__(circulate_ring_grant) *grant;
+    __typeof__(circulate_ring_commit) *commit;
+};
+
+#define CHECK(condition) do { if (!(condition)) return __LINE__; } while (0)
+
+/* Returns 0, or the line of the first check that failed. */
+int circulate_smoke(const struct circulate_api *api) {
+    uint8_t out[16];
+    uint8_t *region;
+    ptrdiff_t granted;
+    circulate_ring_t *ring = api->new_(5);
+    CHECK(ring != NULL);
+    CHECK(api->capacity(ring) >= 5);
+    ptrdiff_t capacity = api->capacity(ring);
+
+    /* Pushing stops when the ring is full. */
+    CHECK(api->push(ring, (const uint8_t *)"hello world", 11) == capacity);
+    CHECK(api->len(ring) == capacity);
+    CHECK(api->push(ring, (const uint8_t *)"!", 1) == 0);
+    CHECK(api->pop(ring, out, 3) == 3);
+    CHECK(memcmp(out, "hel", 3) == 0);
+
+    /* Write in place, which blocks pushing until committed. */
+    granted = api->grant(ring, &region);
+    CHECK(granted > 0 && granted <= 3);
+    CHECK(api->push(ring, (const uint8_t *)"!", 1) == CIRCULATE_ERROR_GRANTED);
+    CHECK(api->commit(ring, granted + 1) == CIRCULATE_ERROR_GRANTED);
+    memcpy(region, "XYZ", granted);
+    CHECK(api->commit(ring, granted) == 0);
+    CHECK(api->commit(ring, 0) == CIRCULATE_ERROR_GRANTED);
+    CHECK(api->len(ring) == capacity - 3 + granted);
+
+    /* Everything comes back out in order. */
+    CHECK(api->pop(ring, out, sizeof out) == capacity - 3 + granted);
+    CHECK(memcmp(out, "lo world", capacity - 3) == 0);
+    CHECK(memcmp(out + capacity - 3, "XYZ", granted) == 0);
+    CHECK(api->len(ring) == 0);
+    CHECK(api->pop(ring, NULL, 0) == 0);
+    CHECK(api->pop(ring, NULL, 1) == CIRCULATE_ERROR_NULL);
+    api->free(ring);
+
+    /* Null rings, and rings that cannot be allocated, including where the allocation panics. */
+    CHECK(api->len(NULL) == CIRCULATE_ERROR_NULL);
+    CHECK(api->grant(NULL, &region) == CIRCULATE_ERROR_NULL);
+    api->free(NULL);
+    CHECK(api->new_(SIZE_MAX - 1) == NULL);
+    CHECK(api->new_(SIZE_MAX - 16) == NULL);
+    return 0;
+}
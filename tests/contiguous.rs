@@ -0,0 +1,78 @@
+//! Checks `make_contiguous` and `shrink_to_fit` on wrapped buffers of items that need dropping, at
+//! every offset and length, so that Miri can catch items that are duplicated or leaked as they move.
+#![cfg(feature = "alloc")]
+
+use std::rc::Rc;
+use circulate::RingBuffer;
+
+/// Fill a buffer with the items `0..len` starting `offset` slots in to the allocation.
+fn wrapped(capacity: usize, exact: bool, offset: usize, len: usize, counter: &Rc<()>) -> RingBuffer<(u32, Rc<()>)> {
+    let mut buffer = if exact { RingBuffer::with_exact_capacity(capacity) } else { RingBuffer::with_capacity(capacity) };
+    for _ in 0..offset {
+        buffer.push((u32::MAX, counter.clone())).unwrap();
+        buffer.pop();
+    }
+    for value in 0..len as u32 {
+        buffer.push((value, counter.clone())).unwrap();
+    }
+    buffer
+}
+
+fn values(items: &[(u32, Rc<()>)]) -> Vec<u32> {
+    items.iter().map(|(value, _)| *value).collect()
+}
+
+#[test]
+fn make_contiguous_every_layout() {
+    let counter = Rc::new(());
+    let mut was_wrapped = 0;
+    for (capacity, exact) in [(7, false), (5, true), (6, true)] {
+        for offset in 0..=capacity {
+            for len in 0..=capacity {
+                let mut buffer = wrapped(capacity, exact, offset, len, &counter);
+                let (_, rhs) = buffer.as_slices();
+                was_wrapped += usize::from(!rhs.is_empty());
+                let expected: Vec<u32> = (0..len as u32).collect();
+                assert_eq!(values(buffer.make_contiguous()), expected, "capacity {capacity}, offset {offset}, len {len}");
+                assert_eq!(values(buffer.as_slices().0), expected);
+                assert!(buffer.as_slices().1.is_empty());
+                // Once contiguous the items stay where they are.
+                let start = buffer.as_slices().0.as_ptr();
+                assert_eq!(buffer.make_contiguous().as_ptr(), start);
+                assert_eq!(Rc::strong_count(&counter), len + 1);
+                buffer.push((len as u32, counter.clone())).unwrap();
+                assert_eq!(buffer.pop().map(|(value, _)| value), Some(0));
+                drop(buffer);
+                assert_eq!(Rc::strong_count(&counter), 1);
+            }
+        }
+    }
+    assert!(was_wrapped != 0);
+}
+
+#[test]
+fn shrink_to_fit_wrapped() {
+    let counter = Rc::new(());
+    for exact in [false, true] {
+        for offset in (0..64).step_by(7) {
+            for len in [0, 1, 3, 20] {
+                let mut buffer = wrapped(63, exact, offset, 60, &counter);
+                buffer.drain(60 - len);
+                buffer.shrink_to_fit();
+                let expected: Vec<u32> = (60 - len as u32..60).collect();
+                assert!(buffer.iter().map(|(value, _)| *value).eq(expected.iter().copied()));
+                assert_eq!(Rc::strong_count(&counter), len + 1);
+                let capacity = buffer.capacity();
+                match exact {
+                    true => assert_eq!(capacity, len + 1),
+                    false => assert_eq!(capacity, (len + 1).next_power_of_two().max(2)),
+                }
+                // The buffer still grows again afterwards.
+                buffer.extend((0..10).map(|value| (value, counter.clone())));
+                assert_eq!(buffer.len(), len + 10);
+                drop(buffer);
+                assert_eq!(Rc::strong_count(&counter), 1);
+            }
+        }
+    }
+}
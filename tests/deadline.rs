@@ -0,0 +1,103 @@
+//! Checks the age of the oldest unflushed output of a `BufStream` as output is buffered, partly
+//! written, written straight to the stream and discarded.
+#![cfg(all(feature = "alloc", not(feature = "no_std")))]
+
+use std::{cell::Cell, io, rc::Rc, thread, time::Duration};
+use circulate::{BufStream, FromStd, Write, WriteExt};
+
+const TICK: Duration = Duration::from_millis(5);
+
+/// Accepts up to `budget` bytes, and then would block.
+struct Sink {
+    budget: Rc<Cell<usize>>,
+    sent: Vec<u8>,
+}
+impl io::Read for Sink {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Ok(0)
+    }
+}
+impl io::Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.budget.get());
+        if len == 0 && !buf.is_empty() {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        self.budget.set(self.budget.get() - len);
+        self.sent.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn stream(budget: usize) -> (BufStream<FromStd<Sink>>, Rc<Cell<usize>>) {
+    let budget = Rc::new(Cell::new(budget));
+    (BufStream::new(FromStd(Sink { budget: budget.clone(), sent: Vec::new() })), budget)
+}
+
+#[test]
+fn partial_flush() {
+    let (mut stream, budget) = stream(0);
+    assert_eq!(stream.oldest_unflushed_age(), None);
+    stream.write(b"abc").unwrap();
+    thread::sleep(TICK);
+    stream.write(b"def").unwrap();
+
+    // The output left after a partial flush is as old as the oldest output was.
+    budget.set(4);
+    let progress = stream.flush_nonblocking().unwrap();
+    assert_eq!((progress.written(), progress.remaining()), (4, 2));
+    assert!(stream.oldest_unflushed_age().unwrap() >= TICK);
+    assert!(stream.flush_if_older_than(TICK).is_err());
+    assert!(stream.oldest_unflushed_age().unwrap() >= TICK);
+
+    budget.set(usize::MAX);
+    assert!(!stream.flush_if_older_than(Duration::from_secs(60)).unwrap());
+    assert!(stream.flush_if_older_than(TICK).unwrap());
+    assert_eq!(stream.oldest_unflushed_age(), None);
+    assert_eq!(stream.get_ref().0.sent, b"abcdef");
+
+    // Output buffered once the rest was written is timed afresh.
+    thread::sleep(TICK);
+    stream.write(b"g").unwrap();
+    assert!(stream.oldest_unflushed_age().unwrap() < TICK);
+}
+
+#[test]
+fn bypass_write() {
+    let (mut stream, _) = stream(usize::MAX);
+    let varint = |len: usize| ([len as u8 | 0x80, (len >> 7) as u8 | 0x80, (len >> 14) as u8], 3);
+
+    // A large frame goes straight to the stream, so is never waiting.
+    stream.write_framed(&[1; 20_000], varint).unwrap();
+    assert_eq!(stream.oldest_unflushed_age(), None);
+
+    // Output buffered ahead of the frame is written first.
+    stream.write(b"buffered").unwrap();
+    assert!(stream.oldest_unflushed_age().is_some());
+    stream.write_framed(&[2; 20_000], varint).unwrap();
+    assert_eq!(stream.oldest_unflushed_age(), None);
+    assert!(!stream.flush_if_older_than(Duration::ZERO).unwrap());
+
+    // A small frame is buffered.
+    stream.write_framed(&[3; 10], varint).unwrap();
+    assert!(stream.flush_if_older_than(Duration::ZERO).unwrap());
+    assert_eq!(stream.get_ref().0.sent.len(), 3 + 20_000 + 8 + 3 + 20_000 + 3 + 10);
+}
+
+#[test]
+fn discard_output() {
+    let (mut stream, _) = stream(0);
+    stream.write(b"lost").unwrap();
+    stream.write_chunk(b" chunk".to_vec());
+    thread::sleep(TICK);
+    assert!(stream.oldest_unflushed_age().unwrap() >= TICK);
+    assert_eq!(stream.discard_output(), 10);
+    assert_eq!(stream.oldest_unflushed_age(), None);
+    assert!(!stream.flush_if_older_than(Duration::ZERO).unwrap());
+
+    stream.write_zeroes(3).unwrap();
+    assert!(stream.oldest_unflushed_age().unwrap() < TICK);
+}
@@ -0,0 +1,141 @@
+//! Checks [`RingBuffer`] used as a double-ended queue against a `VecDeque`, pushing and popping at
+//! both ends across several growths of the buffer, and that the items keep their absolute indices.
+#![cfg(feature = "alloc")]
+
+use std::collections::VecDeque;
+use circulate::RingBuffer;
+
+mod common;
+use common::Rng;
+
+fn check(buffer: &RingBuffer<u32>, expected: &VecDeque<u32>) {
+    assert_eq!(buffer.len(), expected.len());
+    assert!(buffer.iter().eq(expected.iter()));
+    assert_eq!(buffer.front(), expected.front());
+    assert_eq!(buffer.back(), expected.back());
+    assert_eq!(buffer.total_pushed() - buffer.total_popped(), expected.len() as u64);
+    assert!(buffer.len() < buffer.capacity().max(1));
+}
+
+#[test]
+fn interleaved_pushes_grow() {
+    for exact in [false, true] {
+        let mut buffer = if exact { RingBuffer::with_exact_capacity(3) } else { RingBuffer::new() };
+        let mut expected = VecDeque::new();
+        let mut capacities = vec![buffer.capacity()];
+        for value in 0..200 {
+            if value % 3 == 0 {
+                buffer.push(value).unwrap();
+                expected.push_back(value);
+            } else {
+                buffer.push_front(value).unwrap();
+                expected.push_front(value);
+            }
+            check(&buffer, &expected);
+            if capacities.last() != Some(&buffer.capacity()) {
+                capacities.push(buffer.capacity());
+            }
+        }
+        assert!(capacities.len() > 3, "{capacities:?}");
+    }
+}
+
+#[test]
+fn random_operations() {
+    let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+    for max in [None, Some(8)] {
+        let mut buffer = RingBuffer::new();
+        if let Some(max) = max {
+            buffer.set_max_capacity(max);
+        }
+        let mut expected = VecDeque::new();
+        for value in 0..20_000 {
+            match rng.below(6) {
+                0 | 1 => match buffer.push(value) {
+                    Ok(()) => expected.push_back(value),
+                    Err(returned) => assert!(returned == value && buffer.is_full()),
+                },
+                2 | 3 => match buffer.push_front(value) {
+                    Ok(()) => expected.push_front(value),
+                    Err(returned) => assert!(returned == value && buffer.is_full()),
+                },
+                4 => assert_eq!(buffer.pop(), expected.pop_front()),
+                _ => assert_eq!(buffer.pop_back(), expected.pop_back()),
+            }
+            if let (Some(front), Some(back)) = (buffer.front_mut(), expected.front_mut()) {
+                *front += 1;
+                *back += 1;
+            }
+            if let (Some(front), Some(back)) = (buffer.back_mut(), expected.back_mut()) {
+                *front ^= 1;
+                *back ^= 1;
+            }
+            check(&buffer, &expected);
+        }
+    }
+}
+
+#[test]
+fn pop_back_drops_once() {
+    use std::rc::Rc;
+    let item = Rc::new(());
+    let mut buffer = RingBuffer::with_exact_capacity(4);
+    for _ in 0..3 {
+        buffer.push_front(item.clone()).unwrap();
+        buffer.push(item.clone()).unwrap();
+        drop(buffer.pop_back());
+    }
+    assert_eq!(Rc::strong_count(&item), 4);
+    drop(buffer);
+    assert_eq!(Rc::strong_count(&item), 1);
+}
+
+#[test]
+fn absolute_indices_at_both_ends() {
+    let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+    let mut buffer = RingBuffer::with_capacity(4);
+    // Each item is its absolute index, which stays the same while it is buffered.
+    let mut expected = VecDeque::new();
+    let mut front = 0u64;
+    // The totals count pushes and pops at both ends, so only ever grow.
+    let (mut pushed, mut popped) = (0, 0);
+    for _ in 0..2000 {
+        match rng.below(4) {
+            0 => {
+                let absolute = front.wrapping_add(expected.len() as u64);
+                buffer.push(absolute).unwrap();
+                expected.push_back(absolute);
+                pushed += 1;
+            }
+            1 => {
+                front = front.wrapping_sub(1);
+                buffer.push_front(front).unwrap();
+                expected.push_front(front);
+                pushed += 1;
+            }
+            2 => {
+                let item = buffer.pop();
+                assert_eq!(item, expected.pop_front());
+                if item.is_some() {
+                    front = front.wrapping_add(1);
+                    popped += 1;
+                }
+            }
+            _ => {
+                let item = buffer.pop_back();
+                assert_eq!(item, expected.pop_back());
+                if item.is_some() {
+                    popped += 1;
+                }
+            }
+        }
+        assert_eq!((buffer.total_pushed(), buffer.total_popped()), (pushed, popped));
+        for (logical, &absolute) in expected.iter().enumerate() {
+            assert_eq!(buffer.absolute_index_of(logical), Some(absolute));
+            assert_eq!(buffer.logical_index_of(absolute), Some(logical));
+        }
+        assert_eq!(buffer.absolute_index_of(expected.len()), None);
+        assert_eq!(buffer.logical_index_of(front.wrapping_sub(1)), None);
+        assert_eq!(buffer.logical_index_of(front.wrapping_add(expected.len() as u64)), None);
+    }
+}
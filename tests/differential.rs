@@ -0,0 +1,507 @@
+//! Differential testing of [`BufStream`] against [`std::io::BufReader`] and
+//! [`std::io::BufWriter`], running the same random operations on both over identical scripted
+//! streams and comparing the bytes each side reads and writes.
+//!
+//! Where the semantics intentionally differ the harness normalizes: reads may return any number
+//! of bytes so only the bytes read are compared, peeked input is compared up to the shorter of
+//! the two, and transient errors such as [`io::ErrorKind::WouldBlock`] are retried. A failing
+//! sequence of operations is shrunk before it is reported.
+#![cfg(all(feature = "alloc", not(feature = "no_std")))]
+
+use std::{cell::RefCell, io::{self, BufRead, BufReader, BufWriter, Cursor, IoSliceMut, Seek, SeekFrom}, mem::MaybeUninit, rc::Rc};
+use circulate::{BufStream, FromStd, IoVecMut};
+
+mod common;
+use common::Rng;
+
+/// The number of seeded sequences run by each test.
+const SEEDS: u64 = 3000;
+/// The number of operations in each sequence.
+const OPS: usize = 48;
+
+/// A socket-like stream that reads from `input` and writes to `sink`, with short reads and writes
+/// and transient errors at random.
+struct Flaky {
+    input: Vec<u8>,
+    read: usize,
+    sink: Vec<u8>,
+    rng: Rng,
+}
+impl Flaky {
+    fn new(input: Vec<u8>, seed: u64) -> Self {
+        Self { input, read: 0, sink: Vec::new(), rng: Rng::new(seed) }
+    }
+    fn fault(&mut self) -> io::Result<()> {
+        match self.rng.below(12) {
+            0 => Err(io::ErrorKind::WouldBlock.into()),
+            1 => Err(io::ErrorKind::Interrupted.into()),
+            _ => Ok(()),
+        }
+    }
+}
+impl io::Read for Flaky {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fault()?;
+        let len = buf.len().min(1 + self.rng.below(40)).min(self.input.len() - self.read);
+        buf[..len].copy_from_slice(&self.input[self.read..self.read + len]);
+        self.read += len;
+        Ok(len)
+    }
+}
+impl io::Write for Flaky {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.fault()?;
+        let len = buf.len().min(1 + self.rng.below(40));
+        self.sink.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.fault()
+    }
+}
+impl Seek for Flaky {
+    fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// A [`Flaky`] stream shared by a [`BufReader`] and a [`BufWriter`].
+#[derive(Clone)]
+struct Shared(Rc<RefCell<Flaky>>);
+impl io::Read for Shared {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.borrow_mut().read(buf)
+    }
+}
+impl io::Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+impl Seek for Shared {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.0.borrow_mut().seek(position)
+    }
+}
+
+/// An operation, with its arguments, run on both sides.
+#[derive(Debug, Clone, PartialEq)]
+enum Op {
+    /// A single read in to a buffer of this length.
+    Read(usize),
+    /// Reads until this many bytes are read or the stream ends.
+    ReadFull(usize),
+    /// A single vectored read in to buffers of these lengths.
+    ReadVectored(Vec<usize>),
+    /// Peek at the buffered input, reading if there is none, and consume up to this many bytes.
+    PeekConsume(usize),
+    /// Write all of these bytes.
+    Write(Vec<u8>),
+    Flush,
+    Seek(SeekFrom),
+    Position,
+}
+impl Op {
+    /// Simpler versions of this operation, for shrinking.
+    fn simplify(&self) -> Vec<Op> {
+        let halve = |len: usize| if len > 1 { vec![len / 2, len - 1] } else { Vec::new() };
+        match self {
+            Op::Read(len) => halve(*len).into_iter().map(Op::Read).collect(),
+            Op::ReadFull(len) => halve(*len).into_iter().map(Op::ReadFull).chain([Op::Read(*len)]).collect(),
+            Op::ReadVectored(lens) => (0..lens.len()).map(|skip| {
+                Op::ReadVectored(lens.iter().enumerate().filter(|(i, _)| *i != skip).map(|(_, len)| *len).collect())
+            }).collect(),
+            Op::PeekConsume(len) => halve(*len).into_iter().map(Op::PeekConsume).collect(),
+            Op::Write(bytes) if bytes.len() > 1 => vec![Op::Write(bytes[..bytes.len() / 2].to_vec()), Op::Write(bytes[1..].to_vec())],
+            Op::Seek(SeekFrom::Current(offset)) if *offset != 0 => vec![Op::Seek(SeekFrom::Current(offset / 2))],
+            Op::Seek(SeekFrom::Start(offset)) if *offset != 0 => vec![Op::Seek(SeekFrom::Start(offset / 2))],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The operations both sides support, with any transient error returned to the harness.
+trait Side {
+    fn read(&mut self, len: usize) -> io::Result<Vec<u8>>;
+    fn read_vectored(&mut self, lens: &[usize]) -> io::Result<Vec<u8>>;
+    fn peek(&mut self) -> io::Result<Vec<u8>>;
+    fn consume(&mut self, count: usize);
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize>;
+    fn flush(&mut self) -> io::Result<()>;
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64>;
+    fn position(&mut self) -> io::Result<u64>;
+    /// Get everything written to the underlying stream.
+    fn contents(&self) -> Vec<u8>;
+}
+
+/// The underlying streams, which let a side see what was written to them.
+trait Contents {
+    fn contents(&self) -> Vec<u8>;
+}
+impl Contents for Flaky {
+    fn contents(&self) -> Vec<u8> {
+        self.sink.clone()
+    }
+}
+impl Contents for Shared {
+    fn contents(&self) -> Vec<u8> {
+        self.0.borrow().sink.clone()
+    }
+}
+impl Contents for Cursor<Vec<u8>> {
+    fn contents(&self) -> Vec<u8> {
+        self.get_ref().clone()
+    }
+}
+
+impl<S: io::Read + io::Write + Seek + Contents> Side for BufStream<FromStd<S>> {
+    fn read(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![MaybeUninit::uninit(); len];
+        let read = circulate::Read::read(self, &mut buffer)?;
+        // Safety: The first `read` bytes were initialized by the read.
+        Ok(buffer[..read].iter().map(|byte| unsafe { byte.assume_init() }).collect())
+    }
+    fn read_vectored(&mut self, lens: &[usize]) -> io::Result<Vec<u8>> {
+        let mut buffers: Vec<Vec<u8>> = lens.iter().map(|len| vec![0; *len]).collect();
+        let mut iovecs: Vec<IoVecMut> = buffers.iter_mut().map(|buffer| IoVecMut::new(buffer)).collect();
+        let read = circulate::Read::read_vectored(self, &mut iovecs)?;
+        Ok(buffers.concat()[..read].to_vec())
+    }
+    fn peek(&mut self) -> io::Result<Vec<u8>> {
+        let (lhs, rhs) = self.peek_all_input()?;
+        Ok([lhs, rhs].concat())
+    }
+    fn consume(&mut self, count: usize) {
+        BufStream::consume(self, count)
+    }
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        circulate::Write::write(self, bytes)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        circulate::Write::flush(self)
+    }
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        Seek::seek(self, position)
+    }
+    fn position(&mut self) -> io::Result<u64> {
+        Seek::stream_position(self)
+    }
+    fn contents(&self) -> Vec<u8> {
+        self.get_ref().0.contents()
+    }
+}
+
+/// A [`BufReader`] and a [`BufWriter`], seeking and getting the contents of whichever is
+/// `seeks`.
+struct Std<R, W: io::Write> {
+    reader: BufReader<R>,
+    writer: BufWriter<W>,
+    seeks: Half,
+}
+enum Half {
+    Reader,
+    Writer,
+}
+impl<R: io::Read + Seek + Contents, W: io::Write + Seek + Contents> Side for Std<R, W> {
+    fn read(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0; len];
+        let read = io::Read::read(&mut self.reader, &mut buffer)?;
+        Ok(buffer[..read].to_vec())
+    }
+    fn read_vectored(&mut self, lens: &[usize]) -> io::Result<Vec<u8>> {
+        let mut buffers: Vec<Vec<u8>> = lens.iter().map(|len| vec![0; *len]).collect();
+        let mut slices: Vec<IoSliceMut> = buffers.iter_mut().map(|buffer| IoSliceMut::new(buffer)).collect();
+        let read = io::Read::read_vectored(&mut self.reader, &mut slices)?;
+        Ok(buffers.concat()[..read].to_vec())
+    }
+    fn peek(&mut self) -> io::Result<Vec<u8>> {
+        self.reader.fill_buf().map(<[u8]>::to_vec)
+    }
+    fn consume(&mut self, count: usize) {
+        self.reader.consume(count)
+    }
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        io::Write::write(&mut self.writer, bytes)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        io::Write::flush(&mut self.writer)
+    }
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        match self.seeks {
+            Half::Reader => self.reader.seek(position),
+            Half::Writer => self.writer.seek(position),
+        }
+    }
+    fn position(&mut self) -> io::Result<u64> {
+        match self.seeks {
+            Half::Reader => self.reader.stream_position(),
+            Half::Writer => self.writer.stream_position(),
+        }
+    }
+    fn contents(&self) -> Vec<u8> {
+        match self.seeks {
+            Half::Reader => self.reader.get_ref().contents(),
+            Half::Writer => self.writer.get_ref().contents(),
+        }
+    }
+}
+
+/// Retry `f` while it fails with a transient error.
+fn retry<T>(mut f: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    loop {
+        match f() {
+            Err(error) if matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted) => continue,
+            result => return result,
+        }
+    }
+}
+
+/// Everything a side was observed to do.
+#[derive(Debug, Default, PartialEq)]
+struct Observed {
+    /// Every byte read, in order.
+    read: Vec<u8>,
+    /// The results of seeking and getting the position.
+    positions: Vec<Result<u64, io::ErrorKind>>,
+}
+
+/// Run one operation on a side, returning a description of the first unexpected error.
+fn apply(side: &mut dyn Side, op: &Op, observed: &mut Observed, peek_limit: usize) -> Result<(), String> {
+    let error = |error: io::Error| format!("{op:?} failed with {error}");
+    match op {
+        Op::Read(len) => observed.read.extend(retry(|| side.read(*len)).map_err(error)?),
+        Op::ReadFull(len) => {
+            let start = observed.read.len();
+            while observed.read.len() - start < *len {
+                let remaining = *len - (observed.read.len() - start);
+                let bytes = retry(|| side.read(remaining.min(256))).map_err(error)?;
+                if bytes.is_empty() {
+                    break;
+                }
+                observed.read.extend(bytes);
+            }
+        }
+        Op::ReadVectored(lens) => observed.read.extend(retry(|| side.read_vectored(lens)).map_err(error)?),
+        Op::PeekConsume(_) => {
+            let peeked = retry(|| side.peek()).map_err(error)?;
+            observed.read.extend(&peeked[..peek_limit]);
+            side.consume(peek_limit);
+        }
+        Op::Write(bytes) => {
+            let mut bytes = &bytes[..];
+            while !bytes.is_empty() {
+                match retry(|| side.write(bytes)).map_err(error)? {
+                    0 => return Err(format!("{op:?} wrote nothing")),
+                    count => bytes = &bytes[count..],
+                }
+            }
+        }
+        Op::Flush => retry(|| side.flush()).map_err(error)?,
+        // Seeking before the start fails the same way on both sides.
+        Op::Seek(position) => observed.positions.push(retry(|| side.seek(*position)).map_err(|error| error.kind())),
+        Op::Position => observed.positions.push(retry(|| side.position()).map_err(|error| error.kind())),
+    }
+    Ok(())
+}
+
+/// Run `ops` on both sides, returning a description of the first difference.
+///
+/// Afterwards the rest of the input is read if `drain` is set, the output is flushed, and what
+/// was written to the underlying streams is compared.
+fn differ(ours: &mut dyn Side, theirs: &mut dyn Side, ops: &[Op], drain: bool) -> Result<(), String> {
+    let (mut a, mut b) = (Observed::default(), Observed::default());
+    for (index, op) in ops.iter().enumerate() {
+        // Both sides must consume the same bytes, so consume what both of them have peeked.
+        let peek_limit = match op {
+            Op::PeekConsume(len) => {
+                let ours = retry(|| ours.peek()).map_err(|error| format!("peek failed with {error}"))?;
+                let theirs = retry(|| theirs.peek()).map_err(|error| format!("peek failed with {error}"))?;
+                let common = ours.len().min(theirs.len());
+                if ours[..common] != theirs[..common] {
+                    return Err(format!("op {index}: peeked {ours:?}, expected {theirs:?}"));
+                }
+                if ours.is_empty() != theirs.is_empty() {
+                    return Err(format!("op {index}: peeked {ours:?}, expected {theirs:?}"));
+                }
+                common.min(*len)
+            }
+            _ => 0,
+        };
+        apply(ours, op, &mut a, peek_limit).map_err(|error| format!("op {index}: ours: {error}"))?;
+        apply(theirs, op, &mut b, peek_limit).map_err(|error| format!("op {index}: std: {error}"))?;
+        // Reads may return different amounts, so the side that read less catches up, keeping
+        // both at the same position.
+        let behind = a.read.len().abs_diff(b.read.len());
+        if a.read.len() < b.read.len() {
+            apply(ours, &Op::ReadFull(behind), &mut a, 0).map_err(|error| format!("op {index}: ours: {error}"))?;
+        } else if b.read.len() < a.read.len() {
+            apply(theirs, &Op::ReadFull(behind), &mut b, 0).map_err(|error| format!("op {index}: std: {error}"))?;
+        }
+        if a.read != b.read {
+            let common = a.read.len().min(b.read.len());
+            let start = a.read[..common].iter().zip(&b.read).take_while(|(a, b)| a == b).count();
+            return Err(format!("op {index}: read {:?} from {start}, expected {:?}", &a.read[start..], &b.read[start..]));
+        }
+        if a.positions != b.positions {
+            return Err(format!("op {index}: positions {:?}, expected {:?}", a.positions, b.positions));
+        }
+    }
+    let end: &[Op] = if drain { &[Op::ReadFull(usize::MAX), Op::Flush] } else { &[Op::Flush] };
+    for op in end {
+        apply(ours, op, &mut a, 0).map_err(|error| format!("end: ours: {error}"))?;
+        apply(theirs, op, &mut b, 0).map_err(|error| format!("end: std: {error}"))?;
+    }
+    if a != b {
+        return Err(format!("read {:?}, expected {:?}", a, b));
+    }
+    let (ours, theirs) = (ours.contents(), theirs.contents());
+    if ours != theirs {
+        return Err(format!("wrote {ours:?}, expected {theirs:?}"));
+    }
+    Ok(())
+}
+
+/// Remove and simplify operations while `fails` still holds.
+fn shrink(mut ops: Vec<Op>, fails: impl Fn(&[Op]) -> bool) -> Vec<Op> {
+    let mut chunk = ops.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < ops.len() {
+            let mut candidate = ops.clone();
+            candidate.drain(start..(start + chunk).min(ops.len()));
+            if fails(&candidate) {
+                ops = candidate;
+            } else {
+                start += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+    let mut index = 0;
+    while index < ops.len() {
+        match ops[index].simplify().into_iter().find(|simpler| {
+            let mut candidate = ops.clone();
+            candidate[index] = simpler.clone();
+            fails(&candidate)
+        }) {
+            Some(simpler) => ops[index] = simpler,
+            None => index += 1,
+        }
+    }
+    ops
+}
+
+/// Run `SEEDS` random sequences made by `generate`, shrinking and reporting the first failure.
+fn soak(generate: impl Fn(&mut Rng) -> Op, run: impl Fn(u64, &[Op]) -> Result<(), String>) {
+    for seed in 0..SEEDS {
+        let mut rng = Rng::new(seed);
+        let ops: Vec<Op> = (0..OPS).map(|_| generate(&mut rng)).collect();
+        if run(seed, &ops).is_err() {
+            let ops = shrink(ops, |ops| run(seed, ops).is_err());
+            let error = run(seed, &ops).unwrap_err();
+            panic!("seed {seed} failed: {error}\nshrunk to {ops:#?}");
+        }
+    }
+}
+
+fn random_read(rng: &mut Rng) -> Op {
+    match rng.below(4) {
+        0 => Op::Read(rng.below(80)),
+        1 => Op::ReadFull(rng.below(80)),
+        2 => Op::ReadVectored((0..rng.below(4)).map(|_| rng.below(24)).collect()),
+        _ => Op::PeekConsume(rng.below(40)),
+    }
+}
+fn random_write(rng: &mut Rng) -> Op {
+    match rng.below(4) {
+        0 => Op::Flush,
+        _ => {
+            let len = rng.below(80);
+            Op::Write(rng.bytes(len))
+        }
+    }
+}
+fn random_seek(rng: &mut Rng, len: usize) -> Op {
+    match rng.below(4) {
+        0 => Op::Seek(SeekFrom::Start(rng.below(len + 8) as u64)),
+        1 => Op::Seek(SeekFrom::Current(rng.below(64) as i64 - 32)),
+        2 => Op::Seek(SeekFrom::End(-(rng.below(len + 1) as i64))),
+        _ => Op::Position,
+    }
+}
+
+#[test]
+fn socket() {
+    soak(
+        |rng| if rng.below(2) == 0 { random_read(rng) } else { random_write(rng) },
+        |seed, ops| {
+            let mut rng = Rng::new(!seed);
+            let len = rng.below(2048);
+            let input = rng.bytes(len);
+            let mut ours = BufStream::with_capacity(FromStd(Flaky::new(input.clone(), seed)), 1 + rng.below(64));
+            let shared = Shared(Rc::new(RefCell::new(Flaky::new(input, seed))));
+            let capacity = 1 + rng.below(64);
+            let mut theirs = Std {
+                reader: BufReader::with_capacity(capacity, shared.clone()),
+                writer: BufWriter::with_capacity(capacity, shared),
+                seeks: Half::Reader,
+            };
+            differ(&mut ours, &mut theirs, ops, true)
+        },
+    );
+}
+
+#[test]
+fn read_and_seek() {
+    const LEN: usize = 600;
+    soak(
+        |rng| if rng.below(3) == 0 { random_seek(rng, LEN) } else { random_read(rng) },
+        |seed, ops| {
+            let mut rng = Rng::new(!seed);
+            let input = rng.bytes(LEN);
+            let mut ours = BufStream::with_capacity(FromStd(Cursor::new(input.clone())), 1 + rng.below(64));
+            let mut theirs = Std {
+                reader: BufReader::with_capacity(1 + rng.below(64), Cursor::new(input)),
+                writer: BufWriter::new(Cursor::new(Vec::new())),
+                seeks: Half::Reader,
+            };
+            differ(&mut ours, &mut theirs, ops, true)
+        },
+    );
+}
+
+#[test]
+fn write_and_seek() {
+    soak(
+        |rng| if rng.below(3) == 0 { random_seek(rng, 256) } else { random_write(rng) },
+        |seed, ops| {
+            let mut rng = Rng::new(!seed);
+            let mut ours = BufStream::with_capacity(FromStd(Cursor::new(Vec::new())), 1 + rng.below(64));
+            let mut theirs = Std {
+                reader: BufReader::new(Cursor::new(Vec::new())),
+                writer: BufWriter::with_capacity(1 + rng.below(64), Cursor::new(Vec::new())),
+                seeks: Half::Writer,
+            };
+            differ(&mut ours, &mut theirs, ops, false)
+        },
+    );
+}
+
+#[test]
+fn shrinking_finds_a_minimal_sequence() {
+    // A stand-in failure: reading a byte after writing one.
+    let fails = |ops: &[Op]| {
+        let write = ops.iter().position(|op| matches!(op, Op::Write(bytes) if !bytes.is_empty()));
+        write.is_some_and(|write| ops[write..].iter().any(|op| matches!(op, Op::Read(len) if *len > 0)))
+    };
+    let mut rng = Rng::new(1);
+    let ops: Vec<Op> = (0..OPS).map(|_| if rng.below(2) == 0 { random_read(&mut rng) } else { random_write(&mut rng) }).collect();
+    assert!(fails(&ops));
+    let shrunk = shrink(ops, fails);
+    assert_eq!(shrunk.len(), 2);
+    assert!(matches!(&shrunk[0], Op::Write(bytes) if bytes.len() == 1));
+    assert_eq!(shrunk[1], Op::Read(1));
+}
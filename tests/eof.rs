@@ -0,0 +1,95 @@
+//! Checks that every read needing a number of bytes reports a stream that ends too early the same
+//! way, as an [`Eof`] of how many bytes were needed and obtained, and keeps the bytes obtained
+//! buffered.
+//!
+//! Run with `cargo test --features test-util --test eof`.
+#![cfg(all(feature = "test-util", feature = "alloc", not(feature = "no_std")))]
+
+use std::io::Cursor;
+use circulate::{test_util::TruncatedReader, BufStream, CharReadError, Eof, FillStatus, RequestError, ResponseSpec};
+
+type Stream = BufStream<TruncatedReader<Cursor<Vec<u8>>>>;
+
+/// Generate a test for each read, which runs it on `$data` ending at every offset and with
+/// several read sizes. The read returns the [`Eof`] it reported, if any, and needs `$needed`
+/// bytes, or as many as the stream has for reads with no length up front.
+macro_rules! eof_matrix {
+    ($($name:ident($data:expr, $needed:expr) => |$stream:ident| $read:expr;)*) => {$(
+        #[test]
+        fn $name() {
+            let data: &[u8] = $data;
+            let needed: fn(usize) -> usize = $needed;
+            for end in 0..=data.len() {
+                for max_read in [1, 2, 3, usize::MAX] {
+                    let mut reader = TruncatedReader::new(Cursor::new(data.to_vec()), end);
+                    reader.set_max_read(max_read);
+                    let mut stream: Stream = BufStream::with_capacity(reader, 4);
+                    let eof: Option<Eof> = (|$stream: &mut Stream| $read)(&mut stream);
+                    if end >= needed(end) {
+                        assert_eq!(eof, None, "end {end}, max read {max_read}");
+                        continue;
+                    }
+                    assert_eq!(eof, Some(Eof { needed: needed(end), got: end }), "end {end}, max read {max_read}");
+                    let (lhs, rhs) = stream.buffered_input();
+                    assert_eq!([lhs, rhs].concat(), data[..end], "end {end}, max read {max_read}");
+                }
+            }
+        }
+    )*};
+}
+
+eof_matrix! {
+    try_fill(b"0123456789", |_| 10) => |stream| loop {
+        match stream.try_fill(10).unwrap() {
+            FillStatus::Partial(_) => continue,
+            FillStatus::Eof(eof) => break Some(eof),
+            _ => break None,
+        }
+    };
+    fill_at_least(b"0123456789", |_| 10) => |stream| match stream.fill_at_least(10).unwrap() {
+        FillStatus::Eof(eof) => Some(eof),
+        _ => None,
+    };
+    fill_at_least_until(b"0123456789", |_| 10) => |stream| match stream.fill_at_least_until(10, || false).unwrap() {
+        FillStatus::Eof(eof) => Some(eof),
+        _ => None,
+    };
+    read_char("😀".as_bytes(), |end| if end == 0 { 0 } else { 4 }) => |stream| match stream.read_char() {
+        Err(CharReadError::UnexpectedEof(eof)) => Some(eof),
+        Ok(char) => {
+            assert!(matches!(char, None | Some('😀')));
+            None
+        }
+        Err(error) => panic!("{error}"),
+    };
+    chars("😀".as_bytes(), |end| if end == 0 { 0 } else { 4 }) => |stream| {
+        let mut chars = stream.chars();
+        let eof = match chars.next() {
+            Some(Err(CharReadError::UnexpectedEof(eof))) => Some(eof),
+            _ => None,
+        };
+        assert!(eof.is_none() || chars.next().is_none());
+        eof
+    };
+    request_exact(b"0123456789", |_| 10) => |stream| match stream.request(b"ping", ResponseSpec::Exact(10), 10) {
+        Err(RequestError::UnexpectedEof(eof)) => Some(eof),
+        result => {
+            assert!(result.unwrap().iter().eq(b"0123456789"));
+            None
+        }
+    };
+    request_until(b"response\n", |end| if end == 9 { 9 } else { end + 1 }) => |stream| match stream.request(b"ping", ResponseSpec::Until(b'\n'), 64) {
+        Err(RequestError::UnexpectedEof(eof)) => Some(eof),
+        result => {
+            assert!(result.unwrap().iter().eq(b"response"));
+            None
+        }
+    };
+    request_until_seq(b"response\r\n", |end| if end == 10 { 10 } else { end + 1 }) => |stream| match stream.request(b"ping", ResponseSpec::UntilSeq(b"\r\n"), 64) {
+        Err(RequestError::UnexpectedEof(eof)) => Some(eof),
+        result => {
+            assert!(result.unwrap().iter().eq(b"response"));
+            None
+        }
+    };
+}
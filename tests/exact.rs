@@ -0,0 +1,101 @@
+//! Checks the looping helpers of [`Read`] and [`WriteExt`] against readers that return a byte at a
+//! time or end early, and writers that accept a byte at a time or stop accepting bytes.
+#![cfg(feature = "alloc")]
+
+use core::{convert::Infallible, mem::MaybeUninit};
+use circulate::{Eof, Read, ReadExactError, RingBuffer, Write, WriteAllError, WriteExt};
+
+/// Returns at most one byte per read, and then no more once `bytes` run out.
+struct OneByte<'a> {
+    bytes: &'a [u8],
+    reads: usize,
+}
+impl Read for OneByte<'_> {
+    type Error = Infallible;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Infallible> {
+        self.reads += 1;
+        match (self.bytes.split_first(), buffer.first_mut()) {
+            (Some((byte, rest)), Some(slot)) => {
+                *slot = MaybeUninit::new(*byte);
+                self.bytes = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+/// Accepts one byte per write until `limit` bytes are written, and then accepts none.
+struct Limited {
+    written: Vec<u8>,
+    limit: usize,
+}
+impl Write for Limited {
+    type Error = Infallible;
+    fn write(&mut self, slice: &[u8]) -> Result<usize, Infallible> {
+        match slice.first() {
+            Some(byte) if self.written.len() < self.limit => {
+                self.written.push(*byte);
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+    fn flush(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+fn assume_init(buffer: &[MaybeUninit<u8>]) -> Vec<u8> {
+    buffer.iter().map(|byte| unsafe { byte.assume_init() }).collect()
+}
+
+#[test]
+fn read_exact_one_byte_at_a_time() {
+    let mut reader = OneByte { bytes: b"hello world", reads: 0 };
+    let mut buffer = [MaybeUninit::uninit(); 5];
+    reader.read_exact(&mut buffer).unwrap();
+    assert_eq!(assume_init(&buffer), b"hello");
+    assert_eq!(reader.reads, 5);
+    reader.read_exact(&mut []).unwrap();
+    assert_eq!(reader.reads, 5);
+}
+
+#[test]
+fn read_exact_ends_early() {
+    let mut reader = OneByte { bytes: b"abc", reads: 0 };
+    let mut buffer = [MaybeUninit::uninit(); 8];
+    let result = reader.read_exact(&mut buffer);
+    assert_eq!(result, Err(ReadExactError::UnexpectedEof(Eof { needed: 8, got: 3 })));
+    // The bytes that were read are kept.
+    assert_eq!(assume_init(&buffer[..3]), b"abc");
+    assert_eq!(result.unwrap_err().to_string(), "stream ended after 3 of 8 bytes");
+}
+
+#[test]
+fn read_to_end_one_byte_at_a_time() {
+    let data: Vec<u8> = (0..=255).collect();
+    let mut reader = OneByte { bytes: &data, reads: 0 };
+    let mut buffer = RingBuffer::new();
+    buffer.extend_from_slice(b"prefix").unwrap();
+    assert_eq!(reader.read_to_end(&mut buffer).unwrap(), 256);
+    assert!(buffer.iter().eq(b"prefix".iter().chain(&data)));
+    assert_eq!(reader.read_to_end(&mut buffer).unwrap(), 0);
+}
+
+#[test]
+fn write_all_one_byte_at_a_time() {
+    let mut writer = Limited { written: Vec::new(), limit: usize::MAX };
+    writer.write_all(b"hello").unwrap();
+    writer.write_all(b"").unwrap();
+    writer.write_all(b" world").unwrap();
+    assert_eq!(writer.written, b"hello world");
+}
+
+#[test]
+fn write_all_stops_early() {
+    let mut writer = Limited { written: Vec::new(), limit: 4 };
+    let result = writer.write_all(b"abcdef");
+    assert_eq!(result, Err(WriteAllError::WriteZero { bytes_so_far: 4 }));
+    assert_eq!(writer.written, b"abcd");
+}
@@ -0,0 +1,42 @@
+//! Checks that pushing to a full [`RingBuffer`] with `try_push` and `push_overwrite` never moves
+//! or grows its allocation.
+#![cfg(feature = "alloc")]
+
+use circulate::RingBuffer;
+
+#[test]
+fn overwrite_keeps_allocation() {
+    for capacity in [2, 3, 16, 100] {
+        let mut buffer = RingBuffer::with_exact_capacity(capacity);
+        // The spare capacity of a new buffer starts at the start of the allocation.
+        let start = buffer.spare_capacity_mut().0.as_ptr() as usize;
+        let slots = buffer.capacity();
+        let usable = slots - 1;
+        for value in 0..5000u32 {
+            let overwritten = buffer.push_overwrite(value);
+            assert_eq!(overwritten, value.checked_sub(usable as u32));
+            assert_eq!(buffer.try_push(u32::MAX), if value + 1 < usable as u32 { Ok(()) } else { Err(u32::MAX) });
+            if value + 1 < usable as u32 {
+                buffer.pop_back();
+            }
+            assert_eq!(buffer.capacity(), slots);
+            for item in buffer.iter() {
+                let offset = (item as *const u32 as usize).wrapping_sub(start);
+                assert!(offset < slots * size_of::<u32>(), "the allocation moved");
+            }
+        }
+        // The newest items are kept, oldest first.
+        assert!(buffer.iter().copied().eq(5000 - usable as u32..5000));
+    }
+}
+
+#[test]
+fn no_space() {
+    let mut buffer = RingBuffer::new();
+    for value in 0..10 {
+        assert_eq!(buffer.push_overwrite(value), Some(value));
+        assert_eq!(buffer.try_push(value), Err(value));
+    }
+    assert_eq!(buffer.capacity(), 0);
+    assert!(buffer.is_empty());
+}
@@ -0,0 +1,49 @@
+//! Runs the checks of each fuzz target in `fuzz/` on pseudo-random bytes, so that they keep
+//! building and passing without a fuzzer.
+//!
+//! Run with `cargo test --features test-util --test fuzz`. The targets themselves are built by
+//! the ignored test, with `cargo test --features test-util --test fuzz -- --ignored`.
+#![cfg(all(feature = "test-util", not(feature = "no_std"), not(loom)))]
+
+#[path = "../fuzz/src/lib.rs"]
+mod harness;
+
+use std::{path::Path, process::Command};
+
+mod common;
+use common::Rng;
+
+/// Run `target` on inputs of many lengths filled with pseudo-random bytes.
+fn run(target: fn(&[u8])) {
+    let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+    for len in (0..4096).step_by(7) {
+        target(&rng.bytes(len));
+    }
+}
+
+#[test]
+fn ring_buffer() {
+    run(harness::ring_buffer);
+}
+
+#[test]
+fn buf_stream() {
+    run(harness::buf_stream);
+}
+
+#[test]
+fn iovec() {
+    run(harness::iovec);
+}
+
+#[test]
+#[ignore = "builds the fuzz targets"]
+fn fuzz_targets() {
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--bins"])
+        .env("CARGO_TARGET_DIR", Path::new(env!("CARGO_TARGET_TMPDIR")).join("fuzz"))
+        .current_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
@@ -0,0 +1,124 @@
+//! Checks that growing a [`RingBuffer`] whose items wrap around the end of its allocation keeps
+//! every item once and in order. The items own heap memory, so running under Miri also catches
+//! an item read twice or left behind.
+#![cfg(feature = "alloc")]
+
+use std::collections::VecDeque;
+use circulate::RingBuffer;
+
+fn check(buffer: &RingBuffer<String>, expected: &VecDeque<String>) {
+    assert_eq!(buffer.len(), expected.len());
+    assert!(buffer.iter().eq(expected.iter()));
+}
+
+#[test]
+fn grow_wrapped() {
+    let mut buffer = RingBuffer::with_capacity(4);
+    let mut expected = VecDeque::new();
+    let mut next = 0;
+    let mut push = |buffer: &mut RingBuffer<String>, expected: &mut VecDeque<String>| {
+        buffer.push(next.to_string()).unwrap();
+        expected.push_back(next.to_string());
+        next += 1;
+    };
+    let slots = buffer.capacity();
+    for _ in 0..slots - 1 {
+        push(&mut buffer, &mut expected);
+    }
+    for _ in 0..2 {
+        assert_eq!(buffer.pop(), expected.pop_front());
+    }
+    // Wrap around the end of the allocation until the buffer is full again, and then grow it.
+    while !buffer.is_full() {
+        push(&mut buffer, &mut expected);
+    }
+    push(&mut buffer, &mut expected);
+    assert!(buffer.capacity() > slots);
+    check(&buffer, &expected);
+
+    // Keep wrapping and growing several more times.
+    for round in 0..6 {
+        let slots = buffer.capacity();
+        for _ in 0..=round {
+            assert_eq!(buffer.pop(), expected.pop_front());
+        }
+        while buffer.capacity() == slots {
+            push(&mut buffer, &mut expected);
+        }
+        check(&buffer, &expected);
+    }
+    while let Some(item) = buffer.pop() {
+        assert_eq!(Some(item), expected.pop_front());
+    }
+    assert!(expected.is_empty());
+}
+
+#[test]
+fn grow_every_offset() {
+    for slots in [2, 3, 4, 7, 8] {
+        for offset in 0..slots {
+            for exact in [false, true] {
+                let mut buffer = if exact { RingBuffer::with_exact_capacity(slots - 1) } else { RingBuffer::with_capacity(slots - 1) };
+                let mut expected = VecDeque::new();
+                // Move the cursors to `offset`, including the write cursor ending on 0.
+                for value in 0..offset {
+                    buffer.push(value.to_string()).unwrap();
+                    buffer.pop();
+                }
+                let slots = buffer.capacity();
+                for value in 0..slots * 3 {
+                    buffer.push(value.to_string()).unwrap();
+                    expected.push_back(value.to_string());
+                    check(&buffer, &expected);
+                }
+                assert!(buffer.capacity() > slots);
+                // Grow through `reserve` as well as `push`.
+                buffer.reserve(buffer.capacity()).unwrap();
+                check(&buffer, &expected);
+                let drained: Vec<_> = buffer.into_iter().collect();
+                assert!(drained.iter().eq(expected.iter()));
+            }
+        }
+    }
+}
+
+#[test]
+fn grow_to_max_capacity() {
+    // Without a maximum the buffer keeps growing.
+    let mut unbounded = RingBuffer::new();
+    for value in 0..1000 {
+        unbounded.push(value.to_string()).unwrap();
+    }
+    assert!(unbounded.capacity() > 1000);
+    assert!(unbounded.iter().eq((0..1000).map(|value| value.to_string()).collect::<Vec<_>>().iter()));
+
+    for max in [1, 2, 5, 8, 64] {
+        let mut buffer = RingBuffer::with_max_capacity(max);
+        let mut expected = VecDeque::new();
+        let mut value = 0;
+        while let Ok(()) = buffer.push(value.to_string()) {
+            expected.push_back(value.to_string());
+            value += 1;
+        }
+        assert!(buffer.capacity() <= max);
+        check(&buffer, &expected);
+        // The rejected value is handed back and the items are untouched.
+        assert_eq!(buffer.push("rejected".to_owned()), Err("rejected".to_owned()));
+        check(&buffer, &expected);
+        let error = buffer.reserve(1).unwrap_err();
+        assert_eq!(error.max(), max);
+        assert!(!error.allocation_failed() && !error.strategy_failed());
+        assert!(buffer.capacity() <= max);
+        check(&buffer, &expected);
+        // Freeing a slot makes room again without growing.
+        let capacity = buffer.capacity();
+        if let Some(front) = buffer.pop() {
+            expected.pop_front();
+            assert_eq!(front, "0");
+            buffer.push("again".to_owned()).unwrap();
+            expected.push_back("again".to_owned());
+            assert_eq!(buffer.capacity(), capacity);
+            check(&buffer, &expected);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Checks that input taken from a `BufStream` with `take_buffered_input` and handed back with
+//! `prepend_input` is read again first and in order, whatever the state of the input buffer, and
+//! that no buffered bytes are lost when the stream is taken back out with `into_parts` or
+//! `into_inner`, or when it is dropped.
+#![cfg(all(feature = "alloc", not(feature = "no_std")))]
+
+use std::io;
+use circulate::{BufStream, FromStd, Read, RingBuffer, Write};
+
+/// Reads at most `limit` bytes at a time.
+struct Trickle {
+    bytes: io::Cursor<Vec<u8>>,
+    limit: usize,
+}
+impl io::Read for Trickle {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.limit);
+        io::Read::read(&mut self.bytes, &mut buf[..len])
+    }
+}
+impl io::Write for Trickle {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn stream(data: &[u8], limit: usize, max: Option<usize>) -> BufStream<FromStd<Trickle>> {
+    let mut stream = BufStream::with_capacity(FromStd(Trickle { bytes: io::Cursor::new(data.to_vec()), limit }), 8);
+    if let Some(max) = max {
+        stream.set_max_input_capacity(max);
+    }
+    stream
+}
+
+fn ring(bytes: &[u8]) -> RingBuffer<u8> {
+    let mut ring = RingBuffer::new();
+    ring.extend_from_slice(bytes).unwrap();
+    ring
+}
+
+fn read_all(stream: &mut BufStream<FromStd<Trickle>>) -> Vec<u8> {
+    let mut text = String::new();
+    stream.read_to_string(&mut text).unwrap();
+    text.into_bytes()
+}
+
+#[test]
+fn prepend_every_state() {
+    let data: Vec<u8> = (b'a'..=b'z').collect();
+    let prefix: Vec<u8> = (b'A'..=b'Z').collect();
+    for max in [None, Some(8), Some(16)] {
+        for limit in [1, 5, 64] {
+            for consumed in 0..8 {
+                for len in 0..prefix.len() {
+                    let mut stream = stream(&data, limit, max);
+                    // Leave the read cursor part way through the buffer, wrapping the input.
+                    stream.fill_at_least(consumed + 1).unwrap();
+                    stream.consume(consumed);
+                    stream.buffer_read().unwrap();
+                    stream.prepend_input(ring(&prefix[..len]));
+
+                    let (lhs, rhs) = stream.buffered_input();
+                    assert!([lhs, rhs].concat().starts_with(&prefix[..len]));
+                    let expected = [&prefix[..len], &data[consumed..]].concat();
+                    assert_eq!(read_all(&mut stream), expected, "max {max:?}, limit {limit}, consumed {consumed}, len {len}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn prepend_when_empty() {
+    let data = b"from the stream";
+    for max in [None, Some(4)] {
+        let mut stream = stream(data, 3, max);
+        let mut handed = ring(b"0123456789");
+        handed.pop();
+        stream.prepend_input(handed);
+        // Nothing more is read from the stream until the prepended bytes are read.
+        assert_eq!(stream.get_ref().0.bytes.position(), 0);
+        stream.prepend_input(ring(b"<"));
+        assert_eq!(read_all(&mut stream), b"<123456789from the stream");
+    }
+}
+
+#[test]
+fn take_and_prepend() {
+    let data: Vec<u8> = (0..=255).cycle().take(2000).collect();
+    for limit in [1, 7, 100] {
+        let mut stream = stream(&data, limit, Some(32));
+        let mut read = Vec::new();
+        let mut step = 0;
+        while read.len() < data.len() {
+            step += 1;
+            stream.buffer_read().unwrap();
+            let mut taken = stream.take_buffered_input();
+            assert!(!stream.input_has_data());
+            match step % 3 {
+                // Hand the input straight back.
+                0 => stream.prepend_input(taken),
+                // Keep some of it, and hand back the rest after reading more from the stream.
+                1 => {
+                    if let Some(byte) = taken.pop() {
+                        read.push(byte);
+                    }
+                    stream.buffer_read().unwrap();
+                    stream.prepend_input(taken);
+                }
+                // Keep all of it.
+                _ => read.extend(taken.iter()),
+            }
+            let (lhs, rhs) = stream.buffered_input();
+            read.extend_from_slice(lhs);
+            read.extend_from_slice(rhs);
+            let buffered = lhs.len() + rhs.len();
+            stream.consume(buffered);
+        }
+        assert_eq!(read, data);
+    }
+}
+
+#[test]
+fn prepend_after_unread() {
+    let mut stream = stream(b"abcdef", 64, None);
+    stream.fill_at_least(6).unwrap();
+    stream.consume(3);
+    stream.unread(b"c").unwrap();
+    stream.prepend_input(ring(b"xy"));
+    assert_eq!(read_all(&mut stream), b"xycdef");
+}
+
+/// Accepts `limit` bytes and then fails every write.
+struct Breaks {
+    sent: Vec<u8>,
+    limit: usize,
+}
+impl io::Write for Breaks {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.limit - self.sent.len());
+        if len == 0 && !buf.is_empty() {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        self.sent.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn into_parts_keeps_everything() {
+    let data: Vec<u8> = (0..=255).collect();
+    for consumed in [0, 3, 8] {
+        for chunked in [false, true] {
+            let mut stream = stream(&data, 64, Some(16));
+            stream.fill_at_least(consumed + 1).unwrap();
+            stream.consume(consumed);
+            let buffered = stream.input_buffer_len();
+            assert!(buffered != 0);
+            stream.write(b"head").unwrap();
+            if chunked {
+                stream.write_chunk(b"-chunk-".to_vec());
+            }
+            stream.write(b"tail").unwrap();
+            let expected: &[u8] = if chunked { b"head-chunk-tail" } else { b"headtail" };
+            assert_eq!(stream.output_buffer_len(), expected.len());
+
+            let (inner, input, output) = stream.into_parts();
+            assert!(input.iter().eq(&data[consumed..consumed + buffered]));
+            assert_eq!(inner.0.bytes.position() as usize, consumed + buffered);
+            assert!(output.iter().eq(expected), "consumed {consumed}, chunked {chunked}");
+        }
+    }
+}
+
+#[test]
+fn into_inner_failure_keeps_everything() {
+    let mut stream = BufStream::with_capacity(FromStd(Breaks { sent: Vec::new(), limit: 6 }), 8);
+    stream.write(b"abc").unwrap();
+    stream.write_chunk(b"defgh".to_vec());
+    stream.write(b"ijk").unwrap();
+    let Err(error) = stream.into_inner() else { panic!("the stream breaks after 6 bytes") };
+    assert_eq!(error.error().kind(), io::ErrorKind::BrokenPipe);
+    let mut stream = error.into_stream();
+    assert_eq!(stream.get_ref().0.sent, b"abcdef");
+    assert_eq!(stream.output_buffer_len(), 5);
+
+    // The unwritten bytes can be sent over another connection.
+    stream.get_mut().0.limit = usize::MAX;
+    let FromStd(Breaks { sent, .. }) = stream.into_inner().map_err(|error| error.into_error()).unwrap();
+    assert_eq!(sent, b"abcdefghijk");
+
+    let mut stream = BufStream::with_capacity(FromStd(Breaks { sent: Vec::new(), limit: 2 }), 8);
+    stream.write(b"abc").unwrap();
+    stream.write_chunk(b"de".to_vec());
+    let Err(error) = stream.into_inner() else { panic!("the stream breaks after 2 bytes") };
+    let (inner, _, output) = error.into_stream().into_parts();
+    assert_eq!(inner.0.sent, b"ab");
+    assert!(output.iter().eq(b"cde"));
+}
+
+#[test]
+fn drop_flushes_everything() {
+    let mut breaks = Breaks { sent: Vec::new(), limit: usize::MAX };
+    let mut stream = BufStream::with_capacity(FromStd(&mut breaks), 8);
+    stream.write(b"abc").unwrap();
+    stream.write_chunk(b"defgh".to_vec());
+    stream.write(b"ijk").unwrap();
+    drop(stream);
+    assert_eq!(breaks.sent, b"abcdefghijk");
+
+    // A failed flush on drop keeps what was written before it.
+    let mut breaks = Breaks { sent: Vec::new(), limit: 4 };
+    let mut stream = BufStream::with_capacity(FromStd(&mut breaks), 8);
+    stream.write(b"abc").unwrap();
+    stream.write_chunk(b"defgh".to_vec());
+    drop(stream);
+    assert_eq!(breaks.sent, b"abcd");
+
+    // Output is still flushed after the stream is mapped.
+    let mut breaks = Breaks { sent: Vec::new(), limit: usize::MAX };
+    let mut stream = BufStream::with_capacity(FromStd(&mut breaks), 8);
+    stream.write(b"abc").unwrap();
+    let mut stream = stream.map_stream(|FromStd(inner)| FromStd(inner));
+    stream.write(b"def").unwrap();
+    drop(stream);
+    assert_eq!(breaks.sent, b"abcdef");
+    let mut breaks = Breaks { sent: Vec::new(), limit: usize::MAX };
+    let mut stream = BufStream::with_capacity(FromStd(&mut breaks), 8);
+    stream.write(b"abc").unwrap();
+    drop(stream.map_stream(|FromStd(inner)| FromStd(inner)));
+    assert_eq!(breaks.sent, b"abc");
+
+    // Taking the stream does not flush.
+    let mut breaks = Breaks { sent: Vec::new(), limit: usize::MAX };
+    let mut stream = BufStream::with_capacity(FromStd(&mut breaks), 8);
+    stream.write(b"abc").unwrap();
+    let FromStd(_) = stream.take_stream();
+    assert!(breaks.sent.is_empty());
+}
@@ -0,0 +1,89 @@
+//! Checks indexing in to a [`RingBuffer`] against a `VecDeque` holding the same items, including
+//! where the items wrap around the end of the allocation and where slots have been popped.
+#![cfg(feature = "alloc")]
+
+use std::collections::VecDeque;
+use circulate::RingBuffer;
+
+/// Compare every index and range of `buffer` with `expected`, and a few past the end.
+fn check(buffer: &mut RingBuffer<u32>, expected: &VecDeque<u32>) {
+    let len = expected.len();
+    for index in 0..buffer.capacity() + 2 {
+        assert_eq!(buffer.get(index), expected.get(index), "index {index} of {len}");
+        assert_eq!(buffer.get_mut(index).copied(), expected.get(index).copied());
+        if index < len {
+            assert_eq!(buffer[index], expected[index]);
+        }
+    }
+    assert_eq!(buffer.front(), expected.front());
+    assert_eq!(buffer.back(), expected.back());
+    for start in 0..=len + 1 {
+        for end in 0..=len + 1 {
+            let range = buffer.get_range(start..end).map(|(lhs, rhs)| [lhs, rhs].concat());
+            let expected = (start <= end && end <= len).then(|| expected.range(start..end).copied().collect::<Vec<_>>());
+            assert_eq!(range, expected, "range {start}..{end} of {len}");
+        }
+    }
+}
+
+#[test]
+fn wrapped() {
+    let mut wrapped = 0;
+    for capacity in [1, 2, 5, 8] {
+        for offset in 0..capacity {
+            for len in 0..capacity {
+                let mut buffer = RingBuffer::with_exact_capacity(capacity);
+                let mut expected = VecDeque::new();
+                // Move the read cursor part way through the allocation before filling it.
+                for value in 0..offset as u32 {
+                    buffer.push(value).unwrap();
+                    assert_eq!(buffer.pop(), Some(value));
+                }
+                for value in 0..len as u32 {
+                    buffer.push(100 + value).unwrap();
+                    expected.push_back(100 + value);
+                }
+                if buffer.get_range(..).is_some_and(|(_, rhs)| !rhs.is_empty()) {
+                    wrapped += 1;
+                }
+                check(&mut buffer, &expected);
+                for index in 0..len {
+                    buffer[index] += 1;
+                    expected[index] += 1;
+                }
+                check(&mut buffer, &expected);
+            }
+        }
+    }
+    assert!(wrapped != 0);
+}
+
+#[test]
+fn popped_slots_are_unreachable() {
+    let mut buffer = RingBuffer::with_exact_capacity(6);
+    let mut expected = VecDeque::new();
+    for value in 0..5 {
+        buffer.push(value).unwrap();
+        expected.push_back(value);
+    }
+    for _ in 0..5 {
+        buffer.pop();
+        expected.pop_front();
+        check(&mut buffer, &expected);
+    }
+    assert_eq!(buffer.get(0), None);
+    // The popped values are still in the allocation, but not in the buffer.
+    buffer.push(7).unwrap();
+    expected.push_back(7);
+    check(&mut buffer, &expected);
+}
+
+#[test]
+#[should_panic(expected = "index 2 is out of range for a RingBuffer of length 2")]
+fn index_past_the_end() {
+    let mut buffer = RingBuffer::with_exact_capacity(8);
+    buffer.extend_from_slice(&[1, 2]).unwrap();
+    buffer.pop();
+    buffer.push(3).unwrap();
+    buffer[2] += 1;
+}
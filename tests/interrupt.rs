@@ -0,0 +1,59 @@
+//! A single core simulation of a [`StaticRing`] shared between the main loop and an interrupt
+//! handler, as on targets without atomic read-modify-write operations where splitting the ring
+//! relies on the `critical-section` feature.
+#![cfg(all(feature = "critical-section", not(loom)))]
+
+use circulate::{StaticProducer, StaticRing};
+
+mod common;
+use common::Rng;
+
+static QUEUE: StaticRing<u32, 8> = StaticRing::new();
+
+/// The interrupt handler, which pushes up to `burst` more of the counting sequence as a receive
+/// interrupt would, dropping what does not fit.
+fn handler(producer: &mut StaticProducer<'static, u32, 8>, next: &mut u32, dropped: &mut u32, burst: u32) {
+    // The ring is already claimed, so the handler cannot split it again.
+    assert!(QUEUE.split().is_none());
+    for _ in 0..burst {
+        match producer.push(*next) {
+            Ok(()) => *next += 1,
+            Err(_) => *dropped += 1,
+        }
+    }
+}
+
+#[test]
+fn producer_in_handler() {
+    let (mut producer, mut consumer) = QUEUE.split().unwrap();
+    let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+    let mut random = move |range: usize| rng.below(range) as u32;
+    let (mut next, mut dropped, mut expected) = (0, 0, 0);
+    while expected < 10_000 {
+        // The interrupt may fire between any two steps of the main loop.
+        if random(3) == 0 {
+            handler(&mut producer, &mut next, &mut dropped, random(6));
+        }
+        let len = consumer.len();
+        assert!(len <= QUEUE.capacity());
+        if random(4) == 0 {
+            handler(&mut producer, &mut next, &mut dropped, random(6));
+            assert!(consumer.len() >= len);
+        }
+        for _ in 0..random(4) {
+            match consumer.pop() {
+                Some(item) => {
+                    assert_eq!(item, expected);
+                    expected += 1;
+                }
+                None => break,
+            }
+        }
+    }
+    assert!(dropped > 0);
+    while let Some(item) = consumer.pop() {
+        assert_eq!(item, expected);
+        expected += 1;
+    }
+    assert_eq!(expected, next);
+}
@@ -0,0 +1,191 @@
+//! Checks that the iterators of a [`RingBuffer`] give the same results as the default
+//! implementations of each `Iterator` method, over random buffers that may wrap around the end of
+//! their allocation and random sequences of calls.
+#![cfg(feature = "alloc")]
+
+use std::{cell::Cell, rc::Rc};
+use circulate::RingBuffer;
+
+mod common;
+use common::Rng;
+
+/// Hides all but `next` and `next_back`, so that every other method uses its default
+/// implementation.
+struct Plain<I>(I);
+impl<I: Iterator> Iterator for Plain<I> {
+    type Item = I::Item;
+    fn next(&mut self) -> Option<I::Item> {
+        self.0.next()
+    }
+}
+impl<I: DoubleEndedIterator> DoubleEndedIterator for Plain<I> {
+    fn next_back(&mut self) -> Option<I::Item> {
+        self.0.next_back()
+    }
+}
+
+/// Call one random method on both iterators, returning the items each returned to compare.
+/// Methods that consume the iterators take them from the options.
+fn step<T, I, J>(rng: &mut Rng, len: usize, actual: &mut Option<I>, expected: &mut Option<J>) -> (Vec<T>, Vec<T>)
+where
+    I: ExactSizeIterator<Item = T> + DoubleEndedIterator,
+    J: DoubleEndedIterator<Item = T>,
+{
+    let (a, e) = (actual.as_mut().unwrap(), expected.as_mut().unwrap());
+    assert_eq!(a.size_hint(), (a.len(), Some(a.len())));
+    match rng.below(13) {
+        0..=2 => (a.next().into_iter().collect(), e.next().into_iter().collect()),
+        3..=5 => {
+            let n = rng.below(len + 3);
+            (a.nth(n).into_iter().collect(), e.nth(n).into_iter().collect())
+        }
+        6 | 7 => (a.next_back().into_iter().collect(), e.next_back().into_iter().collect()),
+        8 | 9 => {
+            let n = rng.below(len + 3);
+            (a.nth_back(n).into_iter().collect(), e.nth_back(n).into_iter().collect())
+        }
+        10 => {
+            let (a, e) = (actual.take().unwrap(), expected.take().unwrap());
+            (a.rev().collect(), e.rev().collect())
+        }
+        11 => {
+            let (a, e) = (actual.take().unwrap(), expected.take().unwrap());
+            if rng.below(2) == 0 {
+                assert_eq!(a.count(), e.count());
+                (Vec::new(), Vec::new())
+            } else {
+                (a.collect(), e.collect())
+            }
+        }
+        _ => {
+            let (a, e) = (actual.take().unwrap(), expected.take().unwrap());
+            (a.last().into_iter().collect(), e.last().into_iter().collect())
+        }
+    }
+}
+
+/// Create a buffer of `len` items counting up from 0, starting `offset` items in to an
+/// allocation of `capacity`.
+fn buffer<T>(capacity: usize, offset: usize, len: usize, item: impl Fn(u32) -> T) -> RingBuffer<T> {
+    let mut buffer = RingBuffer::with_exact_capacity(capacity);
+    for _ in 0..offset {
+        assert!(buffer.push(item(0)).is_ok());
+        buffer.pop();
+    }
+    for i in 0..len {
+        assert!(buffer.push(item(i as u32)).is_ok());
+    }
+    buffer
+}
+
+/// Run `check` with random buffer shapes.
+fn soak(mut check: impl FnMut(&mut Rng, usize, usize, usize)) {
+    let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+    for _ in 0..2000 {
+        let capacity = 1 + rng.below(12);
+        let offset = rng.below(capacity * 2);
+        let len = rng.below(capacity);
+        check(&mut rng, capacity, offset, len);
+    }
+}
+
+#[test]
+fn iter() {
+    soak(|rng, capacity, offset, len| {
+        let buffer = buffer(capacity, offset, len, |i| i);
+        let model: Vec<u32> = (0..len as u32).collect();
+        let (mut actual, mut expected) = (Some(buffer.iter()), Some(Plain(model.iter())));
+        while let Some(iter) = &actual {
+            assert_eq!(iter.peek(), iter.clone().next());
+            let (lhs, rhs) = iter.as_slices();
+            assert!(lhs.iter().chain(rhs).eq(iter.clone()));
+            let (a, e) = step(rng, len, &mut actual, &mut expected);
+            assert_eq!(a, e);
+        }
+    });
+}
+
+#[test]
+fn iter_mut() {
+    soak(|rng, capacity, offset, len| {
+        let mut buffer = buffer(capacity, offset, len, |i| i);
+        let mut model: Vec<u32> = (0..len as u32).collect();
+        let (mut actual, mut expected) = (Some(buffer.iter_mut()), Some(Plain(model.iter_mut())));
+        while actual.is_some() {
+            let (a, e) = step(rng, len, &mut actual, &mut expected);
+            assert_eq!(a, e);
+            // The references handed out are to the same items.
+            a.into_iter().for_each(|item| *item += 100);
+            e.into_iter().for_each(|item| *item += 100);
+        }
+        assert!(buffer.iter().eq(&model));
+    });
+}
+
+#[test]
+fn into_iter() {
+    #[derive(Debug)]
+    struct Counted(u32, Rc<Cell<usize>>);
+    impl PartialEq for Counted {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() + 1);
+        }
+    }
+
+    soak(|rng, capacity, offset, len| {
+        let (dropped, model_dropped) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+        let buffer = buffer(capacity, offset, len, |i| Counted(i, dropped.clone()));
+        dropped.set(0);
+        let model: Vec<_> = (0..len as u32).map(|i| Counted(i, model_dropped.clone())).collect();
+        let (mut actual, mut expected) = (Some(buffer.into_iter()), Some(Plain(model.into_iter())));
+        while actual.is_some() {
+            let (a, e) = step(rng, len, &mut actual, &mut expected);
+            // Skipped items are dropped as they are by the default implementations.
+            assert_eq!(dropped.get(), model_dropped.get());
+            assert_eq!(a, e);
+        }
+        drop((actual, expected));
+        assert_eq!((dropped.get(), model_dropped.get()), (len, len));
+    });
+}
+
+#[test]
+fn meet_in_the_middle() {
+    for capacity in 1..10 {
+        for offset in 0..capacity {
+            for len in 0..capacity {
+                let mut buffer = buffer(capacity, offset, len, |i| i);
+                let mut model: Vec<u32> = (0..len as u32).collect();
+                let mut from_back = false;
+                let mut iter = buffer.iter();
+                let mut model_iter = model.iter();
+                while iter.len() != 0 {
+                    assert_eq!(iter.len(), model_iter.len());
+                    let (a, e) = if from_back { (iter.next_back(), model_iter.next_back()) } else { (iter.next(), model_iter.next()) };
+                    assert_eq!(a, e);
+                    from_back = !from_back;
+                }
+                assert_eq!((iter.next(), iter.next_back()), (None, None));
+
+                let mut iter = buffer.iter_mut();
+                let mut model_iter = model.iter_mut();
+                while let (Some(a), Some(e)) = (iter.next_back(), model_iter.next_back()) {
+                    *a += 1;
+                    *e += 1;
+                    if let (Some(a), Some(e)) = (iter.next(), model_iter.next()) {
+                        *a *= 2;
+                        *e *= 2;
+                    }
+                    assert_eq!(iter.len(), model_iter.len());
+                }
+                assert!(buffer.iter().eq(&model));
+                assert!(buffer.into_iter().rev().eq(model.into_iter().rev()));
+            }
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Model checks of the memory orderings used by [`StaticRing`], by a [`RingBuffer`] split with
+//! [`RingBuffer::split`] and by [`record_ring`], exploring every interleaving of the producers and
+//! consumer, including batches moved by [`StaticConsumer::steal_batch_into`].
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+#![cfg(loom)]
+
+use loom::{sync::Arc, thread};
+use circulate::{record_ring, RingBuffer, StaticConsumer, StaticRing};
+
+/// Create a ring that outlives the threads of a model, as the handles borrow it.
+fn leak<T, const N: usize>() -> &'static StaticRing<T, N> {
+    Box::leak(Box::new(StaticRing::new()))
+}
+/// Drop a ring created by [`leak`].
+/// # Safety
+/// Nothing may use the ring afterwards, so every thread using it must have been joined.
+unsafe fn free<T, const N: usize>(ring: &'static StaticRing<T, N>) {
+    drop(unsafe { Box::from_raw(ring as *const StaticRing<T, N> as *mut StaticRing<T, N>) });
+}
+
+/// Only one caller can split the ring.
+#[test]
+fn split_once() {
+    loom::model(|| {
+        let ring = leak::<u8, 2>();
+        let other = thread::spawn(move || ring.split().is_some());
+        let split = ring.split().is_some();
+        assert!(split ^ other.join().unwrap());
+        unsafe { free(ring) };
+    });
+}
+
+/// Items pass through a ring of one slot in order, with the producer waiting at the full
+/// boundary and the consumer at the empty boundary.
+#[test]
+fn push_pop_boundaries() {
+    loom::model(|| {
+        let ring = leak::<usize, 2>();
+        let (mut producer, mut consumer) = ring.split().unwrap();
+        let producer = thread::spawn(move || {
+            for item in 0..2 {
+                let mut item = item;
+                while let Err(returned) = producer.push(item) {
+                    item = returned;
+                    thread::yield_now();
+                }
+            }
+        });
+        for expected in 0..2 {
+            loop {
+                match consumer.pop() {
+                    Some(item) => {
+                        assert_eq!(item, expected);
+                        break;
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+        }
+        producer.join().unwrap();
+        assert_eq!(consumer.pop(), None);
+        unsafe { free(ring) };
+    });
+}
+
+/// A push racing a pop from a full ring either fails or lands in the slot just freed, and the
+/// length is never observed outside of its bounds.
+#[test]
+fn push_racing_pop_when_full() {
+    loom::model(|| {
+        let ring = leak::<usize, 3>();
+        let (mut producer, mut consumer) = ring.split().unwrap();
+        producer.push(0).unwrap();
+        producer.push(1).unwrap();
+        let consumer = thread::spawn(move || {
+            assert_eq!(consumer.pop(), Some(0));
+            assert!((1..=2).contains(&consumer.len()));
+            consumer
+        });
+        let pushed = producer.push(2).is_ok();
+        let mut consumer = consumer.join().unwrap();
+        assert_eq!(consumer.pop(), Some(1));
+        assert_eq!(consumer.pop(), pushed.then_some(2));
+        assert_eq!(consumer.pop(), None);
+        unsafe { free(ring) };
+    });
+}
+
+/// A push racing a pop from an empty ring is either seen whole or not at all.
+#[test]
+fn push_racing_pop_when_empty() {
+    loom::model(|| {
+        let ring = leak::<Box<usize>, 2>();
+        let (mut producer, mut consumer) = ring.split().unwrap();
+        let consumer = thread::spawn(move || consumer.pop().map(|item| *item));
+        producer.push(Box::new(7)).unwrap();
+        match consumer.join().unwrap() {
+            Some(item) => assert_eq!(item, 7),
+            // The item was left in the ring, so dropping the ring drops it.
+            None => assert!(producer.is_full()),
+        }
+        unsafe { free(ring) };
+    });
+}
+
+/// Dropping both handles while a final push races a pop drops every item exactly once when the
+/// ring is dropped. `loom` fails the model if an item leaks.
+#[test]
+fn drop_halves_racing_final_push() {
+    loom::model(|| {
+        let ring = leak::<Arc<()>, 3>();
+        let item = Arc::new(());
+        let (mut producer, mut consumer) = ring.split().unwrap();
+        producer.push(item.clone()).unwrap();
+        let final_item = item.clone();
+        // Each handle is dropped as its thread finishes.
+        let producer = thread::spawn(move || producer.push(final_item).unwrap());
+        let consumer = thread::spawn(move || drop(consumer.pop()));
+        producer.join().unwrap();
+        consumer.join().unwrap();
+        assert_eq!(Arc::strong_count(&item), 2);
+        unsafe { free(ring) };
+        assert_eq!(Arc::strong_count(&item), 1);
+    });
+}
+
+/// Pop every item left in the ring in to `batch`.
+fn drain<T, const N: usize>(consumer: &mut StaticConsumer<'_, T, N>, batch: &mut RingBuffer<T>) {
+    while let Some(item) = consumer.pop() {
+        assert!(batch.push(item).is_ok());
+    }
+}
+
+/// A batch that wraps around the end of the ring, racing pushes in to the slots it frees, takes
+/// a prefix of the items in order and hands each slot back only once its item has been moved out.
+#[test]
+fn steal_batch_racing_push() {
+    loom::model(|| {
+        let ring = leak::<Box<usize>, 3>();
+        let (mut producer, mut consumer) = ring.split().unwrap();
+        producer.push(Box::new(0)).unwrap();
+        assert_eq!(consumer.pop().map(|item| *item), Some(0));
+        producer.push(Box::new(1)).unwrap();
+        producer.push(Box::new(2)).unwrap();
+        let stealer = thread::spawn(move || {
+            let mut batch = RingBuffer::new();
+            assert_eq!(consumer.steal_batch_into(&mut batch, 2), 2);
+            (consumer, batch)
+        });
+        // The ring is full until the batch releases both of its slots at once, so once one push
+        // lands the next does too.
+        let pusher = thread::spawn(move || {
+            [3, 4].into_iter().filter(|item| producer.push(Box::new(*item)).is_ok()).collect::<Vec<usize>>()
+        });
+        let pushed = pusher.join().unwrap();
+        assert_ne!(pushed, [3], "the batch released part of its slots");
+        let (mut consumer, mut batch) = stealer.join().unwrap();
+        drain(&mut consumer, &mut batch);
+        assert!(batch.iter().map(|item| **item).eq([1, 2].into_iter().chain(pushed)));
+        unsafe { free(ring) };
+    });
+}
+
+/// A batch racing a push in to an empty ring takes the item whole or leaves it for later, and
+/// a batch limited to fewer items than the ring holds leaves the rest.
+#[test]
+fn steal_batch_boundaries() {
+    loom::model(|| {
+        let ring = leak::<Box<usize>, 3>();
+        let (mut producer, mut consumer) = ring.split().unwrap();
+        let stealer = thread::spawn(move || {
+            let mut batch = RingBuffer::new();
+            let first = consumer.steal_batch_into(&mut batch, 1);
+            let second = consumer.steal_batch_into(&mut batch, 1);
+            assert!(first <= 1 && second <= 1);
+            (consumer, batch)
+        });
+        producer.push(Box::new(0)).unwrap();
+        producer.push(Box::new(1)).unwrap();
+        let (mut consumer, mut batch) = stealer.join().unwrap();
+        drain(&mut consumer, &mut batch);
+        assert!(batch.iter().map(|item| **item).eq([0, 1]));
+        unsafe { free(ring) };
+    });
+}
+
+/// Items pass through a split buffer of one slot in order, as through a [`StaticRing`].
+#[test]
+fn split_buffer_boundaries() {
+    loom::model(|| {
+        let (mut producer, mut consumer) = RingBuffer::with_exact_capacity(1).split();
+        let producer = thread::spawn(move || {
+            for item in 0..2 {
+                let mut item = item;
+                while let Err(returned) = producer.push(item) {
+                    item = returned;
+                    thread::yield_now();
+                }
+            }
+            producer
+        });
+        for expected in 0..2 {
+            loop {
+                match consumer.pop() {
+                    Some(item) => {
+                        assert_eq!(item, expected);
+                        break;
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+        }
+        let producer = producer.join().unwrap();
+        assert_eq!(consumer.pop(), None);
+        drop((producer, consumer));
+    });
+}
+
+/// Dropping both halves of a split buffer on different threads frees it, and the items left in
+/// it, exactly once, whichever half goes last.
+#[test]
+fn split_buffer_concurrent_drop() {
+    loom::model(|| {
+        let item = Arc::new(());
+        let (mut producer, consumer) = RingBuffer::with_exact_capacity(2).split();
+        producer.push(item.clone()).unwrap();
+        let dropper = thread::spawn(move || drop(consumer));
+        drop(producer);
+        dropper.join().unwrap();
+        assert_eq!(Arc::strong_count(&item), 1);
+    });
+}
+
+/// A push racing a peek and pop from an empty split buffer is either seen whole or not at all,
+/// and an item left in the buffer is dropped with it.
+#[test]
+fn split_buffer_push_racing_pop() {
+    loom::model(|| {
+        let item = Arc::new(());
+        let (mut producer, mut consumer) = RingBuffer::with_exact_capacity(2).split();
+        let pushed = item.clone();
+        // The halves are handed back, so the buffer is dropped once both threads are joined.
+        let consumer = thread::spawn(move || {
+            let peeked = consumer.peek().is_some();
+            let popped = consumer.pop().is_some();
+            assert!(popped || !peeked);
+            (consumer, popped)
+        });
+        producer.push(pushed).unwrap();
+        let (consumer, popped) = consumer.join().unwrap();
+        assert_eq!(consumer.len(), usize::from(!popped));
+        drop((producer, consumer));
+        assert_eq!(Arc::strong_count(&item), 1);
+    });
+}
+
+/// Records pushed by two producers racing each other and the consumer arrive whole and once,
+/// including one that pads out the end of the ring when the consumer has made space for it.
+#[test]
+fn record_ring_racing_producers() {
+    loom::model(|| {
+        let (producer, mut consumer) = record_ring(16);
+        let mut record = Vec::new();
+        // Move the next record 4 bytes in, so that a second record of 8 bytes pads out the end.
+        assert!(producer.push_record(&[]));
+        assert!(consumer.pop_record(&mut record) && record.is_empty());
+        let other = producer.clone();
+        let pusher = thread::spawn(move || other.push_record(&[1; 3]));
+        let consumer = thread::spawn(move || {
+            let mut records = Vec::new();
+            while records.is_empty() {
+                if consumer.pop_record(&mut record) {
+                    records.push(record.clone());
+                } else {
+                    thread::yield_now();
+                }
+            }
+            (consumer, records)
+        });
+        let pushed = producer.push_record(&[2; 3]);
+        let other_pushed = pusher.join().unwrap();
+        let (mut consumer, mut records) = consumer.join().unwrap();
+        let mut record = Vec::new();
+        while consumer.pop_record(&mut record) {
+            records.push(record.clone());
+        }
+        // Only one record fits until the consumer releases the first.
+        assert!(pushed || other_pushed);
+        assert_eq!(records.len(), usize::from(pushed) + usize::from(other_pushed));
+        assert!(records.iter().all(|record| record == &[1; 3] || record == &[2; 3]));
+        if let [first, second] = &records[..] {
+            assert_ne!(first, second);
+        }
+    });
+}
@@ -0,0 +1,42 @@
+//! Checks of [`BufStream::copy_to`] between streams backed by file descriptors, where the copy
+//! may be done by the kernel.
+#![cfg(all(unix, feature = "alloc", not(feature = "no_std")))]
+
+use std::{fs::{self, File}, io::{Read as _, Write as _}, net::{TcpListener, TcpStream}, thread};
+use circulate::{BufStream, FromStd, Write};
+
+mod common;
+use common::Rng;
+
+#[test]
+fn file_to_socket_keeps_buffered_bytes() {
+    let contents = Rng::new(0x2545_f491_4f6c_dd1d).bytes(1 << 20);
+    let path = std::env::temp_dir().join(format!("circulate-offload-{}", std::process::id()));
+    File::create(&path).unwrap().write_all(&contents).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        let mut received = Vec::new();
+        listener.accept().unwrap().0.read_to_end(&mut received).unwrap();
+        received
+    });
+
+    let mut src = BufStream::with_capacity(FromStd(File::open(&path).unwrap()), 4096);
+    let mut dst = BufStream::new(FromStd(TcpStream::connect(address).unwrap()));
+    // Input buffered from the file, and output not yet sent, must both keep their place.
+    src.buffer_read().unwrap();
+    src.consume(100);
+    // Note: The std traits are also in scope, which `BufStream` implements too.
+    Write::write(&mut dst, b"header").unwrap();
+    let copied = src.copy_to(&mut dst).unwrap();
+    assert_eq!(copied, contents.len() as u64 - 100);
+    assert_eq!(src.received_bytes(), contents.len() as u64);
+    assert_eq!(dst.flushed_bytes(), contents.len() as u64 - 100 + 6);
+    drop(dst);
+
+    let received = server.join().unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(received[..6], *b"header");
+    assert!(received[6..] == contents[100..]);
+}
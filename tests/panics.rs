@@ -0,0 +1,419 @@
+//! Checks that a panic at each call of a user closure, or in the drop of an item, leaves a
+//! [`RingBuffer`] and the types built on it usable, and that every item is then dropped exactly
+//! once.
+//!
+//! Run under Miri with `cargo +nightly miri test --test panics` to also check the unwinding paths
+//! for undefined behaviour.
+#![cfg(all(feature = "alloc", not(feature = "no_std")))]
+
+use std::{cell::{Cell, RefCell}, mem, panic::{self, AssertUnwindSafe}, rc::Rc, sync::Once};
+use circulate::{RingBuffer, SlidingWindow, WeightedRingBuffer};
+
+/// The payload of every panic injected by these tests, which are not reported.
+struct Injected;
+
+fn quiet() {
+    static QUIET: Once = Once::new();
+    QUIET.call_once(|| {
+        let report = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !info.payload().is::<Injected>() {
+                report(info)
+            }
+        }));
+    });
+}
+
+/// Panics on the call after it has been called `n` times.
+struct Trigger(Cell<usize>);
+impl Trigger {
+    fn tick(&self) {
+        match self.0.get() {
+            0 => panic::panic_any(Injected),
+            left => self.0.set(left - 1),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    drops: RefCell<Vec<usize>>,
+    /// The item that panics when dropped.
+    explode: Cell<Option<usize>>,
+}
+impl Shared {
+    fn item(self: &Rc<Self>) -> Tracked {
+        let mut drops = self.drops.borrow_mut();
+        drops.push(0);
+        Tracked { id: drops.len() - 1, shared: self.clone() }
+    }
+    fn assert_dropped_once(&self, leaked: &[usize]) {
+        for (id, &count) in self.drops.borrow().iter().enumerate() {
+            let expected = if leaked.contains(&id) { 0 } else { 1 };
+            assert_eq!(count, expected, "item {id} dropped {count} times");
+        }
+    }
+}
+
+struct Tracked {
+    id: usize,
+    shared: Rc<Shared>,
+}
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.shared.drops.borrow_mut()[self.id] += 1;
+        if self.shared.explode.get() == Some(self.id) {
+            self.shared.explode.set(None);
+            panic::panic_any(Injected);
+        }
+    }
+}
+
+/// A full buffer of 7 items wrapping around the end of its allocation.
+fn wrapped(shared: &Rc<Shared>) -> RingBuffer<Tracked> {
+    let mut buffer = RingBuffer::with_capacity(7);
+    for _ in 0..5 {
+        assert!(buffer.push(shared.item()).is_ok());
+    }
+    for _ in 0..4 {
+        buffer.pop();
+    }
+    for _ in 0..6 {
+        assert!(buffer.push(shared.item()).is_ok());
+    }
+    assert!(!buffer.as_mut_slices().1.is_empty());
+    buffer
+}
+
+/// Run `op` on a wrapped buffer with a trigger firing at each call in turn, and check the buffer.
+fn closures(op: impl Fn(&Rc<Shared>, &mut RingBuffer<Tracked>, &Trigger)) {
+    quiet();
+    for at in 0..16 {
+        let shared = Rc::new(Shared::default());
+        let mut buffer = wrapped(&shared);
+        let trigger = Trigger(Cell::new(at));
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| op(&shared, &mut buffer, &trigger)));
+        let (lhs, rhs) = buffer.as_mut_slices();
+        assert_eq!(lhs.len() + rhs.len(), buffer.len());
+        assert_eq!(buffer.iter().count(), buffer.len());
+        drop(buffer);
+        shared.assert_dropped_once(&[]);
+    }
+}
+
+/// Run `op` on a wrapped buffer with each item in turn panicking when dropped, and check the buffer.
+fn drops(op: impl Fn(&mut RingBuffer<Tracked>)) {
+    quiet();
+    for index in 0..7 {
+        let shared = Rc::new(Shared::default());
+        let mut buffer = wrapped(&shared);
+        shared.explode.set(Some(buffer.get(index).unwrap().id));
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| op(&mut buffer)));
+        assert_eq!(buffer.iter().count(), buffer.len());
+        shared.explode.set(None);
+        drop(buffer);
+        shared.assert_dropped_once(&[]);
+    }
+}
+
+#[test]
+fn find_and_remove() {
+    closures(|_, buffer, trigger| {
+        buffer.find_and_remove(|item| {
+            trigger.tick();
+            item.id == 9
+        });
+    });
+}
+
+#[test]
+fn evict_while() {
+    closures(|_, buffer, trigger| {
+        buffer.evict_while(|_| {
+            trigger.tick();
+            true
+        });
+    });
+}
+
+#[test]
+fn pop_checked() {
+    closures(|_, buffer, trigger| {
+        while let Ok(Some(_)) = buffer.pop_checked(|_| {
+            trigger.tick();
+            true
+        }) {}
+    });
+    closures(|_, buffer, trigger| {
+        buffer.pop_while_valid(|item| {
+            trigger.tick();
+            item.id != 9
+        }).for_each(drop);
+    });
+    // The item being validated, and those after it, stay in the buffer.
+    quiet();
+    for at in 0..7 {
+        let shared = Rc::new(Shared::default());
+        let mut buffer = wrapped(&shared);
+        let ids: Vec<usize> = buffer.iter().map(|item| item.id).collect();
+        let trigger = Trigger(Cell::new(at));
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| buffer.pop_while_valid(|_| {
+            trigger.tick();
+            true
+        }).count()));
+        assert!(buffer.iter().map(|item| item.id).eq(ids[at..].iter().copied()));
+        drop(buffer);
+        shared.assert_dropped_once(&[]);
+    }
+}
+
+#[test]
+fn sort() {
+    let reversed = |trigger: &Trigger, a: &Tracked, b: &Tracked| {
+        trigger.tick();
+        b.id.cmp(&a.id)
+    };
+    closures(|_, buffer, trigger| buffer.sort_by(|a, b| reversed(trigger, a, b)));
+    closures(|_, buffer, trigger| buffer.sort_unstable_by(|a, b| reversed(trigger, a, b)));
+    closures(|_, buffer, trigger| {
+        buffer.is_sorted_by(|a, b| {
+            trigger.tick();
+            a.id <= b.id
+        });
+    });
+}
+
+#[test]
+fn for_each() {
+    closures(|shared, buffer, trigger| {
+        buffer.for_each_mut(|_, item| {
+            trigger.tick();
+            *item = shared.item();
+        });
+    });
+    closures(|_, buffer, trigger| buffer.for_each_window(3, |_, _| trigger.tick()));
+    closures(|_, buffer, trigger| {
+        buffer.iter().fold((), |_, _| trigger.tick());
+        buffer.iter_mut().for_each(|_| trigger.tick());
+    });
+    closures(|_, buffer, trigger| mem::replace(buffer, RingBuffer::new()).into_iter().for_each(|_| trigger.tick()));
+}
+
+#[test]
+fn spare_capacity() {
+    closures(|shared, buffer, trigger| {
+        for _ in 0..6 {
+            buffer.pop();
+        }
+        buffer.with_spare_capacity(|spare| {
+            while spare.remaining() != 0 {
+                trigger.tick();
+                assert!(spare.push(shared.item()).is_ok());
+            }
+        });
+    });
+
+    // Items initialized by `write_spare_unchecked` before a panic are leaked rather than dropped.
+    quiet();
+    for at in 0..4 {
+        let shared = Rc::new(Shared::default());
+        let mut buffer = wrapped(&shared);
+        for _ in 0..6 {
+            buffer.pop();
+        }
+        let trigger = Trigger(Cell::new(at));
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+            buffer.write_spare_unchecked(|lhs, rhs| {
+                for slot in lhs.iter_mut().chain(rhs).take(3) {
+                    trigger.tick();
+                    slot.write(shared.item());
+                }
+                3
+            });
+        }));
+        let leaked = if at < 3 { (11..11 + at).collect() } else { Vec::new() };
+        drop(buffer);
+        shared.assert_dropped_once(&leaked);
+    }
+
+    // Items initialized by `write_spare` but not committed are dropped, even if it panics.
+    for at in 0..4 {
+        let shared = Rc::new(Shared::default());
+        let mut buffer = wrapped(&shared);
+        for _ in 0..6 {
+            buffer.pop();
+        }
+        let trigger = Trigger(Cell::new(at));
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+            buffer.write_spare(|spare| {
+                for _ in 0..3 {
+                    trigger.tick();
+                    assert!(spare.push(shared.item()).is_ok());
+                }
+                2
+            });
+        }));
+        assert_eq!(buffer.len(), if at < 3 { 1 } else { 3 });
+        // Only the committed items count as pushed.
+        assert_eq!(buffer.total_pushed() - buffer.total_popped(), buffer.len() as u64);
+        drop(buffer);
+        shared.assert_dropped_once(&[]);
+    }
+}
+
+#[test]
+fn item_drops() {
+    drops(|buffer| buffer.clear());
+    drops(|buffer| {
+        buffer.evict_while(|_| true);
+    });
+    drops(|buffer| {
+        mem::replace(buffer, RingBuffer::new()).into_iter().nth(5);
+    });
+    drops(|buffer| mem::replace(buffer, RingBuffer::new()).into_iter().truncate(1));
+    drops(|buffer| drop(mem::replace(buffer, RingBuffer::new())));
+    drops(|buffer| buffer.truncate(2));
+    drops(|buffer| {
+        buffer.drain(5).next();
+    });
+    drops(|buffer| {
+        buffer.drain(6).nth(2);
+    });
+}
+
+#[test]
+fn drain() {
+    for count in 0..9 {
+        for taken in 0..=count {
+            let shared = Rc::new(Shared::default());
+            let mut buffer = wrapped(&shared);
+            let ids: Vec<usize> = buffer.iter().map(|item| item.id).collect();
+            let mut drain = buffer.drain(count);
+            assert_eq!(drain.len(), count.min(7));
+            for id in &ids[..taken.min(7)] {
+                assert_eq!(drain.next().map(|item| item.id), Some(*id));
+            }
+            drop(drain);
+            // Dropping the iterator drops the items it did not yield.
+            assert!(buffer.iter().map(|item| item.id).eq(ids[count.min(7)..].iter().copied()));
+            drop(buffer);
+            shared.assert_dropped_once(&[]);
+
+            // Forgetting it leaves them in the buffer.
+            let shared = Rc::new(Shared::default());
+            let mut buffer = wrapped(&shared);
+            let ids: Vec<usize> = buffer.iter().map(|item| item.id).collect();
+            let mut drain = buffer.drain(count);
+            drain.by_ref().take(taken).for_each(drop);
+            mem::forget(drain);
+            assert!(buffer.iter().map(|item| item.id).eq(ids[taken.min(7)..].iter().copied()));
+            drop(buffer);
+            shared.assert_dropped_once(&[]);
+        }
+    }
+}
+
+#[test]
+fn truncate() {
+    for len in 0..9 {
+        let shared = Rc::new(Shared::default());
+        let mut buffer = wrapped(&shared);
+        let ids: Vec<usize> = buffer.iter().map(|item| item.id).collect();
+        let (pushed, popped) = (buffer.total_pushed(), buffer.total_popped());
+        let first = buffer.absolute_index_of(0).unwrap();
+        buffer.truncate(len);
+        assert!(buffer.iter().map(|item| item.id).eq(ids[..len.min(7)].iter().copied()));
+        // The items kept keep their absolute indices, and the dropped ones count as popped.
+        assert_eq!(buffer.total_pushed(), pushed);
+        assert_eq!(buffer.total_popped(), popped + (7 - len.min(7)) as u64);
+        assert_eq!(buffer.absolute_index_of(0), Some(first).filter(|_| len != 0));
+        assert_eq!(buffer.logical_index_of(first + len.min(7) as u64), None);
+        // Only the items still in the buffer are left to drop.
+        for (id, &count) in shared.drops.borrow().iter().enumerate() {
+            assert_eq!(count, usize::from(!buffer.iter().any(|item| item.id == id)), "item {id}");
+        }
+        drop(buffer);
+        shared.assert_dropped_once(&[]);
+    }
+}
+
+#[test]
+fn sliding_window() {
+    quiet();
+    for at in 0..8 {
+        let mut window = SlidingWindow::with_statistics(8);
+        for value in 0..12u8 {
+            window.push(value);
+        }
+        let trigger = Trigger(Cell::new(at));
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+            window.evict_while(|_| {
+                trigger.tick();
+                true
+            })
+        }));
+        assert_eq!(window.sum(), window.iter().map(|value| *value as f64).sum::<f64>());
+    }
+}
+
+#[test]
+fn weighted() {
+    quiet();
+    for at in 0..8 {
+        let trigger = Rc::new(Trigger(Cell::new(usize::MAX)));
+        let weigher = {
+            let trigger = trigger.clone();
+            move |value: &usize| {
+                trigger.tick();
+                *value
+            }
+        };
+        let mut buffer = WeightedRingBuffer::with_capacity(8, weigher);
+        for value in 1..8 {
+            assert!(buffer.push(value).is_ok());
+        }
+        trigger.0.set(at);
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+            if at % 2 == 0 {
+                buffer.pop_until_weight(0)
+            } else {
+                buffer.evict_while(|_| true)
+            }
+        }));
+        assert_eq!(buffer.total_weight(), buffer.iter().sum::<usize>());
+    }
+}
+
+/// A `BufStream` or `BufWriter` dropped while unwinding does not flush, as the stream could panic
+/// again.
+#[test]
+fn buffered_drop_while_panicking() {
+    use circulate::{BufStream, BufWriter, Write};
+    quiet();
+    let mut sent = Vec::new();
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut stream = BufStream::new(&mut sent);
+        stream.write(b"unsent").unwrap();
+        panic::panic_any(Injected);
+    }));
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut writer = BufWriter::new(&mut sent);
+        writer.write(b"unsent").unwrap();
+        panic::panic_any(Injected);
+    }));
+    assert!(sent.is_empty());
+}
+
+#[test]
+fn io_observer() {
+    use circulate::{BufStream, Write};
+    quiet();
+    let mut stream = BufStream::new(Vec::new());
+    stream.set_io_observer(|_| panic::panic_any(Injected));
+    stream.write(b"before").unwrap();
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| stream.flush())).is_err());
+    stream.clear_io_observer();
+    stream.write(b" after").unwrap();
+    stream.flush().unwrap();
+    assert_eq!(stream.get_ref(), b"before after");
+}
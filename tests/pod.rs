@@ -0,0 +1,131 @@
+//! Checks that values read with `ReadExt::read_pod` and viewed with `BufStream::view_pod` come out
+//! whole from every position in the input buffer, including misaligned positions and values that
+//! wrap around its end.
+#![cfg(all(feature = "pod", feature = "alloc", not(feature = "no_std")))]
+
+use std::io;
+use bytemuck::{Pod, Zeroable};
+use circulate::{BufStream, Eof, FromStd, PodError, PodRef, ReadExt};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Header {
+    kind: u16,
+    flags: u16,
+    len: u32,
+}
+// Safety: `Header` is `repr(C)` with no padding, and every field is `Pod`.
+unsafe impl Zeroable for Header {}
+unsafe impl Pod for Header {}
+
+/// Reads at most `limit` bytes at a time.
+struct Trickle {
+    bytes: io::Cursor<Vec<u8>>,
+    limit: usize,
+}
+impl io::Read for Trickle {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.limit);
+        io::Read::read(&mut self.bytes, &mut buf[..len])
+    }
+}
+impl io::Write for Trickle {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn header(index: usize) -> Header {
+    Header { kind: index as u16, flags: !(index as u16), len: 0x0102_0304 * index as u32 }
+}
+
+/// `skip` filler bytes followed by headers.
+fn input(skip: usize, headers: usize) -> Vec<u8> {
+    let mut bytes = vec![0xee; skip];
+    for index in 0..headers {
+        bytes.extend_from_slice(bytemuck::bytes_of(&header(index)));
+    }
+    bytes
+}
+
+#[test]
+fn view_every_position() {
+    let (mut borrowed, mut copied) = (0, 0);
+    for limit in [1, 3, 7, 64] {
+        for skip in 0..16 {
+            let trickle = FromStd(Trickle { bytes: io::Cursor::new(input(skip, 8)), limit });
+            let mut stream = BufStream::with_capacity(trickle, 16);
+            stream.set_max_input_capacity(16);
+            stream.fill_at_least(skip).unwrap();
+            stream.consume(skip);
+            for index in 0..8 {
+                let (value, was_borrowed) = match stream.view_pod::<Header>().unwrap() {
+                    PodRef::Borrowed(value) => {
+                        assert!((value as *const Header).is_aligned());
+                        (*value, true)
+                    }
+                    PodRef::Copied(value) => (value, false),
+                };
+                assert_eq!(value, header(index), "limit {limit}, skip {skip}, header {index}");
+                // Bytes that wrap around the end of the buffer cannot be borrowed.
+                if stream.buffered_input().0.len() < size_of::<Header>() {
+                    assert!(!was_borrowed);
+                }
+                if was_borrowed {
+                    borrowed += 1;
+                } else {
+                    copied += 1;
+                }
+                stream.consume(size_of::<Header>());
+            }
+            assert!(matches!(stream.view_pod::<Header>(), Err(PodError::UnexpectedEof(Eof { needed: 8, got: 0 }))));
+        }
+    }
+    assert!(borrowed != 0 && copied != 0);
+}
+
+#[test]
+fn view_past_the_end() {
+    let mut bytes = input(0, 1);
+    bytes.truncate(5);
+    let mut stream = BufStream::new(FromStd(Trickle { bytes: io::Cursor::new(bytes), limit: 2 }));
+    assert!(matches!(stream.view_pod::<Header>(), Err(PodError::UnexpectedEof(Eof { needed: 8, got: 5 }))));
+    // The bytes stay buffered.
+    assert_eq!(stream.buffered_input().0.len(), 5);
+
+    // A bounded input buffer cannot hold a value larger than it.
+    let mut stream = BufStream::with_capacity(FromStd(Trickle { bytes: io::Cursor::new(input(0, 4)), limit: 64 }), 4);
+    stream.set_max_input_capacity(4);
+    match stream.view_pod::<Header>() {
+        Err(PodError::Full { buffered }) => assert!(buffered < size_of::<Header>()),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn read_every_position() {
+    for limit in [1, 3, 7, 64] {
+        for skip in 0..16 {
+            // Through `FromStd`, and through a `BufStream`.
+            let mut reader = FromStd(Trickle { bytes: io::Cursor::new(input(skip, 8)), limit });
+            let mut stream = BufStream::with_capacity(FromStd(Trickle { bytes: io::Cursor::new(input(skip, 8)), limit }), 16);
+            let mut filler = vec![0u8; skip];
+            reader.read_pod_vectored(&mut filler[..]).unwrap();
+            stream.read_pod_vectored(&mut filler[..]).unwrap();
+            for index in 0..4 {
+                assert_eq!(reader.read_pod::<Header>().unwrap(), header(index));
+                assert_eq!(stream.read_pod::<Header>().unwrap(), header(index));
+            }
+            let mut rest = [Header::zeroed(); 4];
+            reader.read_pod_vectored(&mut rest).unwrap();
+            assert_eq!(rest, [4, 5, 6, 7].map(header));
+            stream.read_pod_vectored(&mut rest[..3]).unwrap();
+            assert_eq!(rest[..3], [4, 5, 6].map(header));
+            assert_eq!(stream.read_pod::<u64>().unwrap(), bytemuck::cast(header(7)));
+            assert!(matches!(stream.read_pod::<u8>(), Err(PodError::UnexpectedEof(Eof { needed: 1, got: 0 }))));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Checks that a [`RingBuffer`] serializes as the sequence of its items in order, wherever they
+//! lie in the allocation, and that deserializing it gives back an equal buffer.
+//!
+//! Run with `cargo test --features serde --test serde`.
+#![cfg(feature = "serde")]
+
+use circulate::RingBuffer;
+
+#[test]
+fn round_trip_wrapped() {
+    let mut wrapped = 0;
+    for exact in [false, true] {
+        for offset in 0..8 {
+            for len in 0..8 {
+                let mut buffer = if exact { RingBuffer::with_exact_capacity(7) } else { RingBuffer::with_capacity(7) };
+                for _ in 0..offset {
+                    buffer.push(String::new()).unwrap();
+                    buffer.pop();
+                }
+                let items: Vec<String> = (0..len).map(|i| i.to_string()).collect();
+                for item in &items {
+                    buffer.push(item.clone()).unwrap();
+                }
+                if !buffer.as_slices().1.is_empty() {
+                    wrapped += 1;
+                }
+
+                let json = serde_json::to_string(&buffer).unwrap();
+                // The cursors do not leak in to the format.
+                assert_eq!(json, serde_json::to_string(&items).unwrap());
+                let back: RingBuffer<String> = serde_json::from_str(&json).unwrap();
+                assert_eq!(back, buffer, "exact {exact}, offset {offset}, len {len}");
+                assert_eq!(back.as_slices(), (&items[..], &[][..]));
+            }
+        }
+    }
+    assert!(wrapped != 0);
+}
+
+#[test]
+fn grows_past_the_hint() {
+    // A `Vec` serializes the same way, and a bounded buffer deserializes unbounded.
+    let items: Vec<u64> = (0..5000).collect();
+    let mut bounded = RingBuffer::with_max_capacity(16);
+    bounded.extend_from_slice(&items[..15]).unwrap();
+    let json = serde_json::to_string(&bounded).unwrap();
+    let back: RingBuffer<u64> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, bounded);
+    assert_eq!(back.max_capacity(), None);
+
+    // JSON gives no length up front, so every item is pushed as it arrives.
+    let back: RingBuffer<u64> = serde_json::from_str(&serde_json::to_string(&items).unwrap()).unwrap();
+    assert!(back.iter().eq(&items));
+
+    let error = serde_json::from_str::<RingBuffer<u64>>("{}").unwrap_err();
+    assert!(error.to_string().contains("a sequence"), "{error}");
+}
@@ -0,0 +1,96 @@
+//! Checks that a [`SpilloverBuffer`] backed by a file returns every byte in order across repeated
+//! spill and drain cycles, and gives the space of the file back once it has been drained. Bytes
+//! read back before the store fails are returned ahead of the error.
+#![cfg(all(feature = "alloc", not(feature = "no_std"), not(miri)))]
+
+use std::{cell::Cell, fs::{self, File}, io::{self, Cursor, Seek, SeekFrom}, mem::MaybeUninit, rc::Rc};
+use circulate::{Read, SpillStore, SpilloverBuffer, Write};
+
+#[test]
+fn file_backed_cycles() {
+    let path = std::env::temp_dir().join(format!("circulate-spillover-{}", std::process::id()));
+    let file = File::options().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+    let pattern: Vec<u8> = (0..1 << 18).map(|i: u32| (i * 31 % 251) as u8).collect();
+    for memory_capacity in [0, 1, 100] {
+        let mut buffer = SpilloverBuffer::new(&file, memory_capacity);
+        let mut output = Vec::with_capacity(pattern.len());
+        let mut chunk = [MaybeUninit::uninit(); 4096];
+        for (cycle, burst) in pattern.chunks(1 << 16).enumerate() {
+            for slice in burst.chunks(1021 + cycle) {
+                assert_eq!(buffer.write(slice).unwrap(), slice.len());
+            }
+            assert!(file.metadata().unwrap().len() > 0);
+            loop {
+                let read = buffer.read(&mut chunk).unwrap();
+                if read == 0 {
+                    break;
+                }
+                output.extend(chunk[..read].iter().map(|byte| unsafe { byte.assume_init() }));
+            }
+            assert!(buffer.is_empty());
+            assert_eq!(file.metadata().unwrap().len(), 0);
+        }
+        assert!(output == pattern, "memory capacity {memory_capacity}");
+    }
+    drop(file);
+    fs::remove_file(&path).unwrap();
+}
+
+/// A store in memory that fails reads once it has made `allowed` of them.
+struct Flaky {
+    bytes: Cursor<Vec<u8>>,
+    allowed: Rc<Cell<usize>>,
+}
+impl io::Read for Flaky {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.allowed.get() {
+            0 => Err(io::ErrorKind::Other.into()),
+            allowed => {
+                self.allowed.set(allowed - 1);
+                self.bytes.read(buf)
+            }
+        }
+    }
+}
+impl io::Write for Flaky {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl Seek for Flaky {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.bytes.seek(pos)
+    }
+}
+impl SpillStore for Flaky {
+    fn truncate(&mut self, len: u64) -> io::Result<()> {
+        self.bytes.truncate(len)
+    }
+}
+
+#[test]
+fn store_fails_part_way_through_a_refill() {
+    let allowed = Rc::new(Cell::new(usize::MAX));
+    let store = Flaky { bytes: Cursor::new(Vec::new()), allowed: allowed.clone() };
+    let mut buffer = SpilloverBuffer::new(store, 7);
+    assert_eq!(buffer.write(b"abcdefg").unwrap(), 7);
+    assert_eq!(buffer.write(b"hijklmnop").unwrap(), 9);
+    assert_eq!(buffer.spilled_len(), 9);
+    let read = |buffer: &mut SpilloverBuffer<Flaky>| {
+        let mut chunk = [MaybeUninit::uninit(); 7];
+        buffer.read(&mut chunk).map(|read| chunk[..read].iter().map(|byte| unsafe { byte.assume_init() }).collect::<Vec<u8>>())
+    };
+    assert_eq!(read(&mut buffer).unwrap(), b"abcdefg");
+
+    // The free space wraps, so the refill reads the store twice and the second read fails.
+    allowed.set(1);
+    assert_eq!(read(&mut buffer).unwrap(), b"h");
+    assert!(read(&mut buffer).is_err());
+    allowed.set(usize::MAX);
+    assert_eq!(read(&mut buffer).unwrap(), b"ijklmno");
+    assert_eq!(read(&mut buffer).unwrap(), b"p");
+    assert!(buffer.is_empty());
+}
@@ -0,0 +1,177 @@
+//! A stress test of a [`RingBuffer`] split in to a [`Producer`] and [`Consumer`] on different
+//! threads, checking that every item arrives exactly once and in order, and that items left in the
+//! buffer are dropped with it. [`Consumer::pop_latest`] is checked to only ever move forward and
+//! to drop the items it skips.
+//!
+//! Run under Miri with `cargo +nightly miri test --test spsc` to also check for data races.
+#![cfg(all(feature = "alloc", not(feature = "no_std"), not(loom)))]
+
+use std::{rc::Rc, sync::Arc, thread};
+use circulate::{Consumer, Producer, RingBuffer, StaticRing};
+
+const COUNT: u64 = if cfg!(miri) { 2_000 } else { 1_000_000 };
+
+fn pusher(mut producer: Producer<u64>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        for sequence in 0..COUNT {
+            let mut item = sequence;
+            while let Err(returned) = producer.push(item) {
+                item = returned;
+                thread::yield_now();
+            }
+        }
+    })
+}
+
+fn pop_all(consumer: &mut Consumer<u64>) {
+    let mut expected = 0;
+    while expected < COUNT {
+        let Some(&peeked) = consumer.peek() else {
+            thread::yield_now();
+            continue;
+        };
+        assert!(consumer.len() <= 63);
+        assert_eq!(consumer.pop(), Some(peeked));
+        assert_eq!(peeked, expected, "items arrived out of order");
+        expected += 1;
+    }
+}
+
+#[test]
+fn two_threads() {
+    for exact in [false, true] {
+        let buffer = if exact { RingBuffer::with_exact_capacity(63) } else { RingBuffer::with_capacity(63) };
+        let (producer, mut consumer) = buffer.split();
+        let pusher = pusher(producer);
+        pop_all(&mut consumer);
+        pusher.join().unwrap();
+        assert!(consumer.is_empty());
+        assert_eq!(consumer.pop(), None);
+    }
+}
+
+#[test]
+fn keeps_items_and_capacity() {
+    let mut buffer = RingBuffer::with_exact_capacity(3);
+    // Leave the items wrapped around the end of the allocation.
+    buffer.extend_from_slice(&[0, 1]).unwrap();
+    buffer.pop();
+    buffer.extend_from_slice(&[2, 3]).unwrap();
+    let (mut producer, mut consumer) = buffer.split();
+    assert_eq!(producer.capacity(), 4);
+    assert!(producer.is_full());
+    assert_eq!(producer.push(4), Err(4));
+    assert_eq!(consumer.len(), 3);
+    assert_eq!([consumer.pop(), consumer.pop()], [Some(1), Some(2)]);
+    producer.push(4).unwrap();
+    producer.push(5).unwrap();
+    assert_eq!([consumer.pop(), consumer.pop(), consumer.pop(), consumer.pop()], [Some(3), Some(4), Some(5), None]);
+
+    // An unallocated buffer can hold nothing.
+    let (mut producer, consumer) = RingBuffer::new().split();
+    assert!(producer.is_full());
+    assert_eq!(producer.push(0), Err(0));
+    assert!(consumer.is_empty());
+}
+
+#[test]
+fn try_push_when_full() {
+    let (mut producer, mut consumer) = RingBuffer::with_exact_capacity(2).split();
+    assert_eq!(producer.try_push(0), Ok(()));
+    assert_eq!(producer.try_push(1), Ok(()));
+    assert!(producer.is_full());
+    // The value is handed back, and the items already pushed are kept.
+    assert_eq!(producer.try_push(2), Err(2));
+    assert_eq!(consumer.len(), 2);
+    assert_eq!(consumer.pop(), Some(0));
+    assert_eq!(producer.try_push(2), Ok(()));
+    assert_eq!([consumer.pop(), consumer.pop(), consumer.pop()], [Some(1), Some(2), None]);
+}
+
+#[test]
+fn drops_items_once() {
+    let item = Rc::new(());
+    for drop_producer_first in [false, true] {
+        let mut buffer = RingBuffer::with_exact_capacity(4);
+        for _ in 0..3 {
+            buffer.push(item.clone()).unwrap();
+        }
+        buffer.pop();
+        let (mut producer, mut consumer) = buffer.split();
+        producer.push(item.clone()).unwrap();
+        drop(consumer.pop());
+        assert_eq!(Rc::strong_count(&item), 3);
+        if drop_producer_first {
+            drop(producer);
+            assert_eq!(Rc::strong_count(&item), 3);
+            drop(consumer);
+        } else {
+            drop(consumer);
+            assert_eq!(Rc::strong_count(&item), 3);
+            drop(producer);
+        }
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    // Dropping the halves on different threads frees the items once.
+    let item = Arc::new(());
+    let mut buffer = RingBuffer::with_capacity(8);
+    buffer.push(item.clone()).unwrap();
+    let (mut producer, consumer) = buffer.split();
+    producer.push(item.clone()).unwrap();
+    let threads = [thread::spawn(move || drop(producer)), thread::spawn(move || drop(consumer))];
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(Arc::strong_count(&item), 1);
+}
+
+#[test]
+fn pop_latest_drops_older() {
+    let snapshots: Vec<_> = (0..6).map(Rc::new).collect();
+    let mut buffer = RingBuffer::with_exact_capacity(4);
+    // Leave the items wrapped around the end of the allocation.
+    for snapshot in &snapshots[..3] {
+        buffer.push(snapshot.clone()).unwrap();
+    }
+    buffer.pop();
+    buffer.pop();
+    let (mut producer, mut consumer) = buffer.split();
+    for snapshot in &snapshots[3..] {
+        producer.push(snapshot.clone()).unwrap();
+    }
+    assert_eq!(consumer.pop_latest().as_deref(), Some(&5));
+    assert!(snapshots.iter().all(|snapshot| Rc::strong_count(snapshot) == 1));
+    assert!(consumer.is_empty() && consumer.pop_latest().is_none());
+    producer.push(snapshots[0].clone()).unwrap();
+    assert_eq!(consumer.pop_latest().as_deref(), Some(&0));
+
+    let ring = StaticRing::<_, 4>::new();
+    let (mut producer, mut consumer) = ring.split().unwrap();
+    for round in 0..3 {
+        for snapshot in &snapshots[round..round + 3] {
+            producer.push(snapshot.clone()).unwrap();
+        }
+        assert_eq!(consumer.pop_latest().as_deref(), Some(&(round + 2)));
+        assert!(snapshots.iter().all(|snapshot| Rc::strong_count(snapshot) == 1));
+        assert!(consumer.is_empty() && consumer.pop_latest().is_none());
+    }
+}
+
+#[test]
+fn pop_latest_two_threads() {
+    let (producer, mut consumer) = RingBuffer::with_capacity(63).split();
+    let pusher = pusher(producer);
+    let mut latest = None;
+    while latest != Some(COUNT - 1) {
+        match consumer.pop_latest() {
+            Some(sequence) => {
+                assert!(latest < Some(sequence), "an older item was taken after a newer one");
+                latest = Some(sequence);
+            }
+            None => thread::yield_now(),
+        }
+    }
+    pusher.join().unwrap();
+    assert!(consumer.is_empty());
+}
@@ -0,0 +1,68 @@
+//! A stress test of [`StaticConsumer::steal_batch_into`] with the producer on another thread,
+//! checking that every item arrives whole, exactly once and in order.
+//!
+//! Run under Miri with `cargo +nightly miri test --test steal` to also check for data races.
+#![cfg(all(feature = "alloc", not(feature = "no_std"), not(loom)))]
+
+use std::thread;
+use circulate::{RingBuffer, StaticConsumer, StaticRing};
+
+mod common;
+use common::Rng;
+
+const COUNT: u64 = if cfg!(miri) { 2_000 } else { 500_000 };
+
+static QUEUE: StaticRing<[u64; 4], 64> = StaticRing::new();
+
+/// An item spanning several words, the last of which checks the others, so that an item copied
+/// while it was being written is caught.
+fn item(sequence: u64) -> [u64; 4] {
+    let a = sequence.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    let b = a.rotate_left(29) ^ sequence;
+    [sequence, a, b, checksum(sequence, a, b)]
+}
+fn checksum(sequence: u64, a: u64, b: u64) -> u64 {
+    (sequence ^ a.rotate_left(7) ^ b.rotate_left(13)).wrapping_add(0x2545_f491_4f6c_dd1d)
+}
+
+/// Steal batches of pseudo-random sizes until every item has arrived.
+fn steal(consumer: &mut StaticConsumer<'static, [u64; 4], 64>) -> (u64, u64) {
+    let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+    let mut batch = RingBuffer::new();
+    let (mut expected, mut batches) = (0, 0);
+    while expected < COUNT {
+        let max = 1 + rng.below(80);
+        let stolen = consumer.steal_batch_into(&mut batch, max);
+        assert!(stolen <= max && stolen == batch.len());
+        if stolen == 0 {
+            thread::yield_now();
+            continue;
+        }
+        batches += 1;
+        while let Some([sequence, a, b, check]) = batch.pop() {
+            assert_eq!(sequence, expected, "items arrived out of order");
+            assert_eq!(check, checksum(sequence, a, b), "item {sequence} was torn");
+            expected += 1;
+        }
+    }
+    (expected, batches)
+}
+
+#[test]
+fn two_threads() {
+    let (mut producer, mut consumer) = QUEUE.split().unwrap();
+    let pusher = thread::spawn(move || {
+        for sequence in 0..COUNT {
+            let mut value = item(sequence);
+            while let Err(returned) = producer.push(value) {
+                value = returned;
+                thread::yield_now();
+            }
+        }
+    });
+    let (received, batches) = steal(&mut consumer);
+    pusher.join().unwrap();
+    assert_eq!(received, COUNT);
+    assert!(batches < COUNT);
+    assert!(consumer.pop().is_none());
+}
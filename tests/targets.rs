@@ -0,0 +1,40 @@
+//! Builds of the crate for embedded targets, with and without atomic read-modify-write
+//! operations, so that their configurations keep compiling.
+//!
+//! Run with `cargo test --test targets -- --ignored`. Each target is skipped unless it is
+//! installed, such as with `rustup target add thumbv6m-none-eabi`.
+#![cfg(not(loom))]
+
+use std::{path::Path, process::Command};
+
+/// Each target and the features it is built with.
+const TARGETS: &[(&str, &str)] = &[
+    // No atomic read-modify-write operations.
+    ("thumbv6m-none-eabi", "no_std,critical-section"),
+    ("thumbv6m-none-eabi", "no_std,portable-atomic,critical-section"),
+    ("riscv32imc-unknown-none-elf", "no_std,portable-atomic,critical-section"),
+    // Native atomics.
+    ("thumbv7em-none-eabihf", "no_std"),
+    ("thumbv7em-none-eabihf", "no_std,alloc,portable-atomic"),
+];
+
+#[test]
+#[ignore = "builds the crate for other targets"]
+fn embedded_targets() {
+    let output = Command::new("rustc").args(["--print", "sysroot"]).output().unwrap();
+    let sysroot = String::from_utf8(output.stdout).unwrap();
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("targets");
+    for &(target, features) in TARGETS {
+        if !Path::new(sysroot.trim()).join("lib/rustlib").join(target).exists() {
+            eprintln!("skipping {target}, which is not installed");
+            continue;
+        }
+        let status = Command::new(env!("CARGO"))
+            .args(["check", "--lib", "--target", target, "--no-default-features", "--features", features])
+            .env("CARGO_TARGET_DIR", &target_dir)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .unwrap();
+        assert!(status.success(), "{target} with {features}");
+    }
+}
@@ -0,0 +1,199 @@
+//! Checks of the `tracing` events recorded for each call a [`BufStream`], [`BufReader`] or
+//! [`BufWriter`] makes to its stream.
+//!
+//! Run with `cargo test --features tracing --test tracing`.
+#![cfg(all(feature = "tracing", feature = "alloc", not(feature = "no_std")))]
+
+use std::{io, mem::MaybeUninit, sync::{Arc, Mutex}};
+use circulate::{BufReader, BufStream, BufWriter, FromStd, Read, Write};
+use tracing::{field::{Field, Visit}, span, Event, Level, Metadata, Subscriber};
+
+/// The fields of an event recorded by [`Collector`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Record {
+    level: Option<Level>,
+    operation: String,
+    requested: u64,
+    actual: Option<u64>,
+    failed: bool,
+}
+impl Visit for Record {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "operation" {
+            self.operation = value.to_owned();
+        }
+    }
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "requested" => self.requested = value,
+            "actual" => self.actual = Some(value),
+            _ => (),
+        }
+    }
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        if field.name() == "failed" {
+            self.failed = value;
+        }
+    }
+    fn record_debug(&mut self, _: &Field, _: &dyn core::fmt::Debug) {}
+}
+
+/// A subscriber that records the events from this crate.
+#[derive(Clone, Default)]
+struct Collector(Arc<Mutex<Vec<Record>>>);
+impl Subscriber for Collector {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == "circulate::io"
+    }
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+    fn event(&self, event: &Event<'_>) {
+        let mut record = Record { level: Some(*event.metadata().level()), ..Record::default() };
+        event.record(&mut record);
+        self.0.lock().unwrap().push(record);
+    }
+    fn enter(&self, _: &span::Id) {}
+    fn exit(&self, _: &span::Id) {}
+}
+impl Collector {
+    /// Run `f` with this subscriber, returning the events it recorded.
+    fn collect(f: impl FnOnce()) -> Vec<Record> {
+        let collector = Collector::default();
+        tracing::subscriber::with_default(collector.clone(), f);
+        let events = collector.0.lock().unwrap().clone();
+        events
+    }
+}
+
+fn ok(operation: &str, requested: u64, actual: u64) -> Record {
+    Record { level: Some(Level::TRACE), operation: operation.to_owned(), requested, actual: Some(actual), failed: false }
+}
+fn failed(operation: &str, requested: u64) -> Record {
+    Record { level: Some(Level::DEBUG), operation: operation.to_owned(), requested, actual: None, failed: true }
+}
+
+/// Accepts at most four bytes per write, and fails once `fail` is set.
+struct Slow {
+    input: io::Cursor<Vec<u8>>,
+    written: Vec<u8>,
+    fail: bool,
+}
+impl io::Read for Slow {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.fail {
+            return Err(io::ErrorKind::ConnectionReset.into());
+        }
+        io::Read::read(&mut self.input, buf)
+    }
+}
+impl io::Write for Slow {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.fail {
+            return Err(io::ErrorKind::ConnectionReset.into());
+        }
+        let len = buf.len().min(4);
+        self.written.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+fn slow(input: &[u8]) -> FromStd<Slow> {
+    FromStd(Slow { input: io::Cursor::new(input.to_vec()), written: Vec::new(), fail: false })
+}
+
+#[test]
+fn refill_and_bypass_read() {
+    let events = Collector::collect(|| {
+        let mut stream = BufStream::with_capacity(slow(b"hello world"), 7);
+        let mut buffer = [MaybeUninit::uninit(); 4];
+        assert_eq!(stream.read(&mut buffer).unwrap(), 4);
+        assert_eq!(stream.read(&mut buffer).unwrap(), 3);
+        assert_eq!(stream.read(&mut buffer).unwrap(), 4);
+    });
+    assert_eq!(events, [ok("refill", 7, 7), ok("refill", 7, 4)]);
+
+    let events = Collector::collect(|| {
+        // An already buffered stream is read from directly.
+        let mut stream = BufStream::new(BufStream::with_capacity(slow(b"hello world"), 7));
+        let mut buffer = [MaybeUninit::uninit(); 64];
+        assert_eq!(stream.read(&mut buffer).unwrap(), 7);
+    });
+    assert_eq!(events, [ok("refill", 7, 7), ok("bypass_read", 64, 7)]);
+}
+
+#[test]
+fn flush_and_bypass_write() {
+    let events = Collector::collect(|| {
+        let mut stream = BufStream::with_capacity(slow(b""), 15);
+        stream.write(b"0123456789").unwrap();
+        stream.flush().unwrap();
+        stream.write_chunk(b"chunk".to_vec());
+        stream.flush().unwrap();
+        assert_eq!(stream.get_ref().0.written, b"0123456789chunk");
+    });
+    assert_eq!(events, [
+        ok("flush", 10, 4),
+        ok("flush", 6, 4),
+        ok("flush", 2, 2),
+        ok("bypass_write", 5, 4),
+        ok("bypass_write", 1, 1),
+    ]);
+}
+
+#[test]
+fn failures() {
+    let events = Collector::collect(|| {
+        let mut stream = BufStream::new(slow(b"input"));
+        stream.get_mut().0.fail = true;
+        assert!(stream.buffer_read().is_err());
+        stream.write(b"output").unwrap();
+        assert!(stream.flush().is_err());
+        // The output is flushed once more as the stream is dropped.
+        drop(stream);
+    });
+    let requested = events[0].requested;
+    assert!(requested != 0);
+    assert_eq!(events, [failed("refill", requested), failed("flush", 6), failed("flush", 6)]);
+}
+
+#[test]
+fn one_direction() {
+    let events = Collector::collect(|| {
+        let mut reader = BufReader::with_capacity(slow(b"0123456789"), 15);
+        let mut byte = [MaybeUninit::uninit()];
+        reader.read(&mut byte).unwrap();
+        reader.consume(9);
+        let mut large = [MaybeUninit::uninit(); 64];
+        assert_eq!(reader.read(&mut large).unwrap(), 0);
+
+        let mut writer = BufWriter::with_capacity(slow(b""), 15);
+        writer.write(b"0123456").unwrap();
+        writer.flush().unwrap();
+        writer.write(b"large enough to skip the buffer").unwrap();
+        writer.get_mut().0.fail = true;
+        writer.write(b"!").unwrap();
+        assert!(writer.flush().is_err());
+        drop(writer.into_parts());
+    });
+    assert_eq!(events, [
+        ok("refill", 15, 10),
+        ok("bypass_read", 64, 0),
+        ok("flush", 7, 4),
+        ok("flush", 3, 3),
+        ok("bypass_write", 31, 4),
+        failed("flush", 1),
+    ]);
+}
+
+#[test]
+fn nothing_without_a_subscriber_for_the_target() {
+    let events = Collector::collect(|| {
+        tracing::trace!(target: "elsewhere", operation = "refill");
+    });
+    assert!(events.is_empty());
+}
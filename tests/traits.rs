@@ -0,0 +1,61 @@
+//! Checks the standard trait implementations of [`RingBuffer`] on buffers whose items wrap around
+//! the end of their allocation, against the same items in a buffer that does not wrap.
+#![cfg(feature = "alloc")]
+
+use circulate::RingBuffer;
+
+/// A buffer of `items` with its read cursor moved `offset` slots in to the allocation.
+fn at_offset(items: &[String], offset: usize) -> RingBuffer<String> {
+    let mut buffer = RingBuffer::with_exact_capacity(items.len().max(1));
+    for _ in 0..offset {
+        buffer.push(String::new()).unwrap();
+        buffer.pop();
+    }
+    buffer.extend(items.iter().cloned());
+    buffer
+}
+
+#[test]
+fn wrapped_equals_contiguous() {
+    let items: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+    for len in 0..=items.len() {
+        let contiguous: RingBuffer<String> = items[..len].iter().cloned().collect();
+        for offset in 0..=len {
+            let buffer = at_offset(&items[..len], offset);
+            assert_eq!(buffer, contiguous, "len {len}, offset {offset}");
+            assert_eq!(format!("{buffer:?}"), format!("{:?}", &items[..len]));
+
+            let clone = buffer.clone();
+            assert_eq!(clone, buffer);
+            assert_eq!(clone.capacity(), buffer.capacity());
+            assert!((&clone).into_iter().eq(&items[..len]));
+
+            // Buffers differ by any item, or by length.
+            let mut changed = buffer.clone();
+            if let Some(item) = changed.back_mut() {
+                item.push('!');
+                assert_ne!(changed, contiguous);
+            }
+            changed.push(String::new()).unwrap();
+            assert_ne!(changed, contiguous);
+        }
+    }
+}
+
+#[test]
+fn iterate_by_reference() {
+    let mut buffer = at_offset(&["a", "b", "c"].map(String::from), 2);
+    for item in &mut buffer {
+        item.make_ascii_uppercase();
+    }
+    let mut joined = String::new();
+    for item in &buffer {
+        joined.push_str(item);
+    }
+    assert_eq!(joined, "ABC");
+    assert_eq!(RingBuffer::<String>::default(), RingBuffer::new());
+
+    let mut bytes = RingBuffer::<u8>::new();
+    bytes.extend(b"wrap");
+    assert!(bytes.iter().eq(b"wrap"));
+}
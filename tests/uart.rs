@@ -0,0 +1,44 @@
+//! Runs the line echo service of the `no_std_uart` example over its simulated UART, with bytes
+//! arriving at several rates.
+#![cfg(all(feature = "alloc", not(feature = "no_std")))]
+
+extern crate alloc;
+
+#[path = "../examples/no_std_uart/uart.rs"]
+#[allow(dead_code)]
+mod uart;
+
+const RECEIVED: &[u8] = b"hello\nuart\n\nlonger lines than the input buffer holds\nbye\n";
+
+/// What the service sends for `received`.
+fn echoed(received: &[u8]) -> Vec<u8> {
+    received.split_inclusive(|&byte| byte == b'\n').flat_map(|line| [uart::PROMPT, line].concat()).collect()
+}
+
+#[test]
+fn echo() {
+    for spacing in 2..=5 {
+        let transcript = uart::simulate(RECEIVED, spacing);
+        assert_eq!(transcript.overruns, 0, "spacing {spacing}");
+        assert_eq!(transcript.sent, echoed(RECEIVED), "spacing {spacing}");
+    }
+    // Runs are deterministic.
+    assert_eq!(uart::simulate(RECEIVED, 3).ticks, uart::simulate(RECEIVED, 3).ticks);
+}
+
+#[test]
+fn overrun() {
+    // Echoing sends more than is received, so bytes arriving every tick get ahead of the line.
+    // Once the buffers and FIFO are full the bytes that arrive are lost, and the rest still echo.
+    let received = b"0123456789abcdefghijklmnopqrstuv\n".repeat(40);
+    let transcript = uart::simulate(&received, 1);
+    assert!(transcript.overruns > 0);
+    let mut kept = Vec::new();
+    for line in transcript.sent.split_inclusive(|&byte| byte == b'\n') {
+        kept.extend_from_slice(line.strip_prefix(uart::PROMPT).unwrap());
+    }
+    assert_eq!(kept.len() + transcript.overruns, received.len());
+    // The bytes kept arrive in order.
+    let mut received = received.iter();
+    assert!(kept.iter().all(|byte| received.any(|other| other == byte)));
+}
@@ -0,0 +1,173 @@
+//! Checks that wrapped output, and output queued in chunks, is written with one vectored write, and
+//! that a vectored write that stops part way through either slice consumes exactly the bytes the
+//! writer accepted. Reading in to wrapped free space must not read again after a short read.
+#![cfg(all(feature = "alloc", not(feature = "no_std")))]
+
+use std::{collections::VecDeque, io, mem::MaybeUninit};
+use circulate::{BufStream, BufWriter, FromStd, IoVecQueue, Read, RingBuffer, Write};
+
+/// Accepts the number of bytes next in `limits` per write, or none once they run out, recording
+/// the number of slices passed to each write.
+#[derive(Default)]
+struct Gather {
+    written: Vec<u8>,
+    limits: VecDeque<usize>,
+    slices: Vec<usize>,
+}
+impl Gather {
+    fn limited(limits: &[usize]) -> FromStd<Self> {
+        FromStd(Self { limits: limits.iter().copied().collect(), ..Self::default() })
+    }
+}
+impl io::Read for Gather {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Ok(0)
+    }
+}
+impl io::Write for Gather {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::Write::write_vectored(self, &[io::IoSlice::new(buf)])
+    }
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        self.slices.push(bufs.len());
+        let limit = self.limits.pop_front().unwrap_or(0);
+        let before = self.written.len();
+        self.written.extend(bufs.iter().flat_map(|buf| buf.iter()).take(limit));
+        Ok(self.written.len() - before)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn drain_every_split() {
+    let mut wrapped = 0;
+    for offset in 0..8 {
+        for len in 1..8 {
+            for limit in 0..=len {
+                let mut buffer = RingBuffer::with_exact_capacity(7);
+                // Move the read cursor part way through the allocation before filling it.
+                for _ in 0..offset {
+                    buffer.push(0).unwrap();
+                    buffer.pop();
+                }
+                let bytes: Vec<u8> = (1..=len as u8).collect();
+                buffer.extend_from_slice(&bytes).unwrap();
+                let (_, rhs) = buffer.as_mut_slices();
+                let slices = if rhs.is_empty() { 1 } else { 2 };
+                wrapped += slices - 1;
+
+                let mut writer = Gather::limited(&[limit]);
+                assert_eq!(buffer.drain_to(&mut writer).unwrap(), limit);
+                assert_eq!(writer.0.slices, [slices]);
+                assert_eq!(writer.0.written, bytes[..limit]);
+                assert!(buffer.iter().eq(&bytes[limit..]), "offset {offset}, len {len}, limit {limit}");
+                assert_eq!(buffer.total_popped(), (offset + limit) as u64);
+            }
+        }
+    }
+    assert!(wrapped != 0);
+}
+
+#[test]
+fn buf_stream_flush() {
+    let mut stream = BufStream::with_capacity(Gather::limited(&[4, 0, 3, 10]), 7);
+    stream.write(b"abcdef").unwrap();
+    stream.flush().unwrap();
+    // "ef" is left at the end of the buffer, so the next bytes wrap around.
+    stream.write(b"ghij").unwrap();
+    stream.flush().unwrap();
+    let writer = &stream.get_ref().0;
+    assert_eq!(writer.written, b"abcdefghij");
+    assert_eq!(writer.slices, [1, 1, 2, 2]);
+}
+
+#[test]
+fn buf_stream_chunks() {
+    for limit in 1..=12 {
+        let mut stream = BufStream::with_capacity(Gather::limited(&[limit; 12]), 7);
+        stream.write(b"ab").unwrap();
+        stream.write_chunk(b"cd".to_vec());
+        stream.write_chunk(b"ef".to_vec());
+        stream.write(b"gh").unwrap();
+        stream.write_chunk(b"ij".to_vec());
+        stream.write(b"k").unwrap();
+        assert_eq!(stream.output_buffer_len(), 11);
+        stream.flush().unwrap();
+        let writer = &stream.get_ref().0;
+        assert_eq!(writer.written, b"abcdefghijk", "limit {limit}");
+        // The output ahead of the chunks is written with them, and the rest after them.
+        assert_eq!(writer.slices[0], 5);
+        assert_eq!(writer.slices.last(), Some(&1));
+        assert_eq!(writer.slices.len(), 10_usize.div_ceil(limit) + 1);
+    }
+}
+
+#[test]
+fn queue_write_to() {
+    for limit in 0..=10 {
+        let mut queue = IoVecQueue::new();
+        for chunk in [&b"abc"[..], b"de", b"fghij"] {
+            queue.push(chunk.to_vec());
+        }
+        let mut writer = Gather::limited(&[limit]);
+        assert_eq!(queue.write_to(&mut writer).unwrap(), limit);
+        assert_eq!(writer.0.slices, [3]);
+        assert_eq!(writer.0.written, &b"abcdefghij"[..limit]);
+        assert_eq!(queue.len(), 10 - limit);
+        let mut writer = Gather::limited(&[10]);
+        assert_eq!(queue.write_to(&mut writer).unwrap(), 10 - limit);
+        assert_eq!(writer.0.written, &b"abcdefghij"[limit..]);
+        assert!(queue.is_empty());
+    }
+}
+
+#[test]
+fn buf_writer_flush() {
+    let mut writer = BufWriter::with_capacity(Gather::limited(&[4, 0, 3, 10]), 7);
+    writer.write(b"abcdef").unwrap();
+    writer.flush().unwrap();
+    writer.write(b"ghij").unwrap();
+    writer.flush().unwrap();
+    let writer = &writer.get_ref().0;
+    assert_eq!(writer.written, b"abcdefghij");
+    assert_eq!(writer.slices, [1, 1, 2, 2]);
+}
+
+/// Returns up to `bytes` from the first read and fails every read after it.
+struct FailsAgain<'a> {
+    bytes: &'a [u8],
+    reads: usize,
+}
+impl Read for FailsAgain<'_> {
+    type Error = &'static str;
+    fn read(&mut self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize, Self::Error> {
+        self.reads += 1;
+        if self.reads > 1 {
+            return Err("broken");
+        }
+        let len = buffer.len().min(self.bytes.len());
+        for (slot, byte) in buffer.iter_mut().zip(&self.bytes[..len]) {
+            slot.write(*byte);
+        }
+        Ok(len)
+    }
+}
+
+#[test]
+fn short_read_in_to_wrapped_space() {
+    // The free space wraps around the end of the allocation, so is read in to as two slices.
+    let mut buffer = RingBuffer::with_capacity(7);
+    for _ in 0..6 {
+        buffer.push(0).unwrap();
+        buffer.pop();
+    }
+    let mut reader = FailsAgain { bytes: b"abcdefgh", reads: 0 };
+    assert_eq!(buffer.fill_from(&mut reader), Ok(2));
+    assert_eq!(reader.reads, 1);
+    assert!(buffer.iter().eq(b"ab"));
+    // The failure is reported by the next fill, and the bytes already read are kept.
+    assert_eq!(buffer.fill_from(&mut reader), Err("broken"));
+    assert!(buffer.iter().eq(b"ab"));
+}
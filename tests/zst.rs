@@ -0,0 +1,110 @@
+//! Checks a [`RingBuffer`] of zero sized items, which are never allocated, counting each item
+//! dropped so that none are leaked or dropped twice.
+//!
+//! Run under Miri with `cargo +nightly miri test --test zst`.
+#![cfg(feature = "alloc")]
+
+use std::{cell::Cell, collections::VecDeque};
+use circulate::RingBuffer;
+
+thread_local! {
+    static DROPS: Cell<usize> = const { Cell::new(0) };
+}
+fn drops() -> usize {
+    DROPS.with(Cell::take)
+}
+
+/// A zero sized guard that counts its drops.
+#[derive(Debug)]
+struct Guard;
+impl Drop for Guard {
+    fn drop(&mut self) {
+        DROPS.with(|drops| drops.set(drops.get() + 1));
+    }
+}
+
+#[test]
+fn push_pop() {
+    for exact in [false, true] {
+        let mut buffer = if exact { RingBuffer::with_exact_capacity(3) } else { RingBuffer::new() };
+        for len in 1..=100 {
+            buffer.push(Guard).unwrap();
+            assert_eq!(buffer.len(), len);
+        }
+        assert_eq!(buffer.iter().count(), 100);
+        assert_eq!(buffer.iter_mut().count(), 100);
+        assert_eq!(drops(), 0);
+        for len in (0..100).rev() {
+            assert!(buffer.pop().is_some());
+            assert_eq!(buffer.len(), len);
+        }
+        assert!(buffer.pop().is_none());
+        assert_eq!(drops(), 100);
+        assert!(buffer.is_empty());
+    }
+}
+
+#[test]
+fn matches_vec_deque() {
+    let mut buffer = RingBuffer::new();
+    let mut expected = VecDeque::new();
+    for step in 0..500usize {
+        match step % 7 {
+            0..=2 => {
+                buffer.push(()).unwrap();
+                expected.push_back(());
+            }
+            3 => {
+                buffer.push_front(()).unwrap();
+                expected.push_front(());
+            }
+            4 => assert_eq!(buffer.pop(), expected.pop_front()),
+            5 => assert_eq!(buffer.pop_back(), expected.pop_back()),
+            _ => buffer.extend_from_slice(&[(); 3]).map(|()| expected.extend([(); 3])).unwrap(),
+        }
+        assert_eq!(buffer.len(), expected.len());
+        assert_eq!(buffer.iter().count(), expected.len());
+        let (lhs, rhs) = buffer.as_slices();
+        assert_eq!(lhs.len() + rhs.len(), expected.len());
+    }
+    assert_eq!(buffer.into_iter().count(), expected.len());
+}
+
+#[test]
+fn clear_and_drop() {
+    let mut buffer = RingBuffer::with_capacity(4);
+    for _ in 0..10 {
+        buffer.push(Guard).unwrap();
+    }
+    buffer.pop();
+    buffer.pop_back();
+    assert_eq!(drops(), 2);
+    buffer.clear();
+    assert_eq!(drops(), 8);
+    assert!(buffer.is_empty());
+
+    for _ in 0..5 {
+        buffer.push(Guard).unwrap();
+    }
+    drop(buffer);
+    assert_eq!(drops(), 5);
+
+    let mut buffer: RingBuffer<Guard> = (0..7).map(|_| Guard).collect();
+    buffer.pop();
+    assert_eq!(buffer.into_iter().take(2).count(), 2);
+    assert_eq!(drops(), 7);
+}
+
+#[test]
+fn bounded() {
+    let mut buffer = RingBuffer::with_max_capacity(4);
+    for _ in 0..3 {
+        buffer.push(Guard).unwrap();
+    }
+    assert!(buffer.is_full());
+    assert!(buffer.push(Guard).is_err());
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(drops(), 1);
+    drop(buffer);
+    assert_eq!(drops(), 3);
+}